@@ -1,6 +1,14 @@
 fn main() {
     tauri_build::build();
 
+    // gRPC service (src/grpc.rs)
+    println!("cargo:rerun-if-changed=proto");
+    let descriptors = protox::compile(["proto/observer/v1/observer.proto"], ["proto"]).expect("invalid observer.proto");
+    tonic_build::configure()
+        .build_client(false)
+        .compile_fds(descriptors)
+        .expect("failed to generate the gRPC service");
+
     // Platform-specific build steps
     #[cfg(target_os = "android")]
     {
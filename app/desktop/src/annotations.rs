@@ -0,0 +1,653 @@
+// In src-tauri/src/annotations.rs
+//
+// On-screen annotations. Agents send shapes (highlight rects, arrows, text callouts,
+// countdown toasts) in the coordinates of the frame a vision model looked at; they are
+// mapped onto the desktop with the capture geometry reported by the screen-capture plugin
+// and drawn in the "annotations" window, a transparent, click-through window stretched
+// over the captured monitor. Shapes can expire after a TTL and are stacked by `z`.
+
+use crate::screen_share::ScreenShareState;
+use crate::AppState;
+use axum::{extract::State as AxumState, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, State, WebviewUrl,
+    WebviewWindowBuilder,
+};
+use tauri_plugin_screen_capture::geometry::{self, FrameGeometry};
+
+pub const WINDOW_LABEL: &str = "annotations";
+
+/// Toasts without an explicit TTL count down from this
+const DEFAULT_TOAST_TTL_MS: u64 = 5000;
+
+/// How often expired annotations are swept
+const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Units of incoming coordinates
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// Pixels on the streamed frame (or on `frameWidth` x `frameHeight` if given)
+    #[default]
+    Frame,
+    /// 0.0-1.0 fractions of the frame
+    Normalized,
+}
+
+/// A shape as drawn on the annotations window. Input coordinates are in the request's
+/// units; the stored/emitted copy is in CSS pixels of the window.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Shape {
+    /// Highlighted rectangle
+    Rect { x: f64, y: f64, width: f64, height: f64 },
+    /// Arrow pointing from one point to another
+    Arrow { from_x: f64, from_y: f64, to_x: f64, to_y: f64 },
+    /// Text bubble anchored at a point
+    Callout { x: f64, y: f64, text: String },
+    /// Message with a countdown, shown at the top of the monitor until it expires
+    Toast { text: String },
+}
+
+impl Shape {
+    fn needs_geometry(&self) -> bool {
+        !matches!(self, Shape::Toast { .. })
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawItem {
+    #[serde(flatten)]
+    pub shape: Shape,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Any CSS color; the overlay picks one if omitted
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Remove after this many milliseconds; kept until cleared if omitted (except toasts)
+    #[serde(default)]
+    pub ttl_ms: Option<u64>,
+    /// Higher values are drawn on top; ties keep insertion order
+    #[serde(default)]
+    pub z: i32,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawRequest {
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    pub items: Vec<DrawItem>,
+    #[serde(default)]
+    pub units: Units,
+    /// Size of the image the model saw, when it was resized from the streamed frame
+    #[serde(default)]
+    pub frame_width: Option<u32>,
+    #[serde(default)]
+    pub frame_height: Option<u32>,
+    /// Drop this agent's earlier annotations first
+    #[serde(default)]
+    pub replace: bool,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BoxInput {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Bounding boxes straight from a vision model's response; shorthand for a replacing
+/// `DrawRequest` of rects
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BoxesRequest {
+    /// Boxes from the same agent replace each other; other agents' boxes are kept
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    pub boxes: Vec<BoxInput>,
+    #[serde(default)]
+    pub units: Units,
+    #[serde(default)]
+    pub frame_width: Option<u32>,
+    #[serde(default)]
+    pub frame_height: Option<u32>,
+}
+
+impl From<BoxesRequest> for DrawRequest {
+    fn from(request: BoxesRequest) -> Self {
+        DrawRequest {
+            agent_id: request.agent_id,
+            items: request
+                .boxes
+                .into_iter()
+                .map(|b| DrawItem {
+                    shape: Shape::Rect {
+                        x: b.x,
+                        y: b.y,
+                        width: b.width,
+                        height: b.height,
+                    },
+                    label: b.label,
+                    color: b.color,
+                    ttl_ms: None,
+                    z: 0,
+                })
+                .collect(),
+            units: request.units,
+            frame_width: request.frame_width,
+            frame_height: request.frame_height,
+            replace: true,
+        }
+    }
+}
+
+/// An annotation placed on the annotations window
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub id: String,
+    pub agent_id: Option<String>,
+    #[serde(flatten)]
+    pub shape: Shape,
+    pub label: Option<String>,
+    pub color: Option<String>,
+    pub z: i32,
+    /// Unix milliseconds
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+pub struct AnnotationState {
+    annotations: Mutex<Vec<Annotation>>,
+}
+
+impl AnnotationState {
+    pub fn new() -> Self {
+        Self {
+            annotations: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Create the (hidden) annotations window. Like the overlay it is content-protected so
+/// annotations never end up in the frames they annotate.
+pub fn create_window(app: &tauri::App) {
+    match WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App("/annotations".into()))
+        .title("Observer Annotations")
+        .decorations(false)
+        .transparent(true)
+        .shadow(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .visible(false)
+        .resizable(false)
+        .content_protected(true)
+        .build()
+    {
+        Ok(_) => log::info!("Annotations window created"),
+        Err(e) => log::error!("Failed to create annotations window: {}", e),
+    }
+}
+
+/// Remove annotations whose TTL has run out
+pub fn start_expiry_sweeper(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(EXPIRY_SWEEP_INTERVAL);
+        let now = now_ms();
+        let expired = {
+            let state = app_handle.state::<AnnotationState>();
+            let mut annotations = state.annotations.lock().unwrap();
+            let before = annotations.len();
+            annotations.retain(|a| a.expires_at.map_or(true, |t| t > now));
+            annotations.len() != before
+        };
+        if expired {
+            emit_annotations(&app_handle);
+        }
+    });
+}
+
+/// Monitor (physical position, physical size, scale factor) the window is stretched over
+struct TargetMonitor {
+    x: f64,
+    y: f64,
+    width: u32,
+    height: u32,
+    scale: f64,
+}
+
+impl From<&tauri::Monitor> for TargetMonitor {
+    fn from(m: &tauri::Monitor) -> Self {
+        TargetMonitor {
+            x: f64::from(m.position().x),
+            y: f64::from(m.position().y),
+            width: m.size().width,
+            height: m.size().height,
+            scale: m.scale_factor(),
+        }
+    }
+}
+
+/// Find the monitor containing screen point (x, y). Screen coordinates from the plugin are
+/// points on macOS and physical pixels elsewhere; Tauri reports monitors in physical pixels.
+fn monitor_at(app_handle: &AppHandle, x: f64, y: f64) -> Option<TargetMonitor> {
+    let monitors = app_handle.available_monitors().ok()?;
+    let logical_screen = cfg!(target_os = "macos");
+
+    let contains = |m: &TargetMonitor| {
+        let unit = if logical_screen { m.scale } else { 1.0 };
+        let (left, top) = (m.x / unit, m.y / unit);
+        let (right, bottom) = (
+            left + f64::from(m.width) / unit,
+            top + f64::from(m.height) / unit,
+        );
+        x >= left && x < right && y >= top && y < bottom
+    };
+
+    monitors
+        .iter()
+        .map(TargetMonitor::from)
+        .find(|m| contains(m))
+        .or_else(|| monitors.first().map(TargetMonitor::from))
+}
+
+/// Maps request coordinates to CSS pixels of an annotations window covering `monitor`
+struct Mapper {
+    frame: FrameGeometry,
+    monitor: TargetMonitor,
+    /// Request units -> streamed-frame pixels
+    kx: f64,
+    ky: f64,
+    /// Screen units -> physical pixels
+    unit: f64,
+}
+
+impl Mapper {
+    fn new(
+        frame: FrameGeometry,
+        monitor: TargetMonitor,
+        request: &DrawRequest,
+        logical_screen: bool,
+    ) -> Self {
+        let (kx, ky) = match request.units {
+            Units::Normalized => (f64::from(frame.frame_width), f64::from(frame.frame_height)),
+            Units::Frame => (
+                request
+                    .frame_width
+                    .map(|w| f64::from(frame.frame_width) / f64::from(w.max(1)))
+                    .unwrap_or(1.0),
+                request
+                    .frame_height
+                    .map(|h| f64::from(frame.frame_height) / f64::from(h.max(1)))
+                    .unwrap_or(1.0),
+            ),
+        };
+        let unit = if logical_screen { monitor.scale } else { 1.0 };
+        Self {
+            frame,
+            monitor,
+            kx,
+            ky,
+            unit,
+        }
+    }
+
+    fn point(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sx, sy) = self.frame.frame_to_screen(x * self.kx, y * self.ky);
+        (
+            (sx * self.unit - self.monitor.x) / self.monitor.scale,
+            (sy * self.unit - self.monitor.y) / self.monitor.scale,
+        )
+    }
+
+    fn shape(&self, shape: &Shape) -> Shape {
+        match shape {
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let (left, top) = self.point(*x, *y);
+                let (right, bottom) = self.point(x + width, y + height);
+                Shape::Rect {
+                    x: left,
+                    y: top,
+                    width: right - left,
+                    height: bottom - top,
+                }
+            }
+            Shape::Arrow {
+                from_x,
+                from_y,
+                to_x,
+                to_y,
+            } => {
+                let (from_x, from_y) = self.point(*from_x, *from_y);
+                let (to_x, to_y) = self.point(*to_x, *to_y);
+                Shape::Arrow {
+                    from_x,
+                    from_y,
+                    to_x,
+                    to_y,
+                }
+            }
+            Shape::Callout { x, y, text } => {
+                let (x, y) = self.point(*x, *y);
+                Shape::Callout {
+                    x,
+                    y,
+                    text: text.clone(),
+                }
+            }
+            Shape::Toast { text } => Shape::Toast { text: text.clone() },
+        }
+    }
+}
+
+/// Show or hide the annotations window to match the current annotations
+pub fn sync_window(app_handle: &AppHandle) {
+    let Some(window) = app_handle.get_webview_window(WINDOW_LABEL) else {
+        return;
+    };
+    let has_annotations = !app_handle
+        .state::<AnnotationState>()
+        .annotations
+        .lock()
+        .unwrap()
+        .is_empty();
+    let sharing = app_handle.state::<ScreenShareState>().is_sharing();
+
+    if has_annotations && !sharing {
+        if window.show().is_ok() {
+            // Click-through can only be set once the window is realized (Linux)
+            if let Err(e) = window.set_ignore_cursor_events(true) {
+                log::warn!("Failed to enable click-through on annotations: {}", e);
+            }
+        }
+    } else {
+        let _ = window.hide();
+    }
+}
+
+fn emit_annotations(app_handle: &AppHandle) {
+    let annotations = app_handle
+        .state::<AnnotationState>()
+        .annotations
+        .lock()
+        .unwrap()
+        .clone();
+    if let Err(e) = app_handle.emit("overlay-annotations-updated", &annotations) {
+        log::warn!("Failed to emit overlay-annotations-updated event: {}", e);
+    }
+    sync_window(app_handle);
+}
+
+/// Place an agent's annotations on screen
+pub fn draw(app_handle: &AppHandle, request: DrawRequest) -> Result<Vec<Annotation>, String> {
+    let frame = geometry::current();
+    if frame.is_none() && request.items.iter().any(|item| item.shape.needs_geometry()) {
+        return Err("No active screen capture to map annotations onto".to_string());
+    }
+
+    // Stretch the window over the monitor the captured source is on (primary for toasts
+    // when nothing is being captured)
+    let monitor = match &frame {
+        Some(frame) => {
+            let (center_x, center_y) = frame.frame_to_screen(
+                f64::from(frame.frame_width) / 2.0,
+                f64::from(frame.frame_height) / 2.0,
+            );
+            monitor_at(app_handle, center_x, center_y)
+        }
+        None => app_handle
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .map(|m| TargetMonitor::from(&m)),
+    }
+    .ok_or("No monitors found")?;
+
+    if let Some(window) = app_handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.set_position(PhysicalPosition::new(monitor.x as i32, monitor.y as i32));
+        let _ = window.set_size(PhysicalSize::new(monitor.width, monitor.height));
+    }
+
+    let created_at = now_ms();
+    let placed: Vec<Annotation> = {
+        let mapper = frame.map(|frame| Mapper::new(frame, monitor, &request, cfg!(target_os = "macos")));
+        request
+            .items
+            .iter()
+            .map(|item| {
+                let ttl_ms = match item.shape {
+                    Shape::Toast { .. } => Some(item.ttl_ms.unwrap_or(DEFAULT_TOAST_TTL_MS)),
+                    _ => item.ttl_ms,
+                };
+                Annotation {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    agent_id: request.agent_id.clone(),
+                    shape: match &mapper {
+                        Some(mapper) => mapper.shape(&item.shape),
+                        None => item.shape.clone(),
+                    },
+                    label: item.label.clone(),
+                    color: item.color.clone(),
+                    z: item.z,
+                    created_at,
+                    expires_at: ttl_ms.map(|ttl| created_at + ttl),
+                }
+            })
+            .collect()
+    };
+    log::info!(
+        "Drawing {} overlay annotations for agent {:?}",
+        placed.len(),
+        request.agent_id
+    );
+
+    {
+        let state = app_handle.state::<AnnotationState>();
+        let mut annotations = state.annotations.lock().unwrap();
+        if request.replace {
+            annotations.retain(|a| a.agent_id != request.agent_id);
+        }
+        annotations.extend(placed.iter().cloned());
+        // Stable sort keeps insertion order within a z level
+        annotations.sort_by_key(|a| a.z);
+    }
+    emit_annotations(app_handle);
+    Ok(placed)
+}
+
+fn remove(app_handle: &AppHandle, keep: impl Fn(&Annotation) -> bool) {
+    app_handle
+        .state::<AnnotationState>()
+        .annotations
+        .lock()
+        .unwrap()
+        .retain(|a| keep(a));
+    emit_annotations(app_handle);
+}
+
+fn draw_status(result: Result<Vec<Annotation>, String>) -> StatusCode {
+    match result {
+        Ok(_) => StatusCode::OK,
+        Err(e) => {
+            log::warn!("Failed to draw overlay annotations: {}", e);
+            StatusCode::CONFLICT
+        }
+    }
+}
+
+pub async fn boxes_handler(
+    AxumState(state): AxumState<AppState>,
+    Json(request): Json<BoxesRequest>,
+) -> StatusCode {
+    draw_status(draw(&state.app_handle, request.into()))
+}
+
+pub async fn draw_handler(
+    AxumState(state): AxumState<AppState>,
+    Json(request): Json<DrawRequest>,
+) -> StatusCode {
+    draw_status(draw(&state.app_handle, request))
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn show_overlay_boxes(
+    request: BoxesRequest,
+    app_handle: AppHandle,
+) -> Result<Vec<Annotation>, String> {
+    draw(&app_handle, request.into())
+}
+
+#[tauri::command]
+pub async fn draw_overlay(
+    request: DrawRequest,
+    app_handle: AppHandle,
+) -> Result<Vec<Annotation>, String> {
+    draw(&app_handle, request)
+}
+
+#[tauri::command]
+pub async fn get_overlay_annotations(
+    annotation_state: State<'_, AnnotationState>,
+) -> Result<Vec<Annotation>, String> {
+    Ok(annotation_state.annotations.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn remove_overlay_annotation(id: String, app_handle: AppHandle) -> Result<(), String> {
+    remove(&app_handle, |a| a.id != id);
+    Ok(())
+}
+
+/// Clear one agent's annotations, or everything when no agent is given
+#[tauri::command]
+pub async fn clear_overlay_annotations(
+    agent_id: Option<String>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    match agent_id {
+        Some(id) => remove(&app_handle, |a| a.agent_id.as_deref() != Some(id.as_str())),
+        None => remove(&app_handle, |_| false),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(units: Units) -> DrawRequest {
+        DrawRequest {
+            agent_id: None,
+            items: Vec::new(),
+            units,
+            frame_width: None,
+            frame_height: None,
+            replace: false,
+        }
+    }
+
+    fn mapper(units: Units) -> Mapper {
+        // Second monitor at physical x=1920, 3840x2160 at 2x, streamed at 1280x720
+        let monitor = TargetMonitor {
+            x: 1920.0,
+            y: 0.0,
+            width: 3840,
+            height: 2160,
+            scale: 2.0,
+        };
+        let frame = FrameGeometry {
+            screen_x: 1920.0,
+            screen_y: 0.0,
+            screen_width: 3840.0,
+            screen_height: 2160.0,
+            scale_factor: 1.0,
+            crop: None,
+            frame_width: 1280,
+            frame_height: 720,
+        };
+        Mapper::new(frame, monitor, &request(units), false)
+    }
+
+    #[test]
+    fn maps_shapes_onto_hidpi_monitor() {
+        let rect = mapper(Units::Frame).shape(&Shape::Rect {
+            x: 640.0,
+            y: 360.0,
+            width: 64.0,
+            height: 36.0,
+        });
+        assert_eq!(
+            rect,
+            Shape::Rect {
+                x: 960.0,
+                y: 540.0,
+                width: 96.0,
+                height: 54.0
+            }
+        );
+
+        let arrow = mapper(Units::Normalized).shape(&Shape::Arrow {
+            from_x: 0.0,
+            from_y: 0.0,
+            to_x: 0.5,
+            to_y: 0.5,
+        });
+        assert_eq!(
+            arrow,
+            Shape::Arrow {
+                from_x: 0.0,
+                from_y: 0.0,
+                to_x: 960.0,
+                to_y: 540.0
+            }
+        );
+    }
+
+    #[test]
+    fn parses_tagged_draw_items() {
+        let item: DrawItem = serde_json::from_value(serde_json::json!({
+            "kind": "callout",
+            "x": 10.0,
+            "y": 20.0,
+            "text": "Click here",
+            "ttlMs": 3000,
+            "z": 2
+        }))
+        .unwrap();
+        assert_eq!(
+            item.shape,
+            Shape::Callout {
+                x: 10.0,
+                y: 20.0,
+                text: "Click here".to_string()
+            }
+        );
+        assert_eq!((item.ttl_ms, item.z), (Some(3000), 2));
+    }
+}
@@ -0,0 +1,454 @@
+// In src-tauri/src/anthropic.rs
+//
+// Native client for the Anthropic Messages API, used by `inference` for providers of kind
+// `anthropic`. Requests arrive in the OpenAI chat form every other provider takes and are
+// translated here:
+//
+// - system messages become the top-level `system` prompt
+// - `image_url` parts (captured frames as data URLs) become base64 image blocks; images
+//   over the API's limits are downscaled and re-encoded first
+// - `max_tokens` is required by the API and defaults to `DEFAULT_MAX_TOKENS`
+// - a `response_format` schema becomes an instruction in the system prompt, the API having
+//   no structured output option of its own
+// - `tools` become tool definitions; assistant `tool_calls` become `tool_use` blocks and
+//   `tool` turns become `tool_result` blocks in a user turn
+//
+// Replies come back as the same `Completion` the OpenAI path returns, with Anthropic's
+// error types folded into the message. Streamed replies are read from the API's
+// `content_block_delta` events.
+
+use crate::inference::{
+    ChatMessage, Completion, ContentPart, FunctionCall, InferenceError, MessageContent, Sink, StreamEvent,
+    TokenUsage, ToolCall,
+};
+use crate::providers::ResolvedProvider;
+use crate::structured_output;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{imageops::FilterType, GenericImageView, ImageOutputFormat};
+use std::io::Cursor;
+
+pub const API_VERSION: &str = "2023-06-01";
+
+/// Used when the request doesn't set `max_tokens`
+const DEFAULT_MAX_TOKENS: u64 = 1024;
+
+/// Longer image edges are scaled down to this; the API would do it anyway, at our upload cost
+const MAX_IMAGE_EDGE: u32 = 1568;
+
+/// The API rejects larger images
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Request fields that carry over from the OpenAI form
+const PASSTHROUGH_PARAMS: &[&str] = &["temperature", "top_p", "top_k", "metadata"];
+
+/// An image block for a `data:` URL, fitted to the API's limits, or a URL source for http(s)
+fn image_block(url: &str) -> Result<serde_json::Value, InferenceError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Ok(serde_json::json!({ "type": "image", "source": { "type": "url", "url": url } }));
+    }
+    let (media_type, data) = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .ok_or_else(|| InferenceError::Config("Images must be data: or http(s) URLs".to_string()))?;
+    let bytes = BASE64
+        .decode(data)
+        .map_err(|e| InferenceError::Config(format!("Bad base64 image: {}", e)))?;
+    let (bytes, media_type) = fit_image(bytes, media_type)?;
+    Ok(serde_json::json!({
+        "type": "image",
+        "source": { "type": "base64", "media_type": media_type, "data": BASE64.encode(bytes) },
+    }))
+}
+
+/// Downscale an image whose long edge is over `MAX_IMAGE_EDGE`, and fall back to JPEG if
+/// it is still over `MAX_IMAGE_BYTES`. Small images pass through untouched.
+fn fit_image(bytes: Vec<u8>, media_type: &str) -> Result<(Vec<u8>, String), InferenceError> {
+    let supported = matches!(media_type, "image/jpeg" | "image/png" | "image/gif" | "image/webp");
+    let image = image::load_from_memory(&bytes).map_err(|e| InferenceError::Config(format!("Bad image: {}", e)))?;
+    let (width, height) = image.dimensions();
+    if supported && width.max(height) <= MAX_IMAGE_EDGE && bytes.len() <= MAX_IMAGE_BYTES {
+        return Ok((bytes, media_type.to_string()));
+    }
+
+    let image = if width.max(height) > MAX_IMAGE_EDGE {
+        image.resize(MAX_IMAGE_EDGE, MAX_IMAGE_EDGE, FilterType::Triangle)
+    } else {
+        image
+    };
+    let encode = |format: ImageOutputFormat| -> Result<Vec<u8>, InferenceError> {
+        let mut out = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut out), format)
+            .map_err(|e| InferenceError::Config(format!("Failed to re-encode image: {}", e)))?;
+        Ok(out)
+    };
+    if media_type == "image/png" {
+        let png = encode(ImageOutputFormat::Png)?;
+        if png.len() <= MAX_IMAGE_BYTES {
+            return Ok((png, media_type.to_string()));
+        }
+    }
+    let jpeg = encode(ImageOutputFormat::Jpeg(85))?;
+    if jpeg.len() > MAX_IMAGE_BYTES {
+        return Err(InferenceError::Config("Image is too large even after downscaling".to_string()));
+    }
+    Ok((jpeg, "image/jpeg".to_string()))
+}
+
+fn content_blocks(content: &MessageContent) -> Result<Vec<serde_json::Value>, InferenceError> {
+    match content {
+        MessageContent::Text(text) => Ok(vec![serde_json::json!({ "type": "text", "text": text })]),
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => Ok(serde_json::json!({ "type": "text", "text": text })),
+                ContentPart::ImageUrl { image_url } => image_block(&image_url.url),
+            })
+            .collect(),
+    }
+}
+
+/// Translate an OpenAI-style chat body into a Messages API body
+pub fn to_messages_body(body: &serde_json::Value) -> Result<serde_json::Value, InferenceError> {
+    let messages: Vec<ChatMessage> = serde_json::from_value(body["messages"].clone())
+        .map_err(|e| InferenceError::Config(format!("Bad messages: {}", e)))?;
+
+    let mut system = Vec::new();
+    let mut turns: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+    for message in &messages {
+        if message.role == "system" {
+            let blocks = content_blocks(&message.content)?;
+            system.extend(blocks.iter().filter_map(|block| block["text"].as_str().map(String::from)));
+            continue;
+        }
+        let (role, blocks) = match message.role.as_str() {
+            "tool" => {
+                let result = serde_json::json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.tool_call_id,
+                    "content": content_blocks(&message.content)?,
+                });
+                ("user", vec![result])
+            }
+            role => {
+                let mut blocks = content_blocks(&message.content)?;
+                // Assistant turns that only call tools come with empty text, which the API refuses
+                blocks.retain(|block| block["type"] != "text" || block["text"] != "");
+                blocks.extend(message.tool_calls.iter().map(|call| {
+                    let input: serde_json::Value =
+                        serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| serde_json::json!({}));
+                    serde_json::json!({ "type": "tool_use", "id": call.id, "name": call.function.name, "input": input })
+                }));
+                (role, blocks)
+            }
+        };
+        // The API wants user and assistant turns to alternate
+        match turns.last_mut() {
+            Some((last_role, last)) if *last_role == role => last.extend(blocks),
+            _ => turns.push((role.to_string(), blocks)),
+        }
+    }
+
+    if let Some(instruction) = structured_output::instruction(&body["response_format"]) {
+        system.push(instruction);
+    }
+
+    let mut out = serde_json::Map::new();
+    out.insert("model".to_string(), body["model"].clone());
+    out.insert(
+        "max_tokens".to_string(),
+        body["max_tokens"]
+            .as_u64()
+            .or_else(|| body["max_completion_tokens"].as_u64())
+            .unwrap_or(DEFAULT_MAX_TOKENS)
+            .into(),
+    );
+    if !system.is_empty() {
+        out.insert("system".to_string(), system.join("\n\n").into());
+    }
+    out.insert(
+        "messages".to_string(),
+        turns
+            .into_iter()
+            .map(|(role, content)| serde_json::json!({ "role": role, "content": content }))
+            .collect(),
+    );
+    for key in PASSTHROUGH_PARAMS {
+        if let Some(value) = body.get(*key) {
+            out.insert(key.to_string(), value.clone());
+        }
+    }
+    if let Some(tools) = body["tools"].as_array() {
+        let tools: Vec<serde_json::Value> = tools
+            .iter()
+            .map(|tool| {
+                let function = &tool["function"];
+                serde_json::json!({
+                    "name": function["name"],
+                    "description": function["description"],
+                    "input_schema": function["parameters"],
+                })
+            })
+            .collect();
+        out.insert("tools".to_string(), tools.into());
+    }
+    match &body["stop"] {
+        serde_json::Value::String(stop) => {
+            out.insert("stop_sequences".to_string(), serde_json::json!([stop]));
+        }
+        stop @ serde_json::Value::Array(_) => {
+            out.insert("stop_sequences".to_string(), stop.clone());
+        }
+        _ => {}
+    }
+    Ok(serde_json::Value::Object(out))
+}
+
+/// `stop_reason` reported the way OpenAI-compatible providers do
+fn finish_reason(stop_reason: &serde_json::Value) -> Option<String> {
+    stop_reason.as_str().map(|reason| {
+        match reason {
+            "end_turn" | "stop_sequence" => "stop",
+            "max_tokens" => "length",
+            "tool_use" => "tool_calls",
+            other => other,
+        }
+        .to_string()
+    })
+}
+
+/// Read a Messages API response
+fn parse_response(body: &serde_json::Value) -> Result<Completion, InferenceError> {
+    let blocks = body["content"]
+        .as_array()
+        .ok_or_else(|| InferenceError::InvalidResponse("no content".to_string()))?;
+    let content = blocks
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect::<String>();
+    let finish_reason = finish_reason(&body["stop_reason"]);
+    let usage = body["usage"].is_object().then(|| TokenUsage {
+        prompt_tokens: body["usage"]["input_tokens"].as_u64().unwrap_or(0),
+        completion_tokens: body["usage"]["output_tokens"].as_u64().unwrap_or(0),
+    });
+    let tool_calls = blocks
+        .iter()
+        .filter(|block| block["type"] == "tool_use")
+        .map(|block| ToolCall {
+            id: block["id"].as_str().unwrap_or_default().to_string(),
+            kind: "function".to_string(),
+            function: FunctionCall {
+                name: block["name"].as_str().unwrap_or_default().to_string(),
+                arguments: block["input"].to_string(),
+            },
+        })
+        .collect();
+    Ok(Completion { content, finish_reason, usage, tool_calls })
+}
+
+/// Read one streamed event into `completion`, returning what to forward
+fn decode_event(event: &serde_json::Value, completion: &mut Completion) -> Vec<StreamEvent> {
+    match event["type"].as_str() {
+        Some("message_start") => {
+            let usage = completion.usage.get_or_insert_with(TokenUsage::default);
+            usage.prompt_tokens = event["message"]["usage"]["input_tokens"].as_u64().unwrap_or(0);
+            Vec::new()
+        }
+        Some("content_block_delta") => {
+            let delta = &event["delta"];
+            match (delta["type"].as_str(), delta["text"].as_str(), delta["thinking"].as_str()) {
+                (Some("text_delta"), Some(text), _) => vec![StreamEvent::Delta { content: text.to_string() }],
+                (Some("thinking_delta"), _, Some(thinking)) => {
+                    vec![StreamEvent::Reasoning { content: thinking.to_string() }]
+                }
+                _ => Vec::new(),
+            }
+        }
+        Some("message_delta") => {
+            if let Some(reason) = finish_reason(&event["delta"]["stop_reason"]) {
+                completion.finish_reason = Some(reason);
+            }
+            let usage = completion.usage.get_or_insert_with(TokenUsage::default);
+            usage.completion_tokens = event["usage"]["output_tokens"].as_u64().unwrap_or(usage.completion_tokens);
+            Vec::new()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}` as
+/// "Overloaded (overloaded_error)"
+fn error_message(body: &str) -> String {
+    let parsed = serde_json::from_str::<serde_json::Value>(body).ok();
+    match parsed.as_ref().map(|json| (&json["error"]["type"], &json["error"]["message"])) {
+        Some((serde_json::Value::String(kind), serde_json::Value::String(message))) => {
+            format!("{} ({})", message, kind)
+        }
+        _ => crate::inference::error_message(body),
+    }
+}
+
+/// Send an OpenAI-style chat `body` to the provider's Messages API, streaming the reply to
+/// `sink` if given
+pub async fn send(
+    client: &reqwest::Client,
+    provider: &ResolvedProvider,
+    body: &[u8],
+    sink: Option<Sink<'_>>,
+) -> Result<Completion, InferenceError> {
+    let body: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| InferenceError::Config(e.to_string()))?;
+    let mut request = to_messages_body(&body)?;
+    if sink.is_some() {
+        request["stream"] = true.into();
+    }
+    let response = provider
+        .authorize(client.post(format!("{}/messages", provider.api_base())))
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| InferenceError::Network(e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        if let Some(sink) = sink {
+            return crate::inference::read_stream(response, sink, decode_event).await;
+        }
+    }
+    let text = response.text().await.map_err(|e| InferenceError::Network(e.to_string()))?;
+    if !status.is_success() {
+        return Err(InferenceError::Provider { status: status.as_u16(), message: error_message(&text) });
+    }
+    let json: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| InferenceError::InvalidResponse(e.to_string()))?;
+    parse_response(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::DynamicImage::new_rgb8(width, height);
+        let mut out = Vec::new();
+        image.write_to(&mut Cursor::new(&mut out), ImageOutputFormat::Png).unwrap();
+        out
+    }
+
+    #[test]
+    fn translates_system_prompts_images_and_params() {
+        let frame = format!("data:image/png;base64,{}", BASE64.encode(png(4, 4)));
+        let body = serde_json::json!({
+            "model": "claude-x",
+            "temperature": 0.2,
+            "stop": "END",
+            "seed": 7,
+            "messages": [
+                { "role": "system", "content": "Describe the screen." },
+                { "role": "user", "content": [
+                    { "type": "text", "text": "Frame:" },
+                    { "type": "image_url", "image_url": { "url": frame } }
+                ] },
+                { "role": "user", "content": "Be brief." }
+            ]
+        });
+        let out = to_messages_body(&body).unwrap();
+        assert_eq!(out["system"], "Describe the screen.");
+        assert_eq!(out["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(out["temperature"], 0.2);
+        assert_eq!(out["stop_sequences"], serde_json::json!(["END"]));
+        assert!(out.get("seed").is_none());
+        let messages = out["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        let content = messages[0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[1]["source"]["media_type"], "image/png");
+    }
+
+    #[test]
+    fn translates_tool_calls_and_results() {
+        let body = serde_json::json!({
+            "model": "claude-x",
+            "tools": [{ "type": "function", "function": {
+                "name": "run_command", "description": "Run it", "parameters": { "type": "object" }
+            } }],
+            "messages": [
+                { "role": "user", "content": "Which branch am I on?" },
+                { "role": "assistant", "content": "", "tool_calls": [{
+                    "id": "toolu_1", "type": "function",
+                    "function": { "name": "run_command", "arguments": "{\"command\":\"git branch\"}" }
+                }] },
+                { "role": "tool", "tool_call_id": "toolu_1", "content": "Exit status 0" },
+                { "role": "user", "content": "Answer briefly." }
+            ]
+        });
+        let out = to_messages_body(&body).unwrap();
+        assert_eq!(out["tools"][0]["input_schema"]["type"], "object");
+        let messages = out["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"], serde_json::json!([{
+            "type": "tool_use", "id": "toolu_1", "name": "run_command", "input": { "command": "git branch" }
+        }]));
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(messages[2]["content"][1]["text"], "Answer briefly.");
+
+        let reply = serde_json::json!({
+            "content": [{ "type": "tool_use", "id": "toolu_2", "name": "notify", "input": { "title": "Hi" } }],
+            "stop_reason": "tool_use"
+        });
+        let completion = parse_response(&reply).unwrap();
+        assert_eq!(completion.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(completion.tool_calls[0].function.arguments, r#"{"title":"Hi"}"#);
+    }
+
+    #[test]
+    fn downscales_large_images() {
+        let (small, media_type) = fit_image(png(100, 50), "image/png").unwrap();
+        assert_eq!(media_type, "image/png");
+        assert_eq!(image::load_from_memory(&small).unwrap().dimensions(), (100, 50));
+
+        let (fitted, _) = fit_image(png(3136, 1000), "image/png").unwrap();
+        assert_eq!(image::load_from_memory(&fitted).unwrap().dimensions(), (MAX_IMAGE_EDGE, 500));
+    }
+
+    #[test]
+    fn reads_replies_and_errors() {
+        let body = serde_json::json!({
+            "content": [{ "type": "text", "text": "A code editor." }],
+            "stop_reason": "max_tokens",
+            "usage": { "input_tokens": 900, "output_tokens": 4 }
+        });
+        let completion = parse_response(&body).unwrap();
+        assert_eq!(completion.content, "A code editor.");
+        assert_eq!(completion.finish_reason.as_deref(), Some("length"));
+        assert_eq!(completion.usage, Some(TokenUsage { prompt_tokens: 900, completion_tokens: 4 }));
+
+        let error = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(error_message(error), "Overloaded (overloaded_error)");
+    }
+
+    #[test]
+    fn decodes_streamed_events() {
+        let events = [
+            serde_json::json!({ "type": "message_start", "message": { "usage": { "input_tokens": 40 } } }),
+            serde_json::json!({ "type": "content_block_delta",
+                "delta": { "type": "thinking_delta", "thinking": "?" } }),
+            serde_json::json!({ "type": "content_block_delta", "delta": { "type": "text_delta", "text": "A " } }),
+            serde_json::json!({ "type": "ping" }),
+            serde_json::json!({ "type": "message_delta", "delta": { "stop_reason": "end_turn" },
+                "usage": { "output_tokens": 2 } }),
+        ];
+        let mut completion = Completion::default();
+        let forwarded: Vec<StreamEvent> =
+            events.iter().flat_map(|event| decode_event(event, &mut completion)).collect();
+        assert_eq!(
+            forwarded,
+            vec![
+                StreamEvent::Reasoning { content: "?".to_string() },
+                StreamEvent::Delta { content: "A ".to_string() },
+            ]
+        );
+        assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(completion.usage, Some(TokenUsage { prompt_tokens: 40, completion_tokens: 2 }));
+    }
+}
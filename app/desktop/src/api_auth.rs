@@ -0,0 +1,227 @@
+// In src-tauri/src/api_auth.rs
+//
+// Token authentication for the built-in API server. A random token is created on first
+// run and kept in `<app_data_dir>/api_token`; every API route then requires it, as
+// `Authorization: Bearer <token>`, an `x-observer-token` header or a `token` query
+// parameter (for EventSource and WebSocket clients, which can't set headers). The app's
+// own windows get it through `get_api_token`. The static UI files and `/ping` stay open.
+//
+// Rotating the token locks out every client still holding the old one.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::AppState;
+use axum::extract::{Request, State as AxumState};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager, State};
+
+pub const TOKEN_HEADER: &str = "x-observer-token";
+const TOKEN_QUERY: &str = "token";
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiAuthSettings {
+    /// Off lets anything that can reach the port use the API
+    #[serde(default = "default_true")]
+    pub required: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ApiAuthSettings {
+    fn default() -> Self {
+        Self { required: true }
+    }
+}
+
+pub struct ApiAuthState {
+    token: RwLock<String>,
+    path: Option<PathBuf>,
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn save(path: &PathBuf, token: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, token).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+impl ApiAuthState {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let path = app_handle.path().app_data_dir().ok().map(|dir| dir.join("api_token"));
+        let stored = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty());
+        let token = match stored {
+            Some(token) => token,
+            None => {
+                let token = new_token();
+                match &path {
+                    Some(path) => {
+                        if let Err(e) = save(path, &token) {
+                            log::error!("Failed to save the API token, it will change on restart: {}", e);
+                        }
+                    }
+                    None => log::error!("No app data directory, the API token will change on restart"),
+                }
+                token
+            }
+        };
+        Self { token: RwLock::new(token), path }
+    }
+
+    pub fn token(&self) -> String {
+        self.token.read().unwrap().clone()
+    }
+
+    fn rotate(&self) -> Result<String, String> {
+        let token = new_token();
+        if let Some(path) = &self.path {
+            save(path, &token)?;
+        }
+        *self.token.write().unwrap() = token.clone();
+        Ok(token)
+    }
+}
+
+/// Compare without stopping at the first difference, so timing doesn't give the token away
+fn matches(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// The token a request carries, from any of the accepted places
+fn presented(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    if let Some(bearer) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(bearer.trim().to_string());
+    }
+    if let Some(token) = headers.get(TOKEN_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(token.trim().to_string());
+    }
+    request.uri().query()?.split('&').find_map(|pair| match pair.split_once('=') {
+        Some((TOKEN_QUERY, value)) => Some(value.to_string()),
+        _ => None,
+    })
+}
+
+/// Drop the token from headers passed on upstream, so the proxy doesn't hand it to Ollama
+pub fn strip_headers(headers: &mut HeaderMap) {
+    headers.remove(header::AUTHORIZATION);
+    headers.remove(TOKEN_HEADER);
+}
+
+/// `query` without the `token` parameter
+pub fn strip_query(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| pair.split('=').next() != Some(TOKEN_QUERY))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Whether a request presenting `given` may go ahead: it is the token, or none is required
+pub fn accepts(app_handle: &AppHandle, given: Option<&str>) -> bool {
+    let required = app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().api_auth.required;
+    if !required {
+        return true;
+    }
+    let token = app_handle.state::<ApiAuthState>().token();
+    given.is_some_and(|given| matches(given, &token))
+}
+
+/// Middleware for the API routes: reject requests without the token
+pub async fn require_token(AxumState(state): AxumState<AppState>, request: Request, next: Next) -> Response {
+    // CORS preflights never carry credentials
+    if request.method() == Method::OPTIONS || accepts(&state.app_handle, presented(&request).as_deref()) {
+        return next.run(request).await;
+    }
+    log::debug!("Rejected {} {} without a valid API token", request.method(), request.uri().path());
+    let body = serde_json::json!({ "error": "A valid API token is required" });
+    (StatusCode::UNAUTHORIZED, axum::Json(body)).into_response()
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_api_token(auth_state: State<'_, ApiAuthState>) -> Result<String, String> {
+    Ok(auth_state.token())
+}
+
+/// Replace the token; returns the new one
+#[tauri::command]
+pub async fn rotate_api_token(auth_state: State<'_, ApiAuthState>) -> Result<String, String> {
+    log::info!("Rotating the API token");
+    auth_state.rotate()
+}
+
+#[tauri::command]
+pub async fn get_api_auth_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<ApiAuthSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().api_auth.clone())
+}
+
+#[tauri::command]
+pub async fn set_api_auth_settings(
+    settings: ApiAuthSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting API auth settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.api_auth = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str, header: Option<(&str, &str)>) -> Request {
+        let mut builder = axum::http::Request::builder().uri(uri);
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(axum::body::Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn finds_the_token_wherever_it_is_sent() {
+        let bearer = request("/ask", Some(("authorization", "Bearer abc")));
+        assert_eq!(presented(&bearer).as_deref(), Some("abc"));
+        let custom = request("/ask", Some((TOKEN_HEADER, "abc")));
+        assert_eq!(presented(&custom).as_deref(), Some("abc"));
+        let query = request("/commands-stream?x=1&token=abc", None);
+        assert_eq!(presented(&query).as_deref(), Some("abc"));
+        assert_eq!(presented(&request("/ask?tokens=abc", None)), None);
+        assert_eq!(strip_query("x=1&token=abc&tokens=2"), "x=1&tokens=2");
+
+        assert!(matches("abc", "abc"));
+        assert!(!matches("abd", "abc"));
+        assert!(!matches("ab", "abc"));
+        assert_eq!(new_token().len(), 64);
+    }
+}
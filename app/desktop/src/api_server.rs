@@ -0,0 +1,252 @@
+// In src-tauri/src/api_server.rs
+//
+// Where the built-in API server (port 3838 by default) listens. The address comes from
+// `apiServer` in settings.json, with OBSERVER_API_HOST and OBSERVER_API_PORT taking
+// precedence; a host of 0.0.0.0 (or ::) opens the API to the LAN. When the port is taken
+// the server tries the next few and then any free port, rather than not starting at all,
+// and announces the address it got with `api-server-started`.
+//
+// Behind a reverse proxy that forwards a sub-path (Traefik, nginx: `/observer-api/`), set
+// `basePath` or OBSERVER_BASE_PATH to it: every route is then served under that path as
+// well as at the root, and the URLs the server reports include it.
+//
+// With `tls.enabled` it serves HTTPS instead; see `server_tls`. Browser access from other
+// origins is set with `cors`; see `server_cors`. `localSocket` adds a Unix socket or named
+// pipe for local tools; see `local_socket`. A server open to the LAN is announced over
+// mDNS unless `mdns` is off; see `mdns`. `webrtc` configures video streaming to viewers;
+// see `webrtc_stream`.
+//
+// Changes apply the next time the app starts.
+
+use crate::grpc::GrpcSettings;
+use crate::local_socket::LocalSocketSettings;
+use crate::rate_limit::LimitSettings;
+use crate::server_cors::CorsSettings;
+use crate::server_tls::TlsSettings;
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::webrtc_stream::WebRtcSettings;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tokio::net::TcpListener;
+
+pub const STARTED_EVENT: &str = "api-server-started";
+
+const HOST_ENV: &str = "OBSERVER_API_HOST";
+const PORT_ENV: &str = "OBSERVER_API_PORT";
+const BASE_PATH_ENV: &str = "OBSERVER_BASE_PATH";
+
+/// Ports after the configured one tried before leaving the choice to the OS
+const FALLBACK_ATTEMPTS: u16 = 10;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerSettings {
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Use another port when `port` is taken instead of not starting
+    #[serde(default = "default_true")]
+    pub fallback_port: bool,
+    #[serde(default)]
+    pub tls: TlsSettings,
+    /// Path prefix of every route, e.g. `/observer-api`; empty to serve from the root
+    #[serde(default)]
+    pub base_path: String,
+    #[serde(default)]
+    pub cors: CorsSettings,
+    /// Hand over to an instance that is already running instead of starting another
+    #[serde(default = "default_true")]
+    pub single_instance: bool,
+    #[serde(default)]
+    pub local_socket: LocalSocketSettings,
+    /// Announce the server on the LAN when it listens beyond loopback
+    #[serde(default = "default_true")]
+    pub mdns: bool,
+    #[serde(default)]
+    pub webrtc: WebRtcSettings,
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    #[serde(default)]
+    pub limits: LimitSettings,
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    3838
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            port: default_port(),
+            fallback_port: true,
+            tls: TlsSettings::default(),
+            base_path: String::new(),
+            cors: CorsSettings::default(),
+            single_instance: true,
+            local_socket: LocalSocketSettings::default(),
+            mdns: true,
+            webrtc: WebRtcSettings::default(),
+            grpc: GrpcSettings::default(),
+            limits: LimitSettings::default(),
+        }
+    }
+}
+
+/// Payload of `api-server-started`
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStarted {
+    /// How this machine reaches the server
+    pub url: String,
+    pub host: String,
+    pub port: u16,
+    pub requested_port: u16,
+    pub tls: bool,
+    /// Normalized: empty, or a leading slash and no trailing one
+    pub base_path: String,
+}
+
+/// Host and port from `settings`, overridden by the environment as read by `env`
+fn resolve(settings: &ApiServerSettings, env: impl Fn(&str) -> Option<String>) -> (String, u16) {
+    let host = env(HOST_ENV)
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| settings.host.clone());
+    let port = match env(PORT_ENV).map(|port| port.trim().parse::<u16>()) {
+        Some(Ok(port)) => port,
+        Some(Err(e)) => {
+            log::warn!("Ignoring {}: {}", PORT_ENV, e);
+            settings.port
+        }
+        None => settings.port,
+    };
+    (host, port)
+}
+
+/// `/a/b` for any of `a/b`, `/a/b/`, ...; empty for the root
+fn normalize_base_path(path: &str) -> String {
+    let path = path.trim().trim_matches('/');
+    match path {
+        "" => String::new(),
+        path => format!("/{}", path),
+    }
+}
+
+/// The base path from `settings`, overridden by the environment as read by `env`
+fn resolve_base_path(settings: &ApiServerSettings, env: impl Fn(&str) -> Option<String>) -> String {
+    normalize_base_path(&env(BASE_PATH_ENV).unwrap_or_else(|| settings.base_path.clone()))
+}
+
+/// The URL for reaching a server bound to `host`; wildcard addresses mean loopback
+pub fn local_url(host: &str, port: u16, tls: bool) -> String {
+    let host = match host {
+        "0.0.0.0" | "" => "127.0.0.1".to_string(),
+        "::" | "[::]" => "[::1]".to_string(),
+        host if host.contains(':') && !host.starts_with('[') => format!("[{}]", host),
+        host => host.to_string(),
+    };
+    let scheme = if tls { "https" } else { "http" };
+    format!("{}://{}:{}", scheme, host, port)
+}
+
+/// Listen where the settings say, or on a fallback port if that one is taken
+pub async fn bind(settings: &ApiServerSettings) -> Result<(TcpListener, ServerStarted), String> {
+    let (host, requested_port) = resolve(settings, |name| std::env::var(name).ok());
+    let base_path = resolve_base_path(settings, |name| std::env::var(name).ok());
+    let bind_host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let mut ports = vec![requested_port];
+    if settings.fallback_port {
+        ports.extend((1..=FALLBACK_ATTEMPTS).filter_map(|offset| requested_port.checked_add(offset)));
+        // Any free port
+        ports.push(0);
+    }
+    for port in ports {
+        match TcpListener::bind((bind_host, port)).await {
+            Ok(listener) => {
+                let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+                if port != requested_port {
+                    log::warn!("Port {} is in use, API server moved to port {}", requested_port, port);
+                }
+                let started = ServerStarted {
+                    url: format!("{}{}", local_url(&host, port, settings.tls.enabled), base_path),
+                    host,
+                    port,
+                    requested_port,
+                    tls: settings.tls.enabled,
+                    base_path,
+                };
+                return Ok((listener, started));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                log::debug!("Port {} on {} is in use", port, host);
+            }
+            Err(e) => return Err(format!("Failed to bind to {}:{}: {}", host, port, e)),
+        }
+    }
+    Err(format!("Port {} on {} is in use. Is another instance running?", requested_port, host))
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_api_server_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<ApiServerSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().api_server.clone())
+}
+
+/// Takes effect on the next start
+#[tauri::command]
+pub async fn set_api_server_settings(
+    settings: ApiServerSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if settings.host.trim().is_empty() {
+        return Err("Host must not be empty".to_string());
+    }
+    let settings = ApiServerSettings { base_path: normalize_base_path(&settings.base_path), ..settings };
+    log::info!("Setting API server settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.api_server = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_overrides_settings() {
+        let settings = ApiServerSettings::default();
+        assert_eq!(resolve(&settings, |_| None), ("127.0.0.1".to_string(), 3838));
+        let env = |name: &str| match name {
+            HOST_ENV => Some("0.0.0.0".to_string()),
+            _ => Some("4000".to_string()),
+        };
+        assert_eq!(resolve(&settings, env), ("0.0.0.0".to_string(), 4000));
+        let bad_port = |name: &str| (name == PORT_ENV).then(|| "lots".to_string());
+        assert_eq!(resolve(&settings, bad_port).1, 3838);
+
+        assert_eq!(local_url("0.0.0.0", 3838, false), "http://127.0.0.1:3838");
+        assert_eq!(local_url("::", 3838, false), "http://[::1]:3838");
+        assert_eq!(local_url("fe80::1", 80, false), "http://[fe80::1]:80");
+        assert_eq!(local_url("192.168.1.5", 3838, true), "https://192.168.1.5:3838");
+
+        assert_eq!(resolve_base_path(&settings, |_| None), "");
+        let proxied = ApiServerSettings { base_path: "observer-api/".to_string(), ..settings.clone() };
+        assert_eq!(resolve_base_path(&proxied, |_| None), "/observer-api");
+        let env_path = |name: &str| (name == BASE_PATH_ENV).then(|| " /a/b/ ".to_string());
+        assert_eq!(resolve_base_path(&proxied, env_path), "/a/b");
+        assert_eq!(normalize_base_path("/"), "");
+    }
+}
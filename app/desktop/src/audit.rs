@@ -0,0 +1,580 @@
+// In src-tauri/src/audit.rs
+//
+// Outbound data audit log. Every payload the app sends off-box (proxied model requests,
+// and webhooks/provider calls the frontend reports) is recorded in audit.sqlite with its
+// destination, model, byte counts, SHA-256 hashes of any images and a short prompt
+// excerpt, so users can check exactly what was shared and with whom. Entries are kept for
+// `RETENTION_DAYS`; older ones are dropped as new ones are written, at most hourly.
+
+use crate::redaction::Redactions;
+use crate::storage;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+/// Prompt excerpts are cut to this many characters
+const PROMPT_EXCERPT_CHARS: usize = 500;
+
+/// How long entries are kept
+const RETENTION_DAYS: i64 = 90;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// How often old entries are looked for
+const PRUNE_INTERVAL_MS: i64 = 60 * 60 * 1000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS outbound_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    destination TEXT NOT NULL,
+    method TEXT NOT NULL,
+    model TEXT,
+    agent_id TEXT,
+    request_bytes INTEGER NOT NULL,
+    response_bytes INTEGER,
+    status INTEGER,
+    image_hashes TEXT NOT NULL,
+    prompt TEXT,
+    redactions TEXT
+);
+CREATE INDEX IF NOT EXISTS outbound_audit_timestamp ON outbound_audit(timestamp);
+";
+
+/// What an outbound request was for
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundKind {
+    /// Model request, forwarded by the local proxy or sent by `inference`
+    Llm,
+    /// Webhook or other non-model call
+    Webhook,
+}
+
+impl OutboundKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            OutboundKind::Llm => "llm",
+            OutboundKind::Webhook => "webhook",
+        }
+    }
+
+    fn from_str(kind: &str) -> Self {
+        match kind {
+            "webhook" => OutboundKind::Webhook,
+            _ => OutboundKind::Llm,
+        }
+    }
+}
+
+/// One outbound request, as written to the log
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboundRecord {
+    pub kind: OutboundKind,
+    pub destination: String,
+    pub method: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    pub request_bytes: u64,
+    #[serde(default)]
+    pub image_hashes: Vec<String>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// What the redaction stage masked before sending: rule name -> count
+    #[serde(default)]
+    pub redactions: Redactions,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    id: i64,
+    timestamp: i64,
+    #[serde(flatten)]
+    record: OutboundRecord,
+    response_bytes: Option<i64>,
+    status: Option<u16>,
+}
+
+pub struct AuditState {
+    db: Mutex<Option<Connection>>,
+    /// When old entries were last dropped, ms since the epoch
+    last_pruned: AtomicI64,
+}
+
+impl AuditState {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let db = match storage::open_database(app_handle, "audit", SCHEMA) {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                log::error!("Outbound audit log unavailable: {}", e);
+                None
+            }
+        };
+        Self {
+            db: Mutex::new(db),
+            last_pruned: AtomicI64::new(0),
+        }
+    }
+
+    fn with_db<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let guard = self.db.lock().unwrap();
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| "Audit database unavailable".to_string())?;
+        f(conn).map_err(|e| e.to_string())
+    }
+
+    /// Drop entries past `RETENTION_DAYS` unless that was done within the last hour
+    fn prune_if_due(&self, now: i64) {
+        if now - self.last_pruned.load(Ordering::Relaxed) < PRUNE_INTERVAL_MS {
+            return;
+        }
+        self.last_pruned.store(now, Ordering::Relaxed);
+        match self.with_db(|conn| prune(conn, now - RETENTION_DAYS * DAY_MS)) {
+            Ok(0) => {}
+            Ok(dropped) => log::info!(
+                "Dropped {} outbound audit entries older than {} days",
+                dropped,
+                RETENTION_DAYS
+            ),
+            Err(e) => log::warn!("Failed to prune the outbound audit log: {}", e),
+        }
+    }
+}
+
+fn insert(conn: &Connection, record: &OutboundRecord, timestamp: i64) -> rusqlite::Result<i64> {
+    let image_hashes = serde_json::to_string(&record.image_hashes).unwrap_or_default();
+    let redactions = (!record.redactions.is_empty())
+        .then(|| serde_json::to_string(&record.redactions).unwrap_or_default());
+    conn.execute(
+        "INSERT INTO outbound_audit
+         (timestamp, kind, destination, method, model, agent_id, request_bytes, image_hashes, prompt, redactions)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            timestamp,
+            record.kind.as_str(),
+            record.destination,
+            record.method,
+            record.model,
+            record.agent_id,
+            record.request_bytes as i64,
+            image_hashes,
+            record.prompt,
+            redactions,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn update_response(
+    conn: &Connection,
+    id: i64,
+    status: Option<u16>,
+    response_bytes: u64,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE outbound_audit SET status = COALESCE(?2, status), response_bytes = ?3 WHERE id = ?1",
+        params![id, status, response_bytes as i64],
+    )
+}
+
+/// Delete entries written before `before` (ms since the epoch); how many went
+fn prune(conn: &Connection, before: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM outbound_audit WHERE timestamp < ?1",
+        params![before],
+    )
+}
+
+/// Newest-first entries since `since`, optionally to destinations containing `destination`
+/// and of one `kind`
+fn query(
+    conn: &Connection,
+    since: i64,
+    destination: Option<&str>,
+    kind: Option<OutboundKind>,
+    limit: u32,
+) -> rusqlite::Result<Vec<AuditEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, kind, destination, method, model, agent_id, request_bytes,
+                response_bytes, status, image_hashes, prompt, redactions
+         FROM outbound_audit
+         WHERE timestamp >= ?1
+           AND (?2 IS NULL OR instr(destination, ?2) > 0)
+           AND (?3 IS NULL OR kind = ?3)
+         ORDER BY id DESC LIMIT ?4",
+    )?;
+    let rows = stmt.query_map(
+        params![since, destination, kind.map(|k| k.as_str()), limit as i64],
+        |row| {
+            let kind: String = row.get(2)?;
+            let image_hashes: String = row.get(10)?;
+            let redactions: Option<String> = row.get(12)?;
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                record: OutboundRecord {
+                    kind: OutboundKind::from_str(&kind),
+                    destination: row.get(3)?,
+                    method: row.get(4)?,
+                    model: row.get(5)?,
+                    agent_id: row.get(6)?,
+                    request_bytes: row.get::<_, i64>(7)? as u64,
+                    image_hashes: serde_json::from_str(&image_hashes).unwrap_or_default(),
+                    prompt: row.get(11)?,
+                    redactions: redactions
+                        .and_then(|r| serde_json::from_str(&r).ok())
+                        .unwrap_or_default(),
+                },
+                response_bytes: row.get(8)?,
+                status: row.get(9)?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+/// Model, prompt text and image hashes pulled out of a request body
+#[derive(Default)]
+pub struct PayloadSummary {
+    pub model: Option<String>,
+    pub prompt: Option<String>,
+    pub image_hashes: Vec<String>,
+}
+
+/// Inspect an Ollama- or OpenAI-style JSON body. Non-JSON bodies yield an empty summary.
+pub fn summarize_payload(body: &[u8]) -> PayloadSummary {
+    let Ok(json) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return PayloadSummary::default();
+    };
+
+    let mut texts = Vec::new();
+    let mut image_hashes = Vec::new();
+    collect_payload(&json, &mut texts, &mut image_hashes);
+
+    let prompt = (!texts.is_empty()).then(|| {
+        let joined = texts.join("\n");
+        match joined.char_indices().nth(PROMPT_EXCERPT_CHARS) {
+            Some((cut, _)) => format!("{}…", &joined[..cut]),
+            None => joined,
+        }
+    });
+
+    PayloadSummary {
+        model: json["model"].as_str().map(String::from),
+        prompt,
+        image_hashes,
+    }
+}
+
+fn collect_payload(value: &serde_json::Value, texts: &mut Vec<String>, hashes: &mut Vec<String>) {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    // Ollama: "prompt"/"system" strings, "content" on chat messages
+                    ("prompt" | "system" | "content" | "text", Value::String(text)) => {
+                        texts.push(text.clone())
+                    }
+                    // Ollama: "images": [base64, ...]
+                    ("images", Value::Array(images)) => {
+                        hashes.extend(images.iter().filter_map(|i| i.as_str()).map(hash_base64))
+                    }
+                    // OpenAI: {"image_url": {"url": "data:image/png;base64,..."}}
+                    ("url", Value::String(url)) if url.starts_with("data:") => {
+                        if let Some((_, data)) = url.split_once(",") {
+                            hashes.push(hash_base64(data));
+                        }
+                    }
+                    _ => collect_payload(value, texts, hashes),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_payload(item, texts, hashes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// SHA-256 of the decoded image bytes (of the raw string if it isn't valid base64)
+fn hash_base64(data: &str) -> String {
+    use base64::Engine;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .unwrap_or_else(|_| data.as_bytes().to_vec());
+    Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Record an outbound request. Returns the row id so the response can be filled in later.
+pub fn record_outbound(app_handle: &AppHandle, record: &OutboundRecord) -> Option<i64> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    let state = app_handle.state::<AuditState>();
+    state.prune_if_due(timestamp);
+    match state.with_db(|conn| insert(conn, record, timestamp)) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            log::warn!("Failed to write outbound audit entry: {}", e);
+            None
+        }
+    }
+}
+
+/// Fill in the response side of an entry once it is known
+pub fn record_response(app_handle: &AppHandle, id: i64, status: Option<u16>, response_bytes: u64) {
+    let result = app_handle
+        .state::<AuditState>()
+        .with_db(|conn| update_response(conn, id, status, response_bytes));
+    if let Err(e) = result {
+        log::warn!("Failed to update outbound audit entry {}: {}", id, e);
+    }
+}
+
+/// Counts streamed response bytes and writes the total when the stream is dropped,
+/// whether it completed or the client went away.
+pub struct ResponseTally {
+    app_handle: AppHandle,
+    id: i64,
+    bytes: u64,
+}
+
+impl ResponseTally {
+    pub fn new(app_handle: AppHandle, id: i64) -> Self {
+        Self { app_handle, id, bytes: 0 }
+    }
+
+    pub fn add(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+    }
+}
+
+impl Drop for ResponseTally {
+    fn drop(&mut self) {
+        record_response(&self.app_handle, self.id, None, self.bytes);
+    }
+}
+
+// Tauri commands
+
+/// Let the frontend log requests it sends directly (webhooks, cloud providers)
+#[tauri::command]
+pub async fn audit_record_outbound(record: OutboundRecord, app_handle: AppHandle) -> Result<Option<i64>, String> {
+    Ok(record_outbound(&app_handle, &record))
+}
+
+#[tauri::command]
+pub async fn audit_record_response(
+    id: i64,
+    status: Option<u16>,
+    response_bytes: u64,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    record_response(&app_handle, id, status, response_bytes);
+    Ok(())
+}
+
+/// Newest-first audit entries, optionally filtered by time, destination substring and kind
+#[tauri::command]
+pub async fn audit_query(
+    since: Option<i64>,
+    destination: Option<String>,
+    kind: Option<OutboundKind>,
+    limit: Option<u32>,
+    audit_state: State<'_, AuditState>,
+) -> Result<Vec<AuditEntry>, String> {
+    audit_state.with_db(|conn| {
+        query(
+            conn,
+            since.unwrap_or(0),
+            destination.as_deref(),
+            kind,
+            limit.unwrap_or(200),
+        )
+    })
+}
+
+#[tauri::command]
+pub async fn audit_clear(audit_state: State<'_, AuditState>) -> Result<(), String> {
+    log::info!("Clearing outbound audit log");
+    audit_state.with_db(|conn| conn.execute("DELETE FROM outbound_audit", []).map(|_| ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn
+    }
+
+    fn record(kind: OutboundKind, destination: &str) -> OutboundRecord {
+        OutboundRecord {
+            kind,
+            destination: destination.to_string(),
+            method: "POST".to_string(),
+            model: None,
+            agent_id: None,
+            request_bytes: 10,
+            image_hashes: Vec::new(),
+            prompt: None,
+            redactions: Redactions::new(),
+        }
+    }
+
+    #[test]
+    fn stores_entries_and_fills_in_their_responses() {
+        let conn = database();
+        let sent = OutboundRecord {
+            model: Some("llava".to_string()),
+            agent_id: Some("watcher".to_string()),
+            image_hashes: vec!["ab12".to_string()],
+            prompt: Some("What is on screen?".to_string()),
+            redactions: Redactions::from([("email".to_string(), 2)]),
+            ..record(OutboundKind::Llm, "https://api.example.com/v1")
+        };
+        let id = insert(&conn, &sent, 1_000).unwrap();
+        update_response(&conn, id, Some(200), 512).unwrap();
+        // A stream ending later only updates the byte count
+        update_response(&conn, id, None, 2048).unwrap();
+
+        let entries = query(&conn, 0, None, None, 10).unwrap();
+        let [entry] = entries.as_slice() else {
+            panic!("expected one entry")
+        };
+        assert_eq!(
+            (
+                entry.id,
+                entry.timestamp,
+                entry.status,
+                entry.response_bytes
+            ),
+            (id, 1_000, Some(200), Some(2048))
+        );
+        assert_eq!(entry.record.kind, OutboundKind::Llm);
+        assert_eq!(entry.record.model.as_deref(), Some("llava"));
+        assert_eq!(entry.record.agent_id.as_deref(), Some("watcher"));
+        assert_eq!(entry.record.image_hashes, ["ab12"]);
+        assert_eq!(entry.record.prompt.as_deref(), Some("What is on screen?"));
+        assert_eq!(entry.record.redactions, sent.redactions);
+    }
+
+    #[test]
+    fn prunes_entries_past_retention() {
+        let conn = database();
+        for timestamp in [100, 200, 300] {
+            insert(
+                &conn,
+                &record(OutboundKind::Webhook, "https://hooks.example.com"),
+                timestamp,
+            )
+            .unwrap();
+        }
+        assert_eq!(prune(&conn, 250).unwrap(), 2);
+        let left: Vec<i64> = query(&conn, 0, None, None, 10)
+            .unwrap()
+            .iter()
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(left, [300]);
+        assert_eq!(prune(&conn, 250).unwrap(), 0);
+    }
+
+    #[test]
+    fn lists_newest_first_with_filters() {
+        let conn = database();
+        insert(
+            &conn,
+            &record(OutboundKind::Llm, "https://api.openai.com/v1"),
+            100,
+        )
+        .unwrap();
+        insert(
+            &conn,
+            &record(OutboundKind::Webhook, "https://hooks.slack.com/x"),
+            200,
+        )
+        .unwrap();
+        insert(
+            &conn,
+            &record(OutboundKind::Llm, "http://localhost:11434"),
+            300,
+        )
+        .unwrap();
+
+        let destinations = |entries: Vec<AuditEntry>| -> Vec<String> {
+            entries.into_iter().map(|e| e.record.destination).collect()
+        };
+        let all = destinations(query(&conn, 0, None, None, 10).unwrap());
+        assert_eq!(
+            all,
+            [
+                "http://localhost:11434",
+                "https://hooks.slack.com/x",
+                "https://api.openai.com/v1"
+            ]
+        );
+        assert_eq!(
+            destinations(query(&conn, 150, None, None, 10).unwrap()).len(),
+            2
+        );
+        assert_eq!(
+            destinations(query(&conn, 0, Some("openai"), None, 10).unwrap()),
+            ["https://api.openai.com/v1"]
+        );
+        assert_eq!(
+            destinations(query(&conn, 0, None, Some(OutboundKind::Webhook), 10).unwrap()),
+            ["https://hooks.slack.com/x"]
+        );
+        assert_eq!(
+            destinations(query(&conn, 0, None, Some(OutboundKind::Llm), 1).unwrap()),
+            ["http://localhost:11434"]
+        );
+    }
+
+    #[test]
+    fn summarizes_prompts_and_images_of_either_format() {
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [
+                { "role": "system", "content": "Be brief" },
+                { "role": "user", "content": [
+                    { "type": "text", "text": "What changed?" },
+                    { "type": "image_url", "image_url": { "url": "data:image/png;base64,aGVsbG8=" } }
+                ]}
+            ],
+            "images": ["aGVsbG8="]
+        });
+        let summary = summarize_payload(&serde_json::to_vec(&body).unwrap());
+        assert_eq!(summary.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(summary.prompt.as_deref(), Some("Be brief\nWhat changed?"));
+        // Both carry "hello"
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(summary.image_hashes, [hello, hello]);
+
+        let long = summarize_payload(
+            &serde_json::to_vec(&serde_json::json!({ "prompt": "é".repeat(600) })).unwrap(),
+        );
+        assert_eq!(
+            long.prompt.unwrap().chars().count(),
+            PROMPT_EXCERPT_CHARS + 1
+        );
+        assert!(summarize_payload(b"not json").prompt.is_none());
+    }
+}
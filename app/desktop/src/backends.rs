@@ -0,0 +1,366 @@
+// In src-tauri/src/backends.rs
+//
+// Local inference backends and device pinning. Besides the default Ollama URL, users
+// can register extra local servers (a second Ollama, a llama.cpp server on the dGPU,
+// ...) and pin agents to a backend and/or GPU so a heavy vision model doesn't starve
+// everything else:
+//
+// - the proxy sends a pinned agent's requests to its backend's URL
+// - a pinned device is passed to Ollama as `options.main_gpu` (llama.cpp servers fix
+//   their device at startup, so only the backend pin applies to them)
+// - the in-app engine (llama.cpp) is pinned with `llm_set_main_gpu` before loading
+//
+// `get_backend_utilization` reports per-device memory for the in-app engine, per-GPU
+// load from nvidia-smi where available, and the models each backend has resident.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum BackendKind {
+    #[default]
+    Ollama,
+    /// llama.cpp `llama-server` (OpenAI-compatible)
+    LlamaServer,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InferenceBackend {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub kind: BackendKind,
+    /// Base URL, e.g. http://127.0.0.1:11435
+    pub url: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendPin {
+    /// Backend id; None keeps the default Ollama URL
+    #[serde(default)]
+    pub backend_id: Option<String>,
+    /// GPU index on that backend
+    #[serde(default)]
+    pub device: Option<u32>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendSettings {
+    #[serde(default)]
+    pub backends: Vec<InferenceBackend>,
+    /// Agent id -> pin
+    #[serde(default)]
+    pub pins: HashMap<String, BackendPin>,
+}
+
+/// Where a proxied request for `agent_id` should go
+pub struct Route {
+    /// Replaces the default base URL when set
+    pub base_url: Option<String>,
+    pub kind: BackendKind,
+    pub device: Option<u32>,
+}
+
+pub fn route_for(app_handle: &AppHandle, agent_id: Option<&str>) -> Option<Route> {
+    let shortcut_state = app_handle.state::<UnifiedShortcutState>();
+    let config = shortcut_state.config.lock().unwrap();
+    let pin = config.backends.pins.get(agent_id?)?;
+    let backend = pin
+        .backend_id
+        .as_ref()
+        .and_then(|id| config.backends.backends.iter().find(|b| b.id == *id));
+    Some(Route {
+        base_url: backend.map(|b| b.url.trim_end_matches('/').to_string()),
+        kind: backend.map(|b| b.kind).unwrap_or_default(),
+        device: pin.device,
+    })
+}
+
+/// Add `options.main_gpu` to an Ollama generate/chat body. Returns None if the body
+/// isn't a JSON object (it is then forwarded untouched).
+pub fn pin_device(body: &[u8], device: u32) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let options = value
+        .as_object_mut()?
+        .entry("options")
+        .or_insert_with(|| serde_json::json!({}));
+    options.as_object_mut()?.insert("main_gpu".to_string(), device.into());
+    serde_json::to_vec(&value).ok()
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuUtilization {
+    index: u32,
+    name: String,
+    utilization_percent: Option<f64>,
+    memory_used_bytes: Option<u64>,
+    memory_total_bytes: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResidentModel {
+    name: String,
+    size_bytes: u64,
+    vram_bytes: u64,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendLoad {
+    backend_id: String,
+    name: String,
+    reachable: bool,
+    /// Loaded models (Ollama only)
+    models: Vec<ResidentModel>,
+    /// Agents pinned to this backend
+    pinned_agents: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendUtilization {
+    /// Devices visible to the in-app engine
+    devices: Vec<tauri_plugin_llm_engine::BackendDevice>,
+    /// Per-GPU load (NVIDIA only, via nvidia-smi)
+    gpus: Vec<GpuUtilization>,
+    backends: Vec<BackendLoad>,
+}
+
+fn parse_nvidia_smi(output: &str) -> Vec<GpuUtilization> {
+    const MIB: u64 = 1024 * 1024;
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [index, name, util, used, total] = fields.as_slice() else {
+                return None;
+            };
+            Some(GpuUtilization {
+                index: index.parse().ok()?,
+                name: name.to_string(),
+                utilization_percent: util.parse().ok(),
+                memory_used_bytes: used.parse::<u64>().ok().map(|m| m * MIB),
+                memory_total_bytes: total.parse::<u64>().ok().map(|m| m * MIB),
+            })
+        })
+        .collect()
+}
+
+fn nvidia_gpus() -> Vec<GpuUtilization> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=index,name,utilization.gpu,memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Models an Ollama server currently holds in memory (`/api/ps`)
+async fn ollama_resident_models(client: &reqwest::Client, base_url: &str) -> Option<Vec<ResidentModel>> {
+    #[derive(Deserialize)]
+    struct Ps {
+        #[serde(default)]
+        models: Vec<PsModel>,
+    }
+    #[derive(Deserialize)]
+    struct PsModel {
+        name: String,
+        #[serde(default)]
+        size: u64,
+        #[serde(default)]
+        size_vram: u64,
+    }
+
+    let ps: Ps = client
+        .get(format!("{}/api/ps", base_url.trim_end_matches('/')))
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    Some(
+        ps.models
+            .into_iter()
+            .map(|m| ResidentModel {
+                name: m.name,
+                size_bytes: m.size,
+                vram_bytes: m.size_vram,
+            })
+            .collect(),
+    )
+}
+
+async fn backend_reachable(client: &reqwest::Client, backend: &InferenceBackend) -> bool {
+    let path = match backend.kind {
+        BackendKind::Ollama => "/api/version",
+        BackendKind::LlamaServer => "/health",
+    };
+    client
+        .get(format!("{}{}", backend.url.trim_end_matches('/'), path))
+        .send()
+        .await
+        .map(|r| r.status().is_success())
+        .unwrap_or(false)
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_backend_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<BackendSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().backends.clone())
+}
+
+#[tauri::command]
+pub async fn set_backend_settings(
+    settings: BackendSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    for (agent_id, pin) in &settings.pins {
+        if let Some(id) = &pin.backend_id {
+            if !settings.backends.iter().any(|b| b.id == *id) {
+                return Err(format!("Agent {} is pinned to unknown backend {}", agent_id, id));
+            }
+        }
+    }
+    log::info!(
+        "Setting inference backends: {} backends, {} pinned agents",
+        settings.backends.len(),
+        settings.pins.len()
+    );
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.backends = settings)
+}
+
+/// Pin (or with `pin: null`, unpin) a single agent
+#[tauri::command]
+pub async fn set_agent_backend(
+    agent_id: String,
+    pin: Option<BackendPin>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| match pin {
+        Some(pin) => {
+            config.backends.pins.insert(agent_id, pin);
+        }
+        None => {
+            config.backends.pins.remove(&agent_id);
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn get_backend_utilization(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<BackendUtilization, String> {
+    let settings = shortcut_state.config.lock().unwrap().backends.clone();
+    let default_url = app_handle
+        .state::<crate::AppSettings>()
+        .ollama_url
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| "http://127.0.0.1:11434".to_string());
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(3))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let pinned = |backend_id: Option<&str>| {
+        let mut agents: Vec<String> = settings
+            .pins
+            .iter()
+            .filter(|(_, pin)| pin.backend_id.as_deref() == backend_id)
+            .map(|(agent, _)| agent.clone())
+            .collect();
+        agents.sort();
+        agents
+    };
+
+    let default_models = ollama_resident_models(&client, &default_url).await;
+    let mut backends = vec![BackendLoad {
+        backend_id: "default".to_string(),
+        name: "Ollama".to_string(),
+        reachable: default_models.is_some(),
+        models: default_models.unwrap_or_default(),
+        pinned_agents: pinned(None),
+    }];
+    for backend in &settings.backends {
+        let (reachable, models) = match backend.kind {
+            BackendKind::Ollama => {
+                let models = ollama_resident_models(&client, &backend.url).await;
+                (models.is_some(), models.unwrap_or_default())
+            }
+            BackendKind::LlamaServer => (backend_reachable(&client, backend).await, Vec::new()),
+        };
+        backends.push(BackendLoad {
+            backend_id: backend.id.clone(),
+            name: backend.name.clone(),
+            reachable,
+            models,
+            pinned_agents: pinned(Some(&backend.id)),
+        });
+    }
+
+    let (devices, gpus) = tauri::async_runtime::spawn_blocking(|| {
+        let devices = tauri_plugin_llm_engine::init_engine()
+            .map(|_| tauri_plugin_llm_engine::list_devices())
+            .unwrap_or_default();
+        (devices, nvidia_gpus())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(BackendUtilization {
+        devices,
+        gpus,
+        backends,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injects_main_gpu_into_options() {
+        let body = br#"{"model":"llava","options":{"temperature":0.2}}"#;
+        let pinned: serde_json::Value = serde_json::from_slice(&pin_device(body, 1).unwrap()).unwrap();
+        assert_eq!(pinned["options"]["main_gpu"], 1);
+        assert_eq!(pinned["options"]["temperature"], 0.2);
+
+        let pinned: serde_json::Value = serde_json::from_slice(&pin_device(br#"{"model":"llava"}"#, 0).unwrap()).unwrap();
+        assert_eq!(pinned["options"]["main_gpu"], 0);
+        assert!(pin_device(b"not json", 0).is_none());
+    }
+
+    #[test]
+    fn parses_nvidia_smi_csv() {
+        let gpus = parse_nvidia_smi("0, NVIDIA GeForce RTX 4090, 37, 10240, 24564\n1, Quadro P400, [N/A], 12, 2048\n");
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].utilization_percent, Some(37.0));
+        assert_eq!(gpus[0].memory_used_bytes, Some(10240 * 1024 * 1024));
+        assert_eq!(gpus[1].utilization_percent, None);
+    }
+}
@@ -0,0 +1,409 @@
+// In src-tauri/src/benchmark.rs
+//
+// End-to-end latency benchmark. Runs one synthetic pass of the agent pipeline and times
+// each stage separately, so "it's slow" can be pinned on capture, encoding, OCR, the
+// network or the model:
+//
+//   capture -> scale -> encode (JPEG, current capture settings) -> OCR (optional)
+//   -> model request (upload, time to first token, generation) -> parse
+//
+// The model is called directly on the configured Ollama endpoint with a streaming chat
+// request; Ollama's own prompt/eval durations are used to split the round trip into
+// network and model time.
+//
+// It captures and uploads the screen like any agent, so it is refused while incognito
+// mode is on or capture is paused.
+
+use crate::incognito;
+use crate::shortcuts::UnifiedShortcutState;
+use base64::Engine;
+use futures_util::StreamExt;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_screen_capture::{capture_config, pause};
+
+const DEFAULT_PROMPT: &str = "Describe what is on this screen in one sentence.";
+const MODEL_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTiming {
+    name: String,
+    duration_ms: f64,
+    /// Size of the stage's output, where that's meaningful
+    bytes: Option<u64>,
+    detail: Option<String>,
+    /// Stage didn't run (disabled, unavailable); `detail` says why
+    skipped: bool,
+}
+
+impl StageTiming {
+    fn timed(name: &str, elapsed: Duration, bytes: Option<u64>, detail: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            duration_ms: elapsed.as_secs_f64() * 1000.0,
+            bytes,
+            detail,
+            skipped: false,
+        }
+    }
+
+    fn skipped(name: &str, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            duration_ms: 0.0,
+            bytes: None,
+            detail: Some(reason.into()),
+            skipped: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    stages: Vec<StageTiming>,
+    total_ms: f64,
+    /// Slowest stage that ran
+    bottleneck: Option<String>,
+    /// Model output, for sanity-checking the run
+    response_preview: Option<String>,
+}
+
+struct EncodedFrame {
+    jpeg: Vec<u8>,
+    stages: Vec<StageTiming>,
+}
+
+fn ms(d: Duration) -> String {
+    format!("{:.0} ms", d.as_secs_f64() * 1000.0)
+}
+
+/// Capture the primary screen and encode it the way the capture stream does
+fn capture_and_encode() -> Result<EncodedFrame, String> {
+    if pause::is_paused() {
+        return Err("Capture is paused".to_string());
+    }
+    let started = Instant::now();
+    let screens = screenshots::Screen::all().map_err(|e| format!("Failed to list screens: {}", e))?;
+    let screen = screens
+        .iter()
+        .find(|s| s.display_info.is_primary)
+        .or_else(|| screens.first())
+        .ok_or("No screens found")?;
+    let image: RgbaImage = screen
+        .capture()
+        .map_err(|e| format!("Failed to capture screen: {}", e))?;
+    let capture = StageTiming::timed(
+        "capture",
+        started.elapsed(),
+        Some(image.as_raw().len() as u64),
+        Some(format!("{}x{} RGBA", image.width(), image.height())),
+    );
+
+    let started = Instant::now();
+    let max_width = capture_config::max_width();
+    let scaled = if image.width() > max_width {
+        let height = (image.height() as f32 * max_width as f32 / image.width() as f32) as u32;
+        image::imageops::resize(&image, max_width, height, FilterType::Nearest)
+    } else {
+        image
+    };
+    let rgb = image::DynamicImage::ImageRgba8(scaled).into_rgb8();
+    let scale = StageTiming::timed(
+        "scale",
+        started.elapsed(),
+        Some(rgb.as_raw().len() as u64),
+        Some(format!("{}x{}", rgb.width(), rgb.height())),
+    );
+
+    let started = Instant::now();
+    let quality = capture_config::jpeg_quality();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, quality)
+        .encode(rgb.as_raw(), rgb.width(), rgb.height(), image::ColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+    let encode = StageTiming::timed(
+        "encode",
+        started.elapsed(),
+        Some(jpeg.len() as u64),
+        Some(format!("JPEG q{}", quality)),
+    );
+
+    Ok(EncodedFrame {
+        jpeg,
+        stages: vec![capture, scale, encode],
+    })
+}
+
+#[cfg(feature = "ocr")]
+fn run_ocr(jpeg: &[u8], languages: Vec<String>) -> StageTiming {
+    let started = Instant::now();
+    let options = tauri_plugin_screen_capture::ocr::OcrOptions { languages };
+    match tauri_plugin_screen_capture::ocr::recognize_encoded(jpeg, &options) {
+        Ok(result) => StageTiming::timed(
+            "ocr",
+            started.elapsed(),
+            Some(result.text.len() as u64),
+            Some(format!("{} lines", result.lines.len())),
+        ),
+        Err(e) => StageTiming::skipped("ocr", format!("OCR failed: {}", e)),
+    }
+}
+
+#[cfg(not(feature = "ocr"))]
+fn run_ocr(_jpeg: &[u8], _languages: Vec<String>) -> StageTiming {
+    StageTiming::skipped("ocr", "OCR support is not included in this build")
+}
+
+// Final chunk of an Ollama /api/chat stream (durations are in nanoseconds)
+#[derive(Deserialize, Default)]
+struct ChatChunk {
+    #[serde(default)]
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    load_duration: u64,
+    #[serde(default)]
+    prompt_eval_duration: u64,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_duration: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct ChatMessage {
+    #[serde(default)]
+    content: String,
+}
+
+/// Send the frame to the model; returns the stages and the raw streamed body
+async fn run_model(
+    base_url: &str,
+    model: &str,
+    prompt: &str,
+    jpeg: &[u8],
+) -> Result<(Vec<StageTiming>, Vec<u8>), String> {
+    let body = serde_json::to_vec(&serde_json::json!({
+        "model": model,
+        "stream": true,
+        "messages": [{
+            "role": "user",
+            "content": prompt,
+            "images": [base64::engine::general_purpose::STANDARD.encode(jpeg)],
+        }],
+    }))
+    .map_err(|e| e.to_string())?;
+    let upload_bytes = body.len() as u64;
+
+    let client = reqwest::Client::builder()
+        .timeout(MODEL_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    let response = client
+        .post(format!("{}/api/chat", base_url.trim_end_matches('/')))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Model request failed: {}", e))?;
+    let headers_at = started.elapsed();
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("Model returned {}: {}", status, text.trim()));
+    }
+
+    let mut raw = Vec::new();
+    let mut first_chunk_at = None;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Model stream failed: {}", e))?;
+        first_chunk_at.get_or_insert_with(|| started.elapsed());
+        raw.extend_from_slice(&chunk);
+    }
+    let total = started.elapsed();
+    let first_chunk_at = first_chunk_at.unwrap_or(total);
+
+    Ok((
+        vec![
+            StageTiming::timed(
+                "request",
+                headers_at,
+                Some(upload_bytes),
+                Some("upload + time to response headers".to_string()),
+            ),
+            StageTiming::timed(
+                "firstToken",
+                first_chunk_at.saturating_sub(headers_at),
+                None,
+                Some("prompt and image processing".to_string()),
+            ),
+            StageTiming::timed(
+                "generation",
+                total.saturating_sub(first_chunk_at),
+                Some(raw.len() as u64),
+                None,
+            ),
+        ],
+        raw,
+    ))
+}
+
+/// Parse the NDJSON stream; also splits the round trip using Ollama's own timings
+fn parse_response(raw: &[u8], round_trip: Duration) -> (StageTiming, String, Option<StageTiming>) {
+    let started = Instant::now();
+    let mut text = String::new();
+    let mut last = ChatChunk::default();
+    for line in raw.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+        if let Ok(chunk) = serde_json::from_slice::<ChatChunk>(line) {
+            if let Some(message) = &chunk.message {
+                text.push_str(&message.content);
+            }
+            if chunk.done {
+                last = chunk;
+            }
+        }
+    }
+    let parse = StageTiming::timed("parse", started.elapsed(), Some(text.len() as u64), None);
+
+    // Whatever the server didn't spend loading/evaluating went to the network and queueing
+    let server = Duration::from_nanos(last.load_duration + last.prompt_eval_duration + last.eval_duration);
+    let split = (last.done || last.eval_count > 0).then(|| {
+        StageTiming::timed(
+            "network",
+            round_trip.saturating_sub(server),
+            None,
+            Some(format!(
+                "model load {}, prompt eval {} ({} tokens), generation {} ({} tokens)",
+                ms(Duration::from_nanos(last.load_duration)),
+                ms(Duration::from_nanos(last.prompt_eval_duration)),
+                last.prompt_eval_count,
+                ms(Duration::from_nanos(last.eval_duration)),
+                last.eval_count
+            )),
+        )
+    });
+    (parse, text, split)
+}
+
+// Tauri commands
+
+/// Time one pass of capture -> encode -> (OCR) -> model -> parse.
+/// `model` is an Ollama model name; without it the model stages are skipped.
+#[tauri::command]
+pub async fn run_latency_benchmark(
+    model: Option<String>,
+    include_ocr: Option<bool>,
+    prompt: Option<String>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<BenchmarkReport, String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let started = Instant::now();
+    log::info!("Running latency benchmark (model: {:?})", model);
+
+    let frame = tauri::async_runtime::spawn_blocking(capture_and_encode)
+        .await
+        .map_err(|e| e.to_string())??;
+    let mut stages = frame.stages;
+    let jpeg = frame.jpeg;
+
+    if include_ocr.unwrap_or(false) {
+        let languages = shortcut_state.config.lock().unwrap().ocr.languages_for(None);
+        let image = jpeg.clone();
+        stages.push(
+            tauri::async_runtime::spawn_blocking(move || run_ocr(&image, languages))
+                .await
+                .map_err(|e| e.to_string())?,
+        );
+    } else {
+        stages.push(StageTiming::skipped("ocr", "Not requested"));
+    }
+
+    let mut response_preview = None;
+    match model {
+        Some(model) => {
+            let base_url = match crate::ssh_tunnel::local_url(&app_handle, "ollama") {
+                Some(url) => url,
+                None => {
+                    let configured = app_handle
+                        .state::<crate::AppSettings>()
+                        .ollama_url
+                        .lock()
+                        .unwrap()
+                        .clone()
+                        .unwrap_or_else(|| "http://127.0.0.1:11434".to_string());
+                    crate::tailnet::resolve(&app_handle, &configured)
+                }
+            };
+            let prompt = prompt.as_deref().unwrap_or(DEFAULT_PROMPT);
+
+            let model_started = Instant::now();
+            let (model_stages, raw) = run_model(&base_url, &model, prompt, &jpeg).await?;
+            let round_trip = model_started.elapsed();
+            stages.extend(model_stages);
+
+            let (parse, text, network) = parse_response(&raw, round_trip);
+            stages.extend(network);
+            stages.push(parse);
+            response_preview = Some(text.chars().take(200).collect());
+        }
+        None => {
+            for name in ["request", "firstToken", "generation", "parse"] {
+                stages.push(StageTiming::skipped(name, "No model given"));
+            }
+        }
+    }
+
+    // "network" overlaps the request/token stages, so it isn't a candidate on its own
+    let bottleneck = stages
+        .iter()
+        .filter(|s| !s.skipped && s.name != "network")
+        .max_by(|a, b| a.duration_ms.total_cmp(&b.duration_ms))
+        .map(|s| s.name.clone());
+
+    let report = BenchmarkReport {
+        stages,
+        total_ms: started.elapsed().as_secs_f64() * 1000.0,
+        bottleneck,
+        response_preview,
+    };
+    log::info!(
+        "Latency benchmark finished in {:.0} ms (slowest stage: {:?})",
+        report.total_ms,
+        report.bottleneck
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stream_and_splits_network_time() {
+        let raw = concat!(
+            r#"{"message":{"content":"A code "},"done":false}"#, "\n",
+            r#"{"message":{"content":"editor."},"done":false}"#, "\n",
+            r#"{"message":{"content":""},"done":true,"load_duration":100000000,"prompt_eval_duration":300000000,"prompt_eval_count":600,"eval_duration":500000000,"eval_count":4}"#, "\n",
+        );
+        let (parse, text, network) = parse_response(raw.as_bytes(), Duration::from_millis(1000));
+        assert_eq!(text, "A code editor.");
+        assert_eq!(parse.bytes, Some(14));
+        let network = network.unwrap();
+        assert!((network.duration_ms - 100.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,180 @@
+// In src-tauri/src/capture_api.rs
+//
+// Capture control over the API server, for scripts and dashboards that run without the
+// desktop UI:
+//
+// - GET  /api/capture/targets[?thumbnails=true]: monitors and windows that can be captured
+// - POST /api/capture/start {targetId?, sessionId?}: start a capture session
+// - POST /api/capture/stop {sessionId?}: stop one session, or every one without an id
+// - GET  /api/capture/screenshot[?targetId=&format=png|jpeg|webp]: one full-resolution
+//   image (`webpLossless` too), returned as the image itself
+//
+// A session started here has no frontend channel; its frames go to the in-process tap,
+// so `/ws/frames`, OCR and the remote link see them. Starting capture, screenshots and
+// thumbnails are refused while incognito mode is on. Errors come back as
+// `{ "error": "..." }`. The gRPC API (see `grpc.rs`) shares the functions below.
+
+use crate::events::{self, ApiEvent};
+use crate::{incognito, AppState};
+use axum::extract::{Query, State as AxumState};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::Deserialize;
+use tauri::AppHandle;
+use tauri_plugin_screen_capture::capture_config::FrameEncoding;
+use tauri_plugin_screen_capture::screenshot::{self, Screenshot};
+use tauri_plugin_screen_capture::targets::CaptureTarget;
+use tauri_plugin_screen_capture::{sessions, wire::FrameSink};
+
+pub const INCOGNITO_ERROR: &str = "Capture is disabled while incognito mode is on";
+
+fn error(status: StatusCode, message: impl ToString) -> Response {
+    (status, Json(serde_json::json!({ "error": message.to_string() }))).into_response()
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TargetsQuery {
+    #[serde(default)]
+    pub thumbnails: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StartRequest {
+    #[serde(default)]
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StopRequest {
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotQuery {
+    #[serde(default)]
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub format: Option<FrameEncoding>,
+}
+
+fn content_type(format: FrameEncoding) -> &'static str {
+    match format {
+        FrameEncoding::Jpeg => "image/jpeg",
+        FrameEncoding::Png => "image/png",
+        FrameEncoding::Webp | FrameEncoding::WebpLossless => "image/webp",
+    }
+}
+
+/// Monitors and windows that can be captured
+pub async fn targets(thumbnails: bool) -> Result<Vec<CaptureTarget>, String> {
+    tokio::task::spawn_blocking(move || tauri_plugin_screen_capture::desktop::get_capture_targets(thumbnails))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Start a capture session and announce it; returns its id
+pub fn start(app_handle: &AppHandle, target_id: Option<String>, session_id: Option<&str>) -> Result<String, String> {
+    let session_id = sessions::resolve(session_id).to_string();
+    log::info!("Starting capture session '{}' over the API", session_id);
+    tauri_plugin_screen_capture::desktop::start_capture_session(&session_id, target_id.clone(), FrameSink::discard())
+        .map_err(|e| e.to_string())?;
+    events::publish(app_handle, ApiEvent::CaptureStarted { session_id: session_id.clone(), target_id });
+    Ok(session_id)
+}
+
+/// Stop one session, or every one without an id, and announce it
+pub fn stop(app_handle: &AppHandle, session_id: Option<String>) -> Result<(), String> {
+    let stopped = match &session_id {
+        Some(session_id) => tauri_plugin_screen_capture::desktop::stop_capture_session(session_id),
+        None => tauri_plugin_screen_capture::desktop::stop_all_sessions(),
+    };
+    stopped.map_err(|e| e.to_string())?;
+    log::info!("Stopped capture over the API ({})", session_id.as_deref().unwrap_or("all sessions"));
+    events::publish(app_handle, ApiEvent::CaptureStopped { session_id });
+    Ok(())
+}
+
+/// One full-resolution screenshot, with its image bytes
+pub async fn take_screenshot(
+    target_id: Option<String>,
+    format: FrameEncoding,
+) -> Result<(Screenshot, Vec<u8>), String> {
+    let shot = tokio::task::spawn_blocking(move || screenshot::capture(target_id.as_deref(), format, None))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    match shot.data.as_deref().map(|data| BASE64.decode(data)) {
+        Some(Ok(image)) => Ok((shot, image)),
+        _ => Err("Screenshot has no image data".to_string()),
+    }
+}
+
+pub async fn targets_handler(AxumState(state): AxumState<AppState>, Query(query): Query<TargetsQuery>) -> Response {
+    // Thumbnails are fresh captures
+    if query.thumbnails && incognito::is_active(&state.app_handle) {
+        return error(StatusCode::FORBIDDEN, INCOGNITO_ERROR);
+    }
+    match targets(query.thumbnails).await {
+        Ok(targets) => Json(targets).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+pub async fn start_handler(AxumState(state): AxumState<AppState>, body: Option<Json<StartRequest>>) -> Response {
+    if incognito::is_active(&state.app_handle) {
+        return error(StatusCode::FORBIDDEN, INCOGNITO_ERROR);
+    }
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    match start(&state.app_handle, request.target_id, request.session_id.as_deref()) {
+        Ok(session_id) => Json(serde_json::json!({ "sessionId": session_id })).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+pub async fn stop_handler(AxumState(state): AxumState<AppState>, body: Option<Json<StopRequest>>) -> Response {
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    match stop(&state.app_handle, request.session_id) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+pub async fn screenshot_handler(
+    AxumState(state): AxumState<AppState>,
+    Query(query): Query<ScreenshotQuery>,
+) -> Response {
+    if incognito::is_active(&state.app_handle) {
+        return error(StatusCode::FORBIDDEN, INCOGNITO_ERROR);
+    }
+    let format = query.format.unwrap_or(FrameEncoding::Png);
+    match take_screenshot(query.target_id, format).await {
+        Ok((_, image)) => ([(header::CONTENT_TYPE, content_type(format))], image).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_requests() {
+        let start: StartRequest = serde_json::from_str(r#"{"targetId":"monitor-1"}"#).unwrap();
+        assert_eq!(start.target_id.as_deref(), Some("monitor-1"));
+        assert_eq!(start.session_id, None);
+
+        let Query(query) =
+            Query::<ScreenshotQuery>::try_from_uri(&"/shot?targetId=w-2&format=webp".parse().unwrap()).unwrap();
+        assert_eq!(query.target_id.as_deref(), Some("w-2"));
+        assert_eq!(content_type(query.format.unwrap()), "image/webp");
+    }
+}
@@ -6,12 +6,23 @@ use serde::Deserialize;
 
 // Desktop-only implementation using Enigo
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
-use enigo::{Button, Enigo, Mouse, Settings};
+use enigo::{Button, Coordinate, Enigo, Mouse, Settings};
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+use tauri_plugin_screen_capture::geometry::{self, CoordSpace};
 
 #[derive(Deserialize, Default)]
 pub struct ClickRequest {
     #[serde(default = "default_button")]
     button: String,
+    /// Where to click; the current cursor position if omitted
+    #[serde(default)]
+    x: Option<f64>,
+    #[serde(default)]
+    y: Option<f64>,
+    /// Coordinate space of x/y (defaults to frame pixels, as a vision model sees them)
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    #[serde(default)]
+    space: Option<CoordSpace>,
 }
 
 fn default_button() -> String {
@@ -25,28 +36,53 @@ pub async fn click_handler(
     AxumState(_state): AxumState<AppState>,
     Json(payload): Json<Option<ClickRequest>>,
 ) -> StatusCode {
-    let button_type = payload
-        .map(|p| p.button)
-        .unwrap_or_else(|| "left".to_string());
+    let payload = payload.unwrap_or(ClickRequest {
+        button: default_button(),
+        ..Default::default()
+    });
+    let button_type = payload.button.clone();
 
     log::info!("Received click request: {}", button_type);
 
+    // Resolve the target point through the shared frame <-> screen mapping
+    let target = match (payload.x, payload.y) {
+        (Some(x), Some(y)) => match payload.space.unwrap_or(CoordSpace::Frame) {
+            CoordSpace::Screen => Some((x, y)),
+            space => match geometry::current() {
+                Some(frame) => Some(frame.map(x, y, space, CoordSpace::Screen)),
+                None => {
+                    log::warn!("Click at {:?} coordinates needs an active capture", space);
+                    return StatusCode::CONFLICT;
+                }
+            },
+        },
+        _ => None,
+    };
+
     let button = match button_type.to_lowercase().as_str() {
         "right" => Button::Right,
         _ => Button::Left,
     };
 
     match Enigo::new(&Settings::default()) {
-        Ok(mut enigo) => match enigo.button(button, enigo::Direction::Click) {
-            Ok(_) => {
-                log::info!("Mouse {} click executed successfully", button_type);
-                StatusCode::OK
+        Ok(mut enigo) => {
+            if let Some((x, y)) = target {
+                if let Err(e) = enigo.move_mouse(x.round() as i32, y.round() as i32, Coordinate::Abs) {
+                    log::error!("Failed to move mouse to ({}, {}): {}", x, y, e);
+                    return StatusCode::INTERNAL_SERVER_ERROR;
+                }
             }
-            Err(e) => {
-                log::error!("Failed to execute mouse click: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
+            match enigo.button(button, enigo::Direction::Click) {
+                Ok(_) => {
+                    log::info!("Mouse {} click executed successfully", button_type);
+                    StatusCode::OK
+                }
+                Err(e) => {
+                    log::error!("Failed to execute mouse click: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
             }
-        },
+        }
         Err(e) => {
             log::error!("Failed to initialize Enigo: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
@@ -0,0 +1,467 @@
+// In src-tauri/src/digest.rs
+//
+// Daily digest export. Agents (via the frontend) submit the summaries they generate;
+// once a day the collected entries, plus the day's app usage, are rendered to Markdown
+// or PDF in a user-chosen folder so reports can be archived or shared outside the app.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::storage;
+use crate::usage::{self, AppUsage, UsageState};
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+/// How often the scheduler checks whether today's digest is due
+const SCHEDULE_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of apps listed in the usage section
+const DIGEST_TOP_APPS: u32 = 10;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS digest_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    day TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    agent_id TEXT NOT NULL,
+    title TEXT,
+    content TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS digest_entries_day ON digest_entries(day);
+CREATE TABLE IF NOT EXISTS digest_exports (
+    day TEXT PRIMARY KEY,
+    path TEXT NOT NULL
+);
+";
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFormat {
+    #[default]
+    Markdown,
+    Pdf,
+}
+
+impl DigestFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DigestFormat::Markdown => "md",
+            DigestFormat::Pdf => "pdf",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestSettings {
+    /// Write a digest automatically once a day
+    #[serde(default)]
+    pub enabled: bool,
+    /// Output folder; scheduled exports are skipped until one is chosen
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub format: DigestFormat,
+    /// Local hour (0-23) after which the day's digest is written
+    #[serde(default = "default_export_hour")]
+    pub export_hour: u32,
+    /// Include the day's top apps from usage statistics
+    #[serde(default = "default_true")]
+    pub include_usage: bool,
+}
+
+fn default_export_hour() -> u32 {
+    21
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folder: None,
+            format: DigestFormat::default(),
+            export_hour: default_export_hour(),
+            include_usage: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestEntry {
+    pub timestamp: i64,
+    pub agent_id: String,
+    pub title: Option<String>,
+    pub content: String,
+}
+
+pub struct DigestState {
+    db: Mutex<Option<Connection>>,
+}
+
+impl DigestState {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let db = match storage::open_database(app_handle, "digest", SCHEMA) {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                log::error!("Digest export disabled, database unavailable: {}", e);
+                None
+            }
+        };
+        Self { db: Mutex::new(db) }
+    }
+
+    fn with_db<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let guard = self.db.lock().unwrap();
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| "Digest database unavailable".to_string())?;
+        f(conn).map_err(|e| e.to_string())
+    }
+
+    fn entries_for_day(&self, day: &str) -> Result<Vec<DigestEntry>, String> {
+        self.with_db(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT timestamp, agent_id, title, content FROM digest_entries
+                 WHERE day = ?1 ORDER BY agent_id, timestamp",
+            )?;
+            let rows = stmt.query_map(params![day], |row| {
+                Ok(DigestEntry {
+                    timestamp: row.get(0)?,
+                    agent_id: row.get(1)?,
+                    title: row.get(2)?,
+                    content: row.get(3)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+}
+
+/// Local time of day (HH:MM) for a unix timestamp in milliseconds
+fn format_time(timestamp_ms: i64) -> String {
+    use chrono::TimeZone;
+    chrono::Local
+        .timestamp_millis_opt(timestamp_ms)
+        .single()
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn render_markdown(day: &str, entries: &[DigestEntry], usage: Option<&[AppUsage]>) -> String {
+    let mut out = format!("# Observer digest - {}\n\n", day);
+
+    out.push_str("## Agent activity\n\n");
+    if entries.is_empty() {
+        out.push_str("_No agent summaries were recorded._\n\n");
+    }
+    let mut current_agent: Option<&str> = None;
+    for entry in entries {
+        if current_agent != Some(entry.agent_id.as_str()) {
+            out.push_str(&format!("### {}\n\n", entry.agent_id));
+            current_agent = Some(&entry.agent_id);
+        }
+        match &entry.title {
+            Some(title) => out.push_str(&format!("**{}** - {}\n\n", format_time(entry.timestamp), title)),
+            None => out.push_str(&format!("**{}**\n\n", format_time(entry.timestamp))),
+        }
+        out.push_str(entry.content.trim());
+        out.push_str("\n\n");
+    }
+
+    if let Some(usage) = usage {
+        out.push_str("## Top apps\n\n");
+        if usage.is_empty() {
+            out.push_str("_No app usage was recorded._\n");
+        } else {
+            out.push_str("| App | Category | Time |\n|---|---|---|\n");
+            for app in usage {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    app.app,
+                    app.category.as_deref().unwrap_or("-"),
+                    format_duration(app.seconds)
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+/// Lay the Markdown out as plain text pages. Headings are set in bold; everything
+/// else is wrapped to the page width.
+fn write_pdf(path: &Path, day: &str, markdown: &str) -> Result<(), String> {
+    const PAGE_W: f32 = 210.0;
+    const PAGE_H: f32 = 297.0;
+    const MARGIN: f32 = 18.0;
+    const BODY_SIZE: f32 = 10.0;
+    const LINE_H: f32 = 5.0;
+    // Helvetica at 10pt fits roughly this many characters across the text width
+    const WRAP_COLUMNS: usize = 95;
+
+    let (doc, page, layer) =
+        PdfDocument::new(format!("Observer digest - {}", day), Mm(PAGE_W), Mm(PAGE_H), "Layer 1");
+    let regular = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| e.to_string())?;
+
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = PAGE_H - MARGIN;
+
+    for raw_line in markdown.lines() {
+        let (text, font, size) = if let Some(h) = raw_line.strip_prefix("# ") {
+            (h.to_string(), &bold, 16.0)
+        } else if let Some(h) = raw_line.strip_prefix("## ") {
+            (h.to_string(), &bold, 13.0)
+        } else if let Some(h) = raw_line.strip_prefix("### ") {
+            (h.to_string(), &bold, 11.0)
+        } else {
+            (raw_line.replace("**", "").replace('_', ""), &regular, BODY_SIZE)
+        };
+
+        let wrapped = if text.is_empty() {
+            vec![String::new()]
+        } else {
+            wrap_line(&text, WRAP_COLUMNS)
+        };
+        for line in wrapped {
+            if y < MARGIN {
+                let (page, new_layer) = doc.add_page(Mm(PAGE_W), Mm(PAGE_H), "Layer 1");
+                layer = doc.get_page(page).get_layer(new_layer);
+                y = PAGE_H - MARGIN;
+            }
+            if !line.is_empty() {
+                layer.use_text(line, size, Mm(MARGIN), Mm(y), font);
+            }
+            y -= LINE_H * (size / BODY_SIZE);
+        }
+    }
+
+    let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    doc.save(&mut BufWriter::new(file)).map_err(|e| e.to_string())
+}
+
+fn wrap_line(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > columns {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Render the digest for `day` (YYYY-MM-DD) into `folder`. Scheduled exports are recorded
+/// so each day is written once; manual ones don't stop the scheduled one.
+fn export_digest(
+    app_handle: &AppHandle,
+    day: &str,
+    folder: &Path,
+    format: DigestFormat,
+    include_usage: bool,
+    scheduled: bool,
+) -> Result<PathBuf, String> {
+    // The day ends up in the file name, so only an actual date will do
+    let day = chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid day {:?}; expected YYYY-MM-DD", day))?
+        .format("%Y-%m-%d")
+        .to_string();
+    let day = day.as_str();
+    let digest_state = app_handle.state::<DigestState>();
+    let entries = digest_state.entries_for_day(day)?;
+    let usage = if include_usage {
+        match app_handle.state::<UsageState>().top_apps(day, day, DIGEST_TOP_APPS) {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                log::warn!("Digest for {} exported without usage: {}", day, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    let path = folder.join(format!("observer-digest-{}.{}", day, format.extension()));
+
+    let markdown = render_markdown(day, &entries, usage.as_deref());
+    match format {
+        DigestFormat::Markdown => std::fs::write(&path, markdown).map_err(|e| e.to_string())?,
+        DigestFormat::Pdf => write_pdf(&path, day, &markdown)?,
+    }
+
+    if scheduled {
+        let path_str = path.to_string_lossy().to_string();
+        digest_state.with_db(|conn| {
+            conn.execute(
+                "INSERT INTO digest_exports (day, path) VALUES (?1, ?2)
+                 ON CONFLICT(day) DO UPDATE SET path = excluded.path",
+                params![day, path_str],
+            )
+        })?;
+    }
+
+    log::info!("Exported digest for {} to {}", day, path.display());
+    Ok(path)
+}
+
+/// Spawn the scheduler that writes each day's digest once the export hour has passed
+pub fn start_digest_scheduler(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SCHEDULE_POLL_INTERVAL);
+
+        let settings = app_handle
+            .state::<UnifiedShortcutState>()
+            .config
+            .lock()
+            .unwrap()
+            .digest
+            .clone();
+        let Some(folder) = settings.folder.as_deref().filter(|_| settings.enabled) else {
+            continue;
+        };
+
+        use chrono::Timelike;
+        if chrono::Local::now().hour() < settings.export_hour {
+            continue;
+        }
+
+        let day = usage::today();
+        let already_exported = app_handle.state::<DigestState>().with_db(|conn| {
+            conn.query_row(
+                "SELECT 1 FROM digest_exports WHERE day = ?1",
+                params![day],
+                |_| Ok(()),
+            )
+            .optional()
+        });
+        match already_exported {
+            Ok(None) => {}
+            Ok(Some(())) => continue,
+            Err(e) => {
+                log::warn!("Digest scheduler could not check export history: {}", e);
+                continue;
+            }
+        }
+
+        if let Err(e) = export_digest(
+            &app_handle,
+            &day,
+            Path::new(folder),
+            settings.format,
+            settings.include_usage,
+            true,
+        ) {
+            log::error!("Scheduled digest export failed: {}", e);
+        }
+    });
+}
+
+// Tauri commands
+
+/// Record an agent summary for today's digest
+#[tauri::command]
+pub async fn digest_add_entry(
+    agent_id: String,
+    title: Option<String>,
+    content: String,
+    digest_state: State<'_, DigestState>,
+) -> Result<(), String> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    digest_state.with_db(|conn| {
+        conn.execute(
+            "INSERT INTO digest_entries (day, timestamp, agent_id, title, content)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![usage::today(), timestamp, agent_id, title, content],
+        )
+        .map(|_| ())
+    })
+}
+
+#[tauri::command]
+pub async fn digest_get_entries(
+    day: Option<String>,
+    digest_state: State<'_, DigestState>,
+) -> Result<Vec<DigestEntry>, String> {
+    digest_state.entries_for_day(&day.unwrap_or_else(usage::today))
+}
+
+/// Export a digest immediately. Defaults to today and the configured folder/format.
+/// Returns the path of the written file.
+#[tauri::command]
+pub async fn digest_export_now(
+    day: Option<String>,
+    folder: Option<String>,
+    format: Option<DigestFormat>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    let settings = shortcut_state.config.lock().unwrap().digest.clone();
+    let folder = folder
+        .or(settings.folder)
+        .ok_or_else(|| "No digest folder configured".to_string())?;
+    let day = day.unwrap_or_else(usage::today);
+
+    export_digest(
+        &app_handle,
+        &day,
+        Path::new(&folder),
+        format.unwrap_or(settings.format),
+        settings.include_usage,
+        false,
+    )
+    .map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn get_digest_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<DigestSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().digest.clone())
+}
+
+#[tauri::command]
+pub async fn set_digest_settings(
+    settings: DigestSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if settings.export_hour > 23 {
+        return Err("exportHour must be between 0 and 23".to_string());
+    }
+    log::info!("Setting digest settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.digest = settings)
+}
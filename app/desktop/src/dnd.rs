@@ -0,0 +1,312 @@
+// In src-tauri/src/dnd.rs
+//
+// Do Not Disturb awareness for agent notifications. A background thread polls the
+// OS Focus/DND state; while it is on, `/notification` requests are routed according
+// to the configured policy instead of being shown immediately.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often the OS DND state is re-read
+const DND_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Cap on held notifications so a chatty agent can't grow the queue without bound
+const MAX_HELD_NOTIFICATIONS: usize = 50;
+
+/// What to do with a notification that arrives while DND is on
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DndPolicy {
+    /// Hold notifications and deliver them once DND ends
+    #[default]
+    Queue,
+    /// Drop the toast and count it on the tray icon instead; nothing is shown later
+    TrayBadge,
+    /// Ignore the OS state and always deliver
+    Deliver,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub dnd_policy: DndPolicy,
+    /// Agents whose notifications are always delivered, even during DND
+    #[serde(default)]
+    pub break_through_agents: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldNotification {
+    pub title: String,
+    pub body: String,
+    pub agent_id: Option<String>,
+    pub timestamp: u64,
+}
+
+pub struct DndState {
+    active: AtomicBool,
+    held: Mutex<Vec<HeldNotification>>,
+    /// Notifications dropped for the tray badge since DND started
+    badged: AtomicUsize,
+}
+
+impl DndState {
+    pub fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            held: Mutex::new(Vec::new()),
+            badged: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+}
+
+/// Where an incoming notification should go
+pub enum Route {
+    Deliver,
+    Held,
+    Badged,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DndStatus {
+    active: bool,
+    policy: DndPolicy,
+    held: Vec<HeldNotification>,
+}
+
+/// Decide what to do with a notification: deliver it, hold it for when DND ends, or only
+/// count it on the tray badge.
+pub fn route_notification(
+    app_handle: &AppHandle,
+    title: &str,
+    body: &str,
+    agent_id: Option<&str>,
+    break_through: bool,
+) -> Route {
+    let dnd_state = app_handle.state::<DndState>();
+    if !dnd_state.is_active() {
+        return Route::Deliver;
+    }
+
+    let settings = app_handle
+        .state::<UnifiedShortcutState>()
+        .config
+        .lock()
+        .unwrap()
+        .notifications
+        .clone();
+
+    let agent_breaks_through = agent_id
+        .map(|id| settings.break_through_agents.iter().any(|a| a == id))
+        .unwrap_or(false);
+
+    if break_through || agent_breaks_through || settings.dnd_policy == DndPolicy::Deliver {
+        return Route::Deliver;
+    }
+
+    if settings.dnd_policy == DndPolicy::TrayBadge {
+        let badged = dnd_state.badged.fetch_add(1, Ordering::SeqCst) + 1;
+        set_tray_badge(app_handle, badged);
+        return Route::Badged;
+    }
+
+    let mut held = dnd_state.held.lock().unwrap();
+    if held.len() >= MAX_HELD_NOTIFICATIONS {
+        held.remove(0);
+    }
+    held.push(HeldNotification {
+        title: title.to_string(),
+        body: body.to_string(),
+        agent_id: agent_id.map(String::from),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    });
+    Route::Held
+}
+
+/// Read the OS Focus/DND state. Returns false when it can't be determined.
+pub fn is_dnd_active() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        // Focus modes (macOS 12+) record their active assertions in this file
+        let Some(home) = dirs::home_dir() else {
+            return false;
+        };
+        let path = home.join("Library/DoNotDisturb/DB/Assertions.json");
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return false;
+        };
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+            return false;
+        };
+        json["data"]
+            .as_array()
+            .map(|entries| {
+                entries.iter().any(|entry| {
+                    entry["storeAssertionRecords"]
+                        .as_array()
+                        .map(|records| !records.is_empty())
+                        .unwrap_or(false)
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Focus Assist / Do Not Disturb turns off toasts globally
+        use winreg::enums::HKEY_CURRENT_USER;
+        use winreg::RegKey;
+
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        match hkcu.open_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Notifications\\Settings") {
+            Ok(key) => key
+                .get_value::<u32, _>("NOC_GLOBAL_SETTING_TOASTS_ENABLED")
+                .map(|enabled| enabled == 0)
+                .unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // GNOME: "Do Not Disturb" in the quick settings flips show-banners off
+        match std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+            .output()
+        {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).trim() == "false"
+            }
+            _ => false,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        false
+    }
+}
+
+/// Spawn the DND poller. On every transition it emits `dnd-state-changed`; when DND
+/// ends, held notifications are delivered and the tray badge is cleared.
+pub fn start_dnd_monitor(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        let active = is_dnd_active();
+        let dnd_state = app_handle.state::<DndState>();
+        let was_active = dnd_state.active.swap(active, Ordering::SeqCst);
+
+        if active != was_active {
+            log::info!("Do Not Disturb {}", if active { "enabled" } else { "disabled" });
+            if let Err(e) = app_handle.emit("dnd-state-changed", active) {
+                log::warn!("Failed to emit dnd-state-changed event: {}", e);
+            }
+            if !active {
+                flush_held(&app_handle);
+            }
+        }
+
+        std::thread::sleep(DND_POLL_INTERVAL);
+    });
+}
+
+/// Deliver everything held during DND and clear the tray badge
+fn flush_held(app_handle: &AppHandle) {
+    let dnd_state = app_handle.state::<DndState>();
+    dnd_state.badged.store(0, Ordering::SeqCst);
+    let held: Vec<HeldNotification> = dnd_state
+        .held
+        .lock()
+        .unwrap()
+        .drain(..)
+        .collect();
+
+    set_tray_badge(app_handle, 0);
+
+    if held.is_empty() {
+        return;
+    }
+
+    log::info!("Delivering {} notifications held during DND", held.len());
+    for notification in held {
+        if let Err(e) =
+            crate::notifications::show_notification(app_handle, &notification.title, &notification.body)
+        {
+            log::error!("Failed to deliver held notification: {}", e);
+        }
+    }
+}
+
+fn set_tray_badge(app_handle: &AppHandle, count: usize) {
+    let Some(tray) = app_handle.tray_by_id(crate::TRAY_ID) else {
+        return;
+    };
+
+    let (title, tooltip) = if count == 0 {
+        (None, "Observer AI is running".to_string())
+    } else {
+        (
+            Some(count.to_string()),
+            format!("Observer AI - {} notifications during Do Not Disturb", count),
+        )
+    };
+
+    if let Err(e) = tray.set_title(title) {
+        log::warn!("Failed to set tray badge: {}", e);
+    }
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_dnd_status(
+    dnd_state: State<'_, DndState>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<DndStatus, String> {
+    let policy = shortcut_state.config.lock().unwrap().notifications.dnd_policy;
+    Ok(DndStatus {
+        active: dnd_state.is_active(),
+        policy,
+        held: dnd_state.held.lock().unwrap().clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_notification_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<NotificationSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().notifications.clone())
+}
+
+#[tauri::command]
+pub async fn set_notification_settings(
+    settings: NotificationSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting notification settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.notifications = settings
+    })
+}
+
+/// Deliver held notifications now, regardless of the DND state
+#[tauri::command]
+pub async fn flush_held_notifications(app_handle: AppHandle) -> Result<(), String> {
+    flush_held(&app_handle);
+    Ok(())
+}
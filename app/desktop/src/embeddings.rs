@@ -0,0 +1,202 @@
+// In src-tauri/src/embeddings.rs
+//
+// Text embeddings for `vector_store`. Texts are sent to the embedding provider set in the
+// embedding settings (the default Ollama unless changed) with the configured model:
+// Ollama through its native `/api/embed` (falling back to `/api/embeddings` on servers
+// that predate it), OpenAI-compatible providers through `/embeddings`. Like chat
+// requests, the text is masked by `redaction` first and the call is written to the audit
+// log.
+
+use crate::providers::{self, ProviderKind, ResolvedProvider};
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::{audit, redaction};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const EMBED_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingSettings {
+    /// None for the default Ollama
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    #[serde(default = "default_model")]
+    pub model: String,
+}
+
+fn default_model() -> String {
+    "nomic-embed-text".to_string()
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self { provider_id: None, model: default_model() }
+    }
+}
+
+/// Vectors for a batch of texts, in order
+pub struct Embeddings {
+    pub model: String,
+    pub vectors: Vec<Vec<f32>>,
+}
+
+fn parse_vector(value: &serde_json::Value) -> Option<Vec<f32>> {
+    value.as_array()?.iter().map(|x| x.as_f64().map(|x| x as f32)).collect()
+}
+
+/// Vectors out of an `/api/embed` (`embeddings`), `/api/embeddings` (`embedding`) or
+/// `/embeddings` (`data[].embedding`) response
+fn parse_vectors(body: &serde_json::Value) -> Option<Vec<Vec<f32>>> {
+    if let Some(vectors) = body["embeddings"].as_array() {
+        return vectors.iter().map(parse_vector).collect();
+    }
+    if let Some(vector) = parse_vector(&body["embedding"]) {
+        return Some(vec![vector]);
+    }
+    let mut data: Vec<&serde_json::Value> = body["data"].as_array()?.iter().collect();
+    data.sort_by_key(|item| item["index"].as_u64().unwrap_or(0));
+    data.iter().map(|item| parse_vector(&item["embedding"])).collect()
+}
+
+async fn post(
+    client: &reqwest::Client,
+    provider: &ResolvedProvider,
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<(reqwest::StatusCode, String), String> {
+    let response = provider
+        .authorize(client.post(url))
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", provider.id, e))?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    Ok((status, text))
+}
+
+/// Ollama's `/api/embeddings`, once per text, answered as if by `/api/embed`
+async fn embed_one_by_one(
+    client: &reqwest::Client,
+    provider: &ResolvedProvider,
+    body: &serde_json::Value,
+) -> Result<(reqwest::StatusCode, String), String> {
+    let url = format!("{}/api/embeddings", provider.base_url);
+    let mut embeddings = Vec::new();
+    for text in body["input"].as_array().into_iter().flatten() {
+        let single = serde_json::json!({ "model": body["model"], "prompt": text });
+        let (status, reply) = post(client, provider, &url, &single).await?;
+        if !status.is_success() {
+            return Ok((status, reply));
+        }
+        let reply: serde_json::Value = serde_json::from_str(&reply).map_err(|e| e.to_string())?;
+        embeddings.push(reply["embedding"].clone());
+    }
+    Ok((reqwest::StatusCode::OK, serde_json::json!({ "embeddings": embeddings }).to_string()))
+}
+
+/// Embed `texts` with the configured provider and model
+pub async fn embed(app_handle: &AppHandle, agent_id: Option<&str>, texts: &[String]) -> Result<Embeddings, String> {
+    let settings = app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().embeddings.clone();
+    let provider = providers::resolve(app_handle, None, settings.provider_id.as_deref())?;
+    if provider.kind == ProviderKind::Anthropic {
+        return Err(format!("{} has no embeddings API", provider.id));
+    }
+
+    // Mask PII before anything leaves the machine
+    let body = serde_json::json!({ "model": settings.model, "input": texts });
+    let bytes = serde_json::to_vec(&body).map_err(|e| e.to_string())?;
+    let (redacted, redactions) =
+        redaction::redact_request_body(&app_handle.state::<redaction::RedactionState>(), &bytes, agent_id);
+    let body: serde_json::Value = match redacted {
+        Some(redacted) => serde_json::from_slice(&redacted).map_err(|e| e.to_string())?,
+        None => body,
+    };
+
+    let url = match provider.kind {
+        ProviderKind::Ollama => format!("{}/api/embed", provider.base_url),
+        _ => format!("{}/embeddings", provider.api_base()),
+    };
+    let audit_id = audit::record_outbound(
+        app_handle,
+        &audit::OutboundRecord {
+            kind: audit::OutboundKind::Llm,
+            destination: url.clone(),
+            method: "POST".to_string(),
+            model: Some(settings.model.clone()),
+            agent_id: agent_id.map(String::from),
+            request_bytes: body.to_string().len() as u64,
+            image_hashes: Vec::new(),
+            prompt: None,
+            redactions,
+        },
+    );
+
+    let client = reqwest::Client::builder().timeout(EMBED_TIMEOUT).build().map_err(|e| e.to_string())?;
+    let mut result = post(&client, &provider, &url, &body).await;
+    // Ollama before 0.3.4 only has the one-text-per-call endpoint
+    if provider.kind == ProviderKind::Ollama
+        && matches!(&result, Ok((status, _)) if *status == reqwest::StatusCode::NOT_FOUND)
+    {
+        result = embed_one_by_one(&client, &provider, &body).await;
+    }
+
+    if let Some(id) = audit_id {
+        let (status, bytes) = match &result {
+            Ok((status, text)) => (Some(status.as_u16()), text.len() as u64),
+            Err(_) => (None, 0),
+        };
+        audit::record_response(app_handle, id, status, bytes);
+    }
+    let (status, text) = result?;
+    if !status.is_success() {
+        return Err(format!("{} returned {}: {}", provider.id, status, crate::inference::error_message(&text)));
+    }
+    let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    let vectors = parse_vectors(&json)
+        .filter(|vectors| vectors.len() == texts.len())
+        .ok_or_else(|| format!("Unexpected embeddings response from {}", provider.id))?;
+    Ok(Embeddings { model: settings.model, vectors })
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_embedding_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<EmbeddingSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().embeddings.clone())
+}
+
+/// Collections embedded with another model can't be searched with the new one; see
+/// `vector_store`
+#[tauri::command]
+pub async fn set_embedding_settings(
+    settings: EmbeddingSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting embedding settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.embeddings = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_every_response_form() {
+        let embed = serde_json::json!({ "model": "m", "embeddings": [[0.1, 0.2], [0.3, 0.4]] });
+        assert_eq!(parse_vectors(&embed).unwrap(), vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        let legacy = serde_json::json!({ "embedding": [1.0, 0.0] });
+        assert_eq!(parse_vectors(&legacy).unwrap(), vec![vec![1.0, 0.0]]);
+        let openai = serde_json::json!({ "data": [
+            { "index": 1, "embedding": [0.5] },
+            { "index": 0, "embedding": [0.25] }
+        ] });
+        assert_eq!(parse_vectors(&openai).unwrap(), vec![vec![0.25], vec![0.5]]);
+        assert!(parse_vectors(&serde_json::json!({ "embeddings": [["x"]] })).is_none());
+    }
+}
@@ -0,0 +1,178 @@
+// In src-tauri/src/events.rs
+//
+// `/events` on the API server: a server-sent event stream of what the app is doing, for
+// dashboards and automations (`curl -N localhost:3838/events?token=...`). Each event's
+// SSE `event:` name is its type and its data a JSON object with `type`, `timestamp` (ms)
+// and the fields below:
+//
+// - agent-started / agent-finished: one agent run, reported by the frontend's agent loop
+// - capture-started / capture-stopped: video capture sessions
+// - inference-error: a failed `inference_chat` request (cancellations aren't errors)
+// - notification: a system notification that was shown
+//
+// `?types=a,b` limits the stream to those types. Events are not stored; a client only
+// sees what happens while it is connected, and one that falls behind skips events.
+
+use crate::{metrics, AppState};
+use axum::extract::{Query, State as AxumState};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::stream::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+/// Events a slow client may fall behind by before it starts skipping
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ApiEvent {
+    #[serde(rename_all = "camelCase")]
+    AgentStarted { agent_id: String, iteration_id: Option<String> },
+    #[serde(rename_all = "camelCase")]
+    AgentFinished {
+        agent_id: String,
+        iteration_id: Option<String>,
+        success: bool,
+        error: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    CaptureStarted { session_id: String, target_id: Option<String> },
+    /// `session_id` None when every session stopped
+    #[serde(rename_all = "camelCase")]
+    CaptureStopped { session_id: Option<String> },
+    #[serde(rename_all = "camelCase")]
+    InferenceError { agent_id: Option<String>, error: String },
+    Notification { title: String, body: String },
+}
+
+impl ApiEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ApiEvent::AgentStarted { .. } => "agent-started",
+            ApiEvent::AgentFinished { .. } => "agent-finished",
+            ApiEvent::CaptureStarted { .. } => "capture-started",
+            ApiEvent::CaptureStopped { .. } => "capture-stopped",
+            ApiEvent::InferenceError { .. } => "inference-error",
+            ApiEvent::Notification { .. } => "notification",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct Envelope {
+    /// Unix time in milliseconds
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub event: ApiEvent,
+}
+
+pub struct EventBus {
+    tx: broadcast::Sender<Envelope>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self { tx: broadcast::channel(CHANNEL_CAPACITY).0 }
+    }
+}
+
+/// Send `event` to every connected `/events` client
+pub fn publish(app_handle: &AppHandle, event: ApiEvent) {
+    let Some(bus) = app_handle.try_state::<EventBus>() else {
+        return;
+    };
+    // Fails only when nobody is listening
+    let _ = bus.tx.send(Envelope { timestamp: chrono::Utc::now().timestamp_millis(), event });
+}
+
+/// Receive events from now on, like an `/events` client
+pub fn subscribe(app_handle: &AppHandle) -> broadcast::Receiver<Envelope> {
+    app_handle.state::<EventBus>().tx.subscribe()
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct EventsQuery {
+    /// Comma-separated event types; all when absent
+    #[serde(default)]
+    pub types: Option<String>,
+}
+
+fn type_filter(types: Option<&str>) -> Option<HashSet<String>> {
+    let types: HashSet<String> = types?
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    (!types.is_empty()).then_some(types)
+}
+
+pub async fn events_handler(
+    AxumState(state): AxumState<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    log::info!("New SSE client connected to the event stream");
+    let filter = type_filter(query.types.as_deref());
+    let rx = subscribe(&state.app_handle);
+
+    let stream = BroadcastStream::new(rx).filter_map(move |result| {
+        // A lagging client skips what it missed
+        let envelope = result.ok()?;
+        let kind = envelope.event.kind();
+        if filter.as_ref().is_some_and(|types| !types.contains(kind)) {
+            return None;
+        }
+        let data = serde_json::to_string(&envelope).ok()?;
+        Some(Ok(Event::default().event(kind).data(data)))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+// Tauri commands
+
+/// Agent runs happen in the frontend, which reports them here
+#[tauri::command]
+pub async fn publish_agent_event(
+    agent_id: String,
+    iteration_id: Option<String>,
+    finished: bool,
+    success: Option<bool>,
+    error: Option<String>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let event = if finished {
+        let success = success.unwrap_or(error.is_none());
+        metrics::record_agent_run(&app_handle, &agent_id, success);
+        ApiEvent::AgentFinished { agent_id, iteration_id, success, error }
+    } else {
+        ApiEvent::AgentStarted { agent_id, iteration_id }
+    };
+    publish(&app_handle, event);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_carry_their_type() {
+        let envelope = Envelope {
+            timestamp: 1,
+            event: ApiEvent::CaptureStarted { session_id: "default".to_string(), target_id: None },
+        };
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["type"], envelope.event.kind());
+        assert_eq!(json["sessionId"], "default");
+        assert_eq!(json["timestamp"], 1);
+
+        assert_eq!(type_filter(None), None);
+        assert_eq!(type_filter(Some(" , ")), None);
+        let filter = type_filter(Some("notification, agent-finished")).unwrap();
+        assert!(filter.contains("agent-finished") && !filter.contains("agent-started"));
+    }
+}
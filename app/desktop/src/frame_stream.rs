@@ -0,0 +1,86 @@
+// In src-tauri/src/frame_stream.rs
+//
+// `/ws/frames` on the API server: a WebSocket that streams the running capture's frames,
+// so a browser tab, another machine or a script can watch without Tauri IPC. Each frame
+// is one binary message in the capture plugin's wire layout:
+//
+//   [u32 big-endian header length][JSON header][encoded image bytes]
+//
+// where the header has `format`, `timestamp`, `width`, `height` and `frameCount`. The
+// socket only watches: it doesn't start a capture, and stays quiet while none is running.
+// `?fps=N` caps the rate; slow clients skip frames instead of holding up capture.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tauri_plugin_screen_capture::frames;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct FrameStreamQuery {
+    /// Most frames per second to send; every frame when absent
+    #[serde(default)]
+    pub fps: Option<f64>,
+}
+
+/// Shortest gap between frames for a requested rate
+pub fn min_interval(fps: Option<f64>) -> Option<Duration> {
+    fps.filter(|fps| fps.is_finite() && *fps > 0.0)
+        .map(|fps| Duration::from_secs_f64(1.0 / fps))
+}
+
+pub async fn frames_ws_handler(ws: WebSocketUpgrade, Query(query): Query<FrameStreamQuery>) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| async move {
+        log::info!("Frame stream client connected");
+        if let Err(e) = stream(socket, min_interval(query.fps)).await {
+            log::debug!("Frame stream ended: {}", e);
+        }
+        log::info!("Frame stream client disconnected");
+    })
+}
+
+async fn stream(mut socket: WebSocket, interval: Option<Duration>) -> Result<(), String> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut frames_rx = frames::subscribe();
+    let mut last_sent: Option<Instant> = None;
+    loop {
+        tokio::select! {
+            frame = frames_rx.recv() => match frame {
+                Ok(frame) => {
+                    let due = match (interval, last_sent) {
+                        (Some(interval), Some(last)) => last.elapsed() >= interval,
+                        _ => true,
+                    };
+                    if due {
+                        last_sent = Some(Instant::now());
+                        let payload = crate::remote::encode_frame(&frame);
+                        socket.send(Message::Binary(payload)).await.map_err(|e| e.to_string())?;
+                    }
+                }
+                // Client slower than capture: skip ahead to the newest frames
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Err(e)) => return Err(e.to_string()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_the_frame_rate() {
+        assert_eq!(min_interval(None), None);
+        assert_eq!(min_interval(Some(0.0)), None);
+        assert_eq!(min_interval(Some(f64::NAN)), None);
+        assert_eq!(min_interval(Some(4.0)), Some(Duration::from_millis(250)));
+    }
+}
@@ -0,0 +1,472 @@
+// In src-tauri/src/grpc.rs
+//
+// A gRPC API beside the REST one, for applications that want typed clients and streaming:
+// capture control, the frame stream, agent commands and events, and inference. The
+// service is defined in `proto/observer/v1/observer.proto`; generate a client from it in
+// any language.
+//
+// Off by default; `apiServer.grpc` in settings.json turns it on. It listens on the API
+// server's host at its own port (50051), over TLS with the API server's certificate when
+// `apiServer.tls` is on (plaintext HTTP/2 otherwise), and takes the API token as
+// `authorization: Bearer <token>` (or `x-observer-token`) metadata. Calls count against
+// the REST API's rate limits, and messages are capped at its upload size. Each method
+// does what its REST counterpart does: the same incognito refusals, the same `/events`.
+//
+// Chat requests can't offer the model tools, and their agent id has to be one the
+// provider settings know; tool grants belong to the app's own agents. Changes apply the
+// next time the app starts.
+
+use crate::inference::{self, ChatMessage, ChatRequest, ChatResponse, ContentPart, ImageUrl};
+use crate::inference::{Caller, InferenceError, MessageContent, StreamEvent};
+use crate::inference_queue::QueueError;
+use crate::shortcuts::UnifiedShortcutState;
+use crate::structured_output::OutputSchema;
+use crate::{api_auth, capture_api, commands, events, frame_stream, incognito, rate_limit, server_tls, CommandState};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_screen_capture::capture_config::FrameEncoding;
+use tauri_plugin_screen_capture::frames;
+use tauri_plugin_screen_capture::targets::{CaptureTarget, TargetKind};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{BroadcastStream, TcpListenerStream, UnboundedReceiverStream};
+use tokio_stream::StreamExt;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::{Identity, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("observer.v1");
+}
+
+use proto::observer_server::{Observer, ObserverServer};
+
+const AGENT_ACTIONS: &[&str] = &["start", "stop", "toggle"];
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_port() -> u16 {
+    50051
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self { enabled: false, port: default_port() }
+    }
+}
+
+type ServerStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+fn image_format(format: FrameEncoding) -> proto::ImageFormat {
+    match format {
+        FrameEncoding::Jpeg => proto::ImageFormat::Jpeg,
+        FrameEncoding::Png => proto::ImageFormat::Png,
+        FrameEncoding::Webp => proto::ImageFormat::Webp,
+        FrameEncoding::WebpLossless => proto::ImageFormat::WebpLossless,
+    }
+}
+
+fn frame_encoding(format: proto::ImageFormat) -> Option<FrameEncoding> {
+    match format {
+        proto::ImageFormat::Unspecified => None,
+        proto::ImageFormat::Jpeg => Some(FrameEncoding::Jpeg),
+        proto::ImageFormat::Png => Some(FrameEncoding::Png),
+        proto::ImageFormat::Webp => Some(FrameEncoding::Webp),
+        proto::ImageFormat::WebpLossless => Some(FrameEncoding::WebpLossless),
+    }
+}
+
+fn capture_target(target: CaptureTarget) -> proto::CaptureTarget {
+    let kind = match target.kind {
+        TargetKind::Monitor => "monitor",
+        TargetKind::Window => "window",
+        TargetKind::VirtualDesktop => "virtual-desktop",
+    };
+    proto::CaptureTarget {
+        id: target.id,
+        kind: kind.to_string(),
+        name: target.name,
+        app_name: target.app_name,
+        thumbnail: target.thumbnail.and_then(|thumbnail| BASE64.decode(thumbnail).ok()),
+        width: target.width,
+        height: target.height,
+        is_primary: target.is_primary,
+        x: target.x,
+        y: target.y,
+    }
+}
+
+fn chat_message(message: proto::ChatMessage) -> ChatMessage {
+    let content = if message.image_urls.is_empty() {
+        MessageContent::Text(message.text)
+    } else {
+        let text = (!message.text.is_empty()).then_some(ContentPart::Text { text: message.text });
+        let images = message.image_urls.into_iter().map(|url| ContentPart::ImageUrl { image_url: ImageUrl { url } });
+        MessageContent::Parts(text.into_iter().chain(images).collect())
+    };
+    ChatMessage { role: message.role, content, tool_calls: Vec::new(), tool_call_id: None }
+}
+
+fn chat_request(request: proto::ChatRequest) -> Result<ChatRequest, Status> {
+    if !request.tools.is_empty() {
+        return Err(Status::permission_denied("Tools are only available to agents running in the app"));
+    }
+    let params = match request.params_json.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| Status::invalid_argument(format!("params_json: {}", e)))?,
+        None => serde_json::Map::new(),
+    };
+    let output_schema = match request.output_schema_json.as_deref() {
+        Some(json) => {
+            let schema: serde_json::Value = serde_json::from_str(json)
+                .map_err(|e| Status::invalid_argument(format!("output_schema_json: {}", e)))?;
+            let schema = serde_json::from_value::<OutputSchema>(serde_json::json!({ "schema": schema }))
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            Some(schema)
+        }
+        None => None,
+    };
+    Ok(ChatRequest {
+        agent_id: request.agent_id,
+        provider_id: request.provider_id,
+        model: request.model,
+        messages: request.messages.into_iter().map(chat_message).collect(),
+        params,
+        priority: None,
+        request_id: request.request_id,
+        cache: request.cache,
+        output_schema,
+        tools: Vec::new(),
+        caller: Caller::External,
+    })
+}
+
+fn chat_event(event: StreamEvent) -> proto::ChatEvent {
+    use proto::chat_event::Event;
+    let event = match event {
+        StreamEvent::Started { provider_id, model, request_id } => {
+            Event::Started(proto::ChatStarted { provider_id, model, request_id })
+        }
+        StreamEvent::Delta { content } => Event::Delta(content),
+        StreamEvent::Reasoning { content } => Event::Reasoning(content),
+        StreamEvent::ToolCall { name } => Event::ToolCall(name),
+    };
+    proto::ChatEvent { event: Some(event) }
+}
+
+fn chat_completed(response: ChatResponse) -> proto::ChatEvent {
+    let usage = response.usage.unwrap_or_default();
+    let completed = proto::ChatCompleted {
+        content: response.content,
+        model: response.model,
+        provider_id: response.provider_id,
+        request_id: response.request_id,
+        finish_reason: response.finish_reason,
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        cached: response.cached,
+        output_json: response.output.map(|output| output.to_string()),
+    };
+    proto::ChatEvent { event: Some(proto::chat_event::Event::Completed(completed)) }
+}
+
+fn inference_status(error: &InferenceError) -> Status {
+    match error {
+        InferenceError::Config(_) => Status::invalid_argument(error.to_string()),
+        InferenceError::Queue(QueueError::Cancelled) => Status::cancelled(error.to_string()),
+        InferenceError::Queue(QueueError::Superseded) => Status::aborted(error.to_string()),
+        InferenceError::InvalidOutput(_) => Status::failed_precondition(error.to_string()),
+        _ => Status::unavailable(error.to_string()),
+    }
+}
+
+struct ObserverService {
+    app_handle: AppHandle,
+}
+
+impl ObserverService {
+    fn refuse_in_incognito(&self) -> Result<(), Status> {
+        match incognito::is_active(&self.app_handle) {
+            true => Err(Status::permission_denied(capture_api::INCOGNITO_ERROR)),
+            false => Ok(()),
+        }
+    }
+
+    /// A chat's agent has to be one assigned a provider, not any id a client makes up
+    fn check_agent(&self, agent_id: Option<&str>) -> Result<(), Status> {
+        let Some(agent_id) = agent_id else {
+            return Ok(());
+        };
+        let config = self.app_handle.state::<UnifiedShortcutState>();
+        let known = config.config.lock().unwrap().providers.agents.contains_key(agent_id);
+        match known {
+            true => Ok(()),
+            false => Err(Status::not_found(format!("Unknown agent {}", agent_id))),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Observer for ObserverService {
+    async fn list_capture_targets(
+        &self,
+        request: Request<proto::ListCaptureTargetsRequest>,
+    ) -> Result<Response<proto::ListCaptureTargetsResponse>, Status> {
+        let thumbnails = request.into_inner().thumbnails;
+        if thumbnails {
+            self.refuse_in_incognito()?;
+        }
+        let targets = capture_api::targets(thumbnails).await.map_err(Status::internal)?;
+        let targets = targets.into_iter().map(capture_target).collect();
+        Ok(Response::new(proto::ListCaptureTargetsResponse { targets }))
+    }
+
+    async fn start_capture(
+        &self,
+        request: Request<proto::StartCaptureRequest>,
+    ) -> Result<Response<proto::StartCaptureResponse>, Status> {
+        self.refuse_in_incognito()?;
+        let request = request.into_inner();
+        let session_id = capture_api::start(&self.app_handle, request.target_id, request.session_id.as_deref())
+            .map_err(Status::internal)?;
+        Ok(Response::new(proto::StartCaptureResponse { session_id }))
+    }
+
+    async fn stop_capture(
+        &self,
+        request: Request<proto::StopCaptureRequest>,
+    ) -> Result<Response<proto::StopCaptureResponse>, Status> {
+        capture_api::stop(&self.app_handle, request.into_inner().session_id).map_err(Status::internal)?;
+        Ok(Response::new(proto::StopCaptureResponse {}))
+    }
+
+    async fn screenshot(&self, request: Request<proto::ScreenshotRequest>) -> Result<Response<proto::Image>, Status> {
+        self.refuse_in_incognito()?;
+        let request = request.into_inner();
+        let format = frame_encoding(request.format()).unwrap_or(FrameEncoding::Png);
+        let (shot, data) = capture_api::take_screenshot(request.target_id, format).await.map_err(Status::internal)?;
+        Ok(Response::new(proto::Image {
+            data,
+            format: image_format(shot.format) as i32,
+            width: shot.width,
+            height: shot.height,
+        }))
+    }
+
+    type StreamFramesStream = ServerStream<proto::Frame>;
+
+    async fn stream_frames(
+        &self,
+        request: Request<proto::StreamFramesRequest>,
+    ) -> Result<Response<Self::StreamFramesStream>, Status> {
+        let interval = frame_stream::min_interval(Some(request.into_inner().fps));
+        let mut last_sent: Option<Instant> = None;
+        let stream = BroadcastStream::new(frames::subscribe()).filter_map(move |frame| {
+            // A client slower than capture skips ahead to the newest frames
+            let frame = frame.ok()?;
+            if let (Some(interval), Some(last)) = (interval, last_sent) {
+                if last.elapsed() < interval {
+                    return None;
+                }
+            }
+            last_sent = Some(Instant::now());
+            Some(Ok(proto::Frame {
+                data: frame.data.clone(),
+                format: image_format(frame.format) as i32,
+                timestamp: frame.timestamp,
+                width: frame.width,
+                height: frame.height,
+                frame_count: frame.frame_count,
+            }))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn send_agent_command(
+        &self,
+        request: Request<proto::AgentCommandRequest>,
+    ) -> Result<Response<proto::AgentCommandResponse>, Status> {
+        let request = request.into_inner();
+        if request.agent_id.is_empty() {
+            return Err(Status::invalid_argument("agent_id is required"));
+        }
+        if !AGENT_ACTIONS.contains(&request.action.as_str()) {
+            return Err(Status::invalid_argument(format!("action must be one of {}", AGENT_ACTIONS.join(", "))));
+        }
+        commands::broadcast_command(&self.app_handle.state::<CommandState>(), request.agent_id, request.action);
+        Ok(Response::new(proto::AgentCommandResponse {}))
+    }
+
+    type StreamEventsStream = ServerStream<proto::Event>;
+
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let types: HashSet<String> = request.into_inner().types.into_iter().collect();
+        let stream = BroadcastStream::new(events::subscribe(&self.app_handle)).filter_map(move |envelope| {
+            // A lagging client skips what it missed
+            let envelope = envelope.ok()?;
+            let kind = envelope.event.kind();
+            if !types.is_empty() && !types.contains(kind) {
+                return None;
+            }
+            let json = serde_json::to_string(&envelope).ok()?;
+            Some(Ok(proto::Event { r#type: kind.to_string(), timestamp: envelope.timestamp, json }))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    type ChatStream = ServerStream<proto::ChatEvent>;
+
+    async fn chat(&self, request: Request<proto::ChatRequest>) -> Result<Response<Self::ChatStream>, Status> {
+        let request = chat_request(request.into_inner())?;
+        self.check_agent(request.agent_id.as_deref())?;
+        let app_handle = self.app_handle.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let agent_id = request.agent_id.clone();
+            let sink = |event: StreamEvent| tx.send(Ok(chat_event(event))).is_ok();
+            let last = match inference::chat_stream(&app_handle, request, &sink).await {
+                Ok(response) => Ok(chat_completed(response)),
+                Err(e) => {
+                    log::warn!("gRPC inference for {:?} failed: {}", agent_id, e);
+                    inference::publish_error(&app_handle, agent_id, &e);
+                    Err(inference_status(&e))
+                }
+            };
+            let _ = tx.send(last);
+        });
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+/// The token a call carries, from either of the accepted metadata keys
+fn presented<T>(request: &Request<T>) -> Option<&str> {
+    let metadata = request.metadata();
+    if let Some(bearer) = metadata
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(bearer.trim());
+    }
+    metadata.get(api_auth::TOKEN_HEADER).and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+/// Run the gRPC server until the app exits; nothing when it is off
+pub async fn serve(app_handle: AppHandle) {
+    let settings = app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().api_server.clone();
+    if !settings.grpc.enabled {
+        return;
+    }
+    let host = settings.host.trim_start_matches('[').trim_end_matches(']').to_string();
+    let listener = match TcpListener::bind((host.as_str(), settings.grpc.port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("gRPC server can't listen on {}:{}: {}", host, settings.grpc.port, e);
+            return;
+        }
+    };
+    let mut builder = tonic::transport::Server::builder();
+    if settings.tls.enabled {
+        let identity = server_tls::pem_files(&app_handle, &settings).and_then(|(cert_path, key_path)| {
+            let cert = std::fs::read(&cert_path).map_err(|e| format!("{}: {}", cert_path.display(), e))?;
+            let key = std::fs::read(&key_path).map_err(|e| format!("{}: {}", key_path.display(), e))?;
+            Ok(Identity::from_pem(cert, key))
+        });
+        let configured = identity.and_then(|identity| {
+            builder.tls_config(ServerTlsConfig::new().identity(identity)).map_err(|e| e.to_string())
+        });
+        builder = match configured {
+            Ok(builder) => builder,
+            Err(e) => {
+                // Never fall back to plaintext when TLS was asked for
+                log::error!("gRPC server can't set up TLS: {}", e);
+                return;
+            }
+        };
+    }
+    log::info!(
+        "gRPC API listening on {}:{}{}",
+        host,
+        settings.grpc.port,
+        if settings.tls.enabled { " (TLS)" } else { "" }
+    );
+
+    let auth_handle = app_handle.clone();
+    let authenticate = move |request: Request<()>| {
+        let limits = auth_handle.state::<UnifiedShortcutState>().config.lock().unwrap().api_server.limits.clone();
+        let client = request.remote_addr().map(|address| address.ip());
+        let proxied = request.metadata().contains_key("x-forwarded-for");
+        if rate_limit::check(&auth_handle, &limits, client, proxied).is_err() {
+            log::debug!("Rate limited gRPC client {:?}", client);
+            return Err(Status::resource_exhausted("Too many requests"));
+        }
+        let accepted = api_auth::accepts(&auth_handle, presented(&request));
+        match accepted {
+            true => Ok(request),
+            false => Err(Status::unauthenticated("A valid API token is required")),
+        }
+    };
+    let service = ObserverServer::new(ObserverService { app_handle })
+        .max_decoding_message_size(settings.limits.max_upload_bytes);
+    let served = builder
+        .add_service(InterceptedService::new(service, authenticate))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await;
+    if let Err(e) = served {
+        log::error!("gRPC server stopped: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_chat_requests() {
+        let request = proto::ChatRequest {
+            agent_id: Some("watcher".to_string()),
+            messages: vec![proto::ChatMessage {
+                role: "user".to_string(),
+                text: "What's on screen?".to_string(),
+                image_urls: vec!["data:image/png;base64,AAAA".to_string()],
+            }],
+            params_json: Some(r#"{"temperature":0.2}"#.to_string()),
+            output_schema_json: Some(r#"{"type":"object"}"#.to_string()),
+            ..Default::default()
+        };
+        let request = chat_request(request).unwrap();
+        assert_eq!(request.params["temperature"], 0.2);
+        assert_eq!(request.output_schema.unwrap().schema, serde_json::json!({ "type": "object" }));
+        match &request.messages[0].content {
+            MessageContent::Parts(parts) => {
+                assert!(matches!(&parts[0], ContentPart::Text { text } if text == "What's on screen?"));
+                assert!(matches!(&parts[1], ContentPart::ImageUrl { image_url } if image_url.url.ends_with("AAAA")));
+            }
+            content => panic!("expected parts, got {:?}", content),
+        }
+
+        let bad = proto::ChatRequest { params_json: Some("[".to_string()), ..Default::default() };
+        assert_eq!(chat_request(bad).unwrap_err().code(), tonic::Code::InvalidArgument);
+        let tools = proto::ChatRequest { tools: vec!["run_command".to_string()], ..Default::default() };
+        assert_eq!(chat_request(tools).unwrap_err().code(), tonic::Code::PermissionDenied);
+
+        for format in [FrameEncoding::Jpeg, FrameEncoding::Png, FrameEncoding::Webp, FrameEncoding::WebpLossless] {
+            assert_eq!(frame_encoding(image_format(format)), Some(format));
+        }
+        assert_eq!(frame_encoding(proto::ImageFormat::Unspecified), None);
+    }
+}
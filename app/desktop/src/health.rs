@@ -0,0 +1,261 @@
+// In src-tauri/src/health.rs
+//
+// `GET /healthz`: what each part of Observer is doing, for monitoring that wants more
+// than `/ping`'s pong:
+//
+// - capture: running sessions with their fps and dropped frames, and whether capture is
+//   paused or in incognito mode
+// - providers: every configured inference provider (and the default Ollama) with its
+//   circuit-breaker state and the latency of its latest request
+// - scheduler: the inference queue and when an agent run last finished
+// - storage: bytes used by the app data directory and space left on its disk
+//
+// Each part reports `ok`, `degraded` or `down`, and the overall status is the worst of
+// them. An unhealthy provider only degrades Observer (there may be fallbacks); a full
+// disk takes it down. The response is 503 when the overall status is `down`, else 200.
+
+use crate::inference_queue::{InferenceQueue, QueueMetrics};
+use crate::provider_health::{self, ProviderHealth};
+use crate::providers::DEFAULT_PROVIDER_ID;
+use crate::shortcuts::UnifiedShortcutState;
+use crate::{incognito, metrics, AppState};
+use axum::extract::State as AxumState;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_screen_capture::{backpressure, pause};
+
+/// Free space below which storage is degraded
+const LOW_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+/// Free space below which storage is down; databases start failing to write
+const FULL_DISK_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    Ok,
+    Degraded,
+    Down,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureSession {
+    pub session_id: String,
+    pub fps: f64,
+    pub dropped_frames: u64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureHealth {
+    pub status: Status,
+    pub paused: bool,
+    pub incognito: bool,
+    pub sessions: Vec<CaptureSession>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderReport {
+    pub id: String,
+    pub status: Status,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Latency of the latest backend request, successful or not
+    pub last_latency_ms: Option<u64>,
+    pub last_success: Option<bool>,
+    /// Unix time in milliseconds
+    pub last_request_at: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerHealth {
+    pub status: Status,
+    pub queue: QueueMetrics,
+    /// Unix time in milliseconds
+    pub last_agent_run_at: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageHealth {
+    pub status: Status,
+    pub used_bytes: u64,
+    pub available_bytes: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+    pub status: Status,
+    pub version: String,
+    pub capture: CaptureHealth,
+    pub providers: Vec<ProviderReport>,
+    pub scheduler: SchedulerHealth,
+    pub storage: StorageHealth,
+}
+
+fn capture(app_handle: &AppHandle) -> CaptureHealth {
+    let sessions = backpressure::stats(None)
+        .into_iter()
+        .map(|stats| CaptureSession {
+            session_id: stats.session_id,
+            fps: stats.rates.fps,
+            dropped_frames: stats.dropped_frames,
+        })
+        .collect();
+    // Paused and incognito are choices, not faults
+    CaptureHealth {
+        status: Status::Ok,
+        paused: pause::is_paused(),
+        incognito: incognito::is_active(app_handle),
+        sessions,
+    }
+}
+
+fn providers(app_handle: &AppHandle) -> Vec<ProviderReport> {
+    let mut ids: Vec<String> = {
+        let config = app_handle.state::<UnifiedShortcutState>();
+        let config = config.config.lock().unwrap();
+        config.providers.providers.iter().map(|provider| provider.id.clone()).collect()
+    };
+    if !ids.iter().any(|id| id == DEFAULT_PROVIDER_ID) {
+        ids.insert(0, DEFAULT_PROVIDER_ID.to_string());
+    }
+    let breakers = provider_health::statuses(&app_handle.state::<ProviderHealth>());
+    ids.into_iter()
+        .map(|id| {
+            let breaker = breakers.iter().find(|status| status.provider_id == id);
+            let last = metrics::last_inference(app_handle, &id);
+            ProviderReport {
+                status: match breaker {
+                    Some(breaker) if !breaker.healthy => Status::Down,
+                    Some(_) => Status::Degraded,
+                    None => Status::Ok,
+                },
+                consecutive_failures: breaker.map_or(0, |breaker| breaker.consecutive_failures),
+                last_error: breaker.and_then(|breaker| breaker.last_error.clone()),
+                last_latency_ms: last.map(|last| last.latency.as_millis() as u64),
+                last_success: last.map(|last| last.success),
+                last_request_at: last.map(|last| last.at),
+                id,
+            }
+        })
+        .collect()
+}
+
+/// Total size of the files under `dir`
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Space left on the disk holding `dir`: the one with the longest mount point above it
+fn available_space(dir: &Path) -> Option<u64> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn storage_status(available_bytes: Option<u64>) -> Status {
+    match available_bytes {
+        Some(available) if available < FULL_DISK_BYTES => Status::Down,
+        Some(available) if available < LOW_DISK_BYTES => Status::Degraded,
+        _ => Status::Ok,
+    }
+}
+
+fn storage(app_handle: &AppHandle) -> StorageHealth {
+    let Ok(dir) = app_handle.path().app_data_dir() else {
+        return StorageHealth { status: Status::Down, used_bytes: 0, available_bytes: None };
+    };
+    let available_bytes = available_space(&dir);
+    StorageHealth { status: storage_status(available_bytes), used_bytes: dir_size(&dir), available_bytes }
+}
+
+/// The worst of the parts; a provider being down only degrades the whole
+fn overall(capture: Status, providers: &[ProviderReport], scheduler: Status, storage: Status) -> Status {
+    let providers = providers.iter().map(|provider| provider.status).max().unwrap_or(Status::Ok);
+    [capture, providers.min(Status::Degraded), scheduler, storage].into_iter().max().unwrap_or(Status::Ok)
+}
+
+fn health(app_handle: &AppHandle) -> Health {
+    let capture = capture(app_handle);
+    let providers = providers(app_handle);
+    let scheduler = SchedulerHealth {
+        status: Status::Ok,
+        queue: app_handle.state::<InferenceQueue>().metrics(),
+        last_agent_run_at: metrics::last_agent_run(app_handle),
+    };
+    let storage = storage(app_handle);
+    Health {
+        status: overall(capture.status, &providers, scheduler.status, storage.status),
+        version: app_handle.package_info().version.to_string(),
+        capture,
+        providers,
+        scheduler,
+        storage,
+    }
+}
+
+/// `GET /healthz`
+pub async fn healthz_handler(AxumState(state): AxumState<AppState>) -> Response {
+    // Walking the data directory touches the disk
+    let health = tokio::task::spawn_blocking(move || health(&state.app_handle)).await;
+    match health {
+        Ok(health) => {
+            let status = match health.status {
+                Status::Down => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::OK,
+            };
+            (status, Json(health)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(status: Status) -> ProviderReport {
+        ProviderReport {
+            id: "ollama".to_string(),
+            status,
+            consecutive_failures: 0,
+            last_error: None,
+            last_latency_ms: None,
+            last_success: None,
+            last_request_at: None,
+        }
+    }
+
+    #[test]
+    fn overall_status_is_the_worst_part() {
+        assert_eq!(overall(Status::Ok, &[provider(Status::Ok)], Status::Ok, Status::Ok), Status::Ok);
+        assert_eq!(overall(Status::Ok, &[provider(Status::Down)], Status::Ok, Status::Ok), Status::Degraded);
+        assert_eq!(overall(Status::Ok, &[], Status::Ok, Status::Down), Status::Down);
+
+        assert_eq!(storage_status(Some(50 * 1024 * 1024)), Status::Down);
+        assert_eq!(storage_status(Some(500 * 1024 * 1024)), Status::Degraded);
+        assert_eq!(storage_status(None), Status::Ok);
+    }
+}
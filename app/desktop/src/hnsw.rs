@@ -0,0 +1,213 @@
+// In src-tauri/src/hnsw.rs
+//
+// Hierarchical navigable small world graph for approximate nearest-neighbour search over
+// embeddings, as used by `vector_store`. Vectors are normalized on the way in, so the
+// distance is one minus cosine similarity. Each node sits on level 0 and, with
+// exponentially falling probability, on the levels above it; a search walks greedily
+// down from the sparse top level and widens to `ef` candidates on level 0.
+//
+// Nodes can only be added. The store rebuilds the graph after deletions.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+/// Links per node above level 0; level 0 gets twice as many
+pub const DEFAULT_M: usize = 16;
+/// Candidates considered when linking a new node
+pub const DEFAULT_EF_CONSTRUCTION: usize = 100;
+
+struct Node {
+    id: i64,
+    vector: Vec<f32>,
+    /// Neighbours on each level the node is on, bottom first
+    links: Vec<Vec<u32>>,
+}
+
+/// A distance ordered with `total_cmp`, for the heaps
+#[derive(Clone, Copy, PartialEq)]
+struct Dist(f32);
+
+impl Eq for Dist {}
+
+impl PartialOrd for Dist {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dist {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+pub struct Hnsw {
+    m: usize,
+    ef_construction: usize,
+    nodes: Vec<Node>,
+    entry: Option<u32>,
+    top_level: usize,
+    /// xorshift state for level assignment
+    seed: u64,
+}
+
+/// Scale to unit length; None for a zero vector
+pub fn normalize(mut vector: Vec<f32>) -> Option<Vec<f32>> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return None;
+    }
+    vector.iter_mut().for_each(|x| *x /= norm);
+    Some(vector)
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+impl Hnsw {
+    pub fn new(m: usize, ef_construction: usize) -> Self {
+        Self {
+            m: m.max(2),
+            ef_construction: ef_construction.max(1),
+            nodes: Vec::new(),
+            entry: None,
+            top_level: 0,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        let uniform = ((self.seed >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() / (self.m as f64).ln()) as usize
+    }
+
+    fn dist_to(&self, query: &[f32], node: u32) -> Dist {
+        Dist(distance(query, &self.nodes[node as usize].vector))
+    }
+
+    /// The `ef` nodes on `level` closest to `query` reachable from `entries`, closest first
+    fn search_level(&self, query: &[f32], entries: &[u32], ef: usize, level: usize) -> Vec<(Dist, u32)> {
+        let mut visited: HashSet<u32> = entries.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<(Dist, u32)>> = BinaryHeap::new();
+        let mut found: BinaryHeap<(Dist, u32)> = BinaryHeap::new();
+        for &entry in entries {
+            let dist = self.dist_to(query, entry);
+            candidates.push(Reverse((dist, entry)));
+            found.push((dist, entry));
+        }
+        while let Some(Reverse((dist, node))) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|(worst, _)| dist > *worst) {
+                break;
+            }
+            for &next in &self.nodes[node as usize].links[level] {
+                if !visited.insert(next) {
+                    continue;
+                }
+                let dist = self.dist_to(query, next);
+                if found.len() < ef || found.peek().is_some_and(|(worst, _)| dist < *worst) {
+                    candidates.push(Reverse((dist, next)));
+                    found.push((dist, next));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        found.into_sorted_vec()
+    }
+
+    /// Add a vector (normalized by the caller) under `id`
+    pub fn insert(&mut self, id: i64, vector: Vec<f32>) {
+        let level = self.random_level();
+        let index = self.nodes.len() as u32;
+        self.nodes.push(Node { id, vector, links: vec![Vec::new(); level + 1] });
+        let Some(mut entry) = self.entry else {
+            self.entry = Some(index);
+            self.top_level = level;
+            return;
+        };
+
+        let query = self.nodes[index as usize].vector.clone();
+        for upper in (level + 1..=self.top_level).rev() {
+            entry = self.search_level(&query, &[entry], 1, upper)[0].1;
+        }
+        for current in (0..=level.min(self.top_level)).rev() {
+            let found = self.search_level(&query, &[entry], self.ef_construction, current);
+            let max_links = if current == 0 { self.m * 2 } else { self.m };
+            let neighbours: Vec<u32> = found.iter().take(self.m).map(|&(_, node)| node).collect();
+            for &neighbour in &neighbours {
+                let links = &mut self.nodes[neighbour as usize].links[current];
+                links.push(index);
+                if links.len() > max_links {
+                    let base = self.nodes[neighbour as usize].vector.clone();
+                    let mut links = std::mem::take(&mut self.nodes[neighbour as usize].links[current]);
+                    links.sort_by_cached_key(|&link| self.dist_to(&base, link));
+                    links.truncate(max_links);
+                    self.nodes[neighbour as usize].links[current] = links;
+                }
+            }
+            self.nodes[index as usize].links[current] = neighbours;
+            entry = found[0].1;
+        }
+        if level > self.top_level {
+            self.top_level = level;
+            self.entry = Some(index);
+        }
+    }
+
+    /// Up to `k` ids closest to `query` (normalized), with their cosine similarity, most
+    /// similar first. A larger `ef` finds more of the true nearest at some speed cost.
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(i64, f32)> {
+        let Some(mut entry) = self.entry else {
+            return Vec::new();
+        };
+        for level in (1..=self.top_level).rev() {
+            entry = self.search_level(query, &[entry], 1, level)[0].1;
+        }
+        self.search_level(query, &[entry], ef.max(k), 0)
+            .into_iter()
+            .take(k)
+            .map(|(Dist(dist), node)| (self.nodes[node as usize].id, 1.0 - dist))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_nearest_neighbours() {
+        // Deterministic pseudo-random vectors
+        let mut state = 7u32;
+        let mut next = || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+        };
+        let vectors: Vec<Vec<f32>> =
+            (0..500).map(|_| normalize((0..32).map(|_| next()).collect()).unwrap()).collect();
+        let mut index = Hnsw::new(DEFAULT_M, DEFAULT_EF_CONSTRUCTION);
+        for (id, vector) in vectors.iter().enumerate() {
+            index.insert(id as i64, vector.clone());
+        }
+        assert_eq!(index.nodes.len(), 500);
+
+        let mut hits = 0;
+        for (id, vector) in vectors.iter().enumerate().step_by(25) {
+            let found = index.search(vector, 5, 50);
+            assert_eq!(found[0].0, id as i64);
+            assert!((found[0].1 - 1.0).abs() < 1e-5);
+            let mut exact: Vec<(usize, f32)> =
+                vectors.iter().map(|other| distance(vector, other)).enumerate().collect();
+            exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+            hits += exact[..5].iter().filter(|(id, _)| found.iter().any(|(f, _)| *f == *id as i64)).count();
+        }
+        // 20 queries, 5 neighbours each
+        assert!(hits >= 95, "recall {} of 100", hits);
+        assert!(normalize(vec![0.0, 0.0]).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+// In src-tauri/src/incognito.rs
+//
+// Global privacy kill switch. Turning incognito on stops every capture session, the
+// clipboard monitor, active-window streams and WebRTC viewers, holds the capture pause
+// gate and suspends the clipboard monitor so nothing restarts behind the user's back,
+// suspends agent input simulation, tells all agents to pause, and suppresses
+// notifications. Usage tracking skips its samples while it is on. It is toggled from a
+// global shortcut, the tray menu, or the frontend, and every change is announced with
+// an `incognito-changed` event.
+
+use crate::{CommandMessage, CommandState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::menu::CheckMenuItem;
+use tauri::{AppHandle, Emitter, Manager, State, Wry};
+use tauri_plugin_screen_capture::pause::{self, PauseReason};
+
+/// Tray menu id of the incognito toggle
+pub const TRAY_MENU_ID: &str = "incognito";
+
+pub struct IncognitoState {
+    active: AtomicBool,
+    tray_item: Mutex<Option<CheckMenuItem<Wry>>>,
+}
+
+impl IncognitoState {
+    pub fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            tray_item: Mutex::new(None),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Keep a handle on the tray checkbox so it can follow changes made elsewhere
+    pub fn set_tray_item(&self, item: CheckMenuItem<Wry>) {
+        let _ = item.set_checked(self.is_active());
+        *self.tray_item.lock().unwrap() = Some(item);
+    }
+}
+
+/// Whether incognito mode is on
+pub fn is_active(app_handle: &AppHandle) -> bool {
+    app_handle.state::<IncognitoState>().is_active()
+}
+
+/// Turn incognito mode on or off. No-op if it is already in the requested state.
+pub fn set_incognito(app_handle: &AppHandle, active: bool) {
+    let state = app_handle.state::<IncognitoState>();
+    if state.active.swap(active, Ordering::SeqCst) == active {
+        return;
+    }
+
+    log::info!("Incognito mode {}", if active { "enabled" } else { "disabled" });
+
+    // Gate first so no frame slips out while the sessions are being torn down
+    pause::set(PauseReason::Incognito, active);
+    #[cfg(feature = "input-sim")]
+    tauri_plugin_input_sim::guard::set_suspended(active);
+    tauri_plugin_clipboard_monitor::monitor::set_suspended(active);
+    if active {
+        crate::webrtc_stream::hang_up_all(app_handle);
+        tauri::async_runtime::spawn(async {
+            #[cfg(target_os = "macos")]
+            {
+                let _ = tauri_plugin_screen_capture::desktop::stop_audio();
+            }
+            #[cfg(not(target_os = "macos"))]
+            {
+                let _ = tauri_plugin_screen_capture::audio::stop_audio();
+            }
+            tauri_plugin_audio_capture::capture::stop_all();
+            tauri_plugin_clipboard_monitor::monitor::stop();
+            // Finish a running recording so the MP4 is playable, not just cut off
+            if tauri_plugin_screen_capture::recording::is_recording() {
+                if let Err(e) = tauri_plugin_screen_capture::recording::stop() {
+                    log::warn!("Incognito: failed to finish recording: {}", e);
+                }
+            }
+            tauri_plugin_screen_capture::snapshots::stop();
+            tauri_plugin_screen_capture::active_window::stop_all();
+            #[cfg(feature = "ocr")]
+            tauri_plugin_screen_capture::text_watch::stop_all();
+            if let Err(e) = tauri_plugin_screen_capture::desktop::stop_all_sessions() {
+                log::warn!("Incognito: failed to stop capture: {}", e);
+            }
+        });
+    }
+
+    // Agents live in the webview and in SSE clients; tell both
+    let command_state = app_handle.state::<CommandState>();
+    let command_msg = CommandMessage {
+        message_type: "incognito".to_string(),
+        agent_id: "*".to_string(),
+        action: if active { "pause" } else { "resume" }.to_string(),
+    };
+    if let Err(e) = command_state.command_broadcaster.send(command_msg) {
+        log::debug!("No SSE clients for incognito broadcast: {}", e);
+    }
+    if let Err(e) = app_handle.emit("incognito-changed", active) {
+        log::warn!("Failed to emit incognito-changed event: {}", e);
+    }
+
+    update_tray(app_handle, &state, active);
+}
+
+pub fn toggle_incognito(app_handle: &AppHandle) {
+    set_incognito(app_handle, !is_active(app_handle));
+}
+
+fn update_tray(app_handle: &AppHandle, state: &IncognitoState, active: bool) {
+    if let Some(item) = state.tray_item.lock().unwrap().as_ref() {
+        let _ = item.set_checked(active);
+    }
+    if let Some(tray) = app_handle.tray_by_id(crate::TRAY_ID) {
+        let tooltip = if active {
+            "Observer AI - incognito (capture and agents paused)"
+        } else {
+            "Observer AI is running"
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_incognito(incognito_state: State<'_, IncognitoState>) -> Result<bool, String> {
+    Ok(incognito_state.is_active())
+}
+
+#[tauri::command]
+pub async fn set_incognito_mode(active: bool, app_handle: AppHandle) -> Result<(), String> {
+    set_incognito(&app_handle, active);
+    Ok(())
+}
@@ -0,0 +1,1123 @@
+// In src-tauri/src/inference.rs
+//
+// Model requests the backend sends itself, so provider keys never reach the webview.
+// The frontend passes OpenAI-style chat messages to `inference_chat`; the request then
+// goes through the same stages as one through the local proxy (inference queue, PII
+// redaction, outbound audit log) and is sent to the agent's provider (see `providers`),
+// translated for Anthropic by `anthropic`.
+//
+// If that provider errors or doesn't answer within the chain's `fallbackAfterSecs`, the
+// request moves on to the next provider in the fallback chain, unless the provider refused
+// the request itself (400, 413, 422). The response names the provider that answered and
+// lists the ones that failed before it. Within one provider,
+// each try is limited to the provider's `timeoutSecs` and transient failures are retried
+// with jittered backoff; providers `provider_health` has marked unhealthy are skipped.
+// With `inference_cache` on, a repeat of an earlier request is answered from memory.
+// A request with an `outputSchema` gets a reply checked against it (`structured_output`),
+// re-asked when it doesn't match. A request naming `tools` lets the model call the Rust
+// tools in `tools`; the calls are run and their results sent back until the model answers.
+//
+// `inference_chat_stream` sends the same request with `stream: true` and forwards the reply
+// over a Tauri channel as it arrives, so long replies render progressively. A streamed
+// request only falls back before its first token, and stops when cancelled with
+// `inference_cancel` or when the channel's webview goes away.
+
+use crate::inference_queue::{InferenceQueue, Priority, QueueError};
+use crate::providers::{self, ProviderKind, ResolvedProvider};
+use crate::inference_cache::{self, CachedReply, InferenceCache};
+use crate::structured_output::{self, OutputSchema};
+use crate::tools::{self, ToolRun};
+use crate::{anthropic, audit, events, metrics, provider_health, recall, redaction, token_usage};
+use futures::StreamExt;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager};
+
+/// Longest a single model call may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Backoff before the first retry, doubled for each one after it up to `MAX_BACKOFF`
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ImageUrl {
+    /// `data:image/...;base64,...` or an http(s) URL
+    pub url: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ChatMessage {
+    /// system, user, assistant or tool
+    pub role: String,
+    #[serde(default)]
+    pub content: MessageContent,
+    /// Tools an assistant turn called
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a tool turn answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn text(role: &str, text: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: MessageContent::Text(text.into()),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+}
+
+/// A function call in the OpenAI form
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    /// Always "function"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded
+    pub arguments: String,
+}
+
+/// Where a request came from. Tool grants are looked up by agent id, which only the app's
+/// own agents can be trusted to give truthfully.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Caller {
+    /// The main window
+    App,
+    /// The API servers and any other window
+    #[default]
+    External,
+}
+
+impl Caller {
+    fn of(webview: &tauri::Webview) -> Caller {
+        match webview.label() {
+            "main" => Caller::App,
+            _ => Caller::External,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatRequest {
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    /// Overrides the agent's provider
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// Overrides the provider's default model
+    #[serde(default)]
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    /// Extra request fields passed through as-is (temperature, max_tokens, ...)
+    #[serde(default)]
+    pub params: serde_json::Map<String, serde_json::Value>,
+    /// Defaults to the agent's queue priority
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    /// Lets `inference_cancel` find the request; generated if omitted
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// Use (or bypass) the reply cache for this request; defaults to the cache setting
+    #[serde(default)]
+    pub cache: Option<bool>,
+    /// JSON schema the reply must match
+    #[serde(default)]
+    pub output_schema: Option<OutputSchema>,
+    /// Tools the model may call, by name; the agent must be allowed each
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Set by whatever received the request, never by the request itself
+    #[serde(skip)]
+    pub caller: Caller,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// A provider's reply, whatever its API
+#[derive(Clone, Debug, Default)]
+pub struct Completion {
+    pub content: String,
+    pub finish_reason: Option<String>,
+    pub usage: Option<TokenUsage>,
+    pub tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatResponse {
+    pub content: String,
+    pub model: String,
+    pub provider_id: String,
+    pub request_id: String,
+    pub finish_reason: Option<String>,
+    pub usage: Option<TokenUsage>,
+    /// Providers tried before `provider_id`, in order, with why they failed
+    pub fallback_from: Vec<ProviderFailure>,
+    /// Answered from the reply cache
+    pub cached: bool,
+    /// The reply parsed, for requests with an output schema
+    pub output: Option<serde_json::Value>,
+    /// Times the reply was sent back for not matching the schema
+    pub reasks: u32,
+    /// Tool calls made on the way to the reply, in order
+    pub tool_runs: Vec<ToolRun>,
+    /// Calls the reply asks for, run by `run_with_tools`
+    #[serde(skip)]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderFailure {
+    pub provider_id: String,
+    pub error: String,
+}
+
+/// What `inference_chat_stream` sends over its channel before returning the `ChatResponse`
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum StreamEvent {
+    /// An attempt started; sent again if an earlier provider failed before its first token,
+    /// and when a reply not matching the output schema is re-asked
+    Started { provider_id: String, model: String, request_id: String },
+    /// The next piece of the reply
+    Delta { content: String },
+    /// The next piece of a reasoning model's thinking, which isn't part of the reply
+    Reasoning { content: String },
+    /// The model called a tool. Replies of requests with tools arrive as a single delta.
+    ToolCall { name: String },
+}
+
+/// Where streamed events go; returns false once nobody is listening
+pub type Sink<'a> = &'a (dyn Fn(StreamEvent) -> bool + Send + Sync);
+
+#[derive(Debug)]
+pub enum InferenceError {
+    /// The request never reached a provider
+    Config(String),
+    Queue(QueueError),
+    /// Connection failure or timeout
+    Network(String),
+    /// The provider answered with an error status
+    Provider { status: u16, message: String },
+    /// The provider's answer couldn't be understood
+    InvalidResponse(String),
+    /// Every provider in the chain failed
+    AllFailed(Vec<ProviderFailure>),
+    /// The reply still didn't match the output schema after the last re-ask
+    InvalidOutput(String),
+}
+
+impl InferenceError {
+    /// A failure that may pass on its own: connection errors, timeouts, 408, 429 and 5xx
+    pub fn is_transient(&self) -> bool {
+        match self {
+            InferenceError::Network(_) => true,
+            InferenceError::Provider { status, .. } => matches!(status, 408 | 429 | 500..=599),
+            _ => false,
+        }
+    }
+
+    /// Whether the next provider in the chain should get the request: not after a
+    /// cancellation, nor after a provider refused the request itself (400, 413, 422), which
+    /// the next one would only repeat with the prompt in hand
+    pub fn falls_back(&self) -> bool {
+        match self {
+            InferenceError::Queue(_) => false,
+            InferenceError::Provider { status, .. } => !matches!(status, 400 | 413 | 422),
+            _ => true,
+        }
+    }
+}
+
+impl std::fmt::Display for InferenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InferenceError::Config(message) => write!(f, "{}", message),
+            InferenceError::Queue(e) => write!(f, "{}", e),
+            InferenceError::Network(message) => write!(f, "Provider unreachable: {}", message),
+            InferenceError::Provider { status, message } => write!(f, "Provider returned {}: {}", status, message),
+            InferenceError::InvalidResponse(message) => write!(f, "Invalid provider response: {}", message),
+            InferenceError::InvalidOutput(message) => write!(f, "{}", message),
+            InferenceError::AllFailed(failures) => match failures.as_slice() {
+                [only] => write!(f, "{}", only.error),
+                _ => {
+                    let failures: Vec<String> =
+                        failures.iter().map(|f| format!("{}: {}", f.provider_id, f.error)).collect();
+                    write!(f, "All providers failed ({})", failures.join("; "))
+                }
+            },
+        }
+    }
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// The message of an error body (`{"error": {"message": ...}}`, `{"error": "..."}`), or the raw text
+pub fn error_message(body: &str) -> String {
+    let message = serde_json::from_str::<serde_json::Value>(body).ok().and_then(|json| {
+        let error = &json["error"];
+        error["message"].as_str().or_else(|| error.as_str()).map(String::from)
+    });
+    message.unwrap_or_else(|| body.chars().take(500).collect())
+}
+
+/// Pull the reply out of a `/chat/completions` response
+fn parse_completion(body: &serde_json::Value) -> Result<Completion, InferenceError> {
+    let choice = body["choices"]
+        .get(0)
+        .ok_or_else(|| InferenceError::InvalidResponse("no choices".to_string()))?;
+    let content = choice["message"]["content"].as_str().unwrap_or_default().to_string();
+    let finish_reason = choice["finish_reason"].as_str().map(String::from);
+    let usage = body["usage"].is_object().then(|| TokenUsage {
+        prompt_tokens: body["usage"]["prompt_tokens"].as_u64().unwrap_or(0),
+        completion_tokens: body["usage"]["completion_tokens"].as_u64().unwrap_or(0),
+    });
+    let tool_calls = choice["message"]["tool_calls"]
+        .as_array()
+        .map(|calls| calls.iter().filter_map(parse_tool_call).collect())
+        .unwrap_or_default();
+    Ok(Completion { content, finish_reason, usage, tool_calls })
+}
+
+/// Ollama sends `arguments` as an object rather than a JSON string
+fn parse_tool_call(call: &serde_json::Value) -> Option<ToolCall> {
+    let function = &call["function"];
+    let arguments = match &function["arguments"] {
+        serde_json::Value::String(arguments) => arguments.clone(),
+        serde_json::Value::Null => "{}".to_string(),
+        arguments => arguments.to_string(),
+    };
+    Some(ToolCall {
+        id: call["id"].as_str().map(String::from).unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        kind: "function".to_string(),
+        function: FunctionCall { name: function["name"].as_str()?.to_string(), arguments },
+    })
+}
+
+/// Splits a server-sent event stream into its `data:` payloads, whatever the chunk boundaries
+#[derive(Default)]
+struct SseData {
+    pending: Vec<u8>,
+}
+
+impl SseData {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut data = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(payload) = line.trim_end().strip_prefix("data:") {
+                data.push(payload.trim_start().to_string());
+            }
+        }
+        data
+    }
+}
+
+/// Read one streamed `/chat/completions` chunk into `completion`, returning what to forward
+fn decode_openai_chunk(chunk: &serde_json::Value, completion: &mut Completion) -> Vec<StreamEvent> {
+    // Sent in the last chunk when `stream_options.include_usage` is honoured
+    if chunk["usage"].is_object() {
+        completion.usage = Some(TokenUsage {
+            prompt_tokens: chunk["usage"]["prompt_tokens"].as_u64().unwrap_or(0),
+            completion_tokens: chunk["usage"]["completion_tokens"].as_u64().unwrap_or(0),
+        });
+    }
+    let Some(choice) = chunk["choices"].get(0) else {
+        return Vec::new();
+    };
+    if let Some(reason) = choice["finish_reason"].as_str() {
+        completion.finish_reason = Some(reason.to_string());
+    }
+    let delta = &choice["delta"];
+    let mut events = Vec::new();
+    // Ollama and OpenRouter say `reasoning`, vLLM and DeepSeek `reasoning_content`
+    if let Some(reasoning) = delta["reasoning"].as_str().or_else(|| delta["reasoning_content"].as_str()) {
+        if !reasoning.is_empty() {
+            events.push(StreamEvent::Reasoning { content: reasoning.to_string() });
+        }
+    }
+    if let Some(content) = delta["content"].as_str().filter(|content| !content.is_empty()) {
+        events.push(StreamEvent::Delta { content: content.to_string() });
+    }
+    events
+}
+
+/// Read a streamed reply, passing each event `decode` finds to `sink` and collecting the
+/// text into the returned `Completion`
+pub async fn read_stream(
+    response: reqwest::Response,
+    sink: Sink<'_>,
+    decode: fn(&serde_json::Value, &mut Completion) -> Vec<StreamEvent>,
+) -> Result<Completion, InferenceError> {
+    let mut completion = Completion::default();
+    let mut sse = SseData::default();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| InferenceError::Network(e.to_string()))?;
+        for data in sse.push(&chunk) {
+            if data == "[DONE]" {
+                return Ok(completion);
+            }
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(&data) else {
+                continue;
+            };
+            // Errors after the 200 arrive as an event
+            if !event["error"].is_null() {
+                return Err(InferenceError::InvalidResponse(error_message(&data)));
+            }
+            for event in decode(&event, &mut completion) {
+                if let StreamEvent::Delta { content } = &event {
+                    completion.content.push_str(content);
+                }
+                if !sink(event) {
+                    return Err(InferenceError::Queue(QueueError::Cancelled));
+                }
+            }
+        }
+    }
+    Ok(completion)
+}
+
+/// Send `body` to the provider's `/chat/completions`, streaming the reply to `sink` if given
+async fn send_openai(
+    provider: &ResolvedProvider,
+    body: Vec<u8>,
+    sink: Option<Sink<'_>>,
+) -> Result<Completion, InferenceError> {
+    let url = format!("{}/chat/completions", provider.api_base());
+    let response = provider
+        .authorize(client().post(&url))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| InferenceError::Network(e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        if let Some(sink) = sink {
+            return read_stream(response, sink, decode_openai_chunk).await;
+        }
+    }
+    let text = response.text().await.map_err(|e| InferenceError::Network(e.to_string()))?;
+    if !status.is_success() {
+        return Err(InferenceError::Provider { status: status.as_u16(), message: error_message(&text) });
+    }
+    let json: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| InferenceError::InvalidResponse(e.to_string()))?;
+    parse_completion(&json)
+}
+
+/// Send one try to `provider` and log it to the audit table. A streamed try only has to
+/// start answering within `time_limit`.
+async fn attempt(
+    app_handle: &AppHandle,
+    provider: &ResolvedProvider,
+    agent_id: Option<&str>,
+    body: Vec<u8>,
+    redactions: &redaction::Redactions,
+    time_limit: Duration,
+    stream: Option<(Sink<'_>, &AtomicBool)>,
+) -> Result<Completion, InferenceError> {
+    let summary = audit::summarize_payload(&body);
+    let audit_id = audit::record_outbound(
+        app_handle,
+        &audit::OutboundRecord {
+            kind: audit::OutboundKind::Llm,
+            destination: provider.api_base(),
+            method: "POST".to_string(),
+            model: summary.model,
+            agent_id: agent_id.map(String::from),
+            request_bytes: body.len() as u64,
+            image_hashes: summary.image_hashes,
+            prompt: summary.prompt,
+            redactions: redactions.clone(),
+        },
+    );
+
+    let sink = stream.map(|(sink, _)| sink);
+    let send = async {
+        match provider.kind {
+            ProviderKind::Anthropic => anthropic::send(client(), provider, &body, sink).await,
+            ProviderKind::Ollama | ProviderKind::OpenAi => send_openai(provider, body, sink).await,
+        }
+    };
+    let result = within(time_limit, stream.map(|(_, started)| started), send).await;
+
+    if let Some(id) = audit_id {
+        let status = match &result {
+            Ok(_) => Some(200),
+            Err(InferenceError::Provider { status, .. }) => Some(*status),
+            Err(_) => None,
+        };
+        let response_bytes = result.as_ref().map(|c| c.content.len() as u64).unwrap_or(0);
+        audit::record_response(app_handle, id, status, response_bytes);
+    }
+    result
+}
+
+/// `send`, given up as a timeout after `time_limit` unless `started` says its reply is
+/// already streaming
+async fn within<T>(
+    time_limit: Duration,
+    started: Option<&AtomicBool>,
+    send: impl std::future::Future<Output = Result<T, InferenceError>>,
+) -> Result<T, InferenceError> {
+    tokio::pin!(send);
+    tokio::select! {
+        result = &mut send => result,
+        _ = tokio::time::sleep(time_limit) => match started {
+            // Already streaming to the user: too late to give up on it
+            Some(started) if started.load(Ordering::Relaxed) => send.await,
+            _ => Err(InferenceError::Network(format!("timed out after {:.0} s", time_limit.as_secs_f64()))),
+        },
+    }
+}
+
+/// Full-jitter exponential backoff before retry number `retry` (0-based), from a random `seed`
+fn backoff(retry: u32, seed: u32) -> Duration {
+    let ceiling = BASE_BACKOFF.saturating_mul(1 << retry.min(16)).min(MAX_BACKOFF);
+    // Somewhere between half and all of the ceiling, so retries from many agents spread out
+    let fraction = 0.5 + 0.5 * (seed as f64 / u32::MAX as f64);
+    ceiling.mul_f64(fraction)
+}
+
+/// `attempt` with the provider's timeout on each try, retrying transient failures until its
+/// retries run out or `deadline` (when the fallback chain takes over) would pass
+async fn attempt_with_retries(
+    app_handle: &AppHandle,
+    provider: &ResolvedProvider,
+    agent_id: Option<&str>,
+    body: &[u8],
+    redactions: &redaction::Redactions,
+    deadline: Option<Instant>,
+    stream: Option<(Sink<'_>, &AtomicBool)>,
+) -> Result<Completion, InferenceError> {
+    let mut retries = 0;
+    loop {
+        let mut time_limit = provider.policy.timeout();
+        if let Some(deadline) = deadline {
+            time_limit = time_limit.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        let result = attempt(app_handle, provider, agent_id, body.to_vec(), redactions, time_limit, stream).await;
+        let started = stream.is_some_and(|(_, started)| started.load(Ordering::Relaxed));
+        match result {
+            Err(e) if e.is_transient() && !started && retries < provider.policy.max_retries => {
+                let delay = backoff(retries, OsRng.next_u32());
+                if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+                    return Err(e);
+                }
+                log::info!("Retrying {} in {} ms: {}", provider.id, delay.as_millis(), e);
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Call `send` with each of `providers`' index in turn until one answers. A failure that
+/// `falls_back` moves on to the next provider and is returned with the answer; any other,
+/// or one after a streamed reply has `started`, ends the chain.
+async fn in_turn<T, Fut>(
+    providers: &[ResolvedProvider],
+    started: &AtomicBool,
+    send: impl Fn(usize) -> Fut,
+) -> Result<(T, Vec<ProviderFailure>), InferenceError>
+where
+    Fut: std::future::Future<Output = Result<T, InferenceError>>,
+{
+    let mut failures = Vec::new();
+    for (index, provider) in providers.iter().enumerate() {
+        match send(index).await {
+            Ok(answer) => return Ok((answer, failures)),
+            Err(e) if started.load(Ordering::Relaxed) => {
+                log::warn!("Streamed inference on {} failed midway: {}", provider.id, e);
+                return Err(e);
+            }
+            Err(e) if !e.falls_back() => return Err(e),
+            Err(e) => {
+                log::warn!("Inference on {} failed: {}", provider.id, e);
+                failures.push(ProviderFailure { provider_id: provider.id.clone(), error: e.to_string() });
+            }
+        }
+    }
+    Err(InferenceError::AllFailed(failures))
+}
+
+/// Run a chat request against the agent's provider, falling back along the chain
+pub async fn chat(app_handle: &AppHandle, request: ChatRequest) -> Result<ChatResponse, InferenceError> {
+    respond(app_handle, request, None).await
+}
+
+/// `chat`, forwarding the reply to `sink` as it is generated
+pub async fn chat_stream(
+    app_handle: &AppHandle,
+    request: ChatRequest,
+    sink: Sink<'_>,
+) -> Result<ChatResponse, InferenceError> {
+    respond(app_handle, request, Some(sink)).await
+}
+
+async fn respond(
+    app_handle: &AppHandle,
+    mut request: ChatRequest,
+    sink: Option<Sink<'_>>,
+) -> Result<ChatResponse, InferenceError> {
+    recall::augment(app_handle, &mut request).await;
+    match request.output_schema.take() {
+        Some(schema) => run_structured(app_handle, request, schema, sink).await,
+        None => run_with_tools(app_handle, request, sink).await,
+    }
+}
+
+fn add_usage(total: &mut Option<TokenUsage>, spent: &Option<TokenUsage>) {
+    if let Some(spent) = spent {
+        let total = total.get_or_insert_with(TokenUsage::default);
+        total.prompt_tokens += spent.prompt_tokens;
+        total.completion_tokens += spent.completion_tokens;
+    }
+}
+
+/// `run_chat`, running the tools the model calls and sending their results back until it
+/// answers. Every round goes to the provider and model that answered the first.
+async fn run_with_tools(
+    app_handle: &AppHandle,
+    mut request: ChatRequest,
+    sink: Option<Sink<'_>>,
+) -> Result<ChatResponse, InferenceError> {
+    if request.tools.is_empty() {
+        return run_chat(app_handle, request, sink).await;
+    }
+    let agent_id = request.agent_id.clone();
+    let available = tools::resolve(app_handle, request.caller, agent_id.as_deref(), &request.tools)
+        .map_err(InferenceError::Config)?;
+    let max_rounds = tools::max_rounds(app_handle);
+    request.params.insert("tools".to_string(), tools::definitions(&available));
+    // A cached tool call would act on a world that may have moved on
+    request.cache = Some(false);
+
+    let mut usage = None;
+    let mut runs = Vec::new();
+    let mut rounds = 0;
+    loop {
+        // Rounds aren't streamed; the final reply is forwarded whole
+        let mut response = run_chat(app_handle, request.clone(), None).await?;
+        add_usage(&mut usage, &response.usage);
+        if response.tool_calls.is_empty() {
+            if let Some(sink) = sink {
+                sink(StreamEvent::Started {
+                    provider_id: response.provider_id.clone(),
+                    model: response.model.clone(),
+                    request_id: response.request_id.clone(),
+                });
+                sink(StreamEvent::Delta { content: response.content.clone() });
+            }
+            response.usage = usage;
+            response.tool_runs = runs;
+            return Ok(response);
+        }
+        if rounds == max_rounds {
+            return Err(InferenceError::InvalidOutput(format!("Still calling tools after {} rounds", max_rounds)));
+        }
+        rounds += 1;
+
+        request.messages.push(ChatMessage {
+            tool_calls: response.tool_calls.clone(),
+            ..ChatMessage::text("assistant", response.content.clone())
+        });
+        let mut images = Vec::new();
+        for call in &response.tool_calls {
+            if sink.is_some_and(|sink| !sink(StreamEvent::ToolCall { name: call.function.name.clone() })) {
+                return Err(InferenceError::Queue(QueueError::Cancelled));
+            }
+            let (run, image) = tools::run(app_handle, agent_id.as_deref(), &available, call).await;
+            request.messages.push(ChatMessage {
+                tool_call_id: Some(call.id.clone()),
+                ..ChatMessage::text("tool", run.output.clone())
+            });
+            images.extend(image.map(|url| ContentPart::ImageUrl { image_url: ImageUrl { url } }));
+            runs.push(run);
+        }
+        // Tool results are text only; captured images follow as a user turn
+        if !images.is_empty() {
+            request.messages.push(ChatMessage {
+                content: MessageContent::Parts(images),
+                ..ChatMessage::text("user", "")
+            });
+        }
+        request.provider_id = Some(response.provider_id);
+        request.model = Some(response.model);
+    }
+}
+
+/// `run_chat` until the reply matches `schema`, re-asking up to `schema.max_reasks` times.
+/// Re-asks go to the provider and model that answered.
+async fn run_structured(
+    app_handle: &AppHandle,
+    mut request: ChatRequest,
+    schema: OutputSchema,
+    sink: Option<Sink<'_>>,
+) -> Result<ChatResponse, InferenceError> {
+    let validator = structured_output::compile(&schema).map_err(InferenceError::Config)?;
+    request
+        .params
+        .insert("response_format".to_string(), structured_output::response_format(&schema));
+
+    let mut usage: Option<TokenUsage> = None;
+    let mut reasks = 0;
+    loop {
+        let mut response = run_with_tools(app_handle, request.clone(), sink).await?;
+        add_usage(&mut usage, &response.usage);
+        match structured_output::check(&validator, &response.content) {
+            Ok(output) => {
+                response.output = Some(output);
+                response.usage = usage;
+                response.reasks = reasks;
+                return Ok(response);
+            }
+            Err(problem) if reasks < schema.max_reasks => {
+                log::info!("Re-asking {} for output matching the schema: {}", response.provider_id, problem);
+                reasks += 1;
+                request.messages.extend(structured_output::reask_messages(&response.content, &problem));
+                request.provider_id = Some(response.provider_id);
+                request.model = Some(response.model);
+            }
+            Err(problem) => return Err(InferenceError::InvalidOutput(problem)),
+        }
+    }
+}
+
+async fn run_chat(
+    app_handle: &AppHandle,
+    request: ChatRequest,
+    sink: Option<Sink<'_>>,
+) -> Result<ChatResponse, InferenceError> {
+    let agent_id = request.agent_id.as_deref();
+    let chain = providers::chain(app_handle, agent_id, request.provider_id.as_deref())
+        .map_err(InferenceError::Config)?;
+    let request_id = request.request_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    // A repeat of an earlier request is answered from the cache, without queueing
+    let cache = app_handle.state::<InferenceCache>();
+    let cache_key = match chain.providers.first() {
+        Some(primary) if request.cache.unwrap_or_else(|| cache.enabled()) => {
+            match request.model.clone().or_else(|| primary.model.clone()) {
+                Some(model) => {
+                    let (id, messages, params) = (primary.id.clone(), request.messages.clone(), request.params.clone());
+                    tokio::task::spawn_blocking(move || inference_cache::key(&id, &model, &messages, &params))
+                        .await
+                        .ok()
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    };
+    if let Some(reply) = cache_key.as_ref().and_then(|key| cache.lookup(key)) {
+        log::info!("Inference for {:?} answered from the cache", agent_id);
+        if let Some(sink) = sink {
+            sink(StreamEvent::Started {
+                provider_id: reply.provider_id.clone(),
+                model: reply.model.clone(),
+                request_id: request_id.clone(),
+            });
+            sink(StreamEvent::Delta { content: reply.completion.content.clone() });
+        }
+        return Ok(ChatResponse {
+            content: reply.completion.content,
+            model: reply.model,
+            provider_id: reply.provider_id,
+            request_id,
+            finish_reason: reply.completion.finish_reason,
+            // Nothing was spent on this one
+            usage: None,
+            fallback_from: Vec::new(),
+            cached: true,
+            output: None,
+            reasks: 0,
+            tool_runs: Vec::new(),
+            tool_calls: reply.completion.tool_calls,
+        });
+    }
+
+    let mut body = request.params.clone();
+    body.insert(
+        "messages".to_string(),
+        serde_json::to_value(&request.messages).map_err(|e| InferenceError::Config(e.to_string()))?,
+    );
+    body.insert("stream".to_string(), sink.is_some().into());
+    if sink.is_some() {
+        body.insert("stream_options".to_string(), serde_json::json!({ "include_usage": true }));
+    }
+    let body = serde_json::to_vec(&body).map_err(|e| InferenceError::Config(e.to_string()))?;
+
+    token_usage::check_budget(app_handle, agent_id).map_err(|e| InferenceError::Config(e.to_string()))?;
+    // Fallbacks run in the slot taken on the primary provider
+    let primary_id = chain.providers.first().map(|p| p.id.clone()).unwrap_or_default();
+    let queue = app_handle.state::<InferenceQueue>();
+    let mut ticket = queue
+        .acquire(request_id.clone(), request.agent_id.clone(), primary_id, request.priority)
+        .await
+        .map_err(InferenceError::Queue)?;
+
+    // Mask PII before anything leaves the machine; the same masked body goes to every provider
+    let (redacted, redactions) = redaction::redact_request_body(
+        &app_handle.state::<redaction::RedactionState>(),
+        &body,
+        agent_id,
+    );
+    let body: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&redacted.unwrap_or(body)).map_err(|e| InferenceError::Config(e.to_string()))?;
+
+    // Set by the first forwarded event; from then on the reply can't move to another provider
+    let started = AtomicBool::new(false);
+    let forward = |event: StreamEvent| {
+        started.store(true, Ordering::Relaxed);
+        sink.is_some_and(|sink| sink(event))
+    };
+    let stream = sink.map(|_| (&forward as Sink<'_>, &started));
+
+    let providers = &chain.providers;
+    let (body, redactions, request_id, cache, cache_key) = (&body, &redactions, &request_id, &*cache, &cache_key);
+    let requested_model = request.model.as_deref();
+    let send = |index: usize| async move {
+        let provider = &providers[index];
+        // A model named in the request is for the first provider; fallbacks use their own
+        let model = match index {
+            0 => requested_model.or(provider.model.as_deref()),
+            _ => provider.model.as_deref(),
+        };
+        let Some(model) = model.map(String::from) else {
+            return Err(InferenceError::Config(format!("No model given and {} has no default", provider.id)));
+        };
+        let mut body = body.clone();
+        body.insert("model".to_string(), model.clone().into());
+        let body = serde_json::to_vec(&body).map_err(|e| InferenceError::Config(e.to_string()))?;
+        if let Err(wait) = provider_health::admit(app_handle, provider) {
+            return Err(InferenceError::Config(format!("Unhealthy, next try in {} s", wait.as_secs().max(1))));
+        }
+        // Only a provider with somewhere to fall back to is cut short
+        let deadline = (index + 1 < providers.len()).then(|| Instant::now() + chain.fallback_after);
+        if let Some(sink) = sink {
+            let event = StreamEvent::Started {
+                provider_id: provider.id.clone(),
+                model: model.clone(),
+                request_id: request_id.clone(),
+            };
+            if !sink(event) {
+                return Err(InferenceError::Queue(QueueError::Cancelled));
+            }
+        }
+
+        let attempted = Instant::now();
+        let result = attempt_with_retries(app_handle, provider, agent_id, &body, redactions, deadline, stream).await;
+        provider_health::record(app_handle, provider, &result);
+        metrics::record_inference(app_handle, &provider.id, attempted.elapsed(), result.is_ok());
+        let completion = result?;
+        if let Some(usage) = &completion.usage {
+            token_usage::record(app_handle, agent_id, &provider.id, usage);
+        }
+        if let Some(key) = cache_key {
+            let reply = CachedReply {
+                provider_id: provider.id.clone(),
+                model: model.clone(),
+                completion: completion.clone(),
+            };
+            cache.store(key.clone(), reply);
+        }
+        Ok((provider, model, completion))
+    };
+
+    let run = async {
+        let ((provider, model, completion), failures) = in_turn(providers, &started, send).await?;
+        Ok(ChatResponse {
+            content: completion.content,
+            model,
+            provider_id: provider.id.clone(),
+            request_id: request_id.clone(),
+            finish_reason: completion.finish_reason,
+            usage: completion.usage,
+            fallback_from: failures,
+            cached: false,
+            output: None,
+            reasks: 0,
+            tool_runs: Vec::new(),
+            tool_calls: completion.tool_calls,
+        })
+    };
+    tokio::select! {
+        result = run => result,
+        _ = ticket.cancelled() => Err(InferenceError::Queue(QueueError::Cancelled)),
+    }
+}
+
+// Tauri commands
+
+/// Announce a failure on `/events`; a cancelled request didn't fail
+pub fn publish_error(app_handle: &AppHandle, agent_id: Option<String>, error: &InferenceError) {
+    if !matches!(error, InferenceError::Queue(QueueError::Cancelled)) {
+        events::publish(app_handle, events::ApiEvent::InferenceError { agent_id, error: error.to_string() });
+    }
+}
+
+#[tauri::command]
+pub async fn inference_chat(
+    mut request: ChatRequest,
+    webview: tauri::Webview,
+    app_handle: AppHandle,
+) -> Result<ChatResponse, String> {
+    request.caller = Caller::of(&webview);
+    let agent_id = request.agent_id.clone();
+    chat(&app_handle, request).await.map_err(|e| {
+        log::warn!("Inference for {:?} failed: {}", agent_id, e);
+        publish_error(&app_handle, agent_id, &e);
+        e.to_string()
+    })
+}
+
+/// `inference_chat`, sending `StreamEvent`s over `on_event` while the reply is generated
+#[tauri::command]
+pub async fn inference_chat_stream(
+    mut request: ChatRequest,
+    on_event: Channel<StreamEvent>,
+    webview: tauri::Webview,
+    app_handle: AppHandle,
+) -> Result<ChatResponse, String> {
+    request.caller = Caller::of(&webview);
+    let agent_id = request.agent_id.clone();
+    let sink = |event: StreamEvent| on_event.send(event).is_ok();
+    chat_stream(&app_handle, request, &sink).await.map_err(|e| {
+        log::warn!("Streamed inference for {:?} failed: {}", agent_id, e);
+        publish_error(&app_handle, agent_id, &e);
+        e.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_completions_and_error_bodies() {
+        let body = serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": "hi" }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 3 }
+        });
+        let completion = parse_completion(&body).unwrap();
+        assert_eq!(completion.content, "hi");
+        assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(completion.usage, Some(TokenUsage { prompt_tokens: 12, completion_tokens: 3 }));
+        assert!(parse_completion(&serde_json::json!({ "choices": [] })).is_err());
+
+        assert_eq!(error_message(r#"{"error":{"message":"bad key"}}"#), "bad key");
+        assert_eq!(error_message(r#"{"error":"model not found"}"#), "model not found");
+        assert_eq!(error_message("Bad Gateway"), "Bad Gateway");
+    }
+
+    #[test]
+    fn decodes_streamed_chunks_split_anywhere() {
+        let stream = concat!(
+            "data: {\"choices\":[{\"delta\":{\"reasoning\":\"hm\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hé\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"llo\"},\"finish_reason\":\"stop\"}]}\r\n\r\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        // Cut inside the multi-byte 'é' as well as mid-line
+        let mut sse = SseData::default();
+        let mut data = Vec::new();
+        for chunk in stream.as_bytes().chunks(7) {
+            data.extend(sse.push(chunk));
+        }
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[4], "[DONE]");
+
+        let mut completion = Completion::default();
+        let events: Vec<StreamEvent> = data[..4]
+            .iter()
+            .flat_map(|data| decode_openai_chunk(&serde_json::from_str(data).unwrap(), &mut completion))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Reasoning { content: "hm".to_string() },
+                StreamEvent::Delta { content: "Hé".to_string() },
+                StreamEvent::Delta { content: "llo".to_string() },
+            ]
+        );
+        assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(completion.usage, Some(TokenUsage { prompt_tokens: 5, completion_tokens: 2 }));
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_a_cap() {
+        assert_eq!(backoff(0, 0), BASE_BACKOFF / 2);
+        assert_eq!(backoff(0, u32::MAX), BASE_BACKOFF);
+        assert_eq!(backoff(2, u32::MAX), BASE_BACKOFF * 4);
+        assert_eq!(backoff(40, u32::MAX), MAX_BACKOFF);
+        assert!(InferenceError::Provider { status: 503, message: String::new() }.is_transient());
+        assert!(!InferenceError::Provider { status: 401, message: String::new() }.is_transient());
+    }
+
+    #[test]
+    fn messages_serialize_in_openai_form() {
+        let message: ChatMessage = serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "What is on screen?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,AAAA" } }
+            ]
+        }))
+        .unwrap();
+        let MessageContent::Parts(parts) = &message.content else {
+            panic!("expected parts");
+        };
+        assert!(matches!(&parts[1], ContentPart::ImageUrl { image_url } if image_url.url.ends_with("AAAA")));
+        let plain: ChatMessage = serde_json::from_str(r#"{"role":"system","content":"Be brief"}"#).unwrap();
+        assert_eq!(serde_json::to_value(&plain).unwrap()["content"], "Be brief");
+    }
+
+    fn provider(id: &str) -> ResolvedProvider {
+        ResolvedProvider {
+            id: id.to_string(),
+            kind: ProviderKind::OpenAi,
+            base_url: String::new(),
+            api_key: None,
+            model: None,
+            auth: providers::EndpointAuth::default(),
+            policy: providers::RequestPolicy::default(),
+        }
+    }
+
+    fn failed(status: u16) -> InferenceError {
+        InferenceError::Provider { status, message: "nope".to_string() }
+    }
+
+    #[tokio::test]
+    async fn timed_out_and_failed_providers_fall_back_to_the_next() {
+        let chain = [provider("local"), provider("lab"), provider("cloud")];
+        let started = AtomicBool::new(false);
+        let send = |index: usize| async move {
+            match index {
+                0 => within(Duration::from_millis(10), None, std::future::pending()).await,
+                1 => Err(failed(503)),
+                _ => Ok(index),
+            }
+        };
+        let (answered, failures) = in_turn(&chain, &started, send).await.unwrap();
+        assert_eq!(answered, 2);
+        let tried: Vec<&str> = failures.iter().map(|f| f.provider_id.as_str()).collect();
+        assert_eq!(tried, ["local", "lab"]);
+        assert_eq!(failures[0].error, "Provider unreachable: timed out after 0 s");
+        assert_eq!(failures[1].error, "Provider returned 503: nope");
+    }
+
+    #[tokio::test]
+    async fn a_started_stream_outlives_its_time_limit() {
+        let started = AtomicBool::new(true);
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            Ok(7)
+        };
+        assert_eq!(within(Duration::from_millis(10), Some(&started), slow).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn refused_requests_and_cancellations_stop_the_chain() {
+        let chain = [provider("local"), provider("cloud")];
+        let not_started = AtomicBool::new(false);
+        for error in [failed(400), failed(422), InferenceError::Queue(QueueError::Cancelled)] {
+            let error = std::sync::Mutex::new(Some(error));
+            let calls = std::sync::atomic::AtomicUsize::new(0);
+            let send = |_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                let error = error.lock().unwrap().take();
+                async move { Err::<(), _>(error.unwrap_or(failed(500))) }
+            };
+            let result = in_turn(&chain, &not_started, send).await;
+            assert!(!matches!(result, Err(InferenceError::AllFailed(_))), "{:?}", result);
+            assert_eq!(calls.load(Ordering::Relaxed), 1);
+        }
+
+        // A streamed reply that broke off midway isn't sent again elsewhere
+        let started = AtomicBool::new(true);
+        let result = in_turn(&chain, &started, |_| async { Err::<(), _>(failed(502)) }).await;
+        assert!(matches!(result, Err(InferenceError::Provider { status: 502, .. })));
+        assert!(failed(401).falls_back() && failed(404).falls_back() && failed(429).falls_back());
+    }
+
+    #[tokio::test]
+    async fn exhausted_chains_name_every_attempt() {
+        let chain = [provider("local"), provider("cloud")];
+        let started = AtomicBool::new(false);
+        let send = |index: usize| async move {
+            match index {
+                0 => Err::<(), _>(InferenceError::Network("connection refused".to_string())),
+                _ => Err(failed(503)),
+            }
+        };
+        let error = in_turn(&chain, &started, send).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "All providers failed (local: Provider unreachable: connection refused; cloud: Provider returned 503: nope)"
+        );
+
+        // With nothing to fall back to, the one error is reported as is
+        let error = in_turn(&chain[..1], &started, send).await.unwrap_err();
+        assert_eq!(error.to_string(), "Provider unreachable: connection refused");
+    }
+}
@@ -0,0 +1,364 @@
+// In src-tauri/src/inference_cache.rs
+//
+// Optional in-memory cache of model replies for `inference::chat`. Agents often send the
+// same prompt over a screen that hasn't changed; with the cache on, such a request is
+// answered from memory without queueing or calling the provider.
+//
+// Entries are keyed on the provider, the model, a digest of the message text and request
+// parameters, and a perceptual hash (dHash, as the capture plugin computes per frame) of
+// each image. A re-encoded copy of the same frame, or one within `max_frame_distance`
+// hash bits of it (a blinking cursor), still hits. Entries expire after `ttl_secs`, and
+// the least recently used one makes room when the cache is full. Prompts are only kept as
+// digests.
+
+use crate::inference::{ChatMessage, Completion, ContentPart, MessageContent};
+use crate::shortcuts::{self, UnifiedShortcutState};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use tauri_plugin_screen_capture::delta::PixelOrder;
+use tauri_plugin_screen_capture::similarity::dhash;
+
+/// Request fields that don't change the reply
+const IGNORED_PARAMS: &[&str] = &["stream", "stream_options"];
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Differing dHash bits (of 64) up to which two frames count as the same
+    #[serde(default = "default_max_frame_distance")]
+    pub max_frame_distance: u32,
+}
+
+fn default_max_entries() -> usize {
+    256
+}
+
+fn default_ttl_secs() -> u64 {
+    300
+}
+
+fn default_max_frame_distance() -> u32 {
+    2
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_max_entries(),
+            ttl_secs: default_ttl_secs(),
+            max_frame_distance: default_max_frame_distance(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    /// Dropped to make room, not counting expired ones
+    pub evictions: u64,
+    /// Prompt + completion tokens of the replies served from the cache
+    pub tokens_saved: u64,
+}
+
+/// What a request is looked up by
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheKey {
+    provider_id: String,
+    model: String,
+    /// Messages (images left out) and parameters
+    digest: [u8; 32],
+    /// dHash of each image, in order
+    frames: Vec<u64>,
+}
+
+impl CacheKey {
+    fn matches(&self, other: &CacheKey, max_frame_distance: u32) -> bool {
+        self.provider_id == other.provider_id
+            && self.model == other.model
+            && self.digest == other.digest
+            && self.frames.len() == other.frames.len()
+            && self
+                .frames
+                .iter()
+                .zip(&other.frames)
+                .all(|(a, b)| (a ^ b).count_ones() <= max_frame_distance)
+    }
+}
+
+/// A stored reply
+#[derive(Clone, Debug)]
+pub struct CachedReply {
+    /// The provider and model that actually answered
+    pub provider_id: String,
+    pub model: String,
+    pub completion: Completion,
+}
+
+struct Entry {
+    key: CacheKey,
+    reply: CachedReply,
+    stored: Instant,
+    last_used: Instant,
+}
+
+struct Cache {
+    settings: CacheSettings,
+    entries: Vec<Entry>,
+    stats: CacheStats,
+}
+
+impl Cache {
+    fn get(&mut self, key: &CacheKey, now: Instant) -> Option<CachedReply> {
+        let ttl = Duration::from_secs(self.settings.ttl_secs);
+        self.entries.retain(|entry| now.duration_since(entry.stored) < ttl);
+        let max_distance = self.settings.max_frame_distance;
+        match self.entries.iter_mut().find(|entry| entry.key.matches(key, max_distance)) {
+            Some(entry) => {
+                entry.last_used = now;
+                self.stats.hits += 1;
+                if let Some(usage) = &entry.reply.completion.usage {
+                    self.stats.tokens_saved += usage.prompt_tokens + usage.completion_tokens;
+                }
+                Some(entry.reply.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn put(&mut self, key: CacheKey, reply: CachedReply, now: Instant) {
+        self.entries.retain(|entry| entry.key != key);
+        while !self.entries.is_empty() && self.entries.len() >= self.settings.max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            self.entries.swap_remove(oldest);
+            self.stats.evictions += 1;
+        }
+        if self.settings.max_entries > 0 {
+            self.entries.push(Entry { key, reply, stored: now, last_used: now });
+        }
+    }
+}
+
+pub struct InferenceCache {
+    cache: Mutex<Cache>,
+}
+
+impl InferenceCache {
+    pub fn new(settings: CacheSettings) -> Self {
+        Self {
+            cache: Mutex::new(Cache { settings, entries: Vec::new(), stats: CacheStats::default() }),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.cache.lock().unwrap().settings.enabled
+    }
+
+    pub fn lookup(&self, key: &CacheKey) -> Option<CachedReply> {
+        self.cache.lock().unwrap().get(key, Instant::now())
+    }
+
+    pub fn store(&self, key: CacheKey, reply: CachedReply) {
+        self.cache.lock().unwrap().put(key, reply, Instant::now());
+    }
+
+    fn stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        CacheStats { entries: cache.entries.len(), ..cache.stats.clone() }
+    }
+
+    fn apply_settings(&self, settings: CacheSettings) {
+        let mut cache = self.cache.lock().unwrap();
+        if !settings.enabled {
+            cache.entries.clear();
+        }
+        let excess = cache.entries.len().saturating_sub(settings.max_entries);
+        cache.entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used));
+        cache.entries.truncate(settings.max_entries);
+        cache.stats.evictions += excess as u64;
+        cache.settings = settings;
+    }
+}
+
+/// dHash of a `data:` image; None for other URLs or undecodable data
+fn frame_hash(url: &str) -> Option<u64> {
+    let (_, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+    let bytes = BASE64.decode(data).ok()?;
+    let image = image::load_from_memory(&bytes).ok()?.to_rgba8();
+    let (width, height) = image.dimensions();
+    Some(dhash(image.as_raw(), width, height, width as usize * 4, PixelOrder::Rgba))
+}
+
+/// The key for a request to `model` on `provider_id`. Decodes every image, so it belongs
+/// on a blocking thread.
+pub fn key(
+    provider_id: &str,
+    model: &str,
+    messages: &[ChatMessage],
+    params: &serde_json::Map<String, serde_json::Value>,
+) -> CacheKey {
+    let mut hasher = Sha256::new();
+    let mut frames = Vec::new();
+    for message in messages {
+        hasher.update(message.role.as_bytes());
+        hasher.update([0]);
+        let parts = match &message.content {
+            MessageContent::Text(text) => vec![Part::Text(text)],
+            MessageContent::Parts(parts) => parts.iter().map(Part::from).collect(),
+        };
+        for part in parts {
+            match part {
+                Part::Text(text) => hasher.update(text.as_bytes()),
+                Part::Image(url) => match frame_hash(url) {
+                    Some(hash) => frames.push(hash),
+                    // Remote or unreadable images have to match exactly
+                    None => hasher.update(url.as_bytes()),
+                },
+            }
+            hasher.update([0]);
+        }
+    }
+    let params: serde_json::Map<_, _> = params
+        .iter()
+        .filter(|(name, _)| !IGNORED_PARAMS.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    hasher.update(serde_json::Value::Object(params).to_string().as_bytes());
+
+    CacheKey {
+        provider_id: provider_id.to_string(),
+        model: model.to_string(),
+        digest: hasher.finalize().into(),
+        frames,
+    }
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Image(&'a str),
+}
+
+impl<'a> From<&'a ContentPart> for Part<'a> {
+    fn from(part: &'a ContentPart) -> Self {
+        match part {
+            ContentPart::Text { text } => Part::Text(text),
+            ContentPart::ImageUrl { image_url } => Part::Image(&image_url.url),
+        }
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_inference_cache_stats(cache: State<'_, InferenceCache>) -> Result<CacheStats, String> {
+    Ok(cache.stats())
+}
+
+/// Drop every entry; the counters keep running
+#[tauri::command]
+pub async fn clear_inference_cache(cache: State<'_, InferenceCache>) -> Result<(), String> {
+    cache.cache.lock().unwrap().entries.clear();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_inference_cache_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<CacheSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().inference_cache.clone())
+}
+
+#[tauri::command]
+pub async fn set_inference_cache_settings(
+    settings: CacheSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    cache: State<'_, InferenceCache>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting inference cache settings: {:?}", settings);
+    cache.apply_settings(settings.clone());
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.inference_cache = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOutputFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    fn frame(format: ImageOutputFormat, cursor: bool) -> ChatMessage {
+        let mut image = RgbImage::from_fn(320, 200, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 90]));
+        if cursor {
+            image.put_pixel(10, 10, Rgb([255, 255, 255]));
+        }
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+        let url = format!("data:image/png;base64,{}", BASE64.encode(bytes));
+        serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "What changed?" },
+                { "type": "image_url", "image_url": { "url": url } }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn reply() -> CachedReply {
+        CachedReply { provider_id: "ollama".to_string(), model: "m".to_string(), completion: Completion::default() }
+    }
+
+    #[test]
+    fn same_prompt_over_the_same_screen_hits() {
+        let params = serde_json::Map::new();
+        let png = key("ollama", "m", &[frame(ImageOutputFormat::Png, false)], &params);
+        let jpeg = key("ollama", "m", &[frame(ImageOutputFormat::Jpeg(90), true)], &params);
+        assert!(png.matches(&jpeg, 2));
+        assert!(!png.matches(&key("ollama", "other", &[frame(ImageOutputFormat::Png, false)], &params), 2));
+
+        let mut streaming = serde_json::Map::new();
+        streaming.insert("stream".to_string(), true.into());
+        assert_eq!(key("ollama", "m", &[frame(ImageOutputFormat::Png, false)], &streaming), png);
+        streaming.insert("temperature".to_string(), 0.9.into());
+        assert!(!png.matches(&key("ollama", "m", &[frame(ImageOutputFormat::Png, false)], &streaming), 2));
+    }
+
+    #[test]
+    fn expires_and_evicts_least_recently_used() {
+        let settings = CacheSettings { enabled: true, max_entries: 2, ttl_secs: 60, max_frame_distance: 0 };
+        let mut cache = Cache { settings, entries: Vec::new(), stats: CacheStats::default() };
+        let keys: Vec<CacheKey> =
+            ["a", "b", "c"].iter().map(|model| key("ollama", model, &[], &serde_json::Map::new())).collect();
+        let start = Instant::now();
+        cache.put(keys[0].clone(), reply(), start);
+        cache.put(keys[1].clone(), reply(), start + Duration::from_secs(1));
+        assert!(cache.get(&keys[0], start + Duration::from_secs(2)).is_some());
+        cache.put(keys[2].clone(), reply(), start + Duration::from_secs(3));
+        assert!(cache.get(&keys[1], start + Duration::from_secs(4)).is_none());
+        assert!(cache.get(&keys[2], start + Duration::from_secs(4)).is_some());
+        assert!(cache.get(&keys[0], start + Duration::from_secs(61)).is_none());
+        assert_eq!(cache.stats, CacheStats { hits: 2, misses: 2, entries: 0, evictions: 1, tokens_saved: 0 });
+    }
+}
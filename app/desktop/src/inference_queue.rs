@@ -0,0 +1,680 @@
+// In src-tauri/src/inference_queue.rs
+//
+// Queue for inference requests going through the local proxy. Several agents looping
+// over the same local GPU would otherwise pile requests onto Ollama at once; here they
+// wait their turn instead:
+//
+// - at most `max_concurrent` requests run at a time, `per_agent_limit` per agent, and
+//   per provider whatever `provider_limits` allows (a local GPU may take one request
+//   while a hosted API takes several)
+// - higher priority requests (`x-observer-priority: high|normal|low`, else the agent's
+//   entry in `agent_priorities`) go first, FIFO within a priority, so interactive use
+//   isn't stuck behind background agents
+// - a new request from an agent replaces that agent's still-queued one, since an
+//   analysis of an older frame is worthless once a newer frame is waiting
+// - queued and running requests can be cancelled by id (`x-observer-request-id`, or
+//   the id returned in the response header of the same name)
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::{oneshot, watch};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Requests running at once across all agents
+    #[serde(default = "default_one")]
+    pub max_concurrent: usize,
+    /// Requests running at once for a single agent
+    #[serde(default = "default_one")]
+    pub per_agent_limit: usize,
+    /// Drop an agent's queued request when it sends a newer one
+    #[serde(default = "default_true")]
+    pub supersede_pending: bool,
+    /// provider_id -> requests running at once on it; unlisted providers are only held
+    /// to `max_concurrent`
+    #[serde(default)]
+    pub provider_limits: HashMap<String, usize>,
+    /// agent_id -> priority of its requests that don't ask for one
+    #[serde(default)]
+    pub agent_priorities: HashMap<String, Priority>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_one() -> usize {
+    1
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_concurrent: 1,
+            per_agent_limit: 1,
+            supersede_pending: true,
+            provider_limits: HashMap::new(),
+            agent_priorities: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub fn from_header(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "high" => Priority::High,
+            "low" => Priority::Low,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueError {
+    /// A newer request from the same agent took this one's place
+    Superseded,
+    Cancelled,
+}
+
+impl QueueError {
+    /// What a proxied client gets back for a request that never ran
+    pub fn into_response(self) -> axum::response::Response {
+        axum::response::Response::builder()
+            .status(axum::http::StatusCode::CONFLICT)
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(
+                serde_json::json!({ "error": self.to_string() }).to_string(),
+            ))
+            .unwrap()
+    }
+}
+
+impl std::fmt::Display for QueueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueError::Superseded => write!(f, "Superseded by a newer request from the same agent"),
+            QueueError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+type Grant = Result<watch::Receiver<bool>, QueueError>;
+
+struct Pending {
+    id: String,
+    agent_id: Option<String>,
+    provider_id: String,
+    priority: Priority,
+    seq: u64,
+    enqueued: Instant,
+    grant: oneshot::Sender<Grant>,
+}
+
+struct Running {
+    agent_id: Option<String>,
+    provider_id: String,
+    started: Instant,
+    cancel: watch::Sender<bool>,
+}
+
+#[derive(Default)]
+struct Counters {
+    completed: u64,
+    cancelled: u64,
+    superseded: u64,
+    total_wait: Duration,
+    max_wait: Duration,
+    total_run: Duration,
+    started: u64,
+}
+
+struct Scheduler {
+    settings: QueueSettings,
+    pending: Vec<Pending>,
+    running: HashMap<String, Running>,
+    seq: u64,
+    counters: Counters,
+}
+
+impl Scheduler {
+    fn new(settings: QueueSettings) -> Self {
+        Self {
+            settings,
+            pending: Vec::new(),
+            running: HashMap::new(),
+            seq: 0,
+            counters: Counters::default(),
+        }
+    }
+
+    fn limits(&self) -> (usize, usize) {
+        if self.settings.enabled {
+            (self.settings.max_concurrent.max(1), self.settings.per_agent_limit.max(1))
+        } else {
+            (usize::MAX, usize::MAX)
+        }
+    }
+
+    fn provider_limit(&self, provider_id: &str) -> usize {
+        match self.settings.provider_limits.get(provider_id) {
+            Some(&limit) if self.settings.enabled => limit.max(1),
+            _ => usize::MAX,
+        }
+    }
+
+    fn running_on(&self, provider_id: &str) -> usize {
+        self.running.values().filter(|r| r.provider_id == provider_id).count()
+    }
+
+    fn running_for(&self, agent_id: &Option<String>) -> usize {
+        match agent_id {
+            Some(_) => self.running.values().filter(|r| r.agent_id == *agent_id).count(),
+            None => 0,
+        }
+    }
+
+    fn enqueue(
+        &mut self,
+        id: String,
+        agent_id: Option<String>,
+        provider_id: String,
+        priority: Option<Priority>,
+        grant: oneshot::Sender<Grant>,
+    ) {
+        let priority = priority
+            .or_else(|| agent_id.as_ref().and_then(|id| self.settings.agent_priorities.get(id).copied()))
+            .unwrap_or_default();
+        if self.settings.enabled && self.settings.supersede_pending && agent_id.is_some() {
+            let (stale, keep): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+                .into_iter()
+                .partition(|p| p.agent_id == agent_id);
+            self.pending = keep;
+            for p in stale {
+                log::debug!("Inference request {} superseded by {}", p.id, id);
+                self.counters.superseded += 1;
+                let _ = p.grant.send(Err(QueueError::Superseded));
+            }
+        }
+
+        self.seq += 1;
+        self.pending.push(Pending {
+            id,
+            agent_id,
+            provider_id,
+            priority,
+            seq: self.seq,
+            enqueued: Instant::now(),
+            grant,
+        });
+        self.dispatch();
+    }
+
+    /// Start as many queued requests as the limits allow
+    fn dispatch(&mut self) {
+        let (max_concurrent, per_agent_limit) = self.limits();
+        while self.running.len() < max_concurrent {
+            let next = self
+                .pending
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| self.running_for(&p.agent_id) < per_agent_limit)
+                .filter(|(_, p)| self.running_on(&p.provider_id) < self.provider_limit(&p.provider_id))
+                .max_by_key(|(_, p)| (p.priority, Reverse(p.seq)))
+                .map(|(i, _)| i);
+            let Some(index) = next else {
+                break;
+            };
+
+            let p = self.pending.remove(index);
+            let (cancel, cancel_rx) = watch::channel(false);
+            if p.grant.send(Ok(cancel_rx)).is_err() {
+                // Caller gave up while queued
+                continue;
+            }
+            let waited = p.enqueued.elapsed();
+            self.counters.started += 1;
+            self.counters.total_wait += waited;
+            self.counters.max_wait = self.counters.max_wait.max(waited);
+            self.running.insert(
+                p.id,
+                Running {
+                    agent_id: p.agent_id,
+                    provider_id: p.provider_id,
+                    started: Instant::now(),
+                    cancel,
+                },
+            );
+        }
+    }
+
+    /// A request left the queue (finished, failed or was dropped)
+    fn finish(&mut self, id: &str) {
+        self.pending.retain(|p| p.id != id);
+        if let Some(running) = self.running.remove(id) {
+            self.counters.completed += 1;
+            self.counters.total_run += running.started.elapsed();
+        }
+        self.dispatch();
+    }
+
+    fn cancel(&mut self, id: &str) -> bool {
+        if let Some(index) = self.pending.iter().position(|p| p.id == id) {
+            let p = self.pending.remove(index);
+            let _ = p.grant.send(Err(QueueError::Cancelled));
+            self.counters.cancelled += 1;
+            return true;
+        }
+        if let Some(running) = self.running.get(id) {
+            let _ = running.cancel.send(true);
+            self.counters.cancelled += 1;
+            return true;
+        }
+        false
+    }
+
+    fn ids_for_agent(&self, agent_id: &str) -> Vec<String> {
+        self.pending
+            .iter()
+            .filter(|p| p.agent_id.as_deref() == Some(agent_id))
+            .map(|p| p.id.clone())
+            .chain(
+                self.running
+                    .iter()
+                    .filter(|(_, r)| r.agent_id.as_deref() == Some(agent_id))
+                    .map(|(id, _)| id.clone()),
+            )
+            .collect()
+    }
+
+    fn metrics(&self) -> QueueMetrics {
+        let mut by_agent: HashMap<String, AgentQueueMetrics> = HashMap::new();
+        let key = |agent_id: &Option<String>| agent_id.clone().unwrap_or_else(|| "unknown".to_string());
+        for p in &self.pending {
+            let id = key(&p.agent_id);
+            by_agent
+                .entry(id.clone())
+                .or_insert_with(|| AgentQueueMetrics::new(id))
+                .queued += 1;
+        }
+        for r in self.running.values() {
+            let id = key(&r.agent_id);
+            by_agent
+                .entry(id.clone())
+                .or_insert_with(|| AgentQueueMetrics::new(id))
+                .running += 1;
+        }
+        let mut by_agent: Vec<_> = by_agent.into_values().collect();
+        by_agent.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+
+        let mut by_provider: HashMap<&str, ProviderQueueMetrics> = HashMap::new();
+        let provider = |id: &str| {
+            let limit = (self.provider_limit(id) != usize::MAX).then(|| self.provider_limit(id));
+            ProviderQueueMetrics { provider_id: id.to_string(), queued: 0, running: 0, limit }
+        };
+        for p in &self.pending {
+            by_provider.entry(&p.provider_id).or_insert_with(|| provider(&p.provider_id)).queued += 1;
+        }
+        for r in self.running.values() {
+            by_provider.entry(&r.provider_id).or_insert_with(|| provider(&r.provider_id)).running += 1;
+        }
+        let mut by_provider: Vec<_> = by_provider.into_values().collect();
+        by_provider.sort_by(|a, b| a.provider_id.cmp(&b.provider_id));
+
+        let mut by_priority = PriorityDepth::default();
+        for p in &self.pending {
+            match p.priority {
+                Priority::High => by_priority.high += 1,
+                Priority::Normal => by_priority.normal += 1,
+                Priority::Low => by_priority.low += 1,
+            }
+        }
+
+        let c = &self.counters;
+        let avg = |total: Duration, n: u64| if n == 0 { 0 } else { (total / n as u32).as_millis() as u64 };
+        QueueMetrics {
+            enabled: self.settings.enabled,
+            queued: self.pending.len(),
+            running: self.running.len(),
+            by_agent,
+            by_provider,
+            queued_by_priority: by_priority,
+            completed: c.completed,
+            cancelled: c.cancelled,
+            superseded: c.superseded,
+            avg_wait_ms: avg(c.total_wait, c.started),
+            max_wait_ms: c.max_wait.as_millis() as u64,
+            avg_run_ms: avg(c.total_run, c.completed),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentQueueMetrics {
+    agent_id: String,
+    queued: usize,
+    running: usize,
+}
+
+impl AgentQueueMetrics {
+    fn new(agent_id: String) -> Self {
+        Self {
+            agent_id,
+            queued: 0,
+            running: 0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderQueueMetrics {
+    provider_id: String,
+    queued: usize,
+    running: usize,
+    /// None when only `max_concurrent` applies
+    limit: Option<usize>,
+}
+
+/// Queued (not yet running) requests per priority
+#[derive(Clone, Serialize, Debug, Default)]
+pub struct PriorityDepth {
+    high: usize,
+    normal: usize,
+    low: usize,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueMetrics {
+    enabled: bool,
+    queued: usize,
+    running: usize,
+    by_agent: Vec<AgentQueueMetrics>,
+    by_provider: Vec<ProviderQueueMetrics>,
+    queued_by_priority: PriorityDepth,
+    completed: u64,
+    cancelled: u64,
+    superseded: u64,
+    avg_wait_ms: u64,
+    max_wait_ms: u64,
+    avg_run_ms: u64,
+}
+
+pub struct InferenceQueue {
+    scheduler: Arc<Mutex<Scheduler>>,
+}
+
+/// A place in the queue. Once granted, it's a running slot that is released on drop.
+pub struct Ticket {
+    id: String,
+    scheduler: Arc<Mutex<Scheduler>>,
+    cancel: Option<watch::Receiver<bool>>,
+}
+
+impl Ticket {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Resolves when the running request is cancelled
+    pub async fn cancelled(&mut self) {
+        match self.cancel.as_mut() {
+            Some(cancel) => {
+                if cancel.wait_for(|cancelled| *cancelled).await.is_ok() {
+                    return;
+                }
+                std::future::pending().await
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.scheduler.lock().unwrap().finish(&self.id);
+    }
+}
+
+impl InferenceQueue {
+    pub fn new(settings: QueueSettings) -> Self {
+        Self {
+            scheduler: Arc::new(Mutex::new(Scheduler::new(settings))),
+        }
+    }
+
+    /// Wait for a slot on `provider_id`. Without a `priority` the agent's configured one
+    /// applies. Dropping the future gives up the place in the queue.
+    pub async fn acquire(
+        &self,
+        id: String,
+        agent_id: Option<String>,
+        provider_id: String,
+        priority: Option<Priority>,
+    ) -> Result<Ticket, QueueError> {
+        let (grant, granted) = oneshot::channel();
+        let mut ticket = Ticket {
+            id: id.clone(),
+            scheduler: self.scheduler.clone(),
+            cancel: None,
+        };
+        self.scheduler
+            .lock()
+            .unwrap()
+            .enqueue(id, agent_id, provider_id, priority, grant);
+
+        let cancel = granted.await.map_err(|_| QueueError::Cancelled)??;
+        ticket.cancel = Some(cancel);
+        Ok(ticket)
+    }
+
+    pub fn cancel(&self, id: &str) -> bool {
+        self.scheduler.lock().unwrap().cancel(id)
+    }
+
+    pub fn cancel_agent(&self, agent_id: &str) -> usize {
+        let mut scheduler = self.scheduler.lock().unwrap();
+        scheduler
+            .ids_for_agent(agent_id)
+            .iter()
+            .filter(|id| scheduler.cancel(id))
+            .count()
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        self.scheduler.lock().unwrap().metrics()
+    }
+
+    fn apply_settings(&self, settings: QueueSettings) {
+        let mut scheduler = self.scheduler.lock().unwrap();
+        scheduler.settings = settings;
+        scheduler.dispatch();
+    }
+}
+
+/// Proxy paths that run a model (and so go through the queue)
+pub fn is_inference_path(path: &str) -> bool {
+    matches!(
+        path,
+        "/api/generate" | "/api/chat" | "/v1/chat/completions" | "/v1/completions"
+    )
+}
+
+/// `GET /stats/queue`: the queue's depth and counters, as `get_inference_queue_metrics`
+pub async fn stats_handler(
+    axum::extract::State(state): axum::extract::State<crate::AppState>,
+) -> axum::Json<QueueMetrics> {
+    axum::Json(state.app_handle.state::<InferenceQueue>().metrics())
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_inference_queue_metrics(
+    queue: State<'_, InferenceQueue>,
+) -> Result<QueueMetrics, String> {
+    Ok(queue.metrics())
+}
+
+/// Cancel a request by id, or every queued/running request of an agent
+#[tauri::command]
+pub async fn inference_cancel(
+    request_id: Option<String>,
+    agent_id: Option<String>,
+    queue: State<'_, InferenceQueue>,
+) -> Result<usize, String> {
+    match (request_id, agent_id) {
+        (Some(id), _) => Ok(usize::from(queue.cancel(&id))),
+        (None, Some(agent_id)) => Ok(queue.cancel_agent(&agent_id)),
+        (None, None) => Err("Either requestId or agentId is required".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_inference_queue_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<QueueSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().inference_queue.clone())
+}
+
+#[tauri::command]
+pub async fn set_inference_queue_settings(
+    settings: QueueSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    queue: State<'_, InferenceQueue>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting inference queue settings: {:?}", settings);
+    queue.apply_settings(settings.clone());
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.inference_queue = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enqueue(s: &mut Scheduler, id: &str, agent: &str, priority: Priority) -> oneshot::Receiver<Grant> {
+        let (tx, rx) = oneshot::channel();
+        s.enqueue(id.to_string(), Some(agent.to_string()), "ollama".to_string(), Some(priority), tx);
+        rx
+    }
+
+    fn granted(rx: &mut oneshot::Receiver<Grant>) -> Option<Result<(), QueueError>> {
+        rx.try_recv().ok().map(|g| g.map(|_| ()))
+    }
+
+    #[test]
+    fn higher_priority_runs_first() {
+        let mut s = Scheduler::new(QueueSettings::default());
+        let mut a = enqueue(&mut s, "a", "agent-a", Priority::Normal);
+        let mut b = enqueue(&mut s, "b", "agent-b", Priority::Low);
+        let mut c = enqueue(&mut s, "c", "agent-c", Priority::High);
+        assert_eq!(granted(&mut a), Some(Ok(())));
+        assert_eq!(granted(&mut b), None);
+        assert_eq!(granted(&mut c), None);
+
+        s.finish("a");
+        assert_eq!(granted(&mut c), Some(Ok(())));
+        assert_eq!(granted(&mut b), None);
+        s.finish("c");
+        assert_eq!(granted(&mut b), Some(Ok(())));
+    }
+
+    #[test]
+    fn per_agent_limit_lets_other_agents_through() {
+        let mut s = Scheduler::new(QueueSettings {
+            max_concurrent: 2,
+            supersede_pending: false,
+            ..QueueSettings::default()
+        });
+        let mut a1 = enqueue(&mut s, "a1", "agent-a", Priority::High);
+        let mut a2 = enqueue(&mut s, "a2", "agent-a", Priority::High);
+        let mut b1 = enqueue(&mut s, "b1", "agent-b", Priority::Low);
+        assert_eq!(granted(&mut a1), Some(Ok(())));
+        assert_eq!(granted(&mut a2), None);
+        assert_eq!(granted(&mut b1), Some(Ok(())));
+    }
+
+    #[test]
+    fn provider_limit_holds_back_only_that_provider() {
+        let mut s = Scheduler::new(QueueSettings {
+            max_concurrent: 3,
+            per_agent_limit: 3,
+            supersede_pending: false,
+            provider_limits: HashMap::from([("ollama".to_string(), 1)]),
+            agent_priorities: HashMap::from([("interactive".to_string(), Priority::High)]),
+            ..QueueSettings::default()
+        });
+        let mut local1 = enqueue(&mut s, "l1", "agent-a", Priority::Normal);
+        let mut local2 = enqueue(&mut s, "l2", "agent-a", Priority::Normal);
+        let (tx, mut hosted) = oneshot::channel();
+        s.enqueue("h".to_string(), Some("agent-a".to_string()), "openai".to_string(), None, tx);
+        let (tx, mut chat) = oneshot::channel();
+        s.enqueue("c".to_string(), Some("interactive".to_string()), "ollama".to_string(), None, tx);
+        assert_eq!(granted(&mut local1), Some(Ok(())));
+        assert_eq!(granted(&mut local2), None);
+        assert_eq!(granted(&mut hosted), Some(Ok(())));
+        assert_eq!(granted(&mut chat), None);
+
+        let metrics = s.metrics();
+        assert_eq!(metrics.queued_by_priority.high, 1);
+        let ollama = metrics.by_provider.iter().find(|p| p.provider_id == "ollama").unwrap();
+        assert_eq!((ollama.queued, ollama.running, ollama.limit), (2, 1, Some(1)));
+
+        // The agent's configured priority puts the interactive request first
+        s.finish("l1");
+        assert_eq!(granted(&mut chat), Some(Ok(())));
+        assert_eq!(granted(&mut local2), None);
+    }
+
+    #[test]
+    fn newer_request_supersedes_queued_one() {
+        let mut s = Scheduler::new(QueueSettings::default());
+        let mut running = enqueue(&mut s, "r", "agent-a", Priority::Normal);
+        let mut old = enqueue(&mut s, "old", "agent-a", Priority::Normal);
+        let mut new = enqueue(&mut s, "new", "agent-a", Priority::Normal);
+        assert_eq!(granted(&mut running), Some(Ok(())));
+        assert_eq!(granted(&mut old), Some(Err(QueueError::Superseded)));
+        assert_eq!(granted(&mut new), None);
+        assert_eq!(s.metrics().superseded, 1);
+
+        s.finish("r");
+        assert_eq!(granted(&mut new), Some(Ok(())));
+    }
+
+    #[test]
+    fn cancels_queued_and_running_requests() {
+        let mut s = Scheduler::new(QueueSettings::default());
+        let mut a = enqueue(&mut s, "a", "agent-a", Priority::Normal);
+        let mut b = enqueue(&mut s, "b", "agent-b", Priority::Normal);
+        let cancel = match a.try_recv() {
+            Ok(Ok(cancel)) => cancel,
+            _ => panic!("first request should run"),
+        };
+
+        assert!(s.cancel("b"));
+        assert_eq!(granted(&mut b), Some(Err(QueueError::Cancelled)));
+        assert!(s.cancel("a"));
+        assert!(*cancel.borrow());
+        assert!(!s.cancel("missing"));
+    }
+}
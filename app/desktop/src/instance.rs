@@ -0,0 +1,190 @@
+// In src-tauri/src/instance.rs
+//
+// Running instances, and handing over to one. Every instance whose API server is up
+// registers itself in `<app_data_dir>/instances/<pid>.json` (URL, port, pid, version), so
+// scripts and later instances can find it whatever port it ended up on. Files of
+// processes that are gone are ignored and cleaned up.
+//
+// An instance started while another is running asks that one, over its API, to show its
+// window and then quits, instead of starting half-way next to it. With
+// `apiServer.singleInstance` off it starts anyway, on a fallback port if the usual one is
+// taken, and registers as well.
+
+use crate::api_auth::{ApiAuthState, TOKEN_HEADER};
+use crate::api_server::ServerStarted;
+use crate::shortcuts::UnifiedShortcutState;
+use crate::AppState;
+use axum::extract::State as AxumState;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const ACTIVATED_EVENT: &str = "instance-activated";
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceInfo {
+    pub pid: u32,
+    pub url: String,
+    pub port: u16,
+    pub version: String,
+    /// Unix time in milliseconds
+    pub started_at: i64,
+}
+
+fn instances_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle.path().app_data_dir().ok().map(|dir| dir.join("instances"))
+}
+
+fn is_running(pid: u32) -> bool {
+    let pid = sysinfo::Pid::from_u32(pid);
+    let mut system = sysinfo::System::new();
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).is_some()
+}
+
+/// Other instances that are still running; stale registrations are removed on the way
+pub fn discover(app_handle: &AppHandle) -> Vec<InstanceInfo> {
+    let Some(entries) = instances_dir(app_handle).and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut instances = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let info = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<InstanceInfo>(&json).ok());
+        match info {
+            Some(info) if info.pid == std::process::id() => {}
+            Some(info) if is_running(info.pid) => instances.push(info),
+            _ => {
+                log::debug!("Removing stale instance file {}", path.display());
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+    instances
+}
+
+/// Record this instance's server in the discovery directory
+pub fn register(app_handle: &AppHandle, started: &ServerStarted) {
+    let Some(dir) = instances_dir(app_handle) else {
+        return;
+    };
+    let info = InstanceInfo {
+        pid: std::process::id(),
+        url: started.url.clone(),
+        port: started.port,
+        version: app_handle.package_info().version.to_string(),
+        started_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let written = std::fs::create_dir_all(&dir).and_then(|_| {
+        let json = serde_json::to_string_pretty(&info).unwrap_or_default();
+        std::fs::write(dir.join(format!("{}.json", info.pid)), json)
+    });
+    if let Err(e) = written {
+        log::warn!("Failed to register this instance in {}: {}", dir.display(), e);
+    }
+}
+
+/// Remove this instance's registration
+pub fn unregister(app_handle: &AppHandle) {
+    if let Some(dir) = instances_dir(app_handle) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", std::process::id())));
+    }
+}
+
+async fn activate(instance: &InstanceInfo, token: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(HANDOFF_TIMEOUT)
+        // Instances on this machine may serve a self-signed certificate
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(format!("{}/instance/activate", instance.url))
+        .header(TOKEN_HEADER, token)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    match response.status().is_success() {
+        true => Ok(()),
+        false => Err(format!("HTTP {}", response.status())),
+    }
+}
+
+/// Hand over to a running instance if there is one. True when it took over and this one
+/// should quit.
+pub fn hand_off(app_handle: &AppHandle) -> bool {
+    let single_instance = app_handle
+        .state::<UnifiedShortcutState>()
+        .config
+        .lock()
+        .unwrap()
+        .api_server
+        .single_instance;
+    if !single_instance {
+        return false;
+    }
+    let instances = discover(app_handle);
+    if instances.is_empty() {
+        return false;
+    }
+    // Instances share the data directory and with it the API token
+    let token = app_handle.state::<ApiAuthState>().token();
+    tauri::async_runtime::block_on(async {
+        for instance in &instances {
+            match activate(instance, &token).await {
+                Ok(()) => {
+                    log::info!("Observer is already running (pid {}), handing over to it", instance.pid);
+                    return true;
+                }
+                Err(e) => log::warn!("Instance {} at {} didn't respond: {}", instance.pid, instance.url, e),
+            }
+        }
+        false
+    })
+}
+
+/// `POST /instance/activate`: another instance is starting; show this one instead
+pub async fn activate_handler(AxumState(state): AxumState<AppState>) -> Json<serde_json::Value> {
+    log::info!("Activated by another instance");
+    if let Some(window) = state.app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    let _ = state.app_handle.emit(ACTIVATED_EVENT, ());
+    Json(serde_json::json!({ "pid": std::process::id() }))
+}
+
+// Tauri commands
+
+/// Other running instances, from the discovery directory
+#[tauri::command]
+pub async fn list_instances(app_handle: AppHandle) -> Result<Vec<InstanceInfo>, String> {
+    Ok(discover(&app_handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registrations_round_trip() {
+        let info = InstanceInfo {
+            pid: 42,
+            url: "https://127.0.0.1:3840/observer-api".to_string(),
+            port: 3840,
+            version: "1.0.0".to_string(),
+            started_at: 1,
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains("\"startedAt\":1"));
+        assert_eq!(serde_json::from_str::<InstanceInfo>(&json).unwrap(), info);
+
+        assert!(is_running(std::process::id()));
+    }
+}
@@ -2,12 +2,58 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod annotations;
+mod api_auth;
+mod api_server;
+mod anthropic;
+mod audit;
+mod backends;
+mod benchmark;
+mod capture_api;
 mod commands;
 mod controls;
+mod digest;
+mod dnd;
+mod embeddings;
+mod events;
+mod frame_stream;
+mod grpc;
+mod health;
+mod hnsw;
+mod incognito;
+mod instance;
+mod inference;
+mod inference_cache;
+mod inference_queue;
 mod install_cli;
+mod local_socket;
+mod mdns;
+mod metrics;
 mod notifications;
+mod ocr;
+mod ollama_proxy;
 mod overlay;
+mod provider_health;
+mod providers;
+mod rate_limit;
+mod recall;
+mod redaction;
+mod remote;
+mod rtsp_server;
+mod screen_share;
+mod server_cors;
+mod server_tls;
 mod shortcuts;
+mod ssh_tunnel;
+mod storage;
+mod structured_output;
+mod tailnet;
+mod token_usage;
+mod tools;
+mod transcription;
+mod usage;
+mod vector_store;
+mod webrtc_stream;
 
 // Import unified shortcut types (desktop only)
 use shortcuts::UnifiedShortcutState;
@@ -22,6 +68,7 @@ use axum::{
     Router,
 };
 use futures::future::join_all;
+use futures::StreamExt;
 
 use http_body_util::BodyExt;
 
@@ -35,7 +82,7 @@ static DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
 static GENERATION_CANCELLED: AtomicBool = AtomicBool::new(false);
 
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem},
     tray::TrayIconBuilder,
     WebviewUrl, WebviewWindowBuilder,
 };
@@ -44,15 +91,15 @@ use tauri_plugin_dialog::DialogExt;
 
 use tauri_plugin_updater::UpdaterExt;
 
-use tower_http::{
-    cors::{Any, CorsLayer},
-    services::ServeDir,
-};
+use tower_http::services::ServeDir;
 
 struct AppSettings {
     ollama_url: Mutex<Option<String>>,
 }
 
+/// Id of the system tray icon, used to look it up for badge/tooltip updates
+const TRAY_ID: &str = "main";
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct OverlayMessage {
     id: String,
@@ -217,6 +264,13 @@ async fn clear_overlay_messages(
 #[tauri::command]
 async fn show_overlay(app_handle: tauri::AppHandle) -> Result<(), String> {
     log::info!("Showing overlay window");
+    if app_handle
+        .state::<screen_share::ScreenShareState>()
+        .defer_overlay_show()
+    {
+        log::info!("Screen sharing in progress, overlay will be shown when it ends");
+        return Ok(());
+    }
     if let Some(window) = app_handle.get_webview_window("overlay") {
         window.show().map_err(|e| e.to_string())?;
         // Re-enable click-through after showing
@@ -339,20 +393,48 @@ async fn report_target_selection_error(
 // = separate `audio` module).
 // ============================================================================
 
+/// Start a video stream. Without `session_id` this (re)starts the default session;
+/// with one, the stream runs alongside the others. With `binary`, frames arrive as raw
+/// payloads instead of JSON (see the plugin's `wire` module); with `delta`, only the
+/// tiles changed since the last keyframe are sent (see `delta`); with `yuv`, raw NV12 /
+/// I420 planes (see `yuv`). `config` overrides the capture defaults for this stream only.
+/// Returns the session id.
 #[tauri::command]
 async fn sc_start_video_stream(
     target_id: Option<String>,
-    on_frame: Channel<tauri_plugin_screen_capture::desktop::FrameData>,
-) -> Result<(), String> {
-    tauri_plugin_screen_capture::desktop::start_capture_stream(target_id, on_frame)
-        .map_err(|e| e.to_string())
+    session_id: Option<String>,
+    config: Option<tauri_plugin_screen_capture::capture_config::CaptureConfig>,
+    binary: Option<bool>,
+    delta: Option<tauri_plugin_screen_capture::delta::DeltaConfig>,
+    yuv: Option<tauri_plugin_screen_capture::yuv::YuvOutput>,
+    on_frame: Channel<tauri::ipc::Response>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    use tauri_plugin_screen_capture::{sessions, wire::FrameSink};
+
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    let sink = FrameSink::new(on_frame, binary.unwrap_or(false)).with_config(config).with_delta(delta).with_yuv(yuv);
+    tauri_plugin_screen_capture::desktop::start_capture_session(&session_id, target_id.clone(), sink)
+        .map_err(|e| e.to_string())?;
+    events::publish(
+        &app_handle,
+        events::ApiEvent::CaptureStarted { session_id: session_id.clone(), target_id },
+    );
+    Ok(session_id)
 }
 
 #[cfg(target_os = "macos")]
 #[tauri::command]
 async fn sc_start_audio_stream(
     on_audio: Channel<tauri_plugin_screen_capture::desktop::AudioData>,
+    app_handle: AppHandle,
 ) -> Result<(), String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
     tauri_plugin_screen_capture::desktop::start_audio_stream(on_audio).map_err(|e| e.to_string())
 }
 
@@ -360,17 +442,469 @@ async fn sc_start_audio_stream(
 #[tauri::command]
 async fn sc_start_audio_stream(
     on_audio: Channel<tauri_plugin_screen_capture::audio::AudioData>,
+    app_handle: AppHandle,
 ) -> Result<(), String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
     tauri_plugin_screen_capture::audio::start_audio_stream(on_audio).map_err(|e| e.to_string())
 }
 
+/// Capture system output audio on its own, as PCM or Opus chunks in the requested rate
+/// and channels (see the audio-capture plugin). Returns once the output device is open.
+#[tauri::command]
+async fn audio_start_loopback(
+    config: Option<tauri_plugin_audio_capture::pipeline::AudioStreamConfig>,
+    on_chunk: Channel<tauri_plugin_audio_capture::pipeline::AudioChunk>,
+    app_handle: AppHandle,
+) -> Result<tauri_plugin_audio_capture::capture::AudioCaptureStatus, String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        tauri_plugin_audio_capture::capture::start(config.unwrap_or_default(), on_chunk)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn audio_stop_loopback() -> Result<bool, String> {
+    Ok(tauri_plugin_audio_capture::capture::stop(tauri_plugin_audio_capture::capture::SourceKind::Loopback))
+}
+
+#[tauri::command]
+async fn audio_get_loopback_status() -> Result<tauri_plugin_audio_capture::capture::AudioCaptureStatus, String> {
+    Ok(tauri_plugin_audio_capture::capture::status(tauri_plugin_audio_capture::capture::SourceKind::Loopback))
+}
+
+/// List microphones and other input devices
+#[tauri::command]
+async fn audio_get_devices() -> Result<Vec<tauri_plugin_audio_capture::mic::AudioDevice>, String> {
+    tauri::async_runtime::spawn_blocking(tauri_plugin_audio_capture::mic::list_devices)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Record a microphone (the default input unless `device_id` is given) as PCM or Opus
+/// chunks, like `audio_start_loopback`. Returns once the device is open.
+#[tauri::command]
+async fn audio_start_microphone(
+    device_id: Option<String>,
+    config: Option<tauri_plugin_audio_capture::pipeline::AudioStreamConfig>,
+    on_chunk: Channel<tauri_plugin_audio_capture::pipeline::AudioChunk>,
+    app_handle: AppHandle,
+) -> Result<tauri_plugin_audio_capture::capture::AudioCaptureStatus, String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        tauri_plugin_audio_capture::capture::start_microphone(device_id, config.unwrap_or_default(), on_chunk)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn audio_stop_microphone() -> Result<bool, String> {
+    Ok(tauri_plugin_audio_capture::capture::stop(tauri_plugin_audio_capture::capture::SourceKind::Microphone))
+}
+
+#[tauri::command]
+async fn audio_get_microphone_status() -> Result<tauri_plugin_audio_capture::capture::AudioCaptureStatus, String> {
+    Ok(tauri_plugin_audio_capture::capture::status(tauri_plugin_audio_capture::capture::SourceKind::Microphone))
+}
+
+/// Detect speech on the microphone (or system audio), emitting `audio-capture://speech`
+/// events. With `speech_fps`, running capture sessions go to that rate while someone
+/// talks and back to their own when they stop; the capture defaults are left alone.
+#[tauri::command]
+async fn audio_start_vad(
+    config: Option<tauri_plugin_audio_capture::vad::VadConfig>,
+    speech_fps: Option<u32>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    use tauri_plugin_audio_capture::vad::{self, SpeechEventKind, SpeechHook};
+    use tauri_plugin_screen_capture::backpressure;
+
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let hook = speech_fps.map(|fps| {
+        let hook: SpeechHook = Box::new(move |event| {
+            let fps = (event.kind == SpeechEventKind::SpeechStarted).then_some(fps);
+            backpressure::set_fps(None, fps);
+        });
+        hook
+    });
+    tauri::async_runtime::spawn_blocking(move || vad::start(config.unwrap_or_default(), hook))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn audio_stop_vad() -> Result<bool, String> {
+    let stopped = tauri_plugin_audio_capture::vad::stop();
+    // Stopping mid-sentence sends no speech end; don't leave sessions at the speech rate
+    tauri_plugin_screen_capture::backpressure::set_fps(None, None);
+    Ok(stopped)
+}
+
+/// Emit `idle://user-idle` / `idle://user-active` at the config's threshold. With
+/// `pause_capture`, screen capture is held while the user is idle.
+#[tauri::command]
+async fn idle_start_monitor(
+    config: Option<tauri_plugin_idle::monitor::IdleConfig>,
+    pause_capture: Option<bool>,
+) -> Result<(), String> {
+    use tauri_plugin_idle::monitor::{self, IdleEventKind, IdleHook};
+    use tauri_plugin_screen_capture::pause::{self, PauseReason};
+
+    let hook = pause_capture.unwrap_or(false).then(|| {
+        let hook: IdleHook =
+            Box::new(|event| pause::set(PauseReason::UserIdle, event.kind == IdleEventKind::UserIdle));
+        hook
+    });
+    tauri::async_runtime::spawn_blocking(move || monitor::start(config.unwrap_or_default(), hook))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn idle_stop_monitor() -> Result<bool, String> {
+    Ok(tauri_plugin_idle::monitor::stop())
+}
+
+/// Emit `clipboard-monitor://changed` for every copy (text, and images if asked for)
+#[tauri::command]
+async fn clipboard_start_monitor(
+    config: Option<tauri_plugin_clipboard_monitor::monitor::ClipboardMonitorConfig>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Clipboard monitoring is disabled while incognito mode is on".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        tauri_plugin_clipboard_monitor::monitor::start(config.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clipboard_stop_monitor() -> Result<bool, String> {
+    Ok(tauri_plugin_clipboard_monitor::monitor::stop())
+}
+
+/// Stop one video session (the default one unless `session_id` is given)
+#[tauri::command]
+async fn sc_stop_video(session_id: Option<String>, app_handle: AppHandle) -> Result<(), String> {
+    use tauri_plugin_screen_capture::sessions;
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    tauri_plugin_screen_capture::desktop::stop_capture_session(&session_id).map_err(|e| e.to_string())?;
+    events::publish(&app_handle, events::ApiEvent::CaptureStopped { session_id: Some(session_id) });
+    Ok(())
+}
+
+/// Point a running video session at another target without restarting it; the frontend
+/// gets a `screen-capture://target-switched` event once frames come from the new target.
+/// `active-window` / `app:{name}` make the session follow the focused window / that app
+#[tauri::command]
+async fn sc_switch_capture_target(
+    target_id: Option<String>,
+    session_id: Option<String>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    use tauri_plugin_screen_capture::sessions;
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    tauri_plugin_screen_capture::desktop::switch_capture_target(sessions::resolve(session_id.as_deref()), target_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Record a target (primary monitor if omitted) to an MP4 at `path`. The recording runs
+/// as its own capture session alongside any streams; `options` can add system and
+/// microphone audio tracks, and must set `overwrite` to replace an existing file.
+#[tauri::command]
+async fn sc_start_recording(
+    target_id: Option<String>,
+    path: String,
+    options: Option<tauri_plugin_screen_capture::recording::RecordingOptions>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    tauri_plugin_screen_capture::recording::start(target_id, path.into(), options.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Write the last `seconds` of captured frames (the whole replay buffer if omitted) to
+/// `path` as an MP4 or a directory of images; defaults to `replays` in the app data dir
+#[tauri::command]
+async fn sc_export_replay(
+    seconds: Option<f64>,
+    path: Option<String>,
+    format: Option<tauri_plugin_screen_capture::replay::ReplayFormat>,
+) -> Result<tauri_plugin_screen_capture::recording::RecordingSummary, String> {
+    tauri_plugin_screen_capture::replay::export(seconds, path.map(Into::into), format.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Write the last `seconds` of captured frames as a looping GIF; defaults to `replays` in the
+/// app data dir
+#[tauri::command]
+async fn sc_export_gif(
+    seconds: Option<f64>,
+    path: Option<String>,
+    options: Option<tauri_plugin_screen_capture::replay::GifOptions>,
+) -> Result<tauri_plugin_screen_capture::recording::RecordingSummary, String> {
+    tauri_plugin_screen_capture::replay::export_gif(seconds, path.map(Into::into), options.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Finish the running recording; returns the file path and duration
+#[tauri::command]
+async fn sc_stop_recording() -> Result<tauri_plugin_screen_capture::recording::RecordingSummary, String> {
+    tauri_plugin_screen_capture::recording::stop().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_list_capture_sessions() -> Result<Vec<tauri_plugin_screen_capture::sessions::SessionInfo>, String> {
+    Ok(tauri_plugin_screen_capture::desktop::list_capture_sessions())
+}
+
+/// Acknowledge frames the frontend has drawn, so a stalled webview gets frames dropped
+/// instead of queued
+#[tauri::command]
+async fn sc_ack_frames(session_id: Option<String>, frame_count: u64) -> Result<(), String> {
+    use tauri_plugin_screen_capture::{backpressure, sessions};
+    backpressure::ack(sessions::resolve(session_id.as_deref()), frame_count);
+    Ok(())
+}
+
+#[tauri::command]
+async fn sc_get_capture_stats(
+    session_id: Option<String>,
+) -> Result<Vec<tauri_plugin_screen_capture::stats::CaptureStats>, String> {
+    Ok(tauri_plugin_screen_capture::backpressure::stats(session_id.as_deref()))
+}
+
+/// Screen-recording permission status: granted, denied or prompt
+#[tauri::command]
+async fn sc_check_capture_permission() -> Result<tauri_plugin_screen_capture::permission::PermissionStatus, String> {
+    Ok(tauri_plugin_screen_capture::permission::check())
+}
+
+/// Show the OS permission prompt (or its settings page) and return the status afterwards
+#[tauri::command]
+async fn sc_request_capture_permission() -> Result<tauri_plugin_screen_capture::permission::PermissionStatus, String> {
+    tauri::async_runtime::spawn_blocking(tauri_plugin_screen_capture::permission::request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_list_capture_profiles() -> Result<Vec<tauri_plugin_screen_capture::profiles::CaptureProfile>, String> {
+    tauri_plugin_screen_capture::profiles::list().map_err(|e| e.to_string())
+}
+
+/// Save a named capture profile (target, capture config, crop), replacing one with the
+/// same name
+#[tauri::command]
+async fn sc_save_capture_profile(
+    profile: tauri_plugin_screen_capture::profiles::CaptureProfile,
+) -> Result<tauri_plugin_screen_capture::profiles::CaptureProfile, String> {
+    tauri_plugin_screen_capture::profiles::save(profile).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_delete_capture_profile(name: String) -> Result<bool, String> {
+    tauri_plugin_screen_capture::profiles::delete(&name).map_err(|e| e.to_string())
+}
+
+/// Start a video session from a saved profile; returns the session id
+#[tauri::command]
+async fn sc_start_capture_profile(
+    name: String,
+    session_id: Option<String>,
+    binary: Option<bool>,
+    on_frame: Channel<tauri::ipc::Response>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    use tauri_plugin_screen_capture::{profiles, sessions, wire::FrameSink};
+
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    let sink = FrameSink::new(on_frame, binary.unwrap_or(false));
+    profiles::start(&name, &session_id, sink).map_err(|e| e.to_string())?;
+    Ok(session_id)
+}
+
+/// Capture windows matching a title/app regex automatically while they are open; the
+/// rule's session streams to `on_frame`
+#[tauri::command]
+async fn sc_add_watch_rule(
+    rule: tauri_plugin_screen_capture::watch_rules::WatchRule,
+    binary: Option<bool>,
+    on_frame: Channel<tauri::ipc::Response>,
+    app_handle: AppHandle,
+) -> Result<tauri_plugin_screen_capture::watch_rules::WatchRuleStatus, String> {
+    use tauri_plugin_screen_capture::{watch_rules, wire::FrameSink};
+
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    watch_rules::add(rule, FrameSink::new(on_frame, binary.unwrap_or(false))).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_remove_watch_rule(id: String) -> Result<bool, String> {
+    Ok(tauri_plugin_screen_capture::watch_rules::remove(&id))
+}
+
+/// Burn a timestamp and/or label into frames, for one session or (without
+/// `session_id`) all of them
+#[tauri::command]
+async fn sc_set_frame_overlay(
+    overlay: Option<tauri_plugin_screen_capture::overlay::FrameOverlay>,
+    session_id: Option<String>,
+) -> Result<(), String> {
+    tauri_plugin_screen_capture::overlay::set(session_id.as_deref(), overlay);
+    Ok(())
+}
+
+/// Replace the blurred/blacked-out regions of a target ("*" for all targets)
+#[tauri::command]
+async fn sc_set_privacy_regions(
+    target_id: String,
+    regions: Vec<tauri_plugin_screen_capture::privacy::PrivacyRegion>,
+) -> Result<(), String> {
+    tauri_plugin_screen_capture::privacy::set(&target_id, regions).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_list_privacy_regions(
+) -> Result<std::collections::BTreeMap<String, Vec<tauri_plugin_screen_capture::privacy::PrivacyRegion>>, String> {
+    Ok(tauri_plugin_screen_capture::privacy::list())
+}
+
+/// Replace the apps whose windows are sent as "redacted" placeholder frames
+#[tauri::command]
+async fn sc_set_redacted_apps(apps: Vec<String>) -> Result<Vec<String>, String> {
+    tauri_plugin_screen_capture::redaction::set(apps).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_get_redacted_apps() -> Result<Vec<String>, String> {
+    Ok(tauri_plugin_screen_capture::redaction::list())
+}
+
+/// One full-resolution screenshot (PNG by default), returned as base64 or written to
+/// `path`
+#[tauri::command]
+async fn sc_capture_screenshot(
+    target_id: Option<String>,
+    format: Option<tauri_plugin_screen_capture::capture_config::FrameEncoding>,
+    path: Option<String>,
+    app_handle: AppHandle,
+) -> Result<tauri_plugin_screen_capture::screenshot::Screenshot, String> {
+    use tauri_plugin_screen_capture::{capture_config::FrameEncoding, screenshot};
+
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let format = format.unwrap_or(FrameEncoding::Png);
+    tauri::async_runtime::spawn_blocking(move || {
+        screenshot::capture(target_id.as_deref(), format, path.as_deref().map(std::path::Path::new))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// How similar two encoded images are (dHash distance and SSIM), for deciding whether
+/// a new frame is worth a model call
 #[tauri::command]
-async fn sc_stop_video() -> Result<(), String> {
-    tauri_plugin_screen_capture::desktop::stop_capture()
+async fn sc_compare_frames(
+    a: Vec<u8>,
+    b: Vec<u8>,
+) -> Result<tauri_plugin_screen_capture::similarity::Similarity, String> {
+    tauri::async_runtime::spawn_blocking(move || tauri_plugin_screen_capture::similarity::compare(&a, &b))
         .await
+        .map_err(|e| e.to_string())?
         .map_err(|e| e.to_string())
 }
 
+/// QR codes and barcodes in `image` (PNG/JPEG bytes), or in the running stream's next
+/// frame when no image is given
+#[tauri::command]
+async fn sc_detect_codes(
+    image: Option<Vec<u8>>,
+    app_handle: AppHandle,
+) -> Result<tauri_plugin_screen_capture::codes::CodeScan, String> {
+    use tauri_plugin_screen_capture::codes;
+
+    match image {
+        Some(image) => tauri::async_runtime::spawn_blocking(move || codes::scan_image(&image))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string()),
+        None => {
+            if incognito::is_active(&app_handle) {
+                return Err("Capture is disabled while incognito mode is on".to_string());
+            }
+            codes::scan_latest_frame().await.map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Luminance, dominant colors and dark/light guess of a session's latest frame (None =
+/// the default session)
+#[tauri::command]
+async fn sc_get_frame_stats(
+    session_id: Option<String>,
+) -> Result<Option<tauri_plugin_screen_capture::frame_stats::FrameStats>, String> {
+    use tauri_plugin_screen_capture::{frame_stats, sessions};
+    Ok(frame_stats::latest(session_id.as_deref().unwrap_or(sessions::DEFAULT_SESSION)))
+}
+
+/// Write a full-resolution snapshot every N seconds to a directory, with rotation
+#[tauri::command]
+async fn sc_start_snapshots(
+    config: tauri_plugin_screen_capture::snapshots::SnapshotConfig,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    tauri_plugin_screen_capture::snapshots::start(config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sc_stop_snapshots() -> Result<bool, String> {
+    Ok(tauri_plugin_screen_capture::snapshots::stop())
+}
+
+#[tauri::command]
+async fn sc_get_snapshot_status() -> Result<Option<tauri_plugin_screen_capture::snapshots::SnapshotStatus>, String> {
+    Ok(tauri_plugin_screen_capture::snapshots::status())
+}
+
+#[tauri::command]
+async fn sc_list_watch_rules() -> Result<Vec<tauri_plugin_screen_capture::watch_rules::WatchRuleStatus>, String> {
+    Ok(tauri_plugin_screen_capture::watch_rules::list())
+}
+
 #[cfg(target_os = "macos")]
 #[tauri::command]
 async fn sc_stop_audio() -> Result<(), String> {
@@ -384,8 +918,8 @@ async fn sc_stop_audio() -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn sc_stop_capture() -> Result<(), String> {
-    // Stop audio (best-effort) then video, mirroring the plugin's stop_capture_cmd.
+async fn sc_stop_capture(app_handle: AppHandle) -> Result<(), String> {
+    // Stop audio (best-effort) then every video session, mirroring the plugin's stop_capture_cmd.
     #[cfg(target_os = "macos")]
     {
         let _ = tauri_plugin_screen_capture::desktop::stop_audio();
@@ -394,9 +928,9 @@ async fn sc_stop_capture() -> Result<(), String> {
     {
         let _ = tauri_plugin_screen_capture::audio::stop_audio();
     }
-    tauri_plugin_screen_capture::desktop::stop_capture()
-        .await
-        .map_err(|e| e.to_string())
+    tauri_plugin_screen_capture::desktop::stop_all_sessions().map_err(|e| e.to_string())?;
+    events::publish(&app_handle, events::ApiEvent::CaptureStopped { session_id: None });
+    Ok(())
 }
 
 #[tauri::command]
@@ -407,18 +941,134 @@ async fn sc_get_capture_targets(
         .map_err(|e| e.to_string())
 }
 
-/// Set the runtime capture quality knobs. Pushed by the frontend before capture starts;
-/// read when the stream is (re)built, so a change takes effect on the next capture start.
+/// Push refreshed thumbnails of every target about once a second while the picker is
+/// open; stop with `sc_stop_target_preview`
 #[tauri::command]
-async fn sc_set_capture_config(
-    max_width: u32,
-    jpeg_quality: u8,
-    fps: u32,
+async fn sc_start_target_preview(
+    on_update: Channel<Vec<tauri_plugin_screen_capture::thumbnails::Thumbnail>>,
+    app_handle: AppHandle,
 ) -> Result<(), String> {
-    tauri_plugin_screen_capture::capture_config::set(max_width, jpeg_quality, fps);
+    if incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    tauri_plugin_screen_capture::thumbnails::start_preview(on_update);
     Ok(())
 }
 
+#[tauri::command]
+async fn sc_stop_target_preview() -> Result<(), String> {
+    tauri_plugin_screen_capture::thumbnails::stop_preview();
+    Ok(())
+}
+
+/// Set the runtime capture quality defaults; unset fields keep their value. Applies to
+/// running streams (except for fields a stream set in its own `config`) on Windows/Linux
+/// and on the next capture start on macOS (see capture_config.rs). Returns the defaults
+/// now in effect.
+#[tauri::command]
+async fn sc_set_capture_config(
+    max_width: Option<u32>,
+    jpeg_quality: Option<u8>,
+    fps: Option<u32>,
+    encoding: Option<tauri_plugin_screen_capture::capture_config::FrameEncoding>,
+    show_cursor: Option<bool>,
+    backend: Option<tauri_plugin_screen_capture::capture_config::CaptureBackend>,
+    replay_seconds: Option<u32>,
+    target_lost: Option<tauri_plugin_screen_capture::capture_config::TargetLostPolicy>,
+    grayscale: Option<bool>,
+    adaptive_fps: Option<bool>,
+    max_capture_failures: Option<u32>,
+    error_fallback: Option<bool>,
+    frame_stats: Option<bool>,
+) -> Result<tauri_plugin_screen_capture::capture_config::CaptureConfig, String> {
+    use tauri_plugin_screen_capture::capture_config::{self, CaptureConfig};
+    Ok(capture_config::update(&CaptureConfig {
+        max_width,
+        jpeg_quality,
+        fps,
+        encoding,
+        show_cursor,
+        backend,
+        replay_seconds,
+        target_lost,
+        grayscale,
+        adaptive_fps,
+        max_capture_failures,
+        error_fallback,
+        frame_stats,
+    }))
+}
+
+#[tauri::command]
+async fn sc_get_capture_config() -> Result<tauri_plugin_screen_capture::capture_config::CaptureConfig, String> {
+    Ok(tauri_plugin_screen_capture::capture_config::get())
+}
+
+/// Windows (`window:<id>` target ids) to leave out of display captures on macOS.
+/// Replaces the previous list; takes effect the next time capture starts.
+#[tauri::command]
+async fn sc_set_excluded_windows(target_ids: Vec<String>) -> Result<(), String> {
+    tauri_plugin_screen_capture::exclusions::set(&target_ids).map_err(|e| e.to_string())
+}
+
+/// Hide Observer's own windows from the target list and, on macOS, from monitor
+/// captures (on by default)
+#[tauri::command]
+async fn sc_set_exclude_own_windows(exclude: bool) -> Result<(), String> {
+    tauri_plugin_screen_capture::exclusions::set_exclude_self(exclude);
+    Ok(())
+}
+
+/// Toggle password-field / secure-input detection. While it is on (the default), frames
+/// are skipped whenever a password field has focus.
+#[tauri::command]
+async fn sc_set_secure_input_detection(enabled: bool) -> Result<(), String> {
+    tauri_plugin_screen_capture::secure_input::set_enabled(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+async fn sc_get_secure_input_status() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "enabled": tauri_plugin_screen_capture::secure_input::is_enabled(),
+        "holding": tauri_plugin_screen_capture::secure_input::is_holding(),
+    }))
+}
+
+/// Geometry of the frames currently being streamed (source rect, scale factor, crop) by
+/// the default session, or by `session_id`
+#[tauri::command]
+async fn sc_get_frame_geometry(
+    session_id: Option<String>,
+) -> Result<Option<tauri_plugin_screen_capture::geometry::FrameGeometry>, String> {
+    use tauri_plugin_screen_capture::{geometry, sessions};
+    Ok(geometry::for_session(sessions::resolve(session_id.as_deref())))
+}
+
+/// Convert a point between frame, screen and window coordinates. Uses the active capture's
+/// geometry unless one is passed (e.g. the geometry recorded alongside an older frame).
+#[tauri::command]
+async fn sc_map_point(
+    x: f64,
+    y: f64,
+    from: tauri_plugin_screen_capture::geometry::CoordSpace,
+    to: tauri_plugin_screen_capture::geometry::CoordSpace,
+    geometry: Option<tauri_plugin_screen_capture::geometry::FrameGeometry>,
+) -> Result<serde_json::Value, String> {
+    use tauri_plugin_screen_capture::geometry::{self, CoordSpace};
+
+    let geometry = geometry
+        .or_else(geometry::current)
+        .ok_or("No active screen capture")?;
+    let (mapped_x, mapped_y) = geometry.map(x, y, from, to);
+    let (frame_x, frame_y) = geometry.map(x, y, from, CoordSpace::Frame);
+    Ok(serde_json::json!({
+        "x": mapped_x,
+        "y": mapped_y,
+        "onFrame": geometry.contains_frame_point(frame_x, frame_y),
+    }))
+}
+
 // Shortcut commands moved to shortcuts module
 
 // Shortcut helper functions moved to shortcuts module
@@ -1064,6 +1714,26 @@ async fn llm_set_use_gpu(use_gpu: bool) -> Result<(), String> {
     })
 }
 
+/// Pin the in-app engine to one GPU (index from get_backend_utilization devices),
+/// or split across all GPUs with None. Must be called before loading a model to take effect
+#[tauri::command]
+async fn llm_set_main_gpu(main_gpu: Option<u32>) -> Result<(), String> {
+    use tauri_plugin_llm_engine::with_engine;
+
+    with_engine(|engine| {
+        engine.set_main_gpu(main_gpu);
+        Ok(())
+    })
+}
+
+/// Get the GPU the in-app engine is pinned to
+#[tauri::command]
+async fn llm_get_main_gpu() -> Result<Option<u32>, String> {
+    use tauri_plugin_llm_engine::with_engine;
+
+    with_engine(|engine| Ok(engine.get_main_gpu()))
+}
+
 /// Get whether GPU acceleration is enabled
 #[tauri::command]
 async fn llm_get_use_gpu() -> Result<bool, String> {
@@ -1098,7 +1768,6 @@ fn get_memory_info() -> MemoryInfo {
 #[derive(Clone)]
 struct AppState {
     app_handle: AppHandle,
-    http_client: Client,
 }
 
 async fn proxy_handler(
@@ -1109,7 +1778,15 @@ async fn proxy_handler(
     body: Body,
 ) -> Result<Response, StatusCode> {
     let path = uri.path();
-    let query = uri.query().unwrap_or("");
+    let query = api_auth::strip_query(uri.query().unwrap_or(""));
+
+    let agent_id = headers
+        .get("x-observer-agent-id")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    // Agents can be pinned to a specific local backend and GPU
+    let route = backends::route_for(&state.app_handle, agent_id.as_deref());
 
     let target_url = {
         // This whole block will evaluate to a single String value.
@@ -1117,9 +1794,20 @@ async fn proxy_handler(
         let settings = state.app_handle.state::<AppSettings>();
         let ollama_url_guard = settings.ollama_url.lock().unwrap();
 
-        let base_url = ollama_url_guard
-            .as_deref()
-            .unwrap_or("http://127.0.0.1:11434");
+        // A pinned backend wins; then an enabled SSH tunnel for Ollama; then the configured URL
+        let pinned_url = route.as_ref().and_then(|r| r.base_url.clone());
+        let base_url = match (pinned_url, ssh_tunnel::local_url(&state.app_handle, "ollama")) {
+            (Some(pinned_url), _) => tailnet::resolve(&state.app_handle, &pinned_url),
+            (None, Some(tunnel_url)) => tunnel_url,
+            (None, None) => tailnet::resolve(
+                &state.app_handle,
+                &ollama_proxy::normalize_upstream(
+                    ollama_url_guard
+                        .as_deref()
+                        .unwrap_or("http://127.0.0.1:11434"),
+                ),
+            ),
+        };
 
         // 2. This is the last line. With no semicolon, its value is "returned"
         //    from the block and assigned to `target_url`.
@@ -1130,23 +1818,120 @@ async fn proxy_handler(
 
     let body_bytes = match body.collect().await {
         Ok(collected) => collected.to_bytes(),
+        Err(e) if rate_limit::too_large(&e) => return Err(StatusCode::PAYLOAD_TOO_LARGE),
         Err(e) => {
             log::error!("Failed to collect request body: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
+    // Ollama takes the device per request
+    let body_bytes = match route.as_ref() {
+        Some(backends::Route {
+            kind: backends::BackendKind::Ollama,
+            device: Some(device),
+            ..
+        }) if matches!(path, "/api/generate" | "/api/chat") => {
+            match backends::pin_device(&body_bytes, *device) {
+                Some(pinned) => axum::body::Bytes::from(pinned),
+                None => body_bytes,
+            }
+        }
+        _ => body_bytes,
+    };
+
+    // Agents over their daily token budget get no more model calls
+    let is_inference = inference_queue::is_inference_path(path);
+    if is_inference {
+        if let Err(e) = token_usage::check_budget(&state.app_handle, agent_id.as_deref()) {
+            log::info!("Inference request not run: {}", e);
+            return Ok(e.into_response());
+        }
+    }
+
+    // Model calls wait for a slot in the inference queue; other API calls pass straight through
+    let mut ticket = if is_inference {
+        let request_id = headers
+            .get("x-observer-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let priority = headers
+            .get("x-observer-priority")
+            .and_then(|v| v.to_str().ok())
+            .map(inference_queue::Priority::from_header);
+        let queue = state.app_handle.state::<inference_queue::InferenceQueue>();
+        let provider_id = providers::DEFAULT_PROVIDER_ID.to_string();
+        match queue.acquire(request_id, agent_id.clone(), provider_id, priority).await {
+            Ok(ticket) => Some(ticket),
+            Err(e) => {
+                log::info!("Inference request for {:?} not run: {}", agent_id, e);
+                return Ok(e.into_response());
+            }
+        }
+    } else {
+        None
+    };
+
+    // Mask PII before anything leaves the machine
+    let (redacted_body, redactions) = redaction::redact_request_body(
+        &state.app_handle.state::<redaction::RedactionState>(),
+        &body_bytes,
+        agent_id.as_deref(),
+    );
+    let body_bytes = match redacted_body {
+        Some(redacted) => {
+            log::info!("Redacted outbound prompt: {:?}", redactions);
+            axum::body::Bytes::from(redacted)
+        }
+        None => body_bytes,
+    };
+
+    // Record what is about to leave the machine
+    let summary = audit::summarize_payload(&body_bytes);
+    let audit_id = audit::record_outbound(
+        &state.app_handle,
+        &audit::OutboundRecord {
+            kind: audit::OutboundKind::Llm,
+            destination: target_url.clone(),
+            method: method.to_string(),
+            model: summary.model,
+            agent_id: agent_id.clone(),
+            request_bytes: body_bytes.len() as u64,
+            image_hashes: summary.image_hashes,
+            prompt: summary.prompt,
+            redactions,
+        },
+    );
+
     // Strip Origin header before forwarding - Ollama rejects non-web origins like tauri://localhost
     let mut forwarded_headers = headers.clone();
     forwarded_headers.remove(axum::http::header::ORIGIN);
+    // The body may have been rewritten by redaction; let reqwest recompute the length
+    forwarded_headers.remove(axum::http::header::CONTENT_LENGTH);
+    api_auth::strip_headers(&mut forwarded_headers);
+    // Credentials for an Ollama behind an authenticating reverse proxy; pinned backends are other servers
+    if route.as_ref().and_then(|r| r.base_url.as_ref()).is_none() {
+        forwarded_headers.extend(providers::ollama_auth_headers(&state.app_handle));
+    }
 
-    let reqwest_request = state
-        .http_client
+    // Trusts the self-signed certificate configured for Ollama, if any
+    let reqwest_request = ollama_proxy::client(&state.app_handle)
         .request(method, &target_url)
         .headers(forwarded_headers)
         .body(body_bytes);
 
-    match reqwest_request.send().await {
+    let upstream = match ticket.as_mut() {
+        Some(ticket) => tokio::select! {
+            response = reqwest_request.send() => response,
+            _ = ticket.cancelled() => {
+                return Ok(inference_queue::QueueError::Cancelled.into_response());
+            }
+        },
+        None => reqwest_request.send().await,
+    };
+
+    match upstream {
         Ok(upstream_response) => {
             let mut response_builder = Response::builder()
                 .status(upstream_response.status())
@@ -1154,20 +1939,67 @@ async fn proxy_handler(
 
             if let Some(headers) = response_builder.headers_mut() {
                 headers.extend(upstream_response.headers().clone());
+                if let Some(value) = ticket
+                    .as_ref()
+                    .and_then(|t| axum::http::HeaderValue::from_str(t.id()).ok())
+                {
+                    headers.insert("x-observer-request-id", value);
+                }
             }
 
-            let response_stream = upstream_response.bytes_stream();
-            let response_body = Body::from_stream(response_stream);
+            // Response size is only known once the stream finishes
+            let mut tally = audit_id.map(|id| {
+                audit::record_response(&state.app_handle, id, Some(upstream_response.status().as_u16()), 0);
+                audit::ResponseTally::new(state.app_handle.clone(), id)
+            });
+            // Token counts come with the end of a successful model reply
+            let mut tokens = (is_inference && upstream_response.status().is_success()).then(|| {
+                token_usage::TokenTally::new(
+                    state.app_handle.clone(),
+                    agent_id,
+                    providers::DEFAULT_PROVIDER_ID.to_string(),
+                )
+            });
+            let response_stream = upstream_response.bytes_stream().map(move |chunk| {
+                if let (Some(tally), Ok(bytes)) = (tally.as_mut(), &chunk) {
+                    tally.add(bytes.len());
+                }
+                if let (Some(tokens), Ok(bytes)) = (tokens.as_mut(), &chunk) {
+                    tokens.add(bytes);
+                }
+                chunk
+            });
+            // The queue slot is held until the response has streamed out (or is cancelled)
+            let response_body = match ticket {
+                Some(mut ticket) => Body::from_stream(
+                    response_stream.take_until(async move { ticket.cancelled().await }),
+                ),
+                None => Body::from_stream(response_stream),
+            };
 
             Ok(response_builder.body(response_body).unwrap())
         }
         Err(e) => {
             log::error!("Proxy request to Ollama failed: {}", e);
+            if let Some(id) = audit_id {
+                audit::record_response(&state.app_handle, id, Some(StatusCode::BAD_GATEWAY.as_u16()), 0);
+            }
             Err(StatusCode::BAD_GATEWAY)
         }
     }
 }
 
+/// `/v1/ollama/<path>`: `<path>` on Ollama, for a frontend that only talks to its own origin
+async fn ollama_proxy_handler(
+    state: AxumState<AppState>,
+    method: Method,
+    headers: HeaderMap,
+    uri: Uri,
+    body: Body,
+) -> Result<Response, StatusCode> {
+    proxy_handler(state, method, headers, ollama_proxy::strip_prefix(&uri), body).await
+}
+
 #[derive(Clone)]
 struct ServerUrl(String);
 
@@ -1180,9 +2012,34 @@ fn get_server_url(server_url: State<Mutex<ServerUrl>>) -> String {
 fn start_static_server(app_handle: tauri::AppHandle) {
     let rt = tokio::runtime::Runtime::new().unwrap();
     rt.block_on(async {
-        const SERVER_PORT: u16 = 3838;
-        let url = format!("http://127.0.0.1:{}", SERVER_PORT);
-        let addr_str = url.replace("http://", "");
+        let settings = app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().api_server.clone();
+        let tls = match settings.tls.enabled {
+            true => match server_tls::config(&app_handle, &settings).await {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    log::error!("Web server not started, TLS unavailable: {}", e);
+                    return;
+                }
+            },
+            false => None,
+        };
+        let (listener, started) = match api_server::bind(&settings).await {
+            Ok(bound) => bound,
+            Err(e) => {
+                log::error!("Web server not started: {}", e);
+                // Say so rather than carry on as if agents could reach the API
+                app_handle
+                    .dialog()
+                    .message(format!("Observer's local API server could not start:\n{}", e))
+                    .title("Observer")
+                    .kind(tauri_plugin_dialog::MessageDialogKind::Error)
+                    .show(|_| {});
+                return;
+            }
+        };
+        let url = started.url.clone();
+        instance::register(&app_handle, &started);
+        mdns::advertise(&app_handle, &settings, &started);
 
         let server_url_state = app_handle.state::<Mutex<ServerUrl>>();
         *server_url_state.lock().unwrap() = ServerUrl(url.clone());
@@ -1195,27 +2052,33 @@ fn start_static_server(app_handle: tauri::AppHandle) {
 
         log::info!("Serving static files from: {:?}", resource_path);
 
-        let cors = CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any);
+        let cors = server_cors::layer(&settings.cors, &started);
 
         let state = AppState {
             app_handle: app_handle.clone(),
-            http_client: Client::new(),
         };
 
         let app = Router::new()
+            .route(
+                &format!("{}/*path", ollama_proxy::PATH_PREFIX),
+                any(ollama_proxy_handler),
+            )
             .route("/v1/*path", any(proxy_handler))
             .route("/api/*path", any(proxy_handler))
+            // Take precedence over the Ollama proxy's /api/*path
+            .route("/api/capture/targets", axum::routing::get(capture_api::targets_handler))
+            .route("/api/capture/start", axum::routing::post(capture_api::start_handler))
+            .route("/api/capture/stop", axum::routing::post(capture_api::stop_handler))
+            .route("/api/capture/screenshot", axum::routing::get(capture_api::screenshot_handler))
             .route("/ask", axum::routing::post(notifications::ask_handler))
-            .route(
-                "/ping",
-                axum::routing::get(|| async {
-                    log::info!("==== PING-PONG ====");
-                    "pong"
-                }),
-            )
+            .route("/stats/queue", axum::routing::get(inference_queue::stats_handler))
+            .route("/metrics", axum::routing::get(metrics::metrics_handler))
+            .route("/healthz", axum::routing::get(health::healthz_handler))
+            .route("/webrtc/offer", axum::routing::post(webrtc_stream::offer_handler))
+            .route("/webrtc/:session_id", axum::routing::delete(webrtc_stream::hang_up_handler))
+            .route("/ws/frames", axum::routing::get(frame_stream::frames_ws_handler))
+            .route("/events", axum::routing::get(events::events_handler))
+            .route("/instance/activate", axum::routing::post(instance::activate_handler))
             .route(
                 "/message",
                 axum::routing::post(notifications::message_handler),
@@ -1225,6 +2088,14 @@ fn start_static_server(app_handle: tauri::AppHandle) {
                 axum::routing::post(notifications::notification_handler),
             )
             .route("/overlay", axum::routing::post(overlay::overlay_handler))
+            .route(
+                "/overlay/boxes",
+                axum::routing::post(annotations::boxes_handler),
+            )
+            .route(
+                "/overlay/draw",
+                axum::routing::post(annotations::draw_handler),
+            )
             .route("/click", axum::routing::post(controls::click_handler))
             .route(
                 "/commands-stream",
@@ -1239,26 +2110,59 @@ fn start_static_server(app_handle: tauri::AppHandle) {
                 "/commands",
                 axum::routing::post(commands::post_commands_handler),
             )
+            // Everything above needs the API token; the routes below and the UI files don't
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                api_auth::require_token,
+            ))
+            // Before the token check, so guessing tokens is rate limited too
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                rate_limit::limit_requests,
+            ))
+            // Outside the token check, so refused requests are counted too
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                metrics::track_requests,
+            ))
+            // rate_limit caps bodies instead, per route
+            .layer(axum::extract::DefaultBodyLimit::disable())
+            .route(
+                "/ping",
+                axum::routing::get(|| async {
+                    log::info!("==== PING-PONG ====");
+                    "pong"
+                }),
+            )
             .fallback_service(ServeDir::new(resource_path))
             .with_state(state)
             .layer(cors);
+        // Behind a reverse proxy that keeps its sub-path in the URL. The root keeps working
+        // for the app's own windows, which talk to the server directly.
+        let app = match started.base_path.as_str() {
+            "" => app,
+            base_path => Router::new().nest_service(base_path, app.clone()).fallback_service(app),
+        };
 
-        let listener = tokio::net::TcpListener::bind(&addr_str).await;
-
-        match listener {
-            Ok(l) => {
-                log::info!("Web server listening on {}", url);
-                if let Err(e) = axum::serve(l, app.into_make_service()).await {
-                    log::error!("Server error: {}", e);
-                }
-            }
-            Err(e) => {
-                log::error!(
-                    "FATAL: Failed to bind to address {}. Is another instance running? Error: {}",
-                    addr_str,
-                    e
-                );
-            }
+        // Same routes on a Unix socket / named pipe, if enabled
+        tokio::spawn(local_socket::serve(app_handle.clone(), settings.local_socket.clone(), app.clone()));
+
+        log::info!("Web server listening on {} ({}:{})", url, started.host, started.port);
+        let _ = app_handle.emit(api_server::STARTED_EVENT, &started);
+        let served = match tls {
+            Some(config) => match listener.into_std() {
+                Ok(listener) => axum_server::from_tcp_rustls(listener, config)
+                    .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            None => axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .await
+                .map_err(|e| e.to_string()),
+        };
+        if let Err(e) = served {
+            log::error!("Server error: {}", e);
         }
     });
 }
@@ -1273,7 +2177,14 @@ pub fn run() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_screen_capture::init());
+        .plugin(tauri_plugin_screen_capture::init())
+        .plugin(tauri_plugin_audio_capture::init())
+        .plugin(tauri_plugin_idle::init())
+        .plugin(tauri_plugin_clipboard_monitor::init());
+    #[cfg(feature = "input-sim")]
+    {
+        builder = builder.plugin(tauri_plugin_input_sim::init());
+    }
 
     // Updater
     let builder = {
@@ -1300,6 +2211,7 @@ pub fn run() {
                 app.manage(OverlayState {
                     messages: Mutex::new(Vec::new()),
                 });
+                app.manage(annotations::AnnotationState::new());
 
                 app.manage({
                     let (tx, _rx) = broadcast::channel(100); // Buffer up to 100 commands
@@ -1315,6 +2227,111 @@ pub fn run() {
                 registered_shortcuts: Mutex::new(Vec::new()),
             });
 
+            // Granted here rather than in capabilities/, which can't name a plugin that
+            // isn't compiled in
+            #[cfg(feature = "input-sim")]
+            app.add_capability(
+                tauri::ipc::CapabilityBuilder::new("input-sim")
+                    .window("main")
+                    .permission("input-sim:default"),
+            )?;
+
+            // Do Not Disturb tracking for notification routing
+            app.manage(dnd::DndState::new());
+            dnd::start_dnd_monitor(app.handle().clone());
+
+            // Local log of everything sent to model providers and webhooks
+            app.manage(audit::AuditState::new(app.handle()));
+
+            // PII redaction applied to prompts before they reach a provider
+            let redaction_settings = app
+                .state::<UnifiedShortcutState>()
+                .config
+                .lock()
+                .unwrap()
+                .redaction
+                .clone();
+            app.manage(redaction::RedactionState::new(&redaction_settings));
+
+            // OCR language packs live in <app_data_dir>/tessdata
+            ocr::init(app.handle());
+
+            // Privacy kill switch, toggled from the tray, a global shortcut or the UI
+            app.manage(incognito::IncognitoState::new());
+
+            // Hide the overlay (and optionally hold capture) while presenting
+            app.manage(screen_share::ScreenShareState::new());
+            screen_share::start_screen_share_monitor(app.handle().clone());
+
+            // Local app usage statistics (sampler is a no-op until enabled in settings)
+            app.manage(usage::UsageState::new(app.handle()));
+            usage::start_usage_tracker(app.handle().clone());
+
+            // Daily digest export of agent summaries
+            app.manage(digest::DigestState::new(app.handle()));
+            digest::start_digest_scheduler(app.handle().clone());
+
+            // Shared queue for model calls going through the proxy
+            let queue_settings = app
+                .state::<UnifiedShortcutState>()
+                .config
+                .lock()
+                .unwrap()
+                .inference_queue
+                .clone();
+            app.manage(inference_queue::InferenceQueue::new(queue_settings));
+
+            // Client for Ollama traffic, trusting a self-signed certificate if configured
+            let ollama_proxy_settings = app
+                .state::<UnifiedShortcutState>()
+                .config
+                .lock()
+                .unwrap()
+                .ollama_proxy
+                .clone();
+            app.manage(ollama_proxy::OllamaProxyState::new(&ollama_proxy_settings));
+
+            // Circuit breakers for providers that keep failing
+            app.manage(provider_health::ProviderHealth::new());
+
+            // Optional cache of model replies to repeated requests
+            let cache_settings = app
+                .state::<UnifiedShortcutState>()
+                .config
+                .lock()
+                .unwrap()
+                .inference_cache
+                .clone();
+            app.manage(inference_cache::InferenceCache::new(cache_settings));
+
+            // Daily token totals per agent and provider, and per-agent budgets
+            app.manage(token_usage::TokenUsageState::new(app.handle()));
+            app.manage(api_auth::ApiAuthState::new(app.handle()));
+            // Focus an instance that is already running rather than start next to it
+            #[cfg(not(debug_assertions))]
+            if instance::hand_off(app.handle()) {
+                std::process::exit(0);
+            }
+            app.manage(events::EventBus::default());
+            app.manage(metrics::Metrics::default());
+            app.manage(rate_limit::RateLimiter::default());
+            app.manage(webrtc_stream::WebRtcState::default());
+            app.manage(mdns::MdnsState::default());
+            app.manage(vector_store::VectorStore::new(app.handle()));
+            app.manage(recall::RecallState::default());
+
+            // Remote observer link (sender or receiver, off by default)
+            app.manage(remote::RemoteState::new(app.handle()));
+            remote::apply_settings(app.handle());
+
+            // SSH tunnels to model servers behind SSH
+            app.manage(ssh_tunnel::SshTunnelState::new());
+            ssh_tunnel::apply_settings(app.handle());
+
+            // Route provider/link endpoints over Tailscale when the LAN address is unreachable
+            app.manage(tailnet::TailnetState::new());
+            tailnet::start_resolver(app.handle().clone());
+
             // We use the handle to call updater and restart
             {
                 let handle = app.handle().clone();
@@ -1408,6 +2425,12 @@ pub fn run() {
                 });
             }
 
+            // RTSP camera output
+            tauri::async_runtime::spawn(rtsp_server::serve(app.handle().clone()));
+
+            // gRPC API
+            tauri::async_runtime::spawn(grpc::serve(app.handle().clone()));
+
             #[cfg(debug_assertions)]
             {
                 let server_url_state = app.state::<Mutex<ServerUrl>>();
@@ -1420,16 +2443,27 @@ pub fn run() {
                 let menu_handle = app.handle();
 
                 let show = MenuItem::with_id(menu_handle, "show", "Show Launcher", true, None::<&str>)?;
+                let incognito_item = CheckMenuItem::with_id(
+                    menu_handle,
+                    incognito::TRAY_MENU_ID,
+                    "Incognito (pause everything)",
+                    true,
+                    false,
+                    None::<&str>,
+                )?;
                 let quit = MenuItem::with_id(menu_handle, "quit", "Quit", true, None::<&str>)?;
-                let menu = Menu::with_items(menu_handle, &[&show, &quit])?;
+                let menu = Menu::with_items(menu_handle, &[&show, &incognito_item, &quit])?;
+                app.state::<incognito::IncognitoState>()
+                    .set_tray_item(incognito_item.clone());
 
-                let _tray = TrayIconBuilder::new()
+                let _tray = TrayIconBuilder::with_id(TRAY_ID)
                     .tooltip("Observer AI is running")
                     .icon(app.default_window_icon().cloned().unwrap())
                     .menu(&menu)
                     .on_menu_event(move |app, event| match event.id.as_ref() {
                         "quit" => {
                             log::info!("Exit called");
+                            instance::unregister(app);
                             app.exit(0);
                         }
                         "show" => {
@@ -1438,6 +2472,7 @@ pub fn run() {
                                 window.set_focus().unwrap();
                             }
                         }
+                        incognito::TRAY_MENU_ID => incognito::toggle_incognito(app),
                         _ => {}
                     })
                     .build(app)?;
@@ -1480,6 +2515,10 @@ pub fn run() {
                 }
             }
 
+            // Full-monitor, click-through window for shapes agents draw on screen
+            annotations::create_window(app);
+            annotations::start_expiry_sweeper(app.handle().clone());
+
             // The screen selector window is defined statically in tauri.conf.json
             // (label "screen-selector", hidden by default). It must NOT be created
             // at runtime here: on Linux, capabilities are not reliably applied to
@@ -1527,6 +2566,11 @@ pub fn run() {
             clear_overlay_messages,
             show_overlay,
             hide_overlay,
+            annotations::show_overlay_boxes,
+            annotations::draw_overlay,
+            annotations::get_overlay_annotations,
+            annotations::remove_overlay_annotation,
+            annotations::clear_overlay_annotations,
             get_broadcast_status,
             await_target_selection,
             submit_target_selection,
@@ -1534,14 +2578,179 @@ pub fn run() {
             report_target_selection_error,
             sc_start_video_stream,
             sc_start_audio_stream,
+            audio_start_loopback,
+            audio_stop_loopback,
+            audio_get_loopback_status,
+            audio_get_devices,
+            audio_start_microphone,
+            audio_stop_microphone,
+            audio_get_microphone_status,
+            audio_start_vad,
+            audio_stop_vad,
+            idle_start_monitor,
+            idle_stop_monitor,
+            clipboard_start_monitor,
+            clipboard_stop_monitor,
             sc_stop_video,
+            sc_list_capture_sessions,
+            sc_ack_frames,
+            sc_get_capture_stats,
+            sc_check_capture_permission,
+            sc_request_capture_permission,
+            sc_list_capture_profiles,
+            sc_save_capture_profile,
+            sc_delete_capture_profile,
+            sc_start_capture_profile,
+            sc_add_watch_rule,
+            sc_remove_watch_rule,
+            sc_list_watch_rules,
+            sc_set_frame_overlay,
+            sc_set_privacy_regions,
+            sc_list_privacy_regions,
+            sc_set_redacted_apps,
+            sc_get_redacted_apps,
+            sc_capture_screenshot,
+            sc_compare_frames,
+            sc_detect_codes,
+            sc_get_frame_stats,
+            sc_start_snapshots,
+            sc_stop_snapshots,
+            sc_get_snapshot_status,
+            sc_switch_capture_target,
+            sc_start_recording,
+            sc_stop_recording,
+            sc_export_replay,
+            sc_export_gif,
             sc_stop_audio,
             sc_stop_capture,
             sc_get_capture_targets,
+            sc_start_target_preview,
+            sc_stop_target_preview,
             sc_set_capture_config,
+            sc_set_excluded_windows,
+            sc_set_exclude_own_windows,
+            sc_get_capture_config,
+            sc_set_secure_input_detection,
+            sc_get_secure_input_status,
+            sc_get_frame_geometry,
+            sc_map_point,
             shortcuts::get_shortcut_config,
             shortcuts::get_registered_shortcuts,
             shortcuts::set_shortcut_config,
+            dnd::get_dnd_status,
+            dnd::get_notification_settings,
+            dnd::set_notification_settings,
+            dnd::flush_held_notifications,
+            audit::audit_record_outbound,
+            audit::audit_record_response,
+            audit::audit_query,
+            audit::audit_clear,
+            redaction::redact_text,
+            redaction::get_redaction_settings,
+            redaction::set_redaction_settings,
+            ocr::ocr_list_languages,
+            ocr::ocr_download_language,
+            ocr::ocr_delete_language,
+            ocr::ocr_image,
+            ocr::ocr_frame,
+            ocr::ocr_start_text_watch,
+            ocr::ocr_stop_text_watch,
+            ocr::get_ocr_settings,
+            ocr::set_ocr_settings,
+            transcription::transcription_list_models,
+            transcription::transcription_start,
+            transcription::transcription_stop,
+            incognito::get_incognito,
+            incognito::set_incognito_mode,
+            screen_share::get_screen_share_status,
+            screen_share::get_screen_share_settings,
+            screen_share::set_screen_share_settings,
+            usage::usage_top_apps,
+            usage::usage_daily_trend,
+            usage::usage_category_totals,
+            usage::usage_set_category,
+            usage::usage_get_category,
+            usage::usage_clear,
+            usage::get_usage_settings,
+            usage::set_usage_settings,
+            digest::digest_add_entry,
+            digest::digest_get_entries,
+            digest::digest_export_now,
+            digest::get_digest_settings,
+            digest::set_digest_settings,
+            remote::get_remote_settings,
+            remote::set_remote_settings,
+            remote::get_remote_status,
+            remote::remote_start_pairing,
+            remote::remote_pair,
+            remote::remote_unpair,
+            remote::remote_subscribe_frames,
+            remote::remote_unsubscribe_frames,
+            ssh_tunnel::get_ssh_tunnel_settings,
+            ssh_tunnel::set_ssh_tunnel_settings,
+            ssh_tunnel::get_ssh_tunnel_status,
+            ssh_tunnel::ssh_tunnel_reset_host_key,
+            tailnet::get_tailnet_settings,
+            tailnet::set_tailnet_settings,
+            tailnet::tailnet_diagnostics,
+            inference_queue::get_inference_queue_metrics,
+            inference_queue::inference_cancel,
+            inference_queue::get_inference_queue_settings,
+            inference_queue::set_inference_queue_settings,
+            backends::get_backend_settings,
+            backends::set_backend_settings,
+            backends::set_agent_backend,
+            backends::get_backend_utilization,
+            providers::get_providers,
+            providers::save_provider,
+            providers::remove_provider,
+            providers::set_agent_provider,
+            providers::list_provider_models,
+            providers::get_ollama_auth,
+            providers::set_ollama_auth,
+            providers::get_fallback_chain,
+            providers::set_fallback_chain,
+            providers::get_provider_policies,
+            providers::set_provider_policy,
+            provider_health::get_provider_health,
+            provider_health::reset_provider_health,
+            inference_cache::get_inference_cache_stats,
+            inference_cache::clear_inference_cache,
+            inference_cache::get_inference_cache_settings,
+            inference_cache::set_inference_cache_settings,
+            token_usage::get_usage_cmd,
+            token_usage::get_token_budgets,
+            token_usage::set_agent_token_budget,
+            token_usage::clear_token_usage,
+            tools::list_tools,
+            tools::get_tool_settings,
+            tools::set_tool_settings,
+            events::publish_agent_event,
+            instance::list_instances,
+            api_auth::get_api_token,
+            api_auth::rotate_api_token,
+            api_auth::get_api_auth_settings,
+            api_auth::set_api_auth_settings,
+            api_server::get_api_server_settings,
+            api_server::set_api_server_settings,
+            server_tls::get_tls_info,
+            server_tls::regenerate_tls_certificate,
+            rtsp_server::get_rtsp_settings,
+            rtsp_server::set_rtsp_settings,
+            embeddings::get_embedding_settings,
+            embeddings::set_embedding_settings,
+            vector_store::embed_and_store_cmd,
+            vector_store::similarity_search_cmd,
+            vector_store::list_vector_collections,
+            vector_store::delete_vector_collection,
+            recall::record_observation,
+            recall::get_recall_settings,
+            recall::set_recall_settings,
+            inference::inference_chat,
+            inference::inference_chat_stream,
+            ollama_proxy::get_ollama_proxy_settings,
+            ollama_proxy::set_ollama_proxy_settings,
+            benchmark::run_latency_benchmark,
             // LLM commands
             llm_list_gguf,
             llm_download_model,
@@ -1560,6 +2769,8 @@ pub fn run() {
             llm_set_sampler_params,
             llm_set_use_gpu,
             llm_get_use_gpu,
+            llm_set_main_gpu,
+            llm_get_main_gpu,
             llm_get_context_params,
             llm_set_context_params,
             get_memory_info,
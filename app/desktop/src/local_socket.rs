@@ -0,0 +1,155 @@
+// In src-tauri/src/local_socket.rs
+//
+// The API over a Unix domain socket (macOS, Linux) or a named pipe (Windows), next to
+// TCP, so local tools can reach it without any network port:
+//
+//   curl --unix-socket ~/.local/share/<app>/api.sock -H "x-observer-token: ..." http://observer/ping
+//
+// Off by default. `apiServer.localSocket.enabled` (or OBSERVER_API_SOCKET, which also sets
+// the path) turns it on; the socket lives at `<app_data_dir>/api.sock`, the pipe at
+// `\\.\pipe\observer-api`, unless `path` says otherwise. The socket file is only
+// accessible to the current user, and requests need the API token like over TCP.
+
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+const SOCKET_ENV: &str = "OBSERVER_API_SOCKET";
+
+#[cfg(windows)]
+const DEFAULT_PIPE: &str = r"\\.\pipe\observer-api";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalSocketSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Socket file or pipe name; None for the default
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Where to listen, if the local socket is on. The environment variable wins.
+pub fn path(app_handle: &AppHandle, settings: &LocalSocketSettings) -> Option<String> {
+    if let Some(path) = std::env::var(SOCKET_ENV).ok().filter(|path| !path.trim().is_empty()) {
+        return Some(path);
+    }
+    if !settings.enabled {
+        return None;
+    }
+    if let Some(path) = settings.path.clone().filter(|path| !path.trim().is_empty()) {
+        return Some(path);
+    }
+    #[cfg(windows)]
+    {
+        let _ = app_handle;
+        Some(DEFAULT_PIPE.to_string())
+    }
+    #[cfg(not(windows))]
+    {
+        use tauri::Manager;
+        let dir = app_handle.path().app_data_dir().ok()?;
+        Some(dir.join("api.sock").to_string_lossy().into_owned())
+    }
+}
+
+async fn serve_connection<IO>(io: IO, router: Router)
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = TowerToHyperService::new(router);
+    if let Err(e) = auto::Builder::new(TokioExecutor::new())
+        .serve_connection_with_upgrades(TokioIo::new(io), service)
+        .await
+    {
+        log::debug!("Local socket connection ended: {}", e);
+    }
+}
+
+#[cfg(unix)]
+async fn listen(path: &str, router: Router) -> Result<(), String> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    let path = std::path::Path::new(path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    // A socket left behind by an earlier run; anything else at the path is not ours to remove
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(format!("{} exists and is not a socket", path.display()));
+        }
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    let listener = tokio::net::UnixListener::bind(path).map_err(|e| e.to_string())?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    log::info!("API also listening on {}", path.display());
+    loop {
+        let (stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
+        tokio::spawn(serve_connection(stream, router.clone()));
+    }
+}
+
+#[cfg(windows)]
+async fn listen(path: &str, router: Router) -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    // Fails if another process already owns the name
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(path)
+        .map_err(|e| e.to_string())?;
+    log::info!("API also listening on {}", path);
+    loop {
+        server.connect().await.map_err(|e| e.to_string())?;
+        // The next client needs a fresh pipe instance
+        let next = ServerOptions::new().create(path).map_err(|e| e.to_string())?;
+        let connected = std::mem::replace(&mut server, next);
+        tokio::spawn(serve_connection(connected, router.clone()));
+    }
+}
+
+/// Serve `router` on the local socket until the app exits; nothing when it is off
+pub async fn serve(app_handle: AppHandle, settings: LocalSocketSettings, router: Router) {
+    let Some(path) = path(&app_handle, &settings) else {
+        return;
+    };
+    if let Err(e) = listen(&path, router).await {
+        log::error!("API local socket {} stopped: {}", path, e);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn serves_http_over_a_unix_socket() {
+        let dir = std::env::temp_dir().join(format!("observer-socket-test-{}", std::process::id()));
+        let path = dir.join("api.sock");
+        let router = Router::new().route("/ping", axum::routing::get(|| async { "pong" }));
+        let server_path = path.to_string_lossy().into_owned();
+        tokio::spawn(async move { listen(&server_path, router).await });
+
+        let mut stream = loop {
+            match tokio::net::UnixStream::connect(&path).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        stream
+            .write_all(b"GET /ping HTTP/1.1\r\nHost: observer\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("pong"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
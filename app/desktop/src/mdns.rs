@@ -0,0 +1,103 @@
+// In src-tauri/src/mdns.rs
+//
+// Announces the API server on the LAN over mDNS / DNS-SD as `_observer._tcp`, so the
+// mobile app or a browser on another device can find this machine without typing an IP
+// and port. The TXT record carries `tls` (`1` for HTTPS), `path` (the base path) and the
+// app `version`.
+//
+// Only a server that listens beyond loopback (host 0.0.0.0, ::, or a LAN address) is
+// announced, and `apiServer.mdns` turns it off altogether.
+
+use crate::api_server::{ApiServerSettings, ServerStarted};
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub const SERVICE_TYPE: &str = "_observer._tcp.local.";
+
+/// Keeps the responder running for as long as the app does
+#[derive(Default)]
+pub struct MdnsState {
+    daemon: Mutex<Option<ServiceDaemon>>,
+}
+
+/// Whether other devices could reach a server bound to `host`
+fn reachable_from_lan(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => !ip.is_loopback(),
+        Err(_) => host != "localhost",
+    }
+}
+
+/// A DNS label from the machine's name, e.g. `Ana's MacBook` -> `Ana-s-MacBook`
+fn host_label(name: &str) -> String {
+    let label: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect();
+    match label.trim_matches('-') {
+        "" => "observer".to_string(),
+        label => label.to_string(),
+    }
+}
+
+fn txt_properties(started: &ServerStarted, version: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("tls", if started.tls { "1" } else { "0" }.to_string()),
+        ("path", started.base_path.clone()),
+        ("version", version.to_string()),
+    ]
+}
+
+/// Announce the server that just started, if it is reachable from the LAN
+pub fn advertise(app_handle: &AppHandle, settings: &ApiServerSettings, started: &ServerStarted) {
+    if !settings.mdns || !reachable_from_lan(&started.host) {
+        return;
+    }
+    let name = sysinfo::System::host_name().unwrap_or_else(|| "Observer".to_string());
+    let host_name = format!("{}.local.", host_label(&name));
+    let version = app_handle.package_info().version.to_string();
+    let properties = txt_properties(started, &version);
+    let properties: Vec<(&str, &str)> = properties.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+    let registered = ServiceDaemon::new().and_then(|daemon| {
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &format!("Observer on {}", name),
+            &host_name,
+            "",
+            started.port,
+            &properties[..],
+        )?
+        // Announce every address of this machine, following changes
+        .enable_addr_auto();
+        daemon.register(info)?;
+        Ok(daemon)
+    });
+    match registered {
+        Ok(daemon) => {
+            log::info!("Announcing the API server on the LAN as {} (port {})", SERVICE_TYPE, started.port);
+            *app_handle.state::<MdnsState>().daemon.lock().unwrap() = Some(daemon);
+        }
+        Err(e) => log::warn!("Failed to announce the API server over mDNS: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_only_lan_reachable_servers() {
+        assert!(reachable_from_lan("0.0.0.0"));
+        assert!(reachable_from_lan("[::]"));
+        assert!(reachable_from_lan("192.168.1.5"));
+        assert!(!reachable_from_lan("127.0.0.1"));
+        assert!(!reachable_from_lan("::1"));
+        assert!(!reachable_from_lan("localhost"));
+
+        assert_eq!(host_label("Ana's MacBook"), "Ana-s-MacBook");
+        assert_eq!(host_label("???"), "observer");
+    }
+}
@@ -0,0 +1,260 @@
+// In src-tauri/src/metrics.rs
+//
+// Prometheus metrics at `GET /metrics`, so self-hosters can watch Observer in Grafana:
+//
+// - capture: fps, frames and bytes sent, dropped frames and encode times per session
+// - inference: requests by provider and outcome, and their latency (backend inference;
+//   proxied model calls show up under the API request metrics)
+// - agents: runs by agent and outcome, as the frontend reports them
+// - API: requests by method, route and status, and their latency
+//
+// The endpoint needs the API token like the rest of the API; give it to Prometheus as
+// `authorization: { credentials: <token> }` in the scrape config. Counters start at zero
+// with the app; capture counters with their session. The latest request to each provider
+// and the latest agent run are kept as well, for `/healthz`.
+
+use crate::AppState;
+use axum::extract::{MatchedPath, Request, State as AxumState};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_screen_capture::backpressure;
+use tauri_plugin_screen_capture::stats::{CaptureStats, Histogram};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Inference latency buckets, in seconds; model calls take far longer than frames
+const INFERENCE_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// API request latency buckets, in seconds
+const REQUEST_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0];
+
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "error"
+    }
+}
+
+/// The latest backend inference request to a provider
+#[derive(Clone, Copy, Debug)]
+pub struct LastInference {
+    pub latency: Duration,
+    pub success: bool,
+    /// Unix time in milliseconds
+    pub at: i64,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// (provider, outcome) -> requests
+    inference_requests: BTreeMap<(String, &'static str), u64>,
+    inference_latency: BTreeMap<String, Histogram>,
+    last_inference: BTreeMap<String, LastInference>,
+    /// (agent, outcome) -> runs
+    agent_runs: BTreeMap<(String, &'static str), u64>,
+    /// Unix time in milliseconds
+    last_agent_run: Option<i64>,
+    /// (method, route, status) -> requests
+    api_requests: BTreeMap<(String, String, u16), u64>,
+    api_latency: BTreeMap<String, Histogram>,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+fn with_registry(app_handle: &AppHandle, update: impl FnOnce(&mut Registry)) {
+    if let Some(metrics) = app_handle.try_state::<Metrics>() {
+        update(&mut metrics.registry.lock().unwrap());
+    }
+}
+
+/// A backend inference request to `provider_id` finished after `took`
+pub fn record_inference(app_handle: &AppHandle, provider_id: &str, took: Duration, success: bool) {
+    with_registry(app_handle, |registry| {
+        *registry.inference_requests.entry((provider_id.to_string(), outcome(success))).or_default() += 1;
+        registry
+            .inference_latency
+            .entry(provider_id.to_string())
+            .or_insert_with(|| Histogram::new(INFERENCE_BUCKETS))
+            .observe(took);
+        let last = LastInference { latency: took, success, at: chrono::Utc::now().timestamp_millis() };
+        registry.last_inference.insert(provider_id.to_string(), last);
+    });
+}
+
+/// An agent run finished
+pub fn record_agent_run(app_handle: &AppHandle, agent_id: &str, success: bool) {
+    with_registry(app_handle, |registry| {
+        *registry.agent_runs.entry((agent_id.to_string(), outcome(success))).or_default() += 1;
+        registry.last_agent_run = Some(chrono::Utc::now().timestamp_millis());
+    });
+}
+
+pub fn last_inference(app_handle: &AppHandle, provider_id: &str) -> Option<LastInference> {
+    let mut last = None;
+    with_registry(app_handle, |registry| last = registry.last_inference.get(provider_id).copied());
+    last
+}
+
+/// When an agent run last finished, in Unix milliseconds
+pub fn last_agent_run(app_handle: &AppHandle) -> Option<i64> {
+    let mut last = None;
+    with_registry(app_handle, |registry| last = registry.last_agent_run);
+    last
+}
+
+/// Middleware counting API requests by the route they matched (the pattern, like
+/// `/v1/*path`, so paths don't each get their own series)
+pub async fn track_requests(AxumState(state): AxumState<AppState>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => "unmatched".to_string(),
+    };
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let took = started.elapsed();
+    let status = response.status().as_u16();
+    with_registry(&state.app_handle, |registry| {
+        *registry.api_requests.entry((method, route.clone(), status)).or_default() += 1;
+        registry
+            .api_latency
+            .entry(route)
+            .or_insert_with(|| Histogram::new(REQUEST_BUCKETS))
+            .observe(took);
+    });
+    response
+}
+
+/// Label value escaped for the text format
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let pairs: Vec<String> = pairs.iter().map(|(name, value)| format!("{}=\"{}\"", name, escape(value))).collect();
+    pairs.join(",")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    let _ = match labels {
+        "" => writeln!(out, "{} {}", name, value),
+        labels => writeln!(out, "{}{{{}}} {}", name, labels, value),
+    };
+}
+
+fn histogram(out: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    let prefix = match labels {
+        "" => String::new(),
+        labels => format!("{},", labels),
+    };
+    for (bound, count) in histogram.bounds.iter().zip(&histogram.counts) {
+        sample(out, &format!("{}_bucket", name), &format!("{}le=\"{}\"", prefix, bound), count);
+    }
+    sample(out, &format!("{}_bucket", name), &format!("{}le=\"+Inf\"", prefix), histogram.count);
+    sample(out, &format!("{}_sum", name), labels, histogram.sum);
+    sample(out, &format!("{}_count", name), labels, histogram.count);
+}
+
+fn render_registry(out: &mut String, registry: &Registry) {
+    header(out, "observer_inference_requests_total", "counter", "Backend inference requests by provider");
+    for ((provider, outcome), count) in &registry.inference_requests {
+        sample(
+            out,
+            "observer_inference_requests_total",
+            &labels(&[("provider", provider), ("outcome", outcome)]),
+            count,
+        );
+    }
+    header(out, "observer_inference_duration_seconds", "histogram", "Backend inference latency by provider");
+    for (provider, latency) in &registry.inference_latency {
+        histogram(out, "observer_inference_duration_seconds", &labels(&[("provider", provider)]), latency);
+    }
+
+    header(out, "observer_agent_runs_total", "counter", "Finished agent runs");
+    for ((agent, outcome), count) in &registry.agent_runs {
+        sample(out, "observer_agent_runs_total", &labels(&[("agent", agent), ("outcome", outcome)]), count);
+    }
+
+    header(out, "observer_api_requests_total", "counter", "API server requests by route");
+    for ((method, route, status), count) in &registry.api_requests {
+        let status = status.to_string();
+        let labels = labels(&[("method", method), ("route", route), ("status", &status)]);
+        sample(out, "observer_api_requests_total", &labels, count);
+    }
+    header(out, "observer_api_request_duration_seconds", "histogram", "API server latency by route");
+    for (route, latency) in &registry.api_latency {
+        histogram(out, "observer_api_request_duration_seconds", &labels(&[("route", route)]), latency);
+    }
+}
+
+/// Name, type, help and value of a per-session capture series
+type CaptureSeries = (&'static str, &'static str, &'static str, fn(&CaptureStats) -> f64);
+
+fn render_capture(out: &mut String) {
+    let sessions = backpressure::stats(None);
+    let series: [CaptureSeries; 4] = [
+        ("observer_capture_fps", "gauge", "Frames sent per second", |s| s.rates.fps),
+        ("observer_capture_frames_sent_total", "counter", "Frames sent", |s| s.frames_sent as f64),
+        ("observer_capture_bytes_sent_total", "counter", "Encoded bytes sent", |s| s.bytes_sent as f64),
+        ("observer_capture_dropped_frames_total", "counter", "Frames dropped", |s| s.dropped_frames as f64),
+    ];
+    for (name, kind, help, value) in series {
+        header(out, name, kind, help);
+        for session in &sessions {
+            sample(out, name, &labels(&[("session", &session.session_id)]), value(session));
+        }
+    }
+    header(out, "observer_capture_encode_seconds", "histogram", "Frame encode times");
+    for session in &sessions {
+        let labels = labels(&[("session", &session.session_id)]);
+        histogram(out, "observer_capture_encode_seconds", &labels, &session.encode_times);
+    }
+}
+
+/// `GET /metrics`: everything above in the Prometheus text format
+pub async fn metrics_handler(AxumState(state): AxumState<AppState>) -> Response {
+    let mut out = String::new();
+    render_capture(&mut out);
+    with_registry(&state.app_handle, |registry| render_registry(&mut out, registry));
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], out).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_text_format() {
+        let mut registry = Registry::default();
+        registry.inference_requests.insert(("ollama".to_string(), "success"), 3);
+        let mut latency = Histogram::new(INFERENCE_BUCKETS);
+        latency.observe(Duration::from_millis(300));
+        registry.inference_latency.insert("ollama".to_string(), latency);
+        registry.agent_runs.insert(("say \"hi\"".to_string(), "error"), 1);
+
+        let mut out = String::new();
+        render_registry(&mut out, &registry);
+        assert!(out.contains("# TYPE observer_inference_requests_total counter\n"));
+        assert!(out.contains("observer_inference_requests_total{provider=\"ollama\",outcome=\"success\"} 3\n"));
+        assert!(out.contains("observer_inference_duration_seconds_bucket{provider=\"ollama\",le=\"0.25\"} 0\n"));
+        assert!(out.contains("observer_inference_duration_seconds_bucket{provider=\"ollama\",le=\"0.5\"} 1\n"));
+        assert!(out.contains("observer_inference_duration_seconds_bucket{provider=\"ollama\",le=\"+Inf\"} 1\n"));
+        assert!(out.contains("observer_inference_duration_seconds_count{provider=\"ollama\"} 1\n"));
+        assert!(out.contains(r#"observer_agent_runs_total{agent="say \"hi\"",outcome="error"} 1"#));
+    }
+}
@@ -4,7 +4,10 @@ use axum::{extract::State as AxumState, http::StatusCode, response::Json};
 use serde::{Deserialize, Serialize};
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 // ---- NEW IMPORT ----
+use crate::dnd::{self, Route};
+use crate::{events, incognito};
 use crate::AppState;
+use tauri::AppHandle;
 use tauri_plugin_notification::NotificationExt;
 
 // --- STRUCTS FOR /ask ---
@@ -31,6 +34,11 @@ pub struct MessagePayload {
 pub struct NotificationPayload {
     title: String,
     body: String,
+    #[serde(default, rename = "agentId")]
+    agent_id: Option<String>,
+    // Deliver even while the OS is in Do Not Disturb / Focus mode
+    #[serde(default, rename = "breakThrough")]
+    break_through: bool,
 }
 
 // --- HANDLER for /ask (no changes) ---
@@ -91,17 +99,31 @@ pub async fn notification_handler(
         payload.body
     );
 
-    // The .show() method for notifications is NON-BLOCKING.
-    // It returns immediately, so we do NOT need spawn_blocking here.
-    let builder = state
-        .app_handle
-        .notification()
-        .builder()
-        .title(payload.title)
-        .body(payload.body);
+    if incognito::is_active(&state.app_handle) {
+        log::info!("V2: Incognito mode active, notification suppressed.");
+        return StatusCode::ACCEPTED;
+    }
+
+    match dnd::route_notification(
+        &state.app_handle,
+        &payload.title,
+        &payload.body,
+        payload.agent_id.as_deref(),
+        payload.break_through,
+    ) {
+        Route::Deliver => {}
+        Route::Held => {
+            log::info!("V2: Do Not Disturb active, notification held until it ends.");
+            return StatusCode::ACCEPTED;
+        }
+        Route::Badged => {
+            log::info!("V2: Do Not Disturb active, notification downgraded to tray badge.");
+            return StatusCode::ACCEPTED;
+        }
+    }
 
     // Fire and forget the notification.
-    if let Err(e) = builder.show() {
+    if let Err(e) = show_notification(&state.app_handle, &payload.title, &payload.body) {
         log::error!("Failed to show notification: {}", e);
         return StatusCode::INTERNAL_SERVER_ERROR;
     }
@@ -109,3 +131,28 @@ pub async fn notification_handler(
     log::info!("V2: System notification sent successfully.");
     StatusCode::OK
 }
+
+/// Show a system notification immediately.
+// The .show() method for notifications is NON-BLOCKING.
+// It returns immediately, so callers do NOT need spawn_blocking.
+pub fn show_notification(
+    app_handle: &AppHandle,
+    title: &str,
+    body: &str,
+) -> tauri_plugin_notification::Result<()> {
+    if incognito::is_active(app_handle) {
+        log::info!("Incognito mode active, dropping notification '{}'", title);
+        return Ok(());
+    }
+    app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()?;
+    events::publish(
+        app_handle,
+        events::ApiEvent::Notification { title: title.to_string(), body: body.to_string() },
+    );
+    Ok(())
+}
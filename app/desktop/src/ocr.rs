@@ -0,0 +1,322 @@
+// In src-tauri/src/ocr.rs
+//
+// OCR language packs and per-agent language hints. Packs are Tesseract `.traineddata`
+// files downloaded into `<app_data_dir>/tessdata`; recognition itself lives in the
+// screen-capture plugin behind the `ocr` feature. OCR text goes through the redaction
+// stage before it is handed back, like any other text bound for a model.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State};
+
+/// Fast (integer) LSTM models; small enough to fetch on demand
+const TESSDATA_URL: &str = "https://github.com/tesseract-ocr/tessdata_fast/raw/main";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrSettings {
+    /// Languages used when an agent has no hint. Empty = detect the script.
+    #[serde(default)]
+    pub default_languages: Vec<String>,
+    /// agent_id -> Tesseract language codes
+    #[serde(default)]
+    pub agent_languages: HashMap<String, Vec<String>>,
+}
+
+impl OcrSettings {
+    pub fn languages_for(&self, agent_id: Option<&str>) -> Vec<String> {
+        agent_id
+            .and_then(|id| self.agent_languages.get(id))
+            .filter(|langs| !langs.is_empty())
+            .unwrap_or(&self.default_languages)
+            .clone()
+    }
+}
+
+pub fn tessdata_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("tessdata"))
+}
+
+/// Point the plugin's OCR engine at our tessdata directory
+pub fn init(app_handle: &AppHandle) {
+    #[cfg(feature = "ocr")]
+    match tessdata_dir(app_handle) {
+        Ok(dir) => tauri_plugin_screen_capture::ocr::set_tessdata_dir(dir),
+        Err(e) => log::warn!("OCR disabled, no tessdata directory: {}", e),
+    }
+    #[cfg(not(feature = "ocr"))]
+    let _ = app_handle;
+}
+
+/// Tesseract codes are lowercase ASCII with underscores (eng, chi_sim, ...)
+fn validate_language(lang: &str) -> Result<(), String> {
+    if lang.is_empty() || !lang.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+        return Err(format!("Invalid language code: {}", lang));
+    }
+    Ok(())
+}
+
+// Tauri commands
+
+/// Language packs present in the tessdata directory
+#[tauri::command]
+pub async fn ocr_list_languages(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let dir = tessdata_dir(&app_handle)?;
+    let mut languages: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .and_then(|name| name.strip_suffix(".traineddata"))
+                        .map(String::from)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    languages.sort();
+    Ok(languages)
+}
+
+/// Download a language pack (e.g. "jpn", "chi_sim", "rus", "ara")
+#[tauri::command]
+pub async fn ocr_download_language(
+    language: String,
+    app_handle: AppHandle,
+    on_progress: Channel<serde_json::Value>,
+) -> Result<(), String> {
+    use futures_util::StreamExt;
+    use std::io::Write;
+
+    validate_language(&language)?;
+    let dir = tessdata_dir(&app_handle)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create tessdata dir: {}", e))?;
+
+    let url = format!("{}/{}.traineddata", TESSDATA_URL, language);
+    log::info!("Downloading OCR language pack '{}' from {}", language, url);
+
+    let response = reqwest::get(&url)
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed with status: {}", response.status()));
+    }
+
+    let total_size = response.content_length().unwrap_or(0);
+    let part_path = dir.join(format!("{}.traineddata.part", language));
+    let mut file = std::fs::File::create(&part_path)
+        .map_err(|e| format!("Failed to open part file: {}", e))?;
+
+    let mut downloaded = 0u64;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download error: {}", e))?;
+        file.write_all(&chunk).map_err(|e| format!("Write error: {}", e))?;
+        downloaded += chunk.len() as u64;
+        let _ = on_progress.send(serde_json::json!({
+            "status": "downloading",
+            "downloadedBytes": downloaded,
+            "totalBytes": total_size,
+            "language": language
+        }));
+    }
+    drop(file);
+
+    std::fs::rename(&part_path, dir.join(format!("{}.traineddata", language)))
+        .map_err(|e| format!("Failed to finalize download: {}", e))?;
+    let _ = on_progress.send(serde_json::json!({
+        "status": "complete",
+        "downloadedBytes": downloaded,
+        "totalBytes": downloaded,
+        "language": language
+    }));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ocr_delete_language(language: String, app_handle: AppHandle) -> Result<(), String> {
+    validate_language(&language)?;
+    let path = tessdata_dir(&app_handle)?.join(format!("{}.traineddata", language));
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", language, e))
+}
+
+/// OCR an encoded image (PNG/JPEG bytes) using the agent's language hints
+#[cfg(feature = "ocr")]
+#[tauri::command]
+pub async fn ocr_image(
+    image: Vec<u8>,
+    agent_id: Option<String>,
+    app_handle: AppHandle,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    redaction_state: State<'_, crate::redaction::RedactionState>,
+) -> Result<tauri_plugin_screen_capture::ocr::OcrResult, String> {
+    let options = tauri_plugin_screen_capture::ocr::OcrOptions {
+        languages: shortcut_state
+            .config
+            .lock()
+            .unwrap()
+            .ocr
+            .languages_for(agent_id.as_deref()),
+    };
+
+    let mut result = tauri::async_runtime::spawn_blocking(move || {
+        tauri_plugin_screen_capture::ocr::recognize_encoded(&image, &options)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    redact_result(&mut result, agent_id.as_deref(), &redaction_state);
+    let now = chrono::Utc::now().timestamp_millis();
+    crate::recall::observe(&app_handle, crate::recall::Source::Ocr, &result.text, agent_id.as_deref(), now);
+    Ok(result)
+}
+
+#[cfg(not(feature = "ocr"))]
+#[tauri::command]
+pub async fn ocr_image(_image: Vec<u8>, _agent_id: Option<String>) -> Result<(), String> {
+    Err("OCR support is not included in this build".to_string())
+}
+
+/// OCR a capture target at full resolution (None = primary monitor), or with
+/// `latest_frame` the next frame of the running stream, using the agent's language hints
+#[cfg(feature = "ocr")]
+#[tauri::command]
+pub async fn ocr_frame(
+    target_id: Option<String>,
+    latest_frame: Option<bool>,
+    agent_id: Option<String>,
+    app_handle: AppHandle,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    redaction_state: State<'_, crate::redaction::RedactionState>,
+) -> Result<tauri_plugin_screen_capture::ocr::FrameText, String> {
+    use tauri_plugin_screen_capture::ocr;
+
+    if crate::incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let options = ocr::OcrOptions {
+        languages: shortcut_state
+            .config
+            .lock()
+            .unwrap()
+            .ocr
+            .languages_for(agent_id.as_deref()),
+    };
+
+    let mut text = if latest_frame.unwrap_or(false) {
+        ocr::recognize_latest_frame(&options).await.map_err(|e| e.to_string())?
+    } else {
+        tauri::async_runtime::spawn_blocking(move || {
+            ocr::recognize_target(target_id.as_deref(), &options).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+
+    redact_result(&mut text.result, agent_id.as_deref(), &redaction_state);
+    let captured_ms = (text.timestamp * 1000.0) as i64;
+    let source = crate::recall::Source::Ocr;
+    crate::recall::observe(&app_handle, source, &text.result.text, agent_id.as_deref(), captured_ms);
+    Ok(text)
+}
+
+#[cfg(not(feature = "ocr"))]
+#[tauri::command]
+pub async fn ocr_frame(
+    _target_id: Option<String>,
+    _latest_frame: Option<bool>,
+    _agent_id: Option<String>,
+) -> Result<(), String> {
+    Err("OCR support is not included in this build".to_string())
+}
+
+/// OCR a target every `intervalSecs` and emit `screen-capture://text-changed` with the
+/// lines that changed. Without languages in `config`, the agent's hints are used.
+#[cfg(feature = "ocr")]
+#[tauri::command]
+pub async fn ocr_start_text_watch(
+    mut config: tauri_plugin_screen_capture::text_watch::TextWatchConfig,
+    agent_id: Option<String>,
+    app_handle: AppHandle,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<(), String> {
+    if crate::incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    if config.languages.is_empty() {
+        config.languages = shortcut_state
+            .config
+            .lock()
+            .unwrap()
+            .ocr
+            .languages_for(agent_id.as_deref());
+    }
+    tauri_plugin_screen_capture::text_watch::start(config).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "ocr"))]
+#[tauri::command]
+pub async fn ocr_start_text_watch(_config: serde_json::Value, _agent_id: Option<String>) -> Result<(), String> {
+    Err("OCR support is not included in this build".to_string())
+}
+
+#[tauri::command]
+pub async fn ocr_stop_text_watch(id: String) -> Result<bool, String> {
+    #[cfg(feature = "ocr")]
+    return Ok(tauri_plugin_screen_capture::text_watch::stop(&id));
+    #[cfg(not(feature = "ocr"))]
+    {
+        let _ = id;
+        Ok(false)
+    }
+}
+
+/// Run OCR text through the redaction stage when it applies to the agent
+#[cfg(feature = "ocr")]
+fn redact_result(
+    result: &mut tauri_plugin_screen_capture::ocr::OcrResult,
+    agent_id: Option<&str>,
+    redaction_state: &crate::redaction::RedactionState,
+) {
+    if redaction_state.applies_to(agent_id) {
+        let mut redactions = crate::redaction::Redactions::new();
+        result.text = redaction_state.redact(&result.text, &mut redactions);
+        for line in &mut result.lines {
+            line.text = redaction_state.redact(&line.text, &mut redactions);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_ocr_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<OcrSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().ocr.clone())
+}
+
+#[tauri::command]
+pub async fn set_ocr_settings(
+    settings: OcrSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    for lang in settings
+        .default_languages
+        .iter()
+        .chain(settings.agent_languages.values().flatten())
+    {
+        validate_language(lang)?;
+    }
+    log::info!("Setting OCR settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.ocr = settings)
+}
@@ -0,0 +1,123 @@
+// In src-tauri/src/ollama_proxy.rs
+//
+// Ollama behind the app's own server. `/v1/ollama/<path>` is forwarded to
+// `<ollama url>/<path>` through the same proxy as the `/api` and `/v1` routes (queue,
+// redaction, audit), so the frontend only ever talks to its own origin: no mixed content
+// when Ollama is plain http, no CORS preflights Ollama refuses, and no certificate prompts
+// the webview can't show.
+//
+// The upstream may be http or https on any port. Servers with a self-signed certificate
+// are reached by trusting that certificate (a PEM file), or, as a last resort, by turning
+// off certificate checks for Ollama traffic altogether.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+/// Route prefix the frontend uses as its Ollama base URL
+pub const PATH_PREFIX: &str = "/v1/ollama";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OllamaProxySettings {
+    /// PEM certificate (self-signed or private CA) to trust for https upstreams
+    #[serde(default)]
+    pub trusted_cert_path: Option<String>,
+    /// Skip certificate verification entirely
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+/// The client Ollama traffic goes out on, rebuilt when the trust settings change
+pub struct OllamaProxyState {
+    client: Mutex<reqwest::Client>,
+}
+
+impl OllamaProxyState {
+    pub fn new(settings: &OllamaProxySettings) -> Self {
+        let client = build_client(settings).unwrap_or_else(|e| {
+            log::warn!("[OllamaProxy] {}; using default certificate checks", e);
+            reqwest::Client::new()
+        });
+        Self { client: Mutex::new(client) }
+    }
+}
+
+fn build_client(settings: &OllamaProxySettings) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(path) = settings.trusted_cert_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        let pem = std::fs::read(path).map_err(|e| format!("Failed to read certificate {}: {}", path, e))?;
+        let cert = reqwest::Certificate::from_pem(&pem).map_err(|e| format!("Invalid certificate {}: {}", path, e))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    if settings.accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+pub fn client(app_handle: &AppHandle) -> reqwest::Client {
+    app_handle.state::<OllamaProxyState>().client.lock().unwrap().clone()
+}
+
+/// An Ollama URL as configured ("gpu-box:11434", "https://ollama.lan/") in the form paths
+/// are appended to: with a scheme (http if none) and no trailing slash
+pub fn normalize_upstream(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    }
+}
+
+/// `/v1/ollama/api/tags?x=1` → `/api/tags?x=1`
+pub fn strip_prefix(uri: &axum::http::Uri) -> axum::http::Uri {
+    let path_and_query = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+    let rest = path_and_query.strip_prefix(PATH_PREFIX).unwrap_or(path_and_query);
+    let rest = if rest.starts_with('/') { rest.to_string() } else { format!("/{}", rest) };
+    rest.parse().unwrap_or_else(|_| axum::http::Uri::from_static("/"))
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_ollama_proxy_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<OllamaProxySettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().ollama_proxy.clone())
+}
+
+/// Save the trust settings; fails without saving if the certificate can't be loaded
+#[tauri::command]
+pub async fn set_ollama_proxy_settings(
+    settings: OllamaProxySettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    proxy_state: State<'_, OllamaProxyState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting Ollama proxy settings: {:?}", settings);
+    if settings.accept_invalid_certs {
+        log::warn!("[OllamaProxy] Certificate verification is off for Ollama traffic");
+    }
+    *proxy_state.client.lock().unwrap() = build_client(&settings)?;
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.ollama_proxy = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_upstreams_and_strips_the_prefix() {
+        assert_eq!(normalize_upstream("gpu-box:11434"), "http://gpu-box:11434");
+        assert_eq!(normalize_upstream(" https://ollama.lan:8443/ "), "https://ollama.lan:8443");
+
+        let uri: axum::http::Uri = "/v1/ollama/api/tags?verbose=1".parse().unwrap();
+        assert_eq!(strip_prefix(&uri), "/api/tags?verbose=1");
+        let uri: axum::http::Uri = "/v1/ollama/v1/chat/completions".parse().unwrap();
+        assert_eq!(strip_prefix(&uri).path(), "/v1/chat/completions");
+        assert_eq!(strip_prefix(&"/v1/ollama".parse().unwrap()), "/");
+    }
+}
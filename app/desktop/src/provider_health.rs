@@ -0,0 +1,230 @@
+// In src-tauri/src/provider_health.rs
+//
+// Circuit breaker for inference providers. When a provider's requests fail
+// `failureThreshold` times in a row (each after its retries), it is marked unhealthy and
+// requests skip it, moving straight on to the fallback chain or failing fast, instead of
+// waiting on it again. Once `cooldownSecs` have passed, one request is let through as a
+// probe: success marks the provider healthy, failure opens the breaker for another
+// cooldown.
+//
+// Only failures that say something about the provider count: connection errors, timeouts,
+// 429 and 5xx. Changes are emitted as `provider-unhealthy` and `provider-healthy`.
+
+use crate::inference::InferenceError;
+use crate::providers::{RequestPolicy, ResolvedProvider};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+pub const UNHEALTHY_EVENT: &str = "provider-unhealthy";
+pub const HEALTHY_EVENT: &str = "provider-healthy";
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthEventKind {
+    ProviderUnhealthy,
+    ProviderHealthy,
+}
+
+/// Payload of both events
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthEvent {
+    pub kind: HealthEventKind,
+    pub provider_id: String,
+    /// The failure that opened the breaker
+    pub error: Option<String>,
+    /// When the provider gets its next try
+    pub retry_after_secs: Option<u64>,
+}
+
+/// What `get_provider_health` reports for providers that have failed recently
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHealthStatus {
+    pub provider_id: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub retry_after_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// When the probe after a cooldown was let through
+    probe_started: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl Breaker {
+    /// Ok if a request may go out now, else how long until one may
+    fn admit(&mut self, policy: &RequestPolicy, now: Instant) -> Result<(), Duration> {
+        let Some(until) = self.open_until else {
+            return Ok(());
+        };
+        if now < until {
+            return Err(until - now);
+        }
+        // One probe at a time; a probe that never reports back (cancelled) expires
+        match self.probe_started {
+            Some(started) if now < started + policy.cooldown() => Err(started + policy.cooldown() - now),
+            _ => {
+                self.probe_started = Some(now);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns true if this ended an unhealthy spell
+    fn succeed(&mut self) -> bool {
+        let was_open = self.open_until.is_some();
+        *self = Breaker::default();
+        was_open
+    }
+
+    /// Returns true if this opened the breaker
+    fn fail(&mut self, policy: &RequestPolicy, error: String, now: Instant) -> bool {
+        self.failures += 1;
+        self.last_error = Some(error);
+        let probe_failed = self.open_until.is_some();
+        if probe_failed || self.failures >= policy.failure_threshold.max(1) {
+            self.open_until = Some(now + policy.cooldown());
+            self.probe_started = None;
+            return true;
+        }
+        false
+    }
+}
+
+pub struct ProviderHealth {
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+impl ProviderHealth {
+    pub fn new() -> Self {
+        Self {
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Ok if `provider` may be sent a request, else how long it stays unhealthy
+pub fn admit(app_handle: &AppHandle, provider: &ResolvedProvider) -> Result<(), Duration> {
+    let health = app_handle.state::<ProviderHealth>();
+    let mut breakers = health.breakers.lock().unwrap();
+    match breakers.get_mut(&provider.id) {
+        Some(breaker) => breaker.admit(&provider.policy, Instant::now()),
+        None => Ok(()),
+    }
+}
+
+/// Count a finished request towards `provider`'s health
+pub fn record<T>(app_handle: &AppHandle, provider: &ResolvedProvider, result: &Result<T, InferenceError>) {
+    let event = {
+        let health = app_handle.state::<ProviderHealth>();
+        let mut breakers = health.breakers.lock().unwrap();
+        match result {
+            Ok(_) => {
+                let recovered = breakers.remove(&provider.id).is_some_and(|mut breaker| breaker.succeed());
+                recovered.then(|| HealthEvent {
+                    kind: HealthEventKind::ProviderHealthy,
+                    provider_id: provider.id.clone(),
+                    error: None,
+                    retry_after_secs: None,
+                })
+            }
+            Err(e) if e.is_transient() => {
+                let breaker = breakers.entry(provider.id.clone()).or_default();
+                breaker.fail(&provider.policy, e.to_string(), Instant::now()).then(|| HealthEvent {
+                    kind: HealthEventKind::ProviderUnhealthy,
+                    provider_id: provider.id.clone(),
+                    error: Some(e.to_string()),
+                    retry_after_secs: Some(provider.policy.cooldown_secs),
+                })
+            }
+            Err(_) => None,
+        }
+    };
+
+    if let Some(event) = event {
+        let name = match event.kind {
+            HealthEventKind::ProviderUnhealthy => {
+                log::warn!("Provider {} marked unhealthy: {}", event.provider_id, event.error.as_deref().unwrap_or(""));
+                UNHEALTHY_EVENT
+            }
+            HealthEventKind::ProviderHealthy => {
+                log::info!("Provider {} is healthy again", event.provider_id);
+                HEALTHY_EVENT
+            }
+        };
+        if let Err(e) = app_handle.emit(name, &event) {
+            log::error!("Failed to emit {}: {}", name, e);
+        }
+    }
+}
+
+/// Providers that have failed since their last success
+pub fn statuses(health: &ProviderHealth) -> Vec<ProviderHealthStatus> {
+    let now = Instant::now();
+    let breakers = health.breakers.lock().unwrap();
+    let mut statuses: Vec<ProviderHealthStatus> = breakers
+        .iter()
+        .map(|(id, breaker)| ProviderHealthStatus {
+            provider_id: id.clone(),
+            healthy: breaker.open_until.is_none(),
+            consecutive_failures: breaker.failures,
+            retry_after_secs: breaker.open_until.map(|until| until.saturating_duration_since(now).as_secs()),
+            last_error: breaker.last_error.clone(),
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.provider_id.cmp(&b.provider_id));
+    statuses
+}
+
+// Tauri commands
+
+/// Providers that have failed since their last success
+#[tauri::command]
+pub async fn get_provider_health(health: State<'_, ProviderHealth>) -> Result<Vec<ProviderHealthStatus>, String> {
+    Ok(statuses(&health))
+}
+
+/// Forget a provider's failures and let requests through again
+#[tauri::command]
+pub async fn reset_provider_health(provider_id: String, health: State<'_, ProviderHealth>) -> Result<(), String> {
+    health.breakers.lock().unwrap().remove(&provider_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_and_probes_after_cooldown() {
+        let policy = RequestPolicy { failure_threshold: 2, cooldown_secs: 30, ..RequestPolicy::default() };
+        let start = Instant::now();
+        let mut breaker = Breaker::default();
+        assert!(!breaker.fail(&policy, "timed out".to_string(), start));
+        assert!(breaker.admit(&policy, start).is_ok());
+        assert!(breaker.fail(&policy, "timed out".to_string(), start));
+        assert_eq!(breaker.admit(&policy, start + Duration::from_secs(10)), Err(Duration::from_secs(20)));
+
+        // One probe after the cooldown; a failed probe reopens straight away
+        let later = start + Duration::from_secs(31);
+        assert!(breaker.admit(&policy, later).is_ok());
+        assert!(breaker.admit(&policy, later).is_err());
+        assert!(breaker.fail(&policy, "503".to_string(), later));
+        assert!(breaker.admit(&policy, later + Duration::from_secs(29)).is_err());
+
+        // A probe that succeeds closes it
+        assert!(breaker.admit(&policy, later + Duration::from_secs(31)).is_ok());
+        assert!(breaker.succeed());
+        assert!(breaker.admit(&policy, later + Duration::from_secs(31)).is_ok());
+        assert_eq!(breaker.failures, 0);
+    }
+}
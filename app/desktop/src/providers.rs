@@ -0,0 +1,741 @@
+// In src-tauri/src/providers.rs
+//
+// Model providers the backend calls itself. Besides the default Ollama URL, users can add
+// OpenAI-compatible endpoints (vLLM, LM Studio, llama.cpp server, OpenRouter, ...) and
+// Anthropic with a base URL, API key and default model, and assign agents to them. API
+// keys are saved with the app config and never handed back to the webview: the frontend
+// only learns whether a key is set, and requests to these providers go out from
+// `inference::chat`.
+//
+// Endpoints behind an authenticating reverse proxy can also be given extra headers
+// (Authorization, X-Api-Key, ...) and basic-auth credentials, the default Ollama included.
+// Like keys, header values and passwords stay in the backend; the webview sees header
+// names and the basic-auth user only.
+//
+// An ordered fallback chain (e.g. local Ollama, then a cloud provider) is tried after the
+// agent's own provider when it fails (short of refusing the request itself) or takes
+// longer than `fallback_after_secs`. Each provider also has a `RequestPolicy`: how long
+// one try may take, how often transient failures are retried, and when `provider_health`
+// stops sending to it for a while.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+/// Id of the built-in provider backed by the default Ollama URL
+pub const DEFAULT_PROVIDER_ID: &str = "ollama";
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderKind {
+    /// Ollama server; its OpenAI-compatible API lives under `/v1`
+    Ollama,
+    /// Any OpenAI-compatible API; the base URL includes the version, e.g. .../v1
+    #[default]
+    OpenAi,
+    /// Anthropic Messages API (see `anthropic`); base URL https://api.anthropic.com
+    Anthropic,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BasicAuth {
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+/// Credentials sent with every request to an endpoint, on top of its API key
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointAuth {
+    /// Header name -> value; these replace any header the request already has
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+}
+
+/// `EndpointAuth` without the secrets, for the webview
+#[derive(Clone, Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthInfo {
+    pub header_names: Vec<String>,
+    pub basic_auth_user: Option<String>,
+}
+
+/// Changes to an `EndpointAuth`. Missing `headers` keep the saved ones; given, they replace
+/// them, and a header sent with an empty value keeps its saved value. A missing `basicAuth`
+/// keeps the saved credentials, an empty username clears them, and a missing password
+/// keeps the saved one.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthUpdate {
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuthUpdate>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BasicAuthUpdate {
+    pub username: String,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl EndpointAuth {
+    pub fn info(&self) -> AuthInfo {
+        AuthInfo {
+            header_names: self.headers.keys().cloned().collect(),
+            basic_auth_user: self.basic_auth.as_ref().map(|auth| auth.username.clone()),
+        }
+    }
+
+    /// These credentials with `update` applied; fails on a header that can't be sent
+    pub fn updated(&self, update: AuthUpdate) -> Result<EndpointAuth, String> {
+        let headers = match update.headers {
+            None => self.headers.clone(),
+            Some(headers) => headers
+                .into_iter()
+                .filter(|(name, _)| !name.trim().is_empty())
+                .map(|(name, value)| {
+                    let name = name.trim().to_string();
+                    let value = match value.is_empty() {
+                        true => self.headers.get(&name).cloned().unwrap_or_default(),
+                        false => value,
+                    };
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("Invalid header name '{}'", name))?;
+                    HeaderValue::from_str(&value).map_err(|_| format!("Invalid value for header '{}'", name))?;
+                    Ok((name, value))
+                })
+                .collect::<Result<_, String>>()?,
+        };
+        let basic_auth = match update.basic_auth {
+            None => self.basic_auth.clone(),
+            Some(auth) if auth.username.is_empty() => None,
+            Some(auth) => Some(BasicAuth {
+                password: auth
+                    .password
+                    .or_else(|| self.basic_auth.as_ref().map(|saved| saved.password.clone()))
+                    .unwrap_or_default(),
+                username: auth.username,
+            }),
+        };
+        Ok(EndpointAuth { headers, basic_auth })
+    }
+
+    /// The headers to set: basic auth as `Authorization`, then the custom headers over it
+    pub fn header_map(&self) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        if let Some(auth) = &self.basic_auth {
+            let credentials = BASE64.encode(format!("{}:{}", auth.username, auth.password));
+            if let Ok(mut value) = HeaderValue::from_str(&format!("Basic {}", credentials)) {
+                value.set_sensitive(true);
+                map.insert(AUTHORIZATION, value);
+            }
+        }
+        for (name, value) in &self.headers {
+            match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                (Ok(name), Ok(mut value)) => {
+                    value.set_sensitive(true);
+                    map.insert(name, value);
+                }
+                _ => log::warn!("Skipping invalid header {}", name),
+            }
+        }
+        map
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub kind: ProviderKind,
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model used when a request doesn't name one
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub auth: EndpointAuth,
+}
+
+/// Timeouts, retries and circuit breaking for one provider
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPolicy {
+    /// Longest one try may take; for a streamed reply, until its first token
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Extra tries after a connection error, timeout, 429 or 5xx
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Failed requests in a row (after retries) that mark the provider unhealthy
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long an unhealthy provider is skipped before a request may try it again
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_cooldown_secs() -> u64 {
+    60
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_timeout_secs(),
+            max_retries: default_max_retries(),
+            failure_threshold: default_failure_threshold(),
+            cooldown_secs: default_cooldown_secs(),
+        }
+    }
+}
+
+impl RequestPolicy {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderSettings {
+    #[serde(default)]
+    pub providers: Vec<ProviderConfig>,
+    /// Agent id -> provider id
+    #[serde(default)]
+    pub agents: HashMap<String, String>,
+    /// Provider ids tried in order after the request's own provider fails
+    #[serde(default)]
+    pub fallback: Vec<String>,
+    /// How long an attempt may take before the next provider is tried
+    #[serde(default = "default_fallback_after_secs")]
+    pub fallback_after_secs: u64,
+    /// Credentials for the default Ollama URL, also added to requests proxied to it
+    #[serde(default)]
+    pub ollama_auth: EndpointAuth,
+    /// Provider id -> policy, for providers that don't use the default one
+    #[serde(default)]
+    pub policies: HashMap<String, RequestPolicy>,
+}
+
+fn default_fallback_after_secs() -> u64 {
+    60
+}
+
+impl Default for ProviderSettings {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            agents: HashMap::new(),
+            fallback: Vec::new(),
+            fallback_after_secs: default_fallback_after_secs(),
+            ollama_auth: EndpointAuth::default(),
+            policies: HashMap::new(),
+        }
+    }
+}
+
+/// The fallback chain as the webview edits it
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FallbackChain {
+    pub providers: Vec<String>,
+    pub fallback_after_secs: u64,
+}
+
+/// A provider as the webview sees it: everything but the key
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    pub id: String,
+    pub name: String,
+    pub kind: ProviderKind,
+    pub base_url: String,
+    pub model: Option<String>,
+    pub has_api_key: bool,
+    #[serde(flatten)]
+    pub auth: AuthInfo,
+    /// Agents assigned to it
+    pub agents: Vec<String>,
+}
+
+/// What `save_provider` accepts. A missing `apiKey` keeps the saved one; an empty one clears it.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUpdate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub kind: ProviderKind,
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub auth: AuthUpdate,
+}
+
+/// A provider ready to send to
+#[derive(Clone, Debug)]
+pub struct ResolvedProvider {
+    pub id: String,
+    pub kind: ProviderKind,
+    /// Without a trailing slash
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+    pub auth: EndpointAuth,
+    pub policy: RequestPolicy,
+}
+
+/// Providers to try for a request, first to last
+pub struct ResolvedChain {
+    pub providers: Vec<ResolvedProvider>,
+    pub fallback_after: Duration,
+}
+
+impl ResolvedProvider {
+    /// Versioned API base: `/chat/completions`, `/messages` and `/models` hang off it
+    pub fn api_base(&self) -> String {
+        match self.kind {
+            ProviderKind::Ollama | ProviderKind::Anthropic => format!("{}/v1", self.base_url),
+            ProviderKind::OpenAi => self.base_url.clone(),
+        }
+    }
+
+    pub fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let key = self.api_key.as_deref().filter(|key| !key.is_empty());
+        match (self.kind, key) {
+            (ProviderKind::Anthropic, key) => request
+                .header("x-api-key", key.unwrap_or_default())
+                .header("anthropic-version", crate::anthropic::API_VERSION),
+            (_, Some(key)) => request.bearer_auth(key),
+            (_, None) => request,
+        }
+        .headers(self.auth.header_map())
+    }
+}
+
+fn default_ollama_url(app_handle: &AppHandle) -> String {
+    app_handle
+        .state::<crate::AppSettings>()
+        .ollama_url
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| "http://127.0.0.1:11434".to_string())
+}
+
+/// The provider for a request: `provider_id` if given, else the agent's, else the default Ollama
+pub fn resolve(
+    app_handle: &AppHandle,
+    agent_id: Option<&str>,
+    provider_id: Option<&str>,
+) -> Result<ResolvedProvider, String> {
+    let settings = app_handle
+        .state::<UnifiedShortcutState>()
+        .config
+        .lock()
+        .unwrap()
+        .providers
+        .clone();
+    let id = provider_id
+        .map(String::from)
+        .or_else(|| agent_id.and_then(|agent| settings.agents.get(agent).cloned()))
+        .unwrap_or_else(|| DEFAULT_PROVIDER_ID.to_string());
+    let policy = settings.policies.get(&id).cloned().unwrap_or_default();
+
+    if let Some(config) = settings.providers.iter().find(|p| p.id == id) {
+        return Ok(ResolvedProvider {
+            id: config.id.clone(),
+            kind: config.kind,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            auth: config.auth.clone(),
+            policy,
+        });
+    }
+    if id == DEFAULT_PROVIDER_ID {
+        let url = crate::tailnet::resolve(app_handle, &default_ollama_url(app_handle));
+        return Ok(ResolvedProvider {
+            id,
+            kind: ProviderKind::Ollama,
+            base_url: url.trim_end_matches('/').to_string(),
+            api_key: None,
+            model: None,
+            auth: settings.ollama_auth,
+            policy,
+        });
+    }
+    Err(format!("Unknown provider {}", id))
+}
+
+/// Headers for requests to the default Ollama URL
+pub fn ollama_auth_headers(app_handle: &AppHandle) -> HeaderMap {
+    let config = app_handle.state::<UnifiedShortcutState>();
+    let config = config.config.lock().unwrap();
+    config.providers.ollama_auth.header_map()
+}
+
+/// The request's provider (as `resolve` picks it) followed by the fallback chain
+pub fn chain(
+    app_handle: &AppHandle,
+    agent_id: Option<&str>,
+    provider_id: Option<&str>,
+) -> Result<ResolvedChain, String> {
+    let primary = resolve(app_handle, agent_id, provider_id)?;
+    let (fallback, fallback_after_secs) = {
+        let config = app_handle.state::<UnifiedShortcutState>();
+        let config = config.config.lock().unwrap();
+        (config.providers.fallback.clone(), config.providers.fallback_after_secs)
+    };
+    Ok(build_chain(primary, &fallback, fallback_after_secs, |id| resolve(app_handle, None, Some(id))))
+}
+
+/// `primary`, then each `fallback` provider `resolve` knows that isn't in the chain yet
+fn build_chain(
+    primary: ResolvedProvider,
+    fallback: &[String],
+    fallback_after_secs: u64,
+    resolve: impl Fn(&str) -> Result<ResolvedProvider, String>,
+) -> ResolvedChain {
+    let mut providers = vec![primary];
+    for id in fallback {
+        if providers.iter().any(|p| p.id == *id) {
+            continue;
+        }
+        match resolve(id) {
+            Ok(provider) => providers.push(provider),
+            Err(e) => log::warn!("Skipping fallback provider: {}", e),
+        }
+    }
+    ResolvedChain { providers, fallback_after: Duration::from_secs(fallback_after_secs.max(1)) }
+}
+
+fn info(config: &ProviderConfig, agents: &HashMap<String, String>) -> ProviderInfo {
+    let mut assigned: Vec<String> = agents
+        .iter()
+        .filter(|(_, provider)| **provider == config.id)
+        .map(|(agent, _)| agent.clone())
+        .collect();
+    assigned.sort();
+    ProviderInfo {
+        id: config.id.clone(),
+        name: config.name.clone(),
+        kind: config.kind,
+        base_url: config.base_url.clone(),
+        model: config.model.clone(),
+        has_api_key: config.api_key.as_deref().is_some_and(|key| !key.is_empty()),
+        auth: config.auth.info(),
+        agents: assigned,
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_providers(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<Vec<ProviderInfo>, String> {
+    let config = shortcut_state.config.lock().unwrap();
+    let settings = &config.providers;
+    Ok(settings.providers.iter().map(|p| info(p, &settings.agents)).collect())
+}
+
+/// Add a provider, or update the one with the same id
+#[tauri::command]
+pub async fn save_provider(
+    provider: ProviderUpdate,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if provider.id.trim().is_empty() || provider.id == DEFAULT_PROVIDER_ID {
+        return Err(format!("Invalid provider id '{}'", provider.id));
+    }
+    let url = reqwest::Url::parse(&provider.base_url).map_err(|e| format!("Invalid base URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Base URL must be http or https".to_string());
+    }
+    log::info!("Saving inference provider {} ({:?}, {})", provider.id, provider.kind, provider.base_url);
+
+    let auth = {
+        let config = shortcut_state.config.lock().unwrap();
+        let saved = config.providers.providers.iter().find(|p| p.id == provider.id);
+        saved.map(|p| p.auth.clone()).unwrap_or_default().updated(provider.auth)?
+    };
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        let providers = &mut config.providers.providers;
+        let existing = providers.iter().position(|p| p.id == provider.id);
+        let api_key = match provider.api_key {
+            Some(key) if key.is_empty() => None,
+            Some(key) => Some(key),
+            None => existing.and_then(|i| providers[i].api_key.clone()),
+        };
+        let saved = ProviderConfig {
+            id: provider.id,
+            name: provider.name,
+            kind: provider.kind,
+            base_url: provider.base_url,
+            api_key,
+            model: provider.model.filter(|model| !model.is_empty()),
+            auth,
+        };
+        match existing {
+            Some(i) => providers[i] = saved,
+            None => providers.push(saved),
+        }
+    })
+}
+
+/// Remove a provider; agents assigned to it go back to the default
+#[tauri::command]
+pub async fn remove_provider(
+    provider_id: String,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.providers.providers.retain(|p| p.id != provider_id);
+        config.providers.agents.retain(|_, provider| *provider != provider_id);
+        config.providers.fallback.retain(|id| *id != provider_id);
+        config.providers.policies.remove(&provider_id);
+    })
+}
+
+/// Assign (or with `providerId: null`, unassign) an agent
+#[tauri::command]
+pub async fn set_agent_provider(
+    agent_id: String,
+    provider_id: Option<String>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if let Some(id) = &provider_id {
+        let config = shortcut_state.config.lock().unwrap();
+        if id != DEFAULT_PROVIDER_ID && !config.providers.providers.iter().any(|p| p.id == *id) {
+            return Err(format!("Unknown provider {}", id));
+        }
+    }
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| match provider_id {
+        Some(id) => {
+            config.providers.agents.insert(agent_id, id);
+        }
+        None => {
+            config.providers.agents.remove(&agent_id);
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn get_ollama_auth(shortcut_state: State<'_, UnifiedShortcutState>) -> Result<AuthInfo, String> {
+    Ok(shortcut_state.config.lock().unwrap().providers.ollama_auth.info())
+}
+
+/// Set the headers and basic auth sent to the default Ollama URL
+#[tauri::command]
+pub async fn set_ollama_auth(
+    auth: AuthUpdate,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let auth = shortcut_state.config.lock().unwrap().providers.ollama_auth.updated(auth)?;
+    log::info!("Setting Ollama auth: {:?}", auth.info());
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.providers.ollama_auth = auth)
+}
+
+/// The policy of every provider, the default Ollama included
+#[tauri::command]
+pub async fn get_provider_policies(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<HashMap<String, RequestPolicy>, String> {
+    let config = shortcut_state.config.lock().unwrap();
+    let settings = &config.providers;
+    let ids = std::iter::once(DEFAULT_PROVIDER_ID).chain(settings.providers.iter().map(|p| p.id.as_str()));
+    Ok(ids
+        .map(|id| (id.to_string(), settings.policies.get(id).cloned().unwrap_or_default()))
+        .collect())
+}
+
+/// Set a provider's policy, or with `policy: null` go back to the default
+#[tauri::command]
+pub async fn set_provider_policy(
+    provider_id: String,
+    policy: Option<RequestPolicy>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    {
+        let config = shortcut_state.config.lock().unwrap();
+        if provider_id != DEFAULT_PROVIDER_ID && !config.providers.providers.iter().any(|p| p.id == provider_id) {
+            return Err(format!("Unknown provider {}", provider_id));
+        }
+    }
+    if let Some(policy) = &policy {
+        if policy.timeout_secs == 0 || policy.failure_threshold == 0 {
+            return Err("timeoutSecs and failureThreshold must be at least 1".to_string());
+        }
+    }
+    log::info!("Setting request policy for {}: {:?}", provider_id, policy);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| match policy {
+        Some(policy) => {
+            config.providers.policies.insert(provider_id, policy);
+        }
+        None => {
+            config.providers.policies.remove(&provider_id);
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn get_fallback_chain(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<FallbackChain, String> {
+    let config = shortcut_state.config.lock().unwrap();
+    Ok(FallbackChain {
+        providers: config.providers.fallback.clone(),
+        fallback_after_secs: config.providers.fallback_after_secs,
+    })
+}
+
+/// Set the providers tried, in order, when a request's own provider fails
+#[tauri::command]
+pub async fn set_fallback_chain(
+    chain: FallbackChain,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    {
+        let config = shortcut_state.config.lock().unwrap();
+        let known = |id: &String| id == DEFAULT_PROVIDER_ID || config.providers.providers.iter().any(|p| p.id == *id);
+        if let Some(unknown) = chain.providers.iter().find(|id| !known(id)) {
+            return Err(format!("Unknown provider {}", unknown));
+        }
+    }
+    if chain.fallback_after_secs == 0 {
+        return Err("fallbackAfterSecs must be at least 1".to_string());
+    }
+    log::info!("Setting inference fallback chain: {:?}", chain.providers);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.providers.fallback = chain.providers;
+        config.providers.fallback_after_secs = chain.fallback_after_secs;
+    })
+}
+
+/// Models a provider offers (`GET /models`); doubles as a connection and key check
+#[tauri::command]
+pub async fn list_provider_models(provider_id: String, app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let provider = resolve(&app_handle, None, Some(&provider_id))?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = provider
+        .authorize(client.get(format!("{}/models", provider.api_base())))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", provider.id, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} returned {}", provider.id, status));
+    }
+    let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let mut models: Vec<String> = body["data"]
+        .as_array()
+        .map(|data| data.iter().filter_map(|m| m["id"].as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    models.sort();
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_keep_unsent_secrets_and_headers_override_basic_auth() {
+        let saved = EndpointAuth {
+            headers: BTreeMap::from([("X-Api-Key".to_string(), "secret".to_string())]),
+            basic_auth: Some(BasicAuth { username: "me".to_string(), password: "pw".to_string() }),
+        };
+        let update: AuthUpdate = serde_json::from_value(serde_json::json!({
+            "headers": { "X-Api-Key": "", "X-Tenant": "lab" },
+            "basicAuth": { "username": "you" }
+        }))
+        .unwrap();
+        let auth = saved.updated(update).unwrap();
+        assert_eq!(auth.headers["X-Api-Key"], "secret");
+        assert_eq!(auth.headers["X-Tenant"], "lab");
+        assert_eq!(auth.basic_auth, Some(BasicAuth { username: "you".to_string(), password: "pw".to_string() }));
+        assert_eq!(auth.header_map()[AUTHORIZATION], "Basic eW91OnB3");
+
+        let cleared = auth.updated(serde_json::from_str(r#"{"basicAuth":{"username":""}}"#).unwrap()).unwrap();
+        assert_eq!(cleared.basic_auth, None);
+        assert_eq!(cleared.headers.len(), 2);
+        assert!(cleared.updated(serde_json::from_str(r#"{"headers":{"Bad Name":"x"}}"#).unwrap()).is_err());
+
+        let mut custom = cleared;
+        custom.basic_auth = Some(BasicAuth { username: "a".to_string(), password: "b".to_string() });
+        custom.headers.insert("authorization".to_string(), "Bearer proxy-token".to_string());
+        assert_eq!(custom.header_map()[AUTHORIZATION], "Bearer proxy-token");
+    }
+
+    fn resolved(id: &str) -> ResolvedProvider {
+        ResolvedProvider {
+            id: id.to_string(),
+            kind: ProviderKind::Ollama,
+            base_url: String::new(),
+            api_key: None,
+            model: None,
+            auth: EndpointAuth::default(),
+            policy: RequestPolicy::default(),
+        }
+    }
+
+    #[test]
+    fn chains_start_with_the_primary_and_skip_repeats_and_unknown_fallbacks() {
+        let fallback: Vec<String> = ["cloud", "local", "gone", "cloud", "lab"].map(String::from).to_vec();
+        let resolve = |id: &str| match id {
+            "gone" => Err(format!("Unknown provider {}", id)),
+            _ => Ok(resolved(id)),
+        };
+        let chain = build_chain(resolved("local"), &fallback, 30, resolve);
+        let ids: Vec<&str> = chain.providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["local", "cloud", "lab"]);
+        assert_eq!(chain.fallback_after, Duration::from_secs(30));
+
+        let alone = build_chain(resolved("local"), &[], 0, resolve);
+        assert_eq!(alone.providers.len(), 1);
+        assert_eq!(alone.fallback_after, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,314 @@
+// In src-tauri/src/rate_limit.rs
+//
+// Request limits for the API server, so a misbehaving client on the LAN (or behind a
+// reverse proxy) can't flood the capture machine:
+//
+// - rate: each client IP gets `burst` requests at once, refilled at `requestsPerMinute`;
+//   past that it gets 429 with `Retry-After`. Loopback clients (the app's own windows and
+//   local agents) and the local socket are exempt unless `exemptLoopback` is off. A
+//   loopback request carrying `X-Forwarded-For` came through a proxy on this machine and
+//   is counted all the same, against the proxy's address unless `trustForwardedFor` is on.
+// - body size: request bodies over `maxBodyBytes` get 413, except on the model proxies
+//   (the `/*path` routes), which carry images and allow `maxUploadBytes`.
+//
+// The gRPC server counts its calls against the same buckets (`check`); it only looks at
+// the forwarded-for header to tell proxied calls apart.
+//
+// Behind a reverse proxy every request comes from the proxy's address; with
+// `trustForwardedFor` the client is instead the last `X-Forwarded-For` entry, the one the
+// proxy added. Only turn it on when the port is reachable through the proxy alone, as
+// clients can send the header themselves. Changes apply to the next request.
+
+use crate::shortcuts::UnifiedShortcutState;
+use crate::AppState;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, MatchedPath, Request, State as AxumState};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http_body_util::{LengthLimitError, Limited};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Clients tracked before idle ones are forgotten
+const MAX_CLIENTS: usize = 10_000;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitSettings {
+    /// Sustained requests per client; 0 for no rate limit
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// Requests a client may make at once before the rate applies
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    #[serde(default = "default_true")]
+    pub exempt_loopback: bool,
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// For the model proxies, whose requests carry images
+    #[serde(default = "default_max_upload_bytes")]
+    pub max_upload_bytes: usize,
+}
+
+fn default_requests_per_minute() -> u32 {
+    300
+}
+
+fn default_burst() -> u32 {
+    60
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_upload_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: default_requests_per_minute(),
+            burst: default_burst(),
+            exempt_loopback: true,
+            trust_forwarded_for: false,
+            max_body_bytes: default_max_body_bytes(),
+            max_upload_bytes: default_max_upload_bytes(),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Tokens it would have at `now`, up to `burst`
+    fn available(&self, now: Instant, per_second: f64, burst: f64) -> f64 {
+        (self.tokens + now.duration_since(self.updated).as_secs_f64() * per_second).min(burst)
+    }
+
+    /// Refill for the time since the last request, up to `burst`
+    fn refill(&mut self, now: Instant, per_second: f64, burst: f64) {
+        self.tokens = self.available(now, per_second, burst);
+        self.updated = now;
+    }
+
+    /// Spend a request, or say how long until the next one is allowed
+    fn take(&mut self, now: Instant, per_second: f64, burst: f64) -> Result<(), Duration> {
+        self.refill(now, per_second, burst);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        }
+    }
+}
+
+/// Token buckets by client IP
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    fn take(&self, client: IpAddr, settings: &LimitSettings, now: Instant) -> Result<(), Duration> {
+        let per_second = settings.requests_per_minute as f64 / 60.0;
+        let burst = settings.burst.max(1) as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_CLIENTS && !buckets.contains_key(&client) {
+            // A full bucket is a client that has been idle long enough to be forgotten
+            buckets.retain(|_, bucket| bucket.available(now, per_second, burst) < burst);
+            // All of them busy: the memory cap still holds, so the longest quiet one goes
+            if buckets.len() >= MAX_CLIENTS {
+                let oldest = buckets.iter().min_by_key(|(_, bucket)| bucket.updated).map(|(ip, _)| *ip);
+                if let Some(oldest) = oldest {
+                    buckets.remove(&oldest);
+                }
+            }
+        }
+        buckets.entry(client).or_insert(Bucket { tokens: burst, updated: now }).take(now, per_second, burst)
+    }
+}
+
+/// The address requests are counted against; None for the local socket, which has none
+fn client_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trust_forwarded_for: bool) -> Option<IpAddr> {
+    let peer = peer?;
+    let forwarded = forwarded_for
+        .filter(|_| trust_forwarded_for)
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+    Some(forwarded.unwrap_or(peer))
+}
+
+/// The client to count a request against, if any. `proxied` requests carry a forwarded-for
+/// header: from a loopback peer that is a local reverse proxy, whose clients aren't exempt.
+fn counted(settings: &LimitSettings, client: Option<IpAddr>, proxied: bool) -> Option<IpAddr> {
+    client
+        .filter(|ip| !(settings.exempt_loopback && ip.is_loopback() && !proxied))
+        .filter(|_| settings.requests_per_minute > 0)
+}
+
+/// Spend one of `client`'s requests, or say how long it has to wait. Nothing is counted
+/// for the local socket (None), exempt loopback clients or with no rate set.
+pub fn check(
+    app_handle: &AppHandle,
+    settings: &LimitSettings,
+    client: Option<IpAddr>,
+    proxied: bool,
+) -> Result<(), Duration> {
+    match counted(settings, client, proxied) {
+        Some(client) => app_handle.state::<RateLimiter>().take(client, settings, Instant::now()),
+        None => Ok(()),
+    }
+}
+
+/// The model proxies are the catch-all routes
+fn is_upload_route(route: &str) -> bool {
+    route.ends_with("/*path")
+}
+
+/// Whether `error`, or one it wraps, is a body going over its limit
+pub fn too_large(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if error.is::<LengthLimitError>() {
+            return true;
+        }
+        current = error.source();
+    }
+    false
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, axum::Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// Middleware for the API routes: refuse clients over their rate and bodies over their size
+pub async fn limit_requests(AxumState(state): AxumState<AppState>, request: Request, next: Next) -> Response {
+    let settings = state.app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().api_server.limits.clone();
+
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(address)| address.ip());
+    let forwarded_for = request.headers().get(FORWARDED_FOR).and_then(|v| v.to_str().ok());
+    if forwarded_for.is_some() && !settings.trust_forwarded_for {
+        // Once is enough; behind a proxy every request carries it
+        static WARNED: AtomicBool = AtomicBool::new(false);
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "API request from {:?} carries X-Forwarded-For but trustForwardedFor is off; \
+                 clients are counted against the proxy's address",
+                peer
+            );
+        }
+    }
+    let client = client_ip(peer, forwarded_for, settings.trust_forwarded_for);
+    if let Err(wait) = check(&state.app_handle, &settings, client, forwarded_for.is_some()) {
+        log::debug!("Rate limited {:?} on {}", client, request.uri().path());
+        let retry_after = wait.as_secs_f64().ceil().max(1.0).to_string();
+        let mut response = error(StatusCode::TOO_MANY_REQUESTS, "Too many requests");
+        if let Ok(value) = retry_after.parse() {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        return response;
+    }
+
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str()).unwrap_or_default();
+    let limit = match is_upload_route(route) {
+        true => settings.max_upload_bytes,
+        false => settings.max_body_bytes,
+    };
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limit) {
+        return error(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large");
+    }
+    // Bodies without a length are cut off as they arrive
+    let (parts, body) = request.into_parts();
+    next.run(Request::from_parts(parts, Body::new(Limited::new(body, limit)))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_each_client_to_its_rate() {
+        let settings = LimitSettings { requests_per_minute: 60, burst: 2, ..Default::default() };
+        let limiter = RateLimiter::default();
+        let (a, b): (IpAddr, IpAddr) = ("10.0.0.2".parse().unwrap(), "10.0.0.3".parse().unwrap());
+        let start = Instant::now();
+        assert!(limiter.take(a, &settings, start).is_ok());
+        assert!(limiter.take(a, &settings, start).is_ok());
+        let wait = limiter.take(a, &settings, start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(1));
+        // Other clients have their own bucket
+        assert!(limiter.take(b, &settings, start).is_ok());
+        assert!(limiter.take(a, &settings, start + Duration::from_secs(1)).is_ok());
+
+        let proxy = Some("127.0.0.1".parse().unwrap());
+        assert_eq!(client_ip(proxy, Some("1.2.3.4, 10.0.0.2"), true), Some(a));
+        assert_eq!(client_ip(proxy, Some("10.0.0.2"), false), proxy);
+        assert_eq!(client_ip(None, Some("10.0.0.2"), true), None);
+        assert!(is_upload_route("/v1/*path"));
+        assert!(!is_upload_route("/api/capture/start"));
+    }
+
+    #[test]
+    fn forgets_the_quietest_client_once_full() {
+        let settings = LimitSettings { requests_per_minute: 60, burst: 2, ..Default::default() };
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        // Every client spends its whole burst, so none of them has refilled
+        for n in 0..MAX_CLIENTS as u32 {
+            let ip = IpAddr::from(std::net::Ipv4Addr::from(0x0a00_0000 + n));
+            let at = start + Duration::from_micros(n as u64);
+            limiter.take(ip, &settings, at).unwrap();
+            limiter.take(ip, &settings, at).unwrap();
+        }
+        let first = IpAddr::from(std::net::Ipv4Addr::from(0x0a00_0000));
+        let last = IpAddr::from(std::net::Ipv4Addr::from(0x0a00_0000 + MAX_CLIENTS as u32 - 1));
+        let newcomer: IpAddr = "192.168.1.9".parse().unwrap();
+        let now = start + Duration::from_millis(200);
+        assert!(limiter.take(newcomer, &settings, now).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().len(), MAX_CLIENTS);
+        // The oldest was dropped and starts over with a fresh burst; the newest still waits
+        assert!(!limiter.buckets.lock().unwrap().contains_key(&first));
+        assert!(limiter.take(last, &settings, now).is_err());
+    }
+
+    #[test]
+    fn exempts_loopback_only_when_not_proxied() {
+        let settings = LimitSettings::default();
+        let (local, remote): (IpAddr, IpAddr) = ("127.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+        assert_eq!(counted(&settings, Some(local), false), None);
+        // A reverse proxy on this machine still gets counted
+        assert_eq!(counted(&settings, Some(local), true), Some(local));
+        assert_eq!(counted(&settings, Some(remote), false), Some(remote));
+        assert_eq!(counted(&settings, None, true), None);
+
+        let strict = LimitSettings { exempt_loopback: false, ..Default::default() };
+        assert_eq!(counted(&strict, Some(local), false), Some(local));
+        let unlimited = LimitSettings { requests_per_minute: 0, ..Default::default() };
+        assert_eq!(counted(&unlimited, Some(remote), false), None);
+    }
+}
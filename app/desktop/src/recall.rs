@@ -0,0 +1,283 @@
+// In src-tauri/src/recall.rs
+//
+// Retrieval-augmented context from capture history. With history recording on, text read
+// off the screen (`ocr_image`, `ocr_frame`) and transcript segments handed over by the
+// frontend are embedded into the `capture-history` collection of `vector_store`. Before
+// an `inference_chat` request of an agent with recall configured, the observations most
+// similar to its latest user message are looked up and put in front of the conversation
+// as a system message, so the model can draw on what was seen or heard earlier.
+//
+// Retrieval never fails a request: if the embedding provider is down, the request goes
+// out without the extra context.
+
+use crate::inference::{ChatMessage, ChatRequest, ContentPart, MessageContent};
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::vector_store::{self, SearchHit};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+pub const HISTORY_COLLECTION: &str = "capture-history";
+
+/// Shorter text (a clock, a lone button label) isn't worth remembering
+const MIN_OBSERVATION_CHARS: usize = 16;
+/// Longer text is cut before embedding
+const MAX_OBSERVATION_CHARS: usize = 2000;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Ocr,
+    Transcript,
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Source::Ocr => "screen text",
+            Source::Transcript => "transcript",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRecall {
+    /// Observations added to each request, at most
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// Cosine similarity an observation needs to be included
+    #[serde(default = "default_min_score")]
+    pub min_score: f32,
+    #[serde(default = "default_sources")]
+    pub sources: Vec<Source>,
+}
+
+fn default_top_k() -> usize {
+    5
+}
+
+fn default_min_score() -> f32 {
+    0.5
+}
+
+fn default_sources() -> Vec<Source> {
+    vec![Source::Ocr, Source::Transcript]
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecallSettings {
+    /// Embed OCR text and transcripts into the history collection
+    #[serde(default)]
+    pub record_history: bool,
+    /// agent_id -> retrieval settings; agents not listed get no extra context
+    #[serde(default)]
+    pub agents: HashMap<String, AgentRecall>,
+}
+
+/// Last text recorded per source and agent, so an unchanged screen isn't stored again
+#[derive(Default)]
+pub struct RecallState {
+    last: Mutex<HashMap<(Source, Option<String>), u64>>,
+}
+
+fn settings(app_handle: &AppHandle) -> RecallSettings {
+    app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().recall.clone()
+}
+
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_OBSERVATION_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Add `text` to the capture history in the background, if recording is on
+pub fn observe(app_handle: &AppHandle, source: Source, text: &str, agent_id: Option<&str>, timestamp_ms: i64) {
+    let text = truncate(text.trim());
+    if text.chars().count() < MIN_OBSERVATION_CHARS
+        || !settings(app_handle).record_history
+        || crate::incognito::is_active(app_handle)
+    {
+        return;
+    }
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    let hash = hasher.finish();
+    let key = (source, agent_id.map(String::from));
+    if app_handle.state::<RecallState>().last.lock().unwrap().insert(key, hash) == Some(hash) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let text = text.to_string();
+    let metadata = serde_json::json!({ "source": source, "agentId": agent_id, "timestamp": timestamp_ms });
+    let agent_id = agent_id.map(String::from);
+    tauri::async_runtime::spawn(async move {
+        let stored = vector_store::embed_and_store(
+            &app_handle,
+            HISTORY_COLLECTION,
+            vec![text],
+            vec![Some(metadata)],
+            agent_id.as_deref(),
+        );
+        if let Err(e) = stored.await {
+            log::warn!("Failed to record {:?} observation: {}", source, e);
+        }
+    });
+}
+
+/// The text of the last user message, images left out
+fn query_text(messages: &[ChatMessage]) -> Option<String> {
+    let message = messages.iter().rev().find(|message| message.role == "user")?;
+    let text = match &message.content {
+        MessageContent::Text(text) => text.clone(),
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(text.as_str()),
+                ContentPart::ImageUrl { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn source_of(hit: &SearchHit) -> Option<Source> {
+    serde_json::from_value(hit.metadata.as_ref()?.get("source")?.clone()).ok()
+}
+
+/// The system message listing `hits`, most relevant first
+fn context_message(hits: &[SearchHit]) -> ChatMessage {
+    let mut context = "Possibly relevant earlier observations, most relevant first:".to_string();
+    for hit in hits {
+        let timestamp = hit
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata["timestamp"].as_i64())
+            .unwrap_or(hit.created_at);
+        let when = chrono::DateTime::from_timestamp_millis(timestamp)
+            .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let source = source_of(hit).map_or("observation", Source::label);
+        context.push_str(&format!("\n- [{}, {}] {}", when, source, hit.text.replace('\n', " ")));
+    }
+    ChatMessage::text("system", context)
+}
+
+/// Put the observations most relevant to `request` in front of its messages, if its agent
+/// has recall configured. Returns how many were added.
+pub async fn augment(app_handle: &AppHandle, request: &mut ChatRequest) -> usize {
+    let Some(agent_id) = request.agent_id.clone() else {
+        return 0;
+    };
+    let Some(recall) = settings(app_handle).agents.remove(&agent_id) else {
+        return 0;
+    };
+    let Some(query) = query_text(&request.messages) else {
+        return 0;
+    };
+    if recall.top_k == 0 || recall.sources.is_empty() {
+        return 0;
+    }
+
+    // Extra candidates, as some may be from sources the agent doesn't use
+    let found = vector_store::similarity_search(
+        app_handle,
+        HISTORY_COLLECTION,
+        &query,
+        recall.top_k * 2,
+        Some(recall.min_score),
+        Some(&agent_id),
+    )
+    .await;
+    let hits: Vec<SearchHit> = match found {
+        Ok(hits) => hits
+            .into_iter()
+            .filter(|hit| source_of(hit).is_some_and(|source| recall.sources.contains(&source)))
+            .take(recall.top_k)
+            .collect(),
+        Err(e) => {
+            log::warn!("Recall for agent {} skipped: {}", agent_id, e);
+            return 0;
+        }
+    };
+    if !hits.is_empty() {
+        request.messages.insert(0, context_message(&hits));
+    }
+    hits.len()
+}
+
+// Tauri commands
+
+/// Add a transcript segment (or other observation) to the capture history
+#[tauri::command]
+pub async fn record_observation(
+    source: Source,
+    text: String,
+    agent_id: Option<String>,
+    timestamp_ms: Option<i64>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let timestamp_ms = timestamp_ms.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    observe(&app_handle, source, &text, agent_id.as_deref(), timestamp_ms);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_recall_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<RecallSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().recall.clone())
+}
+
+#[tauri::command]
+pub async fn set_recall_settings(
+    settings: RecallSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting recall settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.recall = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_context_from_the_last_user_message() {
+        let messages = vec![
+            ChatMessage::text("user", "What was I reading?"),
+            ChatMessage::text("assistant", "A paper."),
+            ChatMessage {
+                content: MessageContent::Parts(vec![
+                    ContentPart::Text { text: " Which invoice was open yesterday? ".to_string() },
+                    ContentPart::ImageUrl { image_url: crate::inference::ImageUrl { url: "data:,".to_string() } },
+                ]),
+                ..ChatMessage::text("user", "")
+            },
+        ];
+        assert_eq!(query_text(&messages).as_deref(), Some("Which invoice was open yesterday?"));
+        assert_eq!(query_text(&[ChatMessage::text("system", "Be brief.")]), None);
+
+        let hit = SearchHit {
+            id: 1,
+            text: "Invoice #4411\nAcme Corp".to_string(),
+            metadata: Some(serde_json::json!({ "source": "ocr", "timestamp": 0 })),
+            score: 0.8,
+            created_at: 0,
+        };
+        assert_eq!(source_of(&hit), Some(Source::Ocr));
+        let MessageContent::Text(context) = context_message(&[hit]).content else {
+            panic!("expected text");
+        };
+        assert!(context.ends_with(", screen text] Invoice #4411 Acme Corp"), "{}", context);
+    }
+}
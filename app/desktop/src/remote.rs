@@ -0,0 +1,947 @@
+// In src-tauri/src/remote.rs
+//
+// Remote observer mode. A lightweight "sender" instance streams its screen to a
+// "receiver" instance that runs the agents and models (a laptop feeding a desktop GPU).
+//
+// - Pairing: the receiver shows a short one-time code; the sender connects with it once
+//   and both sides remember each other's long-term X25519 public key.
+// - Sessions: every connection does an ephemeral X25519 exchange mixed with the static
+//   keys (and the pairing code while pairing), so only paired devices can talk and each
+//   session has fresh keys. Everything after the hello is ChaCha20-Poly1305 encrypted.
+// - The sender reconnects with backoff; the receiver forwards frames to the frontend on
+//   a channel shaped exactly like the local capture stream.
+//
+// The link runs on its own port (0.0.0.0:3839 by default) because the main API only
+// listens on localhost.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State as AxumState;
+use axum::response::IntoResponse;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use futures_util::{SinkExt, StreamExt};
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, Response};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_screen_capture::frames;
+use tauri_plugin_screen_capture::wire::{self, FrameHeader, FrameSink};
+use tokio::sync::watch;
+use x25519_dalek::{EphemeralSecret, PublicKey, SharedSecret, StaticSecret};
+
+const PROTOCOL_VERSION: u32 = 1;
+const PAIRING_CODE_TTL: Duration = Duration::from_secs(300);
+/// Unambiguous characters (no 0/O, 1/I/L) for codes typed in by hand
+const PAIRING_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const PAIRING_CODE_LEN: usize = 8;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Encrypted message tags
+const TAG_CONTROL: u8 = 0;
+const TAG_FRAME: u8 = 1;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteRole {
+    #[default]
+    Off,
+    /// Capture here and stream to a receiver
+    Sender,
+    /// Accept streams from paired senders
+    Receiver,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PairedDevice {
+    pub device_id: String,
+    pub name: String,
+    /// Base64 X25519 public key
+    pub public_key: String,
+    /// Unix seconds
+    pub paired_at: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSettings {
+    #[serde(default)]
+    pub role: RemoteRole,
+    /// Name shown to the other side
+    #[serde(default = "default_device_name")]
+    pub device_name: String,
+    /// Receiver: port the link listens on
+    #[serde(default = "default_listen_port")]
+    pub listen_port: u16,
+    /// Sender: receiver's host:port
+    #[serde(default)]
+    pub receiver_addr: Option<String>,
+    /// Sender: capture target to stream (primary monitor if None)
+    #[serde(default)]
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub paired: Vec<PairedDevice>,
+}
+
+fn default_device_name() -> String {
+    "Observer".to_string()
+}
+
+fn default_listen_port() -> u16 {
+    3839
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            role: RemoteRole::Off,
+            device_name: default_device_name(),
+            listen_port: default_listen_port(),
+            receiver_addr: None,
+            target_id: None,
+            paired: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteStatus {
+    role: RemoteRole,
+    /// Names of connected peers
+    peers: Vec<String>,
+    last_error: Option<String>,
+}
+
+struct PendingPairing {
+    code: String,
+    expires: Instant,
+}
+
+pub struct RemoteState {
+    identity: StaticSecret,
+    pairing: Mutex<Option<PendingPairing>>,
+    status: Mutex<RemoteStatus>,
+    /// Receiver: frontend channel decrypted frames are forwarded to
+    frame_channel: Mutex<Option<FrameSink>>,
+    /// Bumped on every settings change; running tasks exit when it moves
+    generation: watch::Sender<u64>,
+    /// Sender: whether we started capture ourselves (and should stop it)
+    started_capture: AtomicBool,
+}
+
+impl RemoteState {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        Self {
+            identity: load_identity(app_handle),
+            pairing: Mutex::new(None),
+            status: Mutex::new(RemoteStatus::default()),
+            frame_channel: Mutex::new(None),
+            generation: watch::channel(0).0,
+            started_capture: AtomicBool::new(false),
+        }
+    }
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey::from(&self.identity)
+    }
+}
+
+/// Long-term identity key, kept next to the other app data
+fn load_identity(app_handle: &AppHandle) -> StaticSecret {
+    let path = app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join("remote_identity.key"));
+
+    if let Ok(path) = &path {
+        if let Ok(bytes) = std::fs::read(path) {
+            if let Ok(bytes) = <[u8; 32]>::try_from(bytes.as_slice()) {
+                return StaticSecret::from(bytes);
+            }
+            log::warn!("Remote identity key is corrupt, generating a new one");
+        }
+    }
+
+    let secret = StaticSecret::random_from_rng(OsRng);
+    match &path {
+        Ok(path) => {
+            let write = path
+                .parent()
+                .map(std::fs::create_dir_all)
+                .transpose()
+                .and_then(|_| std::fs::write(path, secret.to_bytes()));
+            if let Err(e) = write {
+                log::error!("Failed to save remote identity key: {}", e);
+            }
+        }
+        Err(e) => log::error!("No app data dir for remote identity key: {}", e),
+    }
+    secret
+}
+
+fn device_id(public: &PublicKey) -> String {
+    Sha256::digest(public.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn encode_key(public: &PublicKey) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(public.as_bytes())
+}
+
+fn decode_key(key: &str) -> Result<PublicKey, String> {
+    use base64::Engine;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(key)
+        .map_err(|e| format!("Invalid key: {}", e))?;
+    let bytes = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| "Invalid key length")?;
+    Ok(PublicKey::from(bytes))
+}
+
+fn generate_pairing_code() -> String {
+    (0..PAIRING_CODE_LEN)
+        .map(|_| {
+            let i = OsRng.next_u32() as usize % PAIRING_ALPHABET.len();
+            PAIRING_ALPHABET[i] as char
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Handshake and session crypto
+// ---------------------------------------------------------------------------
+
+/// First (plaintext) message each side sends
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Hello {
+    version: u32,
+    device_id: String,
+    name: String,
+    static_key: String,
+    ephemeral_key: String,
+    #[serde(default)]
+    pairing: bool,
+}
+
+/// Encrypted control messages
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Control {
+    /// Sender -> receiver: proves it derived the same keys
+    Ready,
+    /// Receiver -> sender: session accepted
+    Accepted,
+}
+
+/// One direction of an encrypted session; nonces are a message counter
+struct Cipher {
+    aead: ChaCha20Poly1305,
+    counter: u64,
+}
+
+impl Cipher {
+    fn new(key: &[u8]) -> Self {
+        Self {
+            aead: ChaCha20Poly1305::new(Key::from_slice(key)),
+            counter: 0,
+        }
+    }
+
+    fn nonce(&mut self) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter += 1;
+        *Nonce::from_slice(&nonce)
+    }
+
+    fn seal(&mut self, tag: u8, payload: &[u8]) -> Vec<u8> {
+        let mut plaintext = Vec::with_capacity(payload.len() + 1);
+        plaintext.push(tag);
+        plaintext.extend_from_slice(payload);
+        let nonce = self.nonce();
+        self.aead
+            .encrypt(&nonce, plaintext.as_slice())
+            .expect("ChaCha20-Poly1305 encryption cannot fail for in-memory buffers")
+    }
+
+    fn open(&mut self, ciphertext: &[u8]) -> Result<(u8, Vec<u8>), String> {
+        let nonce = self.nonce();
+        let mut plaintext = self
+            .aead
+            .decrypt(&nonce, ciphertext)
+            .map_err(|_| "Decryption failed (wrong pairing code or tampered message)")?;
+        if plaintext.is_empty() {
+            return Err("Empty message".to_string());
+        }
+        let tag = plaintext.remove(0);
+        Ok((tag, plaintext))
+    }
+}
+
+struct Session {
+    send: Cipher,
+    recv: Cipher,
+}
+
+/// Derive both directions' keys from the ephemeral and static DH results. The pairing
+/// code is mixed in as the HKDF salt, so a wrong code yields keys that fail to decrypt.
+fn derive_session(
+    ee: &SharedSecret,
+    ss: &SharedSecret,
+    pairing_code: Option<&str>,
+    sender: &Hello,
+    receiver: &Hello,
+    is_sender: bool,
+) -> Session {
+    let mut ikm = Vec::with_capacity(64);
+    ikm.extend_from_slice(ee.as_bytes());
+    ikm.extend_from_slice(ss.as_bytes());
+    let transcript = format!(
+        "observer-remote-v{}|{}|{}|{}|{}",
+        PROTOCOL_VERSION,
+        sender.static_key,
+        sender.ephemeral_key,
+        receiver.static_key,
+        receiver.ephemeral_key
+    );
+
+    let hkdf = Hkdf::<Sha256>::new(pairing_code.map(str::as_bytes), &ikm);
+    let mut okm = [0u8; 64];
+    hkdf.expand(transcript.as_bytes(), &mut okm)
+        .expect("64 bytes is a valid HKDF-SHA256 output length");
+    let (to_receiver, to_sender) = okm.split_at(32);
+
+    if is_sender {
+        Session {
+            send: Cipher::new(to_receiver),
+            recv: Cipher::new(to_sender),
+        }
+    } else {
+        Session {
+            send: Cipher::new(to_sender),
+            recv: Cipher::new(to_receiver),
+        }
+    }
+}
+
+fn hello(state: &RemoteState, name: &str, ephemeral: &PublicKey, pairing: bool) -> Hello {
+    let public = state.public_key();
+    Hello {
+        version: PROTOCOL_VERSION,
+        device_id: device_id(&public),
+        name: name.to_string(),
+        static_key: encode_key(&public),
+        ephemeral_key: encode_key(ephemeral),
+        pairing,
+    }
+}
+
+/// Frames travel in the same layout the capture plugin uses for binary IPC, so the
+/// receiver can hand them to the frontend without re-encoding
+pub(crate) fn encode_frame(frame: &frames::Frame) -> Vec<u8> {
+    let header = FrameHeader {
+        format: frame.format,
+        timestamp: frame.timestamp,
+        width: frame.width,
+        height: frame.height,
+        frame_count: frame.frame_count,
+        source: None,
+        delta: None,
+        yuv: None,
+        change: None,
+        stats: None,
+    };
+    wire::encode(&header, &frame.data)
+}
+
+// ---------------------------------------------------------------------------
+// Status
+// ---------------------------------------------------------------------------
+
+fn update_status(app_handle: &AppHandle, update: impl FnOnce(&mut RemoteStatus)) {
+    let status = {
+        let state = app_handle.state::<RemoteState>();
+        let mut status = state.status.lock().unwrap();
+        update(&mut status);
+        status.clone()
+    };
+    if let Err(e) = app_handle.emit("remote-status-changed", &status) {
+        log::warn!("Failed to emit remote-status-changed event: {}", e);
+    }
+}
+
+fn settings(app_handle: &AppHandle) -> RemoteSettings {
+    app_handle
+        .state::<UnifiedShortcutState>()
+        .config
+        .lock()
+        .unwrap()
+        .remote
+        .clone()
+}
+
+// ---------------------------------------------------------------------------
+// Receiver
+// ---------------------------------------------------------------------------
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    AxumState(app_handle): AxumState<AppHandle>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| async move {
+        if let Err(e) = handle_sender(app_handle.clone(), socket).await {
+            log::warn!("Remote sender connection ended: {}", e);
+            update_status(&app_handle, |s| s.last_error = Some(e));
+        }
+    })
+}
+
+async fn handle_sender(app_handle: AppHandle, socket: WebSocket) -> Result<(), String> {
+    let (mut tx, mut rx) = socket.split();
+    let state = app_handle.state::<RemoteState>();
+    let config = settings(&app_handle);
+
+    let first = tokio::time::timeout(HANDSHAKE_TIMEOUT, rx.next())
+        .await
+        .map_err(|_| "Handshake timed out")?;
+    let peer: Hello = match first {
+        Some(Ok(Message::Text(text))) => {
+            serde_json::from_str(&text).map_err(|e| format!("Invalid hello: {}", e))?
+        }
+        _ => return Err("Expected hello".to_string()),
+    };
+    if peer.version != PROTOCOL_VERSION {
+        return Err(format!("Unsupported protocol version {}", peer.version));
+    }
+
+    // Unknown devices may only connect while a pairing code is active
+    let pairing_code = if peer.pairing {
+        let pending = state.pairing.lock().unwrap();
+        match pending.as_ref() {
+            Some(p) if p.expires > Instant::now() => Some(p.code.clone()),
+            _ => return Err(format!("Pairing attempt from '{}' without an active code", peer.name)),
+        }
+    } else {
+        if !config.paired.iter().any(|d| d.public_key == peer.static_key) {
+            return Err(format!("Unpaired device '{}' rejected", peer.name));
+        }
+        None
+    };
+
+    let peer_static = decode_key(&peer.static_key)?;
+    let peer_ephemeral = decode_key(&peer.ephemeral_key)?;
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let own = hello(&state, &config.device_name, &PublicKey::from(&ephemeral), peer.pairing);
+    tx.send(Message::Text(serde_json::to_string(&own).unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let ee = ephemeral.diffie_hellman(&peer_ephemeral);
+    let ss = state.identity.diffie_hellman(&peer_static);
+    let mut session = derive_session(&ee, &ss, pairing_code.as_deref(), &peer, &own, false);
+
+    // The sender's first encrypted message proves it has the same keys (and code)
+    let ready = tokio::time::timeout(HANDSHAKE_TIMEOUT, rx.next())
+        .await
+        .map_err(|_| "Handshake timed out")?;
+    match ready {
+        Some(Ok(Message::Binary(data))) => match session.recv.open(&data)? {
+            (TAG_CONTROL, body) if matches!(serde_json::from_slice(&body), Ok(Control::Ready)) => {}
+            _ => return Err("Expected ready".to_string()),
+        },
+        _ => return Err("Expected ready".to_string()),
+    }
+
+    if peer.pairing {
+        *state.pairing.lock().unwrap() = None;
+        let device = PairedDevice {
+            device_id: peer.device_id.clone(),
+            name: peer.name.clone(),
+            public_key: peer.static_key.clone(),
+            paired_at: chrono::Utc::now().timestamp(),
+        };
+        let shortcut_state = app_handle.state::<UnifiedShortcutState>();
+        shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+            config.remote.paired.retain(|d| d.public_key != device.public_key);
+            config.remote.paired.push(device);
+        })?;
+        log::info!("Paired with remote sender '{}'", peer.name);
+        let _ = app_handle.emit("remote-device-paired", &peer.name);
+    }
+
+    let accepted = serde_json::to_vec(&Control::Accepted).unwrap_or_default();
+    tx.send(Message::Binary(session.send.seal(TAG_CONTROL, &accepted)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    log::info!("Remote sender '{}' connected", peer.name);
+    update_status(&app_handle, |s| {
+        s.peers.push(peer.name.clone());
+        s.last_error = None;
+    });
+
+    let mut generation = state.generation.subscribe();
+    let result = loop {
+        tokio::select! {
+            message = rx.next() => match message {
+                Some(Ok(Message::Binary(data))) => match session.recv.open(&data) {
+                    Ok((TAG_FRAME, payload)) => {
+                        if wire::decode(&payload).is_none() {
+                            break Err("Invalid frame".to_string());
+                        }
+                        if let Some(sink) = state.frame_channel.lock().unwrap().as_ref() {
+                            let _ = sink.send_binary(payload);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => break Err(e),
+                },
+                Some(Ok(Message::Close(_))) | None => break Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => break Err(e.to_string()),
+            },
+            _ = generation.changed() => break Ok(()),
+        }
+    };
+
+    log::info!("Remote sender '{}' disconnected", peer.name);
+    update_status(&app_handle, |s| {
+        if let Some(i) = s.peers.iter().position(|p| *p == peer.name) {
+            s.peers.remove(i);
+        }
+    });
+    result
+}
+
+async fn run_receiver(app_handle: AppHandle, port: u16, mut generation: watch::Receiver<u64>) {
+    let router = axum::Router::new()
+        .route("/remote/ws", axum::routing::get(ws_handler))
+        .with_state(app_handle.clone());
+
+    let listener = match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Remote receiver failed to bind port {}: {}", port, e);
+            update_status(&app_handle, |s| {
+                s.last_error = Some(format!("Failed to listen on port {}: {}", port, e))
+            });
+            return;
+        }
+    };
+    log::info!("Remote receiver listening on 0.0.0.0:{}", port);
+
+    let shutdown = async move {
+        let _ = generation.changed().await;
+    };
+    if let Err(e) = axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown)
+        .await
+    {
+        log::error!("Remote receiver stopped: {}", e);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sender
+// ---------------------------------------------------------------------------
+
+type ClientStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Connect to a receiver and run the handshake. With `pairing_code` set this is a first
+/// contact; otherwise the receiver must already be in the paired list.
+async fn connect(
+    app_handle: &AppHandle,
+    addr: &str,
+    pairing_code: Option<&str>,
+) -> Result<(ClientStream, Session, Hello), String> {
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    let state = app_handle.state::<RemoteState>();
+    let config = settings(app_handle);
+
+    let url = format!("ws://{}/remote/ws", addr);
+    let (mut ws, _) = tokio::time::timeout(HANDSHAKE_TIMEOUT, tokio_tungstenite::connect_async(&url))
+        .await
+        .map_err(|_| format!("Timed out connecting to {}", addr))?
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let own = hello(&state, &config.device_name, &PublicKey::from(&ephemeral), pairing_code.is_some());
+    ws.send(WsMessage::Text(serde_json::to_string(&own).unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let peer: Hello = match tokio::time::timeout(HANDSHAKE_TIMEOUT, ws.next()).await {
+        Ok(Some(Ok(WsMessage::Text(text)))) => {
+            serde_json::from_str(&text).map_err(|e| format!("Invalid hello: {}", e))?
+        }
+        Ok(_) => return Err("Receiver closed the connection (not paired?)".to_string()),
+        Err(_) => return Err("Handshake timed out".to_string()),
+    };
+    if pairing_code.is_none() && !config.paired.iter().any(|d| d.public_key == peer.static_key) {
+        return Err(format!("Receiver '{}' is not a paired device", peer.name));
+    }
+
+    let ee = ephemeral.diffie_hellman(&decode_key(&peer.ephemeral_key)?);
+    let ss = state.identity.diffie_hellman(&decode_key(&peer.static_key)?);
+    let mut session = derive_session(&ee, &ss, pairing_code, &own, &peer, true);
+
+    let ready = serde_json::to_vec(&Control::Ready).unwrap_or_default();
+    ws.send(WsMessage::Binary(session.send.seal(TAG_CONTROL, &ready)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match tokio::time::timeout(HANDSHAKE_TIMEOUT, ws.next()).await {
+        Ok(Some(Ok(WsMessage::Binary(data)))) => match session.recv.open(&data)? {
+            (TAG_CONTROL, body) if matches!(serde_json::from_slice(&body), Ok(Control::Accepted)) => {}
+            _ => return Err("Unexpected response from receiver".to_string()),
+        },
+        Ok(_) => return Err("Receiver rejected the session (wrong or expired code?)".to_string()),
+        Err(_) => return Err("Handshake timed out".to_string()),
+    }
+
+    Ok((ws, session, peer))
+}
+
+/// Start local capture for the link if nothing else is capturing
+fn ensure_capture(app_handle: &AppHandle, target_id: Option<String>) -> Result<(), String> {
+    let active = tauri_plugin_screen_capture::desktop::get_broadcast_status()
+        .map(|status| status["isActive"].as_bool().unwrap_or(false))
+        .unwrap_or(false);
+    if active {
+        return Ok(());
+    }
+    if crate::incognito::is_active(app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    // Frames reach us through the frame tap; the channel itself is a no-op
+    tauri_plugin_screen_capture::desktop::start_capture_stream(target_id, FrameSink::discard())
+        .map_err(|e| e.to_string())?;
+    app_handle
+        .state::<RemoteState>()
+        .started_capture
+        .store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+async fn stream_frames(
+    app_handle: &AppHandle,
+    mut ws: ClientStream,
+    mut session: Session,
+    generation: &mut watch::Receiver<u64>,
+) -> Result<(), String> {
+    use tokio::sync::broadcast::error::RecvError;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    let mut frames_rx = frames::subscribe();
+    ensure_capture(app_handle, settings(app_handle).target_id)?;
+
+    loop {
+        tokio::select! {
+            frame = frames_rx.recv() => match frame {
+                Ok(frame) => {
+                    let sealed = session.send.seal(TAG_FRAME, &encode_frame(&frame));
+                    ws.send(WsMessage::Binary(sealed)).await.map_err(|e| e.to_string())?;
+                }
+                // Network slower than capture: skip ahead to the newest frames
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = ws.next() => match message {
+                Some(Ok(WsMessage::Close(_))) | None => return Err("Receiver closed the connection".to_string()),
+                Some(Err(e)) => return Err(e.to_string()),
+                Some(Ok(_)) => {}
+            },
+            _ = generation.changed() => {
+                let _ = ws.close(None).await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn run_sender(app_handle: AppHandle, addr: String, mut generation: watch::Receiver<u64>) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        // The receiver may be reachable over the tailnet when its LAN address isn't
+        let resolved = crate::tailnet::resolve(&app_handle, &addr);
+        match connect(&app_handle, &resolved, None).await {
+            Ok((ws, session, peer)) => {
+                log::info!("Streaming to remote receiver '{}' at {}", peer.name, addr);
+                backoff = Duration::from_secs(1);
+                update_status(&app_handle, |s| {
+                    s.peers = vec![peer.name.clone()];
+                    s.last_error = None;
+                });
+                let result = stream_frames(&app_handle, ws, session, &mut generation).await;
+                update_status(&app_handle, |s| {
+                    s.peers.clear();
+                    s.last_error = result.as_ref().err().cloned();
+                });
+                if let Err(e) = result {
+                    log::warn!("Remote link to {} dropped: {}", addr, e);
+                }
+            }
+            Err(e) => {
+                log::warn!("Remote receiver {} unreachable: {}", addr, e);
+                update_status(&app_handle, |s| s.last_error = Some(e));
+            }
+        }
+
+        if generation.has_changed().unwrap_or(true) {
+            break;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = generation.changed() => break,
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+
+    let state = app_handle.state::<RemoteState>();
+    if state.started_capture.swap(false, Ordering::SeqCst) {
+        let _ = tauri_plugin_screen_capture::desktop::stop_capture().await;
+    }
+}
+
+/// (Re)start the link for the current settings
+pub fn apply_settings(app_handle: &AppHandle) {
+    let state = app_handle.state::<RemoteState>();
+    state.generation.send_modify(|g| *g += 1);
+    let generation = state.generation.subscribe();
+    let config = settings(app_handle);
+
+    update_status(app_handle, |s| {
+        s.role = config.role;
+        s.peers.clear();
+        s.last_error = None;
+    });
+
+    match config.role {
+        RemoteRole::Off => {}
+        RemoteRole::Receiver => {
+            tauri::async_runtime::spawn(run_receiver(app_handle.clone(), config.listen_port, generation));
+        }
+        RemoteRole::Sender => match config.receiver_addr {
+            Some(addr) => {
+                tauri::async_runtime::spawn(run_sender(app_handle.clone(), addr, generation));
+            }
+            None => update_status(app_handle, |s| {
+                s.last_error = Some("No receiver address configured".to_string())
+            }),
+        },
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_remote_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<RemoteSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().remote.clone())
+}
+
+/// Update role/name/ports. The paired list is managed by pairing and `remote_unpair`.
+#[tauri::command]
+pub async fn set_remote_settings(
+    settings: RemoteSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting remote settings: role {:?}", settings.role);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.remote = RemoteSettings {
+            paired: std::mem::take(&mut config.remote.paired),
+            ..settings
+        }
+    })?;
+    apply_settings(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_remote_status(remote_state: State<'_, RemoteState>) -> Result<RemoteStatus, String> {
+    Ok(remote_state.status.lock().unwrap().clone())
+}
+
+/// Receiver: show a one-time code for a sender to pair with
+#[tauri::command]
+pub async fn remote_start_pairing(remote_state: State<'_, RemoteState>) -> Result<serde_json::Value, String> {
+    let code = generate_pairing_code();
+    *remote_state.pairing.lock().unwrap() = Some(PendingPairing {
+        code: code.clone(),
+        expires: Instant::now() + PAIRING_CODE_TTL,
+    });
+    log::info!("Remote pairing code issued");
+    Ok(serde_json::json!({
+        "code": code,
+        "expiresInSecs": PAIRING_CODE_TTL.as_secs(),
+    }))
+}
+
+/// Sender: pair with a receiver using the code it shows, then start streaming to it
+#[tauri::command]
+pub async fn remote_pair(
+    addr: String,
+    code: String,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<PairedDevice, String> {
+    let code = code.trim().to_uppercase();
+    let (mut ws, _, peer) = connect(&app_handle, &addr, Some(&code)).await?;
+    let _ = ws.close(None).await;
+
+    let device = PairedDevice {
+        device_id: peer.device_id,
+        name: peer.name,
+        public_key: peer.static_key,
+        paired_at: chrono::Utc::now().timestamp(),
+    };
+    log::info!("Paired with remote receiver '{}' at {}", device.name, addr);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.remote.paired.retain(|d| d.public_key != device.public_key);
+        config.remote.paired.push(device.clone());
+        config.remote.role = RemoteRole::Sender;
+        config.remote.receiver_addr = Some(addr);
+    })?;
+    apply_settings(&app_handle);
+    Ok(device)
+}
+
+#[tauri::command]
+pub async fn remote_unpair(
+    device_id: String,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.remote.paired.retain(|d| d.device_id != device_id)
+    })?;
+    // Drop any live session with the removed device
+    apply_settings(&app_handle);
+    Ok(())
+}
+
+/// Receiver: deliver frames from remote senders to the frontend
+#[tauri::command]
+pub async fn remote_subscribe_frames(
+    on_frame: Channel<Response>,
+    binary: Option<bool>,
+    remote_state: State<'_, RemoteState>,
+) -> Result<(), String> {
+    *remote_state.frame_channel.lock().unwrap() = Some(FrameSink::new(on_frame, binary.unwrap_or(false)));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remote_unsubscribe_frames(remote_state: State<'_, RemoteState>) -> Result<(), String> {
+    *remote_state.frame_channel.lock().unwrap() = None;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri_plugin_screen_capture::capture_config::FrameEncoding;
+    use tauri_plugin_screen_capture::desktop::FrameData;
+
+    struct Side {
+        secret: StaticSecret,
+        ephemeral: Option<EphemeralSecret>,
+        hello: Hello,
+    }
+
+    fn side(name: &str) -> Side {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            device_id: device_id(&public),
+            name: name.to_string(),
+            static_key: encode_key(&public),
+            ephemeral_key: encode_key(&PublicKey::from(&ephemeral)),
+            pairing: true,
+        };
+        Side {
+            secret,
+            ephemeral: Some(ephemeral),
+            hello,
+        }
+    }
+
+    fn sessions(sender_code: Option<&str>, receiver_code: Option<&str>) -> (Session, Session) {
+        let mut sender = side("laptop");
+        let mut receiver = side("desktop");
+        let sender_ee = sender
+            .ephemeral
+            .take()
+            .unwrap()
+            .diffie_hellman(&decode_key(&receiver.hello.ephemeral_key).unwrap());
+        let receiver_ee = receiver
+            .ephemeral
+            .take()
+            .unwrap()
+            .diffie_hellman(&decode_key(&sender.hello.ephemeral_key).unwrap());
+        let sender_ss = sender
+            .secret
+            .diffie_hellman(&decode_key(&receiver.hello.static_key).unwrap());
+        let receiver_ss = receiver
+            .secret
+            .diffie_hellman(&decode_key(&sender.hello.static_key).unwrap());
+        (
+            derive_session(&sender_ee, &sender_ss, sender_code, &sender.hello, &receiver.hello, true),
+            derive_session(&receiver_ee, &receiver_ss, receiver_code, &sender.hello, &receiver.hello, false),
+        )
+    }
+
+    #[test]
+    fn both_sides_derive_matching_keys() {
+        let (mut sender, mut receiver) = sessions(Some("ABCD2345"), Some("ABCD2345"));
+        let frame = frames::Frame {
+            data: vec![0xff, 0xd8, 0xff],
+            format: FrameEncoding::Jpeg,
+            timestamp: 1.5,
+            width: 2,
+            height: 1,
+            frame_count: 7,
+        };
+        let sealed = sender.send.seal(TAG_FRAME, &encode_frame(&frame));
+        let (tag, payload) = receiver.recv.open(&sealed).unwrap();
+        assert_eq!(tag, TAG_FRAME);
+        let decoded = FrameData::from_binary(&payload).unwrap();
+        assert_eq!(decoded.frame, frame.data);
+        assert_eq!(decoded.frame_count, 7);
+
+        let reply = receiver.send.seal(TAG_CONTROL, b"{}");
+        assert!(sender.recv.open(&reply).is_ok());
+    }
+
+    #[test]
+    fn wrong_pairing_code_fails_to_decrypt() {
+        let (mut sender, mut receiver) = sessions(Some("ABCD2345"), Some("ZZZZ9999"));
+        let sealed = sender.send.seal(TAG_CONTROL, b"{\"type\":\"ready\"}");
+        assert!(receiver.recv.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn replayed_messages_are_rejected() {
+        let (mut sender, mut receiver) = sessions(None, None);
+        let first = sender.send.seal(TAG_FRAME, b"one");
+        assert!(receiver.recv.open(&first).is_ok());
+        assert!(receiver.recv.open(&first).is_err());
+    }
+}
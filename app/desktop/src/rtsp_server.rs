@@ -0,0 +1,508 @@
+// In src-tauri/src/rtsp_server.rs
+//
+// The capture as an RTSP camera, so NVRs, VLC and Frigate-style systems can watch or
+// record Observer at `rtsp://<host>:8554/<stream path>`. Off by default; `rtsp` in
+// settings.json turns it on and lists the streams.
+//
+// Every stream serves the active capture (the UI's capture session, through the frame
+// tap) as H.264, encoded per client at the stream's bitrate. Each stream has its own
+// username and password, checked with Basic authentication; a stream without credentials
+// only answers clients on this machine. RTP is sent over the RTSP connection itself
+// (interleaved TCP, ffmpeg's `rtsp_transport=tcp`); clients asking for UDP are told to
+// switch. New sessions are refused while incognito mode is on.
+//
+// Changes apply the next time the app starts.
+
+use crate::incognito;
+use crate::shortcuts::{self, UnifiedShortcutState};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_screen_capture::frames;
+use tauri_plugin_screen_capture::h264::{self, H264Encoder};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Handle;
+use tokio::sync::{broadcast, mpsc, watch};
+
+const PUBLIC_METHODS: &str = "OPTIONS, DESCRIBE, SETUP, PLAY, TEARDOWN, GET_PARAMETER, SET_PARAMETER";
+const REALM: &str = "Observer";
+/// Control URL of the only track
+const TRACK: &str = "trackID=0";
+const SESSION_TIMEOUT_SECS: u32 = 60;
+
+/// Requests with longer headers are refused
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+/// Packets queued for a client before the encoder waits for it
+const OUTGOING_PACKETS: usize = 512;
+
+/// RTP payload type and clock of the video track
+const PAYLOAD_TYPE: u8 = 96;
+const CLOCK_RATE: f64 = 90_000.0;
+/// Largest RTP payload; NAL units above it are split into FU-A fragments
+const MAX_PAYLOAD: usize = 1400;
+const NAL_FU_A: u8 = 28;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RtspStream {
+    /// URL path, e.g. `observer` for `rtsp://host:8554/observer`
+    pub path: String,
+    /// Empty for a stream only this machine may open
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_bitrate_kbps")]
+    pub bitrate_kbps: u32,
+}
+
+fn default_bitrate_kbps() -> u32 {
+    2000
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RtspSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_streams")]
+    pub streams: Vec<RtspStream>,
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    8554
+}
+
+fn default_streams() -> Vec<RtspStream> {
+    vec![RtspStream {
+        path: "observer".to_string(),
+        username: String::new(),
+        password: String::new(),
+        bitrate_kbps: default_bitrate_kbps(),
+    }]
+}
+
+impl Default for RtspSettings {
+    fn default() -> Self {
+        Self { enabled: false, host: default_host(), port: default_port(), streams: default_streams() }
+    }
+}
+
+struct Request {
+    method: String,
+    url: String,
+    /// Lowercase names
+    headers: HashMap<String, String>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+fn parse_request(head: &str) -> Option<Request> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_ascii_uppercase();
+    let url = request_line.next()?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    Some(Request { method, url, headers })
+}
+
+/// The next request, skipping the RTCP packets clients interleave on the connection.
+/// None once the client hangs up.
+async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<Option<Request>> {
+    loop {
+        let buffered = reader.fill_buf().await?;
+        match buffered.first() {
+            None => return Ok(None),
+            Some(b'$') => {
+                let mut header = [0u8; 4];
+                reader.read_exact(&mut header).await?;
+                let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+                reader.read_exact(&mut vec![0u8; length]).await?;
+            }
+            Some(b'\r' | b'\n') => reader.consume(1),
+            Some(_) => break,
+        }
+    }
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        head.push_str(&line);
+        if head.len() > MAX_REQUEST_BYTES {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "request too long"));
+        }
+    }
+    let request = parse_request(&head);
+    // No method we support takes a body; skip one if sent
+    let length = request.as_ref().and_then(|r| r.header("content-length")).and_then(|l| l.parse().ok());
+    if let Some(length) = length.filter(|&length: &usize| length <= MAX_REQUEST_BYTES) {
+        reader.read_exact(&mut vec![0u8; length]).await?;
+    }
+    Ok(request)
+}
+
+/// The stream a request URL names: `rtsp://host:8554/observer/trackID=0` -> `observer`
+fn stream_path(url: &str) -> &str {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map_or("", |(_, path)| path),
+        None => url,
+    };
+    let path = path.split('?').next().unwrap_or_default().trim_matches('/');
+    path.strip_suffix(TRACK).unwrap_or(path).trim_end_matches('/')
+}
+
+/// Whether a request for `stream` may go ahead
+fn authorized(stream: &RtspStream, authorization: Option<&str>, loopback: bool) -> bool {
+    if stream.username.is_empty() && stream.password.is_empty() {
+        return loopback;
+    }
+    let decoded = authorization
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| BASE64.decode(encoded.trim()).ok());
+    decoded.is_some_and(|decoded| decoded == format!("{}:{}", stream.username, stream.password).as_bytes())
+}
+
+fn response(status: &str, cseq: &str, headers: &[(&str, String)], body: &str) -> Vec<u8> {
+    let mut out = format!("RTSP/1.0 {}\r\nCSeq: {}\r\nServer: Observer\r\n", status, cseq);
+    for (name, value) in headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if !body.is_empty() {
+        out.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    out.push_str("\r\n");
+    out.push_str(body);
+    out.into_bytes()
+}
+
+fn sdp(host: &str, session_id: &str) -> String {
+    [
+        "v=0".to_string(),
+        format!("o=- {} 1 IN IP4 {}", session_id, host),
+        "s=Observer".to_string(),
+        "t=0 0".to_string(),
+        "a=control:*".to_string(),
+        format!("m=video 0 RTP/AVP {}", PAYLOAD_TYPE),
+        format!("a=rtpmap:{} H264/90000", PAYLOAD_TYPE),
+        // SPS and PPS travel in-band with every keyframe
+        format!("a=fmtp:{} packetization-mode=1", PAYLOAD_TYPE),
+        format!("a=control:{}", TRACK),
+        String::new(),
+    ]
+    .join("\r\n")
+}
+
+/// Interleaved channel a SETUP's `Transport` asks for, or None for a transport other
+/// than TCP
+fn interleaved_channel(transport: &str) -> Option<u8> {
+    if !transport.contains("RTP/AVP/TCP") {
+        return None;
+    }
+    let channel = transport
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("interleaved="))
+        .and_then(|range| range.split('-').next())
+        .and_then(|channel| channel.parse().ok());
+    Some(channel.unwrap_or(0))
+}
+
+/// RTP packets (RFC 6184) of H.264 access units
+struct Packetizer {
+    ssrc: u32,
+    sequence: u16,
+}
+
+impl Packetizer {
+    fn new(ssrc: u32) -> Self {
+        Self { ssrc, sequence: 0 }
+    }
+
+    /// NAL units that fit go whole, larger ones as FU-A fragments; the last packet of
+    /// the access unit carries the marker
+    fn packetize(&mut self, annex_b: &[u8], timestamp: u32) -> Vec<Vec<u8>> {
+        let mut payloads = Vec::new();
+        for unit in h264::nal_units(annex_b) {
+            if unit.len() <= MAX_PAYLOAD {
+                payloads.push(unit.to_vec());
+                continue;
+            }
+            let indicator = (unit[0] & 0xe0) | NAL_FU_A;
+            let fragments: Vec<&[u8]> = unit[1..].chunks(MAX_PAYLOAD - 2).collect();
+            for (index, fragment) in fragments.iter().enumerate() {
+                let mut header = unit[0] & 0x1f;
+                if index == 0 {
+                    header |= 0x80;
+                }
+                if index + 1 == fragments.len() {
+                    header |= 0x40;
+                }
+                let mut payload = vec![indicator, header];
+                payload.extend_from_slice(fragment);
+                payloads.push(payload);
+            }
+        }
+        let count = payloads.len();
+        payloads
+            .into_iter()
+            .enumerate()
+            .map(|(index, payload)| self.packet(&payload, timestamp, index + 1 == count))
+            .collect()
+    }
+
+    fn packet(&mut self, payload: &[u8], timestamp: u32, marker: bool) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(12 + payload.len());
+        packet.push(0x80);
+        packet.push(PAYLOAD_TYPE | if marker { 0x80 } else { 0 });
+        packet.extend_from_slice(&self.sequence.to_be_bytes());
+        packet.extend_from_slice(&timestamp.to_be_bytes());
+        packet.extend_from_slice(&self.ssrc.to_be_bytes());
+        packet.extend_from_slice(payload);
+        self.sequence = self.sequence.wrapping_add(1);
+        packet
+    }
+}
+
+/// Thread: encode tapped frames and queue them, interleaved on `channel`, until the
+/// client leaves
+fn send_video(
+    runtime: Handle,
+    tx: mpsc::Sender<Vec<u8>>,
+    channel: u8,
+    bitrate_kbps: u32,
+    mut closed: watch::Receiver<bool>,
+) {
+    let mut encoder = match H264Encoder::new(bitrate_kbps) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            log::error!("RTSP video encoder failed to start: {}", e);
+            return;
+        }
+    };
+    let mut packetizer = Packetizer::new(OsRng.next_u32());
+    let mut rx = frames::subscribe();
+    let mut first_timestamp = None;
+    loop {
+        let frame = runtime.block_on(async {
+            tokio::select! {
+                frame = rx.recv() => Some(frame),
+                _ = closed.changed() => None,
+            }
+        });
+        let frame = match frame {
+            Some(Ok(frame)) => frame,
+            Some(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Some(Err(broadcast::error::RecvError::Closed)) | None => return,
+        };
+        let unit = match encoder.encode(&frame.data) {
+            Ok(Some(unit)) => unit,
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("RTSP video encoding failed: {}", e);
+                return;
+            }
+        };
+        let start = *first_timestamp.get_or_insert(frame.timestamp);
+        let timestamp = ((frame.timestamp - start).max(0.0) * CLOCK_RATE) as u64 as u32;
+        for packet in packetizer.packetize(&unit.annex_b, timestamp) {
+            let mut framed = Vec::with_capacity(4 + packet.len());
+            framed.extend_from_slice(&[b'$', channel]);
+            framed.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+            framed.extend_from_slice(&packet);
+            if tx.blocking_send(framed).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn serve_client(app_handle: AppHandle, socket: TcpStream, peer: SocketAddr, settings: Arc<RtspSettings>) {
+    let (reader, mut writer) = socket.into_split();
+    let mut reader = BufReader::new(reader);
+    // Replies and RTP share the connection, so both go through one queue
+    let (tx, mut outgoing) = mpsc::channel::<Vec<u8>>(OUTGOING_PACKETS);
+    tokio::spawn(async move {
+        while let Some(bytes) = outgoing.recv().await {
+            if writer.write_all(&bytes).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let session_id = format!("{:016X}", OsRng.next_u64());
+    let (closed, closed_rx) = watch::channel(false);
+    let mut channel = None;
+    let mut playing = false;
+    while let Ok(Some(request)) = read_request(&mut reader).await {
+        let cseq = request.header("cseq").unwrap_or("0").to_string();
+        let session = || ("Session", format!("{};timeout={}", session_id, SESSION_TIMEOUT_SECS));
+        let reply = |status: &str, headers: &[(&str, String)], body: &str| response(status, &cseq, headers, body);
+
+        if request.method == "OPTIONS" {
+            let _ = tx.send(reply("200 OK", &[("Public", PUBLIC_METHODS.to_string())], "")).await;
+            continue;
+        }
+        let path = stream_path(&request.url);
+        let Some(stream) = settings.streams.iter().find(|stream| stream.path.trim_matches('/') == path) else {
+            let _ = tx.send(reply("404 Not Found", &[], "")).await;
+            continue;
+        };
+        if !authorized(stream, request.header("authorization"), peer.ip().is_loopback()) {
+            let challenge = ("WWW-Authenticate", format!("Basic realm=\"{}\"", REALM));
+            let _ = tx.send(reply("401 Unauthorized", &[challenge], "")).await;
+            continue;
+        }
+        if matches!(request.method.as_str(), "DESCRIBE" | "SETUP" | "PLAY") && incognito::is_active(&app_handle) {
+            let _ = tx.send(reply("403 Forbidden", &[], "")).await;
+            continue;
+        }
+
+        let message = match request.method.as_str() {
+            "DESCRIBE" => {
+                let headers = [
+                    ("Content-Type", "application/sdp".to_string()),
+                    ("Content-Base", format!("{}/", request.url.trim_end_matches('/'))),
+                ];
+                reply("200 OK", &headers, &sdp(&settings.host, &session_id))
+            }
+            "SETUP" => match interleaved_channel(request.header("transport").unwrap_or_default()) {
+                Some(interleaved) => {
+                    channel = Some(interleaved);
+                    let transport = format!("RTP/AVP/TCP;unicast;interleaved={}-{}", interleaved, interleaved + 1);
+                    reply("200 OK", &[("Transport", transport), session()], "")
+                }
+                None => reply("461 Unsupported Transport", &[], ""),
+            },
+            "PLAY" => match channel {
+                Some(channel) => {
+                    if !playing {
+                        playing = true;
+                        log::info!("RTSP client {} playing /{}", peer, stream.path);
+                        let (runtime, tx, bitrate, closed) =
+                            (Handle::current(), tx.clone(), stream.bitrate_kbps, closed_rx.clone());
+                        std::thread::spawn(move || send_video(runtime, tx, channel, bitrate, closed));
+                    }
+                    reply("200 OK", &[session(), ("Range", "npt=0.000-".to_string())], "")
+                }
+                None => reply("455 Method Not Valid in This State", &[], ""),
+            },
+            "TEARDOWN" => {
+                let _ = tx.send(reply("200 OK", &[session()], "")).await;
+                break;
+            }
+            // Keep-alives
+            "GET_PARAMETER" | "SET_PARAMETER" => reply("200 OK", &[session()], ""),
+            _ => reply("501 Not Implemented", &[], ""),
+        };
+        if tx.send(message).await.is_err() {
+            break;
+        }
+    }
+    if playing {
+        log::info!("RTSP client {} left", peer);
+    }
+    let _ = closed.send(true);
+}
+
+/// Run the RTSP server until the app exits; nothing when it is off
+pub async fn serve(app_handle: AppHandle) {
+    let settings = app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().rtsp.clone();
+    if !settings.enabled {
+        return;
+    }
+    let listener = match TcpListener::bind((settings.host.as_str(), settings.port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("RTSP server can't listen on {}:{}: {}", settings.host, settings.port, e);
+            return;
+        }
+    };
+    for stream in &settings.streams {
+        log::info!("RTSP stream at rtsp://{}:{}/{}", settings.host, settings.port, stream.path.trim_matches('/'));
+    }
+    let settings = Arc::new(settings);
+    loop {
+        match listener.accept().await {
+            Ok((socket, peer)) => {
+                tokio::spawn(serve_client(app_handle.clone(), socket, peer, settings.clone()));
+            }
+            Err(e) => log::warn!("RTSP server failed to accept a connection: {}", e),
+        }
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_rtsp_settings(shortcut_state: State<'_, UnifiedShortcutState>) -> Result<RtspSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().rtsp.clone())
+}
+
+#[tauri::command]
+pub async fn set_rtsp_settings(
+    settings: RtspSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting RTSP settings (enabled: {}, port {})", settings.enabled, settings.port);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.rtsp = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_streams_to_authorized_clients_as_rtp() {
+        assert_eq!(stream_path("rtsp://10.0.0.2:8554/observer/trackID=0"), "observer");
+        assert_eq!(stream_path("rtsp://user@host/cams/desk/"), "cams/desk");
+        assert_eq!(interleaved_channel("RTP/AVP/TCP;unicast;interleaved=2-3"), Some(2));
+        assert_eq!(interleaved_channel("RTP/AVP;unicast;client_port=5000-5001"), None);
+
+        let mut stream = default_streams().remove(0);
+        assert!(authorized(&stream, None, true));
+        assert!(!authorized(&stream, None, false));
+        stream.username = "nvr".to_string();
+        stream.password = "secret".to_string();
+        let basic = format!("Basic {}", BASE64.encode("nvr:secret"));
+        assert!(authorized(&stream, Some(&basic), false));
+        assert!(!authorized(&stream, Some("Basic bnZyOm5v"), true));
+
+        // An SPS, then a slice too big for one packet
+        let mut access_unit = vec![0, 0, 0, 1, 0x67, 1, 2, 0, 0, 1, 0x65];
+        access_unit.resize(access_unit.len() + MAX_PAYLOAD * 2, 7);
+        let packets = Packetizer::new(1).packetize(&access_unit, 90);
+        assert_eq!(packets.len(), 4);
+        assert_eq!(&packets[0][12..], &[0x67, 1, 2]);
+        // FU-A: indicator keeps NRI, header marks start and end
+        assert_eq!(&packets[1][12..14], &[0x60 | NAL_FU_A, 0x80 | 5]);
+        assert_eq!(packets[3][13], 0x40 | 5);
+        let markers: Vec<bool> = packets.iter().map(|packet| packet[1] & 0x80 != 0).collect();
+        assert_eq!(markers, vec![false, false, false, true]);
+        assert_eq!(u16::from_be_bytes([packets[3][2], packets[3][3]]), 3);
+    }
+}
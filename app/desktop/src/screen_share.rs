@@ -0,0 +1,222 @@
+// In src-tauri/src/screen_share.rs
+//
+// Screen-share detection. While the user is presenting (Zoom, Meet, Teams, ...), the
+// overlay is hidden so agent output can't pop up in front of a meeting, and capture can
+// optionally be paused through the screen-capture plugin's pause gate.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_screen_capture::pause::{self, PauseReason};
+use tauri_plugin_screen_capture::targets::{self, WindowInfo};
+
+/// How often the window list is scanned for sharing indicators
+const SHARE_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Windows that only exist while a meeting app is sharing the screen.
+/// Each entry is (source label, app name substring, window title substring); substrings
+/// are matched case-insensitively and an empty app name matches any application.
+const SHARING_INDICATORS: &[(&str, &str, &str)] = &[
+    ("Zoom", "zoom", "share toolbar"),
+    ("Zoom", "zoom", "share statusbar"),
+    ("Zoom", "zoom", "you are screen sharing"),
+    ("Teams", "teams", "sharing control bar"),
+    ("Teams", "teams", "sharing toolbar"),
+    ("Webex", "webex", "you're sharing"),
+    // Chromium/Edge/Firefox show a floating "<site> is sharing your screen" bar for
+    // getDisplayMedia, which covers Google Meet and other browser-based meetings
+    ("Browser", "", "is sharing your screen"),
+    ("Browser", "", "is sharing a window"),
+    ("Browser", "", "is sharing this tab"),
+    ("Browser", "", "is sharing your entire screen"),
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenShareSettings {
+    /// Watch for screen sharing and hide the overlay while it's active
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Also hold screen capture while sharing
+    #[serde(default)]
+    pub pause_capture: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ScreenShareSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pause_capture: false,
+        }
+    }
+}
+
+pub struct ScreenShareState {
+    sharing: AtomicBool,
+    source: Mutex<Option<String>>,
+    // Whether the overlay was visible when sharing started, so it can be restored after
+    overlay_was_visible: AtomicBool,
+}
+
+impl ScreenShareState {
+    pub fn new() -> Self {
+        Self {
+            sharing: AtomicBool::new(false),
+            source: Mutex::new(None),
+            overlay_was_visible: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_sharing(&self) -> bool {
+        self.sharing.load(Ordering::SeqCst)
+    }
+
+    /// While sharing, record a request to show the overlay instead of showing it.
+    /// Returns true if the show was deferred until sharing ends.
+    pub fn defer_overlay_show(&self) -> bool {
+        if !self.is_sharing() {
+            return false;
+        }
+        self.overlay_was_visible.store(true, Ordering::SeqCst);
+        true
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenShareStatus {
+    sharing: bool,
+    source: Option<String>,
+}
+
+/// Return the label of the meeting app that is currently sharing, if any
+fn detect_sharing(windows: &[WindowInfo]) -> Option<&'static str> {
+    windows.iter().find_map(|window| {
+        let app = window.app_name.to_lowercase();
+        let title = window.title.to_lowercase();
+        SHARING_INDICATORS
+            .iter()
+            .find(|(_, app_match, title_match)| {
+                (app_match.is_empty() || app.contains(app_match)) && title.contains(title_match)
+            })
+            .map(|(source, _, _)| *source)
+    })
+}
+
+/// Spawn the screen-share poller
+pub fn start_screen_share_monitor(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        let settings = app_handle
+            .state::<UnifiedShortcutState>()
+            .config
+            .lock()
+            .unwrap()
+            .screen_share
+            .clone();
+
+        let source = if settings.enabled {
+            match targets::list_windows() {
+                Ok(windows) => detect_sharing(&windows),
+                Err(e) => {
+                    log::debug!("Screen-share check failed to list windows: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        apply_sharing_state(&app_handle, source, &settings);
+
+        std::thread::sleep(SHARE_POLL_INTERVAL);
+    });
+}
+
+fn apply_sharing_state(
+    app_handle: &AppHandle,
+    source: Option<&'static str>,
+    settings: &ScreenShareSettings,
+) {
+    let share_state = app_handle.state::<ScreenShareState>();
+    let sharing = source.is_some();
+
+    // Keep the capture pause in sync with the setting even without a transition
+    let hold_capture = sharing && settings.pause_capture;
+    if pause::is_set(PauseReason::ScreenShare) != hold_capture {
+        pause::set(PauseReason::ScreenShare, hold_capture);
+    }
+
+    if share_state.sharing.swap(sharing, Ordering::SeqCst) == sharing {
+        return;
+    }
+
+    *share_state.source.lock().unwrap() = source.map(String::from);
+
+    if let Some(overlay) = app_handle.get_webview_window("overlay") {
+        if sharing {
+            let visible = overlay.is_visible().unwrap_or(false);
+            share_state.overlay_was_visible.store(visible, Ordering::SeqCst);
+            if visible {
+                let _ = overlay.hide();
+            }
+        } else if share_state.overlay_was_visible.swap(false, Ordering::SeqCst) {
+            if overlay.show().is_ok() {
+                // Re-enable click-through after showing
+                let _ = overlay.set_ignore_cursor_events(true);
+            }
+        }
+    }
+    crate::annotations::sync_window(app_handle);
+
+    log::info!(
+        "Screen sharing {}{}",
+        if sharing { "started" } else { "ended" },
+        source.map(|s| format!(" ({})", s)).unwrap_or_default()
+    );
+
+    let status = ScreenShareStatus {
+        sharing,
+        source: source.map(String::from),
+    };
+    if let Err(e) = app_handle.emit("screen-share-state-changed", &status) {
+        log::warn!("Failed to emit screen-share-state-changed event: {}", e);
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_screen_share_status(
+    share_state: State<'_, ScreenShareState>,
+) -> Result<ScreenShareStatus, String> {
+    Ok(ScreenShareStatus {
+        sharing: share_state.is_sharing(),
+        source: share_state.source.lock().unwrap().clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_screen_share_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<ScreenShareSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().screen_share.clone())
+}
+
+#[tauri::command]
+pub async fn set_screen_share_settings(
+    settings: ScreenShareSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting screen-share settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        config.screen_share = settings
+    })
+}
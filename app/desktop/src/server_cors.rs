@@ -0,0 +1,113 @@
+// In src-tauri/src/server_cors.rs
+//
+// Which web origins may call the built-in API server from a browser. By default only the
+// app's own: its webviews (`tauri://localhost`, `http(s)://tauri.localhost`), the dev
+// server and pages served by the API server itself. `apiServer.cors.allowedOrigins` (or a
+// comma-separated OBSERVER_CORS_ORIGINS) adds more, e.g. `https://dashboard.example.com`
+// for a frontend hosted elsewhere; `*` allows any origin.
+//
+// CORS only limits browsers. The API token (see `api_auth`) still applies to every caller.
+
+use crate::api_server::ServerStarted;
+use axum::http::HeaderValue;
+use serde::{Deserialize, Serialize};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+const ORIGINS_ENV: &str = "OBSERVER_CORS_ORIGINS";
+
+/// Origins of the app's webviews and of the frontend dev server
+const APP_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+    "http://localhost:3001",
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsSettings {
+    /// Origins allowed on top of the app's own, like `https://example.com`; `*` for any
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Origins {
+    Any,
+    List(Vec<String>),
+}
+
+/// Allowed origins for a server on `port`, with the environment (as read by `env`)
+/// replacing the configured extras
+fn origins(settings: &CorsSettings, env: impl Fn(&str) -> Option<String>, port: u16, tls: bool) -> Origins {
+    let extra: Vec<String> = match env(ORIGINS_ENV) {
+        Some(value) => value.split(',').map(str::to_string).collect(),
+        None => settings.allowed_origins.clone(),
+    };
+    let extra: Vec<String> = extra
+        .iter()
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+    if extra.iter().any(|origin| origin == "*") {
+        return Origins::Any;
+    }
+
+    let scheme = if tls { "https" } else { "http" };
+    let mut list: Vec<String> = APP_ORIGINS.iter().map(|origin| origin.to_string()).collect();
+    // Pages served by this server, however the loopback address is written
+    list.extend(["localhost", "127.0.0.1", "[::1]"].map(|host| format!("{}://{}:{}", scheme, host, port)));
+    list.extend(extra);
+    let mut seen = std::collections::HashSet::new();
+    list.retain(|origin| seen.insert(origin.clone()));
+    Origins::List(list)
+}
+
+/// The CORS layer for the API server
+pub fn layer(settings: &CorsSettings, started: &ServerStarted) -> CorsLayer {
+    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+    match origins(settings, |name| std::env::var(name).ok(), started.port, started.tls) {
+        Origins::Any => {
+            log::info!("API server allows requests from any origin");
+            cors.allow_origin(Any)
+        }
+        Origins::List(list) => {
+            let values: Vec<HeaderValue> = list
+                .iter()
+                .filter_map(|origin| match HeaderValue::from_str(origin) {
+                    Ok(value) => Some(value),
+                    Err(_) => {
+                        log::warn!("Ignoring invalid CORS origin '{}'", origin);
+                        None
+                    }
+                })
+                .collect();
+            cors.allow_origin(AllowOrigin::list(values))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_the_apps_own_origins() {
+        let Origins::List(list) = origins(&CorsSettings::default(), |_| None, 3838, false) else {
+            panic!("expected a list");
+        };
+        assert!(list.contains(&"tauri://localhost".to_string()));
+        assert!(list.contains(&"http://127.0.0.1:3838".to_string()));
+        assert!(!list.iter().any(|origin| origin.contains("example.com")));
+
+        let settings = CorsSettings { allowed_origins: vec!["https://example.com/".to_string()] };
+        let Origins::List(list) = origins(&settings, |_| None, 3838, true) else {
+            panic!("expected a list");
+        };
+        assert!(list.contains(&"https://example.com".to_string()));
+        assert!(list.contains(&"https://localhost:3838".to_string()));
+
+        let any = |name: &str| (name == ORIGINS_ENV).then(|| "https://a.test, *".to_string());
+        assert_eq!(origins(&settings, any, 3838, false), Origins::Any);
+    }
+}
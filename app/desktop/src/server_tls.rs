@@ -0,0 +1,187 @@
+// In src-tauri/src/server_tls.rs
+//
+// HTTPS for the built-in API server. With `apiServer.tls.enabled` the server uses the PEM
+// certificate and key at `certPath` / `keyPath`, or else a self-signed pair generated into
+// `<app_data_dir>/tls` on first use. The self-signed certificate names localhost, the
+// loopback addresses, this machine's host name and LAN address and the configured host,
+// so a phone on the LAN can connect once it trusts the certificate; its SHA-256
+// fingerprint is shown for checking that it is the right one.
+//
+// The gRPC server uses the same certificate.
+//
+// Browsers and the app's own webview only accept the self-signed certificate after it has
+// been trusted (added to the OS or browser store).
+
+use crate::api_server::ApiServerSettings;
+use crate::shortcuts::UnifiedShortcutState;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM certificate (chain); None for the generated self-signed one
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    #[serde(default)]
+    pub key_path: Option<String>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsInfo {
+    pub enabled: bool,
+    pub self_signed: bool,
+    pub cert_path: String,
+    /// SHA-256 of the certificate, colon-separated hex; None until it exists
+    pub fingerprint: Option<String>,
+}
+
+/// Certificate and key files to serve, and whether they are the generated pair
+fn paths(app_handle: &AppHandle, settings: &TlsSettings) -> Result<(PathBuf, PathBuf, bool), String> {
+    match (&settings.cert_path, &settings.key_path) {
+        (Some(cert), Some(key)) => Ok((PathBuf::from(cert), PathBuf::from(key), false)),
+        (None, None) => {
+            let dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?.join("tls");
+            Ok((dir.join("cert.pem"), dir.join("key.pem"), true))
+        }
+        _ => Err("Both a certificate and a key path are needed".to_string()),
+    }
+}
+
+/// This machine's address on the LAN: the one a route to the internet would leave from.
+/// Nothing is sent.
+fn lan_address() -> Option<std::net::IpAddr> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+/// Names and addresses the self-signed certificate is valid for
+fn subject_names(host: &str) -> Vec<String> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    names.extend(sysinfo::System::host_name());
+    names.extend(lan_address().map(|ip| ip.to_string()));
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if !matches!(host, "0.0.0.0" | "::" | "") {
+        names.push(host.to_string());
+    }
+    let mut seen = std::collections::HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    names
+}
+
+/// Write a new self-signed certificate and key
+fn generate(cert_path: &PathBuf, key_path: &PathBuf, host: &str) -> Result<(), String> {
+    let names = subject_names(host);
+    let certified = rcgen::generate_simple_self_signed(names.clone()).map_err(|e| e.to_string())?;
+    if let Some(dir) = cert_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(cert_path, certified.cert.pem()).map_err(|e| e.to_string())?;
+    std::fs::write(key_path, certified.key_pair.serialize_pem()).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(key_path, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    }
+    log::info!("Generated a self-signed API server certificate for {}", names.join(", "));
+    Ok(())
+}
+
+/// SHA-256 of the first certificate in `pem`
+fn fingerprint(pem: &str) -> Option<String> {
+    let body: String = pem
+        .split("-----BEGIN CERTIFICATE-----")
+        .nth(1)?
+        .split("-----END CERTIFICATE-----")
+        .next()?
+        .split_whitespace()
+        .collect();
+    let der = BASE64.decode(body).ok()?;
+    let hex: Vec<String> = Sha256::digest(der).iter().map(|b| format!("{:02X}", b)).collect();
+    Some(hex.join(":"))
+}
+
+fn info(app_handle: &AppHandle, settings: &TlsSettings) -> Result<TlsInfo, String> {
+    let (cert_path, _, self_signed) = paths(app_handle, settings)?;
+    let fingerprint = std::fs::read_to_string(&cert_path).ok().and_then(|pem| fingerprint(&pem));
+    Ok(TlsInfo {
+        enabled: settings.enabled,
+        self_signed,
+        cert_path: cert_path.to_string_lossy().into_owned(),
+        fingerprint,
+    })
+}
+
+/// The certificate and key to serve, generating the self-signed pair if it doesn't exist yet
+pub fn pem_files(app_handle: &AppHandle, settings: &ApiServerSettings) -> Result<(PathBuf, PathBuf), String> {
+    let (cert_path, key_path, self_signed) = paths(app_handle, &settings.tls)?;
+    if self_signed && !(cert_path.is_file() && key_path.is_file()) {
+        generate(&cert_path, &key_path, &settings.host)?;
+    }
+    Ok((cert_path, key_path))
+}
+
+/// The server's TLS configuration
+pub async fn config(
+    app_handle: &AppHandle,
+    settings: &ApiServerSettings,
+) -> Result<axum_server::tls_rustls::RustlsConfig, String> {
+    // Fails only if a provider is installed already, which is as good
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let (cert_path, key_path) = pem_files(app_handle, settings)?;
+    axum_server::tls_rustls::RustlsConfig::from_pem_file(&cert_path, &key_path)
+        .await
+        .map_err(|e| format!("Failed to load {}: {}", cert_path.display(), e))
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_tls_info(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<TlsInfo, String> {
+    let settings = shortcut_state.config.lock().unwrap().api_server.tls.clone();
+    info(&app_handle, &settings)
+}
+
+/// Replace the self-signed certificate, e.g. after the LAN address changed. Takes effect
+/// on the next start.
+#[tauri::command]
+pub async fn regenerate_tls_certificate(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<TlsInfo, String> {
+    let settings = shortcut_state.config.lock().unwrap().api_server.clone();
+    let (cert_path, key_path, self_signed) = paths(&app_handle, &settings.tls)?;
+    if !self_signed {
+        return Err("The API server uses a certificate of its own, not a generated one".to_string());
+    }
+    generate(&cert_path, &key_path, &settings.host)?;
+    info(&app_handle, &settings.tls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprints_the_certificate() {
+        let names = subject_names("0.0.0.0");
+        assert_eq!(&names[..3], ["localhost", "127.0.0.1", "::1"]);
+        assert!(!names.iter().any(|name| name == "0.0.0.0"));
+
+        let certified = rcgen::generate_simple_self_signed(names).unwrap();
+        let expected: Vec<String> = Sha256::digest(certified.cert.der()).iter().map(|b| format!("{:02X}", b)).collect();
+        assert_eq!(fingerprint(&certified.cert.pem()), Some(expected.join(":")));
+        assert_eq!(fingerprint("no certificate here"), None);
+    }
+}
@@ -1,3 +1,24 @@
+use crate::api_auth::ApiAuthSettings;
+use crate::api_server::ApiServerSettings;
+use crate::backends::BackendSettings;
+use crate::digest::DigestSettings;
+use crate::dnd::NotificationSettings;
+use crate::embeddings::EmbeddingSettings;
+use crate::inference_cache::CacheSettings;
+use crate::inference_queue::QueueSettings;
+use crate::ocr::OcrSettings;
+use crate::ollama_proxy::OllamaProxySettings;
+use crate::providers::ProviderSettings;
+use crate::recall::RecallSettings;
+use crate::redaction::RedactionSettings;
+use crate::remote::RemoteSettings;
+use crate::rtsp_server::RtspSettings;
+use crate::screen_share::ScreenShareSettings;
+use crate::ssh_tunnel::SshTunnelSettings;
+use crate::tailnet::TailnetSettings;
+use crate::token_usage::TokenBudgetSettings;
+use crate::tools::ToolSettings;
+use crate::usage::UsageSettings;
 use crate::CommandState;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -9,6 +30,48 @@ use tauri::{AppHandle, Emitter, Manager, State};
 pub struct AppConfig {
     pub shortcuts: UnifiedShortcutConfig,
     pub ollama_url: Option<String>,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub screen_share: ScreenShareSettings,
+    #[serde(default)]
+    pub usage: UsageSettings,
+    #[serde(default)]
+    pub digest: DigestSettings,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    #[serde(default)]
+    pub ocr: OcrSettings,
+    #[serde(default)]
+    pub remote: RemoteSettings,
+    #[serde(default)]
+    pub ssh_tunnels: SshTunnelSettings,
+    #[serde(default)]
+    pub tailnet: TailnetSettings,
+    #[serde(default)]
+    pub inference_queue: QueueSettings,
+    #[serde(default)]
+    pub backends: BackendSettings,
+    #[serde(default)]
+    pub providers: ProviderSettings,
+    #[serde(default)]
+    pub ollama_proxy: OllamaProxySettings,
+    #[serde(default)]
+    pub inference_cache: CacheSettings,
+    #[serde(default)]
+    pub token_budgets: TokenBudgetSettings,
+    #[serde(default)]
+    pub tools: ToolSettings,
+    #[serde(default)]
+    pub embeddings: EmbeddingSettings,
+    #[serde(default)]
+    pub recall: RecallSettings,
+    #[serde(default)]
+    pub api_server: ApiServerSettings,
+    #[serde(default)]
+    pub api_auth: ApiAuthSettings,
+    #[serde(default)]
+    pub rtsp: RtspSettings,
 }
 
 impl Default for AppConfig {
@@ -16,6 +79,27 @@ impl Default for AppConfig {
         Self {
             shortcuts: UnifiedShortcutConfig::default(),
             ollama_url: Some("http://localhost:11434".to_string()),
+            notifications: NotificationSettings::default(),
+            screen_share: ScreenShareSettings::default(),
+            usage: UsageSettings::default(),
+            digest: DigestSettings::default(),
+            redaction: RedactionSettings::default(),
+            ocr: OcrSettings::default(),
+            remote: RemoteSettings::default(),
+            ssh_tunnels: SshTunnelSettings::default(),
+            tailnet: TailnetSettings::default(),
+            inference_queue: QueueSettings::default(),
+            backends: BackendSettings::default(),
+            providers: ProviderSettings::default(),
+            ollama_proxy: OllamaProxySettings::default(),
+            inference_cache: CacheSettings::default(),
+            token_budgets: TokenBudgetSettings::default(),
+            tools: ToolSettings::default(),
+            embeddings: EmbeddingSettings::default(),
+            recall: RecallSettings::default(),
+            api_server: ApiServerSettings::default(),
+            api_auth: ApiAuthSettings::default(),
+            rtsp: RtspSettings::default(),
         }
     }
 }
@@ -33,6 +117,10 @@ pub struct UnifiedShortcutConfig {
     pub overlay_resize_left: Option<String>,
     pub overlay_resize_right: Option<String>,
 
+    // Privacy kill switch
+    #[serde(default = "default_incognito_toggle")]
+    pub incognito_toggle: Option<String>,
+
     // Agent shortcuts: agent_id -> shortcut_key
     pub agent_shortcuts: HashMap<String, String>,
 }
@@ -52,6 +140,7 @@ impl Default for UnifiedShortcutConfig {
                 overlay_resize_down: Some("Alt+Shift+ArrowDown".to_string()),
                 overlay_resize_left: Some("Alt+Shift+ArrowLeft".to_string()),
                 overlay_resize_right: Some("Alt+Shift+ArrowRight".to_string()),
+                incognito_toggle: default_incognito_toggle(),
                 agent_shortcuts: HashMap::new(),
             }
         }
@@ -67,12 +156,24 @@ impl Default for UnifiedShortcutConfig {
                 overlay_resize_down: Some("Cmd+Shift+ArrowDown".to_string()),
                 overlay_resize_left: Some("Cmd+Shift+ArrowLeft".to_string()),
                 overlay_resize_right: Some("Cmd+Shift+ArrowRight".to_string()),
+                incognito_toggle: default_incognito_toggle(),
                 agent_shortcuts: HashMap::new(),
             }
         }
     }
 }
 
+fn default_incognito_toggle() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        Some("Alt+Shift+Escape".to_string())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Some("Cmd+Shift+Escape".to_string())
+    }
+}
+
 pub struct UnifiedShortcutState {
     pub config: Mutex<AppConfig>,
     pub registered_shortcuts: Mutex<Vec<String>>,
@@ -89,6 +190,7 @@ enum ShortcutAction {
     OverlayResizeDown,
     OverlayResizeLeft,
     OverlayResizeRight,
+    IncognitoToggle,
     AgentToggle(String), // agent_id
 }
 
@@ -117,13 +219,9 @@ pub async fn set_shortcut_config(
 ) -> Result<(), String> {
     log::info!("Setting unified shortcut config");
 
-    // Preserve ollama_url from current config
-    let ollama_url = shortcut_state.config.lock().unwrap().ollama_url.clone();
-
-    let new_app_config = AppConfig {
-        shortcuts: config,
-        ollama_url,
-    };
+    // Preserve the non-shortcut settings from the current config
+    let mut new_app_config = shortcut_state.config.lock().unwrap().clone();
+    new_app_config.shortcuts = config;
 
     // Save to disk
     save_config_to_disk(&app_handle, &new_app_config)?;
@@ -165,6 +263,7 @@ pub fn load_config_from_disk(app_handle: &AppHandle) -> AppConfig {
                                         let new_config = AppConfig {
                                             shortcuts: old_config,
                                             ollama_url: None,
+                                            ..AppConfig::default()
                                         };
                                         // Save the migrated config in new format
                                         if let Err(e) = save_config_to_disk(app_handle, &new_config)
@@ -235,9 +334,18 @@ pub fn save_ollama_url(
     shortcut_state: &State<UnifiedShortcutState>,
     ollama_url: Option<String>,
 ) -> Result<(), String> {
-    // Get current config and update ollama_url
+    update_config(app_handle, shortcut_state, |config| config.ollama_url = ollama_url)
+}
+
+// Apply a change to the persisted app config: edit a copy, save it, then swap it in
+pub fn update_config(
+    app_handle: &AppHandle,
+    shortcut_state: &UnifiedShortcutState,
+    apply: impl FnOnce(&mut AppConfig),
+) -> Result<(), String> {
+    // Get current config and apply the change
     let mut app_config = shortcut_state.config.lock().unwrap().clone();
-    app_config.ollama_url = ollama_url;
+    apply(&mut app_config);
 
     // Save to disk
     save_config_to_disk(app_handle, &app_config)?;
@@ -433,6 +541,12 @@ pub fn register_shortcuts_on_startup(
         }
     }
 
+    if let Some(key) = &config.incognito_toggle {
+        if let Some(shortcut) = parse_shortcut_string(key) {
+            shortcuts_to_register.push((shortcut, key.clone(), ShortcutAction::IncognitoToggle));
+        }
+    }
+
     // Agent shortcuts
     for (agent_id, shortcut_key) in &config.agent_shortcuts {
         if !shortcut_key.is_empty() {
@@ -615,6 +729,10 @@ pub fn register_shortcuts_on_startup(
                             }
                         }
 
+                        ShortcutAction::IncognitoToggle => {
+                            crate::incognito::toggle_incognito(app_handle);
+                        }
+
                         ShortcutAction::AgentToggle(agent_id) => {
                             log::info!("Agent hotkey pressed for agent: {}", agent_id);
                             let command_state = app_handle.state::<CommandState>();
@@ -646,6 +764,7 @@ pub fn register_shortcuts_on_startup(
                     ShortcutAction::OverlayResizeDown => "overlay resize down",
                     ShortcutAction::OverlayResizeLeft => "overlay resize left",
                     ShortcutAction::OverlayResizeRight => "overlay resize right",
+                    ShortcutAction::IncognitoToggle => "incognito toggle",
                     ShortcutAction::AgentToggle(agent_id) => {
                         registered_keys.push(format!("{} -> toggle agent {}", key, agent_id));
                         continue;
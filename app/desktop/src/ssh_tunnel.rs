@@ -0,0 +1,688 @@
+// In src-tauri/src/ssh_tunnel.rs
+//
+// SSH tunnels to model servers that are only reachable over SSH (Ollama on a LAN box).
+// Each configured tunnel listens on a local port and forwards connections through an
+// SSH session to the remote endpoint (a `ssh -L local:remote_host:remote_port` that the
+// app keeps alive). Sessions are health-checked and re-established with backoff.
+//
+// Tunnels are tied to a provider; while an "ollama" tunnel is enabled the local proxy
+// sends Ollama traffic through it instead of the configured Ollama URL.
+//
+// Host keys are trusted on first use: the fingerprint seen on the first successful
+// connection is saved and later connections to a different key are refused.
+//
+// Passwords and key passphrases are kept in the OS keyring under the tunnel's id, not in
+// settings.json; settings come back with them blank, and a blank one keeps the saved
+// secret. Secrets found in an older settings.json are moved on startup.
+//
+// Changing settings waits for the old tunnels to release their ports before the new ones
+// bind them.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use futures::future::{FutureExt, Shared};
+use russh::client::{self, Handle};
+use russh::keys::{load_secret_key, ssh_key, PrivateKeyWithHashAlg};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// How often a connected session is probed
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+const KEYRING_SERVICE: &str = "Observer SSH tunnel";
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum SshAuth {
+    /// Private key file (OpenSSH format), optionally encrypted
+    #[serde(rename_all = "camelCase")]
+    Key {
+        path: String,
+        #[serde(default)]
+        passphrase: Option<String>,
+    },
+    Password { password: String },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SshTunnelConfig {
+    pub id: String,
+    /// Provider this tunnel serves ("ollama", or a custom provider name)
+    pub provider: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub username: String,
+    pub auth: SshAuth,
+    /// Endpoint as seen from the SSH server
+    #[serde(default = "default_remote_host")]
+    pub remote_host: String,
+    #[serde(default = "default_remote_port")]
+    pub remote_port: u16,
+    /// Port on 127.0.0.1 the tunnel listens on
+    pub local_port: u16,
+    /// SHA256 fingerprint of the server's host key, recorded on first connect
+    #[serde(default)]
+    pub host_key_fingerprint: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+fn default_remote_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_remote_port() -> u16 {
+    11434
+}
+
+impl SshTunnelConfig {
+    pub fn local_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.local_port)
+    }
+
+    /// The password or key passphrase the config carries, if any
+    fn secret_mut(&mut self) -> Option<&mut String> {
+        match &mut self.auth {
+            SshAuth::Key { passphrase, .. } => passphrase.as_mut(),
+            SshAuth::Password { password } => Some(password),
+        }
+    }
+
+    /// The password or passphrase to log in with: the config's, or else the saved one
+    fn secret(&self) -> Option<String> {
+        let given = match &self.auth {
+            SshAuth::Key { passphrase, .. } => passphrase.clone(),
+            SshAuth::Password { password } => Some(password.clone()),
+        };
+        given
+            .filter(|secret| !secret.is_empty())
+            .or_else(|| keyring_entry(&self.id).ok()?.get_password().ok())
+    }
+}
+
+fn keyring_entry(tunnel_id: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, tunnel_id).map_err(|e| e.to_string())
+}
+
+/// Move the secrets in `tunnels` to the keyring, blanking them; whether any were moved
+fn stash_secrets(tunnels: &mut [SshTunnelConfig]) -> Result<bool, String> {
+    let mut moved = false;
+    for tunnel in tunnels {
+        let id = tunnel.id.clone();
+        let Some(secret) = tunnel.secret_mut().filter(|secret| !secret.is_empty()) else {
+            continue;
+        };
+        keyring_entry(&id)?
+            .set_password(secret)
+            .map_err(|e| format!("Failed to save the secret for tunnel {} to the keyring: {}", id, e))?;
+        secret.clear();
+        moved = true;
+    }
+    Ok(moved)
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SshTunnelSettings {
+    #[serde(default)]
+    pub tunnels: Vec<SshTunnelConfig>,
+}
+
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelState {
+    Connecting,
+    Connected,
+    Reconnecting,
+    Stopped,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelStatus {
+    id: String,
+    provider: String,
+    state: TunnelState,
+    local_url: String,
+    last_error: Option<String>,
+    /// Failed attempts since the last successful connection
+    attempts: u32,
+}
+
+impl TunnelStatus {
+    fn new(tunnel: &SshTunnelConfig) -> Self {
+        Self {
+            id: tunnel.id.clone(),
+            provider: tunnel.provider.clone(),
+            state: TunnelState::Connecting,
+            local_url: tunnel.local_url(),
+            last_error: None,
+            attempts: 0,
+        }
+    }
+
+    /// The SSH session is up
+    fn connected(&mut self) {
+        self.state = TunnelState::Connected;
+        self.last_error = None;
+        self.attempts = 0;
+    }
+
+    /// The session couldn't be set up or ended; the tunnel tries again after a backoff
+    fn lost(&mut self, error: String) {
+        self.state = TunnelState::Reconnecting;
+        self.last_error = Some(error);
+        self.attempts += 1;
+    }
+
+    /// The tunnel gave up and won't try again until settings change
+    fn stopped(&mut self, error: String) {
+        self.state = TunnelState::Stopped;
+        self.last_error = Some(error);
+    }
+}
+
+/// The wait before the next reconnect: `backoff` doubled, up to `MAX_BACKOFF`
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(MAX_BACKOFF)
+}
+
+/// Every enabled tunnel needs a local port of its own
+fn check_local_ports(tunnels: &[SshTunnelConfig]) -> Result<(), String> {
+    let mut ports = std::collections::HashSet::new();
+    for tunnel in tunnels.iter().filter(|t| t.enabled) {
+        if tunnel.local_port == 0 {
+            return Err(format!("Tunnel {} needs a local port", tunnel.id));
+        }
+        if !ports.insert(tunnel.local_port) {
+            return Err(format!("Local port {} is used by more than one tunnel", tunnel.local_port));
+        }
+    }
+    Ok(())
+}
+
+struct RunningTunnel {
+    status: TunnelStatus,
+    stop: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+/// Resolves once the tunnels being replaced have stopped
+type Released = Shared<Pin<Box<dyn Future<Output = ()> + Send>>>;
+
+pub struct SshTunnelState {
+    tunnels: Mutex<HashMap<String, RunningTunnel>>,
+}
+
+impl SshTunnelState {
+    pub fn new() -> Self {
+        Self {
+            tunnels: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Local URL of the enabled tunnel for a provider, if there is one
+pub fn local_url(app_handle: &AppHandle, provider: &str) -> Option<String> {
+    let shortcut_state = app_handle.state::<UnifiedShortcutState>();
+    let config = shortcut_state.config.lock().unwrap();
+    config
+        .ssh_tunnels
+        .tunnels
+        .iter()
+        .find(|t| t.enabled && t.provider == provider)
+        .map(SshTunnelConfig::local_url)
+}
+
+fn update_status(app_handle: &AppHandle, id: &str, update: impl FnOnce(&mut TunnelStatus)) {
+    let statuses = {
+        let state = app_handle.state::<SshTunnelState>();
+        let mut tunnels = state.tunnels.lock().unwrap();
+        if let Some(tunnel) = tunnels.get_mut(id) {
+            update(&mut tunnel.status);
+        }
+        tunnels.values().map(|t| t.status.clone()).collect::<Vec<_>>()
+    };
+    if let Err(e) = app_handle.emit("ssh-tunnel-status-changed", &statuses) {
+        log::warn!("Failed to emit ssh-tunnel-status-changed event: {}", e);
+    }
+}
+
+struct Client {
+    app_handle: AppHandle,
+    tunnel_id: String,
+    expected_fingerprint: Option<String>,
+}
+
+impl client::Handler for Client {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &ssh_key::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let fingerprint = server_public_key
+            .fingerprint(ssh_key::HashAlg::Sha256)
+            .to_string();
+        match &self.expected_fingerprint {
+            Some(expected) if *expected == fingerprint => Ok(true),
+            Some(expected) => {
+                log::error!(
+                    "SSH host key for tunnel {} changed (expected {}, got {}); refusing to connect",
+                    self.tunnel_id,
+                    expected,
+                    fingerprint
+                );
+                Ok(false)
+            }
+            None => {
+                log::info!(
+                    "Trusting SSH host key {} for tunnel {}",
+                    fingerprint,
+                    self.tunnel_id
+                );
+                let shortcut_state = self.app_handle.state::<UnifiedShortcutState>();
+                let id = self.tunnel_id.clone();
+                let saved = shortcuts::update_config(&self.app_handle, &shortcut_state, |config| {
+                    if let Some(t) = config.ssh_tunnels.tunnels.iter_mut().find(|t| t.id == id) {
+                        t.host_key_fingerprint = Some(fingerprint.clone());
+                    }
+                });
+                if let Err(e) = saved {
+                    log::warn!("Failed to save SSH host key fingerprint: {}", e);
+                }
+                self.expected_fingerprint = Some(fingerprint);
+                Ok(true)
+            }
+        }
+    }
+}
+
+async fn connect(app_handle: &AppHandle, tunnel: &SshTunnelConfig) -> Result<Handle<Client>, String> {
+    let config = Arc::new(client::Config {
+        keepalive_interval: Some(HEALTH_CHECK_INTERVAL),
+        keepalive_max: 3,
+        ..Default::default()
+    });
+    let handler = Client {
+        app_handle: app_handle.clone(),
+        tunnel_id: tunnel.id.clone(),
+        expected_fingerprint: tunnel.host_key_fingerprint.clone(),
+    };
+
+    let mut session = tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        client::connect(config, (tunnel.host.as_str(), tunnel.port), handler),
+    )
+    .await
+    .map_err(|_| format!("Timed out connecting to {}:{}", tunnel.host, tunnel.port))?
+    .map_err(|e| format!("SSH connection to {}:{} failed: {}", tunnel.host, tunnel.port, e))?;
+
+    let auth = match &tunnel.auth {
+        SshAuth::Key { path, .. } => {
+            let passphrase = tunnel.secret();
+            let key = load_secret_key(path, passphrase.as_deref())
+                .map_err(|e| format!("Failed to load SSH key {}: {}", path, e))?;
+            let hash_alg = session
+                .best_supported_rsa_hash()
+                .await
+                .map_err(|e| e.to_string())?
+                .flatten();
+            session
+                .authenticate_publickey(
+                    &tunnel.username,
+                    PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg),
+                )
+                .await
+        }
+        SshAuth::Password { .. } => {
+            let password = tunnel.secret().ok_or_else(|| "No password saved for this tunnel".to_string())?;
+            session
+                .authenticate_password(&tunnel.username, password)
+                .await
+        }
+    }
+    .map_err(|e| format!("SSH authentication failed: {}", e))?;
+
+    if !auth.success() {
+        return Err(format!(
+            "SSH authentication rejected for {}@{}",
+            tunnel.username, tunnel.host
+        ));
+    }
+    Ok(session)
+}
+
+/// Open a forwarded channel to the remote endpoint
+async fn open_forward(
+    session: &Handle<Client>,
+    tunnel: &SshTunnelConfig,
+    peer: std::net::SocketAddr,
+) -> Result<russh::ChannelStream<client::Msg>, String> {
+    let channel = session
+        .channel_open_direct_tcpip(
+            tunnel.remote_host.clone(),
+            u32::from(tunnel.remote_port),
+            peer.ip().to_string(),
+            u32::from(peer.port()),
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to forward to {}:{}: {}",
+                tunnel.remote_host, tunnel.remote_port, e
+            )
+        })?;
+    Ok(channel.into_stream())
+}
+
+fn forward_connection(session: Arc<Handle<Client>>, tunnel: SshTunnelConfig, mut local: TcpStream) {
+    tauri::async_runtime::spawn(async move {
+        let peer = match local.peer_addr() {
+            Ok(peer) => peer,
+            Err(_) => return,
+        };
+        match open_forward(&session, &tunnel, peer).await {
+            Ok(mut remote) => {
+                if let Err(e) = tokio::io::copy_bidirectional(&mut local, &mut remote).await {
+                    log::debug!("Tunnel {} connection closed: {}", tunnel.id, e);
+                }
+            }
+            Err(e) => log::warn!("Tunnel {}: {}", tunnel.id, e),
+        }
+    });
+}
+
+/// Serve one SSH session until it fails a health check or the tunnel is stopped.
+/// Returns Ok(()) when stopped.
+async fn serve_session(
+    app_handle: &AppHandle,
+    tunnel: &SshTunnelConfig,
+    listener: &TcpListener,
+    stop: &mut watch::Receiver<bool>,
+) -> Result<(), String> {
+    let session = tokio::select! {
+        session = connect(app_handle, tunnel) => Arc::new(session?),
+        _ = stop.changed() => return Ok(()),
+    };
+    log::info!(
+        "SSH tunnel {} up: {} -> {}:{} via {}",
+        tunnel.id,
+        tunnel.local_url(),
+        tunnel.remote_host,
+        tunnel.remote_port,
+        tunnel.host
+    );
+    update_status(app_handle, &tunnel.id, TunnelStatus::connected);
+
+    let mut health_check = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+    health_check.tick().await;
+    let probe_addr = std::net::SocketAddr::from(([127, 0, 0, 1], tunnel.local_port));
+
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => forward_connection(session.clone(), tunnel.clone(), stream),
+                Err(e) => log::warn!("Tunnel {} accept failed: {}", tunnel.id, e),
+            },
+            _ = health_check.tick() => {
+                // The session is healthy if it's open and can still reach the endpoint
+                if session.is_closed() {
+                    break Err("SSH session closed".to_string());
+                }
+                if let Err(e) = open_forward(&session, tunnel, probe_addr).await {
+                    break Err(e);
+                }
+            },
+            _ = stop.changed() => break Ok(()),
+        }
+    };
+
+    let _ = session
+        .disconnect(russh::Disconnect::ByApplication, "", "en")
+        .await;
+    result
+}
+
+async fn run_tunnel(
+    app_handle: AppHandle,
+    tunnel: SshTunnelConfig,
+    mut stop: watch::Receiver<bool>,
+    released: Released,
+) {
+    // The tunnels this one replaces may hold its port until they have disconnected
+    released.await;
+    if *stop.borrow() {
+        return;
+    }
+    // The local port stays bound across reconnects so clients see refused forwards,
+    // not a vanished port
+    let listener = match TcpListener::bind(("127.0.0.1", tunnel.local_port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Tunnel {} failed to bind port {}: {}", tunnel.id, tunnel.local_port, e);
+            update_status(&app_handle, &tunnel.id, |s| {
+                s.stopped(format!("Port {} unavailable: {}", tunnel.local_port, e))
+            });
+            return;
+        }
+    };
+
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match serve_session(&app_handle, &tunnel, &listener, &mut stop).await {
+            Ok(()) => break,
+            Err(e) => {
+                log::warn!("SSH tunnel {}: {}", tunnel.id, e);
+                update_status(&app_handle, &tunnel.id, |s| s.lost(e));
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = stop.changed() => break,
+        }
+        backoff = next_backoff(backoff);
+    }
+
+    log::info!("SSH tunnel {} stopped", tunnel.id);
+}
+
+/// Stop all running tunnels and start the enabled ones from settings
+pub fn apply_settings(app_handle: &AppHandle) {
+    let shortcut_state = app_handle.state::<UnifiedShortcutState>();
+    let mut tunnels = shortcut_state.config.lock().unwrap().ssh_tunnels.tunnels.clone();
+    // Secrets saved by older versions
+    match stash_secrets(&mut tunnels) {
+        Ok(true) => {
+            let stashed = tunnels.clone();
+            if let Err(e) = shortcuts::update_config(app_handle, &shortcut_state, |config| {
+                config.ssh_tunnels.tunnels = stashed
+            }) {
+                log::warn!("Failed to remove SSH secrets from settings: {}", e);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("SSH secrets stay in settings.json: {}", e),
+    }
+
+    let state = app_handle.state::<SshTunnelState>();
+    let mut running = state.tunnels.lock().unwrap();
+    let stopping: Vec<JoinHandle<()>> = running
+        .drain()
+        .map(|(_, tunnel)| {
+            let _ = tunnel.stop.send(true);
+            tunnel.task
+        })
+        .collect();
+    let released: Released = futures::future::join_all(stopping).map(|_| ()).boxed().shared();
+
+    for tunnel in tunnels.into_iter().filter(|t| t.enabled) {
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let run = run_tunnel(app_handle.clone(), tunnel.clone(), stop_rx, released.clone());
+        let task = tauri::async_runtime::spawn(run);
+        running.insert(
+            tunnel.id.clone(),
+            RunningTunnel {
+                status: TunnelStatus::new(&tunnel),
+                stop: stop_tx,
+                task,
+            },
+        );
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_ssh_tunnel_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<SshTunnelSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().ssh_tunnels.clone())
+}
+
+/// Save the tunnels; blank passwords and passphrases keep the saved ones
+#[tauri::command]
+pub async fn set_ssh_tunnel_settings(
+    mut settings: SshTunnelSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    check_local_ports(&settings.tunnels)?;
+    stash_secrets(&mut settings.tunnels)?;
+    let removed: Vec<String> = {
+        let config = shortcut_state.config.lock().unwrap();
+        let kept: std::collections::HashSet<&str> = settings.tunnels.iter().map(|t| t.id.as_str()).collect();
+        config.ssh_tunnels.tunnels.iter().filter(|t| !kept.contains(t.id.as_str())).map(|t| t.id.clone()).collect()
+    };
+    for id in removed {
+        if let Ok(entry) = keyring_entry(&id) {
+            let _ = entry.delete_credential();
+        }
+    }
+
+    log::info!("Setting SSH tunnels: {} configured", settings.tunnels.len());
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.ssh_tunnels = settings)?;
+    apply_settings(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_ssh_tunnel_status(
+    tunnel_state: State<'_, SshTunnelState>,
+) -> Result<Vec<TunnelStatus>, String> {
+    Ok(tunnel_state
+        .tunnels
+        .lock()
+        .unwrap()
+        .values()
+        .map(|t| t.status.clone())
+        .collect())
+}
+
+/// Forget a tunnel's saved host key (after the server was legitimately re-keyed)
+#[tauri::command]
+pub async fn ssh_tunnel_reset_host_key(
+    id: String,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| {
+        if let Some(t) = config.ssh_tunnels.tunnels.iter_mut().find(|t| t.id == id) {
+            t.host_key_fingerprint = None;
+        }
+    })?;
+    apply_settings(&app_handle);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel(id: &str, local_port: u16) -> SshTunnelConfig {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "provider": "ollama",
+            "host": "gpu-box.lan",
+            "username": "me",
+            "auth": { "method": "key", "path": "~/.ssh/id_ed25519" },
+            "localPort": local_port,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn tunnel_specs_default_to_a_remote_ollama() {
+        let tunnel = tunnel("gpu", 11500);
+        assert!(tunnel.enabled);
+        assert_eq!((tunnel.port, tunnel.remote_host.as_str(), tunnel.remote_port), (22, "127.0.0.1", 11434));
+        assert_eq!(tunnel.local_url(), "http://127.0.0.1:11500");
+        assert!(matches!(&tunnel.auth, SshAuth::Key { path, passphrase: None } if path == "~/.ssh/id_ed25519"));
+
+        let password: SshAuth = serde_json::from_str(r#"{"method":"password","password":"hunter2"}"#).unwrap();
+        let mut with_password = SshTunnelConfig { auth: password, ..tunnel };
+        assert_eq!(with_password.secret_mut().map(|s| s.as_str()), Some("hunter2"));
+    }
+
+    #[test]
+    fn enabled_tunnels_need_their_own_local_port() {
+        assert!(check_local_ports(&[tunnel("a", 11500), tunnel("b", 11501)]).is_ok());
+        let clash = check_local_ports(&[tunnel("a", 11500), tunnel("b", 11500)]).unwrap_err();
+        assert_eq!(clash, "Local port 11500 is used by more than one tunnel");
+        assert!(check_local_ports(&[tunnel("a", 0)]).is_err());
+
+        // A disabled tunnel doesn't bind its port
+        let disabled = SshTunnelConfig { enabled: false, ..tunnel("b", 11500) };
+        assert!(check_local_ports(&[tunnel("a", 11500), disabled]).is_ok());
+    }
+
+    #[test]
+    fn status_follows_the_session_through_failures() {
+        let mut status = TunnelStatus::new(&tunnel("gpu", 11500));
+        assert_eq!((status.state, status.attempts), (TunnelState::Connecting, 0));
+
+        status.lost("SSH connection to gpu-box.lan:22 failed".to_string());
+        status.lost("SSH session closed".to_string());
+        assert_eq!((status.state, status.attempts), (TunnelState::Reconnecting, 2));
+        assert_eq!(status.last_error.as_deref(), Some("SSH session closed"));
+
+        status.connected();
+        assert_eq!((status.state, status.attempts, status.last_error.clone()), (TunnelState::Connected, 0, None));
+
+        status.stopped("Port 11500 unavailable".to_string());
+        assert_eq!(status.state, TunnelState::Stopped);
+        assert_eq!(status.last_error.as_deref(), Some("Port 11500 unavailable"));
+    }
+
+    #[test]
+    fn reconnects_back_off_up_to_a_minute() {
+        let mut backoff = Duration::from_secs(1);
+        let waits: Vec<u64> = (0..8)
+            .map(|_| {
+                backoff = next_backoff(backoff);
+                backoff.as_secs()
+            })
+            .collect();
+        assert_eq!(waits, [2, 4, 8, 16, 32, 60, 60, 60]);
+    }
+}
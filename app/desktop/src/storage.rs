@@ -0,0 +1,27 @@
+// In src-tauri/src/storage.rs
+//
+// Local SQLite databases kept next to settings.json in the app data directory.
+
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+/// Open (creating if needed) `<app_data_dir>/<name>.sqlite` and apply `schema`.
+pub fn open_database(app_handle: &AppHandle, name: &str, schema: &str) -> Result<Connection, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
+
+    let path = app_data_dir.join(format!("{}.sqlite", name));
+    let conn = Connection::open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    // WAL keeps readers (query commands) from blocking the background writers
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.execute_batch(schema).map_err(|e| e.to_string())?;
+
+    log::info!("Opened database at {}", path.display());
+    Ok(conn)
+}
@@ -0,0 +1,124 @@
+// In src-tauri/src/structured_output.rs
+//
+// JSON-schema constrained replies for `inference::chat`. A request carrying an
+// `outputSchema` asks the provider for structured output (`response_format` with the
+// schema for OpenAI-compatible providers and Ollama; an instruction in the system prompt
+// for Anthropic, which has no such option) and checks the reply against the schema. A
+// reply that isn't JSON, or doesn't match, is sent back to the model together with what
+// was wrong, up to `maxReasks` times, so agent logic only ever sees conforming output.
+
+use crate::inference::ChatMessage;
+use serde::Deserialize;
+
+/// Validation errors quoted back to the model at most
+const MAX_REPORTED_ERRORS: usize = 5;
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSchema {
+    /// Name providers show the model; letters, digits, `_` and `-`
+    #[serde(default = "default_name")]
+    pub name: String,
+    pub schema: serde_json::Value,
+    /// How often a non-conforming reply is sent back before giving up
+    #[serde(default = "default_max_reasks")]
+    pub max_reasks: u32,
+}
+
+fn default_name() -> String {
+    "response".to_string()
+}
+
+fn default_max_reasks() -> u32 {
+    2
+}
+
+pub fn compile(schema: &OutputSchema) -> Result<jsonschema::Validator, String> {
+    jsonschema::validator_for(&schema.schema).map_err(|e| format!("Invalid output schema: {}", e))
+}
+
+/// The `response_format` request field asking for `schema`
+pub fn response_format(schema: &OutputSchema) -> serde_json::Value {
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": { "name": schema.name, "schema": schema.schema, "strict": true }
+    })
+}
+
+/// A system prompt line standing in for `response_format`, for providers without it
+pub fn instruction(response_format: &serde_json::Value) -> Option<String> {
+    let schema = response_format["json_schema"].get("schema")?;
+    Some(format!(
+        "Reply with a single JSON value that matches this JSON schema, and nothing else:\n{}",
+        schema
+    ))
+}
+
+/// The reply as JSON if it matches, else what is wrong with it. Tolerates a Markdown code
+/// fence around the JSON.
+pub fn check(validator: &jsonschema::Validator, content: &str) -> Result<serde_json::Value, String> {
+    let text = content.trim();
+    let text = match text.strip_prefix("```") {
+        Some(fenced) => fenced
+            .trim_start_matches(|c: char| c.is_ascii_alphanumeric())
+            .trim_end()
+            .trim_end_matches("```"),
+        None => text,
+    };
+    let output: serde_json::Value =
+        serde_json::from_str(text.trim()).map_err(|e| format!("The reply is not valid JSON: {}", e))?;
+
+    let errors: Vec<String> = validator
+        .iter_errors(&output)
+        .take(MAX_REPORTED_ERRORS)
+        .map(|e| match e.instance_path.to_string() {
+            path if path.is_empty() => e.to_string(),
+            path => format!("{}: {}", path, e),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(output)
+    } else {
+        Err(format!("The reply does not match the schema: {}", errors.join("; ")))
+    }
+}
+
+/// The turns appended to the conversation to have the model try again
+pub fn reask_messages(reply: &str, problem: &str) -> [ChatMessage; 2] {
+    [
+        ChatMessage::text("assistant", reply),
+        ChatMessage::text(
+            "user",
+            format!("{}. Reply again with only JSON that matches the required schema.", problem),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_replies_against_the_schema() {
+        let schema: OutputSchema = serde_json::from_value(serde_json::json!({
+            "schema": {
+                "type": "object",
+                "properties": { "app": { "type": "string" }, "idle": { "type": "boolean" } },
+                "required": ["app", "idle"]
+            }
+        }))
+        .unwrap();
+        assert_eq!(schema.max_reasks, 2);
+        let validator = compile(&schema).unwrap();
+
+        let fenced = "```json\n{ \"app\": \"Code\", \"idle\": false }\n```";
+        assert_eq!(check(&validator, fenced).unwrap(), serde_json::json!({ "app": "Code", "idle": false }));
+        assert!(check(&validator, "The user is coding.").unwrap_err().contains("not valid JSON"));
+        let wrong = check(&validator, r#"{ "app": "Code", "idle": "no" }"#).unwrap_err();
+        assert!(wrong.contains("/idle"), "{}", wrong);
+
+        let format = response_format(&schema);
+        assert_eq!(format["json_schema"]["name"], "response");
+        assert!(instruction(&format).unwrap().contains("\"required\""));
+    }
+}
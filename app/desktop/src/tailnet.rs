@@ -0,0 +1,511 @@
+// In src-tauri/src/tailnet.rs
+//
+// Tailscale-aware endpoint resolution. Provider and link endpoints are usually
+// configured with a LAN address ("192.168.1.20:11434", "gpu-box") that stops working as
+// soon as the laptop leaves home. When Tailscale is running and the endpoint's host is a
+// tailnet peer, we also try the peer's tailnet IP and MagicDNS name and route traffic to
+// the first one that answers, re-checking periodically.
+//
+// Tailnet state comes from `tailscale status --json`; nothing is changed in the user's
+// configuration, only where requests are sent.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often endpoints are re-resolved
+const RESOLVE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TailnetSettings {
+    /// Resolve endpoints through the tailnet when Tailscale is running
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Use the tailnet address even when the configured one is also reachable
+    #[serde(default = "default_true")]
+    pub prefer_tailnet: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for TailnetSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prefer_tailnet: true,
+        }
+    }
+}
+
+// Subset of `tailscale status --json`
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+struct TailscaleStatus {
+    #[serde(default)]
+    backend_state: String,
+    #[serde(default, rename = "MagicDNSSuffix")]
+    magic_dns_suffix: String,
+    #[serde(default, rename = "Self")]
+    self_node: Option<TailscalePeer>,
+    #[serde(default)]
+    peer: HashMap<String, TailscalePeer>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct TailscalePeer {
+    #[serde(default)]
+    host_name: String,
+    #[serde(default, rename = "DNSName")]
+    dns_name: String,
+    #[serde(default)]
+    tailscale_i_ps: Vec<String>,
+    /// Direct endpoints (ip:port), which include the peer's LAN address
+    #[serde(default)]
+    addrs: Option<Vec<String>>,
+    #[serde(default)]
+    online: bool,
+}
+
+impl TailscalePeer {
+    fn magic_dns_name(&self) -> Option<&str> {
+        let name = self.dns_name.trim_end_matches('.');
+        (!name.is_empty()).then_some(name)
+    }
+
+    /// Whether `host` (as configured by the user) refers to this peer
+    fn matches(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let short_dns = self.dns_name.split('.').next().unwrap_or_default().to_lowercase();
+        host == self.host_name.to_lowercase()
+            || host == short_dns
+            || self.magic_dns_name().is_some_and(|n| n.eq_ignore_ascii_case(&host))
+            || self.tailscale_i_ps.iter().any(|ip| *ip == host)
+            || self.addrs.iter().flatten().any(|addr| {
+                addr.parse::<SocketAddr>()
+                    .map(|a| a.ip().to_string() == host)
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// Tailscale's CGNAT (100.64.0.0/10) and ULA (fd7a:115c:a1e0::/48) ranges
+pub fn is_tailnet_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            a == 100 && (64..128).contains(&b)
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            s[0] == 0xfd7a && s[1] == 0x115c && s[2] == 0xa1e0
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CandidateSource {
+    Configured,
+    TailnetIp,
+    MagicDns,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+    host: String,
+    source: CandidateSource,
+    reachable: bool,
+    latency_ms: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointDiagnostics {
+    endpoint: String,
+    tailscale_running: bool,
+    /// MagicDNS suffix of the tailnet this machine is on
+    tailnet: Option<String>,
+    /// This machine's tailnet addresses
+    self_ips: Vec<String>,
+    /// The peer the endpoint's host maps to on the tailnet
+    peer: Option<String>,
+    peer_online: Option<bool>,
+    candidates: Vec<Candidate>,
+    /// Host traffic goes to (the configured host if nothing better was found)
+    selected: String,
+}
+
+/// Hosts to try for `host`, in preference order (configured last when preferring the tailnet)
+fn candidate_hosts(
+    status: &TailscaleStatus,
+    host: &str,
+    prefer_tailnet: bool,
+) -> Vec<(String, CandidateSource)> {
+    let mut tailnet = Vec::new();
+    if let Some(peer) = status.peer.values().find(|p| p.matches(host)) {
+        for ip in &peer.tailscale_i_ps {
+            // IPv4 first; it's what most model servers bind
+            let source = CandidateSource::TailnetIp;
+            if ip.contains(':') {
+                tailnet.push((format!("[{}]", ip), source));
+            } else {
+                tailnet.insert(0, (ip.clone(), source));
+            }
+        }
+        if let Some(name) = peer.magic_dns_name() {
+            tailnet.push((name.to_string(), CandidateSource::MagicDns));
+        }
+    }
+    tailnet.retain(|(h, _)| !h.eq_ignore_ascii_case(host));
+
+    let configured = (host.to_string(), CandidateSource::Configured);
+    if prefer_tailnet {
+        tailnet.push(configured);
+        tailnet
+    } else {
+        let mut all = vec![configured];
+        all.extend(tailnet);
+        all
+    }
+}
+
+fn tailscale_binary() -> &'static str {
+    #[cfg(target_os = "macos")]
+    {
+        let app = "/Applications/Tailscale.app/Contents/MacOS/Tailscale";
+        if std::path::Path::new(app).exists() {
+            return app;
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let exe = "C:\\Program Files\\Tailscale\\tailscale.exe";
+        if std::path::Path::new(exe).exists() {
+            return exe;
+        }
+    }
+    "tailscale"
+}
+
+fn tailscale_status() -> Option<TailscaleStatus> {
+    let output = Command::new(tailscale_binary())
+        .args(["status", "--json"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let status: TailscaleStatus = serde_json::from_slice(&output.stdout)
+        .map_err(|e| log::debug!("Unparseable tailscale status: {}", e))
+        .ok()?;
+    (status.backend_state == "Running").then_some(status)
+}
+
+fn probe(host: &str, port: u16) -> Result<Duration, String> {
+    let started = Instant::now();
+    let addrs = format!("{}:{}", host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("DNS lookup failed: {}", e))?;
+    let mut last_error = "No addresses".to_string();
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, PROBE_TIMEOUT) {
+            Ok(_) => return Ok(started.elapsed()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+/// Split an endpoint ("http://host:port/path" or "host:port") into host and port
+fn host_port(endpoint: &str) -> Option<(String, u16)> {
+    if endpoint.contains("://") {
+        let url = reqwest::Url::parse(endpoint).ok()?;
+        Some((url.host_str()?.to_string(), url.port_or_known_default()?))
+    } else {
+        let (host, port) = endpoint.rsplit_once(':')?;
+        Some((host.to_string(), port.parse().ok()?))
+    }
+}
+
+fn diagnose(endpoint: &str, status: Option<&TailscaleStatus>, prefer_tailnet: bool) -> Option<EndpointDiagnostics> {
+    let (host, port) = host_port(endpoint)?;
+    let bare_host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let peer = status.and_then(|s| s.peer.values().find(|p| p.matches(bare_host)));
+    // Endpoints already configured with a tailnet address need no help
+    let on_tailnet = bare_host.parse::<IpAddr>().is_ok_and(is_tailnet_ip);
+    let hosts = match status {
+        Some(status) if !on_tailnet => candidate_hosts(status, bare_host, prefer_tailnet),
+        _ => Vec::new(),
+    };
+    let hosts = if hosts.is_empty() {
+        vec![(host.clone(), CandidateSource::Configured)]
+    } else {
+        hosts
+    };
+
+    let candidates: Vec<Candidate> = hosts
+        .into_iter()
+        .map(|(h, source)| {
+            let h = if source == CandidateSource::Configured { host.clone() } else { h };
+            let result = probe(&h, port);
+            Candidate {
+                host: h,
+                source,
+                reachable: result.is_ok(),
+                latency_ms: result.as_ref().ok().map(|d| d.as_millis() as u64),
+                error: result.err(),
+            }
+        })
+        .collect();
+
+    let selected = candidates
+        .iter()
+        .find(|c| c.reachable)
+        .map(|c| c.host.clone())
+        .unwrap_or_else(|| host.clone());
+
+    Some(EndpointDiagnostics {
+        endpoint: endpoint.to_string(),
+        tailscale_running: status.is_some(),
+        tailnet: status
+            .map(|s| s.magic_dns_suffix.clone())
+            .filter(|s| !s.is_empty()),
+        self_ips: status
+            .and_then(|s| s.self_node.as_ref())
+            .map(|n| n.tailscale_i_ps.clone())
+            .unwrap_or_default(),
+        peer: peer.map(|p| p.host_name.clone()),
+        peer_online: peer.map(|p| p.online),
+        candidates,
+        selected,
+    })
+}
+
+/// Replace the host of `endpoint` with `host`
+fn with_host(endpoint: &str, host: &str) -> String {
+    if endpoint.contains("://") {
+        let Ok(mut url) = reqwest::Url::parse(endpoint) else {
+            return endpoint.to_string();
+        };
+        if url.set_host(Some(host)).is_err() {
+            return endpoint.to_string();
+        }
+        let mut rewritten = url.to_string();
+        // Url adds a "/" path; keep "http://host:port" joinable with request paths
+        if !endpoint.ends_with('/') && url.path() == "/" {
+            rewritten.pop();
+        }
+        rewritten
+    } else {
+        match host_port(endpoint) {
+            Some((_, port)) => format!("{}:{}", host, port),
+            None => endpoint.to_string(),
+        }
+    }
+}
+
+pub struct TailnetState {
+    /// Configured endpoint -> endpoint to actually use
+    resolved: Mutex<HashMap<String, String>>,
+}
+
+impl TailnetState {
+    pub fn new() -> Self {
+        Self {
+            resolved: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Where requests for a configured endpoint should go
+pub fn resolve(app_handle: &AppHandle, endpoint: &str) -> String {
+    app_handle
+        .state::<TailnetState>()
+        .resolved
+        .lock()
+        .unwrap()
+        .get(endpoint)
+        .cloned()
+        .unwrap_or_else(|| endpoint.to_string())
+}
+
+/// Endpoints the app talks to that are worth resolving
+fn watched_endpoints(app_handle: &AppHandle) -> Vec<String> {
+    let mut endpoints = Vec::new();
+    if let Some(url) = app_handle.state::<crate::AppSettings>().ollama_url.lock().unwrap().clone() {
+        endpoints.push(url);
+    }
+    let config = app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().clone();
+    if let Some(addr) = config.remote.receiver_addr {
+        endpoints.push(addr);
+    }
+    endpoints
+}
+
+/// Spawn the periodic resolver
+pub fn start_resolver(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        let settings = app_handle
+            .state::<UnifiedShortcutState>()
+            .config
+            .lock()
+            .unwrap()
+            .tailnet
+            .clone();
+
+        let mut resolved = HashMap::new();
+        if settings.enabled {
+            if let Some(status) = tailscale_status() {
+                for endpoint in watched_endpoints(&app_handle) {
+                    let Some(diag) = diagnose(&endpoint, Some(&status), settings.prefer_tailnet) else {
+                        continue;
+                    };
+                    let selected = with_host(&endpoint, &diag.selected);
+                    if selected != endpoint {
+                        resolved.insert(endpoint, selected);
+                    }
+                }
+            }
+        }
+
+        let state = app_handle.state::<TailnetState>();
+        let changed = {
+            let mut current = state.resolved.lock().unwrap();
+            let changed = *current != resolved;
+            *current = resolved.clone();
+            changed
+        };
+        if changed {
+            for (from, to) in &resolved {
+                log::info!("Routing {} via tailnet as {}", from, to);
+            }
+            if let Err(e) = app_handle.emit("tailnet-endpoints-changed", &resolved) {
+                log::warn!("Failed to emit tailnet-endpoints-changed event: {}", e);
+            }
+        }
+
+        std::thread::sleep(RESOLVE_INTERVAL);
+    });
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_tailnet_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<TailnetSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().tailnet.clone())
+}
+
+#[tauri::command]
+pub async fn set_tailnet_settings(
+    settings: TailnetSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting tailnet settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.tailnet = settings)
+}
+
+/// Reachability report for an endpoint (defaults to every endpoint the app uses)
+#[tauri::command]
+pub async fn tailnet_diagnostics(
+    endpoint: Option<String>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<Vec<EndpointDiagnostics>, String> {
+    let prefer_tailnet = shortcut_state.config.lock().unwrap().tailnet.prefer_tailnet;
+    let endpoints = match endpoint {
+        Some(endpoint) => vec![endpoint],
+        None => watched_endpoints(&app_handle),
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let status = tailscale_status();
+        endpoints
+            .iter()
+            .map(|e| diagnose(e, status.as_ref(), prefer_tailnet).ok_or_else(|| format!("Invalid endpoint: {}", e)))
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> TailscaleStatus {
+        serde_json::from_str(
+            r#"{
+                "BackendState": "Running",
+                "MagicDNSSuffix": "tail1234.ts.net",
+                "Self": { "HostName": "laptop", "DNSName": "laptop.tail1234.ts.net.", "TailscaleIPs": ["100.101.1.1"], "Online": true },
+                "Peer": {
+                    "nodekey:abc": {
+                        "HostName": "gpu-box",
+                        "DNSName": "gpu-box.tail1234.ts.net.",
+                        "TailscaleIPs": ["100.100.7.7", "fd7a:115c:a1e0::7"],
+                        "Addrs": ["192.168.1.20:41641", "203.0.113.9:41641"],
+                        "Online": true
+                    }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn detects_tailnet_ranges() {
+        assert!(is_tailnet_ip("100.64.0.1".parse().unwrap()));
+        assert!(is_tailnet_ip("100.127.255.255".parse().unwrap()));
+        assert!(!is_tailnet_ip("100.128.0.1".parse().unwrap()));
+        assert!(!is_tailnet_ip("192.168.1.20".parse().unwrap()));
+        assert!(is_tailnet_ip("fd7a:115c:a1e0::7".parse().unwrap()));
+    }
+
+    #[test]
+    fn lan_address_maps_to_tailnet_peer() {
+        let hosts = candidate_hosts(&status(), "192.168.1.20", true);
+        let names: Vec<&str> = hosts.iter().map(|(h, _)| h.as_str()).collect();
+        assert_eq!(
+            names,
+            ["100.100.7.7", "[fd7a:115c:a1e0::7]", "gpu-box.tail1234.ts.net", "192.168.1.20"]
+        );
+        assert_eq!(hosts.last().unwrap().1, CandidateSource::Configured);
+
+        let hosts = candidate_hosts(&status(), "GPU-BOX", false);
+        assert_eq!(hosts[0].1, CandidateSource::Configured);
+        assert_eq!(hosts.len(), 4);
+    }
+
+    #[test]
+    fn unknown_hosts_are_left_alone() {
+        let hosts = candidate_hosts(&status(), "api.openai.com", true);
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].1, CandidateSource::Configured);
+    }
+
+    #[test]
+    fn rewrites_endpoint_host() {
+        assert_eq!(
+            with_host("http://192.168.1.20:11434", "100.100.7.7"),
+            "http://100.100.7.7:11434"
+        );
+        assert_eq!(with_host("http://gpu-box/v1", "100.100.7.7"), "http://100.100.7.7/v1");
+        assert_eq!(with_host("gpu-box:3839", "100.100.7.7"), "100.100.7.7:3839");
+    }
+}
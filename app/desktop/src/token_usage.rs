@@ -0,0 +1,463 @@
+// In src-tauri/src/token_usage.rs
+//
+// Token accounting for model calls. Prompt and completion tokens are added up per day,
+// agent and provider in token_usage.sqlite, from backend inference (`inference::chat`)
+// and from replies streamed back through the Ollama proxy, where the counts are read off
+// the final chunk.
+//
+// An agent can be given a daily token budget. Once its total for the day reaches the
+// budget it is told to stop, an `agent-budget-exceeded` event goes out, and its further
+// model calls are refused until the next day or until the budget is raised.
+
+use crate::inference::TokenUsage;
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::storage;
+use crate::usage::{since_day, today};
+use crate::{commands, CommandState};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+pub const BUDGET_EXCEEDED_EVENT: &str = "agent-budget-exceeded";
+
+/// Requests without an agent are recorded under this id
+const NO_AGENT: &str = "";
+
+/// Proxied bytes kept while looking for the end of a line; a longer line isn't read
+const MAX_PENDING_LINE: usize = 4 * 1024 * 1024;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS token_usage (
+    day TEXT NOT NULL,
+    agent_id TEXT NOT NULL,
+    provider_id TEXT NOT NULL,
+    prompt_tokens INTEGER NOT NULL DEFAULT 0,
+    completion_tokens INTEGER NOT NULL DEFAULT 0,
+    requests INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, agent_id, provider_id)
+);
+";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBudgetSettings {
+    /// Prompt + completion tokens each agent may use per day
+    #[serde(default)]
+    pub daily_budgets: HashMap<String, u64>,
+}
+
+/// Payload of `agent-budget-exceeded`
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetEvent {
+    pub agent_id: String,
+    pub used_tokens: u64,
+    pub budget_tokens: u64,
+}
+
+/// Why a model call was refused
+#[derive(Debug)]
+pub struct BudgetExceeded {
+    pub agent_id: String,
+    pub used_tokens: u64,
+    pub budget_tokens: u64,
+}
+
+impl BudgetExceeded {
+    pub fn into_response(self) -> axum::response::Response {
+        axum::response::Response::builder()
+            .status(axum::http::StatusCode::TOO_MANY_REQUESTS)
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(
+                serde_json::json!({ "error": self.to_string() }).to_string(),
+            ))
+            .unwrap()
+    }
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Agent {} has used its daily token budget ({} of {} tokens)",
+            self.agent_id, self.used_tokens, self.budget_tokens
+        )
+    }
+}
+
+pub struct TokenUsageState {
+    db: Mutex<Option<Connection>>,
+    /// (day, agent) pairs already stopped, so each crossing is announced once
+    stopped: Mutex<HashSet<(String, String)>>,
+}
+
+impl TokenUsageState {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let db = match storage::open_database(app_handle, "token_usage", SCHEMA) {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                log::error!("Token usage tracking disabled, database unavailable: {}", e);
+                None
+            }
+        };
+        Self {
+            db: Mutex::new(db),
+            stopped: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn with_db<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let guard = self.db.lock().unwrap();
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| "Token usage database unavailable".to_string())?;
+        f(conn).map_err(|e| e.to_string())
+    }
+
+    /// Prompt + completion tokens `agent_id` has used on `day`
+    fn used_on(&self, day: &str, agent_id: &str) -> Result<u64, String> {
+        self.with_db(|conn| {
+            conn.query_row(
+                "SELECT COALESCE(SUM(prompt_tokens + completion_tokens), 0) FROM token_usage
+                 WHERE day = ?1 AND agent_id = ?2",
+                params![day, agent_id],
+                |row| row.get::<_, i64>(0),
+            )
+        })
+        .map(|used| used.max(0) as u64)
+    }
+}
+
+fn daily_budget(app_handle: &AppHandle, agent_id: &str) -> Option<u64> {
+    let shortcut_state = app_handle.state::<UnifiedShortcutState>();
+    let config = shortcut_state.config.lock().unwrap();
+    config.token_budgets.daily_budgets.get(agent_id).copied()
+}
+
+/// Err if `agent_id` has used up today's budget. Call before queueing a model call.
+pub fn check_budget(app_handle: &AppHandle, agent_id: Option<&str>) -> Result<(), BudgetExceeded> {
+    let Some(agent_id) = agent_id else {
+        return Ok(());
+    };
+    let Some(budget) = daily_budget(app_handle, agent_id) else {
+        return Ok(());
+    };
+    let state = app_handle.state::<TokenUsageState>();
+    let used = match state.used_on(&today(), agent_id) {
+        Ok(used) => used,
+        Err(e) => {
+            log::warn!("Failed to read token usage for {}: {}", agent_id, e);
+            return Ok(());
+        }
+    };
+    if used < budget {
+        return Ok(());
+    }
+    stop_agent(app_handle, agent_id, used, budget);
+    Err(BudgetExceeded { agent_id: agent_id.to_string(), used_tokens: used, budget_tokens: budget })
+}
+
+/// Add a finished call's tokens, stopping the agent if that takes it over its budget
+pub fn record(app_handle: &AppHandle, agent_id: Option<&str>, provider_id: &str, usage: &TokenUsage) {
+    let state = app_handle.state::<TokenUsageState>();
+    let day = today();
+    let result = state.with_db(|conn| {
+        conn.execute(
+            "INSERT INTO token_usage (day, agent_id, provider_id, prompt_tokens, completion_tokens, requests)
+             VALUES (?1, ?2, ?3, ?4, ?5, 1)
+             ON CONFLICT(day, agent_id, provider_id) DO UPDATE SET
+                 prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                 completion_tokens = completion_tokens + excluded.completion_tokens,
+                 requests = requests + 1",
+            params![
+                day,
+                agent_id.unwrap_or(NO_AGENT),
+                provider_id,
+                usage.prompt_tokens as i64,
+                usage.completion_tokens as i64
+            ],
+        )
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to record token usage for {:?}: {}", agent_id, e);
+        return;
+    }
+
+    let Some(agent_id) = agent_id else {
+        return;
+    };
+    let Some(budget) = daily_budget(app_handle, agent_id) else {
+        return;
+    };
+    if let Ok(used) = state.used_on(&day, agent_id) {
+        if used >= budget {
+            stop_agent(app_handle, agent_id, used, budget);
+        }
+    }
+}
+
+/// Tell the agent to stop and announce it, once per agent and day
+fn stop_agent(app_handle: &AppHandle, agent_id: &str, used: u64, budget: u64) {
+    let day = today();
+    {
+        let state = app_handle.state::<TokenUsageState>();
+        let mut stopped = state.stopped.lock().unwrap();
+        stopped.retain(|(stopped_day, _)| *stopped_day == day);
+        if !stopped.insert((day, agent_id.to_string())) {
+            return;
+        }
+    }
+
+    log::warn!("Agent {} reached its daily token budget ({} of {}); stopping it", agent_id, used, budget);
+    commands::broadcast_command(&app_handle.state::<CommandState>(), agent_id.to_string(), "stop".to_string());
+    let event = BudgetEvent {
+        agent_id: agent_id.to_string(),
+        used_tokens: used,
+        budget_tokens: budget,
+    };
+    if let Err(e) = app_handle.emit(BUDGET_EXCEEDED_EVENT, &event) {
+        log::error!("Failed to emit {}: {}", BUDGET_EXCEEDED_EVENT, e);
+    }
+}
+
+/// Token counts in a reply: OpenAI-style `usage`, or Ollama's `prompt_eval_count` and
+/// `eval_count` on its final object
+fn usage_in(value: &serde_json::Value) -> Option<TokenUsage> {
+    let count = |value: &serde_json::Value, name: &str| value.get(name).and_then(serde_json::Value::as_u64);
+    match value.get("usage").filter(|usage| usage.is_object()) {
+        Some(usage) => Some(TokenUsage {
+            prompt_tokens: count(usage, "prompt_tokens")?,
+            completion_tokens: count(usage, "completion_tokens").unwrap_or(0),
+        }),
+        None => Some(TokenUsage {
+            prompt_tokens: count(value, "prompt_eval_count").unwrap_or(0),
+            completion_tokens: count(value, "eval_count")?,
+        }),
+    }
+}
+
+/// Reads the token counts out of a proxied reply (one JSON object, NDJSON or SSE) as it
+/// streams past, and records them when the stream is dropped
+pub struct TokenTally {
+    app_handle: AppHandle,
+    agent_id: Option<String>,
+    provider_id: String,
+    pending: Vec<u8>,
+    usage: Option<TokenUsage>,
+}
+
+impl TokenTally {
+    pub fn new(app_handle: AppHandle, agent_id: Option<String>, provider_id: String) -> Self {
+        Self { app_handle, agent_id, provider_id, pending: Vec::new(), usage: None }
+    }
+
+    pub fn add(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.scan(&line);
+        }
+        if self.pending.len() > MAX_PENDING_LINE {
+            self.pending.clear();
+        }
+    }
+
+    fn scan(&mut self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        let line = line.strip_prefix("data:").unwrap_or(line).trim_start();
+        if let Some(usage) = serde_json::from_str(line).ok().as_ref().and_then(usage_in) {
+            self.usage = Some(usage);
+        }
+    }
+}
+
+impl Drop for TokenTally {
+    fn drop(&mut self) {
+        let rest = std::mem::take(&mut self.pending);
+        self.scan(&rest);
+        if let Some(usage) = &self.usage {
+            record(&self.app_handle, self.agent_id.as_deref(), &self.provider_id, usage);
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenTotals {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Calls that reported their token counts
+    pub requests: u64,
+}
+
+impl TokenTotals {
+    fn add(&mut self, prompt_tokens: u64, completion_tokens: u64, requests: u64) {
+        self.prompt_tokens += prompt_tokens;
+        self.completion_tokens += completion_tokens;
+        self.requests += requests;
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyTokens {
+    pub day: String,
+    #[serde(flatten)]
+    pub totals: TokenTotals,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderTokens {
+    pub provider_id: String,
+    #[serde(flatten)]
+    pub totals: TokenTotals,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentTokens {
+    /// None for calls made without an agent
+    pub agent_id: Option<String>,
+    #[serde(flatten)]
+    pub totals: TokenTotals,
+    pub today_tokens: u64,
+    pub daily_budget: Option<u64>,
+    pub over_budget: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsageReport {
+    pub from_day: String,
+    pub to_day: String,
+    pub daily: Vec<DailyTokens>,
+    pub agents: Vec<AgentTokens>,
+    pub providers: Vec<ProviderTokens>,
+}
+
+// Tauri commands
+
+/// Token totals over the last `days` days (default 30): per day, per agent (with today's
+/// total against its budget) and per provider. Agents with a budget are listed even
+/// without usage.
+#[tauri::command]
+pub async fn get_usage_cmd(
+    days: Option<u32>,
+    token_state: State<'_, TokenUsageState>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<TokenUsageReport, String> {
+    let (from_day, to_day) = (since_day(days.unwrap_or(30)), today());
+    let rows = token_state.with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT day, agent_id, provider_id, prompt_tokens, completion_tokens, requests
+             FROM token_usage WHERE day >= ?1 AND day <= ?2",
+        )?;
+        let rows = stmt.query_map(params![from_day, to_day], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?.max(0) as u64,
+                row.get::<_, i64>(4)?.max(0) as u64,
+                row.get::<_, i64>(5)?.max(0) as u64,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+    let budgets = shortcut_state.config.lock().unwrap().token_budgets.daily_budgets.clone();
+
+    let mut daily: BTreeMap<String, TokenTotals> = BTreeMap::new();
+    let mut providers: BTreeMap<String, TokenTotals> = BTreeMap::new();
+    let mut agents: BTreeMap<String, (TokenTotals, u64)> =
+        budgets.keys().map(|agent_id| (agent_id.clone(), Default::default())).collect();
+    for (day, agent_id, provider_id, prompt, completion, requests) in rows {
+        daily.entry(day.clone()).or_default().add(prompt, completion, requests);
+        providers.entry(provider_id).or_default().add(prompt, completion, requests);
+        let (totals, today_tokens) = agents.entry(agent_id).or_default();
+        totals.add(prompt, completion, requests);
+        if day == to_day {
+            *today_tokens += prompt + completion;
+        }
+    }
+
+    Ok(TokenUsageReport {
+        daily: daily.into_iter().map(|(day, totals)| DailyTokens { day, totals }).collect(),
+        providers: providers
+            .into_iter()
+            .map(|(provider_id, totals)| ProviderTokens { provider_id, totals })
+            .collect(),
+        agents: agents
+            .into_iter()
+            .map(|(agent_id, (totals, today_tokens))| {
+                let daily_budget = budgets.get(&agent_id).copied();
+                AgentTokens {
+                    agent_id: Some(agent_id).filter(|id| id != NO_AGENT),
+                    totals,
+                    today_tokens,
+                    daily_budget,
+                    over_budget: daily_budget.is_some_and(|budget| today_tokens >= budget),
+                }
+            })
+            .collect(),
+        from_day,
+        to_day,
+    })
+}
+
+#[tauri::command]
+pub async fn get_token_budgets(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<TokenBudgetSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().token_budgets.clone())
+}
+
+/// Set an agent's daily token budget, or with `None` remove it. A stopped agent may be
+/// started again once the new budget is above what it has used today.
+#[tauri::command]
+pub async fn set_agent_token_budget(
+    agent_id: String,
+    daily_tokens: Option<u64>,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    token_state: State<'_, TokenUsageState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting daily token budget for {}: {:?}", agent_id, daily_tokens);
+    token_state.stopped.lock().unwrap().retain(|(_, stopped)| *stopped != agent_id);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| match daily_tokens {
+        Some(budget) => {
+            config.token_budgets.daily_budgets.insert(agent_id, budget);
+        }
+        None => {
+            config.token_budgets.daily_budgets.remove(&agent_id);
+        }
+    })
+}
+
+/// Delete all recorded token usage (budgets are kept)
+#[tauri::command]
+pub async fn clear_token_usage(token_state: State<'_, TokenUsageState>) -> Result<(), String> {
+    log::info!("Clearing recorded token usage");
+    token_state.stopped.lock().unwrap().clear();
+    token_state.with_db(|conn| conn.execute("DELETE FROM token_usage", []).map(|_| ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_token_counts_from_replies() {
+        let usage = |prompt_tokens, completion_tokens| Some(TokenUsage { prompt_tokens, completion_tokens });
+        let ollama = serde_json::json!({ "done": true, "prompt_eval_count": 812, "eval_count": 40 });
+        assert_eq!(usage_in(&ollama), usage(812, 40));
+        let openai = serde_json::json!({ "choices": [], "usage": { "prompt_tokens": 9, "completion_tokens": 3 } });
+        assert_eq!(usage_in(&openai), usage(9, 3));
+        // Mid-stream chunks carry neither
+        assert_eq!(usage_in(&serde_json::json!({ "message": { "content": "Hi" }, "done": false })), None);
+        assert_eq!(usage_in(&serde_json::json!({ "choices": [], "usage": null })), None);
+    }
+}
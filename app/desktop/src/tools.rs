@@ -0,0 +1,570 @@
+// In src-tauri/src/tools.rs
+//
+// Rust-side tools models can call during `inference::chat`: show a notification, capture
+// a screenshot, run a shell command, make an HTTP request. A request names the tools it
+// wants; they are offered to the model in the OpenAI function-calling form (translated
+// for Anthropic by `anthropic`), and each call the model makes is run here and its result
+// sent back until the model gives its answer.
+//
+// Nothing is available by default: each agent has to be allowed each tool in the tool
+// settings, and only requests from the main window get tools at all, since anyone else
+// (the API servers, other windows) could name any agent. Tools honor the same guards as
+// their frontend counterparts (incognito, Do Not Disturb, the outbound audit log);
+// commands and HTTP requests are also refused while capture is paused, and HTTP requests
+// can't reach this machine or its local network unless `allowLocalNetwork` is on. Errors
+// go back to the model as the call's result so it can react to them.
+
+use crate::dnd::{self, Route};
+use crate::inference::{Caller, ToolCall};
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::{audit, incognito, notifications, redaction};
+use serde::{Deserialize, Serialize};
+use reqwest::Url;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_screen_capture::pause;
+
+/// Tool output sent back to the model at most; longer output is cut
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Tool {
+    Notify,
+    CaptureScreenshot,
+    RunCommand,
+    HttpRequest,
+}
+
+const ALL_TOOLS: [Tool; 4] = [Tool::Notify, Tool::CaptureScreenshot, Tool::RunCommand, Tool::HttpRequest];
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Notify => "notify",
+            Tool::CaptureScreenshot => "capture_screenshot",
+            Tool::RunCommand => "run_command",
+            Tool::HttpRequest => "http_request",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Tool> {
+        ALL_TOOLS.into_iter().find(|tool| tool.name() == name)
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Tool::Notify => "Show the user a desktop notification.",
+            Tool::CaptureScreenshot => "Take a screenshot of the user's primary monitor and look at it.",
+            Tool::RunCommand => "Run a shell command on the user's computer and get its exit status and output.",
+            Tool::HttpRequest => "Make an HTTP request and get the status and response body.",
+        }
+    }
+
+    /// JSON schema of the call's arguments
+    fn parameters(self) -> serde_json::Value {
+        match self {
+            Tool::Notify => serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["title", "body"]
+            }),
+            Tool::CaptureScreenshot => serde_json::json!({ "type": "object", "properties": {} }),
+            Tool::RunCommand => serde_json::json!({
+                "type": "object",
+                "properties": { "command": { "type": "string", "description": "Passed to the system shell" } },
+                "required": ["command"]
+            }),
+            Tool::HttpRequest => serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string" },
+                    "method": { "type": "string", "description": "GET if omitted" },
+                    "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "body": { "type": "string" }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolSettings {
+    /// The tools each agent may call
+    #[serde(default)]
+    pub agents: HashMap<String, Vec<Tool>>,
+    /// Rounds of tool calls per request before it is given up on
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: u32,
+    #[serde(default = "default_command_timeout_secs")]
+    pub command_timeout_secs: u64,
+    /// Let `http_request` reach loopback, private and link-local addresses
+    #[serde(default)]
+    pub allow_local_network: bool,
+}
+
+fn default_max_rounds() -> u32 {
+    8
+}
+
+fn default_command_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for ToolSettings {
+    fn default() -> Self {
+        Self {
+            agents: HashMap::new(),
+            max_rounds: default_max_rounds(),
+            command_timeout_secs: default_command_timeout_secs(),
+            allow_local_network: false,
+        }
+    }
+}
+
+/// One tool call made for a request
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolRun {
+    pub name: String,
+    pub arguments: serde_json::Value,
+    pub ok: bool,
+    /// What the model was sent back
+    pub output: String,
+}
+
+/// What `list_tools` reports
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: serde_json::Value,
+}
+
+fn settings(app_handle: &AppHandle) -> ToolSettings {
+    app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().tools.clone()
+}
+
+/// The tools named in a request, if the agent may use all of them
+pub fn resolve(
+    app_handle: &AppHandle,
+    caller: Caller,
+    agent_id: Option<&str>,
+    names: &[String],
+) -> Result<Vec<Tool>, String> {
+    check_grants(&settings(app_handle), caller, agent_id, names)
+}
+
+fn check_grants(
+    settings: &ToolSettings,
+    caller: Caller,
+    agent_id: Option<&str>,
+    names: &[String],
+) -> Result<Vec<Tool>, String> {
+    if caller != Caller::App && !names.is_empty() {
+        return Err("Tools are only available to agents running in the app".to_string());
+    }
+    let allowed = agent_id.and_then(|id| settings.agents.get(id)).cloned().unwrap_or_default();
+    let mut tools = Vec::new();
+    for name in names {
+        let tool = Tool::from_name(name).ok_or_else(|| format!("Unknown tool {}", name))?;
+        if !allowed.contains(&tool) {
+            return Err(format!("Agent {} is not allowed to use {}", agent_id.unwrap_or("(none)"), name));
+        }
+        if !tools.contains(&tool) {
+            tools.push(tool);
+        }
+    }
+    Ok(tools)
+}
+
+pub fn max_rounds(app_handle: &AppHandle) -> u32 {
+    settings(app_handle).max_rounds
+}
+
+/// The `tools` request field offering `tools`
+pub fn definitions(tools: &[Tool]) -> serde_json::Value {
+    tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name(),
+                    "description": tool.description(),
+                    "parameters": tool.parameters()
+                }
+            })
+        })
+        .collect()
+}
+
+/// Run one call. Returns the record of it and, for screenshots, the image as a data URL.
+pub async fn run(
+    app_handle: &AppHandle,
+    agent_id: Option<&str>,
+    available: &[Tool],
+    call: &ToolCall,
+) -> (ToolRun, Option<String>) {
+    let arguments = match call.function.arguments.trim() {
+        "" => Ok(serde_json::json!({})),
+        text => serde_json::from_str::<serde_json::Value>(text).map_err(|e| format!("Arguments are not JSON: {}", e)),
+    };
+    let tool = Tool::from_name(&call.function.name).filter(|tool| available.contains(tool));
+    let result = match (tool, &arguments) {
+        (None, _) => Err(format!("No tool named {} is available", call.function.name)),
+        (_, Err(e)) => Err(e.clone()),
+        (Some(tool), Ok(arguments)) => {
+            log::info!("Agent {:?} calls {} with {}", agent_id, tool.name(), arguments);
+            match tool {
+                Tool::Notify => notify(app_handle, agent_id, arguments).map(|text| (text, None)),
+                Tool::CaptureScreenshot => capture_screenshot(app_handle).await.map(|(text, url)| (text, Some(url))),
+                Tool::RunCommand => run_command(app_handle, arguments).await.map(|text| (text, None)),
+                Tool::HttpRequest => http_request(app_handle, agent_id, arguments).await.map(|text| (text, None)),
+            }
+        }
+    };
+    if let Err(e) = &result {
+        log::warn!("Tool call {} for {:?} failed: {}", call.function.name, agent_id, e);
+    }
+
+    let (ok, output, image) = match result {
+        Ok((text, image)) => (true, text, image),
+        Err(e) => (false, format!("Error: {}", e), None),
+    };
+    let run = ToolRun {
+        name: call.function.name.clone(),
+        arguments: arguments.unwrap_or(serde_json::Value::Null),
+        ok,
+        output: truncate(output),
+    };
+    (run, image)
+}
+
+fn truncate(mut text: String) -> String {
+    if text.len() > MAX_OUTPUT_BYTES {
+        let mut end = MAX_OUTPUT_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n[output truncated]");
+    }
+    text
+}
+
+fn string_arg<'a>(arguments: &'a serde_json::Value, name: &str) -> Result<&'a str, String> {
+    arguments[name].as_str().ok_or_else(|| format!("Missing string argument {}", name))
+}
+
+fn notify(app_handle: &AppHandle, agent_id: Option<&str>, arguments: &serde_json::Value) -> Result<String, String> {
+    let (title, body) = (string_arg(arguments, "title")?, string_arg(arguments, "body")?);
+    if incognito::is_active(app_handle) {
+        return Ok("Not shown: notifications are off while incognito mode is on".to_string());
+    }
+    match dnd::route_notification(app_handle, title, body, agent_id, false) {
+        Route::Deliver => {
+            notifications::show_notification(app_handle, title, body).map_err(|e| e.to_string())?;
+            Ok("Notification shown".to_string())
+        }
+        Route::Held => Ok("Do Not Disturb is on; the notification is held until it ends".to_string()),
+        Route::Badged => Ok("Do Not Disturb is on; the notification is counted on the tray icon instead".to_string()),
+    }
+}
+
+async fn capture_screenshot(app_handle: &AppHandle) -> Result<(String, String), String> {
+    use tauri_plugin_screen_capture::{capture_config::FrameEncoding, screenshot};
+
+    if incognito::is_active(app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let shot = tauri::async_runtime::spawn_blocking(|| screenshot::capture(None, FrameEncoding::Jpeg, None))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let data = shot.data.ok_or_else(|| "Screenshot has no image data".to_string())?;
+    let text = format!("Screenshot of the primary monitor ({}x{}), attached below", shot.width, shot.height);
+    Ok((text, format!("data:image/jpeg;base64,{}", data)))
+}
+
+/// Commands and HTTP requests act for the user, so they stop whenever capture does
+fn check_active(app_handle: &AppHandle) -> Result<(), String> {
+    if incognito::is_active(app_handle) {
+        return Err("Not available while incognito mode is on".to_string());
+    }
+    if pause::is_paused() {
+        return Err("Not available while capture is paused".to_string());
+    }
+    Ok(())
+}
+
+async fn run_command(app_handle: &AppHandle, arguments: &serde_json::Value) -> Result<String, String> {
+    let command = string_arg(arguments, "command")?;
+    check_active(app_handle)?;
+    let timeout = Duration::from_secs(settings(app_handle).command_timeout_secs);
+
+    #[cfg(target_os = "windows")]
+    let mut process = {
+        let mut process = tokio::process::Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut process = {
+        let mut process = tokio::process::Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+    process.stdin(std::process::Stdio::null()).kill_on_drop(true);
+
+    let output = tokio::time::timeout(timeout, process.output())
+        .await
+        .map_err(|_| format!("Timed out after {} s", timeout.as_secs()))?
+        .map_err(|e| format!("Failed to run: {}", e))?;
+    let status = match output.status.code() {
+        Some(code) => format!("Exit status {}", code),
+        None => "Terminated by a signal".to_string(),
+    };
+    Ok(format!(
+        "{}\nstdout:\n{}\nstderr:\n{}",
+        status,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+async fn http_request(
+    app_handle: &AppHandle,
+    agent_id: Option<&str>,
+    arguments: &serde_json::Value,
+) -> Result<String, String> {
+    let url = string_arg(arguments, "url")?;
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err("Only http and https URLs are supported".to_string());
+    }
+    check_active(app_handle)?;
+    let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+    let host = parsed.host_str().unwrap_or_default().to_string();
+    let addresses = resolve_host(&parsed).await?;
+    if !settings(app_handle).allow_local_network && addresses.iter().any(|address| is_local(address.ip())) {
+        return Err("Requests to this computer or its local network are not allowed".to_string());
+    }
+    let method = arguments["method"].as_str().unwrap_or("GET").to_uppercase();
+    let method = reqwest::Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+    let headers: BTreeMap<String, String> = serde_json::from_value(arguments["headers"].clone()).unwrap_or_default();
+    let body = arguments["body"].as_str().unwrap_or_default().to_string();
+
+    let audit_id = audit::record_outbound(
+        app_handle,
+        &audit::OutboundRecord {
+            kind: audit::OutboundKind::Webhook,
+            destination: url.to_string(),
+            method: method.to_string(),
+            model: None,
+            agent_id: agent_id.map(String::from),
+            request_bytes: body.len() as u64,
+            image_hashes: Vec::new(),
+            prompt: None,
+            redactions: redaction::Redactions::new(),
+        },
+    );
+
+    // Connect to the addresses just checked, not whatever the name resolves to next, and
+    // only follow redirects that stay on the same host
+    let redirect_host = host.clone();
+    let client = reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .resolve_to_addrs(&host, &addresses)
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() < 10 && attempt.url().host_str() == Some(redirect_host.as_str()) {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.request(method, url);
+    for (name, value) in &headers {
+        request = request.header(name, value);
+    }
+    let result = async {
+        let response = request.body(body).send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        Ok::<_, reqwest::Error>((status, text))
+    }
+    .await;
+    if let Some(id) = audit_id {
+        let (status, bytes) = match &result {
+            Ok((status, text)) => (Some(status.as_u16()), text.len() as u64),
+            Err(_) => (None, 0),
+        };
+        audit::record_response(app_handle, id, status, bytes);
+    }
+    let (status, text) = result.map_err(|e| e.to_string())?;
+    Ok(format!("HTTP {}\n{}", status, text))
+}
+
+async fn resolve_host(url: &Url) -> Result<Vec<SocketAddr>, String> {
+    let port = url.port_or_known_default().unwrap_or(80);
+    let host = url.host_str().ok_or_else(|| "URL has no host".to_string())?;
+    let addresses: Vec<SocketAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+            .collect(),
+    };
+    match addresses.is_empty() {
+        true => Err("Host has no addresses".to_string()),
+        false => Ok(addresses),
+    }
+}
+
+/// Loopback, private, link-local (cloud metadata) and shared (tailnet) addresses
+fn is_local(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_local(ip.into()),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback() || ip.is_unspecified() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+            }
+        },
+    }
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn list_tools() -> Result<Vec<ToolInfo>, String> {
+    Ok(ALL_TOOLS
+        .into_iter()
+        .map(|tool| ToolInfo { name: tool.name(), description: tool.description(), parameters: tool.parameters() })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_tool_settings(shortcut_state: State<'_, UnifiedShortcutState>) -> Result<ToolSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().tools.clone())
+}
+
+#[tauri::command]
+pub async fn set_tool_settings(
+    settings: ToolSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting tool settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.tools = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn offers_tools_in_the_function_calling_form() {
+        let definitions = definitions(&[Tool::Notify, Tool::RunCommand]);
+        assert_eq!(definitions[0]["type"], "function");
+        assert_eq!(definitions[1]["function"]["name"], "run_command");
+        assert_eq!(definitions[1]["function"]["parameters"]["required"], serde_json::json!(["command"]));
+        assert!(ALL_TOOLS.into_iter().all(|tool| Tool::from_name(tool.name()) == Some(tool)));
+    }
+
+    #[test]
+    fn reads_grants_per_agent() {
+        let settings: ToolSettings = serde_json::from_str(r#"{"agents":{"a":["http_request"]}}"#).unwrap();
+        assert_eq!(settings.agents["a"], vec![Tool::HttpRequest]);
+        assert_eq!(settings.max_rounds, 8);
+    }
+
+    #[test]
+    fn grants_only_listed_tools_to_app_agents() {
+        let settings: ToolSettings = serde_json::from_str(r#"{"agents":{"a":["http_request"]}}"#).unwrap();
+        let grants = |caller, agent_id: Option<&str>, requested: &[&str]| {
+            check_grants(&settings, caller, agent_id, &names(requested))
+        };
+        assert_eq!(grants(Caller::App, Some("a"), &["http_request", "http_request"]), Ok(vec![Tool::HttpRequest]));
+        assert_eq!(grants(Caller::External, Some("a"), &[]), Ok(vec![]));
+        assert!(grants(Caller::External, Some("a"), &["http_request"]).is_err());
+        assert!(grants(Caller::App, Some("b"), &["http_request"]).is_err());
+        assert!(grants(Caller::App, None, &["http_request"]).is_err());
+        assert!(grants(Caller::App, Some("a"), &["run_command"]).is_err());
+        assert!(grants(Caller::App, Some("a"), &["format_disk"]).is_err());
+    }
+
+    #[test]
+    fn treats_private_and_special_addresses_as_local() {
+        for local in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.0.10",
+            "169.254.169.254",
+            "0.0.0.0",
+            "255.255.255.255",
+            "100.64.0.1",
+            "100.127.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "::ffff:169.254.169.254",
+            "::ffff:100.64.0.1",
+            "::ffff:0.0.0.0",
+        ] {
+            assert!(is_local(local.parse().unwrap()), "{}", local);
+        }
+    }
+
+    #[test]
+    fn treats_public_addresses_as_remote() {
+        for remote in [
+            "1.1.1.1",
+            "8.8.8.8",
+            "100.63.255.255",
+            "100.128.0.1",
+            "172.32.0.1",
+            "2606:4700::1111",
+            "::ffff:1.1.1.1",
+        ] {
+            assert!(!is_local(remote.parse().unwrap()), "{}", remote);
+        }
+    }
+
+    #[test]
+    fn truncates_long_output_on_a_char_boundary() {
+        let short = "é".repeat(10);
+        assert_eq!(truncate(short.clone()), short);
+        let long = truncate("é".repeat(MAX_OUTPUT_BYTES));
+        assert!(long.ends_with("\n[output truncated]"));
+        assert!(long.len() < MAX_OUTPUT_BYTES + 32);
+    }
+}
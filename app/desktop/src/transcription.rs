@@ -0,0 +1,85 @@
+// In src-tauri/src/transcription.rs
+//
+// Local speech-to-text. Whisper models are ggml files kept in
+// `<app_data_dir>/whisper-models`; transcription itself lives in the audio-capture
+// plugin behind the `whisper` feature.
+
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+pub fn models_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("whisper-models"))
+}
+
+/// A bare file name refers to the models directory; anything else is used as given
+#[cfg(feature = "whisper")]
+fn resolve_model(app_handle: &AppHandle, model: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(model);
+    if path.components().count() == 1 {
+        Ok(models_dir(app_handle)?.join(path))
+    } else {
+        Ok(path)
+    }
+}
+
+// Tauri commands
+
+/// Models present in the models directory
+#[tauri::command]
+pub async fn transcription_list_models(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let dir = models_dir(&app_handle)?;
+    let mut models: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+                .filter(|name| name.ends_with(".bin"))
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort();
+    Ok(models)
+}
+
+/// Transcribe the microphone (or system audio) into `on_segment`. `modelPath` may be a
+/// file name from `transcription_list_models`. Phrases in `config` also emit
+/// `audio-capture://phrase-detected`.
+#[cfg(feature = "whisper")]
+#[tauri::command]
+pub async fn transcription_start(
+    mut config: tauri_plugin_audio_capture::transcribe::TranscriptionConfig,
+    on_segment: tauri::ipc::Channel<tauri_plugin_audio_capture::transcribe::TranscriptSegment>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if crate::incognito::is_active(&app_handle) {
+        return Err("Capture is disabled while incognito mode is on".to_string());
+    }
+    let model = resolve_model(&app_handle, &config.model_path)?;
+    if !model.is_file() {
+        return Err(format!("Whisper model not found: {}", model.display()));
+    }
+    config.model_path = model.to_string_lossy().into_owned();
+    tauri::async_runtime::spawn_blocking(move || {
+        tauri_plugin_audio_capture::transcribe::start(config, on_segment).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(not(feature = "whisper"))]
+#[tauri::command]
+pub async fn transcription_start(_config: serde_json::Value) -> Result<(), String> {
+    Err("Transcription support is not included in this build".to_string())
+}
+
+#[tauri::command]
+pub async fn transcription_stop() -> Result<bool, String> {
+    #[cfg(feature = "whisper")]
+    return Ok(tauri_plugin_audio_capture::transcribe::stop());
+    #[cfg(not(feature = "whisper"))]
+    Ok(false)
+}
@@ -0,0 +1,293 @@
+// In src-tauri/src/usage.rs
+//
+// Local screen-time statistics. A background thread samples the focused window and
+// accumulates seconds per app per day in usage.sqlite; the query commands below back the
+// usage dashboard (top apps, daily trends, per-category totals). Nothing is sampled
+// while incognito mode is on.
+
+use crate::shortcuts::{self, UnifiedShortcutState};
+use crate::storage;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_screen_capture::targets;
+
+/// How often the focused window is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Gaps longer than this (sleep, suspended process) are not counted as usage
+const MAX_SAMPLE_GAP: Duration = Duration::from_secs(30);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS app_usage (
+    day TEXT NOT NULL,
+    app TEXT NOT NULL,
+    seconds INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, app)
+);
+CREATE TABLE IF NOT EXISTS app_categories (
+    app TEXT PRIMARY KEY,
+    category TEXT NOT NULL
+);
+";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSettings {
+    /// Record which app is focused. Off by default; nothing is sampled until enabled.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+pub struct UsageState {
+    db: Mutex<Option<Connection>>,
+}
+
+impl UsageState {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let db = match storage::open_database(app_handle, "usage", SCHEMA) {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                log::error!("Usage statistics disabled, database unavailable: {}", e);
+                None
+            }
+        };
+        Self { db: Mutex::new(db) }
+    }
+
+    fn with_db<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let guard = self.db.lock().unwrap();
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| "Usage database unavailable".to_string())?;
+        f(conn).map_err(|e| e.to_string())
+    }
+
+    /// Apps ranked by focused time between two days (YYYY-MM-DD, inclusive)
+    pub fn top_apps(&self, from_day: &str, to_day: &str, limit: u32) -> Result<Vec<AppUsage>, String> {
+        self.with_db(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT u.app, c.category, SUM(u.seconds) AS total
+                 FROM app_usage u LEFT JOIN app_categories c ON c.app = u.app
+                 WHERE u.day >= ?1 AND u.day <= ?2
+                 GROUP BY u.app ORDER BY total DESC LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![from_day, to_day, limit as i64], |row| {
+                Ok(AppUsage {
+                    app: row.get(0)?,
+                    category: row.get(1)?,
+                    seconds: row.get(2)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUsage {
+    pub app: String,
+    pub category: Option<String>,
+    pub seconds: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyUsage {
+    day: String,
+    seconds: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    category: String,
+    seconds: i64,
+}
+
+/// First day (YYYY-MM-DD, local time) of a window of `days` days ending today
+pub fn since_day(days: u32) -> String {
+    let days = days.max(1) as i64;
+    (chrono::Local::now().date_naive() - chrono::Duration::days(days - 1))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+pub fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Spawn the focused-window sampler
+pub fn start_usage_tracker(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_sample = Instant::now();
+        loop {
+            std::thread::sleep(SAMPLE_INTERVAL);
+
+            let elapsed = last_sample.elapsed();
+            last_sample = Instant::now();
+
+            let enabled = app_handle
+                .state::<UnifiedShortcutState>()
+                .config
+                .lock()
+                .unwrap()
+                .usage
+                .enabled;
+            // Which apps are open is exactly what incognito hides
+            if !enabled || crate::incognito::is_active(&app_handle) || elapsed > MAX_SAMPLE_GAP {
+                continue;
+            }
+
+            let focused = match targets::list_windows() {
+                Ok(windows) => windows.into_iter().find(|w| w.is_focused),
+                Err(e) => {
+                    log::debug!("Usage sample failed to list windows: {}", e);
+                    continue;
+                }
+            };
+            let Some(window) = focused else {
+                continue;
+            };
+            if window.app_name.is_empty() {
+                continue;
+            }
+
+            let result = app_handle.state::<UsageState>().with_db(|conn| {
+                conn.execute(
+                    "INSERT INTO app_usage (day, app, seconds) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(day, app) DO UPDATE SET seconds = seconds + excluded.seconds",
+                    params![today(), window.app_name, elapsed.as_secs() as i64],
+                )
+            });
+            if let Err(e) = result {
+                log::warn!("Failed to record usage sample: {}", e);
+            }
+        }
+    });
+}
+
+// Tauri commands
+
+/// Apps ranked by focused time over the last `days` days (default 1, i.e. today)
+#[tauri::command]
+pub async fn usage_top_apps(
+    days: Option<u32>,
+    limit: Option<u32>,
+    usage_state: State<'_, UsageState>,
+) -> Result<Vec<AppUsage>, String> {
+    let since = since_day(days.unwrap_or(1));
+    usage_state.top_apps(&since, &today(), limit.unwrap_or(10))
+}
+
+/// Focused time per day over the last `days` days, optionally for a single app.
+/// Days with no recorded usage are omitted.
+#[tauri::command]
+pub async fn usage_daily_trend(
+    days: Option<u32>,
+    app: Option<String>,
+    usage_state: State<'_, UsageState>,
+) -> Result<Vec<DailyUsage>, String> {
+    let since = since_day(days.unwrap_or(7));
+    usage_state.with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT day, SUM(seconds) FROM app_usage
+             WHERE day >= ?1 AND (?2 IS NULL OR app = ?2)
+             GROUP BY day ORDER BY day",
+        )?;
+        let rows = stmt.query_map(params![since, app], |row| {
+            Ok(DailyUsage {
+                day: row.get(0)?,
+                seconds: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Focused time per category over the last `days` days. Untagged apps are grouped
+/// under "uncategorized".
+#[tauri::command]
+pub async fn usage_category_totals(
+    days: Option<u32>,
+    usage_state: State<'_, UsageState>,
+) -> Result<Vec<CategoryUsage>, String> {
+    let since = since_day(days.unwrap_or(1));
+    usage_state.with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT COALESCE(c.category, 'uncategorized') AS cat, SUM(u.seconds) AS total
+             FROM app_usage u LEFT JOIN app_categories c ON c.app = u.app
+             WHERE u.day >= ?1
+             GROUP BY cat ORDER BY total DESC",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(CategoryUsage {
+                category: row.get(0)?,
+                seconds: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Tag an app with a category, or clear its tag with `None`
+#[tauri::command]
+pub async fn usage_set_category(
+    app: String,
+    category: Option<String>,
+    usage_state: State<'_, UsageState>,
+) -> Result<(), String> {
+    usage_state.with_db(|conn| {
+        match category {
+            Some(category) => conn.execute(
+                "INSERT INTO app_categories (app, category) VALUES (?1, ?2)
+                 ON CONFLICT(app) DO UPDATE SET category = excluded.category",
+                params![app, category],
+            )?,
+            None => conn.execute("DELETE FROM app_categories WHERE app = ?1", params![app])?,
+        };
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn usage_get_category(
+    app: String,
+    usage_state: State<'_, UsageState>,
+) -> Result<Option<String>, String> {
+    usage_state.with_db(|conn| {
+        conn.query_row(
+            "SELECT category FROM app_categories WHERE app = ?1",
+            params![app],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Delete all recorded usage (categories are kept)
+#[tauri::command]
+pub async fn usage_clear(usage_state: State<'_, UsageState>) -> Result<(), String> {
+    log::info!("Clearing recorded app usage");
+    usage_state.with_db(|conn| conn.execute("DELETE FROM app_usage", []).map(|_| ()))
+}
+
+#[tauri::command]
+pub async fn get_usage_settings(
+    shortcut_state: State<'_, UnifiedShortcutState>,
+) -> Result<UsageSettings, String> {
+    Ok(shortcut_state.config.lock().unwrap().usage.clone())
+}
+
+#[tauri::command]
+pub async fn set_usage_settings(
+    settings: UsageSettings,
+    shortcut_state: State<'_, UnifiedShortcutState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("Setting usage settings: {:?}", settings);
+    shortcuts::update_config(&app_handle, &shortcut_state, |config| config.usage = settings)
+}
@@ -0,0 +1,354 @@
+// In src-tauri/src/vector_store.rs
+//
+// A small on-disk vector store so agents can remember past observations and find them
+// again by meaning rather than by exact wording. Texts are embedded (`embeddings`) and
+// kept with their vector and optional JSON metadata in vectors.sqlite, grouped into named
+// collections. Each collection gets an in-memory HNSW graph (`hnsw`), built from the
+// database the first time it is searched and kept up to date as texts are added.
+//
+// A collection belongs to the embedding model that filled it: vectors from different
+// models can't be compared, so storing into or searching a collection with another model
+// configured is refused until the collection is deleted.
+
+use crate::embeddings;
+use crate::hnsw::{self, Hnsw};
+use crate::storage;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS vectors (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    collection TEXT NOT NULL,
+    text TEXT NOT NULL,
+    metadata TEXT,
+    model TEXT NOT NULL,
+    embedding BLOB NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS vectors_collection ON vectors(collection);
+";
+
+const DEFAULT_LIMIT: usize = 5;
+/// Graph candidates per search, at least; more than `limit` for better recall
+const MIN_SEARCH_EF: usize = 64;
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub id: i64,
+    pub text: String,
+    pub metadata: Option<serde_json::Value>,
+    /// Cosine similarity to the query, 1 for the same direction
+    pub score: f32,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionInfo {
+    pub name: String,
+    pub model: String,
+    pub count: u64,
+}
+
+struct Index {
+    model: String,
+    dims: usize,
+    graph: Hnsw,
+}
+
+impl Index {
+    fn check(&self, collection: &str, model: &str, dims: usize) -> Result<(), String> {
+        if self.model != model {
+            return Err(format!(
+                "Collection {} was embedded with {}, not {}; delete it to switch models",
+                collection, self.model, model
+            ));
+        }
+        if self.dims != dims {
+            return Err(format!(
+                "Collection {} holds {}-dimensional vectors, {} returned {}",
+                collection, self.dims, model, dims
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct VectorStore {
+    db: Mutex<Option<Connection>>,
+    /// Graphs of the collections used so far. Lock before `db` when holding both.
+    indexes: Mutex<HashMap<String, Index>>,
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+impl VectorStore {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let db = match storage::open_database(app_handle, "vectors", SCHEMA) {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                log::error!("Vector store disabled, database unavailable: {}", e);
+                None
+            }
+        };
+        Self {
+            db: Mutex::new(db),
+            indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn with_db<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let guard = self.db.lock().unwrap();
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| "Vector store database unavailable".to_string())?;
+        f(conn).map_err(|e| e.to_string())
+    }
+
+    /// Load `collection` into `indexes` unless it is there already or empty
+    fn load(&self, indexes: &mut HashMap<String, Index>, collection: &str) -> Result<(), String> {
+        if indexes.contains_key(collection) {
+            return Ok(());
+        }
+        let rows = self.with_db(|conn| {
+            let mut stmt = conn.prepare("SELECT id, model, embedding FROM vectors WHERE collection = ?1 ORDER BY id")?;
+            let rows = stmt.query_map(params![collection], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Vec<u8>>(2)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+        })?;
+        let Some((_, model, first)) = rows.first() else {
+            return Ok(());
+        };
+        let mut index = Index {
+            model: model.clone(),
+            dims: first.len() / 4,
+            graph: Hnsw::new(hnsw::DEFAULT_M, hnsw::DEFAULT_EF_CONSTRUCTION),
+        };
+        for (id, _, blob) in rows {
+            index.graph.insert(id, from_blob(&blob));
+        }
+        log::debug!("Loaded vector collection {} ({})", collection, index.model);
+        indexes.insert(collection.to_string(), index);
+        Ok(())
+    }
+
+    /// Keep already normalized `vectors` for `texts`; returns the new row ids
+    fn insert(
+        &self,
+        collection: &str,
+        model: &str,
+        texts: &[String],
+        metadata: &[Option<serde_json::Value>],
+        vectors: Vec<Vec<f32>>,
+    ) -> Result<Vec<i64>, String> {
+        let dims = vectors.first().map_or(0, Vec::len);
+        let mut indexes = self.indexes.lock().unwrap();
+        self.load(&mut indexes, collection)?;
+        if let Some(index) = indexes.get(collection) {
+            index.check(collection, model, dims)?;
+        }
+
+        let created_at = now_ms();
+        let ids = {
+            let mut guard = self.db.lock().unwrap();
+            let conn = guard
+                .as_mut()
+                .ok_or_else(|| "Vector store database unavailable".to_string())?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            let mut ids = Vec::with_capacity(texts.len());
+            for ((text, metadata), vector) in texts.iter().zip(metadata).zip(&vectors) {
+                tx.execute(
+                    "INSERT INTO vectors (collection, text, metadata, model, embedding, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        collection,
+                        text,
+                        metadata.as_ref().map(|m| m.to_string()),
+                        model,
+                        to_blob(vector),
+                        created_at
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                ids.push(tx.last_insert_rowid());
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+            ids
+        };
+
+        let index = indexes.entry(collection.to_string()).or_insert_with(|| Index {
+            model: model.to_string(),
+            dims,
+            graph: Hnsw::new(hnsw::DEFAULT_M, hnsw::DEFAULT_EF_CONSTRUCTION),
+        });
+        for (&id, vector) in ids.iter().zip(vectors) {
+            index.graph.insert(id, vector);
+        }
+        Ok(ids)
+    }
+
+    fn search(
+        &self,
+        collection: &str,
+        model: &str,
+        query: &[f32],
+        limit: usize,
+        min_score: Option<f32>,
+    ) -> Result<Vec<SearchHit>, String> {
+        let found = {
+            let mut indexes = self.indexes.lock().unwrap();
+            self.load(&mut indexes, collection)?;
+            let Some(index) = indexes.get(collection) else {
+                return Ok(Vec::new());
+            };
+            index.check(collection, model, query.len())?;
+            index.graph.search(query, limit, (limit * 4).max(MIN_SEARCH_EF))
+        };
+
+        let mut hits = Vec::with_capacity(found.len());
+        for (id, score) in found {
+            if min_score.is_some_and(|min| score < min) {
+                break;
+            }
+            let row = self.with_db(|conn| {
+                conn.query_row(
+                    "SELECT text, metadata, created_at FROM vectors WHERE id = ?1",
+                    params![id],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?)),
+                )
+            })?;
+            let (text, metadata, created_at) = row;
+            let metadata = metadata.and_then(|m| serde_json::from_str(&m).ok());
+            hits.push(SearchHit { id, text, metadata, score, created_at });
+        }
+        Ok(hits)
+    }
+}
+
+/// Embed `texts` and add them to `collection`, each with its metadata; returns the ids
+pub async fn embed_and_store(
+    app_handle: &AppHandle,
+    collection: &str,
+    texts: Vec<String>,
+    metadata: Vec<Option<serde_json::Value>>,
+    agent_id: Option<&str>,
+) -> Result<Vec<i64>, String> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let embedded = embeddings::embed(app_handle, agent_id, &texts).await?;
+    let vectors = embedded
+        .vectors
+        .into_iter()
+        .map(|vector| hnsw::normalize(vector).ok_or_else(|| format!("{} returned an empty vector", embedded.model)))
+        .collect::<Result<Vec<_>, _>>()?;
+    app_handle
+        .state::<VectorStore>()
+        .insert(collection, &embedded.model, &texts, &metadata, vectors)
+}
+
+/// The stored texts in `collection` most similar in meaning to `query`
+pub async fn similarity_search(
+    app_handle: &AppHandle,
+    collection: &str,
+    query: &str,
+    limit: usize,
+    min_score: Option<f32>,
+    agent_id: Option<&str>,
+) -> Result<Vec<SearchHit>, String> {
+    let embedded = embeddings::embed(app_handle, agent_id, &[query.to_string()]).await?;
+    let vector = embedded.vectors.into_iter().next().and_then(hnsw::normalize);
+    let Some(vector) = vector else {
+        return Err(format!("{} returned an empty vector", embedded.model));
+    };
+    app_handle
+        .state::<VectorStore>()
+        .search(collection, &embedded.model, &vector, limit, min_score)
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn embed_and_store_cmd(
+    collection: String,
+    text: String,
+    metadata: Option<serde_json::Value>,
+    agent_id: Option<String>,
+    app_handle: AppHandle,
+) -> Result<i64, String> {
+    let ids = embed_and_store(&app_handle, &collection, vec![text], vec![metadata], agent_id.as_deref()).await?;
+    ids.into_iter().next().ok_or_else(|| "Nothing was stored".to_string())
+}
+
+#[tauri::command]
+pub async fn similarity_search_cmd(
+    collection: String,
+    query: String,
+    limit: Option<usize>,
+    min_score: Option<f32>,
+    agent_id: Option<String>,
+    app_handle: AppHandle,
+) -> Result<Vec<SearchHit>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    similarity_search(&app_handle, &collection, &query, limit, min_score, agent_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn list_vector_collections(store: State<'_, VectorStore>) -> Result<Vec<CollectionInfo>, String> {
+    store.with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT collection, MAX(model), COUNT(*) FROM vectors GROUP BY collection ORDER BY collection",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(CollectionInfo {
+                name: row.get(0)?,
+                model: row.get(1)?,
+                count: row.get::<_, i64>(2)? as u64,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+#[tauri::command]
+pub async fn delete_vector_collection(collection: String, store: State<'_, VectorStore>) -> Result<u64, String> {
+    let mut indexes = store.indexes.lock().unwrap();
+    indexes.remove(&collection);
+    let deleted = store.with_db(|conn| conn.execute("DELETE FROM vectors WHERE collection = ?1", params![collection]))?;
+    log::info!("Deleted vector collection {} ({} entries)", collection, deleted);
+    Ok(deleted as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blobs_round_trip() {
+        let vector = vec![0.5, -1.25, f32::MIN_POSITIVE, 3.0e7];
+        let blob = to_blob(&vector);
+        assert_eq!(blob.len(), 16);
+        assert_eq!(from_blob(&blob), vector);
+    }
+}
@@ -0,0 +1,398 @@
+// In src-tauri/src/webrtc_stream.rs
+//
+// The capture as a WebRTC video stream, for remote viewers that want low latency instead
+// of polling base64 JPEGs. Signaling is a single HTTP exchange on the API server:
+//
+// - POST /webrtc/offer {sdp, audio?: "loopback" | "microphone", bitrateKbps?}: the
+//   viewer's SDP offer in, `{sessionId, type: "answer", sdp}` out, with ICE candidates
+//   already gathered (no trickle)
+// - DELETE /webrtc/{sessionId}: hang up
+//
+// Video is H.264, encoded per viewer from the frames the UI's capture session sends (see
+// the frame tap), so viewers see whatever target is selected. Each viewer's bitrate
+// follows its RTCP receiver reports: heavy loss cuts it, a clean link raises it again up
+// to `apiServer.webrtc.maxBitrateKbps`; picture-loss requests get a keyframe. With `audio`
+// set, what the system plays or the microphone goes along as Opus.
+//
+// `apiServer.webrtc.iceServers` (STUN/TURN URLs) is needed for viewers outside the LAN.
+// Offers are refused while incognito mode is on, and turning it on hangs up every viewer
+// (their audio doesn't come from the capture sessions incognito stops).
+
+use crate::shortcuts::UnifiedShortcutState;
+use crate::{incognito, AppState};
+use axum::extract::{Path, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_audio_capture::capture::{self as audio_capture, SourceKind};
+use tauri_plugin_audio_capture::pipeline::{AudioEncoding, AudioStreamConfig, Pipeline};
+use tauri_plugin_screen_capture::frames;
+use tauri_plugin_screen_capture::h264::H264Encoder;
+use tokio::runtime::Handle;
+use tokio::sync::{broadcast, watch};
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_H264, MIME_TYPE_OPUS};
+use webrtc::api::APIBuilder;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::interceptor::registry::Registry;
+use webrtc::media::Sample;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use webrtc::rtcp::receiver_report::ReceiverReport;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::rtp_transceiver::rtp_sender::RTCRtpSender;
+use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use webrtc::track::track_local::TrackLocal;
+
+const STREAM_ID: &str = "observer";
+const MIN_BITRATE_KBPS: u32 = 300;
+const START_BITRATE_KBPS: u32 = 1500;
+
+/// Receiver-reported loss (out of 256) above which the bitrate is cut, and below which
+/// it may grow
+const HIGH_LOSS: u8 = 26;
+const LOW_LOSS: u8 = 5;
+
+/// Audio goes out as 48 kHz Opus in 20 ms packets
+const AUDIO_RATE: u32 = 48000;
+const AUDIO_PACKET: Duration = Duration::from_millis(20);
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebRtcSettings {
+    /// STUN/TURN server URLs, e.g. `stun:stun.l.google.com:19302`
+    #[serde(default)]
+    pub ice_servers: Vec<String>,
+    #[serde(default = "default_max_bitrate_kbps")]
+    pub max_bitrate_kbps: u32,
+}
+
+fn default_max_bitrate_kbps() -> u32 {
+    4000
+}
+
+impl Default for WebRtcSettings {
+    fn default() -> Self {
+        Self { ice_servers: Vec::new(), max_bitrate_kbps: default_max_bitrate_kbps() }
+    }
+}
+
+/// Open viewer connections by session id
+#[derive(Default)]
+pub struct WebRtcState {
+    peers: Mutex<HashMap<String, Peer>>,
+}
+
+struct Peer {
+    connection: Arc<RTCPeerConnection>,
+    closed: watch::Sender<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferRequest {
+    pub sdp: String,
+    #[serde(default)]
+    pub audio: Option<SourceKind>,
+    #[serde(default)]
+    pub bitrate_kbps: Option<u32>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerResponse {
+    pub session_id: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub sdp: String,
+}
+
+/// What the RTCP reader tells the video sender
+struct Control {
+    bitrate_kbps: AtomicU32,
+    keyframe: AtomicBool,
+}
+
+/// Bitrate after a receiver report of `fraction_lost` (out of 256): a quarter off under
+/// heavy loss, a tenth more on a clean link
+fn adapt_bitrate(current: u32, fraction_lost: u8, max: u32) -> u32 {
+    let next = if fraction_lost > HIGH_LOSS {
+        current - current / 4
+    } else if fraction_lost < LOW_LOSS {
+        current + current / 10
+    } else {
+        current
+    };
+    next.clamp(MIN_BITRATE_KBPS.min(max), max)
+}
+
+/// Restarting the encoder costs a keyframe, so small changes wait until they add up
+fn needs_restart(encoder_kbps: u32, target_kbps: u32) -> bool {
+    encoder_kbps.abs_diff(target_kbps) * 5 >= encoder_kbps
+}
+
+fn settings(app_handle: &AppHandle) -> WebRtcSettings {
+    app_handle.state::<UnifiedShortcutState>().config.lock().unwrap().api_server.webrtc.clone()
+}
+
+/// Feed RTCP from the viewer into `control` until the connection goes away
+async fn read_rtcp(sender: Arc<RTCRtpSender>, control: Arc<Control>, max_kbps: u32) {
+    while let Ok((packets, _)) = sender.read_rtcp().await {
+        for packet in packets {
+            let packet = packet.as_any();
+            if let Some(report) = packet.downcast_ref::<ReceiverReport>() {
+                for reception in &report.reports {
+                    let current = control.bitrate_kbps.load(Ordering::Relaxed);
+                    let next = adapt_bitrate(current, reception.fraction_lost, max_kbps);
+                    control.bitrate_kbps.store(next, Ordering::Relaxed);
+                }
+            } else if packet.is::<PictureLossIndication>() || packet.is::<FullIntraRequest>() {
+                control.keyframe.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Thread: encode tapped frames and write them to `track` until the viewer leaves
+fn send_video(
+    runtime: Handle,
+    track: Arc<TrackLocalStaticSample>,
+    control: Arc<Control>,
+    mut closed: watch::Receiver<bool>,
+) {
+    let mut encoder = match H264Encoder::new(control.bitrate_kbps.load(Ordering::Relaxed)) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            log::error!("WebRTC video encoder failed to start: {}", e);
+            return;
+        }
+    };
+    let mut rx = frames::subscribe();
+    let mut last_timestamp: Option<f64> = None;
+    loop {
+        let frame = runtime.block_on(async {
+            tokio::select! {
+                frame = rx.recv() => Some(frame),
+                _ = closed.changed() => None,
+            }
+        });
+        let frame = match frame {
+            Some(Ok(frame)) => frame,
+            Some(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Some(Err(broadcast::error::RecvError::Closed)) | None => return,
+        };
+
+        let target = control.bitrate_kbps.load(Ordering::Relaxed);
+        if control.keyframe.swap(false, Ordering::Relaxed) || needs_restart(encoder.bitrate_kbps(), target) {
+            log::debug!("WebRTC video restarting at {} kbps", target);
+            if let Err(e) = encoder.restart(target) {
+                log::error!("WebRTC video encoder failed to restart: {}", e);
+                return;
+            }
+        }
+        let unit = match encoder.encode(&frame.data) {
+            Ok(Some(unit)) => unit,
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("WebRTC video encoding failed: {}", e);
+                return;
+            }
+        };
+        let fps = tauri_plugin_screen_capture::capture_config::target_fps().max(1);
+        let duration = match last_timestamp {
+            Some(last) if frame.timestamp > last => Duration::from_secs_f64(frame.timestamp - last),
+            _ => Duration::from_secs(1) / fps,
+        };
+        last_timestamp = Some(frame.timestamp);
+        let sample = Sample { data: unit.annex_b.into(), duration, ..Default::default() };
+        if let Err(e) = runtime.block_on(track.write_sample(&sample)) {
+            log::debug!("WebRTC video track closed: {}", e);
+            return;
+        }
+    }
+}
+
+/// Thread: encode `kind` audio to Opus and write it to `track` until the viewer leaves
+fn send_audio(runtime: Handle, track: Arc<TrackLocalStaticSample>, kind: SourceKind, closed: watch::Receiver<bool>) {
+    let config = AudioStreamConfig {
+        encoding: AudioEncoding::Opus,
+        sample_rate: AUDIO_RATE,
+        channels: if kind == SourceKind::Loopback { 2 } else { 1 },
+        chunk_ms: 100,
+        bitrate: None,
+    };
+    let opening = audio_capture::open_source(kind, None)
+        .and_then(|(source, _)| Pipeline::new(config, source.format()).map(|pipeline| (source, pipeline)));
+    let (mut source, mut pipeline) = match opening {
+        Ok(opening) => opening,
+        Err(e) => {
+            log::error!("WebRTC {:?} audio failed to start: {}", kind, e);
+            return;
+        }
+    };
+    let mut samples = Vec::new();
+    while !*closed.borrow() {
+        samples.clear();
+        let packets = source.read(&mut samples).and_then(|_| pipeline.push_packets(&samples));
+        let packets = match packets {
+            Ok(packets) => packets,
+            Err(e) => {
+                log::error!("WebRTC {:?} audio stopped: {}", kind, e);
+                return;
+            }
+        };
+        for packet in packets {
+            let sample = Sample { data: packet.into(), duration: AUDIO_PACKET, ..Default::default() };
+            if runtime.block_on(track.write_sample(&sample)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn hang_up(app_handle: &AppHandle, session_id: &str) -> bool {
+    let peer = app_handle.state::<WebRtcState>().peers.lock().unwrap().remove(session_id);
+    let Some(peer) = peer else {
+        return false;
+    };
+    log::info!("WebRTC viewer {} disconnected", session_id);
+    let _ = peer.closed.send(true);
+    tauri::async_runtime::spawn(async move {
+        let _ = peer.connection.close().await;
+    });
+    true
+}
+
+/// Disconnect every viewer
+pub fn hang_up_all(app_handle: &AppHandle) {
+    let sessions: Vec<String> = app_handle.state::<WebRtcState>().peers.lock().unwrap().keys().cloned().collect();
+    for session_id in sessions {
+        hang_up(app_handle, &session_id);
+    }
+}
+
+async fn connect(app_handle: &AppHandle, request: OfferRequest) -> Result<AnswerResponse, webrtc::Error> {
+    let settings = settings(app_handle);
+    let mut media = MediaEngine::default();
+    media.register_default_codecs()?;
+    let interceptors = register_default_interceptors(Registry::new(), &mut media)?;
+    let api = APIBuilder::new().with_media_engine(media).with_interceptor_registry(interceptors).build();
+    let mut config = RTCConfiguration::default();
+    if !settings.ice_servers.is_empty() {
+        config.ice_servers = vec![RTCIceServer { urls: settings.ice_servers.clone(), ..Default::default() }];
+    }
+    let connection = Arc::new(api.new_peer_connection(config).await?);
+
+    let video = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability { mime_type: MIME_TYPE_H264.to_string(), ..Default::default() },
+        "video".to_string(),
+        STREAM_ID.to_string(),
+    ));
+    let video_sender = connection.add_track(video.clone() as Arc<dyn TrackLocal + Send + Sync>).await?;
+    let audio = match request.audio {
+        Some(kind) => {
+            let track = Arc::new(TrackLocalStaticSample::new(
+                RTCRtpCodecCapability { mime_type: MIME_TYPE_OPUS.to_string(), ..Default::default() },
+                "audio".to_string(),
+                STREAM_ID.to_string(),
+            ));
+            let sender = connection.add_track(track.clone() as Arc<dyn TrackLocal + Send + Sync>).await?;
+            // RTCP has to be read for the interceptors to run
+            tokio::spawn(async move { while sender.read_rtcp().await.is_ok() {} });
+            Some((track, kind))
+        }
+        None => None,
+    };
+
+    connection.set_remote_description(RTCSessionDescription::offer(request.sdp)?).await?;
+    let answer = connection.create_answer(None).await?;
+    let mut gathered = connection.gathering_complete_promise().await;
+    connection.set_local_description(answer).await?;
+    let _ = gathered.recv().await;
+    let sdp = connection.local_description().await.map(|description| description.sdp).unwrap_or_default();
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (closed, closed_rx) = watch::channel(false);
+    let max_kbps = settings.max_bitrate_kbps.max(MIN_BITRATE_KBPS);
+    let start_kbps = request.bitrate_kbps.unwrap_or(START_BITRATE_KBPS).clamp(MIN_BITRATE_KBPS, max_kbps);
+    let control = Arc::new(Control { bitrate_kbps: AtomicU32::new(start_kbps), keyframe: AtomicBool::new(false) });
+    tokio::spawn(read_rtcp(video_sender, control.clone(), max_kbps));
+    // Encoding is CPU-bound, so it runs on threads that block on the runtime to write
+    let (runtime, video_closed) = (Handle::current(), closed_rx.clone());
+    std::thread::spawn(move || send_video(runtime, video, control, video_closed));
+    if let Some((track, kind)) = audio {
+        let runtime = Handle::current();
+        std::thread::spawn(move || send_audio(runtime, track, kind, closed_rx));
+    }
+
+    let handle = app_handle.clone();
+    let id = session_id.clone();
+    connection.on_peer_connection_state_change(Box::new(move |state: RTCPeerConnectionState| {
+        if matches!(
+            state,
+            RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed | RTCPeerConnectionState::Disconnected
+        ) {
+            hang_up(&handle, &id);
+        }
+        Box::pin(async {})
+    }));
+    let peer = Peer { connection, closed };
+    app_handle.state::<WebRtcState>().peers.lock().unwrap().insert(session_id.clone(), peer);
+    log::info!("WebRTC viewer {} connected at {} kbps, audio {:?}", session_id, start_kbps, request.audio);
+    Ok(AnswerResponse { session_id, kind: "answer", sdp })
+}
+
+fn error(status: StatusCode, message: impl ToString) -> Response {
+    (status, Json(serde_json::json!({ "error": message.to_string() }))).into_response()
+}
+
+/// `POST /webrtc/offer`
+pub async fn offer_handler(AxumState(state): AxumState<AppState>, Json(request): Json<OfferRequest>) -> Response {
+    if incognito::is_active(&state.app_handle) {
+        return error(StatusCode::FORBIDDEN, "Streaming is disabled while incognito mode is on");
+    }
+    match connect(&state.app_handle, request).await {
+        Ok(answer) => Json(answer).into_response(),
+        Err(e) => {
+            log::error!("WebRTC offer failed: {}", e);
+            error(StatusCode::BAD_REQUEST, e)
+        }
+    }
+}
+
+/// `DELETE /webrtc/{sessionId}`
+pub async fn hang_up_handler(AxumState(state): AxumState<AppState>, Path(session_id): Path<String>) -> StatusCode {
+    match hang_up(&state.app_handle, &session_id) {
+        true => StatusCode::NO_CONTENT,
+        false => StatusCode::NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitrate_follows_loss() {
+        // 20% loss cuts a quarter; 1% adds a tenth; in between holds
+        assert_eq!(adapt_bitrate(2000, 51, 4000), 1500);
+        assert_eq!(adapt_bitrate(2000, 2, 4000), 2200);
+        assert_eq!(adapt_bitrate(2000, 13, 4000), 2000);
+        assert_eq!(adapt_bitrate(3900, 0, 4000), 4000);
+        assert_eq!(adapt_bitrate(320, 255, 4000), MIN_BITRATE_KBPS);
+
+        assert!(!needs_restart(2000, 2200));
+        assert!(needs_restart(2000, 2420));
+        assert!(needs_restart(2000, 1500));
+    }
+}
@@ -0,0 +1,16 @@
+const COMMANDS: &[&str] = &[
+    "start_audio_capture_cmd",
+    "stop_audio_capture_cmd",
+    "get_audio_capture_status_cmd",
+    "get_audio_devices_cmd",
+    "start_microphone_stream_cmd",
+    "stop_microphone_stream_cmd",
+    "start_transcription_cmd",
+    "stop_transcription_cmd",
+    "start_vad_cmd",
+    "stop_vad_cmd",
+];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -0,0 +1,248 @@
+//! The running captures: at most one per source kind, each on its own thread.
+//!
+//! `start` opens the platform's loopback source, and `start_microphone` an input
+//! device (see `mic`), on the capture thread (WASAPI needs COM initialized on the
+//! thread that reads) and waits for it to open, so device and permission errors come
+//! back from the command instead of only landing in the log. System audio and the
+//! microphone run side by side; starting a kind that is already running replaces it.
+//! A capture ends when stopped, when the source fails, or when the frontend drops the
+//! channel.
+
+use crate::error::{Error, Result};
+use crate::mic::Microphone;
+use crate::pipeline::{AudioChunk, AudioStreamConfig, Pipeline, SourceFormat};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::ipc::Channel;
+
+#[cfg(target_os = "windows")]
+use crate::wasapi::Loopback;
+
+#[cfg(target_os = "linux")]
+use crate::pulse::Loopback;
+
+#[cfg(target_os = "macos")]
+use crate::tap::Loopback;
+
+/// How long `start` waits for the source to open
+pub const OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where a capture's samples come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceKind {
+    /// What the system plays
+    #[default]
+    Loopback,
+    /// An input device
+    Microphone,
+}
+
+/// A platform source the capture thread reads from
+pub trait AudioSource {
+    fn format(&self) -> SourceFormat;
+
+    /// Append the samples captured since the last read, interleaved. May append
+    /// nothing when there is nothing yet.
+    fn read(&mut self, samples: &mut Vec<f32>) -> Result<()>;
+}
+
+struct Capture {
+    config: AudioStreamConfig,
+    source: SourceFormat,
+    device: Option<String>,
+    stop: Arc<AtomicBool>,
+    chunks: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+static LOOPBACK: Mutex<Option<Capture>> = Mutex::new(None);
+static MICROPHONE: Mutex<Option<Capture>> = Mutex::new(None);
+
+fn slot(kind: SourceKind) -> &'static Mutex<Option<Capture>> {
+    match kind {
+        SourceKind::Loopback => &LOOPBACK,
+        SourceKind::Microphone => &MICROPHONE,
+    }
+}
+
+/// What `get_audio_capture_status_cmd` reports
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioCaptureStatus {
+    pub kind: SourceKind,
+    pub active: bool,
+    /// Name of the input device, for microphone captures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<AudioStreamConfig>,
+    /// Format the device delivers, before remixing and resampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceFormat>,
+    /// Chunks sent so far
+    pub chunks: u64,
+}
+
+/// Start capturing system output into `channel`, replacing a running loopback capture
+pub fn start(config: AudioStreamConfig, channel: Channel<AudioChunk>) -> Result<AudioCaptureStatus> {
+    spawn(SourceKind::Loopback, None, config, channel)
+}
+
+/// Start recording `device_id` (see `mic::list_devices`), or the default input device,
+/// into `channel`, replacing a running microphone capture
+pub fn start_microphone(
+    device_id: Option<String>,
+    config: AudioStreamConfig,
+    channel: Channel<AudioChunk>,
+) -> Result<AudioCaptureStatus> {
+    spawn(SourceKind::Microphone, device_id, config, channel)
+}
+
+/// Open a source of `kind` on the calling thread, with the input device's name, for
+/// consumers that read it themselves (transcription, recordings). Sources stay on the
+/// thread that opened them.
+pub fn open_source(
+    kind: SourceKind,
+    device_id: Option<&str>,
+) -> Result<(Box<dyn AudioSource>, Option<String>)> {
+    match kind {
+        #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+        SourceKind::Loopback => Ok((Box::new(Loopback::open()?), None)),
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        SourceKind::Loopback => Err(Error::NotSupported),
+        SourceKind::Microphone => {
+            let source = Microphone::open(device_id)?;
+            let name = source.name().to_string();
+            Ok((Box::new(source), Some(name)))
+        }
+    }
+}
+
+/// Open a source of `kind` on a new capture thread and run it into `channel`
+fn spawn(
+    kind: SourceKind,
+    device_id: Option<String>,
+    config: AudioStreamConfig,
+    channel: Channel<AudioChunk>,
+) -> Result<AudioCaptureStatus> {
+    let config = config.validated()?;
+    stop(kind);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let chunks = Arc::new(AtomicU64::new(0));
+    let (opened, on_open) = mpsc::sync_channel::<Result<(SourceFormat, Option<String>)>>(1);
+    let thread = {
+        let stop = stop.clone();
+        let chunks = chunks.clone();
+        std::thread::spawn(move || {
+            let opening = open_source(kind, device_id.as_deref()).and_then(|(source, device)| {
+                Pipeline::new(config, source.format()).map(|pipeline| (source, device, pipeline))
+            });
+            let (mut source, mut pipeline) = match opening {
+                Ok((source, device, pipeline)) => {
+                    let _ = opened.send(Ok((source.format(), device)));
+                    (source, pipeline)
+                }
+                Err(e) => {
+                    let _ = opened.send(Err(e));
+                    return;
+                }
+            };
+            run(source.as_mut(), &mut pipeline, &channel, &stop, &chunks);
+            log::info!("[AudioCapture] {:?} capture stopped after {} chunks", kind, chunks.load(Ordering::Relaxed));
+        })
+    };
+
+    let (source, device) = match on_open.recv_timeout(OPEN_TIMEOUT) {
+        Ok(Ok(opened)) => opened,
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            stop.store(true, Ordering::SeqCst);
+            return Err(Error::Device("Timed out opening the audio device".to_string()));
+        }
+    };
+    *slot(kind).lock() = Some(Capture { config, source, device, stop, chunks, thread: Some(thread) });
+    Ok(status(kind))
+}
+
+fn run(
+    source: &mut dyn AudioSource,
+    pipeline: &mut Pipeline,
+    channel: &Channel<AudioChunk>,
+    stop: &AtomicBool,
+    chunks: &AtomicU64,
+) {
+    let mut samples = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        samples.clear();
+        if let Err(e) = source.read(&mut samples) {
+            log::error!("[AudioCapture] {}", e);
+            return;
+        }
+        if samples.is_empty() {
+            continue;
+        }
+        let completed = match pipeline.push(&samples) {
+            Ok(completed) => completed,
+            Err(e) => {
+                log::error!("[AudioCapture] {}", e);
+                return;
+            }
+        };
+        for chunk in completed {
+            if let Err(e) = channel.send(chunk) {
+                log::warn!("[AudioCapture] Channel closed, stopping: {}", e);
+                return;
+            }
+            if chunks.fetch_add(1, Ordering::Relaxed) == 0 {
+                log::info!("[AudioCapture] First chunk sent");
+            }
+        }
+    }
+}
+
+/// Stop the running capture of `kind`; false if none was running
+pub fn stop(kind: SourceKind) -> bool {
+    // Take it out first so the join in Drop doesn't hold the lock
+    let capture = slot(kind).lock().take();
+    capture.is_some()
+}
+
+/// Stop system audio and microphone capture, the VAD and transcription
+pub fn stop_all() {
+    stop(SourceKind::Loopback);
+    stop(SourceKind::Microphone);
+    crate::vad::stop();
+    #[cfg(feature = "whisper")]
+    crate::transcribe::stop();
+}
+
+pub fn status(kind: SourceKind) -> AudioCaptureStatus {
+    let capture = slot(kind).lock();
+    match capture.as_ref() {
+        // A capture whose thread ended (source error, closed channel) isn't active
+        Some(capture) if capture.thread.as_ref().is_some_and(|thread| !thread.is_finished()) => AudioCaptureStatus {
+            kind,
+            active: true,
+            device: capture.device.clone(),
+            config: Some(capture.config),
+            source: Some(capture.source),
+            chunks: capture.chunks.load(Ordering::Relaxed),
+        },
+        _ => AudioCaptureStatus { kind, active: false, device: None, config: None, source: None, chunks: 0 },
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("System audio capture is not supported on this platform")]
+    NotSupported,
+
+    #[error("Invalid audio config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Audio device error: {0}")]
+    Device(String),
+
+    #[error("Audio encoding error: {0}")]
+    Encode(String),
+
+    #[error("Transcription error: {0}")]
+    Transcription(String),
+
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,37 @@
+//! Events the plugin emits to the frontend.
+//!
+//! Transcription and the VAD run on their own threads without an `AppHandle`, so `init` stores an
+//! emitter at plugin setup and `emit` is a no-op until then (e.g. in tests).
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// A transcript segment contained one of the transcription's phrases
+/// (`transcribe::PhraseDetected` payload)
+pub const PHRASE_DETECTED: &str = "audio-capture://phrase-detected";
+
+/// Speech started or ended on a VAD's source (`vad::SpeechEvent` payload)
+pub const SPEECH: &str = "audio-capture://speech";
+
+type EmitFn = Box<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+static EMITTER: OnceLock<EmitFn> = OnceLock::new();
+
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    let _ = EMITTER.set(Box::new(move |event, payload| {
+        if let Err(e) = app.emit(event, payload) {
+            log::warn!("[AudioCapture] Failed to emit {}: {}", event, e);
+        }
+    }));
+}
+
+pub fn emit(event: &str, payload: impl Serialize) {
+    if let Some(emitter) = EMITTER.get() {
+        match serde_json::to_value(payload) {
+            Ok(payload) => emitter(event, payload),
+            Err(e) => log::warn!("[AudioCapture] Failed to serialize {}: {}", event, e),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! System audio loopback capture.
+//!
+//! Captures what the computer plays — meetings, videos, notifications — and streams it
+//! to the frontend over a channel as PCM or Opus chunks, so agents can listen as well
+//! as watch. Each platform taps its output mix natively:
+//!
+//! - Windows: WASAPI loopback of the default render device (`wasapi`)
+//! - macOS 14.2+: a Core Audio process tap on every process (`tap`)
+//! - Linux: the default sink's monitor via PulseAudio or pipewire-pulse (`pulse`)
+//!
+//! Microphones are captured through cpal (`mic`), alongside the loopback and with the
+//! same pipeline and chunk format. The commands mirror the screen-capture plugin's:
+//! list what can be captured, start a stream into a channel, stop it.
+//!
+//! With the `whisper` feature, either source can also be transcribed locally
+//! (`transcribe`), with phrase triggers for agents. A voice activity detector (`vad`)
+//! emits speech started/ended events so agents can react only while someone talks.
+//!
+//! This is separate from the screen-capture plugin's audio, which only runs alongside
+//! a screen stream on some platforms; here audio runs on its own, in the format the
+//! consumer asks for (see `pipeline`).
+
+use tauri::{
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    Runtime,
+};
+
+mod error;
+
+// Remixing, resampling, chunking and PCM/Opus encoding
+pub mod pipeline;
+
+// The running captures and their threads
+pub mod capture;
+
+// Input devices through cpal
+pub mod mic;
+
+// Events emitted to the frontend
+mod events;
+
+// Local speech-to-text with whisper.cpp
+#[cfg(feature = "whisper")]
+pub mod transcribe;
+
+// Voice activity detection with speech started/ended events
+pub mod vad;
+
+// WASAPI loopback (Windows)
+#[cfg(target_os = "windows")]
+mod wasapi;
+
+// Default sink monitor through PulseAudio / pipewire-pulse (Linux)
+#[cfg(target_os = "linux")]
+mod pulse;
+
+// Core Audio process tap (macOS 14.2+)
+#[cfg(target_os = "macos")]
+mod tap;
+
+pub use error::{Error, Result};
+
+/// Initializes the audio capture plugin
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    PluginBuilder::new("audio-capture")
+        .invoke_handler(tauri::generate_handler![
+            start_audio_capture_cmd,
+            stop_audio_capture_cmd,
+            get_audio_capture_status_cmd,
+            get_audio_devices_cmd,
+            start_microphone_stream_cmd,
+            stop_microphone_stream_cmd,
+            start_vad_cmd,
+            stop_vad_cmd,
+            #[cfg(feature = "whisper")]
+            start_transcription_cmd,
+            #[cfg(feature = "whisper")]
+            stop_transcription_cmd
+        ])
+        .setup(|app, _api| {
+            events::init(app);
+            Ok(())
+        })
+        .build()
+}
+
+/// Start capturing system audio into `on_chunk`, replacing a running capture. Returns
+/// once the output device is open.
+#[tauri::command]
+async fn start_audio_capture_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    config: Option<pipeline::AudioStreamConfig>,
+    on_chunk: tauri::ipc::Channel<pipeline::AudioChunk>,
+) -> Result<capture::AudioCaptureStatus> {
+    tauri::async_runtime::spawn_blocking(move || capture::start(config.unwrap_or_default(), on_chunk))
+        .await
+        .map_err(|e| Error::Device(e.to_string()))?
+}
+
+/// Stop system audio capture; false if none was running
+#[tauri::command]
+async fn stop_audio_capture_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(capture::stop(capture::SourceKind::Loopback))
+}
+
+/// Status of the system audio capture, or of the microphone with `kind: "microphone"`
+#[tauri::command]
+fn get_audio_capture_status_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    kind: Option<capture::SourceKind>,
+) -> Result<capture::AudioCaptureStatus> {
+    Ok(capture::status(kind.unwrap_or_default()))
+}
+
+/// List input devices to record from
+#[tauri::command]
+async fn get_audio_devices_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Vec<mic::AudioDevice>> {
+    tauri::async_runtime::spawn_blocking(mic::list_devices).await.map_err(|e| Error::Device(e.to_string()))?
+}
+
+/// Start recording `device_id` (or the default input device) into `on_chunk`, replacing
+/// a running microphone stream. Returns once the device is open.
+#[tauri::command]
+async fn start_microphone_stream_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    device_id: Option<String>,
+    config: Option<pipeline::AudioStreamConfig>,
+    on_chunk: tauri::ipc::Channel<pipeline::AudioChunk>,
+) -> Result<capture::AudioCaptureStatus> {
+    tauri::async_runtime::spawn_blocking(move || {
+        capture::start_microphone(device_id, config.unwrap_or_default(), on_chunk)
+    })
+    .await
+    .map_err(|e| Error::Device(e.to_string()))?
+}
+
+/// Stop the microphone stream; false if none was running
+#[tauri::command]
+async fn stop_microphone_stream_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(capture::stop(capture::SourceKind::Microphone))
+}
+
+/// Detect speech on the microphone (or system audio), emitting `audio-capture://speech`
+/// events; replaces a running VAD. Returns once the source is open.
+#[tauri::command]
+async fn start_vad_cmd<R: Runtime>(_app: tauri::AppHandle<R>, config: Option<vad::VadConfig>) -> Result<()> {
+    tauri::async_runtime::spawn_blocking(move || vad::start(config.unwrap_or_default(), None))
+        .await
+        .map_err(|e| Error::Device(e.to_string()))?
+}
+
+/// Stop the VAD; false if none was running
+#[tauri::command]
+async fn stop_vad_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(vad::stop())
+}
+
+/// Transcribe the microphone (or system audio) locally, sending timestamped segments to
+/// `on_segment`; replaces a running transcription. Returns once the model is loaded and
+/// the source is open.
+#[cfg(feature = "whisper")]
+#[tauri::command]
+async fn start_transcription_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    config: transcribe::TranscriptionConfig,
+    on_segment: tauri::ipc::Channel<transcribe::TranscriptSegment>,
+) -> Result<()> {
+    tauri::async_runtime::spawn_blocking(move || transcribe::start(config, on_segment))
+        .await
+        .map_err(|e| Error::Transcription(e.to_string()))?
+}
+
+/// Stop transcribing; false if no transcription was running
+#[cfg(feature = "whisper")]
+#[tauri::command]
+async fn stop_transcription_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(transcribe::stop())
+}
@@ -0,0 +1,150 @@
+//! Microphones and other input devices, through cpal.
+//!
+//! Device ids are cpal's "host:device" ids, which stay stable across restarts and
+//! device reordering, so the frontend can save a choice. The input callback runs on the
+//! driver's thread and only queues samples; `read` drains the queue on the capture
+//! thread, as with the macOS tap. Unplugging the device ends the capture.
+
+use crate::capture::AudioSource;
+use crate::error::{Error, Result};
+use crate::pipeline::SourceFormat;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{DeviceId, FromSample, SampleFormat, SizedSample, Stream, StreamConfig, StreamError};
+use serde::Serialize;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::time::Duration;
+
+/// Buffers queued between the input callback and the reader
+const QUEUE: usize = 100;
+
+/// How long a read waits for the next buffer
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Samples from the input callback, or the error that ended the stream
+type Buffer = std::result::Result<Vec<f32>, String>;
+
+/// An input device, as `get_audio_devices_cmd` lists it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioDevice {
+    /// Pass to `start_microphone_stream_cmd`
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    /// The device's own rate and channels, before the pipeline converts them
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Input devices on the default host; ones that can't report an input format are left out
+pub fn list_devices() -> Result<Vec<AudioDevice>> {
+    let host = cpal::default_host();
+    let default_id = host.default_input_device().and_then(|device| device.id().ok());
+    let devices = host.input_devices().map_err(|e| Error::Device(format!("Failed to list input devices: {}", e)))?;
+    Ok(devices
+        .filter_map(|device| {
+            let id = device.id().ok()?;
+            let config = device.default_input_config().ok()?;
+            let name = device.description().map(|d| d.name().to_string()).unwrap_or_else(|_| id.to_string());
+            Some(AudioDevice {
+                is_default: default_id.as_ref() == Some(&id),
+                id: id.to_string(),
+                name,
+                sample_rate: config.sample_rate(),
+                channels: config.channels(),
+            })
+        })
+        .collect())
+}
+
+pub struct Microphone {
+    // Recording stops when the stream is dropped
+    _stream: Stream,
+    receiver: Receiver<Buffer>,
+    format: SourceFormat,
+    name: String,
+}
+
+impl Microphone {
+    /// Open `device_id`, or the default input device, in its default format
+    pub fn open(device_id: Option<&str>) -> Result<Self> {
+        let host = cpal::default_host();
+        let device = match device_id {
+            Some(id) => {
+                let parsed: DeviceId =
+                    id.parse().map_err(|e| Error::InvalidConfig(format!("Bad device id {}: {}", id, e)))?;
+                host.device_by_id(&parsed).ok_or_else(|| Error::Device(format!("No input device {}", id)))?
+            }
+            None => host.default_input_device().ok_or_else(|| Error::Device("No default input device".to_string()))?,
+        };
+        let name = device.description().map(|d| d.name().to_string()).unwrap_or_else(|_| "Microphone".to_string());
+        let supported = device
+            .default_input_config()
+            .map_err(|e| Error::Device(format!("Failed to read the input format of {}: {}", name, e)))?;
+        let config = supported.config();
+
+        let (sender, receiver) = sync_channel(QUEUE);
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build::<f32>(&device, &config, sender),
+            SampleFormat::I16 => build::<i16>(&device, &config, sender),
+            SampleFormat::U16 => build::<u16>(&device, &config, sender),
+            SampleFormat::I32 => build::<i32>(&device, &config, sender),
+            other => return Err(Error::Device(format!("Unsupported input sample format: {}", other))),
+        }?;
+        stream.play().map_err(|e| Error::Device(format!("Failed to start {}: {}", name, e)))?;
+
+        let format = SourceFormat { sample_rate: config.sample_rate, channels: config.channels };
+        log::info!("[AudioCapture] Microphone {}: {} Hz, {} channels", name, format.sample_rate, format.channels);
+        Ok(Self { _stream: stream, receiver, format, name })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl AudioSource for Microphone {
+    fn format(&self) -> SourceFormat {
+        self.format
+    }
+
+    /// Append the buffers recorded since the last read
+    fn read(&mut self, samples: &mut Vec<f32>) -> Result<()> {
+        let mut next = match self.receiver.recv_timeout(READ_TIMEOUT) {
+            Ok(buffer) => Some(buffer),
+            Err(RecvTimeoutError::Timeout) => return Ok(()),
+            Err(RecvTimeoutError::Disconnected) => return Err(Error::Device("Input stream stopped".to_string())),
+        };
+        while let Some(buffer) = next {
+            samples.extend(buffer.map_err(Error::Device)?);
+            next = self.receiver.try_recv().ok();
+        }
+        Ok(())
+    }
+}
+
+/// Input stream converting `T` samples to f32
+fn build<T>(device: &cpal::Device, config: &StreamConfig, sender: SyncSender<Buffer>) -> Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let errors = sender.clone();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let _ = sender.try_send(Ok(data.iter().map(|sample| sample.to_sample::<f32>()).collect()));
+            },
+            move |e| match e {
+                // Glitches are survivable; a lost device is not
+                StreamError::BufferUnderrun => log::debug!("[AudioCapture] Input overrun"),
+                e => {
+                    log::warn!("[AudioCapture] Input stream error: {}", e);
+                    let _ = errors.try_send(Err(e.to_string()));
+                }
+            },
+            None,
+        )
+        .map_err(|e| Error::Device(format!("Failed to open the input stream: {}", e)))
+}
@@ -0,0 +1,350 @@
+//! From device buffers to chunks: channel remix, resampling, chunking and encoding.
+//!
+//! Loopback sources deliver interleaved f32 at whatever rate and channel count the
+//! device mixes at (usually 48 kHz stereo). Each buffer is remixed to the requested
+//! channels (mono averages every channel, stereo keeps the first two or duplicates a
+//! mono source), resampled with rubato when the rates differ, and collected until a
+//! `chunk_ms` chunk is full. Chunks are then sent as:
+//!
+//! - `pcm`: f32 little-endian interleaved samples, base64
+//! - `opus`: 20 ms Opus packets back to back, base64, with each packet's length in
+//!   `packets` so they can be fed to a decoder (or an Ogg/WebM muxer) one by one
+//!
+//! Opus only runs at 8, 12, 16, 24 or 48 kHz, and its chunks are whole packets, so
+//! `chunk_ms` is rounded up to a multiple of 20.
+
+use crate::error::{Error, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rubato::{FftFixedIn, Resampler};
+use serde::{Deserialize, Serialize};
+
+/// Opus packet length
+const OPUS_FRAME_MS: u32 = 20;
+
+/// Sample rates Opus encodes at
+const OPUS_RATES: [u32; 5] = [8000, 12000, 16000, 24000, 48000];
+
+/// Largest Opus packet we ask the encoder for
+const OPUS_MAX_PACKET: usize = 4000;
+
+/// Input frames per resampler call
+const RESAMPLE_CHUNK: usize = 1024;
+
+fn default_sample_rate() -> u32 {
+    16000
+}
+
+fn default_channels() -> u16 {
+    1
+}
+
+fn default_chunk_ms() -> u32 {
+    100
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioEncoding {
+    #[default]
+    Pcm,
+    Opus,
+}
+
+/// What chunks a capture sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStreamConfig {
+    #[serde(default)]
+    pub encoding: AudioEncoding,
+    /// Output rate; 16 kHz (the default) is what transcription models expect
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: u32,
+    /// 1 or 2
+    #[serde(default = "default_channels")]
+    pub channels: u16,
+    /// Audio per chunk
+    #[serde(default = "default_chunk_ms")]
+    pub chunk_ms: u32,
+    /// Opus bitrate in bits per second; None lets the encoder pick
+    #[serde(default)]
+    pub bitrate: Option<u32>,
+}
+
+impl Default for AudioStreamConfig {
+    fn default() -> Self {
+        Self {
+            encoding: AudioEncoding::Pcm,
+            sample_rate: default_sample_rate(),
+            channels: default_channels(),
+            chunk_ms: default_chunk_ms(),
+            bitrate: None,
+        }
+    }
+}
+
+impl AudioStreamConfig {
+    /// Check the config and round `chunk_ms` to what the encoding can send
+    pub fn validated(mut self) -> Result<Self> {
+        if !(1..=2).contains(&self.channels) {
+            return Err(Error::InvalidConfig(format!("{} channels (1 or 2 supported)", self.channels)));
+        }
+        if !(8000..=48000).contains(&self.sample_rate) {
+            return Err(Error::InvalidConfig(format!("{} Hz (8000–48000 supported)", self.sample_rate)));
+        }
+        if self.encoding == AudioEncoding::Opus && !OPUS_RATES.contains(&self.sample_rate) {
+            return Err(Error::InvalidConfig(format!(
+                "Opus can't encode at {} Hz (8000, 12000, 16000, 24000 or 48000)",
+                self.sample_rate
+            )));
+        }
+        self.chunk_ms = self.chunk_ms.clamp(10, 5000);
+        if self.encoding == AudioEncoding::Opus {
+            self.chunk_ms = self.chunk_ms.div_ceil(OPUS_FRAME_MS) * OPUS_FRAME_MS;
+        }
+        Ok(self)
+    }
+}
+
+/// Rate and channel count a loopback source delivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// A chunk of captured audio
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioChunk {
+    pub encoding: AudioEncoding,
+    /// Base64 of f32 LE interleaved samples (`pcm`) or Opus packets back to back (`opus`)
+    pub data: String,
+    /// Byte length of each Opus packet in `data`; empty for PCM
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub packets: Vec<u32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// When the chunk was completed, seconds since the epoch
+    pub timestamp: f64,
+    pub chunk_count: u64,
+}
+
+enum Encoder {
+    Pcm,
+    Opus(opus::Encoder),
+}
+
+/// Turns one source's buffers into chunks
+pub struct Pipeline {
+    config: AudioStreamConfig,
+    source: SourceFormat,
+    resampler: Option<FftFixedIn<f32>>,
+    /// Remixed samples waiting for a full resampler input, per channel
+    pending: Vec<Vec<f32>>,
+    /// Output-rate samples waiting for a full chunk, per channel
+    ready: Vec<Vec<f32>>,
+    chunk_frames: usize,
+    encoder: Encoder,
+    chunk_count: u64,
+}
+
+impl Pipeline {
+    /// `config` must have been validated
+    pub fn new(config: AudioStreamConfig, source: SourceFormat) -> Result<Self> {
+        let channels = usize::from(config.channels);
+        let resampler = if source.sample_rate == config.sample_rate {
+            None
+        } else {
+            let resampler = FftFixedIn::<f32>::new(
+                source.sample_rate as usize,
+                config.sample_rate as usize,
+                RESAMPLE_CHUNK,
+                2,
+                channels,
+            )
+            .map_err(|e| Error::Encode(format!("Failed to create resampler: {}", e)))?;
+            Some(resampler)
+        };
+        let encoder = match config.encoding {
+            AudioEncoding::Pcm => Encoder::Pcm,
+            AudioEncoding::Opus => {
+                let opus_channels = if channels == 1 { opus::Channels::Mono } else { opus::Channels::Stereo };
+                let mut encoder = opus::Encoder::new(config.sample_rate, opus_channels, opus::Application::Audio)
+                    .map_err(|e| Error::Encode(format!("Failed to create Opus encoder: {}", e)))?;
+                if let Some(bitrate) = config.bitrate {
+                    encoder
+                        .set_bitrate(opus::Bitrate::Bits(bitrate.min(512_000) as i32))
+                        .map_err(|e| Error::Encode(format!("Failed to set Opus bitrate: {}", e)))?;
+                }
+                Encoder::Opus(encoder)
+            }
+        };
+        log::info!(
+            "[AudioCapture] Pipeline: {} Hz x{} -> {} Hz x{} {:?}, {} ms chunks",
+            source.sample_rate,
+            source.channels,
+            config.sample_rate,
+            config.channels,
+            config.encoding,
+            config.chunk_ms
+        );
+        Ok(Self {
+            config,
+            source,
+            resampler,
+            pending: vec![Vec::new(); channels],
+            ready: vec![Vec::new(); channels],
+            chunk_frames: (config.sample_rate as usize * config.chunk_ms as usize) / 1000,
+            encoder,
+            chunk_count: 0,
+        })
+    }
+
+    /// Feed interleaved source samples; returns the chunks they completed
+    pub fn push(&mut self, interleaved: &[f32]) -> Result<Vec<AudioChunk>> {
+        self.push_samples(interleaved)?.iter().map(|samples| self.encode(samples)).collect()
+    }
+
+    /// Like `push`, but returns each completed chunk's interleaved samples at the
+    /// requested rate and channels instead of encoding them
+    pub fn push_samples(&mut self, interleaved: &[f32]) -> Result<Vec<Vec<f32>>> {
+        let remixed = remix(interleaved, usize::from(self.source.channels), self.ready.len());
+        match &mut self.resampler {
+            None => append(&mut self.ready, remixed),
+            Some(resampler) => {
+                append(&mut self.pending, remixed);
+                while self.pending[0].len() >= resampler.input_frames_next() {
+                    let take = resampler.input_frames_next();
+                    let input: Vec<Vec<f32>> = self.pending.iter_mut().map(|c| c.drain(..take).collect()).collect();
+                    let output = resampler
+                        .process(&input, None)
+                        .map_err(|e| Error::Encode(format!("Resampling failed: {}", e)))?;
+                    append(&mut self.ready, output);
+                }
+            }
+        }
+
+        let mut chunks = Vec::new();
+        while self.ready[0].len() >= self.chunk_frames {
+            let frames: Vec<Vec<f32>> = self.ready.iter_mut().map(|c| c.drain(..self.chunk_frames).collect()).collect();
+            chunks.push(interleave(&frames));
+        }
+        Ok(chunks)
+    }
+
+    /// Like `push`, but returns the 20 ms Opus packets on their own. Needs Opus encoding.
+    pub fn push_packets(&mut self, interleaved: &[f32]) -> Result<Vec<Vec<u8>>> {
+        let mut packets = Vec::new();
+        for samples in self.push_samples(interleaved)? {
+            packets.extend(self.opus_packets(&samples)?);
+        }
+        Ok(packets)
+    }
+
+    /// Opus packets of interleaved samples, one per `OPUS_FRAME_MS`
+    fn opus_packets(&mut self, samples: &[f32]) -> Result<Vec<Vec<u8>>> {
+        let Encoder::Opus(encoder) = &mut self.encoder else {
+            return Err(Error::Encode("Opus packets need Opus encoding".to_string()));
+        };
+        let packet_samples =
+            (self.config.sample_rate * OPUS_FRAME_MS / 1000) as usize * usize::from(self.config.channels);
+        let mut packet = [0u8; OPUS_MAX_PACKET];
+        samples
+            .chunks_exact(packet_samples)
+            .map(|frame| {
+                let len = encoder
+                    .encode_float(frame, &mut packet)
+                    .map_err(|e| Error::Encode(format!("Opus encoding failed: {}", e)))?;
+                Ok(packet[..len].to_vec())
+            })
+            .collect()
+    }
+
+    fn encode(&mut self, samples: &[f32]) -> Result<AudioChunk> {
+        let (bytes, packets) = match self.encoder {
+            Encoder::Pcm => (samples.iter().flat_map(|s| s.to_le_bytes()).collect(), Vec::new()),
+            Encoder::Opus(_) => {
+                let packets = self.opus_packets(samples)?;
+                (packets.concat(), packets.iter().map(|p| p.len() as u32).collect())
+            }
+        };
+        self.chunk_count += 1;
+        Ok(AudioChunk {
+            encoding: self.config.encoding,
+            data: STANDARD.encode(&bytes),
+            packets,
+            sample_rate: self.config.sample_rate,
+            channels: self.config.channels,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            chunk_count: self.chunk_count,
+        })
+    }
+}
+
+fn append(target: &mut [Vec<f32>], planar: Vec<Vec<f32>>) {
+    for (channel, samples) in target.iter_mut().zip(planar) {
+        channel.extend(samples);
+    }
+}
+
+/// Interleaved samples with `from` channels as `to` (1 or 2) planar channels
+fn remix(interleaved: &[f32], from: usize, to: usize) -> Vec<Vec<f32>> {
+    let from = from.max(1);
+    let frames = interleaved.chunks_exact(from);
+    match (to, from) {
+        (1, _) => vec![frames.map(|f| f.iter().sum::<f32>() / from as f32).collect()],
+        (_, 1) => {
+            let mono: Vec<f32> = frames.map(|f| f[0]).collect();
+            vec![mono.clone(), mono]
+        }
+        _ => vec![frames.clone().map(|f| f[0]).collect(), frames.map(|f| f[1]).collect()],
+    }
+}
+
+fn interleave(planar: &[Vec<f32>]) -> Vec<f32> {
+    (0..planar[0].len()).flat_map(|i| planar.iter().map(move |c| c[i])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remixes_to_requested_channels() {
+        let stereo = [0.2, 0.4, -1.0, 1.0];
+        assert_eq!(remix(&stereo, 2, 1), vec![vec![0.3, 0.0]]);
+        assert_eq!(remix(&stereo, 2, 2), vec![vec![0.2, -1.0], vec![0.4, 1.0]]);
+        assert_eq!(remix(&[0.5, 0.25], 1, 2), vec![vec![0.5, 0.25], vec![0.5, 0.25]]);
+        // Surround keeps front left/right
+        assert_eq!(remix(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 6, 2), vec![vec![1.0], vec![2.0]]);
+        assert_eq!(interleave(&[vec![1.0, 3.0], vec![2.0, 4.0]]), vec![1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn collects_pcm_into_chunks() {
+        let config = AudioStreamConfig { sample_rate: 16000, chunk_ms: 10, ..Default::default() }.validated().unwrap();
+        let mut pipeline = Pipeline::new(config, SourceFormat { sample_rate: 16000, channels: 2 }).unwrap();
+        // 10 ms = 160 frames; 250 stereo frames make one chunk with 90 left over
+        let chunks = pipeline.push(&vec![0.5; 500]).unwrap();
+        assert_eq!(chunks.len(), 1);
+        let bytes = STANDARD.decode(&chunks[0].data).unwrap();
+        assert_eq!(bytes.len(), 160 * 4);
+        assert_eq!(&bytes[..4], &0.5f32.to_le_bytes());
+        assert!(chunks[0].packets.is_empty());
+        assert_eq!(pipeline.push(&vec![0.0; 140]).unwrap().len(), 1);
+        assert_eq!(pipeline.ready[0].len(), 0);
+    }
+
+    #[test]
+    fn validates_and_rounds_configs() {
+        let opus = AudioStreamConfig { encoding: AudioEncoding::Opus, chunk_ms: 50, ..Default::default() };
+        assert_eq!(opus.validated().unwrap().chunk_ms, 60);
+        assert!(AudioStreamConfig { sample_rate: 44100, ..opus }.validated().is_err());
+        assert!(AudioStreamConfig { sample_rate: 44100, ..Default::default() }.validated().is_ok());
+        assert!(AudioStreamConfig { channels: 6, ..Default::default() }.validated().is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Linux: the default sink's monitor source, through the PulseAudio API.
+//!
+//! `@DEFAULT_MONITOR@` is the monitor of whatever sink is the default, so this follows
+//! the output the user hears. PipeWire desktops serve the same API through
+//! pipewire-pulse, so one backend covers both. Reads block for `READ_MS` of audio; the
+//! monitor delivers silence while nothing plays.
+
+use crate::capture::AudioSource;
+use crate::error::{Error, Result};
+use crate::pipeline::SourceFormat;
+use libpulse_binding::sample::{Format, Spec};
+use libpulse_binding::stream::Direction;
+use libpulse_simple_binding::Simple;
+
+/// Audio per read
+const READ_MS: usize = 20;
+
+/// Rate and channels we ask the server for; it converts from the sink's own
+const FORMAT: SourceFormat = SourceFormat { sample_rate: 48000, channels: 2 };
+
+pub struct Loopback {
+    stream: Simple,
+    buffer: Vec<u8>,
+}
+
+impl Loopback {
+    pub fn open() -> Result<Self> {
+        let spec = Spec { format: Format::F32le, channels: FORMAT.channels as u8, rate: FORMAT.sample_rate };
+        let stream = Simple::new(
+            None,
+            "Observer",
+            Direction::Record,
+            Some("@DEFAULT_MONITOR@"),
+            "System audio",
+            &spec,
+            None,
+            None,
+        )
+        .map_err(|e| Error::Device(format!("Failed to open the output monitor: {}", e)))?;
+        log::info!("[AudioCapture] Pulse monitor: {} Hz, {} channels", FORMAT.sample_rate, FORMAT.channels);
+        let frames = FORMAT.sample_rate as usize * READ_MS / 1000;
+        Ok(Self { stream, buffer: vec![0u8; frames * usize::from(FORMAT.channels) * 4] })
+    }
+}
+
+impl AudioSource for Loopback {
+    fn format(&self) -> SourceFormat {
+        FORMAT
+    }
+
+    /// Append the next `READ_MS` of output
+    fn read(&mut self, samples: &mut Vec<f32>) -> Result<()> {
+        self.stream.read(&mut self.buffer).map_err(|e| Error::Device(format!("Monitor read failed: {}", e)))?;
+        samples.extend(self.buffer.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+        Ok(())
+    }
+}
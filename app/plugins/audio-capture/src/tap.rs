@@ -0,0 +1,260 @@
+//! macOS: a Core Audio process tap on everything the system plays (macOS 14.2+).
+//!
+//! A private, unmuted global tap (excluding no process) is wrapped in a private
+//! aggregate device, and an IO proc on that device receives the tap's stereo mix. The
+//! IO proc runs on Core Audio's real-time thread, so it only copies samples into a
+//! bounded queue; `read` drains it on the capture thread. If the reader falls behind,
+//! buffers are dropped rather than blocking the audio thread.
+//!
+//! The first tap prompts for "System Audio Recording" access; the app's Info.plist needs
+//! `NSAudioCaptureUsageDescription`. Without access the tap delivers silence.
+
+use crate::capture::AudioSource;
+use crate::error::{Error, Result};
+use crate::pipeline::SourceFormat;
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2::AnyThread;
+use objc2_core_audio::{
+    kAudioAggregateDeviceIsPrivateKey, kAudioAggregateDeviceNameKey, kAudioAggregateDeviceTapAutoStartKey,
+    kAudioAggregateDeviceTapListKey, kAudioAggregateDeviceUIDKey, kAudioObjectPropertyElementMain,
+    kAudioObjectPropertyScopeGlobal, kAudioSubTapUIDKey, kAudioTapPropertyFormat, AudioDeviceCreateIOProcID,
+    AudioDeviceDestroyIOProcID, AudioDeviceIOProcID, AudioDeviceStart, AudioDeviceStop,
+    AudioHardwareCreateAggregateDevice, AudioHardwareCreateProcessTap, AudioHardwareDestroyAggregateDevice,
+    AudioHardwareDestroyProcessTap, AudioObjectGetPropertyData, AudioObjectID, AudioObjectPropertyAddress,
+    CATapDescription, CATapMuteBehavior,
+};
+use objc2_core_audio_types::{
+    kAudioFormatFlagIsNonInterleaved, AudioBufferList, AudioStreamBasicDescription, AudioTimeStamp,
+};
+use objc2_core_foundation::CFDictionary;
+use objc2_foundation::{NSArray, NSDictionary, NSNumber, NSString};
+use std::ffi::{c_void, CStr};
+use std::ptr::NonNull;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::time::Duration;
+
+/// Buffers queued between the IO proc and the reader (~1 s at 10 ms buffers)
+const QUEUE: usize = 100;
+
+/// How long a read waits for the next buffer
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// What the IO proc needs, owned by the `Loopback`
+struct IoState {
+    sender: SyncSender<Vec<f32>>,
+    non_interleaved: bool,
+}
+
+pub struct Loopback {
+    tap: AudioObjectID,
+    device: AudioObjectID,
+    proc_id: AudioDeviceIOProcID,
+    state: *mut IoState,
+    receiver: Receiver<Vec<f32>>,
+    format: SourceFormat,
+}
+
+// The raw state pointer is only touched by the IO proc and by Drop, after the proc is
+// destroyed
+unsafe impl Send for Loopback {}
+
+fn check(status: i32, what: &str) -> Result<()> {
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(Error::Device(format!("Failed to {} (OSStatus {})", what, status)))
+    }
+}
+
+fn key(key: &CStr) -> objc2::rc::Retained<NSString> {
+    NSString::from_str(key.to_str().unwrap_or_default())
+}
+
+impl Loopback {
+    pub fn open() -> Result<Self> {
+        if AnyClass::get(c"CATapDescription").is_none() {
+            return Err(Error::Device("System audio capture needs macOS 14.2 or later".to_string()));
+        }
+
+        // Global stereo tap excluding no process; the user keeps hearing the audio
+        let description = unsafe {
+            CATapDescription::initStereoGlobalTapButExcludeProcesses(
+                CATapDescription::alloc(),
+                &NSArray::<NSNumber>::new(),
+            )
+        };
+        unsafe {
+            description.setPrivate(true);
+            description.setMuteBehavior(CATapMuteBehavior::Unmuted);
+        }
+        let tap_uid = unsafe { description.UUID() }.UUIDString();
+        let mut tap: AudioObjectID = 0;
+        check(unsafe { AudioHardwareCreateProcessTap(Some(&description), &mut tap) }, "create the process tap")?;
+
+        let loopback = Self::with_tap(tap, &tap_uid);
+        if loopback.is_err() {
+            unsafe { AudioHardwareDestroyProcessTap(tap) };
+        }
+        loopback
+    }
+
+    /// Aggregate device and IO proc around a created tap
+    fn with_tap(tap: AudioObjectID, tap_uid: &NSString) -> Result<Self> {
+        let format = tap_format(tap)?;
+        let source = SourceFormat { sample_rate: format.mSampleRate as u32, channels: format.mChannelsPerFrame as u16 };
+
+        let sub_tap_key = key(kAudioSubTapUIDKey);
+        let sub_tap = NSDictionary::<NSString, AnyObject>::from_slices(&[&*sub_tap_key], &[tap_uid.as_ref()]);
+        let taps = NSArray::from_retained_slice(&[sub_tap]);
+        let device_uid = NSString::from_str(&format!("observer-system-audio-{}", tap_uid));
+        let name = NSString::from_str("Observer System Audio");
+        let yes = NSNumber::numberWithBool(true);
+        let keys = [
+            key(kAudioAggregateDeviceNameKey),
+            key(kAudioAggregateDeviceUIDKey),
+            key(kAudioAggregateDeviceIsPrivateKey),
+            key(kAudioAggregateDeviceTapAutoStartKey),
+            key(kAudioAggregateDeviceTapListKey),
+        ];
+        let values: [&AnyObject; 5] = [name.as_ref(), device_uid.as_ref(), yes.as_ref(), yes.as_ref(), taps.as_ref()];
+        let keys: Vec<&NSString> = keys.iter().map(|k| &**k).collect();
+        let description = NSDictionary::<NSString, AnyObject>::from_slices(&keys, &values);
+        // NSDictionary is toll-free bridged to CFDictionary
+        let description = unsafe { &*(objc2::rc::Retained::as_ptr(&description).cast::<CFDictionary>()) };
+        let mut device: AudioObjectID = 0;
+        check(
+            unsafe { AudioHardwareCreateAggregateDevice(description, NonNull::from(&mut device)) },
+            "create the tap device",
+        )?;
+
+        let (sender, receiver) = sync_channel(QUEUE);
+        let state = Box::into_raw(Box::new(IoState {
+            sender,
+            non_interleaved: format.mFormatFlags & kAudioFormatFlagIsNonInterleaved != 0,
+        }));
+        let mut proc_id: AudioDeviceIOProcID = None;
+        let started = check(
+            unsafe { AudioDeviceCreateIOProcID(device, Some(io_proc), state.cast(), NonNull::from(&mut proc_id)) },
+            "add the IO proc",
+        )
+        .and_then(|()| check(unsafe { AudioDeviceStart(device, proc_id) }, "start the tap device"));
+        if let Err(e) = started {
+            unsafe {
+                if proc_id.is_some() {
+                    AudioDeviceDestroyIOProcID(device, proc_id);
+                }
+                AudioHardwareDestroyAggregateDevice(device);
+                drop(Box::from_raw(state));
+            }
+            return Err(e);
+        }
+
+        log::info!("[AudioCapture] Core Audio tap: {} Hz, {} channels", source.sample_rate, source.channels);
+        Ok(Self { tap, device, proc_id, state, receiver, format: source })
+    }
+}
+
+impl AudioSource for Loopback {
+    fn format(&self) -> SourceFormat {
+        self.format
+    }
+
+    /// Append the buffers the tap delivered since the last read
+    fn read(&mut self, samples: &mut Vec<f32>) -> Result<()> {
+        match self.receiver.recv_timeout(READ_TIMEOUT) {
+            Ok(buffer) => samples.extend(buffer),
+            Err(RecvTimeoutError::Timeout) => return Ok(()),
+            Err(RecvTimeoutError::Disconnected) => return Err(Error::Device("Tap stopped".to_string())),
+        }
+        while let Ok(buffer) = self.receiver.try_recv() {
+            samples.extend(buffer);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Loopback {
+    fn drop(&mut self) {
+        unsafe {
+            AudioDeviceStop(self.device, self.proc_id);
+            AudioDeviceDestroyIOProcID(self.device, self.proc_id);
+            AudioHardwareDestroyAggregateDevice(self.device);
+            AudioHardwareDestroyProcessTap(self.tap);
+            drop(Box::from_raw(self.state));
+        }
+    }
+}
+
+fn tap_format(tap: AudioObjectID) -> Result<AudioStreamBasicDescription> {
+    let address = AudioObjectPropertyAddress {
+        mSelector: kAudioTapPropertyFormat,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMain,
+    };
+    let mut format = AudioStreamBasicDescription {
+        mSampleRate: 0.0,
+        mFormatID: 0,
+        mFormatFlags: 0,
+        mBytesPerPacket: 0,
+        mFramesPerPacket: 0,
+        mBytesPerFrame: 0,
+        mChannelsPerFrame: 0,
+        mBitsPerChannel: 0,
+        mReserved: 0,
+    };
+    let mut size = std::mem::size_of::<AudioStreamBasicDescription>() as u32;
+    check(
+        unsafe {
+            AudioObjectGetPropertyData(
+                tap,
+                NonNull::from(&address),
+                0,
+                std::ptr::null(),
+                NonNull::from(&mut size),
+                NonNull::from(&mut format).cast(),
+            )
+        },
+        "read the tap format",
+    )?;
+    if format.mBitsPerChannel != 32 || format.mChannelsPerFrame == 0 {
+        return Err(Error::Device(format!(
+            "Unexpected tap format: {} bits, {} channels",
+            format.mBitsPerChannel, format.mChannelsPerFrame
+        )));
+    }
+    Ok(format)
+}
+
+/// Copy the tap's input into the queue as interleaved f32
+unsafe extern "C-unwind" fn io_proc(
+    _device: AudioObjectID,
+    _now: NonNull<AudioTimeStamp>,
+    input: NonNull<AudioBufferList>,
+    _input_time: NonNull<AudioTimeStamp>,
+    _output: NonNull<AudioBufferList>,
+    _output_time: NonNull<AudioTimeStamp>,
+    client_data: *mut c_void,
+) -> i32 {
+    let state = &*client_data.cast::<IoState>();
+    let list = input.as_ref();
+    let buffers = std::slice::from_raw_parts(list.mBuffers.as_ptr(), list.mNumberBuffers as usize);
+    let channel = |buffer: &objc2_core_audio_types::AudioBuffer| -> &[f32] {
+        if buffer.mData.is_null() {
+            &[]
+        } else {
+            std::slice::from_raw_parts(buffer.mData.cast::<f32>(), buffer.mDataByteSize as usize / 4)
+        }
+    };
+    let samples = if state.non_interleaved && buffers.len() > 1 {
+        // One buffer per channel
+        let channels: Vec<&[f32]> = buffers.iter().map(channel).collect();
+        let frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        (0..frames).flat_map(|i| channels.iter().map(move |c| c[i])).collect()
+    } else {
+        buffers.first().map(|b| channel(b).to_vec()).unwrap_or_default()
+    };
+    if !samples.is_empty() {
+        let _ = state.sender.try_send(samples);
+    }
+    0
+}
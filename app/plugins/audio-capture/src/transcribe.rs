@@ -0,0 +1,335 @@
+//! Local speech-to-text with whisper.cpp (behind the `whisper` feature).
+//!
+//! A transcription opens its own source — the microphone by default, or the system
+//! audio — independent of any stream, converts it to the 16 kHz mono whisper expects
+//! and transcribes it in windows of `window_secs`. Each segment goes to the channel with
+//! its wall-clock start and end. Reading and transcribing run on separate threads so a
+//! slow model doesn't drop audio at the source; if whisper falls a window behind, the
+//! next window is dropped instead.
+//!
+//! Nearly silent windows and segments whisper itself rates as no speech are skipped:
+//! whisper tends to invent text ("Thank you.") on silence. With `phrases`, a segment
+//! containing one (ignoring case and punctuation) also emits
+//! `audio-capture://phrase-detected`, so an agent can trigger on "when someone says X"
+//! without a model call per window. Words cut by a window boundary can be missed;
+//! shorter windows react sooner but give whisper less context.
+//!
+//! Models are ggml files (e.g. `ggml-base.en.bin`). Loading one takes a moment, so
+//! `start` returns once the model and source are ready. One transcription runs at a
+//! time.
+
+use crate::capture::{self, AudioSource, SourceKind};
+use crate::error::{Error, Result};
+use crate::events;
+use crate::pipeline::{AudioEncoding, AudioStreamConfig, Pipeline};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tauri::ipc::Channel;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState};
+
+/// The rate whisper works at
+const SAMPLE_RATE: u32 = 16000;
+
+/// Windows below this RMS aren't transcribed
+const SILENCE_RMS: f32 = 0.005;
+
+/// Segments whisper rates more likely than this to be no speech are dropped
+const MAX_NO_SPEECH: f32 = 0.6;
+
+fn default_source() -> SourceKind {
+    SourceKind::Microphone
+}
+
+fn default_window_secs() -> u32 {
+    5
+}
+
+/// What to transcribe and how
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionConfig {
+    /// Path to a ggml whisper model
+    pub model_path: String,
+    #[serde(default = "default_source")]
+    pub source: SourceKind,
+    /// Input device for the microphone; None = default
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// Language code such as "en"; None detects it
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Seconds of audio per whisper pass (1–30)
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u32,
+    /// Phrases that emit `events::PHRASE_DETECTED` when heard
+    #[serde(default)]
+    pub phrases: Vec<String>,
+}
+
+/// One transcribed segment, as sent over the channel
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSegment {
+    pub text: String,
+    /// Seconds since the epoch
+    pub start: f64,
+    pub end: f64,
+    /// Phrases from the config found in `text`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub phrases: Vec<String>,
+}
+
+/// Payload of `events::PHRASE_DETECTED`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhraseDetected {
+    pub phrase: String,
+    pub segment: TranscriptSegment,
+}
+
+struct Transcription {
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Transcription {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // The reader first: the transcriber ends once the reader drops its sender
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+static TRANSCRIPTION: Mutex<Option<Transcription>> = Mutex::new(None);
+
+/// A window of audio and when it started, seconds since the epoch
+type Window = (f64, Vec<f32>);
+
+/// Load the model, open the source and start transcribing into `channel`, replacing a
+/// running transcription
+pub fn start(config: TranscriptionConfig, channel: Channel<TranscriptSegment>) -> Result<()> {
+    if config.model_path.is_empty() {
+        return Err(Error::InvalidConfig("modelPath is required".to_string()));
+    }
+    let window_secs = config.window_secs.clamp(1, 30);
+    stop();
+
+    let context = WhisperContext::new_with_params(&config.model_path, WhisperContextParameters::default())
+        .map_err(|e| Error::Transcription(format!("Failed to load {}: {}", config.model_path, e)))?;
+    let state = context
+        .create_state()
+        .map_err(|e| Error::Transcription(format!("Failed to create whisper state: {}", e)))?;
+
+    let source_kind = config.source;
+    let stop = Arc::new(AtomicBool::new(false));
+    // One window queued while the previous one is transcribed
+    let (windows, pending) = mpsc::sync_channel::<Window>(1);
+    let (opened, on_open) = mpsc::sync_channel::<Result<()>>(1);
+    let reader = {
+        let stop = stop.clone();
+        let device_id = config.device_id.clone();
+        std::thread::spawn(move || {
+            let pipeline_config = AudioStreamConfig {
+                encoding: AudioEncoding::Pcm,
+                sample_rate: SAMPLE_RATE,
+                channels: 1,
+                chunk_ms: 100,
+                bitrate: None,
+            };
+            let opening = capture::open_source(source_kind, device_id.as_deref())
+                .and_then(|(source, _)| Pipeline::new(pipeline_config, source.format()).map(|p| (source, p)));
+            let (mut source, mut pipeline) = match opening {
+                Ok(opening) => {
+                    let _ = opened.send(Ok(()));
+                    opening
+                }
+                Err(e) => {
+                    let _ = opened.send(Err(e));
+                    return;
+                }
+            };
+            read_windows(source.as_mut(), &mut pipeline, window_secs, &windows, &stop);
+        })
+    };
+    let transcriber = {
+        let stop = stop.clone();
+        std::thread::spawn(move || transcribe_windows(state, &config, &pending, &channel, &stop))
+    };
+
+    match on_open.recv_timeout(capture::OPEN_TIMEOUT) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            stop.store(true, Ordering::SeqCst);
+            return Err(Error::Device("Timed out opening the audio device".to_string()));
+        }
+    }
+    *TRANSCRIPTION.lock() = Some(Transcription { stop, threads: vec![reader, transcriber] });
+    log::info!("[AudioCapture] Transcription started ({:?}, {} s windows)", source_kind, window_secs);
+    Ok(())
+}
+
+/// Stop the running transcription; false if none was running
+pub fn stop() -> bool {
+    // Take it out first so the joins in Drop don't hold the lock
+    let transcription = TRANSCRIPTION.lock().take();
+    transcription.is_some()
+}
+
+fn now() -> f64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+/// Read the source into windows of `window_secs` and queue them for the transcriber
+fn read_windows(
+    source: &mut dyn AudioSource,
+    pipeline: &mut Pipeline,
+    window_secs: u32,
+    windows: &SyncSender<Window>,
+    stop: &AtomicBool,
+) {
+    let window_samples = (SAMPLE_RATE * window_secs) as usize;
+    let mut window = Vec::with_capacity(window_samples);
+    let mut window_start = 0.0;
+    let mut samples = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        samples.clear();
+        if let Err(e) = source.read(&mut samples) {
+            log::error!("[AudioCapture] Transcription source: {}", e);
+            return;
+        }
+        if samples.is_empty() {
+            continue;
+        }
+        let chunks = match pipeline.push_samples(&samples) {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                log::error!("[AudioCapture] Transcription source: {}", e);
+                return;
+            }
+        };
+        for chunk in chunks {
+            if window.is_empty() {
+                window_start = now() - chunk.len() as f64 / f64::from(SAMPLE_RATE);
+            }
+            window.extend(chunk);
+        }
+        if window.len() < window_samples {
+            continue;
+        }
+        let full = std::mem::replace(&mut window, Vec::with_capacity(window_samples));
+        if rms(&full) < SILENCE_RMS {
+            continue;
+        }
+        match windows.try_send((window_start, full)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("[AudioCapture] Transcription is behind, dropping a window"),
+            Err(TrySendError::Disconnected(_)) => return,
+        }
+    }
+}
+
+/// Transcribe queued windows until the reader stops or the channel closes
+fn transcribe_windows(
+    mut state: WhisperState,
+    config: &TranscriptionConfig,
+    pending: &Receiver<Window>,
+    channel: &Channel<TranscriptSegment>,
+    stop: &AtomicBool,
+) {
+    for (start, window) in pending {
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(config.language.as_deref());
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        if let Err(e) = state.full(params, &window) {
+            log::warn!("[AudioCapture] Transcription failed: {}", e);
+            continue;
+        }
+        for segment in state.as_iter() {
+            if segment.no_speech_probability() > MAX_NO_SPEECH {
+                continue;
+            }
+            let Ok(text) = segment.to_str_lossy() else { continue };
+            let text = text.trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+            // Whisper timestamps are in hundredths of a second from the window start
+            let segment = TranscriptSegment {
+                phrases: matched_phrases(&text, &config.phrases),
+                text,
+                start: start + segment.start_timestamp() as f64 / 100.0,
+                end: start + segment.end_timestamp() as f64 / 100.0,
+            };
+            for phrase in &segment.phrases {
+                events::emit(
+                    events::PHRASE_DETECTED,
+                    PhraseDetected { phrase: phrase.clone(), segment: segment.clone() },
+                );
+            }
+            if let Err(e) = channel.send(segment) {
+                log::warn!("[AudioCapture] Transcript channel closed, stopping: {}", e);
+                stop.store(true, Ordering::SeqCst);
+                return;
+            }
+        }
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Lowercase words separated by single spaces, without punctuation
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The phrases `text` contains as whole words
+fn matched_phrases(text: &str, phrases: &[String]) -> Vec<String> {
+    let text = format!(" {} ", normalize(text));
+    phrases
+        .iter()
+        .filter(|phrase| {
+            let phrase = normalize(phrase);
+            !phrase.is_empty() && text.contains(&format!(" {} ", phrase))
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_phrases_as_whole_words() {
+        let phrases = vec!["hey observer".to_string(), "stop".to_string(), "deploy".to_string()];
+        assert_eq!(matched_phrases("Hey, Observer! Can you stop?", &phrases), vec!["hey observer", "stop"]);
+        assert!(matched_phrases("Unstoppable deployment", &phrases).is_empty());
+        assert!(matched_phrases("anything", &["  ".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn silence_has_low_rms() {
+        assert!(rms(&[0.0; 1600]) < SILENCE_RMS);
+        assert!(rms(&[0.1, -0.1, 0.1, -0.1]) > SILENCE_RMS);
+        assert_eq!(rms(&[]), 0.0);
+    }
+}
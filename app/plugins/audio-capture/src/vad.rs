@@ -0,0 +1,355 @@
+//! Voice activity detection: speechStarted / speechEnded events from a source.
+//!
+//! The VAD opens its own source — the microphone by default — converts it to 16 kHz
+//! mono and classifies each 30 ms frame with WebRTC's VAD (a small GMM, negligible
+//! CPU). Speech starts after `start_ms` of mostly voiced frames and ends after
+//! `hangover_ms` without any, so a cough doesn't start it and a pause between words
+//! doesn't end it. Each transition emits `audio-capture://speech` (`SpeechEvent`),
+//! backdated to when the speech actually began or stopped.
+//!
+//! Agents use the events to raise the capture rate or run transcription only while
+//! someone talks. A hook passed to `start` sees every event too, for Rust-side
+//! reactions. One VAD runs at a time.
+
+use crate::capture::{self, AudioSource, SourceKind};
+use crate::error::{Error, Result};
+use crate::events;
+use crate::pipeline::{AudioEncoding, AudioStreamConfig, Pipeline};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use webrtc_vad::{SampleRate, Vad};
+
+/// WebRTC's VAD takes 10, 20 or 30 ms frames
+const FRAME_MS: u32 = 30;
+
+fn default_source() -> SourceKind {
+    SourceKind::Microphone
+}
+
+fn default_start_ms() -> u32 {
+    210
+}
+
+fn default_hangover_ms() -> u32 {
+    900
+}
+
+/// How readily frames count as speech
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VadMode {
+    /// Flags the most as speech; best in quiet rooms
+    Quality,
+    LowBitrate,
+    #[default]
+    Aggressive,
+    /// Flags the least; best with background noise or music
+    VeryAggressive,
+}
+
+impl From<VadMode> for webrtc_vad::VadMode {
+    fn from(mode: VadMode) -> Self {
+        match mode {
+            VadMode::Quality => webrtc_vad::VadMode::Quality,
+            VadMode::LowBitrate => webrtc_vad::VadMode::LowBitrate,
+            VadMode::Aggressive => webrtc_vad::VadMode::Aggressive,
+            VadMode::VeryAggressive => webrtc_vad::VadMode::VeryAggressive,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VadConfig {
+    #[serde(default = "default_source")]
+    pub source: SourceKind,
+    /// Input device for the microphone; None = default
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub mode: VadMode,
+    /// Speech needed before `speechStarted`
+    #[serde(default = "default_start_ms")]
+    pub start_ms: u32,
+    /// Silence needed before `speechEnded`
+    #[serde(default = "default_hangover_ms")]
+    pub hangover_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            source: default_source(),
+            device_id: None,
+            mode: VadMode::default(),
+            start_ms: default_start_ms(),
+            hangover_ms: default_hangover_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpeechEventKind {
+    SpeechStarted,
+    SpeechEnded,
+}
+
+/// Payload of `events::SPEECH`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechEvent {
+    pub kind: SpeechEventKind,
+    pub source: SourceKind,
+    /// When the speech began or stopped, seconds since the epoch
+    pub timestamp: f64,
+    /// On `speechEnded`, how long the speech lasted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+}
+
+/// Called with every event, on the VAD thread
+pub type SpeechHook = Box<dyn Fn(&SpeechEvent) + Send>;
+
+/// A change the detector reports, with how many frames ago it really happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    Started { frames_ago: u32 },
+    Ended { frames_ago: u32 },
+}
+
+/// Debounces per-frame voiced/unvoiced decisions into speech start and end
+struct SpeechDetector {
+    start_frames: u32,
+    hangover_frames: u32,
+    speaking: bool,
+    /// Voiced frames in the current run; one unvoiced frame in a row is forgiven
+    voiced: u32,
+    /// Frames since the current run began, forgiven ones included
+    run: u32,
+    gap: u32,
+    /// Unvoiced frames since the last voiced one
+    silent: u32,
+}
+
+impl SpeechDetector {
+    fn new(start_ms: u32, hangover_ms: u32) -> Self {
+        Self {
+            start_frames: (start_ms / FRAME_MS).max(1),
+            hangover_frames: (hangover_ms / FRAME_MS).max(1),
+            speaking: false,
+            voiced: 0,
+            run: 0,
+            gap: 0,
+            silent: 0,
+        }
+    }
+
+    fn update(&mut self, voiced: bool) -> Option<Transition> {
+        if voiced {
+            self.silent = 0;
+            self.gap = 0;
+            self.voiced += 1;
+            self.run += 1;
+        } else {
+            self.silent += 1;
+            self.gap += 1;
+            if self.gap > 1 {
+                self.voiced = 0;
+                self.run = 0;
+            } else if self.voiced > 0 {
+                self.run += 1;
+            }
+        }
+
+        if !self.speaking && self.voiced >= self.start_frames {
+            self.speaking = true;
+            return Some(Transition::Started { frames_ago: self.run });
+        }
+        if self.speaking && self.silent >= self.hangover_frames {
+            self.speaking = false;
+            self.voiced = 0;
+            self.run = 0;
+            return Some(Transition::Ended { frames_ago: self.silent });
+        }
+        None
+    }
+}
+
+struct Detection {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Detection {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+static DETECTION: Mutex<Option<Detection>> = Mutex::new(None);
+
+/// Start detecting speech, replacing a running VAD. Returns once the source is open.
+pub fn start(config: VadConfig, hook: Option<SpeechHook>) -> Result<()> {
+    stop();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (opened, on_open) = mpsc::sync_channel::<Result<()>>(1);
+    let thread = {
+        let stop = stop.clone();
+        let config = config.clone();
+        std::thread::spawn(move || {
+            let pipeline_config = AudioStreamConfig {
+                encoding: AudioEncoding::Pcm,
+                sample_rate: 16000,
+                channels: 1,
+                chunk_ms: FRAME_MS,
+                bitrate: None,
+            };
+            let opening = capture::open_source(config.source, config.device_id.as_deref())
+                .and_then(|(source, _)| Pipeline::new(pipeline_config, source.format()).map(|p| (source, p)));
+            let (mut source, mut pipeline) = match opening {
+                Ok(opening) => {
+                    let _ = opened.send(Ok(()));
+                    opening
+                }
+                Err(e) => {
+                    let _ = opened.send(Err(e));
+                    return;
+                }
+            };
+            detect(source.as_mut(), &mut pipeline, &config, hook.as_ref(), &stop);
+        })
+    };
+
+    match on_open.recv_timeout(capture::OPEN_TIMEOUT) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            stop.store(true, Ordering::SeqCst);
+            return Err(Error::Device("Timed out opening the audio device".to_string()));
+        }
+    }
+    log::info!("[AudioCapture] VAD started on {:?} ({:?})", config.source, config.mode);
+    *DETECTION.lock() = Some(Detection { stop, thread: Some(thread) });
+    Ok(())
+}
+
+/// Stop the running VAD; false if none was running
+pub fn stop() -> bool {
+    // Take it out first so the join in Drop doesn't hold the lock
+    let detection = DETECTION.lock().take();
+    detection.is_some()
+}
+
+fn detect(
+    source: &mut dyn AudioSource,
+    pipeline: &mut Pipeline,
+    config: &VadConfig,
+    hook: Option<&SpeechHook>,
+    stop: &AtomicBool,
+) {
+    let mut vad = Vad::new_with_rate_and_mode(SampleRate::Rate16kHz, config.mode.into());
+    let mut detector = SpeechDetector::new(config.start_ms, config.hangover_ms);
+    let mut started_at = None;
+    let mut samples = Vec::new();
+    let mut frame = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        samples.clear();
+        if let Err(e) = source.read(&mut samples) {
+            log::error!("[AudioCapture] VAD source: {}", e);
+            return;
+        }
+        if samples.is_empty() {
+            continue;
+        }
+        let frames = match pipeline.push_samples(&samples) {
+            Ok(frames) => frames,
+            Err(e) => {
+                log::error!("[AudioCapture] VAD source: {}", e);
+                return;
+            }
+        };
+        let now = now();
+        for samples in frames {
+            frame.clear();
+            frame.extend(samples.iter().map(|s| (s.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16));
+            let voiced = vad.is_voice_segment(&frame).unwrap_or(false);
+            let (kind, frames_ago) = match detector.update(voiced) {
+                Some(Transition::Started { frames_ago }) => (SpeechEventKind::SpeechStarted, frames_ago),
+                Some(Transition::Ended { frames_ago }) => (SpeechEventKind::SpeechEnded, frames_ago),
+                None => continue,
+            };
+            let timestamp = now - f64::from(frames_ago * FRAME_MS) / 1000.0;
+            let duration_secs = match kind {
+                SpeechEventKind::SpeechStarted => {
+                    started_at = Some(timestamp);
+                    None
+                }
+                SpeechEventKind::SpeechEnded => started_at.take().map(|start| (timestamp - start).max(0.0)),
+            };
+            publish(SpeechEvent { kind, source: config.source, timestamp, duration_secs }, hook);
+        }
+    }
+    // Don't leave listeners thinking someone is still talking
+    if let Some(start) = started_at {
+        let timestamp = now();
+        let event = SpeechEvent {
+            kind: SpeechEventKind::SpeechEnded,
+            source: config.source,
+            timestamp,
+            duration_secs: Some(timestamp - start),
+        };
+        publish(event, hook);
+    }
+}
+
+fn now() -> f64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+fn publish(event: SpeechEvent, hook: Option<&SpeechHook>) {
+    log::debug!("[AudioCapture] {:?} at {:.2}", event.kind, event.timestamp);
+    if let Some(hook) = hook {
+        hook(&event);
+    }
+    events::emit(events::SPEECH, &event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(detector: &mut SpeechDetector, frames: &str) -> Vec<(usize, Transition)> {
+        frames
+            .chars()
+            .enumerate()
+            .filter_map(|(i, c)| detector.update(c == 'x').map(|t| (i, t)))
+            .collect()
+    }
+
+    #[test]
+    fn debounces_speech_start_and_end() {
+        // 3 frames to start, 4 to end
+        let mut detector = SpeechDetector::new(90, 120);
+        let transitions = run(&mut detector, "x..xxx.xx....x");
+        assert_eq!(
+            transitions,
+            vec![(5, Transition::Started { frames_ago: 3 }), (12, Transition::Ended { frames_ago: 4 })]
+        );
+    }
+
+    #[test]
+    fn forgives_a_single_unvoiced_frame_while_starting() {
+        let mut detector = SpeechDetector::new(90, 300);
+        assert_eq!(run(&mut detector, "xx.x"), vec![(3, Transition::Started { frames_ago: 4 })]);
+        let mut detector = SpeechDetector::new(90, 300);
+        assert!(run(&mut detector, "xx..x").is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Windows: WASAPI loopback of the default render device.
+//!
+//! A shared-mode capture client on the render endpoint gets everything the device
+//! plays, in its mix format. WASAPI delivers nothing while nothing is playing, so reads
+//! come back empty then instead of with silence.
+
+use crate::capture::AudioSource;
+use crate::error::{Error, Result};
+use crate::pipeline::SourceFormat;
+use std::time::Duration;
+use wasapi::*;
+
+/// Polling interval
+const POLL: Duration = Duration::from_millis(10);
+
+pub struct Loopback {
+    client: AudioClient,
+    capture: AudioCaptureClient,
+    format: SourceFormat,
+    block_align: usize,
+    bits_per_sample: u16,
+}
+
+impl Loopback {
+    /// Open the default render device. Must run on the thread that reads: COM is
+    /// initialized for it here.
+    pub fn open() -> Result<Self> {
+        initialize_mta().ok().map_err(|e| Error::Device(format!("Failed to initialize COM: {:?}", e)))?;
+        let device = DeviceEnumerator::new()
+            .and_then(|enumerator| enumerator.get_default_device(&Direction::Render))
+            .map_err(|e| Error::Device(format!("No default output device: {:?}", e)))?;
+        let mut client = device.get_iaudioclient().map_err(|e| Error::Device(format!("{:?}", e)))?;
+        let wave_format = client.get_mixformat().map_err(|e| Error::Device(format!("{:?}", e)))?;
+        let (default_period, _) = client.get_device_period().map_err(|e| Error::Device(format!("{:?}", e)))?;
+        // Capture direction on a render device = loopback
+        client
+            .initialize_client(
+                &wave_format,
+                &Direction::Capture,
+                &StreamMode::PollingShared { autoconvert: false, buffer_duration_hns: default_period },
+            )
+            .map_err(|e| Error::Device(format!("Failed to start loopback: {:?}", e)))?;
+        let capture = client.get_audiocaptureclient().map_err(|e| Error::Device(format!("{:?}", e)))?;
+        client.start_stream().map_err(|e| Error::Device(format!("{:?}", e)))?;
+
+        let format = SourceFormat {
+            sample_rate: wave_format.get_samplespersec(),
+            channels: wave_format.get_nchannels(),
+        };
+        log::info!(
+            "[AudioCapture] WASAPI loopback: {} Hz, {} channels, {} bits",
+            format.sample_rate,
+            format.channels,
+            wave_format.get_bitspersample()
+        );
+        Ok(Self {
+            client,
+            capture,
+            format,
+            block_align: usize::from(wave_format.get_blockalign()),
+            bits_per_sample: wave_format.get_bitspersample(),
+        })
+    }
+}
+
+impl AudioSource for Loopback {
+    fn format(&self) -> SourceFormat {
+        self.format
+    }
+
+    /// Append what the device played since the last read
+    fn read(&mut self, samples: &mut Vec<f32>) -> Result<()> {
+        std::thread::sleep(POLL);
+        loop {
+            let frames = match self.capture.get_next_packet_size() {
+                Ok(Some(frames)) if frames > 0 => frames,
+                Ok(_) => return Ok(()),
+                Err(e) => return Err(Error::Device(format!("{:?}", e))),
+            };
+            let mut buffer = vec![0u8; frames as usize * self.block_align];
+            self.capture.read_from_device(&mut buffer).map_err(|e| Error::Device(format!("{:?}", e)))?;
+            match self.bits_per_sample {
+                32 => samples.extend(buffer.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))),
+                16 => samples
+                    .extend(buffer.chunks_exact(2).map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)),
+                bits => return Err(Error::Device(format!("Unsupported bit depth: {}", bits))),
+            }
+        }
+    }
+}
+
+impl Drop for Loopback {
+    fn drop(&mut self) {
+        let _ = self.client.stop_stream();
+    }
+}
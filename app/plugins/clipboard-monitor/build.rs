@@ -0,0 +1,5 @@
+const COMMANDS: &[&str] = &["start_clipboard_monitor_cmd", "stop_clipboard_monitor_cmd"];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -0,0 +1,27 @@
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid clipboard monitor config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Clipboard unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Clipboard monitoring is suspended (incognito)")]
+    Suspended,
+
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,33 @@
+//! Events the plugin emits to the frontend.
+//!
+//! The monitor runs on its own thread without an `AppHandle`, so `init` stores an
+//! emitter at plugin setup and `emit` is a no-op until then (e.g. in tests).
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// New text or an image was copied (`monitor::ClipboardChange` payload)
+pub const CHANGED: &str = "clipboard-monitor://changed";
+
+type EmitFn = Box<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+static EMITTER: OnceLock<EmitFn> = OnceLock::new();
+
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    let _ = EMITTER.set(Box::new(move |event, payload| {
+        if let Err(e) = app.emit(event, payload) {
+            log::warn!("[ClipboardMonitor] Failed to emit {}: {}", event, e);
+        }
+    }));
+}
+
+pub fn emit(event: &str, payload: impl Serialize) {
+    if let Some(emitter) = EMITTER.get() {
+        match serde_json::to_value(payload) {
+            Ok(payload) => emitter(event, payload),
+            Err(e) => log::warn!("[ClipboardMonitor] Failed to serialize {}: {}", event, e),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! What the platform says about the clipboard without reading it.
+//!
+//! - A change counter (Windows' sequence number, the pasteboard's changeCount) lets
+//!   the monitor skip reading the clipboard until something was copied. Linux has none,
+//!   so the monitor reads and compares the content instead.
+//! - Password managers mark what they copy as not for clipboard monitors
+//!   (`ExcludeClipboardContentFromMonitorProcessing` on Windows,
+//!   `org.nspasteboard.ConcealedType` on macOS); those copies are never read.
+
+/// Counter that changes with every copy; None where the platform has none
+#[cfg(target_os = "windows")]
+pub fn change_count() -> Option<u64> {
+    use windows::Win32::System::DataExchange::GetClipboardSequenceNumber;
+    // 0 means the window station has no access to the clipboard
+    match unsafe { GetClipboardSequenceNumber() } {
+        0 => None,
+        count => Some(u64::from(count)),
+    }
+}
+
+/// Whether the clipboard owner asked monitors to leave its content alone
+#[cfg(target_os = "windows")]
+pub fn is_concealed() -> bool {
+    use windows::core::w;
+    use windows::Win32::System::DataExchange::{IsClipboardFormatAvailable, RegisterClipboardFormatW};
+    let format = unsafe { RegisterClipboardFormatW(w!("ExcludeClipboardContentFromMonitorProcessing")) };
+    format != 0 && unsafe { IsClipboardFormatAvailable(format) }.is_ok()
+}
+
+#[cfg(target_os = "macos")]
+pub fn change_count() -> Option<u64> {
+    let count = objc2_app_kit::NSPasteboard::generalPasteboard().changeCount();
+    Some(count as u64)
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_concealed() -> bool {
+    let Some(types) = objc2_app_kit::NSPasteboard::generalPasteboard().types() else {
+        return false;
+    };
+    types.iter().any(|kind| kind.to_string() == "org.nspasteboard.ConcealedType")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn change_count() -> Option<u64> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn is_concealed() -> bool {
+    false
+}
@@ -0,0 +1,55 @@
+//! Clipboard monitoring.
+//!
+//! Emits `clipboard-monitor://changed` when text or an image is copied, so agents can
+//! react to copied content ("when I copy a tracking number, look it up"). Monitoring is
+//! opt-in: nothing is read until `start_clipboard_monitor_cmd`, images need their own
+//! flag, and both are size-limited (`monitor`). Copies that password managers mark as
+//! concealed are skipped (`hints`).
+//!
+//! Reading goes through arboard on every desktop (X11 and Wayland included).
+
+use tauri::{
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    Runtime,
+};
+
+mod error;
+
+// The polling thread, size limits and change payloads
+pub mod monitor;
+
+// Change counters and concealed-content markers from the platform
+mod hints;
+
+// Events emitted to the frontend
+mod events;
+
+pub use error::{Error, Result};
+
+/// Initializes the clipboard monitor plugin
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    PluginBuilder::new("clipboard-monitor")
+        .invoke_handler(tauri::generate_handler![start_clipboard_monitor_cmd, stop_clipboard_monitor_cmd])
+        .setup(|app, _api| {
+            events::init(app);
+            Ok(())
+        })
+        .build()
+}
+
+/// Emit `clipboard-monitor://changed` for every copy, replacing a running monitor
+#[tauri::command]
+async fn start_clipboard_monitor_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    config: Option<monitor::ClipboardMonitorConfig>,
+) -> Result<()> {
+    tauri::async_runtime::spawn_blocking(move || monitor::start(config.unwrap_or_default()))
+        .await
+        .map_err(|e| Error::Unavailable(e.to_string()))?
+}
+
+/// Stop monitoring the clipboard; false if no monitor was running
+#[tauri::command]
+async fn stop_clipboard_monitor_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(monitor::stop())
+}
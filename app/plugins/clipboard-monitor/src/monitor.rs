@@ -0,0 +1,379 @@
+//! The clipboard monitor: a `clipboard-monitor://changed` event per copy.
+//!
+//! Nothing is watched until `start` is called, and images only with `images` on. A
+//! thread polls every `poll_ms`; where the platform has a change counter (see `hints`)
+//! the clipboard is only read after it moves, elsewhere each poll reads it and compares
+//! a digest with the last copy. What was on the clipboard when the monitor started is
+//! not reported, and neither is anything a password manager marks as concealed.
+//!
+//! Text is cut at `max_text_bytes` (on a character boundary) with `truncated` set.
+//! Images are downscaled to `max_image_width` and sent as PNG; ones bigger than
+//! `max_image_bytes` uncompressed are reported without data. One monitor runs at a time.
+//!
+//! The host can suspend monitoring (incognito): `start` is refused until it lifts the
+//! suspension. Stopping a monitor that is already running is left to the host.
+
+use crate::error::{Error, Result};
+use crate::{events, hints};
+use arboard::Clipboard;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbaImage};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How long `start` waits for the clipboard to open
+const OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_poll_ms() -> u64 {
+    500
+}
+
+fn default_max_text_bytes() -> usize {
+    16 * 1024
+}
+
+fn default_max_image_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+fn default_max_image_width() -> u32 {
+    1280
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardMonitorConfig {
+    /// Report copied text
+    #[serde(default = "default_true")]
+    pub text: bool,
+    /// Report copied images (off unless asked for)
+    #[serde(default)]
+    pub images: bool,
+    #[serde(default = "default_poll_ms")]
+    pub poll_ms: u64,
+    /// Longer text is cut to this many bytes
+    #[serde(default = "default_max_text_bytes")]
+    pub max_text_bytes: usize,
+    /// Images bigger than this uncompressed (RGBA) are reported without data
+    #[serde(default = "default_max_image_bytes")]
+    pub max_image_bytes: usize,
+    /// Wider images are downscaled before encoding
+    #[serde(default = "default_max_image_width")]
+    pub max_image_width: u32,
+}
+
+impl Default for ClipboardMonitorConfig {
+    fn default() -> Self {
+        Self {
+            text: true,
+            images: false,
+            poll_ms: default_poll_ms(),
+            max_text_bytes: default_max_text_bytes(),
+            max_image_bytes: default_max_image_bytes(),
+            max_image_width: default_max_image_width(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipboardKind {
+    Text,
+    Image,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardImage {
+    /// Of the encoded image, after downscaling
+    pub width: u32,
+    pub height: u32,
+    /// Base64 PNG; None when the copy was over `max_image_bytes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// Payload of `events::CHANGED`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardChange {
+    pub kind: ClipboardKind,
+    /// Seconds since the epoch
+    pub timestamp: f64,
+    /// Size of the copy: UTF-8 bytes of text, RGBA bytes of an image
+    pub bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The text was cut at `max_text_bytes`
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ClipboardImage>,
+}
+
+struct Monitor {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            // Wake it from its poll sleep
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+static MONITOR: Mutex<Option<Monitor>> = Mutex::new(None);
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// Refuse (or allow again) starting a monitor
+pub fn set_suspended(suspended: bool) {
+    SUSPENDED.store(suspended, Ordering::SeqCst);
+}
+
+/// Start monitoring, replacing a running monitor. Returns once the clipboard is open.
+pub fn start(config: ClipboardMonitorConfig) -> Result<()> {
+    if !config.text && !config.images {
+        return Err(Error::InvalidConfig("Enable text, images or both".to_string()));
+    }
+    if SUSPENDED.load(Ordering::SeqCst) {
+        return Err(Error::Suspended);
+    }
+    stop();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (opened, on_open) = mpsc::sync_channel::<Result<()>>(1);
+    let thread = {
+        let stop = stop.clone();
+        let config = config.clone();
+        std::thread::spawn(move || {
+            // The clipboard handle isn't Send everywhere; it stays on this thread
+            let mut clipboard = match Clipboard::new() {
+                Ok(clipboard) => {
+                    let _ = opened.send(Ok(()));
+                    clipboard
+                }
+                Err(e) => {
+                    let _ = opened.send(Err(Error::Unavailable(e.to_string())));
+                    return;
+                }
+            };
+            watch(&mut clipboard, &config, &stop);
+        })
+    };
+
+    match on_open.recv_timeout(OPEN_TIMEOUT) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            stop.store(true, Ordering::SeqCst);
+            return Err(Error::Unavailable("Timed out opening the clipboard".to_string()));
+        }
+    }
+    let monitor = Monitor { stop, thread: Some(thread) };
+    let mut slot = MONITOR.lock();
+    // Suspended while the clipboard was opening
+    if SUSPENDED.load(Ordering::SeqCst) {
+        drop(slot);
+        drop(monitor);
+        return Err(Error::Suspended);
+    }
+    log::info!("[ClipboardMonitor] Started (text: {}, images: {})", config.text, config.images);
+    *slot = Some(monitor);
+    Ok(())
+}
+
+/// Stop the running monitor; false if none was running
+pub fn stop() -> bool {
+    // Take it out first so the join in Drop doesn't hold the lock
+    let monitor = MONITOR.lock().take();
+    monitor.is_some()
+}
+
+/// Whether a monitor is running
+pub fn is_running() -> bool {
+    MONITOR.lock().is_some()
+}
+
+fn now() -> f64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+/// What was read from the clipboard
+enum Content {
+    Text(String),
+    Image { width: u32, height: u32, rgba: Vec<u8> },
+}
+
+impl Content {
+    fn digest(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            Content::Text(text) => text.hash(&mut hasher),
+            Content::Image { width, height, rgba } => (width, height, rgba).hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+fn read(clipboard: &mut Clipboard, config: &ClipboardMonitorConfig) -> Option<Content> {
+    if config.text {
+        if let Ok(text) = clipboard.get_text() {
+            if !text.is_empty() {
+                return Some(Content::Text(text));
+            }
+        }
+    }
+    if config.images {
+        if let Ok(image) = clipboard.get_image() {
+            return Some(Content::Image {
+                width: image.width as u32,
+                height: image.height as u32,
+                rgba: image.bytes.into_owned(),
+            });
+        }
+    }
+    None
+}
+
+fn watch(clipboard: &mut Clipboard, config: &ClipboardMonitorConfig, stop: &AtomicBool) {
+    let interval = Duration::from_millis(config.poll_ms.max(100));
+    // What's there at start is the baseline, not a copy
+    let mut last_count = hints::change_count();
+    let mut last_digest = read(clipboard, config).map(|content| content.digest());
+    while !stop.load(Ordering::SeqCst) {
+        std::thread::park_timeout(interval);
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let count = hints::change_count();
+        if count.is_some() && count == last_count {
+            continue;
+        }
+        last_count = count;
+        if hints::is_concealed() {
+            log::debug!("[ClipboardMonitor] Skipping a concealed copy");
+            last_digest = None;
+            continue;
+        }
+        let Some(content) = read(clipboard, config) else {
+            continue;
+        };
+        let digest = content.digest();
+        if last_digest == Some(digest) {
+            continue;
+        }
+        last_digest = Some(digest);
+        match change(content, config) {
+            Ok(change) => {
+                log::debug!("[ClipboardMonitor] {:?} copied ({} bytes)", change.kind, change.bytes);
+                events::emit(events::CHANGED, change);
+            }
+            Err(e) => log::warn!("[ClipboardMonitor] Failed to report a copy: {}", e),
+        }
+    }
+    log::info!("[ClipboardMonitor] Stopped");
+}
+
+fn change(content: Content, config: &ClipboardMonitorConfig) -> Result<ClipboardChange> {
+    let timestamp = now();
+    match content {
+        Content::Text(text) => {
+            let bytes = text.len();
+            let kept = truncate(&text, config.max_text_bytes);
+            let truncated = kept.len() < bytes;
+            Ok(ClipboardChange {
+                kind: ClipboardKind::Text,
+                timestamp,
+                bytes,
+                text: Some(kept.to_string()),
+                truncated,
+                image: None,
+            })
+        }
+        Content::Image { width, height, rgba } => {
+            let bytes = rgba.len();
+            let image = if bytes > config.max_image_bytes {
+                ClipboardImage { width, height, data: None }
+            } else {
+                encode_png(width, height, rgba, config.max_image_width)?
+            };
+            Ok(ClipboardChange {
+                kind: ClipboardKind::Image,
+                timestamp,
+                bytes,
+                text: None,
+                truncated: false,
+                image: Some(image),
+            })
+        }
+    }
+}
+
+/// `text` cut to at most `max_bytes`, on a character boundary
+fn truncate(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn encode_png(width: u32, height: u32, rgba: Vec<u8>, max_width: u32) -> Result<ClipboardImage> {
+    let image = RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| Error::Unavailable(format!("Bad {}x{} clipboard image", width, height)))?;
+    let mut image = DynamicImage::ImageRgba8(image);
+    if width > max_width.max(1) {
+        image = image.resize(max_width, u32::MAX, FilterType::Triangle);
+    }
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| Error::Unavailable(format!("Failed to encode the clipboard image: {}", e)))?;
+    Ok(ClipboardImage { width: image.width(), height: image.height(), data: Some(BASE64.encode(png)) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_text_on_a_character_boundary() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 3), "hel");
+        // "é" is two bytes; cutting inside it backs off to before it
+        assert_eq!(truncate("café", 4), "caf");
+        assert_eq!(truncate("café", 5), "café");
+    }
+
+    #[test]
+    fn reports_oversized_images_without_data_and_downscales_the_rest() {
+        let config = ClipboardMonitorConfig { max_image_bytes: 1000, max_image_width: 8, ..Default::default() };
+        let big = change(Content::Image { width: 20, height: 20, rgba: vec![0; 1600] }, &config).unwrap();
+        let image = big.image.unwrap();
+        assert!(image.data.is_none());
+        assert_eq!((image.width, image.height, big.bytes), (20, 20, 1600));
+
+        let small = change(Content::Image { width: 16, height: 4, rgba: vec![255; 256] }, &config).unwrap();
+        let image = small.image.unwrap();
+        assert!(image.data.is_some());
+        assert_eq!((image.width, image.height), (8, 2));
+    }
+}
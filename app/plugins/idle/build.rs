@@ -0,0 +1,5 @@
+const COMMANDS: &[&str] = &["get_idle_time_cmd", "start_idle_monitor_cmd", "stop_idle_monitor_cmd"];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
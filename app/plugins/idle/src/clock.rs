@@ -0,0 +1,32 @@
+//! The platform's idle clock: time since the last keyboard or mouse input, session-wide.
+
+use crate::error::Result;
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+pub use crate::win32::IdleClock;
+
+#[cfg(target_os = "linux")]
+pub use crate::linux::IdleClock;
+
+#[cfg(target_os = "macos")]
+pub use crate::quartz::IdleClock;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub struct IdleClock;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+impl IdleClock {
+    pub fn open() -> Result<Self> {
+        Err(crate::Error::NotSupported)
+    }
+
+    pub fn idle_time(&mut self) -> Result<Duration> {
+        Err(crate::Error::NotSupported)
+    }
+}
+
+/// Time since the last input, opening a clock for the one reading
+pub fn idle_time() -> Result<Duration> {
+    IdleClock::open()?.idle_time()
+}
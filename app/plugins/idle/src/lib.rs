@@ -0,0 +1,79 @@
+//! System idle detection.
+//!
+//! Reports how long it has been since the last keyboard or mouse input, session-wide,
+//! from each platform's own idle clock (`clock`):
+//!
+//! - Windows: GetLastInputInfo (`win32`)
+//! - macOS: the HID event source (`quartz`)
+//! - Linux: Mutter's IdleMonitor on GNOME, else the X11 screensaver extension (`linux`)
+//!
+//! A monitor (`monitor`) turns the clock into `idle://user-idle` and
+//! `idle://user-active` events at a threshold, so capture and agents can pause while
+//! nobody is at the machine. Unlike an input hook, none of this sees what was typed.
+
+use tauri::{
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    Runtime,
+};
+
+mod error;
+
+// Idle time from the platform
+pub mod clock;
+
+// Idle/active events at a threshold
+pub mod monitor;
+
+// Events emitted to the frontend
+mod events;
+
+// GetLastInputInfo (Windows)
+#[cfg(target_os = "windows")]
+mod win32;
+
+// Mutter IdleMonitor / MIT-SCREEN-SAVER (Linux)
+#[cfg(target_os = "linux")]
+mod linux;
+
+// CGEventSource (macOS)
+#[cfg(target_os = "macos")]
+mod quartz;
+
+pub use error::{Error, Result};
+
+/// Initializes the idle plugin
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    PluginBuilder::new("idle")
+        .invoke_handler(tauri::generate_handler![get_idle_time_cmd, start_idle_monitor_cmd, stop_idle_monitor_cmd])
+        .setup(|app, _api| {
+            events::init(app);
+            Ok(())
+        })
+        .build()
+}
+
+/// Seconds since the last input, and whether the running monitor considers the user idle
+#[tauri::command]
+async fn get_idle_time_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<monitor::IdleStatus> {
+    tauri::async_runtime::spawn_blocking(monitor::status)
+        .await
+        .map_err(|e| Error::Unavailable(e.to_string()))?
+}
+
+/// Emit `idle://user-idle` / `idle://user-active` at `config.thresholdSecs`, replacing a
+/// running monitor
+#[tauri::command]
+async fn start_idle_monitor_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    config: Option<monitor::IdleConfig>,
+) -> Result<()> {
+    tauri::async_runtime::spawn_blocking(move || monitor::start(config.unwrap_or_default(), None))
+        .await
+        .map_err(|e| Error::Unavailable(e.to_string()))?
+}
+
+/// Stop the idle monitor; false if none was running
+#[tauri::command]
+async fn stop_idle_monitor_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(monitor::stop())
+}
@@ -0,0 +1,69 @@
+//! Idle time on Linux: Mutter's IdleMonitor, else the X11 screensaver extension.
+//!
+//! GNOME exposes the session's idle time over D-Bus, which also covers Wayland
+//! sessions. Elsewhere the MIT-SCREEN-SAVER extension reports the X server's idle
+//! time; under XWayland on other Wayland compositors that only sees input to X
+//! clients, so it can report idle while the user types into native Wayland windows.
+
+use crate::error::{Error, Result};
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::screensaver;
+use x11rb::rust_connection::RustConnection;
+
+const MUTTER_NAME: &str = "org.gnome.Mutter.IdleMonitor";
+const MUTTER_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+
+pub enum IdleClock {
+    Mutter(zbus::blocking::Connection),
+    X11 { conn: Box<RustConnection>, root: u32 },
+}
+
+fn unavailable(what: &str, e: impl std::fmt::Display) -> Error {
+    Error::Unavailable(format!("{}: {}", what, e))
+}
+
+impl IdleClock {
+    /// The first backend that answers
+    pub fn open() -> Result<Self> {
+        match Self::open_mutter() {
+            Ok(clock) => return Ok(clock),
+            Err(e) => log::debug!("[Idle] Mutter idle monitor unavailable, trying X11: {}", e),
+        }
+        Self::open_x11()
+    }
+
+    fn open_mutter() -> Result<Self> {
+        let conn = zbus::blocking::Connection::session().map_err(|e| unavailable("D-Bus session bus", e))?;
+        let mut clock = Self::Mutter(conn);
+        clock.idle_time()?;
+        Ok(clock)
+    }
+
+    fn open_x11() -> Result<Self> {
+        let (conn, screen) = x11rb::connect(None).map_err(|e| unavailable("X11 connection", e))?;
+        let root = conn.setup().roots[screen].root;
+        let mut clock = Self::X11 { conn: Box::new(conn), root };
+        clock.idle_time()?;
+        Ok(clock)
+    }
+
+    pub fn idle_time(&mut self) -> Result<Duration> {
+        match self {
+            Self::Mutter(conn) => {
+                let reply = conn
+                    .call_method(Some(MUTTER_NAME), MUTTER_PATH, Some(MUTTER_NAME), "GetIdletime", &())
+                    .map_err(|e| unavailable("Mutter GetIdletime", e))?;
+                let ms: u64 = reply.body().deserialize().map_err(|e| unavailable("Mutter GetIdletime", e))?;
+                Ok(Duration::from_millis(ms))
+            }
+            Self::X11 { conn, root } => {
+                let info = screensaver::query_info(conn.as_ref(), *root)
+                    .map_err(|e| unavailable("MIT-SCREEN-SAVER", e))?
+                    .reply()
+                    .map_err(|e| unavailable("MIT-SCREEN-SAVER", e))?;
+                Ok(Duration::from_millis(u64::from(info.ms_since_user_input)))
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Idle monitoring: userIdle / userActive events at a threshold.
+//!
+//! A thread polls the idle clock every second. Once nobody has touched the keyboard or
+//! mouse for `threshold_secs`, it emits `idle://user-idle`; the first input after that
+//! emits `idle://user-active` with how long the user was away. Both are backdated to
+//! the input that ended or started the absence, not the poll that noticed it. A hook
+//! passed to `start` sees every event too, for Rust-side reactions such as pausing
+//! capture. One monitor runs at a time.
+
+use crate::clock::IdleClock;
+use crate::error::{Error, Result};
+use crate::events;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often the idle clock is read
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long `start` waits for the idle clock to open
+const OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn default_threshold_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleConfig {
+    /// Seconds without input before `userIdle`
+    #[serde(default = "default_threshold_secs")]
+    pub threshold_secs: u64,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self { threshold_secs: default_threshold_secs() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IdleEventKind {
+    UserIdle,
+    UserActive,
+}
+
+/// Payload of `events::USER_IDLE` and `events::USER_ACTIVE`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleEvent {
+    pub kind: IdleEventKind,
+    /// The last input before going idle, or the input that ended it; seconds since the epoch
+    pub timestamp: f64,
+    /// Seconds without input: so far on `userIdle`, in total on `userActive`
+    pub idle_secs: f64,
+}
+
+/// What `get_idle_time_cmd` reports
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleStatus {
+    /// Seconds since the last keyboard or mouse input
+    pub idle_secs: f64,
+    /// The running monitor's threshold; None without a monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold_secs: Option<u64>,
+    /// Whether the running monitor has reported the user idle
+    pub idle: bool,
+}
+
+/// Called with every event, on the monitor thread
+pub type IdleHook = Box<dyn Fn(&IdleEvent) + Send>;
+
+/// Turns idle-time readings into idle/active transitions
+struct IdleTracker {
+    threshold_secs: f64,
+    /// When the input before the current absence happened
+    idle_since: Option<f64>,
+}
+
+impl IdleTracker {
+    fn new(threshold_secs: u64) -> Self {
+        Self { threshold_secs: threshold_secs as f64, idle_since: None }
+    }
+
+    /// Feed a reading taken at `now` (seconds since the epoch)
+    fn update(&mut self, now: f64, idle_secs: f64) -> Option<IdleEvent> {
+        let last_input = now - idle_secs;
+        match self.idle_since {
+            None if idle_secs >= self.threshold_secs => {
+                self.idle_since = Some(last_input);
+                Some(IdleEvent { kind: IdleEventKind::UserIdle, timestamp: last_input, idle_secs })
+            }
+            Some(since) if idle_secs < self.threshold_secs => {
+                self.idle_since = None;
+                Some(IdleEvent {
+                    kind: IdleEventKind::UserActive,
+                    timestamp: last_input,
+                    idle_secs: (last_input - since).max(0.0),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Monitor {
+    threshold_secs: u64,
+    idle: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            // Wake it from its poll sleep
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+static MONITOR: Mutex<Option<Monitor>> = Mutex::new(None);
+
+/// Start monitoring, replacing a running monitor. Returns once the idle clock is open.
+pub fn start(config: IdleConfig, hook: Option<IdleHook>) -> Result<()> {
+    if config.threshold_secs == 0 {
+        return Err(Error::InvalidConfig("thresholdSecs must be at least 1".to_string()));
+    }
+    stop();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let idle = Arc::new(AtomicBool::new(false));
+    let (opened, on_open) = mpsc::sync_channel::<Result<()>>(1);
+    let thread = {
+        let stop = stop.clone();
+        let idle = idle.clone();
+        let threshold_secs = config.threshold_secs;
+        std::thread::spawn(move || {
+            let mut clock = match IdleClock::open() {
+                Ok(clock) => {
+                    let _ = opened.send(Ok(()));
+                    clock
+                }
+                Err(e) => {
+                    let _ = opened.send(Err(e));
+                    return;
+                }
+            };
+            watch(&mut clock, threshold_secs, hook.as_ref(), &idle, &stop);
+        })
+    };
+
+    match on_open.recv_timeout(OPEN_TIMEOUT) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            stop.store(true, Ordering::SeqCst);
+            return Err(Error::Unavailable("Timed out opening the idle clock".to_string()));
+        }
+    }
+    log::info!("[Idle] Monitor started ({} s threshold)", config.threshold_secs);
+    *MONITOR.lock() = Some(Monitor { threshold_secs: config.threshold_secs, idle, stop, thread: Some(thread) });
+    Ok(())
+}
+
+/// Stop the running monitor; false if none was running
+pub fn stop() -> bool {
+    // Take it out first so the join in Drop doesn't hold the lock
+    let monitor = MONITOR.lock().take();
+    monitor.is_some()
+}
+
+/// The current idle time, and the monitor's view of it if one is running
+pub fn status() -> Result<IdleStatus> {
+    let idle_secs = crate::clock::idle_time()?.as_secs_f64();
+    let monitor = MONITOR.lock();
+    Ok(IdleStatus {
+        idle_secs,
+        threshold_secs: monitor.as_ref().map(|monitor| monitor.threshold_secs),
+        idle: monitor.as_ref().is_some_and(|monitor| monitor.idle.load(Ordering::SeqCst)),
+    })
+}
+
+fn now() -> f64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+fn watch(clock: &mut IdleClock, threshold_secs: u64, hook: Option<&IdleHook>, idle: &AtomicBool, stop: &AtomicBool) {
+    let mut tracker = IdleTracker::new(threshold_secs);
+    while !stop.load(Ordering::SeqCst) {
+        match clock.idle_time() {
+            Ok(idle_time) => {
+                if let Some(event) = tracker.update(now(), idle_time.as_secs_f64()) {
+                    idle.store(event.kind == IdleEventKind::UserIdle, Ordering::SeqCst);
+                    publish(&event, hook);
+                }
+            }
+            // Keep polling; a D-Bus or X hiccup shouldn't end the monitor
+            Err(e) => log::debug!("[Idle] Failed to read the idle time: {}", e),
+        }
+        std::thread::park_timeout(POLL_INTERVAL);
+    }
+    // Don't leave listeners (or a paused capture) thinking nobody is there
+    if idle.swap(false, Ordering::SeqCst) {
+        let timestamp = now();
+        let idle_secs = tracker.idle_since.map(|since| (timestamp - since).max(0.0)).unwrap_or_default();
+        publish(&IdleEvent { kind: IdleEventKind::UserActive, timestamp, idle_secs }, hook);
+    }
+}
+
+fn publish(event: &IdleEvent, hook: Option<&IdleHook>) {
+    log::info!("[Idle] {:?} after {:.0} s without input", event.kind, event.idle_secs);
+    if let Some(hook) = hook {
+        hook(event);
+    }
+    let name = match event.kind {
+        IdleEventKind::UserIdle => events::USER_IDLE,
+        IdleEventKind::UserActive => events::USER_ACTIVE,
+    };
+    events::emit(name, event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_idle_once_then_active_with_the_time_away() {
+        let mut tracker = IdleTracker::new(60);
+        assert_eq!(tracker.update(100.0, 10.0), None);
+        let idle = tracker.update(150.0, 61.0).unwrap();
+        assert_eq!(idle.kind, IdleEventKind::UserIdle);
+        assert_eq!(idle.timestamp, 89.0);
+        assert_eq!(tracker.update(200.0, 111.0), None);
+        let active = tracker.update(201.0, 0.5).unwrap();
+        assert_eq!(active.kind, IdleEventKind::UserActive);
+        assert_eq!(active.timestamp, 200.5);
+        assert_eq!(active.idle_secs, 111.5);
+        assert_eq!(tracker.update(202.0, 1.5), None);
+    }
+}
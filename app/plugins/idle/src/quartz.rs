@@ -0,0 +1,24 @@
+//! Idle time from the HID event source (macOS).
+//!
+//! Seconds since the last keyboard, mouse or trackpad event system-wide. Reading it
+//! needs no Accessibility or Input Monitoring permission.
+
+use crate::error::Result;
+use objc2_core_graphics::{CGEventSource, CGEventSourceStateID, CGEventType};
+use std::time::Duration;
+
+/// kCGAnyInputEventType
+const ANY_INPUT: CGEventType = CGEventType(u32::MAX);
+
+pub struct IdleClock;
+
+impl IdleClock {
+    pub fn open() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn idle_time(&mut self) -> Result<Duration> {
+        let secs = CGEventSource::seconds_since_last_event_type(CGEventSourceStateID::HIDSystemState, ANY_INPUT);
+        Ok(Duration::from_secs_f64(secs.max(0.0)))
+    }
+}
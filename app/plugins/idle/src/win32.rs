@@ -0,0 +1,28 @@
+//! Idle time from GetLastInputInfo (Windows).
+//!
+//! The last input's tick count in this session, against the current one. The tick in
+//! LASTINPUTINFO is 32-bit and wraps every 49.7 days, so the difference is taken with
+//! wrapping 32-bit arithmetic.
+
+use crate::error::{Error, Result};
+use std::time::Duration;
+use windows::Win32::System::SystemInformation::GetTickCount64;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+pub struct IdleClock;
+
+impl IdleClock {
+    pub fn open() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn idle_time(&mut self) -> Result<Duration> {
+        let mut info = LASTINPUTINFO { cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+        // cbSize is set as the call requires
+        if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+            return Err(Error::Unavailable("GetLastInputInfo failed".to_string()));
+        }
+        let now = unsafe { GetTickCount64() } as u32;
+        Ok(Duration::from_millis(u64::from(now.wrapping_sub(info.dwTime))))
+    }
+}
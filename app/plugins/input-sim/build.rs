@@ -0,0 +1,12 @@
+const COMMANDS: &[&str] = &[
+    "send_keys_cmd",
+    "click_at_cmd",
+    "scroll_cmd",
+    "get_input_permissions_cmd",
+    "set_input_enabled_cmd",
+    "set_agent_input_permission_cmd",
+];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
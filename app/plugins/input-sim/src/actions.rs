@@ -0,0 +1,155 @@
+//! The actions themselves, on top of enigo.
+//!
+//! Each opens its own enigo connection on the calling thread (keep them off the async
+//! runtime) after checking the guard. Text is typed in short chunks with the guard checked
+//! between them, so the kill switch also cuts off a long string halfway. Keys held for a
+//! combo are always released, even when a later press fails.
+
+use crate::error::{Error, Result};
+use crate::{guard, keys};
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
+use serde::Deserialize;
+
+/// Characters typed between guard checks
+const TEXT_CHUNK: usize = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Right,
+    Middle,
+}
+
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => Button::Left,
+            MouseButton::Right => Button::Right,
+            MouseButton::Middle => Button::Middle,
+        }
+    }
+}
+
+fn open() -> Result<Enigo> {
+    Enigo::new(&Settings::default()).map_err(|e| Error::Input(e.to_string()))
+}
+
+fn input_err(e: enigo::InputError) -> Error {
+    Error::Input(e.to_string())
+}
+
+/// Type `text`, then press the `keys` combo (e.g. `["ctrl", "v"]`); either may be omitted
+pub fn send_keys(agent_id: &str, text: Option<&str>, keys: Option<&[String]>) -> Result<()> {
+    if text.is_none() && keys.is_none() {
+        return Err(Error::InvalidAction("Pass text, keys or both".to_string()));
+    }
+    guard::check(agent_id)?;
+    let combo = keys.map(keys::parse_combo).transpose()?;
+    let mut enigo = open()?;
+    if let Some(text) = text {
+        for chunk in chunks(text, TEXT_CHUNK) {
+            guard::check(agent_id)?;
+            enigo.text(chunk).map_err(input_err)?;
+        }
+        log::info!("[InputSim] Agent '{}' typed {} character(s)", agent_id, text.chars().count());
+    }
+    if let (Some(combo), Some(names)) = (combo, keys) {
+        guard::check(agent_id)?;
+        press_combo(&mut enigo, &combo)?;
+        log::info!("[InputSim] Agent '{}' pressed {}", agent_id, names.join("+"));
+    }
+    Ok(())
+}
+
+/// Hold every key but the last, click the last, then release the held ones in reverse
+fn press_combo(enigo: &mut Enigo, combo: &[Key]) -> Result<()> {
+    let Some((last, modifiers)) = combo.split_last() else {
+        return Ok(());
+    };
+    let mut held = Vec::new();
+    let mut result = Ok(());
+    for key in modifiers {
+        match enigo.key(*key, Direction::Press) {
+            Ok(()) => held.push(*key),
+            Err(e) => {
+                result = Err(input_err(e));
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        result = enigo.key(*last, Direction::Click).map_err(input_err);
+    }
+    for key in held.into_iter().rev() {
+        if let Err(e) = enigo.key(key, Direction::Release) {
+            log::warn!("[InputSim] Failed to release {:?}: {}", key, e);
+        }
+    }
+    result
+}
+
+/// Click `button` at screen point (`x`, `y`) in logical pixels, twice if `double`
+pub fn click_at(agent_id: &str, x: f64, y: f64, button: MouseButton, double: bool) -> Result<()> {
+    if !x.is_finite() || !y.is_finite() {
+        return Err(Error::InvalidAction(format!("Bad click point ({}, {})", x, y)));
+    }
+    guard::check(agent_id)?;
+    let mut enigo = open()?;
+    enigo.move_mouse(x.round() as i32, y.round() as i32, Coordinate::Abs).map_err(input_err)?;
+    for _ in 0..if double { 2 } else { 1 } {
+        enigo.button(button.into(), Direction::Click).map_err(input_err)?;
+    }
+    log::info!("[InputSim] Agent '{}' clicked {:?} at ({:.0}, {:.0})", agent_id, button, x, y);
+    Ok(())
+}
+
+/// Scroll by wheel notches: positive `dy` is down, positive `dx` right. Moves the pointer
+/// to `at` (screen point) first when given, since scrolling goes to what is under it.
+pub fn scroll(agent_id: &str, dx: i32, dy: i32, at: Option<(f64, f64)>) -> Result<()> {
+    if dx == 0 && dy == 0 {
+        return Err(Error::InvalidAction("Nothing to scroll".to_string()));
+    }
+    guard::check(agent_id)?;
+    let mut enigo = open()?;
+    if let Some((x, y)) = at {
+        enigo.move_mouse(x.round() as i32, y.round() as i32, Coordinate::Abs).map_err(input_err)?;
+    }
+    if dy != 0 {
+        enigo.scroll(dy, Axis::Vertical).map_err(input_err)?;
+    }
+    if dx != 0 {
+        enigo.scroll(dx, Axis::Horizontal).map_err(input_err)?;
+    }
+    log::info!("[InputSim] Agent '{}' scrolled ({}, {})", agent_id, dx, dy);
+    Ok(())
+}
+
+/// `text` split into pieces of at most `size` characters
+fn chunks(text: &str, size: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for (count, (index, _)) in text.char_indices().enumerate() {
+        if count > 0 && count % size == 0 {
+            pieces.push(&text[start..index]);
+            start = index;
+        }
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_text_on_character_boundaries() {
+        assert_eq!(chunks("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(chunks("héllo", 2), vec!["hé", "ll", "o"]);
+        assert!(chunks("", 3).is_empty());
+    }
+}
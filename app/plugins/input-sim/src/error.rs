@@ -0,0 +1,36 @@
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Input simulation is turned off")]
+    Disabled,
+
+    #[error("Input simulation is suspended (incognito)")]
+    Suspended,
+
+    #[error("Agent '{0}' is not allowed to send input")]
+    NotAllowed(String),
+
+    #[error("Invalid input action: {0}")]
+    InvalidAction(String),
+
+    #[error("Input failed: {0}")]
+    Input(String),
+
+    #[error("Failed to save input permissions: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,146 @@
+//! Who may send input, and the switch that stops everyone.
+//!
+//! Every action passes two checks: the global switch must be on, and the user must have
+//! allowed the agent asking. The switch starts off at every launch and turning it off is
+//! the kill switch; it applies from the next keystroke on, including the rest of text
+//! being typed. Agent grants persist in `<app_data_dir>/input-permissions.json`, the
+//! switch does not. The host can also suspend input (incognito) without touching either.
+
+use crate::error::{Error, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Grants file name in the app data dir
+const FILE_NAME: &str = "input-permissions.json";
+
+/// What `get_input_permissions_cmd` reports
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputPermissions {
+    /// The global switch
+    pub enabled: bool,
+    /// Held off by the host, e.g. while incognito is on
+    pub suspended: bool,
+    /// Agents the user has allowed to send input
+    pub agents: BTreeSet<String>,
+}
+
+impl InputPermissions {
+    fn check(&self, agent_id: &str) -> Result<()> {
+        if !self.enabled {
+            return Err(Error::Disabled);
+        }
+        if self.suspended {
+            return Err(Error::Suspended);
+        }
+        if !self.agents.contains(agent_id) {
+            return Err(Error::NotAllowed(agent_id.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// On-disk form of the grants
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GrantsFile {
+    #[serde(default)]
+    agents: BTreeSet<String>,
+}
+
+static STATE: Mutex<InputPermissions> =
+    Mutex::new(InputPermissions { enabled: false, suspended: false, agents: BTreeSet::new() });
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Load the saved grants from `dir`; called at plugin setup
+pub fn load(dir: &Path) {
+    let path = dir.join(FILE_NAME);
+    let grants = match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str::<GrantsFile>(&content).unwrap_or_else(|e| {
+            log::warn!("[InputSim] Ignoring unreadable {:?}: {}", path, e);
+            GrantsFile::default()
+        }),
+        Err(_) => GrantsFile::default(),
+    };
+    log::info!("[InputSim] {} agent(s) allowed to send input", grants.agents.len());
+    STATE.lock().agents = grants.agents;
+    let _ = PATH.set(path);
+}
+
+fn save(agents: &BTreeSet<String>) -> Result<()> {
+    let Some(path) = PATH.get() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(&GrantsFile { agents: agents.clone() })
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// Ok if `agent_id` may send input right now
+pub fn check(agent_id: &str) -> Result<()> {
+    STATE.lock().check(agent_id)
+}
+
+pub fn permissions() -> InputPermissions {
+    STATE.lock().clone()
+}
+
+/// Turn the global switch on or off
+pub fn set_enabled(enabled: bool) {
+    let was = std::mem::replace(&mut STATE.lock().enabled, enabled);
+    if was != enabled {
+        log::info!("[InputSim] Input simulation {}", if enabled { "enabled" } else { "disabled" });
+    }
+}
+
+/// Hold all input off (or release the hold) without changing the switch or grants
+pub fn set_suspended(suspended: bool) {
+    STATE.lock().suspended = suspended;
+}
+
+/// Allow or revoke `agent_id`, saving the grants
+pub fn set_agent_allowed(agent_id: &str, allowed: bool) -> Result<()> {
+    if agent_id.trim().is_empty() {
+        return Err(Error::InvalidAction("agentId is empty".to_string()));
+    }
+    let agents = {
+        let mut state = STATE.lock();
+        let changed = if allowed {
+            state.agents.insert(agent_id.to_string())
+        } else {
+            state.agents.remove(agent_id)
+        };
+        if !changed {
+            return Ok(());
+        }
+        state.agents.clone()
+    };
+    let verb = if allowed { "allowed" } else { "no longer allowed" };
+    log::info!("[InputSim] Agent '{}' {} to send input", agent_id, verb);
+    save(&agents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_the_switch_no_suspension_and_a_grant() {
+        let mut permissions = InputPermissions::default();
+        permissions.agents.insert("clicker".to_string());
+        assert!(matches!(permissions.check("clicker"), Err(Error::Disabled)));
+
+        permissions.enabled = true;
+        assert!(permissions.check("clicker").is_ok());
+        assert!(matches!(permissions.check("other"), Err(Error::NotAllowed(id)) if id == "other"));
+
+        permissions.suspended = true;
+        assert!(matches!(permissions.check("clicker"), Err(Error::Suspended)));
+    }
+}
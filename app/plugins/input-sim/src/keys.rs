@@ -0,0 +1,85 @@
+//! Key names agents use in combos, e.g. `["ctrl", "shift", "t"]`.
+//!
+//! Names are case-insensitive. `cmdorctrl` is Command on macOS and Control elsewhere, so
+//! one combo works on every desktop; any other single character is typed as itself.
+
+use crate::error::{Error, Result};
+use enigo::Key;
+
+/// The enigo key for `name`
+pub fn parse(name: &str) -> Result<Key> {
+    let lower = name.trim().to_lowercase();
+    let key = match lower.as_str() {
+        "ctrl" | "control" => Key::Control,
+        "shift" => Key::Shift,
+        "alt" | "option" => Key::Alt,
+        "meta" | "cmd" | "command" | "super" | "win" => Key::Meta,
+        #[cfg(target_os = "macos")]
+        "cmdorctrl" => Key::Meta,
+        #[cfg(not(target_os = "macos"))]
+        "cmdorctrl" => Key::Control,
+        "enter" | "return" => Key::Return,
+        "tab" => Key::Tab,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "escape" | "esc" => Key::Escape,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "capslock" => Key::CapsLock,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => {
+            let mut chars = lower.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Unicode(c),
+                _ => return Err(Error::InvalidAction(format!("Unknown key '{}'", name))),
+            }
+        }
+    };
+    Ok(key)
+}
+
+/// Parse a whole combo; modifiers first, the key last
+pub fn parse_combo(names: &[String]) -> Result<Vec<Key>> {
+    if names.is_empty() {
+        return Err(Error::InvalidAction("Empty key combo".to_string()));
+    }
+    names.iter().map(|name| parse(name)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_keys_and_single_characters() {
+        assert_eq!(parse("Ctrl").unwrap(), Key::Control);
+        assert_eq!(parse("esc").unwrap(), Key::Escape);
+        assert_eq!(parse("F5").unwrap(), Key::F5);
+        assert_eq!(parse("T").unwrap(), Key::Unicode('t'));
+        assert_eq!(parse("/").unwrap(), Key::Unicode('/'));
+        assert!(parse("hyper").is_err());
+        assert!(parse("").is_err());
+
+        let combo = parse_combo(&["shift".to_string(), "tab".to_string()]).unwrap();
+        assert_eq!(combo, vec![Key::Shift, Key::Tab]);
+        assert!(parse_combo(&[]).is_err());
+    }
+}
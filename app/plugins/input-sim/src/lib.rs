@@ -0,0 +1,115 @@
+//! Input simulation for agent actions.
+//!
+//! Lets agents act on what they see: type text and key combos (`send_keys_cmd`), click at
+//! a screen point (`click_at_cmd`) and scroll (`scroll_cmd`), through enigo. Every action
+//! names the agent asking for it and must pass `guard`: the global switch has to be on
+//! and the user has to have allowed that agent. Points are screen coordinates; map frame
+//! points through the screen-capture geometry first.
+//!
+//! The app only registers this plugin when built with its `input-sim` feature.
+
+use tauri::{
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    Manager, Runtime,
+};
+
+mod error;
+
+// The global switch and per-agent grants
+pub mod guard;
+
+// Key names for combos
+mod keys;
+
+// Typing, clicking and scrolling through enigo
+pub mod actions;
+
+pub use error::{Error, Result};
+
+/// Initializes the input simulation plugin
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    PluginBuilder::new("input-sim")
+        .invoke_handler(tauri::generate_handler![
+            send_keys_cmd,
+            click_at_cmd,
+            scroll_cmd,
+            get_input_permissions_cmd,
+            set_input_enabled_cmd,
+            set_agent_input_permission_cmd
+        ])
+        .setup(|app, _api| {
+            match app.path().app_data_dir() {
+                Ok(dir) => guard::load(&dir),
+                Err(e) => log::warn!("[InputSim] No app data dir; agent grants won't be saved: {}", e),
+            }
+            Ok(())
+        })
+        .build()
+}
+
+async fn blocking<T: Send + 'static>(action: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tauri::async_runtime::spawn_blocking(action)
+        .await
+        .map_err(|e| Error::Input(e.to_string()))?
+}
+
+/// Type `text` and/or press the `keys` combo on behalf of `agent_id`
+#[tauri::command]
+async fn send_keys_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    agent_id: String,
+    text: Option<String>,
+    keys: Option<Vec<String>>,
+) -> Result<()> {
+    blocking(move || actions::send_keys(&agent_id, text.as_deref(), keys.as_deref())).await
+}
+
+/// Click at screen point (`x`, `y`) on behalf of `agent_id`
+#[tauri::command]
+async fn click_at_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    agent_id: String,
+    x: f64,
+    y: f64,
+    button: Option<actions::MouseButton>,
+    double: Option<bool>,
+) -> Result<()> {
+    blocking(move || actions::click_at(&agent_id, x, y, button.unwrap_or_default(), double.unwrap_or(false))).await
+}
+
+/// Scroll by `dx`/`dy` wheel notches on behalf of `agent_id`, at (`x`, `y`) if given
+#[tauri::command]
+async fn scroll_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    agent_id: String,
+    dx: Option<i32>,
+    dy: Option<i32>,
+    x: Option<f64>,
+    y: Option<f64>,
+) -> Result<()> {
+    let at = x.zip(y);
+    blocking(move || actions::scroll(&agent_id, dx.unwrap_or(0), dy.unwrap_or(0), at)).await
+}
+
+/// The global switch, suspension and allowed agents
+#[tauri::command]
+async fn get_input_permissions_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<guard::InputPermissions> {
+    Ok(guard::permissions())
+}
+
+/// Turn input simulation on or off for every agent (off is the kill switch)
+#[tauri::command]
+async fn set_input_enabled_cmd<R: Runtime>(_app: tauri::AppHandle<R>, enabled: bool) -> Result<()> {
+    guard::set_enabled(enabled);
+    Ok(())
+}
+
+/// Allow or revoke input for one agent; saved across restarts
+#[tauri::command]
+async fn set_agent_input_permission_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    agent_id: String,
+    allowed: bool,
+) -> Result<()> {
+    guard::set_agent_allowed(&agent_id, allowed)
+}
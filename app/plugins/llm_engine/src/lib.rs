@@ -42,7 +42,7 @@ macro_rules! llm_log {
 use llama_cpp_2::context::params::LlamaContextParams;
 use llama_cpp_2::llama_backend::LlamaBackend;
 use llama_cpp_2::llama_batch::LlamaBatch;
-use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::params::{LlamaModelParams, LlamaSplitMode};
 use llama_cpp_2::model::{AddBos, LlamaModel};
 use llama_cpp_2::openai::OpenAIChatTemplateParams;
 use llama_cpp_2::sampling::LlamaSampler;
@@ -126,6 +126,66 @@ pub struct NativeModelInfo {
     pub is_multimodal: bool,  // Whether model supports vision/multimodal
 }
 
+/// A compute device llama.cpp can run on
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendDevice {
+    pub name: String,
+    pub description: String,
+    /// "cpu", "gpu" or "other" (accelerators such as BLAS)
+    pub kind: String,
+    /// Index to pass to `set_main_gpu` (GPU devices only)
+    pub gpu_index: Option<u32>,
+    pub memory_total_bytes: u64,
+    pub memory_free_bytes: u64,
+}
+
+/// List the devices ggml has registered, with their current memory use
+pub fn list_devices() -> Vec<BackendDevice> {
+    use llama_cpp_sys_2 as sys;
+    use std::ffi::CStr;
+
+    let text = |ptr: *const std::os::raw::c_char| {
+        if ptr.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+        }
+    };
+
+    let mut devices = Vec::new();
+    let mut next_gpu = 0u32;
+    unsafe {
+        for i in 0..sys::ggml_backend_dev_count() {
+            let dev = sys::ggml_backend_dev_get(i);
+            if dev.is_null() {
+                continue;
+            }
+            let mut free = 0usize;
+            let mut total = 0usize;
+            sys::ggml_backend_dev_memory(dev, &mut free, &mut total);
+
+            let (kind, gpu_index) = match sys::ggml_backend_dev_type(dev) {
+                sys::ggml_backend_dev_type_GGML_BACKEND_DEVICE_TYPE_CPU => ("cpu", None),
+                sys::ggml_backend_dev_type_GGML_BACKEND_DEVICE_TYPE_GPU => {
+                    next_gpu += 1;
+                    ("gpu", Some(next_gpu - 1))
+                }
+                _ => ("other", None),
+            };
+            devices.push(BackendDevice {
+                name: text(sys::ggml_backend_dev_name(dev)),
+                description: text(sys::ggml_backend_dev_description(dev)),
+                kind: kind.to_string(),
+                gpu_index,
+                memory_total_bytes: total as u64,
+                memory_free_bytes: free as u64,
+            });
+        }
+    }
+    devices
+}
+
 /// Content part for multimodal messages
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(tag = "type")]
@@ -163,6 +223,7 @@ pub struct LlmEngine {
     context_params: ContextParams, // Configurable context/inference parameters
     last_metrics: Option<GenerationMetrics>, // Metrics from last generation
     use_gpu: bool, // Whether to use GPU acceleration (Metal). Default false for compatibility.
+    main_gpu: Option<u32>, // Pin the model to one GPU (index from list_devices); None = split across GPUs
 }
 
 impl LlmEngine {
@@ -183,6 +244,7 @@ impl LlmEngine {
             context_params: ContextParams::default(),
             last_metrics: None,
             use_gpu: false, // Default to CPU for maximum compatibility
+            main_gpu: None,
         })
     }
 
@@ -205,8 +267,15 @@ impl LlmEngine {
         };
         llm_log!("GPU mode: {}, n_gpu_layers: {}", self.use_gpu, n_gpu_layers);
 
-        let model_params = LlamaModelParams::default()
+        let mut model_params = LlamaModelParams::default()
             .with_n_gpu_layers(n_gpu_layers as u32);
+        if let Some(gpu) = self.main_gpu {
+            // Keep every offloaded layer on the pinned device instead of splitting
+            llm_log!("Pinning model to GPU {}", gpu);
+            model_params = model_params
+                .with_split_mode(LlamaSplitMode::None)
+                .with_main_gpu(gpu as i32);
+        }
 
         let model = LlamaModel::load_from_file(&self.backend, &model_path, &model_params)
             .map_err(|e| format!("Failed to load model: {}", e))?;
@@ -316,6 +385,18 @@ impl LlmEngine {
         llm_log!("GPU mode set to: {}", use_gpu);
     }
 
+    /// Get the GPU the model is pinned to, if any
+    pub fn get_main_gpu(&self) -> Option<u32> {
+        self.main_gpu
+    }
+
+    /// Pin the model to one GPU (None splits layers across all GPUs)
+    /// Must be called before load_model to take effect
+    pub fn set_main_gpu(&mut self, main_gpu: Option<u32>) {
+        self.main_gpu = main_gpu;
+        llm_log!("Main GPU set to: {:?}", main_gpu);
+    }
+
     /// Get the last generation metrics
     pub fn get_last_metrics(&self) -> Option<&GenerationMetrics> {
         self.last_metrics.as_ref()
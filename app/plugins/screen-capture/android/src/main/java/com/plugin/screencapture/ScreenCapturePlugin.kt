@@ -65,6 +65,61 @@ class ScreenCapturePlugin(private val activity: Activity) : Plugin(activity) {
         }
     }
 
+    // ==================== Permission ====================
+
+    private fun hasProjectionConsent(): Boolean =
+        isDataReady && mediaProjectionResultCode == Activity.RESULT_OK && mediaProjectionData != null
+
+    private fun permissionStatus(status: String): JSObject = JSObject().put("status", status)
+
+    @Command
+    fun checkPermission(invoke: Invoke) {
+        // MediaProjection consent isn't persistent: it's asked for every capture and
+        // kept until capture stops
+        invoke.resolve(permissionStatus(if (hasProjectionConsent()) "granted" else "prompt"))
+    }
+
+    @Command
+    fun requestPermission(invoke: Invoke) {
+        Log.d(TAG, "requestPermission called")
+
+        if (hasProjectionConsent()) {
+            invoke.resolve(permissionStatus("granted"))
+            return
+        }
+
+        activity.runOnUiThread {
+            try {
+                val mediaProjectionManager = activity.getSystemService(
+                    Context.MEDIA_PROJECTION_SERVICE
+                ) as MediaProjectionManager
+
+                val captureIntent = mediaProjectionManager.createScreenCaptureIntent()
+                startActivityForResult(invoke, captureIntent, "onPermissionResult")
+                Log.d(TAG, "MediaProjection permission dialog shown")
+            } catch (e: Exception) {
+                Log.e(TAG, "Error requesting permission: ${e.message}", e)
+                invoke.reject("Failed to request permission: ${e.message}")
+            }
+        }
+    }
+
+    @ActivityCallback
+    private fun onPermissionResult(invoke: Invoke, result: ActivityResult) {
+        Log.d(TAG, "Permission result: resultCode=${result.resultCode}")
+
+        if (result.resultCode == Activity.RESULT_OK && result.data != null) {
+            // Keep the consent for the next startVideoStream / startAudioStream
+            mediaProjectionResultCode = result.resultCode
+            mediaProjectionData = result.data
+            isDataReady = true
+            invoke.resolve(permissionStatus("granted"))
+        } else {
+            Log.w(TAG, "MediaProjection permission denied or cancelled")
+            invoke.resolve(permissionStatus("denied"))
+        }
+    }
+
     @Command
     fun stopCapture(invoke: Invoke) {
         Log.d(TAG, "stopCapture called")
@@ -0,0 +1,98 @@
+//! JPEG encoder throughput at common capture sizes.
+//!
+//!     cargo bench --bench jpeg_encode                       # image + jpeg-encoder
+//!     cargo bench --bench jpeg_encode --features turbojpeg  # + libjpeg-turbo
+//!
+//! Frames are synthetic but screen-like (flat panels, text-like strokes, a gradient),
+//! since photographic noise would flatter the slower encoders. Each encoder gets the
+//! pixel layout it would see in the capture loop: `image` needs RGB, the others take
+//! RGBA directly.
+
+use image::codecs::jpeg::JpegEncoder;
+use std::time::{Duration, Instant};
+
+const QUALITY: u8 = 55;
+const ITERATIONS: u32 = 30;
+const WIDTHS: [u32; 4] = [1280, 1920, 2560, 3840];
+
+fn synthetic_frame(width: u32, height: u32) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = if y < height / 20 {
+                // Title bar
+                [40, 44, 52, 255]
+            } else if (y / 14) % 2 == 0 && (x / 7 + y / 3) % 5 < 3 && x % 600 < 520 {
+                // Rows of "glyphs"
+                [20, 20, 20, 255]
+            } else if x > width * 3 / 4 {
+                // Gradient side panel
+                [(x % 256) as u8, (y % 256) as u8, 180, 255]
+            } else {
+                [250, 250, 250, 255]
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    rgba
+}
+
+fn rgba_to_rgb(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect()
+}
+
+fn time(mut encode: impl FnMut() -> usize) -> (Duration, usize) {
+    let size = encode(); // warm-up
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(encode());
+    }
+    (start.elapsed() / ITERATIONS, size)
+}
+
+fn report(name: &str, (per_frame, size): (Duration, usize), baseline: Duration) {
+    println!(
+        "  {:<14} {:>7.2} ms/frame  {:>6.1}x  {:>7} KiB",
+        name,
+        per_frame.as_secs_f64() * 1000.0,
+        baseline.as_secs_f64() / per_frame.as_secs_f64(),
+        size / 1024
+    );
+}
+
+fn main() {
+    for width in WIDTHS {
+        let height = width * 9 / 16;
+        let rgba = synthetic_frame(width, height);
+        println!("{}x{} @ quality {}", width, height, QUALITY);
+
+        let image_rs = time(|| {
+            let rgb = rgba_to_rgb(&rgba);
+            let mut out = Vec::new();
+            JpegEncoder::new_with_quality(&mut out, QUALITY)
+                .encode(&rgb, width, height, image::ExtendedColorType::Rgb8)
+                .unwrap();
+            out.len()
+        });
+        report("image", image_rs, image_rs.0);
+
+        let simd = time(|| {
+            let mut out = Vec::new();
+            jpeg_encoder::Encoder::new(&mut out, QUALITY)
+                .encode(&rgba, width as u16, height as u16, jpeg_encoder::ColorType::Rgba)
+                .unwrap();
+            out.len()
+        });
+        report("jpeg-encoder", simd, image_rs.0);
+
+        if tauri_plugin_screen_capture::encode::encode_jpeg_turbo(&rgba, width, height, QUALITY).is_some() {
+            let turbo = time(|| {
+                tauri_plugin_screen_capture::encode::encode_jpeg_turbo(&rgba, width, height, QUALITY)
+                    .map_or(0, |out| out.len())
+            });
+            report("libjpeg-turbo", turbo, image_rs.0);
+        } else {
+            println!("  libjpeg-turbo  (not built; pass --features turbojpeg)");
+        }
+    }
+}
@@ -3,12 +3,54 @@ const COMMANDS: &[&str] = &[
     "stop_capture_cmd",
     "stop_video_cmd",
     "stop_audio_cmd",
+    "check_capture_permission_cmd",
+    "request_capture_permission_cmd",
     "get_frame_cmd",
     "get_broadcast_status",
     "get_capture_targets_cmd",
+    "start_target_preview_cmd",
+    "stop_target_preview_cmd",
     "start_capture_stream_cmd",
     "start_video_stream_cmd",
     "start_audio_stream_cmd",
+    "set_capture_config_cmd",
+    "set_excluded_windows_cmd",
+    "set_exclude_own_windows_cmd",
+    "get_capture_config_cmd",
+    "list_capture_sessions_cmd",
+    "ack_frames_cmd",
+    "get_capture_stats_cmd",
+    "list_capture_profiles_cmd",
+    "save_capture_profile_cmd",
+    "delete_capture_profile_cmd",
+    "start_capture_profile_cmd",
+    "add_watch_rule_cmd",
+    "remove_watch_rule_cmd",
+    "list_watch_rules_cmd",
+    "get_active_window_cmd",
+    "start_active_window_stream_cmd",
+    "stop_active_window_stream_cmd",
+    "set_frame_overlay_cmd",
+    "set_privacy_regions_cmd",
+    "list_privacy_regions_cmd",
+    "set_redacted_apps_cmd",
+    "get_redacted_apps_cmd",
+    "capture_screenshot_cmd",
+    "compare_frames_cmd",
+    "detect_codes_cmd",
+    "get_frame_stats_cmd",
+    "ocr_frame_cmd",
+    "start_text_watch_cmd",
+    "stop_text_watch_cmd",
+    "list_text_watches_cmd",
+    "start_snapshots_cmd",
+    "stop_snapshots_cmd",
+    "get_snapshot_status_cmd",
+    "switch_capture_target_cmd",
+    "start_recording_cmd",
+    "stop_recording_cmd",
+    "export_replay_cmd",
+    "export_gif_cmd",
     // Android channel-based streaming commands
     "stop_video_stream_cmd",
     "stop_audio_stream_cmd",
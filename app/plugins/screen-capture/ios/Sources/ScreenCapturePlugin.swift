@@ -65,6 +65,16 @@ import Tauri
         }
     }
 
+    // ReplayKit has no permission to check or grant ahead of time: the broadcast picker
+    // asks every time a broadcast starts
+    @objc public func checkPermission(_ invoke: Invoke) throws {
+        invoke.resolve(["status": "prompt"])
+    }
+
+    @objc public func requestPermission(_ invoke: Invoke) throws {
+        invoke.resolve(["status": "prompt"])
+    }
+
     @objc public func startCapture(_ invoke: Invoke) throws {
         DispatchQueue.main.async { [weak self] in
             guard let self = self else { return }
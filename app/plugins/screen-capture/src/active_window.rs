@@ -0,0 +1,276 @@
+//! Active-window tracking without frame capture.
+//!
+//! Many agents only need "which app and title is in front right now". A stream sends
+//! an `ActiveWindowChange` to its channel whenever focus moves to another window, the
+//! focused window's title changes, or nothing has focus anymore, with how long the
+//! previous window was in front. The first message on a new stream is the current
+//! state, so consumers don't wait for the next change.
+//!
+//! One watcher thread reads the window list every `POLL_INTERVAL` while any stream is
+//! open and exits with the last one; a stream whose channel closes is dropped. Nothing
+//! is captured, so it keeps running while other pause reasons are held, but titles are
+//! often private: nothing is read or sent while incognito mode is on, and the host closes
+//! the open streams when it turns on. Not available on Wayland, where clients can't see
+//! other apps' windows.
+
+use crate::error::{Error, Result};
+use crate::pause::{self, PauseReason};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use xcap::Window;
+
+/// How often the focused window is read
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The window in front
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveWindow {
+    /// `window:<id>` targets capture it
+    pub window_id: u32,
+    pub app_name: String,
+    pub title: String,
+    pub pid: u32,
+    /// When it came to the front, seconds since the epoch
+    pub active_since: f64,
+}
+
+/// Sent on a stream's channel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveWindowChange {
+    /// None while nothing has focus (desktop, lock screen)
+    pub window: Option<ActiveWindow>,
+    /// Same window as before with a new title (a tab or document switch)
+    pub title_changed: bool,
+    /// Seconds since the epoch
+    pub timestamp: f64,
+    /// How long the previous window (or title) was in front; None on a stream's first message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_secs: Option<f64>,
+}
+
+/// Focused-window facts the tracker compares
+#[derive(Debug, Clone, PartialEq)]
+struct Focused {
+    id: u32,
+    app_name: String,
+    title: String,
+    pid: u32,
+}
+
+/// Turns focused-window readings into changes
+#[derive(Default)]
+struct Tracker {
+    current: Option<ActiveWindow>,
+    /// When the current window or title came to the front
+    title_since: f64,
+}
+
+impl Tracker {
+    fn update(&mut self, now: f64, focused: Option<Focused>) -> Option<ActiveWindowChange> {
+        let same_window = match (&self.current, &focused) {
+            (Some(current), Some(focused)) => current.window_id == focused.id,
+            (None, None) => return None,
+            _ => false,
+        };
+        if same_window && self.current.as_ref().map(|c| &c.title) == focused.as_ref().map(|f| &f.title) {
+            return None;
+        }
+        let previous_secs = Some((now - self.title_since).max(0.0));
+        let active_since = match &self.current {
+            Some(current) if same_window => current.active_since,
+            _ => now,
+        };
+        self.current = focused.map(|f| ActiveWindow {
+            window_id: f.id,
+            app_name: f.app_name,
+            title: f.title,
+            pid: f.pid,
+            active_since,
+        });
+        self.title_since = now;
+        Some(ActiveWindowChange {
+            window: self.current.clone(),
+            title_changed: same_window,
+            timestamp: now,
+            previous_secs,
+        })
+    }
+}
+
+struct Stream {
+    id: String,
+    channel: Channel<ActiveWindowChange>,
+}
+
+static STREAMS: Mutex<Vec<Stream>> = Mutex::new(Vec::new());
+static TRACKER: Mutex<Option<Tracker>> = Mutex::new(None);
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+fn now() -> f64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+fn check_supported() -> Result<()> {
+    #[cfg(target_os = "linux")]
+    if crate::desktop_wayland::is_wayland() {
+        return Err(Error::Platform("Active-window tracking is not available on Wayland".to_string()));
+    }
+    if pause::is_set(PauseReason::Incognito) {
+        return Err(Error::Platform("Active-window tracking is disabled while incognito mode is on".to_string()));
+    }
+    Ok(())
+}
+
+fn focused() -> Result<Option<Focused>> {
+    let windows = Window::all().map_err(|e| Error::Platform(format!("Failed to enumerate windows: {}", e)))?;
+    Ok(windows.iter().find(|w| w.is_focused().unwrap_or(false)).and_then(|w| {
+        Some(Focused {
+            id: w.id().ok()?,
+            app_name: w.app_name().unwrap_or_default(),
+            title: w.title().unwrap_or_default(),
+            pid: w.pid().unwrap_or(0),
+        })
+    }))
+}
+
+/// The window in front right now, if any
+pub fn current() -> Result<Option<ActiveWindow>> {
+    check_supported()?;
+    // A running watcher knows how long it has been in front
+    if let Some(window) = TRACKER.lock().as_ref().and_then(|tracker| tracker.current.clone()) {
+        return Ok(Some(window));
+    }
+    Ok(focused()?.map(|f| ActiveWindow {
+        window_id: f.id,
+        app_name: f.app_name,
+        title: f.title,
+        pid: f.pid,
+        active_since: now(),
+    }))
+}
+
+/// Open a stream into `channel`; returns its id for `stop`
+pub fn start(channel: Channel<ActiveWindowChange>) -> Result<String> {
+    check_supported()?;
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let id = format!("active-window-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+
+    let window = {
+        let mut tracker = TRACKER.lock();
+        if tracker.is_none() {
+            // Prime it so the watcher's first poll doesn't repeat this stream's first message
+            let mut primed = Tracker::default();
+            primed.update(now(), focused()?);
+            *tracker = Some(primed);
+        }
+        tracker.as_ref().and_then(|tracker| tracker.current.clone())
+    };
+    let first = ActiveWindowChange { window, title_changed: false, timestamp: now(), previous_secs: None };
+    channel.send(first).map_err(|e| Error::Platform(format!("Active-window channel closed: {}", e)))?;
+
+    STREAMS.lock().push(Stream { id: id.clone(), channel });
+    log::info!("[ScreenCapture] Active-window stream {} started", id);
+    ensure_watcher();
+    Ok(id)
+}
+
+/// Close stream `id`; false if there was none
+pub fn stop(id: &str) -> bool {
+    let mut streams = STREAMS.lock();
+    let before = streams.len();
+    streams.retain(|stream| stream.id != id);
+    before != streams.len()
+}
+
+/// Close every stream; the watcher exits on its next poll
+pub fn stop_all() {
+    let closed = std::mem::take(&mut *STREAMS.lock()).len();
+    if closed > 0 {
+        log::info!("[ScreenCapture] Closed {} active-window stream(s)", closed);
+    }
+}
+
+/// Start the watcher thread unless it is running; it exits once no streams are left
+fn ensure_watcher() {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        log::info!("[ScreenCapture] Active-window watcher started");
+        loop {
+            if STREAMS.lock().is_empty() {
+                *TRACKER.lock() = None;
+                WATCHING.store(false, Ordering::SeqCst);
+                // A stream opened between the check and the store would have found the
+                // flag still set; pick it up instead of leaving it unserved
+                if STREAMS.lock().is_empty() || WATCHING.swap(true, Ordering::SeqCst) {
+                    break;
+                }
+            }
+            // A stream may outlive the switch by a poll or two; send it nothing meanwhile
+            if pause::is_set(PauseReason::Incognito) {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            match focused() {
+                Ok(focused) => {
+                    let change = TRACKER.lock().get_or_insert_with(Tracker::default).update(now(), focused);
+                    if let Some(change) = change {
+                        broadcast(&change);
+                    }
+                }
+                Err(e) => log::debug!("[ScreenCapture] Active-window check failed: {}", e),
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        log::info!("[ScreenCapture] Active-window watcher stopped");
+    });
+}
+
+/// Send `change` to every stream, dropping the ones whose channel closed
+fn broadcast(change: &ActiveWindowChange) {
+    STREAMS.lock().retain(|stream| match stream.channel.send(change.clone()) {
+        Ok(()) => true,
+        Err(e) => {
+            log::info!("[ScreenCapture] Active-window stream {} closed: {}", stream.id, e);
+            false
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focused(id: u32, title: &str) -> Option<Focused> {
+        Some(Focused { id, app_name: "Code".to_string(), title: title.to_string(), pid: 7 })
+    }
+
+    #[test]
+    fn reports_focus_and_title_changes_with_time_in_front() {
+        let mut tracker = Tracker { title_since: 100.0, ..Default::default() };
+        let first = tracker.update(100.0, focused(1, "main.rs")).unwrap();
+        assert_eq!(first.window.as_ref().map(|w| w.window_id), Some(1));
+        assert!(!first.title_changed);
+        assert_eq!(tracker.update(101.0, focused(1, "main.rs")), None);
+
+        let renamed = tracker.update(110.0, focused(1, "lib.rs")).unwrap();
+        assert!(renamed.title_changed);
+        assert_eq!(renamed.previous_secs, Some(10.0));
+        assert_eq!(renamed.window.unwrap().active_since, 100.0);
+
+        let switched = tracker.update(115.0, focused(2, "Inbox")).unwrap();
+        assert!(!switched.title_changed);
+        assert_eq!(switched.previous_secs, Some(5.0));
+        assert_eq!(switched.window.unwrap().active_since, 115.0);
+
+        let none = tracker.update(120.0, None).unwrap();
+        assert_eq!(none.window, None);
+        assert_eq!(tracker.update(121.0, None), None);
+    }
+}
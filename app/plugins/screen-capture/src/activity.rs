@@ -0,0 +1,146 @@
+//! Adaptive frame rate for the polling capture loops.
+//!
+//! With `adaptive_fps` on in the stream's config, each loop keeps a `Pacer`: every captured
+//! frame is reduced to a coarse luma grid and compared with the previous one. Any change
+//! puts the loop at the configured FPS right away; once nothing has changed for
+//! `SETTLE_AFTER` it drops to `IDLE_FPS`. Fast up, slow down, so a blinking caret or a
+//! pause between keystrokes doesn't make the rate flap. Static screens still deliver a
+//! frame per second, they just stop costing a capture and encode per configured tick.
+//! A rate set on the stream's `Flow` (`backpressure::set_fps`) wins over both.
+//!
+//! ScreenCaptureKit only delivers frames when the screen changes, so macOS streams are
+//! adaptive already and don't use this.
+
+use crate::backpressure::Flow;
+use crate::capture_config::CaptureConfig;
+use crate::wire::FrameSink;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Frame rate while the screen is static
+pub const IDLE_FPS: u32 = 1;
+/// How long the screen must stay unchanged before dropping to `IDLE_FPS`
+pub const SETTLE_AFTER: Duration = Duration::from_secs(3);
+
+/// Sample grid the change check runs on
+const GRID_COLUMNS: usize = 64;
+const GRID_ROWS: usize = 36;
+/// Luma difference below which a sample counts as unchanged (compression/dither noise)
+const NOISE: u8 = 8;
+/// Samples that must change for the frame to count as changed
+const MIN_CHANGED: usize = 2;
+
+/// Paces one capture loop
+#[derive(Default)]
+pub struct Pacer {
+    /// The stream's capture settings, read every frame so default changes still apply
+    config: CaptureConfig,
+    /// The stream's flow, for a rate set while it runs
+    flow: Arc<Flow>,
+    last_signature: Option<Vec<u8>>,
+    /// When the screen last changed
+    last_change: Option<Instant>,
+}
+
+impl Pacer {
+    pub fn new(sink: &FrameSink) -> Self {
+        Self { config: *sink.config(), flow: sink.flow(), ..Self::default() }
+    }
+
+    /// Record a captured RGBA frame
+    pub fn observe(&mut self, rgba: &[u8], width: u32, height: u32, now: Instant) {
+        if !self.config.adaptive_fps() {
+            self.last_signature = None;
+            return;
+        }
+        let signature = signature(rgba, width as usize, height as usize);
+        let changed = self.last_signature.as_ref().is_none_or(|last| differs(last, &signature));
+        if changed {
+            self.last_change = Some(now);
+        }
+        self.last_signature = Some(signature);
+    }
+
+    /// Time to wait between frames from `now` on
+    pub fn frame_time(&self, now: Instant) -> Duration {
+        let fps = match self.flow.fps() {
+            Some(fps) => fps,
+            None if self.config.adaptive_fps() && self.is_idle(now) => IDLE_FPS,
+            None => self.config.target_fps(),
+        };
+        Duration::from_millis(1000 / u64::from(fps.max(1)))
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        self.last_change.is_some_and(|at| now.duration_since(at) >= SETTLE_AFTER)
+    }
+}
+
+/// Luma at each grid point of a tightly packed RGBA frame
+fn signature(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    if width == 0 || height == 0 || rgba.len() < width * height * 4 {
+        return Vec::new();
+    }
+    let mut samples = Vec::with_capacity(GRID_COLUMNS * GRID_ROWS);
+    for row in 0..GRID_ROWS {
+        let y = (row * height + height / 2) / GRID_ROWS;
+        for column in 0..GRID_COLUMNS {
+            let x = (column * width + width / 2) / GRID_COLUMNS;
+            let i = (y * width + x) * 4;
+            let luma = (u32::from(rgba[i]) * 77 + u32::from(rgba[i + 1]) * 150 + u32::from(rgba[i + 2]) * 29) >> 8;
+            samples.push(luma as u8);
+        }
+    }
+    samples
+}
+
+fn differs(a: &[u8], b: &[u8]) -> bool {
+    a.len() != b.len() || a.iter().zip(b).filter(|(x, y)| x.abs_diff(**y) > NOISE).count() >= MIN_CHANGED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: usize, height: usize, value: u8) -> Vec<u8> {
+        vec![value; width * height * 4]
+    }
+
+    #[test]
+    fn ignores_noise_and_single_samples() {
+        let base = signature(&frame(640, 360, 100), 640, 360);
+        assert_eq!(base.len(), GRID_COLUMNS * GRID_ROWS);
+        assert!(!differs(&base, &signature(&frame(640, 360, 104), 640, 360)));
+
+        let mut one = base.clone();
+        one[0] = 255;
+        assert!(!differs(&base, &one));
+        one[1] = 255;
+        assert!(differs(&base, &one));
+    }
+
+    #[test]
+    fn settles_only_after_quiet_period() {
+        let start = Instant::now();
+        let mut pacer = Pacer { last_change: Some(start), ..Pacer::default() };
+        assert!(!pacer.is_idle(start + SETTLE_AFTER / 2));
+        assert!(pacer.is_idle(start + SETTLE_AFTER));
+
+        pacer.last_change = Some(start + SETTLE_AFTER);
+        assert!(!pacer.is_idle(start + SETTLE_AFTER + Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn rate_set_on_the_flow_wins_until_released() {
+        let start = Instant::now();
+        let config = CaptureConfig { fps: Some(5), adaptive_fps: Some(true), ..Default::default() };
+        let pacer = Pacer { config, last_change: Some(start), ..Pacer::default() };
+        let idle = start + SETTLE_AFTER;
+        assert_eq!(pacer.frame_time(idle), Duration::from_secs(1));
+
+        pacer.flow.set_fps(Some(20));
+        assert_eq!(pacer.frame_time(idle), Duration::from_millis(50));
+        pacer.flow.set_fps(None);
+        assert_eq!(pacer.frame_time(start), Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,241 @@
+//! Backpressure on frame channels.
+//!
+//! A Tauri channel never blocks the sender, so a consumer that falls behind (a busy
+//! webview, a slow model call) used to build up a backlog of frames and ever-growing
+//! latency. Each `FrameSink` now carries a `Flow` that the capture loops ask before
+//! encoding a frame:
+//!
+//! - Consumers that call `ack_frames_cmd` with the last frame they finished are tracked
+//!   by frames in flight. With `MAX_IN_FLIGHT` unacknowledged frames out, new frames are
+//!   dropped before they are encoded.
+//! - Slow sends (a smoothed send time above `SLOW_SEND`) and drops lower the JPEG
+//!   quality in `QUALITY_STEP_DOWN` steps, down to `MIN_QUALITY`. After
+//!   `RECOVER_AFTER` clean frames it climbs back by `QUALITY_STEP_UP`, so quality
+//!   doesn't oscillate.
+//!
+//! Consumers that never ack only get the send-time half. The flow also carries the
+//! channel's `stats::Recorder`, so `get_capture_stats_cmd` reports quality and drops
+//! next to fps, stage times and throughput.
+//!
+//! `set_fps` puts running streams at another frame rate for a while (the app raises it
+//! while someone talks) without touching their config or the defaults. The polling
+//! loops' `Pacer` reads it every frame; ScreenCaptureKit bakes its frame interval into
+//! the stream, and Wayland can't go above the rate it negotiated at start.
+
+use crate::capture_config;
+use crate::stats::{CaptureStats, Recorder, Stage};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Unacknowledged frames allowed out before new ones are dropped
+pub const MAX_IN_FLIGHT: u64 = 3;
+/// Smoothed send time that counts as a congested channel
+pub const SLOW_SEND: Duration = Duration::from_millis(30);
+const QUALITY_STEP_DOWN: u8 = 10;
+const QUALITY_STEP_UP: u8 = 2;
+const MIN_QUALITY: u8 = 25;
+/// Clean frames before quality climbs a step
+const RECOVER_AFTER: u32 = 10;
+
+/// Backpressure state of one frame channel
+#[derive(Default)]
+pub struct Flow {
+    state: Mutex<FlowState>,
+}
+
+#[derive(Default)]
+struct FlowState {
+    /// Last frame sent
+    sent: Option<u64>,
+    /// Last frame the consumer acknowledged, once it acks at all
+    acked: Option<u64>,
+    /// The stream's configured JPEG quality, as of its last frame
+    configured: Option<u8>,
+    /// Quality taken off the configured JPEG quality
+    penalty: u8,
+    clean_streak: u32,
+    dropped: u64,
+    recorder: Recorder,
+    /// Frame rate over the stream's config until released
+    fps: Option<u32>,
+}
+
+impl FlowState {
+    fn in_flight(&self) -> u64 {
+        match (self.sent, self.acked) {
+            (Some(sent), Some(acked)) => sent.saturating_sub(acked),
+            _ => 0,
+        }
+    }
+
+    fn quality(&self, configured: u8) -> u8 {
+        configured.saturating_sub(self.penalty).max(MIN_QUALITY.min(configured))
+    }
+
+    fn congested(&mut self) {
+        self.penalty = self.penalty.saturating_add(QUALITY_STEP_DOWN).min(100 - MIN_QUALITY);
+        self.clean_streak = 0;
+    }
+
+    fn admit(&mut self, configured: u8) -> Option<u8> {
+        self.configured = Some(configured);
+        if self.in_flight() >= MAX_IN_FLIGHT {
+            self.dropped += 1;
+            self.congested();
+            return None;
+        }
+        Some(self.quality(configured))
+    }
+
+    fn sent(&mut self, frame_count: u64, bytes: usize, took: Duration, now: Instant) {
+        self.sent = Some(frame_count);
+        self.recorder.sent(bytes, took, now);
+
+        if self.recorder.send_time() > SLOW_SEND {
+            self.congested();
+        } else if self.penalty > 0 {
+            self.clean_streak += 1;
+            if self.clean_streak >= RECOVER_AFTER {
+                self.penalty = self.penalty.saturating_sub(QUALITY_STEP_UP);
+                self.clean_streak = 0;
+            }
+        }
+    }
+}
+
+impl Flow {
+    /// JPEG quality to encode the next frame with (`configured` less any congestion
+    /// penalty), or None to drop it
+    pub fn admit(&self, configured: u8) -> Option<u8> {
+        self.state.lock().admit(configured)
+    }
+
+    /// Record a frame of `bytes` handed to the channel and how long the send took
+    pub fn sent(&self, frame_count: u64, bytes: usize, took: Duration) {
+        self.state.lock().sent(frame_count, bytes, took, Instant::now());
+    }
+
+    /// Record how long a pipeline stage took for the current frame
+    pub fn record(&self, stage: Stage, took: Duration) {
+        self.state.lock().recorder.record(stage, took);
+    }
+
+    /// Run the stream at `fps` instead of its config's rate (None goes back to it)
+    pub fn set_fps(&self, fps: Option<u32>) {
+        self.state.lock().fps = fps.map(capture_config::clamp_fps);
+    }
+
+    pub fn fps(&self) -> Option<u32> {
+        self.state.lock().fps
+    }
+
+    /// The consumer finished with `frame_count` (and everything before it)
+    pub fn ack(&self, frame_count: u64) {
+        let mut state = self.state.lock();
+        state.acked = Some(state.acked.map_or(frame_count, |acked| acked.max(frame_count)));
+    }
+
+    pub fn stats(&self, session_id: &str) -> CaptureStats {
+        let state = self.state.lock();
+        let (frames_sent, bytes_sent) = state.recorder.totals();
+        CaptureStats {
+            session_id: session_id.to_string(),
+            rates: state.recorder.snapshot(Instant::now()),
+            quality: state.quality(state.configured.unwrap_or_else(capture_config::jpeg_quality)),
+            dropped_frames: state.dropped,
+            in_flight: state.in_flight(),
+            frames_sent,
+            bytes_sent,
+            encode_times: state.recorder.encode_times().clone(),
+        }
+    }
+}
+
+fn flows() -> &'static Mutex<HashMap<String, Arc<Flow>>> {
+    static FLOWS: OnceLock<Mutex<HashMap<String, Arc<Flow>>>> = OnceLock::new();
+    FLOWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Track `flow` as `session_id`'s channel, replacing the previous one
+pub fn register(session_id: &str, flow: Arc<Flow>) {
+    flows().lock().insert(session_id.to_string(), flow);
+}
+
+pub fn unregister(session_id: &str) {
+    flows().lock().remove(session_id);
+}
+
+pub fn unregister_all() {
+    flows().lock().clear();
+}
+
+/// Acknowledge frames on `session_id`'s channel
+pub fn ack(session_id: &str, frame_count: u64) {
+    if let Some(flow) = flows().lock().get(session_id) {
+        flow.ack(frame_count);
+    }
+}
+
+/// Set (or with None release) the frame rate of one session, or of every session with a
+/// channel
+pub fn set_fps(session_id: Option<&str>, fps: Option<u32>) {
+    let flows = flows().lock();
+    for (_, flow) in flows.iter().filter(|(id, _)| session_id.is_none_or(|wanted| wanted == id.as_str())) {
+        flow.set_fps(fps);
+    }
+}
+
+/// Stats of one session, or of every session with a channel
+pub fn stats(session_id: Option<&str>) -> Vec<CaptureStats> {
+    let flows = flows().lock();
+    let mut list: Vec<CaptureStats> = flows
+        .iter()
+        .filter(|(id, _)| session_id.is_none_or(|wanted| wanted == id.as_str()))
+        .map(|(id, flow)| flow.stats(id))
+        .collect();
+    list.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn drops_once_consumer_falls_behind() {
+        let now = Instant::now();
+        let mut state = FlowState::default();
+        for frame in 0..5 {
+            assert!(state.admit(80).is_some());
+            state.sent(frame, 0, FAST, now);
+        }
+        // Never acked: no in-flight tracking
+        assert_eq!(state.in_flight(), 0);
+
+        state.acked = Some(1);
+        assert_eq!(state.in_flight(), 3);
+        assert_eq!(state.admit(80), None);
+        assert_eq!(state.dropped, 1);
+        assert_eq!(state.quality(80), 70);
+    }
+
+    #[test]
+    fn slow_sends_lower_quality_and_clean_frames_restore_it() {
+        let now = Instant::now();
+        let mut state = FlowState::default();
+        for frame in 0..10 {
+            state.sent(frame, 0, SLOW_SEND * 4, now);
+        }
+        assert_eq!(state.quality(80), MIN_QUALITY);
+        assert_eq!(state.quality(20), 20);
+
+        for frame in 10..200 {
+            state.sent(frame, 0, FAST, now);
+        }
+        assert!(state.quality(80) > MIN_QUALITY);
+    }
+}
@@ -1,26 +1,167 @@
 //! Runtime-tunable screen-capture quality knobs: max width, JPEG quality, FPS.
 //!
-//! The frontend pushes these (see the `sc_set_capture_config` app command) right before
-//! it starts a capture stream, and the platform capture code reads them when it builds
-//! the stream / encodes each frame. There is deliberately no live-restart machinery: a
-//! value change takes effect the next time capture starts (toggle the screen sensor),
-//! which keeps this a plain read-at-build config with no stream-lifecycle coupling.
+//! The frontend (or an agent) sets these with `set_capture_config_cmd` / the
+//! `sc_set_capture_config` app command. They are the defaults every stream follows; a
+//! `config` passed when starting a stream stays on that stream's `FrameSink` and
+//! overrides them for it alone (the `CaptureConfig` getters fall back to the defaults
+//! for fields it leaves unset), so sessions running side by side keep their own. The
+//! xcap backend (Windows/Linux) reads them per frame, so changes apply to a running
+//! stream. ScreenCaptureKit bakes size and frame interval into the stream configuration,
+//! so on macOS width/FPS changes take effect the next time capture starts (JPEG quality
+//! still applies per frame).
+//!
+//! `show_cursor` draws the mouse pointer into frames (composited on Windows/Linux, native
+//! on macOS, where it applies from the next capture start). On Wayland the portal
+//! session is shared, so only the default applies, when the portal is first opened.
+//!
+//! `backend` picks the capture API where a platform has more than one: on Windows,
+//! Windows.Graphics.Capture (the default when available) or xcap's GDI/DXGI grabs. It
+//! applies from the next capture start. Other platforms ignore it.
+//!
+//! `replay_seconds` is how much history the instant-replay buffer keeps (0 = off). The
+//! buffer is shared, so it is only set through the defaults.
+//!
+//! `target_lost` decides what a session does when its window closes or its monitor is
+//! unplugged: stop (the default), fall back to the primary monitor, or wait for the
+//! target to come back. See `lifecycle`.
+//!
+//! `max_capture_failures` is how many grabs in a row may fail (with growing pauses in
+//! between) before the session gives up (0 = never); `error_fallback` then moves it to
+//! the primary monitor instead of stopping it. See `recovery`.
+//!
+//! `adaptive_fps` drops the polling backends to 1 fps while the screen is static and
+//! back to the configured FPS as soon as it changes (see `activity`).
+//!
+//! `grayscale` encodes frames as 8-bit luma — about half the size and encode time, for
+//! agents that only read text. It applies per frame on every backend.
+//!
+//! `frame_stats` attaches each frame's mean luminance, dominant colors and dark/light
+//! guess to it (see `frame_stats`). They're computed either way for
+//! `get_frame_stats_cmd`; this only controls whether every frame carries them.
+//!
+//! Frames are JPEG by default. PNG and lossless WebP keep small text crisp for OCR-heavy
+//! agents at the cost of larger frames; lossy WebP uses the JPEG quality setting.
 //!
 //! Defaults match the "Low" tier — light and fast (≈ the pre-native-resolution behavior),
 //! but sharper-per-pixel because the capture is now sized in real pixels, not points.
 
-use std::sync::atomic::{AtomicU32, Ordering};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
 
 static MAX_WIDTH: AtomicU32 = AtomicU32::new(1280);
 static JPEG_QUALITY: AtomicU32 = AtomicU32::new(55);
 static TARGET_FPS: AtomicU32 = AtomicU32::new(10);
+static ENCODING: AtomicU8 = AtomicU8::new(FrameEncoding::Jpeg as u8);
+// ScreenCaptureKit has always drawn the cursor, so macOS keeps it on by default
+static SHOW_CURSOR: AtomicBool = AtomicBool::new(cfg!(target_os = "macos"));
+static BACKEND: AtomicU8 = AtomicU8::new(CaptureBackend::Auto as u8);
+static REPLAY_SECONDS: AtomicU32 = AtomicU32::new(30);
+static TARGET_LOST: AtomicU8 = AtomicU8::new(TargetLostPolicy::Stop as u8);
+static GRAYSCALE: AtomicBool = AtomicBool::new(false);
+static ADAPTIVE_FPS: AtomicBool = AtomicBool::new(false);
+static MAX_CAPTURE_FAILURES: AtomicU32 = AtomicU32::new(10);
+static ERROR_FALLBACK: AtomicBool = AtomicBool::new(false);
+static FRAME_STATS: AtomicBool = AtomicBool::new(false);
+
+/// Image format frames are encoded in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FrameEncoding {
+    #[default]
+    Jpeg = 0,
+    Png = 1,
+    /// Lossy WebP at the configured quality
+    Webp = 2,
+    WebpLossless = 3,
+}
+
+impl FrameEncoding {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => FrameEncoding::Png,
+            2 => FrameEncoding::Webp,
+            3 => FrameEncoding::WebpLossless,
+            _ => FrameEncoding::Jpeg,
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            FrameEncoding::Jpeg => "image/jpeg",
+            FrameEncoding::Png => "image/png",
+            FrameEncoding::Webp | FrameEncoding::WebpLossless => "image/webp",
+        }
+    }
+}
+
+/// Capture API selection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureBackend {
+    /// The platform's preferred backend
+    #[default]
+    Auto = 0,
+    /// xcap (GDI/DXGI on Windows)
+    Xcap = 1,
+    /// Windows.Graphics.Capture
+    Wgc = 2,
+}
+
+impl CaptureBackend {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => CaptureBackend::Xcap,
+            2 => CaptureBackend::Wgc,
+            _ => CaptureBackend::Auto,
+        }
+    }
+}
+
+/// What a session does when its capture target disappears
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetLostPolicy {
+    /// End the session
+    #[default]
+    Stop = 0,
+    /// Keep the session running on the primary monitor
+    FallbackToPrimary = 1,
+    /// Keep the session open and resume once the target is back
+    Wait = 2,
+}
+
+impl TargetLostPolicy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => TargetLostPolicy::FallbackToPrimary,
+            2 => TargetLostPolicy::Wait,
+            _ => TargetLostPolicy::Stop,
+        }
+    }
+}
 
 /// Store a new capture config. Values are clamped to sane ranges so a stray input field
 /// can't hand the capture pipeline a zero width or a 1000fps interval.
 pub fn set(max_width: u32, jpeg_quality: u8, fps: u32) {
-    MAX_WIDTH.store(max_width.clamp(160, 7680), Ordering::Relaxed);
-    JPEG_QUALITY.store(u32::from(jpeg_quality.clamp(1, 100)), Ordering::Relaxed);
-    TARGET_FPS.store(fps.clamp(1, 120), Ordering::Relaxed);
+    MAX_WIDTH.store(clamp_width(max_width), Ordering::Relaxed);
+    JPEG_QUALITY.store(u32::from(clamp_quality(jpeg_quality)), Ordering::Relaxed);
+    TARGET_FPS.store(clamp_fps(fps), Ordering::Relaxed);
+}
+
+fn clamp_width(max_width: u32) -> u32 {
+    max_width.clamp(160, 7680)
+}
+
+fn clamp_quality(jpeg_quality: u8) -> u8 {
+    jpeg_quality.clamp(1, 100)
+}
+
+pub(crate) fn clamp_fps(fps: u32) -> u32 {
+    fps.clamp(1, 120)
+}
+
+fn clamp_failures(failures: u32) -> u32 {
+    failures.min(1000)
 }
 
 /// Max output width in pixels; the source is downscaled to fit (aspect preserved).
@@ -28,7 +169,7 @@ pub fn max_width() -> u32 {
     MAX_WIDTH.load(Ordering::Relaxed)
 }
 
-/// JPEG encode quality, 1–100.
+/// JPEG (and lossy WebP) encode quality, 1–100.
 pub fn jpeg_quality() -> u8 {
     JPEG_QUALITY.load(Ordering::Relaxed) as u8
 }
@@ -37,3 +178,250 @@ pub fn jpeg_quality() -> u8 {
 pub fn target_fps() -> u32 {
     TARGET_FPS.load(Ordering::Relaxed)
 }
+
+/// Frame image format
+pub fn encoding() -> FrameEncoding {
+    FrameEncoding::from_u8(ENCODING.load(Ordering::Relaxed))
+}
+
+pub fn set_encoding(encoding: FrameEncoding) {
+    ENCODING.store(encoding as u8, Ordering::Relaxed);
+}
+
+/// Whether frames include the mouse cursor
+pub fn show_cursor() -> bool {
+    SHOW_CURSOR.load(Ordering::Relaxed)
+}
+
+pub fn set_show_cursor(show: bool) {
+    SHOW_CURSOR.store(show, Ordering::Relaxed);
+}
+
+/// Requested capture backend
+pub fn backend() -> CaptureBackend {
+    CaptureBackend::from_u8(BACKEND.load(Ordering::Relaxed))
+}
+
+pub fn set_backend(backend: CaptureBackend) {
+    BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+/// Seconds of frames the replay buffer keeps
+pub fn replay_seconds() -> u32 {
+    REPLAY_SECONDS.load(Ordering::Relaxed)
+}
+
+pub fn set_replay_seconds(seconds: u32) {
+    REPLAY_SECONDS.store(seconds.min(600), Ordering::Relaxed);
+}
+
+/// What sessions do when their target disappears
+pub fn target_lost_policy() -> TargetLostPolicy {
+    TargetLostPolicy::from_u8(TARGET_LOST.load(Ordering::Relaxed))
+}
+
+pub fn set_target_lost_policy(policy: TargetLostPolicy) {
+    TARGET_LOST.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Whether frames are encoded as 8-bit grayscale
+pub fn grayscale() -> bool {
+    GRAYSCALE.load(Ordering::Relaxed)
+}
+
+pub fn set_grayscale(grayscale: bool) {
+    GRAYSCALE.store(grayscale, Ordering::Relaxed);
+}
+
+/// Whether capture slows down while the screen is static
+pub fn adaptive_fps() -> bool {
+    ADAPTIVE_FPS.load(Ordering::Relaxed)
+}
+
+pub fn set_adaptive_fps(adaptive: bool) {
+    ADAPTIVE_FPS.store(adaptive, Ordering::Relaxed);
+}
+
+/// Consecutive failed grabs before a session gives up (0 = keep retrying)
+pub fn max_capture_failures() -> u32 {
+    MAX_CAPTURE_FAILURES.load(Ordering::Relaxed)
+}
+
+pub fn set_max_capture_failures(failures: u32) {
+    MAX_CAPTURE_FAILURES.store(clamp_failures(failures), Ordering::Relaxed);
+}
+
+/// Whether a session that gave up moves to the primary monitor instead of stopping
+pub fn error_fallback() -> bool {
+    ERROR_FALLBACK.load(Ordering::Relaxed)
+}
+
+pub fn set_error_fallback(fallback: bool) {
+    ERROR_FALLBACK.store(fallback, Ordering::Relaxed);
+}
+
+/// Whether frames carry their color statistics
+pub fn frame_stats() -> bool {
+    FRAME_STATS.load(Ordering::Relaxed)
+}
+
+pub fn set_frame_stats(attach: bool) {
+    FRAME_STATS.store(attach, Ordering::Relaxed);
+}
+
+/// Capture settings as seen by the frontend. When used as an update, unset fields keep
+/// their current value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureConfig {
+    pub max_width: Option<u32>,
+    pub jpeg_quality: Option<u8>,
+    pub fps: Option<u32>,
+    pub encoding: Option<FrameEncoding>,
+    pub show_cursor: Option<bool>,
+    pub backend: Option<CaptureBackend>,
+    pub replay_seconds: Option<u32>,
+    pub target_lost: Option<TargetLostPolicy>,
+    pub grayscale: Option<bool>,
+    pub adaptive_fps: Option<bool>,
+    pub max_capture_failures: Option<u32>,
+    pub error_fallback: Option<bool>,
+    pub frame_stats: Option<bool>,
+}
+
+/// A stream's settings: its own value where set, the default otherwise
+impl CaptureConfig {
+    pub fn max_width(&self) -> u32 {
+        self.max_width.map_or_else(max_width, clamp_width)
+    }
+
+    pub fn jpeg_quality(&self) -> u8 {
+        self.jpeg_quality.map_or_else(jpeg_quality, clamp_quality)
+    }
+
+    pub fn target_fps(&self) -> u32 {
+        self.fps.map_or_else(target_fps, clamp_fps)
+    }
+
+    pub fn encoding(&self) -> FrameEncoding {
+        self.encoding.unwrap_or_else(encoding)
+    }
+
+    pub fn show_cursor(&self) -> bool {
+        self.show_cursor.unwrap_or_else(show_cursor)
+    }
+
+    pub fn backend(&self) -> CaptureBackend {
+        self.backend.unwrap_or_else(backend)
+    }
+
+    pub fn target_lost_policy(&self) -> TargetLostPolicy {
+        self.target_lost.unwrap_or_else(target_lost_policy)
+    }
+
+    pub fn grayscale(&self) -> bool {
+        self.grayscale.unwrap_or_else(grayscale)
+    }
+
+    pub fn adaptive_fps(&self) -> bool {
+        self.adaptive_fps.unwrap_or_else(adaptive_fps)
+    }
+
+    pub fn max_capture_failures(&self) -> u32 {
+        self.max_capture_failures.map_or_else(max_capture_failures, clamp_failures)
+    }
+
+    pub fn error_fallback(&self) -> bool {
+        self.error_fallback.unwrap_or_else(error_fallback)
+    }
+
+    pub fn frame_stats(&self) -> bool {
+        self.frame_stats.unwrap_or_else(frame_stats)
+    }
+}
+
+/// Current settings, with every field set
+pub fn get() -> CaptureConfig {
+    CaptureConfig {
+        max_width: Some(max_width()),
+        jpeg_quality: Some(jpeg_quality()),
+        fps: Some(target_fps()),
+        encoding: Some(encoding()),
+        show_cursor: Some(show_cursor()),
+        backend: Some(backend()),
+        replay_seconds: Some(replay_seconds()),
+        target_lost: Some(target_lost_policy()),
+        grayscale: Some(grayscale()),
+        adaptive_fps: Some(adaptive_fps()),
+        max_capture_failures: Some(max_capture_failures()),
+        error_fallback: Some(error_fallback()),
+        frame_stats: Some(frame_stats()),
+    }
+}
+
+/// Apply the fields that are set to the defaults and return the resulting (clamped)
+/// settings
+pub fn update(config: &CaptureConfig) -> CaptureConfig {
+    set(
+        config.max_width.unwrap_or_else(max_width),
+        config.jpeg_quality.unwrap_or_else(jpeg_quality),
+        config.fps.unwrap_or_else(target_fps),
+    );
+    if let Some(encoding) = config.encoding {
+        set_encoding(encoding);
+    }
+    if let Some(show) = config.show_cursor {
+        set_show_cursor(show);
+    }
+    if let Some(backend) = config.backend {
+        set_backend(backend);
+    }
+    if let Some(seconds) = config.replay_seconds {
+        set_replay_seconds(seconds);
+    }
+    if let Some(policy) = config.target_lost {
+        set_target_lost_policy(policy);
+    }
+    if let Some(grayscale) = config.grayscale {
+        set_grayscale(grayscale);
+    }
+    if let Some(adaptive) = config.adaptive_fps {
+        set_adaptive_fps(adaptive);
+    }
+    if let Some(failures) = config.max_capture_failures {
+        set_max_capture_failures(failures);
+    }
+    if let Some(fallback) = config.error_fallback {
+        set_error_fallback(fallback);
+    }
+    if let Some(attach) = config.frame_stats {
+        set_frame_stats(attach);
+    }
+    let applied = get();
+    log::info!(
+        "[ScreenCapture] Capture config: max width {}, quality {}, {} fps{}, {:?}{}, cursor {}, {:?} backend",
+        max_width(),
+        jpeg_quality(),
+        target_fps(),
+        if adaptive_fps() { " (adaptive)" } else { "" },
+        encoding(),
+        if grayscale() { " grayscale" } else { "" },
+        if show_cursor() { "shown" } else { "hidden" },
+        backend()
+    );
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_settings_fall_back_to_the_defaults() {
+        let stream = CaptureConfig { max_width: Some(100_000), fps: Some(5), ..CaptureConfig::default() };
+        assert_eq!(stream.max_width(), 7680);
+        assert_eq!(stream.target_fps(), 5);
+        assert_eq!(stream.jpeg_quality(), jpeg_quality());
+        assert_eq!(CaptureConfig::default().target_fps(), target_fps());
+    }
+}
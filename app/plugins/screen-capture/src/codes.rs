@@ -0,0 +1,116 @@
+//! QR code and barcode detection.
+//!
+//! Scans an image, or the default stream's next frame, for QR codes, Data Matrix, Aztec,
+//! PDF417 and the common 1D barcodes (EAN/UPC, Code 128, Code 39, ITF, ...) with the
+//! pure-Rust ZXing port `rxing`, and returns each payload with where it was found.
+//! Detection runs on grayscale, so JPEG frames are fine as long as the code isn't
+//! downscaled past a few pixels per module; for small codes on a large screen, raise
+//! `capture_config::max_width` or scan a full-resolution screenshot instead.
+//!
+//! Frames come from the frame tap, after redaction and privacy regions, so codes inside
+//! hidden areas aren't found.
+
+use crate::error::{Error, Result};
+use crate::frames;
+use serde::Serialize;
+
+/// A decoded code
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedCode {
+    /// Symbology, e.g. `qrcode`, `ean 13`, `code 128`
+    pub format: String,
+    pub text: String,
+    /// Bounds of the points the decoder located, in image pixels
+    pub bounds: CodeBounds,
+    /// The located points themselves: a QR code's finder patterns, a 1D barcode's ends
+    pub points: Vec<(f32, f32)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeBounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Codes found in one image
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeScan {
+    pub width: u32,
+    pub height: u32,
+    /// Stream frame scanned, when scanning the latest frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_count: Option<u64>,
+    pub codes: Vec<DetectedCode>,
+}
+
+/// Codes in 8-bit grayscale pixels
+pub fn detect(luma: Vec<u8>, width: u32, height: u32) -> Vec<DetectedCode> {
+    match rxing::helpers::detect_multiple_in_luma(luma, width, height) {
+        Ok(results) => results
+            .iter()
+            .map(|result| {
+                let points: Vec<(f32, f32)> = result.getPoints().iter().map(|p| (p.x, p.y)).collect();
+                DetectedCode {
+                    format: result.getBarcodeFormat().to_string(),
+                    text: result.getText().to_string(),
+                    bounds: bounds(&points),
+                    points,
+                }
+            })
+            .collect(),
+        // rxing reports "nothing found" as an error
+        Err(e) => {
+            log::trace!("[ScreenCapture] No codes: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Scan an encoded image (PNG/JPEG)
+pub fn scan_image(bytes: &[u8]) -> Result<CodeScan> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| Error::Platform(format!("Failed to decode image: {}", e)))?
+        .to_luma8();
+    let (width, height) = image.dimensions();
+    Ok(CodeScan { width, height, frame_count: None, codes: detect(image.into_raw(), width, height) })
+}
+
+/// Scan the default stream's next frame
+pub async fn scan_latest_frame() -> Result<CodeScan> {
+    let frame = frames::next(frames::NEXT_TIMEOUT)
+        .await
+        .ok_or_else(|| Error::Platform("No frame from the capture stream (is one running?)".to_string()))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_image(&frame.data).map(|scan| CodeScan { frame_count: Some(frame.frame_count), ..scan })
+    })
+    .await
+    .map_err(|e| Error::Platform(e.to_string()))?
+}
+
+fn bounds(points: &[(f32, f32)]) -> CodeBounds {
+    if points.is_empty() {
+        return CodeBounds { x: 0.0, y: 0.0, width: 0.0, height: 0.0 };
+    }
+    let min_x = points.iter().map(|p| p.0).fold(f32::INFINITY, f32::min);
+    let min_y = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+    let max_x = points.iter().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max);
+    let max_y = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+    CodeBounds { x: min_x, y: min_y, width: max_x - min_x, height: max_y - min_y }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_cover_the_located_points() {
+        let points = [(10.0, 40.0), (50.0, 40.0), (10.0, 5.0)];
+        assert_eq!(bounds(&points), CodeBounds { x: 10.0, y: 5.0, width: 40.0, height: 35.0 });
+        assert_eq!(bounds(&[]).width, 0.0);
+    }
+}
@@ -0,0 +1,90 @@
+//! Mouse cursor compositing for the xcap backend.
+//!
+//! xcap grabs the screen without the pointer, so when `capture_config::show_cursor()` is
+//! on we draw a standard arrow at the current pointer position before the frame is
+//! downscaled and encoded. ScreenCaptureKit draws the real cursor itself
+//! (`with_shows_cursor`), so macOS doesn't use this module.
+//!
+//! The pointer position comes from the OS in screen coordinates (physical pixels on
+//! Windows/X11). Wayland capture goes through the ScreenCast portal, which embeds the
+//! cursor itself (`desktop_wayland`), so this is X11/Windows only.
+
+use image::{Rgba, RgbaImage};
+
+/// Classic arrow cursor: `B` outline, `W` fill, `.` transparent. Hotspot is top-left.
+const ARROW: [&str; 19] = [
+    "B...........",
+    "BB..........",
+    "BWB.........",
+    "BWWB........",
+    "BWWWB.......",
+    "BWWWWB......",
+    "BWWWWWB.....",
+    "BWWWWWWB....",
+    "BWWWWWWWB...",
+    "BWWWWWWWWB..",
+    "BWWWWWWWWWB.",
+    "BWWWWWWBBBBB",
+    "BWWWBWWB....",
+    "BWWBBWWB....",
+    "BWB..BWWB...",
+    "BB...BWWB...",
+    "B.....BWWB..",
+    "......BWWB..",
+    ".......BB...",
+];
+
+const OUTLINE: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const FILL: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Current pointer position in screen coordinates, if the platform exposes it
+pub fn position() -> Option<(i32, i32)> {
+    use mouse_position::mouse_position::Mouse;
+    match Mouse::get_mouse_position() {
+        Mouse::Position { x, y } => Some((x, y)),
+        Mouse::Error => None,
+    }
+}
+
+/// Draw the arrow with its hotspot at image pixel (`x`, `y`). `scale` enlarges it on
+/// high-DPI sources so it stays visible after the frame is downscaled.
+pub fn draw(image: &mut RgbaImage, x: i64, y: i64, scale: f64) {
+    let scale = scale.max(1.0);
+    let sprite_w = (ARROW[0].len() as f64 * scale).ceil() as i64;
+    let sprite_h = (ARROW.len() as f64 * scale).ceil() as i64;
+
+    for dy in 0..sprite_h {
+        for dx in 0..sprite_w {
+            let (px, py) = (x + dx, y + dy);
+            if px < 0 || py < 0 || px >= i64::from(image.width()) || py >= i64::from(image.height()) {
+                continue;
+            }
+            let row = ARROW[((dy as f64 / scale) as usize).min(ARROW.len() - 1)].as_bytes();
+            let color = match row[((dx as f64 / scale) as usize).min(row.len() - 1)] {
+                b'B' => OUTLINE,
+                b'W' => FILL,
+                _ => continue,
+            };
+            image.put_pixel(px as u32, py as u32, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_arrow_at_hotspot_and_clips_at_edges() {
+        let mut image = RgbaImage::from_pixel(40, 40, Rgba([10, 20, 30, 255]));
+        draw(&mut image, 5, 5, 1.0);
+        assert_eq!(*image.get_pixel(5, 5), OUTLINE);
+        assert_eq!(*image.get_pixel(6, 7), FILL);
+        // Transparent parts of the sprite leave the frame alone
+        assert_eq!(*image.get_pixel(15, 5), Rgba([10, 20, 30, 255]));
+
+        // Partly off-frame pointers are clipped rather than panicking
+        draw(&mut image, 35, -3, 2.0);
+        draw(&mut image, -100, -100, 1.0);
+    }
+}
@@ -0,0 +1,305 @@
+//! Delta-encoded streams: the tiles that changed since the last keyframe instead of
+//! whole frames.
+//!
+//! A sink started with a `DeltaConfig` compares each frame with the last keyframe it
+//! sent, in `tile_size` squares. Only the tiles that differ are encoded, each as a small
+//! image in the stream's format, and sent with a `FrameDelta` saying where they go. A
+//! full keyframe goes out first, then every `keyframe_interval` frames, whenever the
+//! frame size changes and whenever more than `max_changed` of the tiles differ (by then
+//! the tiles cost about as much as a frame).
+//!
+//! Deltas are taken against the keyframe, not the previous frame, so frames dropped by
+//! backpressure don't break reassembly: draw the keyframe, then the latest delta's tiles
+//! over it (`FrameReassembler` in the frontend's `frameDelta.ts`). On a static document
+//! or terminal most deltas carry a handful of tiles or none at all.
+//!
+//! Only the sink's channel sees deltas. The frame tap still gets full frames: for the
+//! default session, delta frames are also encoded whole while the tap has subscribers.
+
+use crate::capture_config::FrameEncoding;
+use crate::encode;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+fn default_tile_size() -> u32 {
+    64
+}
+
+fn default_keyframe_interval() -> u32 {
+    100
+}
+
+fn default_max_changed() -> f32 {
+    0.5
+}
+
+/// How a sink delta-encodes its frames
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaConfig {
+    /// Side of a tile in frame pixels (16–1024)
+    #[serde(default = "default_tile_size")]
+    pub tile_size: u32,
+    /// Frames between keyframes (0 = only when needed)
+    #[serde(default = "default_keyframe_interval")]
+    pub keyframe_interval: u32,
+    /// Fraction of changed tiles above which a keyframe is sent instead
+    #[serde(default = "default_max_changed")]
+    pub max_changed: f32,
+}
+
+impl Default for DeltaConfig {
+    fn default() -> Self {
+        Self {
+            tile_size: default_tile_size(),
+            keyframe_interval: default_keyframe_interval(),
+            max_changed: default_max_changed(),
+        }
+    }
+}
+
+/// A changed tile; its image is `len` bytes of the frame payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub len: u32,
+}
+
+/// How to read a frame of a delta stream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameDelta {
+    /// The frame is a keyframe: the payload is the whole image
+    pub key: bool,
+    /// Id of the keyframe the tiles apply to (the frame's own id on keyframes)
+    pub keyframe: u64,
+    /// Changed tiles; their images are concatenated in the payload in this order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<Tile>,
+}
+
+/// Byte order of a 4-bytes-per-pixel buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelOrder {
+    Rgba,
+    Bgra,
+}
+
+/// What to send for a frame
+#[derive(Debug, PartialEq, Eq)]
+pub enum Plan {
+    Key(u64),
+    /// Regions (x, y, width, height) that differ from keyframe `keyframe`
+    Tiles { keyframe: u64, regions: Vec<(u32, u32, u32, u32)> },
+}
+
+/// Per-sink delta state: the last keyframe's pixels
+#[derive(Debug)]
+pub struct DeltaEncoder {
+    config: DeltaConfig,
+    /// 0 until the first keyframe
+    keyframe: u64,
+    since_key: u32,
+    width: u32,
+    height: u32,
+    /// Tightly packed 4-byte pixels of the keyframe
+    key_pixels: Vec<u8>,
+}
+
+impl DeltaEncoder {
+    pub fn new(config: DeltaConfig) -> Self {
+        Self { config, keyframe: 0, since_key: 0, width: 0, height: 0, key_pixels: Vec::new() }
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.config.tile_size.clamp(16, 1024)
+    }
+
+    /// Decide how to send a `width`x`height` frame whose rows are `stride` bytes apart.
+    /// A `Plan::Key` takes the frame as the new keyframe.
+    pub fn plan(&mut self, pixels: &[u8], width: u32, height: u32, stride: usize) -> Plan {
+        let interval = self.config.keyframe_interval;
+        let due = self.keyframe == 0
+            || (width, height) != (self.width, self.height)
+            || (interval > 0 && self.since_key >= interval);
+        if !due {
+            let tile = self.tile_size();
+            let regions = self.changed(pixels, stride, tile);
+            let total = width.div_ceil(tile) * height.div_ceil(tile);
+            if regions.len() as f32 <= self.config.max_changed * total as f32 {
+                self.since_key += 1;
+                return Plan::Tiles { keyframe: self.keyframe, regions };
+            }
+        }
+
+        self.keyframe += 1;
+        self.since_key = 0;
+        self.width = width;
+        self.height = height;
+        self.key_pixels.clear();
+        let row_bytes = width as usize * 4;
+        for row in 0..height as usize {
+            self.key_pixels.extend_from_slice(&pixels[row * stride..row * stride + row_bytes]);
+        }
+        Plan::Key(self.keyframe)
+    }
+
+    /// The planned keyframe never reached the consumer; the next frame is a keyframe
+    pub fn invalidate(&mut self) {
+        self.keyframe += 1;
+        self.width = 0;
+        self.height = 0;
+    }
+
+    /// Tiles of the frame that differ from the keyframe
+    fn changed(&self, pixels: &[u8], stride: usize, tile: u32) -> Vec<(u32, u32, u32, u32)> {
+        let key_stride = self.width as usize * 4;
+        let mut regions = Vec::new();
+        for y in (0..self.height).step_by(tile as usize) {
+            let h = tile.min(self.height - y);
+            for x in (0..self.width).step_by(tile as usize) {
+                let w = tile.min(self.width - x);
+                let differs = (y..y + h).any(|row| {
+                    let start = x as usize * 4;
+                    let end = start + w as usize * 4;
+                    let row = row as usize;
+                    pixels[row * stride + start..row * stride + end]
+                        != self.key_pixels[row * key_stride + start..row * key_stride + end]
+                });
+                if differs {
+                    regions.push((x, y, w, h));
+                }
+            }
+        }
+        regions
+    }
+}
+
+/// Encode a frame for a delta sink: `encode_full` on keyframes, the changed tiles
+/// otherwise. None if encoding failed, in which case the next frame is a keyframe.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_frame(
+    encoder: &Mutex<DeltaEncoder>,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    order: PixelOrder,
+    format: FrameEncoding,
+    grayscale: bool,
+    quality: u8,
+    encode_full: impl FnOnce() -> Option<Vec<u8>>,
+) -> Option<(Vec<u8>, FrameDelta)> {
+    let mut encoder = encoder.lock();
+    let encoded = match encoder.plan(pixels, width, height, stride) {
+        Plan::Key(keyframe) => {
+            encode_full().map(|full| (full, FrameDelta { key: true, keyframe, tiles: Vec::new() }))
+        }
+        Plan::Tiles { keyframe, regions } => {
+            encode_tiles(&regions, pixels, stride, order, format, grayscale, quality)
+                .map(|(bytes, tiles)| (bytes, FrameDelta { key: false, keyframe, tiles }))
+        }
+    };
+    if encoded.is_none() {
+        encoder.invalidate();
+    }
+    encoded
+}
+
+/// Encode each region as its own image, concatenated
+fn encode_tiles(
+    regions: &[(u32, u32, u32, u32)],
+    pixels: &[u8],
+    stride: usize,
+    order: PixelOrder,
+    format: FrameEncoding,
+    grayscale: bool,
+    quality: u8,
+) -> Option<(Vec<u8>, Vec<Tile>)> {
+    let mut bytes = Vec::new();
+    let mut tiles = Vec::with_capacity(regions.len());
+    for &(x, y, width, height) in regions {
+        let (w, h) = (width as usize, height as usize);
+        let mut packed = Vec::with_capacity(w * h * 4);
+        for row in y as usize..y as usize + h {
+            let start = row * stride + x as usize * 4;
+            packed.extend_from_slice(&pixels[start..start + w * 4]);
+        }
+        let image = match (grayscale, order) {
+            (true, PixelOrder::Rgba) => encode::encode_luma(&encode::rgba_to_luma(&packed), width, height, format, quality),
+            (true, PixelOrder::Bgra) => {
+                encode::encode_luma(&encode::bgra_to_luma(&packed, w, h, w * 4), width, height, format, quality)
+            }
+            (false, PixelOrder::Rgba) => {
+                let rgb: Vec<u8> = packed.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+                encode::encode_rgb(&rgb, width, height, format, quality)
+            }
+            (false, PixelOrder::Bgra) => {
+                encode::encode_rgb(&encode::bgra_to_rgb(&packed, w, h, w * 4), width, height, format, quality)
+            }
+        }?;
+        tiles.push(Tile { x, y, width, height, len: image.len() as u32 });
+        bytes.extend_from_slice(&image);
+    }
+    Some((bytes, tiles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keyframe_interval: u32) -> DeltaConfig {
+        DeltaConfig { tile_size: 16, keyframe_interval, max_changed: 0.5 }
+    }
+
+    #[test]
+    fn sends_changed_tiles_against_the_keyframe() {
+        let (width, height) = (40u32, 20u32);
+        let stride = width as usize * 4;
+        let mut frame = vec![0u8; stride * height as usize];
+        let mut encoder = DeltaEncoder::new(config(0));
+        assert_eq!(encoder.plan(&frame, width, height, stride), Plan::Key(1));
+        assert_eq!(encoder.plan(&frame, width, height, stride), Plan::Tiles { keyframe: 1, regions: vec![] });
+
+        // A pixel in the last column and last row: the clipped bottom-right tile
+        frame[(19 * width as usize + 39) * 4] = 255;
+        assert_eq!(
+            encoder.plan(&frame, width, height, stride),
+            Plan::Tiles { keyframe: 1, regions: vec![(32, 16, 8, 4)] }
+        );
+        // Still against keyframe 1, so the change keeps being sent
+        frame[0] = 255;
+        assert_eq!(
+            encoder.plan(&frame, width, height, stride),
+            Plan::Tiles { keyframe: 1, regions: vec![(0, 0, 16, 16), (32, 16, 8, 4)] }
+        );
+    }
+
+    #[test]
+    fn keyframes_on_interval_size_change_and_large_changes() {
+        let (width, height) = (32u32, 32u32);
+        let stride = width as usize * 4;
+        let frame = vec![0u8; stride * height as usize];
+        let mut encoder = DeltaEncoder::new(config(2));
+        assert_eq!(encoder.plan(&frame, width, height, stride), Plan::Key(1));
+        assert!(matches!(encoder.plan(&frame, width, height, stride), Plan::Tiles { .. }));
+        assert!(matches!(encoder.plan(&frame, width, height, stride), Plan::Tiles { .. }));
+        assert_eq!(encoder.plan(&frame, width, height, stride), Plan::Key(2));
+
+        // 3 of 4 tiles changed is over half
+        let mut changed = frame.clone();
+        for &(x, y) in &[(0usize, 0usize), (16, 0), (0, 16)] {
+            changed[(y * width as usize + x) * 4] = 1;
+        }
+        assert_eq!(encoder.plan(&changed, width, height, stride), Plan::Key(3));
+
+        let small = vec![0u8; 16 * 4 * 16];
+        assert_eq!(encoder.plan(&small, 16, 16, 16 * 4), Plan::Key(4));
+        encoder.invalidate();
+        assert_eq!(encoder.plan(&small, 16, 16, 16 * 4), Plan::Key(6));
+    }
+}
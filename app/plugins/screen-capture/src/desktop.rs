@@ -1,16 +1,43 @@
-use crate::capture_config;
-use crate::error::Result;
+use crate::activity::Pacer;
+use crate::backpressure;
+use crate::capture_config::{self, FrameEncoding, TargetLostPolicy};
+use crate::cursor;
+use crate::delta::{self, FrameDelta, PixelOrder};
+use crate::frame_stats::{self, FrameStats};
+use crate::similarity;
+use crate::yuv::YuvFrame;
+#[cfg(target_os = "linux")]
+use crate::desktop_wayland;
+#[cfg(target_os = "windows")]
+use crate::wgc;
+use crate::encode;
+use crate::wire::FrameSink;
+use crate::frames;
+use crate::geometry::{self, CropRect, FrameGeometry, SourceMetrics};
+use crate::overlay;
+use crate::privacy::{self, PrivacyRegion};
+use crate::redaction;
+use crate::pause;
+use crate::secure_input;
+use crate::stats::Stage;
+use crate::error::{Error, Result};
+use crate::events;
+use crate::follow::{self, Follow};
+use crate::lifecycle::{self, LifecycleKind, LossTracker};
+use crate::recovery::{self, Backoff, ErrorAction};
 use crate::targets::{self, CaptureTarget, TargetKind};
 use image::codecs::jpeg::JpegEncoder;
 use image::imageops::FilterType;
 use image::RgbaImage;
-use parking_lot::RwLock;
+use crate::sessions::{self, SessionInfo};
+use crate::stitch;
+use parking_lot::Mutex;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tauri::ipc::Channel;
 use tauri::{plugin::PluginApi, AppHandle, Runtime};
 use tokio::sync::watch;
 use xcap::{Monitor, Window};
@@ -19,9 +46,11 @@ use xcap::{Monitor, Window};
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FrameData {
-    /// Raw JPEG bytes (sent as Uint8Array to frontend)
+    /// Encoded image bytes (sent as Uint8Array to frontend), in `format`
     #[serde(with = "serde_bytes")]
     pub frame: Vec<u8>,
+    /// Image format of `frame` (unless `yuv` is set)
+    pub format: FrameEncoding,
     /// Unix timestamp in seconds
     pub timestamp: f64,
     /// Frame dimensions
@@ -29,39 +58,89 @@ pub struct FrameData {
     pub height: u32,
     /// Frame sequence number
     pub frame_count: u64,
+    /// Source rect and DPI of what the frame shows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceMetrics>,
+    /// How to reassemble the frame on delta streams (see `delta`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<FrameDelta>,
+    /// Plane layout on YUV streams (see `yuv`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yuv: Option<YuvFrame>,
+    /// Change from the previous frame, 0–1 (see `similarity`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<f32>,
+    /// Luminance and dominant colors, with `capture_config::frame_stats` (see `frame_stats`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FrameStats>,
 }
 
 // Capture quality (max width / JPEG quality / FPS) is runtime-tunable via `capture_config`
 // — pushed from the frontend before capture starts and read when the capture loop begins
 // and as each frame is encoded.
 
-/// Shared capture state accessible across async contexts
-struct CaptureState {
-    /// Whether capture is currently active
+/// One capture stream (see `sessions`). Each session runs its own capture thread.
+struct CaptureSession {
+    id: String,
+    /// Capture target (None = primary monitor)
+    target_id: Mutex<Option<String>>,
+    /// Target the capture thread should switch to on its next iteration
+    pending_switch: Mutex<Option<SwitchRequest>>,
+    /// Whether the capture thread is running
     is_active: AtomicBool,
-    /// Total frames captured
+    /// Frames sent by this session
     frame_count: AtomicU64,
     /// Signal to stop the capture thread
     stop_signal: watch::Sender<bool>,
-    /// Currently selected capture target (None = primary monitor)
-    selected_target: RwLock<Option<String>>,
 }
 
-/// Global capture state - initialized on first use
-static CAPTURE_STATE: std::sync::OnceLock<Arc<CaptureState>> = std::sync::OnceLock::new();
-
-fn get_capture_state() -> Arc<CaptureState> {
-    CAPTURE_STATE
-        .get_or_init(|| {
-            let (tx, _rx) = watch::channel(false);
-            Arc::new(CaptureState {
-                is_active: AtomicBool::new(false),
-                frame_count: AtomicU64::new(0),
-                stop_signal: tx,
-                selected_target: RwLock::new(None),
-            })
-        })
-        .clone()
+/// A hot-switch to a new target (None = primary monitor)
+struct SwitchRequest {
+    target: Option<(TargetKind, u32)>,
+    target_id: Option<String>,
+}
+
+/// Where a session goes after its target disappeared
+enum Recovery {
+    Stop,
+    Capture(Option<(TargetKind, u32)>),
+    /// A switch arrived while waiting for the target
+    Switch(SwitchRequest),
+}
+
+impl CaptureSession {
+    fn take_switch(&self) -> Option<SwitchRequest> {
+        self.pending_switch.lock().take()
+    }
+
+    /// The capture thread has moved to `request`'s target: record it and announce it to
+    /// the frontend
+    fn switched(&self, request: SwitchRequest) {
+        *self.target_id.lock() = request.target_id.clone();
+        events::emit(events::TARGET_SWITCHED, events::TargetSwitched {
+            session_id: self.id.clone(),
+            target_id: request.target_id,
+            frame_count: self.frame_count.load(Ordering::SeqCst),
+        });
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            id: self.id.clone(),
+            target_id: self.target_id.lock().clone(),
+            is_active: self.is_active.load(Ordering::SeqCst),
+            frame_count: self.frame_count.load(Ordering::SeqCst),
+            following: follow::is_following(&self.id),
+        }
+    }
+}
+
+/// Running sessions by id - initialized on first use
+static SESSIONS: std::sync::OnceLock<Mutex<HashMap<String, Arc<CaptureSession>>>> =
+    std::sync::OnceLock::new();
+
+fn sessions() -> &'static Mutex<HashMap<String, Arc<CaptureSession>>> {
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
 pub fn init<R: Runtime, C: serde::de::DeserializeOwned>(
@@ -72,237 +151,461 @@ pub fn init<R: Runtime, C: serde::de::DeserializeOwned>(
     Ok(())
 }
 
+/// Signal a session's thread to stop. Geometry is cleared right away so nothing maps
+/// points onto a stream that is going away.
+fn signal_stop(session: &CaptureSession) {
+    // Mark as inactive FIRST to prevent race condition on restart
+    session.is_active.store(false, Ordering::SeqCst);
+    let _ = session.stop_signal.send(true);
+    geometry::set_for(&session.id, None);
+    frame_stats::clear(&session.id);
+}
+
+/// Stop the default capture session
 pub async fn stop_capture() -> Result<()> {
-    let state = get_capture_state();
+    stop_capture_session(sessions::DEFAULT_SESSION)
+}
 
-    if !state.is_active.load(Ordering::SeqCst) {
-        log::info!("[ScreenCapture] Capture not active");
+/// Stop one capture session; other sessions keep running
+pub fn stop_capture_session(session_id: &str) -> Result<()> {
+    let Some(session) = sessions().lock().remove(session_id) else {
+        log::info!("[ScreenCapture] Capture session {} not active", session_id);
         return Ok(());
-    }
-
-    log::info!("[ScreenCapture] Stopping capture...");
-
-    // Mark as inactive FIRST to prevent race condition on restart
-    state.is_active.store(false, Ordering::SeqCst);
+    };
 
-    // Send stop signal to the capture thread
-    let _ = state.stop_signal.send(true);
+    log::info!("[ScreenCapture] Stopping capture session {}...", session_id);
+    follow::stop(session_id);
+    backpressure::unregister(session_id);
+    signal_stop(&session);
+    log::info!("[ScreenCapture] Capture session {} stopped", session_id);
+    Ok(())
+}
 
-    // Clear the selected target
-    {
-        let mut target = state.selected_target.write();
-        *target = None;
+/// Stop every capture session
+pub fn stop_all_sessions() -> Result<()> {
+    let stopped: Vec<Arc<CaptureSession>> = sessions().lock().drain().map(|(_, s)| s).collect();
+    for session in &stopped {
+        signal_stop(session);
     }
-
-    log::info!("[ScreenCapture] Capture stopped");
+    backpressure::unregister_all();
+    log::info!("[ScreenCapture] Stopped {} capture session(s)", stopped.len());
     Ok(())
 }
 
-/// Get broadcast status
-pub fn get_broadcast_status() -> Result<serde_json::Value> {
-    let state = get_capture_state();
+/// All running capture sessions
+pub fn list_capture_sessions() -> Vec<SessionInfo> {
+    let mut list: Vec<SessionInfo> = sessions().lock().values().map(|s| s.info()).collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
 
-    let is_active = state.is_active.load(Ordering::SeqCst);
-    let frame_count = state.frame_count.load(Ordering::SeqCst);
-    let selected_target = state.selected_target.read().clone();
+/// Get broadcast status. Top-level fields describe the default session.
+pub fn get_broadcast_status() -> Result<serde_json::Value> {
+    let default = sessions().lock().get(sessions::DEFAULT_SESSION).map(|s| s.info());
 
     Ok(serde_json::json!({
-        "isActive": is_active,
-        "frameCount": frame_count,
-        "targetId": selected_target
+        "isActive": default.as_ref().map_or(false, |s| s.is_active),
+        "frameCount": default.as_ref().map_or(0, |s| s.frame_count),
+        "targetId": default.and_then(|s| s.target_id),
+        "sessions": list_capture_sessions()
     }))
 }
 
 /// Get all available capture targets
 pub fn get_capture_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget>> {
-    targets::get_all_targets(include_thumbnails)
+    // Wayland hides other clients' surfaces; the portal dialog is the only way to pick
+    #[cfg(target_os = "linux")]
+    if desktop_wayland::is_wayland() {
+        return desktop_wayland::select_targets();
+    }
+    let list = targets::get_all_targets(include_thumbnails)?;
+    #[cfg(target_os = "windows")]
+    let list = {
+        let backends = wgc::available_backends();
+        list.into_iter()
+            .map(|target| match target.kind {
+                // Stitching always goes through xcap
+                TargetKind::VirtualDesktop => CaptureTarget { backends: vec![capture_config::CaptureBackend::Xcap], ..target },
+                _ => CaptureTarget { backends: backends.clone(), ..target },
+            })
+            .collect()
+    };
+    Ok(list)
+}
+
+/// Point a running session at another target without restarting it. The capture thread
+/// picks the new source up before its next frame; once it has, the session reports the
+/// new target and `events::TARGET_SWITCHED` is emitted.
+/// `targets::ACTIVE_WINDOW` and `app:{name}` make the session follow the focused window
+/// or that app's windows from then on.
+pub fn switch_capture_target(session_id: &str, target_id: Option<String>) -> Result<()> {
+    if let Some(follow) = target_id.as_deref().and_then(Follow::parse) {
+        let session = sessions().lock().get(session_id).cloned().ok_or(Error::NotStarted)?;
+        let current = follow_start_window(&follow)?;
+        if let Some(window_id) = current {
+            retarget(session_id, Some(format!("window:{}", window_id)))?;
+        }
+        start_following(&session, follow, current);
+        return Ok(());
+    }
+    follow::stop(session_id);
+    retarget(session_id, target_id)
+}
+
+/// Window a following session starts on (None = keep the current target)
+fn follow_start_window(follow: &Follow) -> Result<Option<u32>> {
+    #[cfg(target_os = "linux")]
+    if desktop_wayland::is_wayland() {
+        return Err(Error::Platform("Following windows isn't supported on Wayland".to_string()));
+    }
+    follow.initial_window()
+}
+
+/// Start the follow watcher for `session`, which currently shows window `current`
+fn start_following(session: &Arc<CaptureSession>, follow: Follow, current: Option<u32>) {
+    let watched = session.clone();
+    let id = session.id.clone();
+    follow::start(
+        &session.id,
+        follow,
+        current,
+        move || {
+            watched.is_active.load(Ordering::SeqCst)
+                && sessions().lock().get(&watched.id).is_some_and(|s| Arc::ptr_eq(s, &watched))
+        },
+        move |target_id| retarget(&id, Some(target_id)),
+    );
 }
 
-/// Start capture with channel-based streaming (push instead of poll)
+/// Queue a hot-switch of a running session to `target_id`
+fn retarget(session_id: &str, target_id: Option<String>) -> Result<()> {
+    let target = target_id.as_deref().map(targets::parse_target_id).transpose()?;
+    let session = sessions().lock().get(session_id).cloned().ok_or(Error::NotStarted)?;
+
+    // Check the target exists now so the caller gets the error, not the capture thread
+    #[cfg(target_os = "linux")]
+    if desktop_wayland::is_wayland() {
+        desktop_wayland::resolve_stream(target.clone())?;
+    } else {
+        CaptureSource::find(target.as_ref())?;
+    }
+    #[cfg(not(target_os = "linux"))]
+    CaptureSource::find(target.as_ref())?;
+
+    log::info!("[ScreenCapture] Switching session {} to {:?}", session_id, target_id);
+    *session.pending_switch.lock() = Some(SwitchRequest { target, target_id });
+    Ok(())
+}
+
+/// Start the default capture session with channel-based streaming (push instead of poll).
 /// Frames are pushed to the frontend as they're captured
 pub fn start_capture_stream(
     target_id: Option<String>,
-    on_frame: Channel<FrameData>,
+    on_frame: FrameSink,
 ) -> Result<()> {
-    let state = get_capture_state();
+    start_capture_session(sessions::DEFAULT_SESSION, target_id, on_frame)
+}
 
-    log::info!("[ScreenCapture] Starting channel-based capture stream with target: {:?}", target_id);
+/// Start (or restart) a named capture session. Sessions with other ids are untouched.
+pub fn start_capture_session(
+    session_id: &str,
+    target_id: Option<String>,
+    on_frame: FrameSink,
+) -> Result<()> {
+    log::info!(
+        "[ScreenCapture] Starting capture session {} with target: {:?}",
+        session_id,
+        target_id
+    );
 
-    // Send stop signal first to ensure any existing thread stops
-    let _ = state.stop_signal.send(true);
+    // Following sessions start on the picked window (the primary monitor if none)
+    let follow_from = match target_id.as_deref().and_then(Follow::parse) {
+        Some(follow) => {
+            let window = follow_start_window(&follow)?;
+            Some((follow, window))
+        }
+        None => None,
+    };
+    let target_id = match &follow_from {
+        Some((_, window_id)) => window_id.map(|id| format!("window:{}", id)),
+        None => target_id,
+    };
 
-    // Wait for any existing capture thread to exit
-    if state.is_active.load(Ordering::SeqCst) {
-        log::info!("[ScreenCapture] Waiting for existing capture to stop...");
-        std::thread::sleep(Duration::from_millis(100));
+    // Parse up front so a bad id fails the call instead of the thread
+    let target = target_id.as_deref().map(targets::parse_target_id).transpose()?;
+    #[cfg(target_os = "linux")]
+    if desktop_wayland::is_wayland() {
+        desktop_wayland::check_target(target.as_ref())?;
     }
+    follow::stop(session_id);
 
-    // Now reset the stop signal to false BEFORE creating the receiver
-    let _ = state.stop_signal.send(false);
-
-    // Small delay to ensure the channel value is settled
-    std::thread::sleep(Duration::from_millis(10));
+    let (stop_signal, stop_rx) = watch::channel(false);
+    let session = Arc::new(CaptureSession {
+        id: session_id.to_string(),
+        target_id: Mutex::new(target_id.clone()),
+        pending_switch: Mutex::new(None),
+        is_active: AtomicBool::new(true),
+        frame_count: AtomicU64::new(0),
+        stop_signal,
+    });
 
-    // Update selected target
-    {
-        let mut target = state.selected_target.write();
-        *target = target_id.clone();
+    // Replace any session already running under this id
+    let previous = sessions().lock().insert(session_id.to_string(), session.clone());
+    if let Some(previous) = previous {
+        let was_active = previous.is_active.load(Ordering::SeqCst);
+        signal_stop(&previous);
+        if was_active {
+            log::info!("[ScreenCapture] Waiting for existing capture to stop...");
+            std::thread::sleep(Duration::from_millis(100));
+        }
     }
 
-    // Mark as active
-    state.is_active.store(true, Ordering::SeqCst);
-    state.frame_count.store(0, Ordering::SeqCst);
-
-    // Create a receiver for the stop signal AFTER resetting it
-    let stop_rx = state.stop_signal.subscribe();
-
-    // Verify the stop signal is actually false
-    if *stop_rx.borrow() {
-        log::error!("[ScreenCapture] Stop signal is still true after reset! This is a bug.");
-        let _ = state.stop_signal.send(false);
-        std::thread::sleep(Duration::from_millis(10));
+    backpressure::register(session_id, on_frame.flow());
+    if let Some((follow, current)) = follow_from {
+        start_following(&session, follow, current);
     }
 
-    let capture_state = state.clone();
-
     // Spawn the capture thread with channel
     std::thread::spawn(move || {
-        log::info!("[ScreenCapture] Channel capture thread started");
-
-        let capture_result = match &target_id {
-            Some(id) => {
-                match targets::parse_target_id(id) {
-                    Ok((kind, numeric_id)) => {
-                        log::info!("[ScreenCapture] Stream capturing {:?} with id {}", kind, numeric_id);
-                        run_capture_loop_with_channel(capture_state, stop_rx, Some((kind, numeric_id)), on_frame)
-                    }
-                    Err(e) => {
-                        log::error!("[ScreenCapture] Failed to parse target ID: {:?}", e);
-                        Err(e)
-                    }
-                }
-            }
-            None => {
-                log::info!("[ScreenCapture] Stream capturing primary monitor");
-                run_capture_loop_with_channel(capture_state, stop_rx, None, on_frame)
+        log::info!("[ScreenCapture] Capture thread for session {} started", session.id);
+
+        match &target {
+            Some((kind, numeric_id)) => {
+                log::info!("[ScreenCapture] Stream capturing {:?} with id {}", kind, numeric_id)
             }
+            None => log::info!("[ScreenCapture] Stream capturing primary monitor"),
+        }
+
+        #[cfg(target_os = "linux")]
+        let result = if desktop_wayland::is_wayland() {
+            run_wayland_capture(session.clone(), stop_rx, target, on_frame)
+        } else {
+            run_capture_loop_with_channel(session.clone(), stop_rx, target, on_frame)
+        };
+        #[cfg(target_os = "windows")]
+        let result = if wgc::should_use(on_frame.config().backend()) {
+            run_wgc_capture(session.clone(), stop_rx, target, on_frame)
+        } else {
+            run_capture_loop_with_channel(session.clone(), stop_rx, target, on_frame)
         };
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        let result = run_capture_loop_with_channel(session.clone(), stop_rx, target, on_frame);
 
-        if let Err(e) = capture_result {
+        if let Err(e) = result {
             log::error!("[ScreenCapture] Channel capture loop failed: {:?}", e);
         }
+
+        // Clean up unless a newer session has taken over the id
+        session.is_active.store(false, Ordering::SeqCst);
+        let mut registry = sessions().lock();
+        let replaced = registry.get(&session.id).is_some_and(|s| !Arc::ptr_eq(s, &session));
+        if !replaced {
+            registry.remove(&session.id);
+            geometry::set_for(&session.id, None);
+            frame_stats::clear(&session.id);
+        }
     });
 
-    log::info!("[ScreenCapture] Channel capture stream started");
+    log::info!("[ScreenCapture] Capture session {} started", session_id);
     Ok(())
 }
 
+/// Report that `target` is gone and apply the stream's `policy`. With `Wait` this blocks,
+/// polling `exists` until the target is back, a switch arrives or the session stops.
+fn recover_lost_target(
+    session: &CaptureSession,
+    stop_rx: &watch::Receiver<bool>,
+    target: Option<(TargetKind, u32)>,
+    policy: TargetLostPolicy,
+    exists: impl Fn(Option<&(TargetKind, u32)>) -> bool,
+) -> Recovery {
+    let target_id = session.target_id.lock().clone();
+    lifecycle::notify(lifecycle::lost_kind(target.as_ref()), &session.id, target_id.clone(), policy);
+    match policy {
+        // Falling back from the primary monitor to itself would just fail again
+        TargetLostPolicy::FallbackToPrimary if target.is_some() => {
+            *session.target_id.lock() = None;
+            Recovery::Capture(None)
+        }
+        TargetLostPolicy::Stop | TargetLostPolicy::FallbackToPrimary => Recovery::Stop,
+        TargetLostPolicy::Wait => loop {
+            std::thread::sleep(lifecycle::RETRY_INTERVAL);
+            if *stop_rx.borrow() {
+                return Recovery::Stop;
+            }
+            if let Some(request) = session.take_switch() {
+                return Recovery::Switch(request);
+            }
+            if exists(target.as_ref()) {
+                lifecycle::notify(LifecycleKind::TargetRestored, &session.id, target_id, policy);
+                return Recovery::Capture(target);
+            }
+        },
+    }
+}
+
 /// Run the capture loop, pushing frames through a channel
 fn run_capture_loop_with_channel(
-    capture_state: Arc<CaptureState>,
+    session: Arc<CaptureSession>,
     stop_rx: watch::Receiver<bool>,
     target: Option<(TargetKind, u32)>,
-    on_frame: Channel<FrameData>,
+    on_frame: FrameSink,
 ) -> Result<()> {
-    let target_frame_time = Duration::from_millis(1000 / capture_config::target_fps().max(1) as u64);
-
-    enum CaptureSource {
-        Monitor(Monitor),
-        Window(Window),
-    }
-
-    let source = match &target {
-        Some((TargetKind::Monitor, id)) => {
-            let monitors = Monitor::all()
-                .map_err(|e| crate::error::Error::Platform(format!("Failed to get monitors: {}", e)))?;
-            let monitor = monitors.into_iter()
-                .find(|m| m.id().ok() == Some(*id))
-                .ok_or_else(|| crate::error::Error::Platform(format!("Monitor {} not found", id)))?;
-            log::info!(
-                "[ScreenCapture] Channel capturing monitor: {} ({}x{})",
-                monitor.name().unwrap_or_default(),
-                monitor.width().unwrap_or(0),
-                monitor.height().unwrap_or(0)
-            );
-            CaptureSource::Monitor(monitor)
-        }
-        Some((TargetKind::Window, id)) => {
-            let windows = Window::all()
-                .map_err(|e| crate::error::Error::Platform(format!("Failed to get windows: {}", e)))?;
-            let window = windows.into_iter()
-                .find(|w| w.id().ok() == Some(*id))
-                .ok_or_else(|| crate::error::Error::Platform(format!("Window {} not found", id)))?;
-            log::info!(
-                "[ScreenCapture] Channel capturing window: {} ({}x{})",
-                window.title().unwrap_or_default(),
-                window.width().unwrap_or(0),
-                window.height().unwrap_or(0)
-            );
-            CaptureSource::Window(window)
-        }
-        None => {
-            let monitors = Monitor::all()
-                .map_err(|e| crate::error::Error::Platform(format!("Failed to get monitors: {}", e)))?;
-            let monitor = monitors.into_iter()
-                .find(|m| m.is_primary().unwrap_or(false))
-                .or_else(|| Monitor::all().ok().and_then(|m| m.into_iter().next()))
-                .ok_or_else(|| crate::error::Error::Platform("No monitors found".to_string()))?;
-            log::info!(
-                "[ScreenCapture] Channel capturing primary monitor: {} ({}x{}, {}fps)",
-                monitor.name().unwrap_or_default(),
-                monitor.width().unwrap_or(0),
-                monitor.height().unwrap_or(0),
-                capture_config::target_fps()
-            );
-            CaptureSource::Monitor(monitor)
-        }
-    };
+    let mut target = target;
+    let config = *on_frame.config();
+    let mut source = CaptureSource::find(target.as_ref())?;
+    // Looked up with the source; a window dragged to another display keeps the old scale
+    // until it is re-resolved
+    let mut display_scale = source.display_scale();
+    let mut loss = LossTracker::default();
+    let mut backoff = Backoff::default();
+    let mut pacer = Pacer::new(&on_frame);
 
     let mut frame_count: u64 = 0;
 
     loop {
         let frame_start = Instant::now();
 
+        // Hot-switch: swap the source in place, the stream keeps going
+        if let Some(request) = session.take_switch() {
+            match CaptureSource::find(request.target.as_ref()) {
+                Ok(next) => {
+                    source = next;
+                    display_scale = source.display_scale();
+                    target = request.target.clone();
+                    session.switched(request);
+                    loss.success();
+                    backoff.success();
+                }
+                Err(e) => log::error!("[ScreenCapture] Target switch failed: {}", e),
+            }
+        }
+        // Re-read every frame so FPS changes apply to the running stream
+        let target_frame_time = pacer.frame_time(frame_start);
+
         // Check stop signal
         if *stop_rx.borrow() {
             log::info!("[ScreenCapture] Channel capture stop signal received");
             break;
         }
 
+        // Drop frames while something (e.g. screen sharing) holds the pause gate
+        if pause::is_paused() {
+            std::thread::sleep(target_frame_time);
+            continue;
+        }
+
+        // Never grab the screen while a password field has focus
+        if secure_input::should_skip_frame() {
+            std::thread::sleep(target_frame_time);
+            continue;
+        }
+
         // Capture frame
-        let capture_result = match &source {
-            CaptureSource::Monitor(monitor) => monitor.capture_image(),
-            CaptureSource::Window(window) => window.capture_image(),
-        };
+        let capture_start = Instant::now();
+        let capture_result = source.capture_image(config.max_width());
+        on_frame.record(Stage::Capture, capture_start.elapsed());
 
         match capture_result {
-            Ok(image) => {
-                // Process and send frame through channel
-                if let Some(frame_data) = process_frame_for_channel(&image, frame_count) {
-                    frame_count += 1;
-
-                    if frame_count == 1 {
-                        log::info!(
-                            "[ScreenCapture] First channel frame sent ({}x{}, {} bytes)",
-                            frame_data.width,
-                            frame_data.height,
-                            frame_data.frame.len()
-                        );
-                    }
+            Ok(mut image) => {
+                loss.success();
+                backoff.success();
+                pacer.observe(image.as_raw(), image.width(), image.height(), Instant::now());
+                // Windows can move between frames, so re-read the source rect each time
+                let (x, y, w, h) = source.rect();
+                let screen_width = f64::from(w.unwrap_or(image.width()).max(1));
+                let scale = f64::from(image.width()) / screen_width;
 
-                    // Push frame to frontend via channel
-                    if let Err(e) = on_frame.send(frame_data) {
-                        log::error!("[ScreenCapture] Failed to send frame through channel: {:?}", e);
-                        // Channel closed, stop capture
-                        break;
+                // xcap leaves the pointer out; draw it in before the frame is downscaled
+                if config.show_cursor() {
+                    if let Some((cursor_x, cursor_y)) = cursor::position() {
+                        let px = (f64::from(cursor_x - x.unwrap_or(0)) * scale).round() as i64;
+                        let py = (f64::from(cursor_y - y.unwrap_or(0)) * scale).round() as i64;
+                        cursor::draw(&mut image, px, py, scale);
                     }
+                }
 
-                    // Update shared state frame count
-                    capture_state.frame_count.store(frame_count, Ordering::SeqCst);
+                let source_rect = SourceMetrics::new(
+                    f64::from(x.unwrap_or(0)),
+                    f64::from(y.unwrap_or(0)),
+                    screen_width,
+                    f64::from(h.unwrap_or(image.height())),
+                    display_scale,
+                );
+                if !deliver_frame(&session, &on_frame, &image, &mut frame_count, source_rect) {
+                    // Channel closed, stop capture
+                    break;
                 }
             }
             Err(e) => {
-                log::error!("[ScreenCapture] Channel capture failed: {:?}", e);
+                // Log the first failure of a run, not one per frame
+                if !loss.is_failing() {
+                    log::error!("[ScreenCapture] Channel capture failed: {:?}", e);
+                }
+                let retry_in = backoff.failure();
+                if loss.failure(Instant::now()) {
+                    match CaptureSource::find(target.as_ref()) {
+                        // Still there (possibly under a fresh handle); keep trying
+                        Ok(fresh) => {
+                            source = fresh;
+                            display_scale = source.display_scale();
+                        }
+                        Err(_) => {
+                            let exists = |t: Option<&(TargetKind, u32)>| CaptureSource::find(t).is_ok();
+                            let policy = config.target_lost_policy();
+                            let (next, request) =
+                                match recover_lost_target(&session, &stop_rx, target.clone(), policy, exists) {
+                                    Recovery::Stop => break,
+                                    Recovery::Capture(next) => (next, None),
+                                    Recovery::Switch(request) => (request.target.clone(), Some(request)),
+                                };
+                            match CaptureSource::find(next.as_ref()) {
+                                Ok(next_source) => {
+                                    source = next_source;
+                                    display_scale = source.display_scale();
+                                    target = next;
+                                    if let Some(request) = request {
+                                        session.switched(request);
+                                    }
+                                    loss.success();
+                                    backoff.success();
+                                    continue;
+                                }
+                                Err(e) => {
+                                    log::error!("[ScreenCapture] Recovery capture failed: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // The source is there but can't be read: give up after too many tries
+                if backoff.exhausted(config.max_capture_failures()) {
+                    let target_id = session.target_id.lock().clone();
+                    match recovery::give_up(&session.id, target_id, &e, &backoff, config.error_fallback()) {
+                        ErrorAction::Stopped => break,
+                        ErrorAction::FallbackToPrimary => match CaptureSource::find(None) {
+                            Ok(primary) => {
+                                source = primary;
+                                display_scale = source.display_scale();
+                                target = None;
+                                *session.target_id.lock() = None;
+                                loss.success();
+                                backoff.success();
+                                continue;
+                            }
+                            Err(e) => {
+                                log::error!("[ScreenCapture] Fallback to primary monitor failed: {}", e);
+                                break;
+                            }
+                        },
+                    }
+                }
+                if recovery::wait(retry_in.max(target_frame_time), &stop_rx) {
+                    break;
+                }
+                continue;
             }
         }
 
@@ -313,58 +616,523 @@ fn run_capture_loop_with_channel(
         }
     }
 
-    log::info!("[ScreenCapture] Channel capture thread exiting after {} frames", frame_count);
-    capture_state.is_active.store(false, Ordering::SeqCst);
+    log::info!(
+        "[ScreenCapture] Capture thread for session {} exiting after {} frames",
+        session.id,
+        frame_count
+    );
     Ok(())
 }
 
+/// Wayland variant of the capture loop: frames come from the portal's PipeWire stream
+/// (which paces them and embeds the cursor itself) instead of xcap polling
+#[cfg(target_os = "linux")]
+fn run_wayland_capture(
+    session: Arc<CaptureSession>,
+    stop_rx: watch::Receiver<bool>,
+    target: Option<(TargetKind, u32)>,
+    on_frame: FrameSink,
+) -> Result<()> {
+    let mut target = target;
+    // A switch being made, and the target to go back to if it fails
+    let mut switching: Option<(SwitchRequest, Option<(TargetKind, u32)>)> = None;
+    // Each pass runs one PipeWire stream; a hot-switch ends it and opens the next
+    loop {
+        let stream = match desktop_wayland::resolve_stream(target.clone()) {
+            Ok(stream) => stream,
+            Err(e) => match switching.take() {
+                Some((_, previous)) => {
+                    log::error!("[ScreenCapture] Target switch failed: {}", e);
+                    target = previous;
+                    continue;
+                }
+                None => return Err(e),
+            },
+        };
+        if let Some((request, _)) = switching.take() {
+            session.switched(request);
+        }
+        log::info!(
+            "[ScreenCapture] Wayland capturing {:?} via PipeWire node {}",
+            stream.kind,
+            stream.node_id
+        );
+
+        let (x, y) = stream.position.unwrap_or((0, 0));
+        let size = stream.size;
+        let thread_session = session.clone();
+        let switch = Arc::new(Mutex::new(None));
+        let switch_slot = switch.clone();
+        let sink = on_frame.clone();
+        let mut frame_count = session.frame_count.load(Ordering::SeqCst);
+        let result = desktop_wayland::run_capture(&stream, &on_frame, stop_rx.clone(), move |image, copy| {
+            sink.record(Stage::Capture, copy);
+            if let Some(request) = thread_session.take_switch() {
+                *switch_slot.lock() = Some(request);
+                return false;
+            }
+            let (width, height) = size.unwrap_or((image.width() as i32, image.height() as i32));
+            // Portal positions and sizes are logical; the stream is in physical pixels
+            let width = f64::from(width.max(1));
+            let source_rect = SourceMetrics::logical(
+                f64::from(x),
+                f64::from(y),
+                width,
+                f64::from(height.max(1)),
+                f64::from(image.width()) / width,
+            );
+            deliver_frame(&thread_session, &sink, &image, &mut frame_count, source_rect)
+        });
+        if let Err(Error::TargetLost) = result {
+            let exists = |t: Option<&(TargetKind, u32)>| desktop_wayland::resolve_stream(t.cloned()).is_ok();
+            let policy = on_frame.config().target_lost_policy();
+            match recover_lost_target(&session, &stop_rx, target.clone(), policy, exists) {
+                Recovery::Stop => break,
+                Recovery::Capture(next) => target = next,
+                Recovery::Switch(request) => {
+                    let previous = std::mem::replace(&mut target, request.target.clone());
+                    switching = Some((request, previous));
+                }
+            }
+            continue;
+        }
+        result?;
+
+        let Some(request) = switch.lock().take() else {
+            break;
+        };
+        let previous = std::mem::replace(&mut target, request.target.clone());
+        switching = Some((request, previous));
+    }
+
+    log::info!(
+        "[ScreenCapture] Capture thread for session {} exiting after {} frames",
+        session.id,
+        session.frame_count.load(Ordering::SeqCst)
+    );
+    Ok(())
+}
+
+/// Windows variant of the capture loop: frames come from Windows.Graphics.Capture, which
+/// also sees hardware-accelerated/UWP windows and draws the cursor itself
+#[cfg(target_os = "windows")]
+fn run_wgc_capture(
+    session: Arc<CaptureSession>,
+    stop_rx: watch::Receiver<bool>,
+    target: Option<(TargetKind, u32)>,
+    on_frame: FrameSink,
+) -> Result<()> {
+    let mut target = target;
+    let mut frame_count: u64 = 0;
+    // A switch being made, and the target to go back to if it fails
+    let mut switching: Option<(SwitchRequest, Option<(TargetKind, u32)>)> = None;
+    // Each pass runs one WGC session; a hot-switch ends it and opens the next
+    loop {
+        // xcap still resolves the target and tracks where it sits on screen
+        let source = match CaptureSource::find(target.as_ref()) {
+            Ok(source) => source,
+            Err(e) => match switching.take() {
+                Some((_, previous)) => {
+                    log::error!("[ScreenCapture] Target switch failed: {}", e);
+                    target = previous;
+                    continue;
+                }
+                None => return Err(e),
+            },
+        };
+        if let Some((request, _)) = switching.take() {
+            session.switched(request);
+        }
+        let display_scale = source.display_scale();
+        let (kind, handle) = match &source {
+            CaptureSource::Monitor(monitor) => (TargetKind::Monitor, monitor.id().unwrap_or(0)),
+            CaptureSource::Window(window) => (TargetKind::Window, window.id().unwrap_or(0)),
+            // WGC captures one display per item; stitching needs the xcap loop
+            CaptureSource::VirtualDesktop(_) => {
+                return run_capture_loop_with_channel(session, stop_rx, target, on_frame);
+            }
+        };
+
+        let mut switch = None;
+        let result = wgc::run_capture(kind, handle, &on_frame, stop_rx.clone(), |image, readback| {
+            on_frame.record(Stage::Capture, readback);
+            if let Some(request) = session.take_switch() {
+                switch = Some(request);
+                return false;
+            }
+            let (x, y, w, h) = source.rect();
+            let source_rect = SourceMetrics::new(
+                f64::from(x.unwrap_or(0)),
+                f64::from(y.unwrap_or(0)),
+                f64::from(w.unwrap_or(image.width()).max(1)),
+                f64::from(h.unwrap_or(image.height()).max(1)),
+                display_scale,
+            );
+            deliver_frame(&session, &on_frame, &image, &mut frame_count, source_rect)
+        });
+        if let Err(Error::TargetLost) = result {
+            let exists = |t: Option<&(TargetKind, u32)>| CaptureSource::find(t).is_ok();
+            let policy = on_frame.config().target_lost_policy();
+            match recover_lost_target(&session, &stop_rx, target.clone(), policy, exists) {
+                Recovery::Stop => break,
+                Recovery::Capture(next) => target = next,
+                Recovery::Switch(request) => {
+                    let previous = std::mem::replace(&mut target, request.target.clone());
+                    switching = Some((request, previous));
+                }
+            }
+            continue;
+        }
+        result?;
+
+        let Some(request) = switch else {
+            break;
+        };
+        let previous = std::mem::replace(&mut target, request.target.clone());
+        switching = Some((request, previous));
+    }
+
+    log::info!(
+        "[ScreenCapture] Capture thread for session {} exiting after {} frames",
+        session.id,
+        frame_count
+    );
+    Ok(())
+}
+
+/// An xcap capture source
+enum CaptureSource {
+    Monitor(Monitor),
+    Window(Window),
+    /// Every monitor, stitched into one frame
+    VirtualDesktop(Vec<Monitor>),
+}
+
+impl CaptureSource {
+    /// Resolve a parsed target id (None = primary monitor)
+    fn find(target: Option<&(TargetKind, u32)>) -> Result<Self> {
+        let source = match target {
+            Some((TargetKind::Monitor, id)) => {
+                let monitors = Monitor::all()
+                    .map_err(|e| crate::error::Error::Platform(format!("Failed to get monitors: {}", e)))?;
+                let monitor = monitors.into_iter()
+                    .find(|m| m.id().ok() == Some(*id))
+                    .ok_or_else(|| crate::error::Error::Platform(format!("Monitor {} not found", id)))?;
+                log::info!(
+                    "[ScreenCapture] Channel capturing monitor: {} ({}x{})",
+                    monitor.name().unwrap_or_default(),
+                    monitor.width().unwrap_or(0),
+                    monitor.height().unwrap_or(0)
+                );
+                CaptureSource::Monitor(monitor)
+            }
+            Some((TargetKind::Window, id)) => {
+                let windows = Window::all()
+                    .map_err(|e| crate::error::Error::Platform(format!("Failed to get windows: {}", e)))?;
+                let window = windows.into_iter()
+                    .find(|w| w.id().ok() == Some(*id))
+                    .ok_or_else(|| crate::error::Error::Platform(format!("Window {} not found", id)))?;
+                log::info!(
+                    "[ScreenCapture] Channel capturing window: {} ({}x{})",
+                    window.title().unwrap_or_default(),
+                    window.width().unwrap_or(0),
+                    window.height().unwrap_or(0)
+                );
+                CaptureSource::Window(window)
+            }
+            Some((TargetKind::VirtualDesktop, _)) => {
+                let monitors = stitch::monitors()?;
+                log::info!("[ScreenCapture] Channel capturing virtual desktop ({} monitors)", monitors.len());
+                CaptureSource::VirtualDesktop(monitors)
+            }
+            None => {
+                let monitors = Monitor::all()
+                    .map_err(|e| crate::error::Error::Platform(format!("Failed to get monitors: {}", e)))?;
+                let monitor = monitors.into_iter()
+                    .find(|m| m.is_primary().unwrap_or(false))
+                    .or_else(|| Monitor::all().ok().and_then(|m| m.into_iter().next()))
+                    .ok_or_else(|| crate::error::Error::Platform("No monitors found".to_string()))?;
+                log::info!(
+                    "[ScreenCapture] Channel capturing primary monitor: {} ({}x{})",
+                    monitor.name().unwrap_or_default(),
+                    monitor.width().unwrap_or(0),
+                    monitor.height().unwrap_or(0)
+                );
+                CaptureSource::Monitor(monitor)
+            }
+        };
+        Ok(source)
+    }
+
+    /// Grab a frame; `max_width` caps the stitched virtual desktop as it is composited
+    fn capture_image(&self, max_width: u32) -> Result<RgbaImage> {
+        let captured = match self {
+            CaptureSource::Monitor(monitor) => monitor.capture_image(),
+            CaptureSource::Window(window) => window.capture_image(),
+            CaptureSource::VirtualDesktop(monitors) => return stitch::capture_at(monitors, max_width),
+        };
+        captured.map_err(|e| Error::Platform(e.to_string()))
+    }
+
+    /// Screen rect (x, y, width, height); windows can move, so this is read per frame
+    fn rect(&self) -> (Option<i32>, Option<i32>, Option<u32>, Option<u32>) {
+        match self {
+            CaptureSource::Monitor(monitor) => (monitor.x().ok(), monitor.y().ok(), monitor.width().ok(), monitor.height().ok()),
+            CaptureSource::Window(window) => (window.x().ok(), window.y().ok(), window.width().ok(), window.height().ok()),
+            CaptureSource::VirtualDesktop(monitors) => {
+                match stitch::bounds(monitors.iter().filter_map(stitch::Rect::of)) {
+                    Some(bounds) => (Some(bounds.x), Some(bounds.y), Some(bounds.width), Some(bounds.height)),
+                    None => (None, None, None, None),
+                }
+            }
+        }
+    }
+
+    /// Scale factor of the display the source is on (1.0 if unknown); the densest one
+    /// for the virtual desktop
+    fn display_scale(&self) -> f64 {
+        let scale = match self {
+            CaptureSource::Monitor(monitor) => monitor.scale_factor().ok(),
+            CaptureSource::Window(window) => window.current_monitor().ok().and_then(|m| m.scale_factor().ok()),
+            CaptureSource::VirtualDesktop(monitors) => {
+                monitors.iter().filter_map(|m| m.scale_factor().ok()).reduce(f32::max)
+            }
+        };
+        scale.map(f64::from).unwrap_or(1.0)
+    }
+}
+
+/// Encode a captured image and push it to the session's channel (and, for the default
+/// session, the frame tap). Returns false once the channel is closed.
+fn deliver_frame(
+    session: &CaptureSession,
+    on_frame: &FrameSink,
+    image: &RgbaImage,
+    frame_count: &mut u64,
+    source: SourceMetrics,
+) -> bool {
+    // Consumer is behind: drop the frame before paying for the encode
+    let Some(quality) = on_frame.admit() else {
+        return true;
+    };
+    // Captured images are in source pixels, so a profile's crop applies as-is
+    let region = on_frame.crop().and_then(|crop| crop.region(image.width(), image.height(), 1.0));
+    let cropped = region.map(|(x, y, width, height)| image::imageops::crop_imm(image, x, y, width, height).to_image());
+    let frame_image = cropped.as_ref().unwrap_or(image);
+    let target_id = session.target_id.lock().clone();
+    let redacted = redaction::should_redact(target_id.as_deref()).then(|| {
+        let mut placeholder = RgbaImage::new(frame_image.width(), frame_image.height());
+        let (width, height) = placeholder.dimensions();
+        redaction::placeholder(&mut placeholder, width, height, width as usize * 4);
+        placeholder
+    });
+    let origin = region.map_or((0.0, 0.0), |(x, y, _, _)| (f64::from(x), f64::from(y)));
+    let hidden = match redacted {
+        Some(_) => Vec::new(),
+        None => privacy::for_frame(target_id.as_deref(), origin),
+    };
+    let frame_image = redacted.as_ref().unwrap_or(frame_image);
+    let Some(mut frame_data) =
+        process_frame_for_channel(frame_image, *frame_count, quality, &session.id, &hidden, on_frame)
+    else {
+        return true;
+    };
+    frame_data.source = Some(source);
+    *frame_count += 1;
+
+    if *frame_count == 1 {
+        log::info!(
+            "[ScreenCapture] First channel frame sent ({}x{}, {} bytes)",
+            frame_data.width,
+            frame_data.height,
+            frame_data.frame.len()
+        );
+    }
+
+    geometry::set_for(&session.id, Some(FrameGeometry {
+        screen_x: source.x,
+        screen_y: source.y,
+        screen_width: source.width,
+        screen_height: source.height,
+        scale_factor: f64::from(image.width()) / source.width.max(1.0),
+        crop: region.map(|region| CropRect::from_region(region, 1.0)),
+        frame_width: frame_data.width,
+        frame_height: frame_data.height,
+    }));
+
+    // Tile and YUV frames were published whole while encoding
+    if session.id == sessions::DEFAULT_SESSION && frame_data.is_whole() {
+        frames::publish(|| frames::Frame {
+            data: frame_data.frame.clone(),
+            format: frame_data.format,
+            timestamp: frame_data.timestamp,
+            width: frame_data.width,
+            height: frame_data.height,
+            frame_count: frame_data.frame_count,
+        });
+    }
+
+    // Push frame to frontend via channel
+    if let Err(e) = on_frame.send(frame_data) {
+        log::error!("[ScreenCapture] Failed to send frame through channel: {:?}", e);
+        return false;
+    }
+
+    // Update shared state frame count
+    session.frame_count.store(*frame_count, Ordering::SeqCst);
+    true
+}
+
 /// Process a frame and return FrameData ready for channel transmission
-fn process_frame_for_channel(image: &RgbaImage, frame_count: u64) -> Option<FrameData> {
+fn process_frame_for_channel(
+    image: &RgbaImage,
+    frame_count: u64,
+    quality: u8,
+    session_id: &str,
+    hidden: &[PrivacyRegion],
+    sink: &FrameSink,
+) -> Option<FrameData> {
     let width = image.width();
     let height = image.height();
 
     // Downscale if too large
-    let max_width = capture_config::max_width();
-    let resized = if width > max_width {
+    let resize_start = Instant::now();
+    let config = sink.config();
+    let max_width = config.max_width();
+    let mut resized = if width > max_width {
         let scale = max_width as f32 / width as f32;
         let new_height = (height as f32 * scale) as u32;
         image::imageops::resize(image, max_width, new_height, FilterType::Nearest)
     } else {
         image.clone()
     };
+    sink.record(Stage::Resize, resize_start.elapsed());
 
     let final_width = resized.width();
     let final_height = resized.height();
-
-    // Convert RGBA to RGB
-    let rgba_bytes = resized.as_raw();
-    let mut rgb_bytes = Vec::with_capacity((final_width * final_height * 3) as usize);
-    for chunk in rgba_bytes.chunks_exact(4) {
-        rgb_bytes.push(chunk[0]);
-        rgb_bytes.push(chunk[1]);
-        rgb_bytes.push(chunk[2]);
-    }
-
-    // Encode to JPEG
-    let mut jpeg_buffer = Cursor::new(Vec::new());
-    let mut encoder = JpegEncoder::new_with_quality(&mut jpeg_buffer, capture_config::jpeg_quality());
-
-    if let Err(e) = encoder.encode(&rgb_bytes, final_width, final_height, image::ExtendedColorType::Rgb8) {
-        log::error!("[ScreenCapture] Failed to encode JPEG for channel: {:?}", e);
-        return None;
-    }
-
-    let jpeg_bytes = jpeg_buffer.into_inner();
+    // Regions are in source pixels; the image was captured at source resolution
+    let scale = f64::from(final_width) / f64::from(width);
+    privacy::apply(hidden, &mut resized, final_width, final_height, final_width as usize * 4, scale);
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs_f64();
+    if let Some(overlay) = overlay::for_session(session_id) {
+        overlay.draw(&mut resized, final_width, final_height, final_width as usize * 4, timestamp);
+    }
+
+    let rgba_bytes = resized.as_raw();
+    let format = config.encoding();
+    let grayscale = config.grayscale();
+    let stride = final_width as usize * 4;
+    let hash = similarity::dhash(rgba_bytes, final_width, final_height, stride, PixelOrder::Rgba);
+    let change = sink.change(hash);
+    let stats = frame_stats::observe(
+        session_id,
+        config.frame_stats(),
+        rgba_bytes,
+        final_width,
+        final_height,
+        stride,
+        PixelOrder::Rgba,
+        timestamp,
+    );
+    let encode_start = Instant::now();
+    let encode_full = || encode_rgba(rgba_bytes, final_width, final_height, format, grayscale, quality);
+    let (encoded, delta, yuv) = if let Some(yuv) = sink.yuv() {
+        let (planes, yuv) = yuv.convert_frame(rgba_bytes, final_width, final_height, stride, PixelOrder::Rgba)?;
+        (planes, None, Some(yuv))
+    } else if let Some(encoder) = sink.delta() {
+        let (bytes, delta) = delta::encode_frame(
+            encoder,
+            rgba_bytes,
+            final_width,
+            final_height,
+            stride,
+            PixelOrder::Rgba,
+            format,
+            grayscale,
+            quality,
+            &encode_full,
+        )?;
+        (bytes, Some(delta), None)
+    } else {
+        (encode_full()?, None, None)
+    };
+    sink.record(Stage::Encode, encode_start.elapsed());
 
-    Some(FrameData {
-        frame: jpeg_bytes,
+    let frame_data = FrameData {
+        frame: encoded,
+        format,
         timestamp,
         width: final_width,
         height: final_height,
         frame_count,
-    })
+        source: None,
+        delta,
+        yuv,
+        change,
+        stats,
+    };
+    // The frame tap gets whole frames
+    if !frame_data.is_whole() && session_id == sessions::DEFAULT_SESSION {
+        frames::publish(|| frames::Frame {
+            data: encode_full().unwrap_or_default(),
+            format,
+            timestamp,
+            width: final_width,
+            height: final_height,
+            frame_count,
+        });
+    }
+    Some(frame_data)
+}
+
+/// Encode a tightly packed RGBA frame in `format`, or as luma in grayscale mode
+fn encode_rgba(
+    rgba_bytes: &[u8],
+    width: u32,
+    height: u32,
+    format: FrameEncoding,
+    grayscale: bool,
+    quality: u8,
+) -> Option<Vec<u8>> {
+    // libjpeg-turbo (when built in) takes RGBA as-is, skipping the RGB pass below
+    let turbo = if format == FrameEncoding::Jpeg && !grayscale {
+        encode::encode_jpeg_turbo(rgba_bytes, width, height, quality)
+    } else {
+        None
+    };
+
+    let encoded = if let Some(jpeg) = turbo {
+        jpeg
+    } else if grayscale {
+        let luma = encode::rgba_to_luma(rgba_bytes);
+        encode::encode_luma(&luma, width, height, format, quality)?
+    } else if format == FrameEncoding::Jpeg {
+        let rgb_bytes = rgba_to_rgb(rgba_bytes, width, height);
+        let mut jpeg_buffer = Cursor::new(Vec::new());
+        let mut encoder = JpegEncoder::new_with_quality(&mut jpeg_buffer, quality);
+
+        if let Err(e) = encoder.encode(&rgb_bytes, width, height, image::ExtendedColorType::Rgb8) {
+            log::error!("[ScreenCapture] Failed to encode JPEG for channel: {:?}", e);
+            return None;
+        }
+        jpeg_buffer.into_inner()
+    } else {
+        let rgb_bytes = rgba_to_rgb(rgba_bytes, width, height);
+        encode::encode_rgb(&rgb_bytes, width, height, format, quality)?
+    };
+    Some(encoded)
+}
+
+/// Drop the alpha channel
+fn rgba_to_rgb(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut rgb_bytes = Vec::with_capacity((width * height * 3) as usize);
+    for chunk in rgba.chunks_exact(4) {
+        rgb_bytes.push(chunk[0]);
+        rgb_bytes.push(chunk[1]);
+        rgb_bytes.push(chunk[2]);
+    }
+    rgb_bytes
 }
@@ -0,0 +1,454 @@
+//! Wayland capture through the xdg-desktop-portal ScreenCast interface and PipeWire.
+//!
+//! Wayland compositors don't let clients read the screen directly, which is why xcap
+//! hands back black or empty frames there. Instead the user picks monitors/windows in
+//! the portal's own dialog; the portal then exposes each pick as a PipeWire node we
+//! read frames from.
+//!
+//! Target selection therefore happens in `get_capture_targets`: on Wayland it opens the
+//! portal dialog and returns the picked sources as `monitor:<node>` / `window:<node>`
+//! targets. The portal session stays open so starting and stopping streams on those
+//! targets doesn't prompt again, and the restore token lets a later selection reuse the
+//! previous pick without the dialog (where the portal supports it).
+//!
+//! Window and monitor positions aren't exposed on Wayland; streams report the logical
+//! position the portal gives us (monitors only), otherwise the origin.
+
+use crate::activity::Pacer;
+use crate::capture_config;
+use crate::error::{Error, Result};
+use crate::pause;
+use crate::secure_input;
+use crate::targets::{CaptureTarget, TargetKind};
+use crate::wire::FrameSink;
+use ashpd::desktop::screencast::{CursorMode, Screencast, SourceType};
+use ashpd::desktop::{PersistMode, Session};
+use ashpd::enumflags2::BitFlags;
+use image::RgbaImage;
+use parking_lot::Mutex;
+use pipewire as pw;
+use pw::spa;
+use pw::spa::param::format::{FormatProperties, MediaSubtype, MediaType};
+use pw::spa::param::format_utils;
+use pw::spa::param::video::{VideoFormat, VideoInfoRaw};
+use pw::spa::pod::Pod;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Whether this is a Wayland session (xcap can't capture there)
+pub fn is_wayland() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// A source the user picked in the portal dialog
+#[derive(Debug, Clone)]
+pub struct PortalStream {
+    pub node_id: u32,
+    pub kind: TargetKind,
+    /// Logical position/size, when the portal reports them
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(i32, i32)>,
+}
+
+struct PortalState {
+    proxy: Screencast<'static>,
+    session: Session<'static, Screencast<'static>>,
+    streams: Vec<PortalStream>,
+}
+
+static PORTAL: Mutex<Option<PortalState>> = Mutex::new(None);
+static RESTORE_TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+fn portal_error(e: impl std::fmt::Display) -> Error {
+    Error::Platform(format!("ScreenCast portal: {}", e))
+}
+
+/// Wait for a portal call. Callers include async commands on a runtime worker, where
+/// `block_on` panics, so the call is driven from a thread of its own.
+fn wait_for<T: Send>(call: impl std::future::Future<Output = T> + Send) -> T {
+    std::thread::scope(|scope| scope.spawn(|| tauri::async_runtime::block_on(call)).join())
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Open a portal session and let the user pick sources
+async fn open_portal(types: BitFlags<SourceType>) -> Result<PortalState> {
+    let proxy = Screencast::new().await.map_err(portal_error)?;
+    let session = proxy.create_session().await.map_err(portal_error)?;
+    let cursor_mode = if capture_config::show_cursor() {
+        CursorMode::Embedded
+    } else {
+        CursorMode::Hidden
+    };
+    let restore_token = RESTORE_TOKEN.lock().clone();
+    proxy
+        .select_sources(
+            &session,
+            cursor_mode,
+            types,
+            true,
+            restore_token.as_deref(),
+            PersistMode::ExplicitlyRevoked,
+        )
+        .await
+        .map_err(portal_error)?;
+
+    let response = proxy
+        .start(&session, None)
+        .await
+        .map_err(portal_error)?
+        .response()
+        .map_err(|e| match e {
+            ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled) => Error::PermissionDenied,
+            e => portal_error(e),
+        })?;
+
+    if let Some(token) = response.restore_token() {
+        *RESTORE_TOKEN.lock() = Some(token.to_string());
+    }
+
+    let streams = response
+        .streams()
+        .iter()
+        .map(|stream| PortalStream {
+            node_id: stream.pipe_wire_node_id(),
+            kind: match stream.source_type() {
+                Some(SourceType::Window) => TargetKind::Window,
+                _ => TargetKind::Monitor,
+            },
+            position: stream.position(),
+            size: stream.size(),
+        })
+        .collect::<Vec<_>>();
+    log::info!("[ScreenCapture] Portal session opened with {} stream(s)", streams.len());
+
+    Ok(PortalState {
+        proxy,
+        session,
+        streams,
+    })
+}
+
+/// Whether a portal session is open, i.e. the user already picked sources
+pub fn has_session() -> bool {
+    PORTAL.lock().is_some()
+}
+
+/// Run the portal dialog (closing any previous portal session) and return the picked
+/// sources as capture targets
+pub fn select_targets() -> Result<Vec<CaptureTarget>> {
+    let state = wait_for(open_portal(SourceType::Monitor | SourceType::Window))?;
+    let targets = state
+        .streams
+        .iter()
+        .enumerate()
+        .map(|(index, stream)| {
+            let (width, height) = stream.size.unwrap_or((0, 0));
+            let (x, y) = stream.position.unwrap_or((0, 0));
+            let kind_name = match stream.kind {
+                TargetKind::Window => "window",
+                TargetKind::Monitor | TargetKind::VirtualDesktop => "monitor",
+            };
+            CaptureTarget {
+                id: format!("{}:{}", kind_name, stream.node_id),
+                kind: stream.kind.clone(),
+                name: match stream.kind {
+                    TargetKind::Window => format!("Window {}", index + 1),
+                    TargetKind::Monitor | TargetKind::VirtualDesktop => format!("Screen {}", index + 1),
+                },
+                app_name: None,
+                thumbnail: None,
+                width: width.max(0) as u32,
+                height: height.max(0) as u32,
+                is_primary: index == 0 && stream.kind == TargetKind::Monitor,
+                x,
+                y,
+                backends: Vec::new(),
+                // The portal reports logical sizes only; frames carry the scale
+                source: None,
+            }
+        })
+        .collect();
+
+    if let Some(previous) = PORTAL.lock().replace(state) {
+        let _ = wait_for(previous.session.close());
+    }
+    Ok(targets)
+}
+
+/// Node of the stream sessions without a target use, without opening the portal dialog
+pub fn primary_node() -> Option<u32> {
+    let portal = PORTAL.lock();
+    let streams = &portal.as_ref()?.streams;
+    let stream = streams.iter().find(|s| s.kind == TargetKind::Monitor).or_else(|| streams.first());
+    stream.map(|s| s.node_id)
+}
+
+/// Fail for targets portals can't provide: the virtual desktop would need one stream per
+/// monitor stitched together, and the portal picks sources one stream each
+pub fn check_target(target: Option<&(TargetKind, u32)>) -> Result<()> {
+    match target {
+        Some((TargetKind::VirtualDesktop, _)) => {
+            Err(Error::Platform("The virtual desktop isn't available on Wayland".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The portal stream for a target. With no target, the first picked monitor is used,
+/// opening the portal dialog (monitors only) if nothing has been picked yet.
+pub fn resolve_stream(target: Option<(TargetKind, u32)>) -> Result<PortalStream> {
+    check_target(target.as_ref())?;
+    if PORTAL.lock().is_none() {
+        if target.is_some() {
+            return Err(Error::Platform(
+                "No portal session; list capture targets to pick a source first".to_string(),
+            ));
+        }
+        let state = wait_for(open_portal(SourceType::Monitor.into()))?;
+        *PORTAL.lock() = Some(state);
+    }
+
+    let portal = PORTAL.lock();
+    let streams = &portal.as_ref().ok_or(Error::NotStarted)?.streams;
+    let stream = match target {
+        Some((_, node_id)) => streams.iter().find(|s| s.node_id == node_id),
+        None => streams
+            .iter()
+            .find(|s| s.kind == TargetKind::Monitor)
+            .or_else(|| streams.first()),
+    };
+    stream
+        .cloned()
+        .ok_or_else(|| Error::Platform("Source not shared through the portal".to_string()))
+}
+
+/// Negotiated video format of a running stream
+#[derive(Default)]
+struct StreamFormat {
+    info: VideoInfoRaw,
+}
+
+/// EnumFormat params: raw 8-bit RGB(A) video in any size, at up to `fps`
+fn format_params(fps: u32) -> Vec<u8> {
+    let object = spa::pod::object!(
+        spa::utils::SpaTypes::ObjectParamFormat,
+        spa::param::ParamType::EnumFormat,
+        spa::pod::property!(FormatProperties::MediaType, Id, MediaType::Video),
+        spa::pod::property!(FormatProperties::MediaSubtype, Id, MediaSubtype::Raw),
+        spa::pod::property!(
+            FormatProperties::VideoFormat,
+            Choice,
+            Enum,
+            Id,
+            VideoFormat::BGRx,
+            VideoFormat::BGRx,
+            VideoFormat::BGRA,
+            VideoFormat::RGBx,
+            VideoFormat::RGBA
+        ),
+        spa::pod::property!(
+            FormatProperties::VideoSize,
+            Choice,
+            Range,
+            Rectangle,
+            spa::utils::Rectangle { width: 1920, height: 1080 },
+            spa::utils::Rectangle { width: 1, height: 1 },
+            spa::utils::Rectangle { width: 8192, height: 8192 }
+        ),
+        spa::pod::property!(
+            FormatProperties::VideoFramerate,
+            Choice,
+            Range,
+            Fraction,
+            spa::utils::Fraction { num: fps, denom: 1 },
+            spa::utils::Fraction { num: 0, denom: 1 },
+            spa::utils::Fraction { num: 120, denom: 1 }
+        ),
+    );
+    spa::pod::serialize::PodSerializer::serialize(
+        std::io::Cursor::new(Vec::new()),
+        &spa::pod::Value::Object(object),
+    )
+    .map(|(cursor, _)| cursor.into_inner())
+    .unwrap_or_default()
+}
+
+/// Repack a PipeWire buffer as RGBA
+fn to_rgba(data: &[u8], format: VideoFormat, width: u32, height: u32, stride: usize) -> Option<RgbaImage> {
+    let bgr = match format {
+        VideoFormat::BGRx | VideoFormat::BGRA => true,
+        VideoFormat::RGBx | VideoFormat::RGBA => false,
+        _ => return None,
+    };
+    let row_bytes = width as usize * 4;
+    if height == 0 || stride < row_bytes || data.len() < stride * (height as usize - 1) + row_bytes {
+        return None;
+    }
+    let mut rgba = Vec::with_capacity(row_bytes * height as usize);
+    for y in 0..height as usize {
+        for pixel in data[y * stride..y * stride + row_bytes].chunks_exact(4) {
+            if bgr {
+                rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+            } else {
+                rgba.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+            }
+        }
+    }
+    RgbaImage::from_raw(width, height, rgba)
+}
+
+/// Read frames from a portal stream until `stop_rx` fires or `on_image` returns false;
+/// it also gets how long copying the buffer out took. Frames are dropped while capture is paused or a password field has focus, and
+/// throttled to `sink`'s FPS. Fails with `Error::TargetLost` if the compositor
+/// ends the stream (window closed, monitor unplugged).
+pub fn run_capture(
+    stream: &PortalStream,
+    sink: &FrameSink,
+    mut stop_rx: watch::Receiver<bool>,
+    mut on_image: impl FnMut(RgbaImage, Duration) -> bool + 'static,
+) -> Result<()> {
+    let fd = {
+        let portal = PORTAL.lock();
+        let portal = portal.as_ref().ok_or(Error::NotStarted)?;
+        wait_for(portal.proxy.open_pipe_wire_remote(&portal.session)).map_err(portal_error)?
+    };
+
+    pw::init();
+    let pw_error = |e: pw::Error| Error::Platform(format!("PipeWire: {}", e));
+    let mainloop = pw::main_loop::MainLoop::new(None).map_err(pw_error)?;
+    let context = pw::context::Context::new(&mainloop).map_err(pw_error)?;
+    let core = context.connect_fd(fd, None).map_err(pw_error)?;
+    let pw_stream = pw::stream::Stream::new(
+        &core,
+        "observer-screen-capture",
+        pw::properties::properties! {
+            *pw::keys::MEDIA_TYPE => "Video",
+            *pw::keys::MEDIA_CATEGORY => "Capture",
+            *pw::keys::MEDIA_ROLE => "Screen",
+        },
+    )
+    .map_err(pw_error)?;
+
+    let mut last_frame: Option<Instant> = None;
+    let mut pacer = Pacer::new(sink);
+    let quit_on_close = mainloop.clone();
+    let quit_on_lost = mainloop.clone();
+    let lost = Arc::new(AtomicBool::new(false));
+    let lost_flag = lost.clone();
+    let listener = pw_stream
+        .add_local_listener_with_user_data(StreamFormat::default())
+        .state_changed(move |_, _, _, new| {
+            if let pw::stream::StreamState::Error(e) = &new {
+                log::warn!("[ScreenCapture] PipeWire stream error: {}", e);
+            }
+            // Streams start out unconnected, so going back there means the node is gone
+            if matches!(new, pw::stream::StreamState::Error(_) | pw::stream::StreamState::Unconnected) {
+                lost_flag.store(true, Ordering::SeqCst);
+                quit_on_lost.quit();
+            }
+        })
+        .param_changed(|_, format, id, param| {
+            let Some(param) = param else { return };
+            if id != spa::param::ParamType::Format.as_raw() {
+                return;
+            }
+            match format_utils::parse_format(param) {
+                Ok((MediaType::Video, MediaSubtype::Raw)) => {}
+                _ => return,
+            }
+            if format.info.parse(param).is_ok() {
+                log::info!(
+                    "[ScreenCapture] PipeWire stream format {:?} {}x{}",
+                    format.info.format(),
+                    format.info.size().width,
+                    format.info.size().height
+                );
+            }
+        })
+        .process(move |stream, format| {
+            let Some(mut buffer) = stream.dequeue_buffer() else { return };
+
+            let frame_time = pacer.frame_time(Instant::now());
+            if last_frame.is_some_and(|t| t.elapsed() < frame_time)
+                || pause::is_paused()
+                || secure_input::should_skip_frame()
+            {
+                return;
+            }
+
+            let datas = buffer.datas_mut();
+            let Some(data) = datas.first_mut() else { return };
+            let stride = data.chunk().stride().max(0) as usize;
+            let size = format.info.size();
+            let Some(bytes) = data.data() else { return };
+            let copy_start = Instant::now();
+            let Some(image) = to_rgba(bytes, format.info.format(), size.width, size.height, stride) else {
+                return;
+            };
+            let copy = copy_start.elapsed();
+            last_frame = Some(Instant::now());
+            pacer.observe(image.as_raw(), image.width(), image.height(), Instant::now());
+            if !on_image(image, copy) {
+                quit_on_close.quit();
+            }
+        })
+        .register()
+        .map_err(pw_error)?;
+
+    let params_bytes = format_params(sink.config().target_fps());
+    let mut params = [Pod::from_bytes(&params_bytes)
+        .ok_or_else(|| Error::Platform("Invalid PipeWire format params".to_string()))?];
+    pw_stream
+        .connect(
+            spa::utils::Direction::Input,
+            Some(stream.node_id),
+            pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+            &mut params,
+        )
+        .map_err(pw_error)?;
+
+    // Poll the stop signal from inside the loop
+    let quit_on_stop = mainloop.clone();
+    let timer = mainloop.loop_().add_timer(move |_| {
+        if *stop_rx.borrow_and_update() {
+            quit_on_stop.quit();
+        }
+    });
+    timer
+        .update_timer(Some(Duration::from_millis(100)), Some(Duration::from_millis(100)))
+        .into_result()
+        .map_err(|e| Error::Platform(format!("PipeWire timer: {}", e)))?;
+
+    log::info!("[ScreenCapture] PipeWire capture running on node {}", stream.node_id);
+    mainloop.run();
+    // Disconnecting moves the stream to Unconnected itself, which isn't a lost node
+    let lost = lost.load(Ordering::SeqCst);
+    drop(listener);
+    let _ = pw_stream.disconnect();
+    if lost {
+        return Err(Error::TargetLost);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repacks_bgrx_rows_with_padding() {
+        // 2x2 BGRx, stride 12 (4 bytes of row padding)
+        let data = [
+            1, 2, 3, 0, 4, 5, 6, 0, 9, 9, 9, 9, //
+            7, 8, 9, 0, 10, 11, 12, 0, 9, 9, 9, 9,
+        ];
+        let image = to_rgba(&data, VideoFormat::BGRx, 2, 2, 12).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [3, 2, 1, 255]);
+        assert_eq!(image.get_pixel(1, 1).0, [12, 11, 10, 255]);
+        assert!(to_rgba(&data[..19], VideoFormat::BGRx, 2, 2, 12).is_none());
+        assert!(to_rgba(&data, VideoFormat::NV12, 2, 2, 12).is_none());
+    }
+}
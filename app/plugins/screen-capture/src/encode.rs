@@ -0,0 +1,223 @@
+//! Frame encoding for the formats in `capture_config::FrameEncoding`.
+//!
+//! The capture backends keep their own fast JPEG paths (xcap frames go through `image`,
+//! ScreenCaptureKit BGRA buffers through the SIMD `jpeg-encoder`); everything else comes
+//! through here as packed RGB. PNG uses fast compression — frames are throwaway, and
+//! the default level costs several times the CPU for a few percent.
+//!
+//! In grayscale mode (`grayscale` in the stream's config) frames are reduced to 8-bit
+//! luma first and JPEG/PNG store a single channel, roughly halving size and encode time.
+//! WebP has no grayscale mode, so luma is expanded back to RGB for it.
+//!
+//! With the `turbojpeg` feature, JPEG frames on the xcap path are encoded by
+//! libjpeg-turbo straight from RGBA. If the library fails at runtime (e.g. a broken
+//! system install) we log once and fall back to the pure-Rust encoder for the rest of
+//! the session. `cargo bench --bench jpeg_encode --features turbojpeg` compares them.
+
+use crate::capture_config::FrameEncoding;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ExtendedColorType, ImageEncoder};
+
+/// Encode tightly packed RGB pixels. `quality` (1–100) applies to JPEG and lossy WebP.
+pub fn encode_rgb(
+    rgb: &[u8],
+    width: u32,
+    height: u32,
+    encoding: FrameEncoding,
+    quality: u8,
+) -> Option<Vec<u8>> {
+    let result = match encoding {
+        FrameEncoding::Jpeg => {
+            let mut bytes = Vec::new();
+            jpeg_encoder::Encoder::new(&mut bytes, quality)
+                .encode(rgb, width as u16, height as u16, jpeg_encoder::ColorType::Rgb)
+                .map(|_| bytes)
+                .map_err(|e| e.to_string())
+        }
+        FrameEncoding::Png => {
+            let mut bytes = Vec::new();
+            PngEncoder::new_with_quality(&mut bytes, CompressionType::Fast, FilterType::Adaptive)
+                .write_image(rgb, width, height, ExtendedColorType::Rgb8)
+                .map(|_| bytes)
+                .map_err(|e| e.to_string())
+        }
+        FrameEncoding::Webp | FrameEncoding::WebpLossless => {
+            let lossless = encoding == FrameEncoding::WebpLossless;
+            webp::Encoder::from_rgb(rgb, width, height)
+                .encode_simple(lossless, f32::from(quality))
+                .map(|memory| memory.to_vec())
+                .map_err(|e| format!("{:?}", e))
+        }
+    };
+
+    match result {
+        Ok(bytes) => Some(bytes),
+        Err(e) => {
+            log::error!("[ScreenCapture] Failed to encode {:?} frame: {}", encoding, e);
+            None
+        }
+    }
+}
+
+/// Encode 8-bit luma. `quality` (1–100) applies to JPEG and lossy WebP.
+pub fn encode_luma(
+    luma: &[u8],
+    width: u32,
+    height: u32,
+    encoding: FrameEncoding,
+    quality: u8,
+) -> Option<Vec<u8>> {
+    let result = match encoding {
+        FrameEncoding::Jpeg => {
+            let mut bytes = Vec::new();
+            jpeg_encoder::Encoder::new(&mut bytes, quality)
+                .encode(luma, width as u16, height as u16, jpeg_encoder::ColorType::Luma)
+                .map(|_| bytes)
+                .map_err(|e| e.to_string())
+        }
+        FrameEncoding::Png => {
+            let mut bytes = Vec::new();
+            PngEncoder::new_with_quality(&mut bytes, CompressionType::Fast, FilterType::Adaptive)
+                .write_image(luma, width, height, ExtendedColorType::L8)
+                .map(|_| bytes)
+                .map_err(|e| e.to_string())
+        }
+        FrameEncoding::Webp | FrameEncoding::WebpLossless => {
+            let rgb: Vec<u8> = luma.iter().flat_map(|&y| [y, y, y]).collect();
+            return encode_rgb(&rgb, width, height, encoding, quality);
+        }
+    };
+
+    match result {
+        Ok(bytes) => Some(bytes),
+        Err(e) => {
+            log::error!("[ScreenCapture] Failed to encode grayscale {:?} frame: {}", encoding, e);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "turbojpeg")]
+static TURBO_AVAILABLE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+
+#[cfg(feature = "turbojpeg")]
+thread_local! {
+    // Compressors are cheap to reuse but not Send; each capture thread keeps its own
+    static COMPRESSOR: std::cell::RefCell<Option<turbojpeg::Compressor>> = const { std::cell::RefCell::new(None) };
+}
+
+/// JPEG-encode tightly packed RGBA with libjpeg-turbo. None when the feature is off or
+/// libjpeg-turbo is unusable, in which case the caller uses its own encoder.
+pub fn encode_jpeg_turbo(rgba: &[u8], width: u32, height: u32, quality: u8) -> Option<Vec<u8>> {
+    #[cfg(feature = "turbojpeg")]
+    {
+        use std::sync::atomic::Ordering;
+
+        if !TURBO_AVAILABLE.load(Ordering::Relaxed) {
+            return None;
+        }
+        let result = COMPRESSOR.with(|cell| -> Result<Vec<u8>, turbojpeg::Error> {
+            let mut cell = cell.borrow_mut();
+            if cell.is_none() {
+                *cell = Some(turbojpeg::Compressor::new()?);
+            }
+            let compressor = cell.as_mut().expect("compressor initialized above");
+            compressor.set_quality(i32::from(quality))?;
+            compressor.set_subsamp(turbojpeg::Subsamp::Sub2x2)?;
+            compressor.compress_to_vec(turbojpeg::Image {
+                pixels: rgba,
+                width: width as usize,
+                pitch: width as usize * 4,
+                height: height as usize,
+                format: turbojpeg::PixelFormat::RGBA,
+            })
+        });
+        match result {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                log::warn!("[ScreenCapture] libjpeg-turbo failed ({}), using the built-in JPEG encoder", e);
+                TURBO_AVAILABLE.store(false, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+    #[cfg(not(feature = "turbojpeg"))]
+    {
+        let _ = (rgba, width, height, quality);
+        None
+    }
+}
+
+/// Repack a (possibly row-padded) BGRA buffer as tightly packed RGB
+pub fn bgra_to_rgb(bgra: &[u8], width: usize, height: usize, bytes_per_row: usize) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        let row = &bgra[y * bytes_per_row..y * bytes_per_row + width * 4];
+        for pixel in row.chunks_exact(4) {
+            rgb.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+    }
+    rgb
+}
+
+/// BT.601 luma, in integer math
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((77 * u32::from(r) + 150 * u32::from(g) + 29 * u32::from(b) + 128) >> 8) as u8
+}
+
+/// Luma of tightly packed RGBA
+pub fn rgba_to_luma(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4).map(|p| luma(p[0], p[1], p[2])).collect()
+}
+
+/// Luma of a (possibly row-padded) BGRA buffer
+pub fn bgra_to_luma(bgra: &[u8], width: usize, height: usize, bytes_per_row: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = &bgra[y * bytes_per_row..y * bytes_per_row + width * 4];
+        out.extend(row.chunks_exact(4).map(|p| luma(p[2], p[1], p[0])));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trips_losslessly() {
+        let rgb: Vec<u8> = (0..4 * 3 * 3).map(|i| (i * 7) as u8).collect();
+        let png = encode_rgb(&rgb, 4, 3, FrameEncoding::Png, 50).unwrap();
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.dimensions(), (4, 3));
+        assert_eq!(decoded.into_raw(), rgb);
+    }
+
+    #[test]
+    fn strips_row_padding_and_swaps_channels() {
+        // 2x2 BGRA with 4 bytes of padding per row
+        let bgra = [
+            1, 2, 3, 255, 4, 5, 6, 255, 0, 0, 0, 0, //
+            7, 8, 9, 255, 10, 11, 12, 255, 0, 0, 0, 0,
+        ];
+        assert_eq!(
+            bgra_to_rgb(&bgra, 2, 2, 12),
+            vec![3, 2, 1, 6, 5, 4, 9, 8, 7, 12, 11, 10]
+        );
+    }
+
+    #[test]
+    fn grayscale_png_is_single_channel() {
+        let rgba = [255, 255, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255];
+        let luma = rgba_to_luma(&rgba);
+        assert_eq!(luma, vec![255, 0, 77, 29]);
+        assert_eq!(bgra_to_luma(&[0, 0, 255, 255, 9, 9, 9, 9], 1, 1, 8), vec![77]);
+
+        let png = encode_luma(&luma, 2, 2, FrameEncoding::Png, 50).unwrap();
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+        assert_eq!(decoded.color(), image::ColorType::L8);
+        assert_eq!(decoded.into_luma8().into_raw(), luma);
+    }
+}
@@ -14,6 +14,9 @@ pub enum Error {
     #[error("No frame available")]
     NoFrame,
 
+    #[error("Capture target closed or disconnected")]
+    TargetLost,
+
     #[error("Platform error: {0}")]
     Platform(String),
 
@@ -23,6 +26,9 @@ pub enum Error {
     #[error("Audio device error: {0}")]
     AudioDevice(String),
 
+    #[error("OCR error: {0}")]
+    Ocr(String),
+
     #[error(transparent)]
     Tauri(#[from] tauri::Error),
 
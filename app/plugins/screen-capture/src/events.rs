@@ -0,0 +1,59 @@
+//! Events the plugin emits to the frontend.
+//!
+//! The capture backends run on their own threads without an `AppHandle`, so `init`
+//! stores an emitter at plugin setup and `emit` is a no-op until then (e.g. in tests).
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// A session's capture target changed while it kept running (`TargetSwitched` payload)
+pub const TARGET_SWITCHED: &str = "screen-capture://target-switched";
+
+/// A session's target went away or came back (`lifecycle::TargetLifecycle` payload)
+pub const TARGET_LIFECYCLE: &str = "screen-capture://target-lifecycle";
+
+/// A session gave up on a target that kept failing (`recovery::CaptureError` payload)
+pub const CAPTURE_ERROR: &str = "screen-capture://capture-error";
+
+/// A watch rule's session started, switched windows or stopped (`watch_rules::WatchEvent`
+/// payload)
+pub const WATCH_RULE: &str = "screen-capture://watch-rule";
+
+/// Target thumbnails finished capturing (`Vec<thumbnails::Thumbnail>` payload)
+pub const THUMBNAILS: &str = "screen-capture://thumbnails";
+
+/// A text watch read different lines than before (`text_watch::TextChanged` payload)
+pub const TEXT_CHANGED: &str = "screen-capture://text-changed";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetSwitched {
+    pub session_id: String,
+    /// New target (None = primary monitor)
+    pub target_id: Option<String>,
+    /// Frames sent before the switch; later frames show the new target
+    pub frame_count: u64,
+}
+
+type EmitFn = Box<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+static EMITTER: OnceLock<EmitFn> = OnceLock::new();
+
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    let _ = EMITTER.set(Box::new(move |event, payload| {
+        if let Err(e) = app.emit(event, payload) {
+            log::warn!("[ScreenCapture] Failed to emit {}: {}", event, e);
+        }
+    }));
+}
+
+pub fn emit(event: &str, payload: impl Serialize) {
+    if let Some(emitter) = EMITTER.get() {
+        match serde_json::to_value(payload) {
+            Ok(payload) => emitter(event, payload),
+            Err(e) => log::warn!("[ScreenCapture] Failed to serialize {}: {}", event, e),
+        }
+    }
+}
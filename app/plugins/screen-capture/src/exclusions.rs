@@ -0,0 +1,56 @@
+//! Windows to leave out of display captures.
+//!
+//! ScreenCaptureKit can drop individual windows from a display stream, so on macOS the
+//! listed windows are excluded whenever a monitor is captured (from the next stream
+//! start; running streams keep their filter). Capturing an excluded window directly
+//! still works. xcap and the Wayland portal grab whole monitors, so the list has no
+//! effect on Windows/Linux.
+//!
+//! Observer's own windows (main window, overlay) are excluded the same way unless
+//! `set_exclude_self(false)`, and are left out of the target list on every platform so
+//! an agent can't end up watching itself.
+
+use crate::error::{Error, Result};
+use crate::targets::{self, TargetKind};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static EXCLUDED: RwLock<Vec<u32>> = RwLock::new(Vec::new());
+static EXCLUDE_SELF: AtomicBool = AtomicBool::new(true);
+
+/// Replace the exclusion list with `window:<id>` target ids
+pub fn set(target_ids: &[String]) -> Result<()> {
+    let mut ids = Vec::with_capacity(target_ids.len());
+    for target_id in target_ids {
+        match targets::parse_target_id(target_id)? {
+            (TargetKind::Window, id) => ids.push(id),
+            (TargetKind::Monitor | TargetKind::VirtualDesktop, _) => {
+                return Err(Error::Platform(format!("{} is not a window", target_id)));
+            }
+        }
+    }
+    ids.sort_unstable();
+    ids.dedup();
+    log::info!("[ScreenCapture] Excluding {} window(s) from display capture", ids.len());
+    *EXCLUDED.write() = ids;
+    Ok(())
+}
+
+/// Window ids currently excluded
+pub fn window_ids() -> Vec<u32> {
+    EXCLUDED.read().clone()
+}
+
+/// Whether Observer's own windows are hidden from targets and display captures
+pub fn exclude_self() -> bool {
+    EXCLUDE_SELF.load(Ordering::Relaxed)
+}
+
+pub fn set_exclude_self(exclude: bool) {
+    EXCLUDE_SELF.store(exclude, Ordering::Relaxed);
+}
+
+/// Whether a window owned by process `pid` should be hidden as one of ours
+pub fn is_own_window(pid: u32) -> bool {
+    exclude_self() && pid == std::process::id()
+}
@@ -0,0 +1,195 @@
+//! Following targets: sessions whose window is picked at runtime instead of pinned.
+//!
+//! - `targets::ACTIVE_WINDOW` captures whichever window has focus. Observer's own windows
+//!   are skipped: opening the app to check on an agent keeps it watching the previous
+//!   window.
+//! - `app:{name}` captures a window of that application (matched case-insensitively):
+//!   its focused window if it has one, else its largest. Apps that close and recreate
+//!   windows keep being captured.
+//!
+//! A watcher thread re-picks the window every `POLL_INTERVAL` and hot-switches the
+//! session when the pick changes, so every change shows up as a
+//! `screen-capture://target-switched` event. Only one watcher runs per session;
+//! starting a new one, switching the session to a fixed target or stopping it ends the
+//! old watcher. Not available on Wayland, where clients can't see other apps' windows.
+
+use crate::error::{Error, Result};
+use crate::targets;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use xcap::Window;
+
+/// How often the followed window is re-picked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What a following session tracks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Follow {
+    ActiveWindow,
+    /// Windows of the app with this name
+    App(String),
+}
+
+impl Follow {
+    /// The follow mode a target id asks for, if any
+    pub fn parse(target_id: &str) -> Option<Self> {
+        if target_id == targets::ACTIVE_WINDOW {
+            return Some(Follow::ActiveWindow);
+        }
+        let name = target_id.strip_prefix(targets::APP_PREFIX)?.trim();
+        (!name.is_empty()).then(|| Follow::App(name.to_string()))
+    }
+
+    /// Window to capture right now, if any
+    pub fn pick(&self) -> Option<u32> {
+        let windows = Window::all().ok()?;
+        let candidates: Vec<Candidate> = windows
+            .iter()
+            .filter_map(|w| {
+                Some(Candidate {
+                    id: w.id().ok()?,
+                    pid: w.pid().unwrap_or(0),
+                    app_name: w.app_name().unwrap_or_default(),
+                    focused: w.is_focused().unwrap_or(false),
+                    minimized: w.is_minimized().unwrap_or(false),
+                    area: u64::from(w.width().unwrap_or(0)) * u64::from(w.height().unwrap_or(0)),
+                })
+            })
+            .collect();
+        self.pick_from(&candidates, std::process::id())
+    }
+
+    /// Window to start a session on; app targets must match a window now
+    pub fn initial_window(&self) -> Result<Option<u32>> {
+        match (self, self.pick()) {
+            (Follow::App(name), None) => Err(Error::Platform(format!("No window found for app {}", name))),
+            (_, window) => Ok(window),
+        }
+    }
+
+    fn pick_from(&self, candidates: &[Candidate], own_pid: u32) -> Option<u32> {
+        match self {
+            Follow::ActiveWindow => candidates.iter().find(|c| c.focused && c.pid != own_pid).map(|c| c.id),
+            Follow::App(name) => {
+                let windows = candidates
+                    .iter()
+                    .filter(|c| !c.minimized && c.area > 0 && c.app_name.eq_ignore_ascii_case(name));
+                windows
+                    .clone()
+                    .find(|c| c.focused)
+                    .or_else(|| windows.max_by_key(|c| c.area))
+                    .map(|c| c.id)
+            }
+        }
+    }
+}
+
+/// Window facts a pick is based on
+struct Candidate {
+    id: u32,
+    pid: u32,
+    app_name: String,
+    focused: bool,
+    minimized: bool,
+    area: u64,
+}
+
+/// Watcher generation per following session; a watcher exits once its entry changes
+fn watchers() -> &'static Mutex<HashMap<String, u64>> {
+    static WATCHERS: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Follow `follow` in `session_id`, which currently shows window `current` (if any).
+/// `switch` gets a `window:<id>` target id whenever the pick changes; the watcher runs
+/// until `stop` is called for the session or `is_running` returns false.
+pub fn start(
+    session_id: &str,
+    follow: Follow,
+    current: Option<u32>,
+    is_running: impl Fn() -> bool + Send + 'static,
+    switch: impl Fn(String) -> Result<()> + Send + 'static,
+) {
+    static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    watchers().lock().insert(session_id.to_string(), generation);
+    let session_id = session_id.to_string();
+    log::info!("[ScreenCapture] Session {} following {:?}", session_id, follow);
+
+    std::thread::spawn(move || {
+        let mut current = current;
+        loop {
+            let latest = watchers().lock().get(&session_id).copied();
+            if latest != Some(generation) || !is_running() {
+                break;
+            }
+            // No pick (nothing focused, app has no windows) keeps the current window
+            if let Some(window_id) = follow.pick().filter(|&id| current != Some(id)) {
+                match switch(format!("window:{}", window_id)) {
+                    Ok(()) => current = Some(window_id),
+                    Err(e) => log::warn!("[ScreenCapture] Could not follow window {}: {}", window_id, e),
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        let mut watchers = watchers().lock();
+        if watchers.get(&session_id) == Some(&generation) {
+            watchers.remove(&session_id);
+        }
+        log::info!("[ScreenCapture] Session {} stopped following {:?}", session_id, follow);
+    });
+}
+
+/// Stop following in `session_id`
+pub fn stop(session_id: &str) {
+    watchers().lock().remove(session_id);
+}
+
+/// Whether `session_id` follows the active window or an app
+pub fn is_following(session_id: &str) -> bool {
+    watchers().lock().contains_key(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, pid: u32, app_name: &str, focused: bool, area: u64) -> Candidate {
+        Candidate { id, pid, app_name: app_name.to_string(), focused, minimized: false, area }
+    }
+
+    #[test]
+    fn parses_follow_targets() {
+        assert_eq!(Follow::parse("active-window"), Some(Follow::ActiveWindow));
+        assert_eq!(Follow::parse("app:Slack"), Some(Follow::App("Slack".to_string())));
+        assert_eq!(Follow::parse("app: "), None);
+        assert_eq!(Follow::parse("window:3"), None);
+    }
+
+    #[test]
+    fn active_window_skips_own_windows() {
+        let windows = [window(1, 42, "Observer", true, 100), window(2, 7, "Code", false, 100)];
+        assert_eq!(Follow::ActiveWindow.pick_from(&windows, 42), None);
+        assert_eq!(Follow::ActiveWindow.pick_from(&windows, 1), Some(1));
+    }
+
+    #[test]
+    fn app_prefers_focused_then_largest_window() {
+        let mut windows = vec![
+            window(1, 7, "Slack", false, 100),
+            window(2, 7, "slack", false, 400),
+            window(3, 8, "Code", true, 900),
+        ];
+        let slack = Follow::App("Slack".to_string());
+        assert_eq!(slack.pick_from(&windows, 0), Some(2));
+        windows[0].focused = true;
+        assert_eq!(slack.pick_from(&windows, 0), Some(1));
+        windows[1].minimized = true;
+        windows[0].focused = false;
+        assert_eq!(slack.pick_from(&windows, 0), Some(1));
+        assert_eq!(Follow::App("Zoom".to_string()).pick_from(&windows, 0), None);
+    }
+}
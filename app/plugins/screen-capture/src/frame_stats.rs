@@ -0,0 +1,170 @@
+//! Lightweight color statistics of frames: mean luminance, dominant colors and a
+//! dark/light guess.
+//!
+//! Computed for every frame from a `GRID_WIDTH`x`GRID_HEIGHT` grid of sampled pixels, so
+//! it costs about as much as hashing the frame and lets agents trigger on "the screen
+//! went black" (mean luminance near 0) or "a red banner appeared" (red among the
+//! dominant colors) without a vision model. The latest stats of each session are kept
+//! for `get_frame_stats_cmd`; with `frame_stats` in the stream's config they also ride
+//! along on every frame.
+//!
+//! Dominant colors are found by bucketing samples into 512 colors (3 bits per channel)
+//! and averaging the samples of the largest buckets. Stats describe the frame as sent:
+//! after privacy regions, redaction and the overlay.
+
+use crate::delta::PixelOrder;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Sampled columns
+const GRID_WIDTH: u32 = 64;
+/// Sampled rows
+const GRID_HEIGHT: u32 = 36;
+
+/// Dominant colors reported
+const TOP_COLORS: usize = 4;
+
+/// Luminance below which a sample counts as dark, and above which (1 minus it) light
+const DARK_LEVEL: f32 = 0.35;
+
+/// Share of dark (or light) samples for a frame to be called dark (or light)
+const THEME_SHARE: f32 = 0.6;
+
+static LATEST: Mutex<Option<HashMap<String, FrameStats>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Theme {
+    Dark,
+    Light,
+    Mixed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DominantColor {
+    /// `#rrggbb`
+    pub hex: String,
+    /// Share of the frame, 0–1
+    pub share: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameStats {
+    /// Frame time, seconds since the epoch
+    pub timestamp: f64,
+    /// BT.601 luminance, 0 (black) – 1 (white)
+    pub mean_luminance: f32,
+    /// Share of dark samples
+    pub dark_share: f32,
+    pub theme: Theme,
+    /// Largest first
+    pub dominant_colors: Vec<DominantColor>,
+}
+
+/// Stats of a 4-bytes-per-pixel buffer whose rows are `stride` bytes apart
+pub fn compute(pixels: &[u8], width: u32, height: u32, stride: usize, order: PixelOrder, timestamp: f64) -> FrameStats {
+    let columns = GRID_WIDTH.min(width);
+    let rows = GRID_HEIGHT.min(height);
+    // Bucket -> (samples, summed r, g, b)
+    let mut buckets: HashMap<u16, (u32, u32, u32, u32)> = HashMap::new();
+    let (mut luminance, mut dark, mut light) = (0.0f32, 0u32, 0u32);
+    for row in 0..rows {
+        let y = (row * 2 + 1) * height / (rows * 2);
+        for column in 0..columns {
+            let x = (column * 2 + 1) * width / (columns * 2);
+            let p = &pixels[y as usize * stride + x as usize * 4..][..4];
+            let (r, g, b) = match order {
+                PixelOrder::Rgba => (p[0], p[1], p[2]),
+                PixelOrder::Bgra => (p[2], p[1], p[0]),
+            };
+            let luma = (0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b)) / 255.0;
+            luminance += luma;
+            dark += u32::from(luma < DARK_LEVEL);
+            light += u32::from(luma > 1.0 - DARK_LEVEL);
+            let bucket = (u16::from(r >> 5) << 6) | (u16::from(g >> 5) << 3) | u16::from(b >> 5);
+            let entry = buckets.entry(bucket).or_default();
+            *entry = (entry.0 + 1, entry.1 + u32::from(r), entry.2 + u32::from(g), entry.3 + u32::from(b));
+        }
+    }
+
+    let samples = (columns * rows).max(1) as f32;
+    let dark_share = dark as f32 / samples;
+    let theme = if dark_share >= THEME_SHARE {
+        Theme::Dark
+    } else if light as f32 / samples >= THEME_SHARE {
+        Theme::Light
+    } else {
+        Theme::Mixed
+    };
+    let mut buckets: Vec<(u16, (u32, u32, u32, u32))> = buckets.into_iter().collect();
+    // Ties broken by bucket so the order is stable
+    buckets.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(&b.0)));
+    let dominant_colors = buckets
+        .iter()
+        .take(TOP_COLORS)
+        .map(|&(_, (count, r, g, b))| DominantColor {
+            hex: format!("#{:02x}{:02x}{:02x}", r / count, g / count, b / count),
+            share: count as f32 / samples,
+        })
+        .collect();
+    FrameStats { timestamp, mean_luminance: luminance / samples, dark_share, theme, dominant_colors }
+}
+
+/// Compute and keep a session's frame stats; returned when `attach` says frames carry them
+#[allow(clippy::too_many_arguments)]
+pub fn observe(
+    session_id: &str,
+    attach: bool,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    order: PixelOrder,
+    timestamp: f64,
+) -> Option<FrameStats> {
+    let stats = compute(pixels, width, height, stride, order, timestamp);
+    let attach = attach.then(|| stats.clone());
+    LATEST.lock().get_or_insert_with(HashMap::new).insert(session_id.to_string(), stats);
+    attach
+}
+
+/// Stats of a session's latest frame
+pub fn latest(session_id: &str) -> Option<FrameStats> {
+    LATEST.lock().as_ref()?.get(session_id).cloned()
+}
+
+/// Forget a stopped session's stats
+pub fn clear(session_id: &str) {
+    if let Some(latest) = LATEST.lock().as_mut() {
+        latest.remove(session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_luminance_theme_and_dominant_colors() {
+        // 100x10: left 70% near-black, right 30% red, BGRA
+        let (width, height) = (100u32, 10u32);
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| if i % width < 70 { [10, 10, 10, 255] } else { [0, 0, 230, 255] })
+            .collect();
+        let stats = compute(&pixels, width, height, width as usize * 4, PixelOrder::Bgra, 1.0);
+        assert_eq!(stats.theme, Theme::Dark);
+        assert!((stats.dark_share - 1.0).abs() < 1e-6);
+        assert_eq!(stats.dominant_colors.len(), 2);
+        assert_eq!(stats.dominant_colors[0].hex, "#0a0a0a");
+        assert_eq!(stats.dominant_colors[1].hex, "#e60000");
+        assert!((stats.dominant_colors[1].share - 0.3).abs() < 0.02);
+
+        let white = vec![255u8; 8 * 8 * 4];
+        let stats = compute(&white, 8, 8, 32, PixelOrder::Rgba, 1.0);
+        assert_eq!(stats.theme, Theme::Light);
+        assert!((stats.mean_luminance - 1.0).abs() < 1e-3);
+    }
+}
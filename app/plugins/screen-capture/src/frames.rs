@@ -0,0 +1,66 @@
+//! In-process tap on the encoded frame stream.
+//!
+//! The capture backends push every frame the default session sends to the frontend into
+//! a broadcast channel as well, so Rust-side consumers (the remote observer link, recorders, ...)
+//! can read frames without going through the webview. Publishing is skipped entirely
+//! while nobody is subscribed.
+
+use crate::capture_config::FrameEncoding;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Slow subscribers lag (and skip frames) rather than hold up capture
+const TAP_CAPACITY: usize = 8;
+
+/// How long one-off readers (OCR, code detection) wait for the stream's next frame
+pub const NEXT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// An encoded frame as sent to the frontend
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Encoded image bytes, in `format`
+    pub data: Vec<u8>,
+    pub format: FrameEncoding,
+    /// Unix timestamp in seconds
+    pub timestamp: f64,
+    pub width: u32,
+    pub height: u32,
+    pub frame_count: u64,
+}
+
+static TAP: OnceLock<broadcast::Sender<Arc<Frame>>> = OnceLock::new();
+
+fn sender() -> &'static broadcast::Sender<Arc<Frame>> {
+    TAP.get_or_init(|| broadcast::channel(TAP_CAPACITY).0)
+}
+
+pub fn subscribe() -> broadcast::Receiver<Arc<Frame>> {
+    sender().subscribe()
+}
+
+/// Publish a frame; `frame` is only built when someone is listening
+pub fn publish(frame: impl FnOnce() -> Frame) {
+    let tx = sender();
+    if tx.receiver_count() > 0 {
+        let _ = tx.send(Arc::new(frame()));
+    }
+}
+
+/// The next frame published, or None if none arrives within `timeout` (no stream is
+/// running, or capture is paused)
+pub async fn next(timeout: Duration) -> Option<Arc<Frame>> {
+    let mut rx = subscribe();
+    tokio::time::timeout(timeout, async {
+        loop {
+            match rx.recv().await {
+                Ok(frame) => return Some(frame),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .await
+    .ok()
+    .flatten()
+}
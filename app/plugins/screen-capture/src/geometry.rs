@@ -0,0 +1,361 @@
+//! Where the frames being streamed come from on screen.
+//!
+//! The capture backends record the source rect (monitor or window, in the platform's
+//! screen coordinates) alongside the size of the frames they hand the frontend, so a
+//! point a vision model picks out on a downscaled frame can be mapped back onto the
+//! desktop. Screen coordinates are points on macOS and physical pixels elsewhere, which
+//! is what xcap/ScreenCaptureKit report for monitors and windows.
+//!
+//! Three coordinate spaces are supported:
+//! - `frame`: pixels of the (downscaled, possibly cropped) frame sent to the frontend
+//! - `screen`: absolute desktop coordinates
+//! - `window`: relative to the captured source's top-left (the window, or the monitor)
+//!
+//! Automation (clicks) and overlay annotations both go through `FrameGeometry::map`.
+//!
+//! `SourceMetrics` is the DPI-facing summary reported with capture targets and each
+//! frame: the source rect, the display's scale factor and the source size in both
+//! physical pixels and logical points, so mixed-DPI setups can be mapped without
+//! guessing which unit a width is in.
+
+use crate::sessions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Region of the source the frame covers, in source pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CropRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl CropRect {
+    /// Pixel region (x, y, width, height) of this crop in a `width`x`height` image with
+    /// `scale` image pixels per source pixel, clamped to the image; None if nothing is
+    /// left
+    pub fn region(&self, width: u32, height: u32, scale: f64) -> Option<(u32, u32, u32, u32)> {
+        let clamp = |value: f64, max: u32| (value * scale).round().clamp(0.0, f64::from(max)) as u32;
+        let (left, top) = (clamp(self.x, width), clamp(self.y, height));
+        let right = clamp(self.x + self.width, width);
+        let bottom = clamp(self.y + self.height, height);
+        (right > left && bottom > top).then(|| (left, top, right - left, bottom - top))
+    }
+
+    /// Source-pixel rect of a pixel region taken at `scale` image pixels per source pixel
+    pub fn from_region((x, y, width, height): (u32, u32, u32, u32), scale: f64) -> Self {
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+        CropRect {
+            x: f64::from(x) / scale,
+            y: f64::from(y) / scale,
+            width: f64::from(width) / scale,
+            height: f64::from(height) / scale,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameGeometry {
+    /// Top-left of the captured monitor/window in screen coordinates
+    pub screen_x: f64,
+    pub screen_y: f64,
+    /// Size of the captured monitor/window in screen coordinates
+    pub screen_width: f64,
+    pub screen_height: f64,
+    /// Source pixels per screen unit (2.0 on a Retina display, 1.0 where screen
+    /// coordinates are already physical pixels)
+    #[serde(default = "default_scale_factor")]
+    pub scale_factor: f64,
+    /// Part of the source the frame shows; the whole source when None
+    #[serde(default)]
+    pub crop: Option<CropRect>,
+    /// Size of the frames sent to the frontend, in pixels
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+fn default_scale_factor() -> f64 {
+    1.0
+}
+
+/// Source rect and DPI facts for a capture target or frame
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMetrics {
+    /// Source rect in screen coordinates (points on macOS, physical pixels elsewhere)
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Display scale: physical pixels per logical point (2.0 on Retina, 1.5 at 150% on
+    /// Windows)
+    pub scale_factor: f64,
+    pub physical_width: u32,
+    pub physical_height: u32,
+    pub logical_width: u32,
+    pub logical_height: u32,
+}
+
+impl SourceMetrics {
+    /// Metrics for a source rect in screen coordinates on a display with `scale_factor`.
+    /// Screen units are logical points on macOS and physical pixels elsewhere (except
+    /// Wayland portal streams, see `logical`).
+    pub fn new(x: f64, y: f64, width: f64, height: f64, scale_factor: f64) -> Self {
+        if cfg!(target_os = "macos") {
+            Self::logical(x, y, width, height, scale_factor)
+        } else {
+            Self::physical(x, y, width, height, scale_factor)
+        }
+    }
+
+    /// Metrics for a rect given in physical pixels
+    pub fn physical(x: f64, y: f64, width: f64, height: f64, scale_factor: f64) -> Self {
+        let scale_factor = sanitize(scale_factor);
+        Self::with_factors(x, y, width, height, scale_factor, 1.0, 1.0 / scale_factor)
+    }
+
+    /// Metrics for a rect given in logical points
+    pub fn logical(x: f64, y: f64, width: f64, height: f64, scale_factor: f64) -> Self {
+        let scale_factor = sanitize(scale_factor);
+        Self::with_factors(x, y, width, height, scale_factor, scale_factor, 1.0)
+    }
+
+    fn with_factors(
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        scale_factor: f64,
+        to_physical: f64,
+        to_logical: f64,
+    ) -> Self {
+        let size = |value: f64, factor: f64| (value * factor).round().max(0.0) as u32;
+        Self {
+            x,
+            y,
+            width,
+            height,
+            scale_factor,
+            physical_width: size(width, to_physical),
+            physical_height: size(height, to_physical),
+            logical_width: size(width, to_logical),
+            logical_height: size(height, to_logical),
+        }
+    }
+
+    /// Metrics of a streamed source, whose pixels-per-screen-unit is the display scale
+    /// on macOS
+    pub fn from_geometry(geometry: &FrameGeometry) -> Self {
+        Self::new(
+            geometry.screen_x,
+            geometry.screen_y,
+            geometry.screen_width,
+            geometry.screen_height,
+            geometry.scale_factor,
+        )
+    }
+}
+
+fn sanitize(scale_factor: f64) -> f64 {
+    if scale_factor.is_finite() && scale_factor > 0.0 {
+        scale_factor
+    } else {
+        1.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordSpace {
+    Frame,
+    Screen,
+    Window,
+}
+
+impl FrameGeometry {
+    /// The crop rect, or the full source in source pixels
+    fn source_rect(&self) -> CropRect {
+        self.crop.unwrap_or(CropRect {
+            x: 0.0,
+            y: 0.0,
+            width: self.screen_width * self.scale_factor,
+            height: self.screen_height * self.scale_factor,
+        })
+    }
+
+    /// Source pixels per frame pixel, per axis
+    fn frame_scale(&self) -> (f64, f64) {
+        let source = self.source_rect();
+        (
+            source.width / f64::from(self.frame_width.max(1)),
+            source.height / f64::from(self.frame_height.max(1)),
+        )
+    }
+
+    fn frame_to_window(&self, x: f64, y: f64) -> (f64, f64) {
+        let source = self.source_rect();
+        let (kx, ky) = self.frame_scale();
+        let scale = self.scale_factor.max(f64::EPSILON);
+        ((source.x + x * kx) / scale, (source.y + y * ky) / scale)
+    }
+
+    fn window_to_frame(&self, x: f64, y: f64) -> (f64, f64) {
+        let source = self.source_rect();
+        let (kx, ky) = self.frame_scale();
+        (
+            (x * self.scale_factor - source.x) / kx.max(f64::EPSILON),
+            (y * self.scale_factor - source.y) / ky.max(f64::EPSILON),
+        )
+    }
+
+    /// Map a point on the frame to screen coordinates
+    pub fn frame_to_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        self.map(x, y, CoordSpace::Frame, CoordSpace::Screen)
+    }
+
+    /// Map a screen point to frame pixels (may fall outside the frame)
+    pub fn screen_to_frame(&self, x: f64, y: f64) -> (f64, f64) {
+        self.map(x, y, CoordSpace::Screen, CoordSpace::Frame)
+    }
+
+    /// Map a point between any two coordinate spaces
+    pub fn map(&self, x: f64, y: f64, from: CoordSpace, to: CoordSpace) -> (f64, f64) {
+        // Go through window space, which both other spaces are a simple step from
+        let (wx, wy) = match from {
+            CoordSpace::Frame => self.frame_to_window(x, y),
+            CoordSpace::Screen => (x - self.screen_x, y - self.screen_y),
+            CoordSpace::Window => (x, y),
+        };
+        match to {
+            CoordSpace::Frame => self.window_to_frame(wx, wy),
+            CoordSpace::Screen => (wx + self.screen_x, wy + self.screen_y),
+            CoordSpace::Window => (wx, wy),
+        }
+    }
+
+    /// Whether a frame point lies on the frame
+    pub fn contains_frame_point(&self, x: f64, y: f64) -> bool {
+        x >= 0.0 && y >= 0.0 && x < f64::from(self.frame_width) && y < f64::from(self.frame_height)
+    }
+}
+
+/// Geometry per capture session
+static SESSIONS: RwLock<Option<HashMap<String, FrameGeometry>>> = RwLock::new(None);
+
+/// Record the geometry of a capture session (None when the session stops)
+pub fn set_for(session_id: &str, geometry: Option<FrameGeometry>) {
+    let mut sessions = SESSIONS.write().unwrap();
+    let sessions = sessions.get_or_insert_with(HashMap::new);
+    match geometry {
+        Some(geometry) => {
+            sessions.insert(session_id.to_string(), geometry);
+        }
+        None => {
+            sessions.remove(session_id);
+        }
+    }
+}
+
+/// Geometry of the frames a session is streaming, if it is running
+pub fn for_session(session_id: &str) -> Option<FrameGeometry> {
+    SESSIONS.read().unwrap().as_ref()?.get(session_id).copied()
+}
+
+/// Record the geometry of the default capture session
+pub fn set_current(geometry: Option<FrameGeometry>) {
+    set_for(sessions::DEFAULT_SESSION, geometry);
+}
+
+/// Geometry of the frames the default session is streaming, if capture is running
+pub fn current() -> Option<FrameGeometry> {
+    for_session(sessions::DEFAULT_SESSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset_monitor() -> FrameGeometry {
+        // 2560x1440 monitor to the right of a 1920 wide one, streamed at 1280x720
+        FrameGeometry {
+            screen_x: 1920.0,
+            screen_y: 0.0,
+            screen_width: 2560.0,
+            screen_height: 1440.0,
+            scale_factor: 1.0,
+            crop: None,
+            frame_width: 1280,
+            frame_height: 720,
+        }
+    }
+
+    #[test]
+    fn maps_downscaled_frame_onto_offset_monitor() {
+        let geometry = offset_monitor();
+        assert_eq!(geometry.frame_to_screen(0.0, 0.0), (1920.0, 0.0));
+        assert_eq!(geometry.frame_to_screen(640.0, 360.0), (3200.0, 720.0));
+        assert_eq!(geometry.screen_to_frame(3200.0, 720.0), (640.0, 360.0));
+        assert_eq!(
+            geometry.map(640.0, 360.0, CoordSpace::Frame, CoordSpace::Window),
+            (1280.0, 720.0)
+        );
+    }
+
+    #[test]
+    fn accounts_for_retina_scale_and_crop() {
+        // 1440x900 pt window at (100, 50) on a 2x display; frame shows the 1000x500 px
+        // region starting at (200, 100) px, delivered at 500x250
+        let geometry = FrameGeometry {
+            screen_x: 100.0,
+            screen_y: 50.0,
+            screen_width: 1440.0,
+            screen_height: 900.0,
+            scale_factor: 2.0,
+            crop: Some(CropRect {
+                x: 200.0,
+                y: 100.0,
+                width: 1000.0,
+                height: 500.0,
+            }),
+            frame_width: 500,
+            frame_height: 250,
+        };
+        // Frame origin = source px (200, 100) = window pt (100, 50) = screen pt (200, 100)
+        assert_eq!(geometry.frame_to_screen(0.0, 0.0), (200.0, 100.0));
+        // Frame (250, 125) = source px (700, 350) = window pt (350, 175)
+        assert_eq!(
+            geometry.map(250.0, 125.0, CoordSpace::Frame, CoordSpace::Window),
+            (350.0, 175.0)
+        );
+        assert_eq!(geometry.screen_to_frame(450.0, 225.0), (250.0, 125.0));
+    }
+
+    #[test]
+    fn crop_regions_scale_and_clamp() {
+        let crop = CropRect { x: 100.0, y: 50.0, width: 400.0, height: 300.0 };
+        assert_eq!(crop.region(1920, 1080, 1.0), Some((100, 50, 400, 300)));
+        // Buffer scaled down to half the source
+        assert_eq!(crop.region(960, 540, 0.5), Some((50, 25, 200, 150)));
+        assert_eq!(CropRect::from_region((50, 25, 200, 150), 0.5), crop);
+        // Partly outside the image, then entirely
+        assert_eq!(crop.region(300, 200, 1.0), Some((100, 50, 200, 150)));
+        assert_eq!(crop.region(80, 40, 1.0), None);
+    }
+
+    #[test]
+    fn reports_physical_and_logical_sizes() {
+        let physical = SourceMetrics::physical(0.0, 0.0, 3000.0, 2000.0, 1.5);
+        assert_eq!((physical.physical_width, physical.logical_width), (3000, 2000));
+        assert_eq!((physical.physical_height, physical.logical_height), (2000, 1333));
+
+        let logical = SourceMetrics::logical(0.0, 0.0, 1440.0, 900.0, 2.0);
+        assert_eq!((logical.physical_width, logical.logical_width), (2880, 1440));
+        assert_eq!((logical.physical_height, logical.logical_height), (1800, 900));
+
+        assert_eq!(SourceMetrics::new(0.0, 0.0, 10.0, 10.0, 0.0).scale_factor, 1.0);
+    }
+}
@@ -0,0 +1,157 @@
+//! H.264 encoding of captured frames, shared by MP4 recording and live network outputs.
+//!
+//! Frames arrive encoded in the capture config's format; `H264Encoder` decodes each one,
+//! scales it to the size of the first frame (rounded down to even dimensions, which
+//! H.264 requires) and encodes it with OpenH264 (software). The result is an Annex-B
+//! access unit, which RTP packetizers take as is and MP4 converts to length-prefixed NAL
+//! units. `restart` changes the bitrate and makes the next frame a keyframe, for outputs
+//! that adapt to their network or need to resync a viewer.
+
+use crate::error::{Error, Result};
+use image::imageops::FilterType;
+use image::RgbImage;
+use openh264::encoder::{BitRate, Encoder, EncoderConfig, FrameRate, FrameType};
+use openh264::formats::{RgbSliceU8, YUVBuffer};
+use openh264::OpenH264API;
+
+pub const NAL_SPS: u8 = 7;
+pub const NAL_PPS: u8 = 8;
+
+/// One encoded frame
+#[derive(Debug, Clone)]
+pub struct AccessUnit {
+    /// NAL units with start codes; keyframes carry SPS and PPS
+    pub annex_b: Vec<u8>,
+    pub keyframe: bool,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn h264_error(e: openh264::Error) -> Error {
+    Error::Platform(format!("H.264: {}", e))
+}
+
+fn open(bitrate_kbps: u32) -> Result<Encoder> {
+    let config = EncoderConfig::new()
+        .bitrate(BitRate::from_bps(bitrate_kbps * 1000))
+        .max_frame_rate(FrameRate::from_hz(crate::capture_config::target_fps() as f32));
+    Encoder::with_api_config(OpenH264API::from_source(), config).map_err(h264_error)
+}
+
+/// Encoded images in, H.264 access units out
+pub struct H264Encoder {
+    encoder: Encoder,
+    bitrate_kbps: u32,
+    size: Option<(u32, u32)>,
+}
+
+impl H264Encoder {
+    pub fn new(bitrate_kbps: u32) -> Result<Self> {
+        Ok(Self { encoder: open(bitrate_kbps)?, bitrate_kbps, size: None })
+    }
+
+    pub fn bitrate_kbps(&self) -> u32 {
+        self.bitrate_kbps
+    }
+
+    /// Video size, once the first frame set it
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.size
+    }
+
+    /// Continue at `bitrate_kbps` with a fresh encoder, whose first frame is a keyframe
+    pub fn restart(&mut self, bitrate_kbps: u32) -> Result<()> {
+        self.encoder = open(bitrate_kbps)?;
+        self.bitrate_kbps = bitrate_kbps;
+        Ok(())
+    }
+
+    /// Encode an image (any format `image` decodes). None when the frame was skipped:
+    /// undecodable, empty, or dropped by rate control.
+    pub fn encode(&mut self, encoded: &[u8]) -> Result<Option<AccessUnit>> {
+        let decoded = match image::load_from_memory(encoded) {
+            Ok(decoded) => decoded.to_rgb8(),
+            Err(e) => {
+                log::warn!("[ScreenCapture] H.264 encoder skipped an undecodable frame: {}", e);
+                return Ok(None);
+            }
+        };
+        let (width, height) = *self.size.get_or_insert((decoded.width() & !1, decoded.height() & !1));
+        if width == 0 || height == 0 {
+            return Ok(None);
+        }
+        let rgb: RgbImage = if decoded.dimensions() == (width, height) {
+            decoded
+        } else {
+            image::imageops::resize(&decoded, width, height, FilterType::Triangle)
+        };
+
+        let yuv = YUVBuffer::from_rgb_source(RgbSliceU8::new(rgb.as_raw(), (width as usize, height as usize)));
+        let bitstream = self.encoder.encode(&yuv).map_err(h264_error)?;
+        let keyframe = matches!(bitstream.frame_type(), FrameType::IDR | FrameType::I);
+        let annex_b = bitstream.to_vec();
+        if annex_b.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(AccessUnit { annex_b, keyframe, width, height }))
+    }
+}
+
+/// NAL units of an Annex-B stream (start codes stripped)
+pub fn nal_units(data: &[u8]) -> Vec<&[u8]> {
+    let mut units = Vec::new();
+    let mut start = None;
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            if let Some(s) = start {
+                // A 4-byte start code leaves a trailing zero on the previous unit
+                let end = if i > 0 && data[i - 1] == 0 { i - 1 } else { i };
+                units.push(&data[s..end.max(s)]);
+            }
+            i += 3;
+            start = Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(s) = start {
+        units.push(&data[s..]);
+    }
+    units.retain(|unit| !unit.is_empty());
+    units
+}
+
+pub fn nal_type(unit: &[u8]) -> u8 {
+    unit[0] & 0x1f
+}
+
+/// SPS and PPS from a keyframe's Annex-B stream
+pub fn parameter_sets(data: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let units = nal_units(data);
+    let sps = units.iter().find(|u| nal_type(u) == NAL_SPS)?;
+    let pps = units.iter().find(|u| nal_type(u) == NAL_PPS)?;
+    Some((sps.to_vec(), pps.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_annex_b() {
+        // SPS, PPS (4-byte start codes) then an IDR slice (3-byte start code)
+        let stream = [
+            0, 0, 0, 1, 0x67, 1, 2, //
+            0, 0, 0, 1, 0x68, 3, //
+            0, 0, 1, 0x65, 4, 5, 6,
+        ];
+        let units = nal_units(&stream);
+        assert_eq!(units, vec![&[0x67, 1, 2][..], &[0x68, 3][..], &[0x65, 4, 5, 6][..]]);
+
+        let (sps, pps) = parameter_sets(&stream).unwrap();
+        assert_eq!(sps, vec![0x67, 1, 2]);
+        assert_eq!(pps, vec![0x68, 3]);
+        assert!(parameter_sets(&[0, 0, 1, 0x41, 9]).is_none());
+    }
+}
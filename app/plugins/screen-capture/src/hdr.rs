@@ -0,0 +1,220 @@
+//! HDR display detection and tone mapping for the WGC backend.
+//!
+//! With Windows HDR on, the desktop is composed in scRGB (linear, 1.0 = 80 nits) and
+//! the BGRA8 copies WGC hands out clip and wash out. On those displays the frame pool is
+//! created as FP16 instead and each frame is mapped back to 8-bit sRGB here: scaled so
+//! the user's "SDR content brightness" lands on white, highlights above `KNEE` rolled
+//! off instead of clipped, then gamma encoded. SDR displays keep the BGRA8 path.
+//!
+//! xcap's GDI/DXGI grabs can't be fixed this way; `CaptureBackend::Auto` prefers WGC.
+
+use crate::targets::TargetKind;
+use windows::core::Interface;
+use windows::Win32::Devices::Display::{
+    DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes, QueryDisplayConfig, DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL,
+    DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME, DISPLAYCONFIG_DEVICE_INFO_HEADER, DISPLAYCONFIG_MODE_INFO,
+    DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SDR_WHITE_LEVEL, DISPLAYCONFIG_SOURCE_DEVICE_NAME, QDC_ONLY_ACTIVE_PATHS,
+};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Dxgi::Common::DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020;
+use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1, IDXGIOutput6};
+use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, HMONITOR, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTONEAREST};
+
+/// scRGB level of SDR white when the display doesn't report one (200 nits)
+const DEFAULT_SDR_WHITE: f32 = 2.5;
+/// Relative level above which highlights are compressed instead of passed through
+const KNEE: f32 = 0.8;
+/// Gamma lookup resolution over [0, 1]
+const LUT_SIZE: usize = 4096;
+
+/// Maps FP16 scRGB pixels to 8-bit sRGB
+pub struct ToneMapper {
+    /// scRGB value that becomes sRGB white
+    sdr_white: f32,
+    lut: Vec<u8>,
+}
+
+impl ToneMapper {
+    pub fn new(sdr_white: f32) -> Self {
+        let sdr_white = if sdr_white.is_finite() && sdr_white > 0.0 { sdr_white } else { DEFAULT_SDR_WHITE };
+        let lut = (0..LUT_SIZE)
+            .map(|i| (srgb_encode(i as f32 / (LUT_SIZE - 1) as f32) * 255.0).round() as u8)
+            .collect();
+        Self { sdr_white, lut }
+    }
+
+    /// Map one R16G16B16A16_FLOAT pixel (little-endian bytes) to opaque RGBA
+    pub fn map_pixel(&self, pixel: &[u8]) -> [u8; 4] {
+        let channel = |i: usize| (half_to_f32(u16::from_le_bytes([pixel[i], pixel[i + 1]])) / self.sdr_white).max(0.0);
+        let (r, g, b) = (channel(0), channel(2), channel(4));
+
+        // Compress on the brightest channel so highlights keep their hue
+        let peak = r.max(g).max(b);
+        let gain = if peak > KNEE { roll_off(peak) / peak } else { 1.0 };
+        let encode = |value: f32| {
+            let value = (value * gain).clamp(0.0, 1.0);
+            self.lut[(value * (LUT_SIZE - 1) as f32).round() as usize]
+        };
+        [encode(r), encode(g), encode(b), 255]
+    }
+}
+
+/// Identity up to `KNEE`, then approaches 1.0 with a continuous slope
+fn roll_off(value: f32) -> f32 {
+    let headroom = 1.0 - KNEE;
+    let over = (value - KNEE) / headroom;
+    KNEE + headroom * over / (1.0 + over)
+}
+
+fn srgb_encode(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// IEEE 754 half to f32
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = u32::from((bits >> 10) & 0x1f);
+    let mantissa = u32::from(bits & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa as f32 * 2f32.powi(-24),
+        0x1f if mantissa == 0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => f32::from_bits(((exponent + 112) << 23) | (mantissa << 13)),
+    };
+    sign * magnitude
+}
+
+/// Tone mapper for the display showing `handle` (an xcap monitor/window id), if that
+/// display is in HDR mode
+pub fn tone_mapper_for(kind: &TargetKind, handle: u32) -> Option<ToneMapper> {
+    let raw = handle as usize as *mut std::ffi::c_void;
+    let monitor = match kind {
+        TargetKind::Monitor => HMONITOR(raw),
+        TargetKind::Window => unsafe { MonitorFromWindow(HWND(raw), MONITOR_DEFAULTTONEAREST) },
+        TargetKind::VirtualDesktop => return None,
+    };
+    if !is_hdr(monitor) {
+        return None;
+    }
+    let sdr_white = sdr_white_level(monitor).unwrap_or(DEFAULT_SDR_WHITE);
+    log::info!("[ScreenCapture] HDR display detected, tone mapping with SDR white at {:.0} nits", sdr_white * 80.0);
+    Some(ToneMapper::new(sdr_white))
+}
+
+/// Whether the DXGI output for `monitor` uses the HDR10 (PQ) color space
+fn is_hdr(monitor: HMONITOR) -> bool {
+    let Ok(factory) = (unsafe { CreateDXGIFactory1::<IDXGIFactory1>() }) else {
+        return false;
+    };
+    let mut adapter_index = 0;
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(adapter_index) } {
+        adapter_index += 1;
+        let mut output_index = 0;
+        while let Ok(output) = unsafe { adapter.EnumOutputs(output_index) } {
+            output_index += 1;
+            if unsafe { output.GetDesc() }.map(|desc| desc.Monitor) != Ok(monitor) {
+                continue;
+            }
+            return output
+                .cast::<IDXGIOutput6>()
+                .and_then(|output| unsafe { output.GetDesc1() })
+                .is_ok_and(|desc| desc.ColorSpace == DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020);
+        }
+    }
+    false
+}
+
+/// The display's "SDR content brightness" as an scRGB level (1.0 = 80 nits)
+fn sdr_white_level(monitor: HMONITOR) -> Option<f32> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    unsafe { GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO) }.ok().ok()?;
+
+    let (mut path_count, mut mode_count) = (0u32, 0u32);
+    unsafe { GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count) }.ok().ok()?;
+    let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+    let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+    unsafe {
+        QueryDisplayConfig(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count,
+            paths.as_mut_ptr(),
+            &mut mode_count,
+            modes.as_mut_ptr(),
+            None,
+        )
+    }
+    .ok()
+    .ok()?;
+
+    paths.iter().take(path_count as usize).find_map(|path| {
+        let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME {
+            header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+                size: std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32,
+                adapterId: path.sourceInfo.adapterId,
+                id: path.sourceInfo.id,
+            },
+            ..Default::default()
+        };
+        if unsafe { DisplayConfigGetDeviceInfo(&mut source.header) } != 0 || source.viewGdiDeviceName != info.szDevice {
+            return None;
+        }
+        let mut white = DISPLAYCONFIG_SDR_WHITE_LEVEL {
+            header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL,
+                size: std::mem::size_of::<DISPLAYCONFIG_SDR_WHITE_LEVEL>() as u32,
+                adapterId: path.targetInfo.adapterId,
+                id: path.targetInfo.id,
+            },
+            ..Default::default()
+        };
+        // Reported in thousandths of 80 nits
+        (unsafe { DisplayConfigGetDeviceInfo(&mut white.header) } == 0).then(|| white.SDRWhiteLevel as f32 / 1000.0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Half-float bit patterns
+    const ZERO: u16 = 0x0000;
+    const ONE: u16 = 0x3c00;
+    const TWO: u16 = 0x4000;
+    const EIGHT: u16 = 0x4800;
+
+    fn pixel(r: u16, g: u16, b: u16) -> Vec<u8> {
+        [r, g, b, ONE].iter().flat_map(|h| h.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn converts_halves() {
+        assert_eq!(half_to_f32(0x3c00), 1.0);
+        assert_eq!(half_to_f32(0xc000), -2.0);
+        assert_eq!(half_to_f32(0x0001), 2f32.powi(-24));
+        assert!(half_to_f32(0x7c00).is_infinite());
+    }
+
+    #[test]
+    fn sdr_white_maps_near_white_and_highlights_keep_headroom() {
+        let mapper = ToneMapper::new(2.0);
+        assert_eq!(mapper.map_pixel(&pixel(ZERO, ZERO, ZERO)), [0, 0, 0, 255]);
+        // SDR white lands just under full white instead of clipping with the highlights
+        let white = mapper.map_pixel(&pixel(TWO, TWO, TWO));
+        let highlight = mapper.map_pixel(&pixel(EIGHT, EIGHT, EIGHT));
+        assert!(white[0] > 220 && white[0] < highlight[0]);
+        // Below the knee levels pass through: half of SDR white is ~188 in sRGB
+        assert_eq!(mapper.map_pixel(&pixel(ONE, ONE, ONE))[0], 188);
+    }
+
+    #[test]
+    fn roll_off_is_continuous_and_bounded() {
+        assert!((roll_off(KNEE) - KNEE).abs() < 1e-6);
+        assert!(roll_off(1000.0) < 1.0);
+    }
+}
@@ -10,10 +10,65 @@ mod error;
 pub mod audio_pipeline;
 
 // Runtime-tunable capture quality config (max width / JPEG quality / FPS), shared by all
-// desktop capture backends and set from the frontend before capture starts.
+// desktop capture backends; set from the frontend or per stream when capture starts.
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 pub mod capture_config;
 
+// PNG / WebP frame encoding (JPEG stays on each backend's fast path)
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod encode;
+
+// Raw binary frame payloads for the IPC channel (JSON FrameData is the fallback)
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod wire;
+
+// Broadcast of encoded frames for in-process consumers
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod frames;
+
+// Changed-tile streams with periodic keyframes, for mostly static screens
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod delta;
+
+// NV12 / I420 planes instead of encoded images, for external video encoders
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod yuv;
+
+// Per-frame change scores (dHash) and image comparison
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod similarity;
+
+// QR code / barcode detection on frames and images
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod codes;
+
+// Mean luminance / dominant colors / dark-light guess per frame
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod frame_stats;
+
+// Per-stream capture sessions so several targets can be captured at once
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod sessions;
+
+// Source rect / frame size of the active capture, for mapping frame coordinates to screen
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod geometry;
+
+// Pause gate that lets the app hold capture (screen sharing, ...) without stopping streams
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod pause;
+
+// Password-field / secure-input detection; capture skips frames while it is active
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod secure_input;
+
+// Cursor compositing for xcap frames (ScreenCaptureKit draws the cursor itself)
+#[cfg(all(
+    not(any(target_os = "android", target_os = "ios")),
+    not(target_os = "macos")
+))]
+pub mod cursor;
+
 // Audio module - only needed for Windows/Linux (macOS uses unified desktop module)
 #[cfg(all(
     not(any(target_os = "android", target_os = "ios")),
@@ -24,6 +79,101 @@ pub mod audio;
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 pub mod targets;
 
+// H.264 encoding for recordings and live network outputs
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod h264;
+
+// MP4/H.264 recording of a capture session to disk
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod recording;
+
+// Events emitted to the frontend from capture threads
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod events;
+
+// Following targets (active window, `app:{name}`) that hot-switch as windows change
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod follow;
+
+// Sessions started and stopped by windows matching title/app patterns
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod watch_rules;
+
+// Focused app/title stream without frame capture
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod active_window;
+
+// Timestamp/label burned into frames before encoding
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod overlay;
+
+// Per-target regions blurred or blacked out before encoding
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod privacy;
+
+// Placeholder frames while a denied app is captured or focused
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod redaction;
+
+// One-shot full-resolution screenshots
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod screenshot;
+
+// Periodic snapshots written to a directory with rotation
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod snapshots;
+
+// Cached, background-captured target thumbnails
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod thumbnails;
+
+// All monitors composited into one virtual-desktop frame
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod stitch;
+
+// Named target + settings profiles saved in the app data dir
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod profiles;
+
+// Frame dropping and quality reduction while a channel's consumer is behind
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod backpressure;
+
+// Per-session fps, stage timings and throughput
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod stats;
+
+// Adaptive frame rate: idle FPS while the screen is static
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod activity;
+
+// Target lost/restored handling for capture sessions
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod lifecycle;
+
+// Backoff and give-up handling for capture sources that keep failing
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod recovery;
+
+// Instant-replay ring buffer over the frame tap, exportable as MP4/images
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod replay;
+
+// Windows left out of display captures (applied by ScreenCaptureKit)
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub mod exclusions;
+
+// Tesseract OCR with language packs and script detection
+#[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+pub mod ocr;
+
+// Low-rate OCR of a target with events for the lines that changed
+#[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+pub mod text_watch;
+
+// Screen-recording permission status and request flow
+pub mod permission;
+
 // Platform-specific desktop implementations
 // macOS uses unified ScreenCaptureKit for BOTH video and audio
 // Windows/Linux use xcap for video + WASAPI/ALSA for audio
@@ -34,6 +184,22 @@ pub mod desktop;
 #[cfg(all(not(target_os = "macos"), not(any(target_os = "android", target_os = "ios"))))]
 pub mod desktop;
 
+// xcap polling for macOS releases without ScreenCaptureKit audio (before 13)
+#[cfg(target_os = "macos")]
+mod macos_xcap;
+
+// Windows.Graphics.Capture backend, used by `desktop` unless xcap is requested
+#[cfg(target_os = "windows")]
+pub mod wgc;
+
+// HDR detection and tone mapping for WGC frames
+#[cfg(target_os = "windows")]
+mod hdr;
+
+// ScreenCast portal + PipeWire backend, used by `desktop` on Wayland sessions
+#[cfg(target_os = "linux")]
+pub mod desktop_wayland;
+
 #[cfg(any(target_os = "android", target_os = "ios"))]
 mod mobile;
 
@@ -55,6 +221,8 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             stop_capture_cmd,
             stop_video_cmd,
             stop_audio_cmd,
+            check_capture_permission_cmd,
+            request_capture_permission_cmd,
             #[cfg(any(target_os = "android", target_os = "ios"))]
             get_frame_cmd,
             #[cfg(target_os = "ios")]
@@ -66,11 +234,91 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             get_capture_targets_cmd,
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            start_target_preview_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            stop_target_preview_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
             start_capture_stream_cmd,
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             start_video_stream_cmd,
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             start_audio_stream_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            set_capture_config_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            set_excluded_windows_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            set_exclude_own_windows_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            get_capture_config_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            list_capture_sessions_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            ack_frames_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            get_capture_stats_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            list_capture_profiles_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            save_capture_profile_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            delete_capture_profile_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            start_capture_profile_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            add_watch_rule_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            remove_watch_rule_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            list_watch_rules_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            get_active_window_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            start_active_window_stream_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            stop_active_window_stream_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            set_frame_overlay_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            set_privacy_regions_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            list_privacy_regions_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            set_redacted_apps_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            get_redacted_apps_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            capture_screenshot_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            compare_frames_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            detect_codes_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            get_frame_stats_cmd,
+            #[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+            ocr_frame_cmd,
+            #[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+            start_text_watch_cmd,
+            #[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+            stop_text_watch_cmd,
+            #[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+            list_text_watches_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            start_snapshots_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            stop_snapshots_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            get_snapshot_status_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            switch_capture_target_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            start_recording_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            stop_recording_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            export_replay_cmd,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            export_gif_cmd,
             // Android channel-based streaming commands
             #[cfg(target_os = "android")]
             start_video_stream_cmd,
@@ -89,7 +337,23 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             }
 
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
-            desktop::init(app, api)?;
+            {
+                events::init(app);
+                desktop::init(app, api)?;
+                replay::start();
+                match app.path().app_data_dir() {
+                    Ok(dir) => {
+                        profiles::init(dir.join("screen-capture"));
+                        privacy::init(dir.join("screen-capture"));
+                        redaction::init(dir.join("screen-capture"));
+                        replay::init(dir.join("replays"));
+                    }
+                    Err(e) => log::warn!(
+                        "[ScreenCapture] No app data dir, capture profiles, privacy regions and redaction unavailable: {}",
+                        e
+                    ),
+                }
+            }
 
             Ok(())
         })
@@ -162,7 +426,7 @@ async fn list_app_group_files_cmd<R: Runtime>(
 
 // ==================== Cross-platform commands ====================
 
-/// Stop all capture (video + audio)
+/// Stop all capture (every video session + audio)
 #[tauri::command]
 async fn stop_capture_cmd<R: Runtime>(
     #[allow(unused_variables)] app: tauri::AppHandle<R>,
@@ -177,7 +441,7 @@ async fn stop_capture_cmd<R: Runtime>(
     #[cfg(target_os = "macos")]
     {
         let _ = desktop::stop_audio();
-        return desktop::stop_capture().await;
+        return desktop::stop_all_sessions();
     }
 
     // Windows/Linux: separate audio module
@@ -187,14 +451,16 @@ async fn stop_capture_cmd<R: Runtime>(
     ))]
     {
         let _ = audio::stop_audio();
-        return desktop::stop_capture().await;
+        return desktop::stop_all_sessions();
     }
 }
 
-/// Stop only video capture
+/// Stop only video capture. On desktop, stops one session (the default one unless
+/// `session_id` is given); other sessions keep running.
 #[tauri::command]
 async fn stop_video_cmd<R: Runtime>(
     #[allow(unused_variables)] app: tauri::AppHandle<R>,
+    #[allow(unused_variables)] session_id: Option<String>,
 ) -> Result<()> {
     #[cfg(any(target_os = "android", target_os = "ios"))]
     {
@@ -204,7 +470,7 @@ async fn stop_video_cmd<R: Runtime>(
 
     #[cfg(not(any(target_os = "android", target_os = "ios")))]
     {
-        return desktop::stop_capture().await;
+        return desktop::stop_capture_session(sessions::resolve(session_id.as_deref()));
     }
 }
 
@@ -236,9 +502,48 @@ async fn stop_audio_cmd<R: Runtime>(
     }
 }
 
+/// Whether screen recording is allowed: `granted`, `denied` (the user has to change it in
+/// the OS settings) or `prompt` (the OS asks on request or when capture starts)
+#[tauri::command]
+async fn check_capture_permission_cmd<R: Runtime>(
+    #[allow(unused_variables)] app: tauri::AppHandle<R>,
+) -> Result<permission::PermissionStatus> {
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        let screen_capture = app.state::<mobile::ScreenCapture<R>>();
+        return screen_capture.check_permission();
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        return Ok(permission::check());
+    }
+}
+
+/// Trigger the OS permission flow (system prompt, System Settings or portal dialog) and
+/// return the status afterwards
+#[tauri::command]
+async fn request_capture_permission_cmd<R: Runtime>(
+    #[allow(unused_variables)] app: tauri::AppHandle<R>,
+) -> Result<permission::PermissionStatus> {
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        let screen_capture = app.state::<mobile::ScreenCapture<R>>();
+        return screen_capture.request_permission();
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        return tauri::async_runtime::spawn_blocking(permission::request)
+            .await
+            .map_err(|e| Error::Platform(e.to_string()));
+    }
+}
+
 // ==================== Desktop-only commands ====================
 
-/// Get all available capture targets (monitors and windows)
+/// Get all available capture targets (monitors and windows). Thumbnails come from a cache
+/// and never block; missing ones follow as a `screen-capture://thumbnails` event.
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 #[tauri::command]
 async fn get_capture_targets_cmd<R: Runtime>(
@@ -253,25 +558,44 @@ async fn get_capture_targets_cmd<R: Runtime>(
 /// Frames are pushed to frontend via channel instead of polling
 /// Audio capture is also started and streamed via separate channel
 /// NOTE: For independent control, use start_video_stream_cmd and start_audio_stream_cmd instead
+/// Pass a `session_id` to run alongside other streams; audio only follows the default
+/// session. With `binary`, frames arrive as raw payloads (see `wire`); with `delta`, as
+/// changed tiles (see `delta`); with `yuv`, as raw planes (see `yuv`). `config`
+/// overrides the capture defaults for this stream only. Returns the session id.
 #[cfg(target_os = "macos")]
 #[tauri::command]
 fn start_capture_stream_cmd<R: Runtime>(
     _app: tauri::AppHandle<R>,
     target_id: Option<String>,
-    on_frame: tauri::ipc::Channel<desktop::FrameData>,
+    session_id: Option<String>,
+    config: Option<capture_config::CaptureConfig>,
+    binary: Option<bool>,
+    delta: Option<delta::DeltaConfig>,
+    yuv: Option<yuv::YuvOutput>,
+    on_frame: tauri::ipc::Channel<tauri::ipc::Response>,
     on_audio: tauri::ipc::Channel<desktop::AudioData>,
-) -> Result<()> {
+) -> Result<String> {
     // macOS: unified module handles both - start video first, then audio
-    desktop::start_capture_stream(target_id, on_frame)?;
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    desktop::start_capture_session(
+        &session_id,
+        target_id,
+        wire::FrameSink::new(on_frame, binary.unwrap_or(false))
+            .with_config(config)
+            .with_delta(delta)
+            .with_yuv(yuv),
+    )?;
 
-    if let Err(e) = desktop::start_audio_stream(on_audio) {
-        log::warn!("[ScreenCapture] Audio capture failed to start: {:?}", e);
+    if session_id == sessions::DEFAULT_SESSION {
+        if let Err(e) = desktop::start_audio_stream(on_audio) {
+            log::warn!("[ScreenCapture] Audio capture failed to start: {:?}", e);
+        }
     }
 
-    Ok(())
+    Ok(session_id)
 }
 
-/// Start capture with channel-based streaming (Windows/Linux)
+/// Start capture with channel-based streaming (Windows/Linux); see the macOS variant
 #[cfg(all(
     not(any(target_os = "android", target_os = "ios")),
     not(target_os = "macos")
@@ -280,30 +604,475 @@ fn start_capture_stream_cmd<R: Runtime>(
 fn start_capture_stream_cmd<R: Runtime>(
     _app: tauri::AppHandle<R>,
     target_id: Option<String>,
-    on_frame: tauri::ipc::Channel<desktop::FrameData>,
+    session_id: Option<String>,
+    config: Option<capture_config::CaptureConfig>,
+    binary: Option<bool>,
+    delta: Option<delta::DeltaConfig>,
+    yuv: Option<yuv::YuvOutput>,
+    on_frame: tauri::ipc::Channel<tauri::ipc::Response>,
     on_audio: tauri::ipc::Channel<audio::AudioData>,
-) -> Result<()> {
+) -> Result<String> {
     // Start video capture
-    desktop::start_capture_stream(target_id, on_frame)?;
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    desktop::start_capture_session(
+        &session_id,
+        target_id,
+        wire::FrameSink::new(on_frame, binary.unwrap_or(false))
+            .with_config(config)
+            .with_delta(delta)
+            .with_yuv(yuv),
+    )?;
 
-    // Start audio capture
-    if let Err(e) = audio::start_audio_stream(on_audio) {
-        log::warn!("[ScreenCapture] Audio capture failed to start: {:?}", e);
+    // Start audio capture (default session only)
+    if session_id == sessions::DEFAULT_SESSION {
+        if let Err(e) = audio::start_audio_stream(on_audio) {
+            log::warn!("[ScreenCapture] Audio capture failed to start: {:?}", e);
+        }
     }
 
-    Ok(())
+    Ok(session_id)
 }
 
 /// Start video-only capture with channel-based streaming (desktop only)
 /// Frames are pushed to frontend via channel instead of polling
+/// Pass a `session_id` to run alongside other streams. With `binary`, frames arrive as
+/// raw payloads (see `wire`); with `delta`, as changed tiles (see `delta`); with `yuv`,
+/// as raw planes (see `yuv`). `config` overrides the capture defaults for this stream
+/// only. Returns the session id.
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 #[tauri::command]
 fn start_video_stream_cmd<R: Runtime>(
     _app: tauri::AppHandle<R>,
     target_id: Option<String>,
-    on_frame: tauri::ipc::Channel<desktop::FrameData>,
+    session_id: Option<String>,
+    config: Option<capture_config::CaptureConfig>,
+    binary: Option<bool>,
+    delta: Option<delta::DeltaConfig>,
+    yuv: Option<yuv::YuvOutput>,
+    on_frame: tauri::ipc::Channel<tauri::ipc::Response>,
+) -> Result<String> {
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    desktop::start_capture_session(
+        &session_id,
+        target_id,
+        wire::FrameSink::new(on_frame, binary.unwrap_or(false))
+            .with_config(config)
+            .with_delta(delta)
+            .with_yuv(yuv),
+    )?;
+    Ok(session_id)
+}
+
+/// Point a running session (the default one unless `session_id` is given) at another
+/// target without stopping the stream. Emits `screen-capture://target-switched`;
+/// `active-window` and `app:{name}` keep switching to the focused window / that app's window.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn switch_capture_target_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    target_id: Option<String>,
+    session_id: Option<String>,
+) -> Result<()> {
+    desktop::switch_capture_target(sessions::resolve(session_id.as_deref()), target_id)
+}
+
+/// Push refreshed thumbnails of every target over `on_update` about once a second while
+/// the target picker is open. Replaces a running preview.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn start_target_preview_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    on_update: tauri::ipc::Channel<Vec<thumbnails::Thumbnail>>,
+) -> Result<()> {
+    thumbnails::start_preview(on_update);
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn stop_target_preview_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<()> {
+    thumbnails::stop_preview();
+    Ok(())
+}
+
+/// Running capture sessions (desktop only)
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn list_capture_sessions_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+) -> Result<Vec<sessions::SessionInfo>> {
+    Ok(desktop::list_capture_sessions())
+}
+
+/// Tell a session (the default one unless `session_id` is given) that the consumer is
+/// done with `frame_count`. Consumers that ack get frames dropped instead of queued
+/// while they fall behind (see `backpressure`).
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn ack_frames_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    session_id: Option<String>,
+    frame_count: u64,
+) -> Result<()> {
+    backpressure::ack(sessions::resolve(session_id.as_deref()), frame_count);
+    Ok(())
+}
+
+/// Achieved fps, stage times, throughput, quality and dropped frames of one session, or
+/// of all of them
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn get_capture_stats_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    session_id: Option<String>,
+) -> Result<Vec<stats::CaptureStats>> {
+    Ok(backpressure::stats(session_id.as_deref()))
+}
+
+/// Saved capture profiles, sorted by name
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn list_capture_profiles_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Vec<profiles::CaptureProfile>> {
+    profiles::list()
+}
+
+/// Save a profile (target, capture config, crop), replacing one with the same name
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn save_capture_profile_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    profile: profiles::CaptureProfile,
+) -> Result<profiles::CaptureProfile> {
+    profiles::save(profile)
+}
+
+/// Delete a profile; false if there was none by that name
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn delete_capture_profile_cmd<R: Runtime>(_app: tauri::AppHandle<R>, name: String) -> Result<bool> {
+    profiles::delete(&name)
+}
+
+/// Start a video stream from a saved profile: its config is applied and the session runs
+/// on its target with its crop. Returns the session id.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn start_capture_profile_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    name: String,
+    session_id: Option<String>,
+    binary: Option<bool>,
+    on_frame: tauri::ipc::Channel<tauri::ipc::Response>,
+) -> Result<String> {
+    let session_id = sessions::resolve(session_id.as_deref()).to_string();
+    profiles::start(&name, &session_id, wire::FrameSink::new(on_frame, binary.unwrap_or(false)))?;
+    Ok(session_id)
+}
+
+/// Capture windows matching `rule` automatically: its session (`watch:{id}`) streams to
+/// `on_frame` while a matching window is open. Replaces a rule with the same id.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn add_watch_rule_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    rule: watch_rules::WatchRule,
+    binary: Option<bool>,
+    on_frame: tauri::ipc::Channel<tauri::ipc::Response>,
+) -> Result<watch_rules::WatchRuleStatus> {
+    watch_rules::add(rule, wire::FrameSink::new(on_frame, binary.unwrap_or(false)))
+}
+
+/// Remove a watch rule and stop its session; false if there was none
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn remove_watch_rule_cmd<R: Runtime>(_app: tauri::AppHandle<R>, id: String) -> Result<bool> {
+    Ok(watch_rules::remove(&id))
+}
+
+/// Registered watch rules and the window each one captures
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn list_watch_rules_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Vec<watch_rules::WatchRuleStatus>> {
+    Ok(watch_rules::list())
+}
+
+/// The focused window's app, title and pid, without capturing it
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn get_active_window_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Option<active_window::ActiveWindow>> {
+    active_window::current()
+}
+
+/// Stream focus and title changes to `on_change`, starting with the current window.
+/// Returns the stream id for `stop_active_window_stream_cmd`.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn start_active_window_stream_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    on_change: tauri::ipc::Channel<active_window::ActiveWindowChange>,
+) -> Result<String> {
+    active_window::start(on_change)
+}
+
+/// Close an active-window stream; false if there was none
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn stop_active_window_stream_cmd<R: Runtime>(_app: tauri::AppHandle<R>, id: String) -> Result<bool> {
+    Ok(active_window::stop(&id))
+}
+
+/// Burn a timestamp and/or label into frames. With `session_id` it applies to that
+/// session only (None drops its override); otherwise it's the default for all sessions.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn set_frame_overlay_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    overlay: Option<overlay::FrameOverlay>,
+    session_id: Option<String>,
+) -> Result<()> {
+    overlay::set(session_id.as_deref(), overlay);
+    Ok(())
+}
+
+/// Replace the privacy regions of `target_id` ("*" for every target); an empty list
+/// clears them. Saved across restarts.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn set_privacy_regions_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    target_id: String,
+    regions: Vec<privacy::PrivacyRegion>,
+) -> Result<()> {
+    privacy::set(&target_id, regions)
+}
+
+/// Privacy regions by target id
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn list_privacy_regions_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+) -> Result<std::collections::BTreeMap<String, Vec<privacy::PrivacyRegion>>> {
+    Ok(privacy::list())
+}
+
+/// Replace the list of apps whose windows are never sent; returns the stored list
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn set_redacted_apps_cmd<R: Runtime>(_app: tauri::AppHandle<R>, apps: Vec<String>) -> Result<Vec<String>> {
+    redaction::set(apps)
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn get_redacted_apps_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Vec<String>> {
+    Ok(redaction::list())
+}
+
+/// Take one full-resolution screenshot of a target (primary monitor if omitted), PNG
+/// unless `format` says otherwise. Written to `path` if given, else returned as base64.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+async fn capture_screenshot_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    target_id: Option<String>,
+    format: Option<capture_config::FrameEncoding>,
+    path: Option<String>,
+) -> Result<screenshot::Screenshot> {
+    let format = format.unwrap_or(capture_config::FrameEncoding::Png);
+    tauri::async_runtime::spawn_blocking(move || {
+        screenshot::capture(target_id.as_deref(), format, path.as_deref().map(std::path::Path::new))
+    })
+    .await
+    .map_err(|e| Error::Platform(e.to_string()))?
+}
+
+/// How similar two encoded images (PNG/JPEG) are: dHash distance and SSIM
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+async fn compare_frames_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    a: Vec<u8>,
+    b: Vec<u8>,
+) -> Result<similarity::Similarity> {
+    tauri::async_runtime::spawn_blocking(move || similarity::compare(&a, &b))
+        .await
+        .map_err(|e| Error::Platform(e.to_string()))?
+}
+
+/// Find QR codes and barcodes in `image` (PNG/JPEG bytes), or without one in the
+/// default stream's next frame
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+async fn detect_codes_cmd<R: Runtime>(_app: tauri::AppHandle<R>, image: Option<Vec<u8>>) -> Result<codes::CodeScan> {
+    match image {
+        Some(image) => tauri::async_runtime::spawn_blocking(move || codes::scan_image(&image))
+            .await
+            .map_err(|e| Error::Platform(e.to_string()))?,
+        None => codes::scan_latest_frame().await,
+    }
+}
+
+/// Luminance, dominant colors and dark/light guess of a session's latest frame (None =
+/// default session); None before its first frame
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn get_frame_stats_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    session_id: Option<String>,
+) -> Result<Option<frame_stats::FrameStats>> {
+    Ok(frame_stats::latest(session_id.as_deref().unwrap_or(sessions::DEFAULT_SESSION)))
+}
+
+/// Read the text of a capture target (None = primary monitor) at full resolution, or
+/// with `latest_frame` of the default stream's next frame. `languages` are Tesseract
+/// codes; empty detects the script (see `ocr`).
+#[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+#[tauri::command]
+async fn ocr_frame_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    target_id: Option<String>,
+    latest_frame: Option<bool>,
+    languages: Option<Vec<String>>,
+) -> Result<ocr::FrameText> {
+    let options = ocr::OcrOptions { languages: languages.unwrap_or_default() };
+    if latest_frame.unwrap_or(false) {
+        return ocr::recognize_latest_frame(&options).await;
+    }
+    tauri::async_runtime::spawn_blocking(move || ocr::recognize_target(target_id.as_deref(), &options))
+        .await
+        .map_err(|e| Error::Platform(e.to_string()))?
+}
+
+/// OCR a target every `intervalSecs` and emit `screen-capture://text-changed` with the
+/// lines added and removed since the last read. Replaces a watch with the same id.
+#[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+#[tauri::command]
+fn start_text_watch_cmd<R: Runtime>(_app: tauri::AppHandle<R>, config: text_watch::TextWatchConfig) -> Result<()> {
+    text_watch::start(config)
+}
+
+/// Stop a text watch; false if none had that id
+#[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+#[tauri::command]
+fn stop_text_watch_cmd<R: Runtime>(_app: tauri::AppHandle<R>, id: String) -> Result<bool> {
+    Ok(text_watch::stop(&id))
+}
+
+#[cfg(all(feature = "ocr", not(any(target_os = "android", target_os = "ios"))))]
+#[tauri::command]
+fn list_text_watches_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Vec<text_watch::TextWatchConfig>> {
+    Ok(text_watch::list())
+}
+
+/// Write a full-resolution snapshot every `intervalSecs` to a directory, deleting the
+/// oldest past `maxFiles` / `maxBytes`. Replaces a running snapshot writer.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn start_snapshots_cmd<R: Runtime>(_app: tauri::AppHandle<R>, config: snapshots::SnapshotConfig) -> Result<()> {
+    snapshots::start(config)
+}
+
+/// Stop writing snapshots; false if none were being written
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn stop_snapshots_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<bool> {
+    Ok(snapshots::stop())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn get_snapshot_status_cmd<R: Runtime>(_app: tauri::AppHandle<R>) -> Result<Option<snapshots::SnapshotStatus>> {
+    Ok(snapshots::status())
+}
+
+/// Record a target (primary monitor if omitted) to an MP4 file at `path`, with system
+/// and/or microphone audio tracks when `options` ask for them. An existing file is only
+/// replaced with `options.overwrite`; refused while incognito mode is on.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn start_recording_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    target_id: Option<String>,
+    path: String,
+    options: Option<recording::RecordingOptions>,
 ) -> Result<()> {
-    desktop::start_capture_stream(target_id, on_frame)
+    recording::start(target_id, path.into(), options.unwrap_or_default())
+}
+
+/// Finish the running recording; returns the file path and duration
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn stop_recording_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+) -> Result<recording::RecordingSummary> {
+    recording::stop()
+}
+
+/// Write the last `seconds` of the replay buffer (all of it if omitted) to `path`, as an
+/// MP4 or a directory of images. Without a path the export goes to `replays` in the app
+/// data dir.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn export_replay_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    seconds: Option<f64>,
+    path: Option<String>,
+    format: Option<replay::ReplayFormat>,
+) -> Result<recording::RecordingSummary> {
+    replay::export(seconds, path.map(Into::into), format.unwrap_or_default())
+}
+
+/// Write the last `seconds` of captured frames as a looping GIF (for attaching to
+/// notifications). Without a path the GIF goes to `replays` in the app data dir.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn export_gif_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    seconds: Option<f64>,
+    path: Option<String>,
+    options: Option<replay::GifOptions>,
+) -> Result<recording::RecordingSummary> {
+    replay::export_gif(seconds, path.map(Into::into), options.unwrap_or_default())
+}
+
+/// Change the capture defaults (FPS / JPEG quality / max width, ...). Unset fields keep
+/// their value; streams started with their own `config` keep it for the fields it sets.
+/// Returns the defaults now in effect.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn set_capture_config_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    config: capture_config::CaptureConfig,
+) -> Result<capture_config::CaptureConfig> {
+    Ok(capture_config::update(&config))
+}
+
+/// Windows (`window:<id>` targets) to leave out of display captures. Replaces the
+/// previous list; applied by ScreenCaptureKit from the next stream start.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn set_excluded_windows_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    target_ids: Vec<String>,
+) -> Result<()> {
+    exclusions::set(&target_ids)
+}
+
+/// Hide Observer's own windows from the target list and (on macOS) display captures.
+/// On by default.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn set_exclude_own_windows_cmd<R: Runtime>(_app: tauri::AppHandle<R>, exclude: bool) -> Result<()> {
+    exclusions::set_exclude_self(exclude);
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+#[tauri::command]
+fn get_capture_config_cmd<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+) -> Result<capture_config::CaptureConfig> {
+    Ok(capture_config::get())
 }
 
 /// Start audio-only capture with channel-based streaming (macOS)
@@ -0,0 +1,133 @@
+//! Capture target lifecycle: noticing that a captured window closed or its monitor was
+//! unplugged, telling the frontend, and applying `capture_config::target_lost_policy`.
+//!
+//! xcap grabs simply start failing when the target is gone, so the polling loop feeds a
+//! `LossTracker` and looks the target up again once failures have persisted for
+//! `LOST_AFTER`. WGC and PipeWire end their stream instead, which the backends report as
+//! `Error::TargetLost`. Either way the session gets one `targetLost` /
+//! `monitorDisconnected` event, and a `targetRestored` one if it waited and the target
+//! came back.
+//!
+//! ScreenCaptureKit ends the stream itself when its target goes away, so macOS sessions
+//! report the loss and stop regardless of the policy.
+
+use crate::capture_config::TargetLostPolicy;
+use crate::events;
+use crate::targets::TargetKind;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// How long grabs must keep failing before the target is looked up again
+pub const LOST_AFTER: Duration = Duration::from_secs(1);
+/// How often a lost target is looked for while waiting
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LifecycleKind {
+    /// The captured window closed
+    TargetLost,
+    /// A lost target is capturable again
+    TargetRestored,
+    /// The captured monitor was unplugged
+    MonitorDisconnected,
+}
+
+/// Payload of `events::TARGET_LIFECYCLE`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetLifecycle {
+    pub kind: LifecycleKind,
+    pub session_id: String,
+    /// Target the event is about (None = primary monitor)
+    pub target_id: Option<String>,
+    /// What the session does next
+    pub policy: TargetLostPolicy,
+}
+
+/// Event kind for losing `target` (None = primary monitor)
+pub fn lost_kind(target: Option<&(TargetKind, u32)>) -> LifecycleKind {
+    match target {
+        Some((TargetKind::Window, _)) => LifecycleKind::TargetLost,
+        Some((TargetKind::Monitor | TargetKind::VirtualDesktop, _)) | None => LifecycleKind::MonitorDisconnected,
+    }
+}
+
+pub fn notify(kind: LifecycleKind, session_id: &str, target_id: Option<String>, policy: TargetLostPolicy) {
+    log::warn!("[ScreenCapture] Session {} {:?} ({:?}), policy {:?}", session_id, kind, target_id, policy);
+    events::emit(events::TARGET_LIFECYCLE, TargetLifecycle {
+        kind,
+        session_id: session_id.to_string(),
+        target_id,
+        policy,
+    });
+}
+
+/// Tracks a run of failed grabs so a flaky frame isn't mistaken for a closed window
+#[derive(Debug, Default)]
+pub struct LossTracker {
+    failing_since: Option<Instant>,
+    last_lookup: Option<Instant>,
+}
+
+impl LossTracker {
+    /// A frame was captured
+    pub fn success(&mut self) {
+        self.failing_since = None;
+        self.last_lookup = None;
+    }
+
+    /// Whether the previous grab failed too
+    pub fn is_failing(&self) -> bool {
+        self.failing_since.is_some()
+    }
+
+    /// A grab failed at `now`. Returns true when the target should be looked up again:
+    /// after `LOST_AFTER` of failures, then at most once per `RETRY_INTERVAL`.
+    pub fn failure(&mut self, now: Instant) -> bool {
+        let since = *self.failing_since.get_or_insert(now);
+        if now.duration_since(since) < LOST_AFTER {
+            return false;
+        }
+        if self.last_lookup.is_some_and(|t| now.duration_since(t) < RETRY_INTERVAL) {
+            return false;
+        }
+        self.last_lookup = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_after_sustained_failures_then_throttles() {
+        let start = Instant::now();
+        let mut tracker = LossTracker::default();
+        assert!(!tracker.failure(start));
+        assert!(tracker.is_failing());
+        assert!(!tracker.failure(start + Duration::from_millis(500)));
+        assert!(tracker.failure(start + LOST_AFTER));
+        assert!(!tracker.failure(start + LOST_AFTER + Duration::from_millis(100)));
+        assert!(tracker.failure(start + LOST_AFTER + RETRY_INTERVAL));
+    }
+
+    #[test]
+    fn success_resets_the_run() {
+        let start = Instant::now();
+        let mut tracker = LossTracker::default();
+        tracker.failure(start);
+        tracker.success();
+        assert!(!tracker.is_failing());
+        assert!(!tracker.failure(start + LOST_AFTER));
+    }
+
+    #[test]
+    fn monitors_disconnect_and_windows_are_lost() {
+        assert_eq!(lost_kind(Some(&(TargetKind::Window, 7))), LifecycleKind::TargetLost);
+        assert_eq!(lost_kind(Some(&(TargetKind::Monitor, 1))), LifecycleKind::MonitorDisconnected);
+        assert_eq!(lost_kind(None), LifecycleKind::MonitorDisconnected);
+        assert_eq!(serde_json::to_value(LifecycleKind::TargetRestored).unwrap(), "targetRestored");
+    }
+}
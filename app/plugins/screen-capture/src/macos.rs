@@ -6,9 +6,37 @@
 //!
 //! This fixes the "stream output NOT found" errors that occurred when
 //! running separate video and audio SCStreams.
+//!
+//! That unified stream is the default capture session. Additional named sessions
+//! (see `sessions`) each get their own video-only SCStream.
+//!
+//! Display streams leave out the windows listed in `exclusions`. Before macOS 13 every
+//! video session falls back to xcap polling instead (`macos_xcap`) and audio is
+//! unavailable.
 
+use crate::backpressure;
 use crate::audio_pipeline::{SharedResampler, TARGET_SAMPLE_RATE};
-use crate::capture_config;
+use crate::capture_config::{CaptureConfig, FrameEncoding, TargetLostPolicy};
+use crate::delta::{self, FrameDelta, PixelOrder};
+use crate::frame_stats::{self, FrameStats};
+use crate::similarity;
+use crate::yuv::YuvFrame;
+use crate::encode;
+use crate::events;
+use crate::exclusions;
+use crate::follow::{self, Follow};
+use crate::lifecycle;
+use crate::macos_xcap;
+use crate::wire::FrameSink;
+use crate::frames;
+use crate::geometry::{self, CropRect, FrameGeometry, SourceMetrics};
+use crate::overlay;
+use crate::privacy;
+use crate::redaction;
+use crate::pause;
+use crate::secure_input;
+use crate::sessions::{self, SessionInfo};
+use crate::stats::Stage;
 use crate::error::{Error, Result};
 use crate::targets::{self, CaptureTarget, TargetKind};
 use base64::{engine::general_purpose::STANDARD, Engine};
@@ -19,8 +47,10 @@ use screencapturekit::prelude::*;
 use screencapturekit::shareable_content::SCShareableContentInfo;
 use screencapturekit::stream::delegate_trait::StreamCallbacks;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tauri::ipc::Channel;
 use tauri::{plugin::PluginApi, AppHandle, Runtime};
 
@@ -28,9 +58,11 @@ use tauri::{plugin::PluginApi, AppHandle, Runtime};
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FrameData {
-    /// Raw JPEG bytes (sent as Uint8Array to frontend)
+    /// Encoded image bytes (sent as Uint8Array to frontend), in `format`
     #[serde(with = "serde_bytes")]
     pub frame: Vec<u8>,
+    /// Image format of `frame` (unless `yuv` is set)
+    pub format: FrameEncoding,
     /// Unix timestamp in seconds
     pub timestamp: f64,
     /// Frame dimensions
@@ -38,6 +70,21 @@ pub struct FrameData {
     pub height: u32,
     /// Frame sequence number
     pub frame_count: u64,
+    /// Source rect and DPI of what the frame shows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceMetrics>,
+    /// How to reassemble the frame on delta streams (see `delta`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<FrameDelta>,
+    /// Plane layout on YUV streams (see `yuv`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yuv: Option<YuvFrame>,
+    /// Change from the previous frame, 0–1 (see `similarity`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<f32>,
+    /// Luminance and dominant colors, with `capture_config::frame_stats` (see `frame_stats`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FrameStats>,
 }
 
 /// Audio data sent through the channel to the frontend
@@ -54,9 +101,9 @@ pub struct AudioData {
     pub chunk_count: u64,
 }
 
-// Capture quality (max width / JPEG quality / FPS) is runtime-tunable — pushed from the
-// frontend before capture starts and read from `capture_config` when we build the stream
-// and encode frames. See that module for defaults and rationale.
+// Capture quality (max width / JPEG quality / FPS) is runtime-tunable — read from the
+// stream's `CaptureConfig` (its `FrameSink`'s, over the `capture_config` defaults) when we
+// build the stream and encode frames. See that module for defaults and rationale.
 const AUDIO_SAMPLE_RATE: u32 = 48000;
 
 /// Unified capture state for both video and audio
@@ -80,7 +127,7 @@ struct UnifiedCaptureState {
     /// Active SCStream instance
     active_stream: Mutex<Option<SCStream>>,
     /// Video channel (set when video is requested)
-    video_channel: RwLock<Option<Channel<FrameData>>>,
+    video_channel: RwLock<Option<FrameSink>>,
     /// Audio channel (set when audio is requested)
     audio_channel: RwLock<Option<Channel<AudioData>>>,
     /// Audio resampler for 16kHz transcription output
@@ -203,7 +250,11 @@ pub fn init<R: Runtime, C: serde::de::DeserializeOwned>(
     _app: &AppHandle<R>,
     _api: PluginApi<R, C>,
 ) -> Result<()> {
-    log::info!("[ScreenCapture] macOS unified capture initialized (ScreenCaptureKit)");
+    if macos_xcap::sck_supported() {
+        log::info!("[ScreenCapture] macOS unified capture initialized (ScreenCaptureKit)");
+    } else {
+        log::info!("[ScreenCapture] macOS capture initialized (xcap fallback, no system audio)");
+    }
     Ok(())
 }
 
@@ -212,12 +263,28 @@ pub fn get_capture_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget
     targets::get_all_targets(include_thumbnails)
 }
 
-/// Start video capture stream
+/// Start video capture on the default session
 /// If capture is already running (for audio), reuses the existing stream
 pub fn start_capture_stream(
     target_id: Option<String>,
-    on_frame: Channel<FrameData>,
+    on_frame: FrameSink,
 ) -> Result<()> {
+    if target_id.as_deref().and_then(Follow::parse).is_some() {
+        return start_capture_session(sessions::DEFAULT_SESSION, target_id, on_frame);
+    }
+    backpressure::register(sessions::DEFAULT_SESSION, on_frame.flow());
+    if !macos_xcap::sck_supported() {
+        return start_xcap_session(sessions::DEFAULT_SESSION, target_id, on_frame);
+    }
+    // The virtual desktop spans displays, which one SCStream can't; it polls through xcap
+    if is_virtual_desktop(target_id.as_deref()) {
+        stop_default_video()?;
+        return start_xcap_session(sessions::DEFAULT_SESSION, target_id, on_frame);
+    }
+    if let Some(previous) = video_sessions().lock().remove(sessions::DEFAULT_SESSION) {
+        previous.stop();
+    }
+
     let state = get_capture_state();
 
     log::info!("[ScreenCapture] Starting video stream with target: {:?}", target_id);
@@ -246,6 +313,10 @@ pub fn start_capture_stream(
 /// Start audio capture stream
 /// If capture is already running (for video), reuses the existing stream
 pub fn start_audio_stream(on_audio: Channel<AudioData>) -> Result<()> {
+    if !macos_xcap::sck_supported() {
+        return Err(Error::AudioNotAvailable);
+    }
+
     let state = get_capture_state();
 
     log::info!("[ScreenCapture] Starting audio stream");
@@ -266,9 +337,18 @@ pub fn start_audio_stream(on_audio: Channel<AudioData>) -> Result<()> {
     Ok(())
 }
 
-/// Stop video capture
+/// Stop video capture on the default session
 /// Only tears down SCStream if audio is also stopped
 pub async fn stop_capture() -> Result<()> {
+    stop_default_video()
+}
+
+fn stop_default_video() -> Result<()> {
+    // Pre-13 fallback: the default session is a polling thread, not the unified stream
+    if let Some(session) = video_sessions().lock().remove(sessions::DEFAULT_SESSION) {
+        session.stop();
+    }
+
     let state = get_capture_state();
 
     log::info!("[ScreenCapture] Stopping video stream...");
@@ -279,6 +359,7 @@ pub async fn stop_capture() -> Result<()> {
         *channel = None;
     }
     state.wants_video.store(false, Ordering::SeqCst);
+    geometry::set_current(None);
 
     // Check if we should tear down the stream
     maybe_stop_capture(&state);
@@ -324,10 +405,371 @@ pub fn get_broadcast_status() -> Result<serde_json::Value> {
         "wantsAudio": state.wants_audio.load(Ordering::SeqCst),
         "frameCount": state.frame_count.load(Ordering::SeqCst),
         "audioChunkCount": state.audio_chunk_count.load(Ordering::SeqCst),
-        "targetId": state.selected_target.lock().clone()
+        "targetId": state.selected_target.lock().clone(),
+        "sessions": list_capture_sessions()
     }))
 }
 
+/// A named, video-only capture session (see `sessions`). The default session is the
+/// unified video+audio stream above; every other session gets its own SCStream (or, on
+/// the pre-13 fallback, every session including the default is an xcap poller).
+struct VideoSession {
+    id: String,
+    target_id: Mutex<Option<String>>,
+    /// The stream's capture settings, for rebuilding its configuration on a switch
+    config: CaptureConfig,
+    is_active: Arc<AtomicBool>,
+    frame_count: Arc<AtomicU64>,
+    stream: Mutex<Option<SessionStream>>,
+}
+
+enum SessionStream {
+    ScreenCaptureKit(SCStream),
+    Xcap(macos_xcap::Poller),
+}
+
+impl VideoSession {
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            id: self.id.clone(),
+            target_id: self.target_id.lock().clone(),
+            is_active: self.is_active.load(Ordering::SeqCst),
+            frame_count: self.frame_count.load(Ordering::SeqCst),
+            following: follow::is_following(&self.id),
+        }
+    }
+
+    fn stop(&self) {
+        self.is_active.store(false, Ordering::SeqCst);
+        match self.stream.lock().take() {
+            Some(SessionStream::ScreenCaptureKit(stream)) => {
+                if let Err(e) = stream.stop_capture() {
+                    log::warn!("[ScreenCapture] Error stopping session {} stream: {}", self.id, e);
+                }
+            }
+            Some(SessionStream::Xcap(poller)) => poller.stop(),
+            None => {}
+        }
+        geometry::set_for(&self.id, None);
+        frame_stats::clear(&self.id);
+    }
+}
+
+static VIDEO_SESSIONS: std::sync::OnceLock<Mutex<HashMap<String, Arc<VideoSession>>>> =
+    std::sync::OnceLock::new();
+
+fn video_sessions() -> &'static Mutex<HashMap<String, Arc<VideoSession>>> {
+    VIDEO_SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Start (or restart) a capture session. The default session goes through the unified
+/// stream; other ids get an independent video-only stream.
+pub fn start_capture_session(
+    session_id: &str,
+    target_id: Option<String>,
+    on_frame: FrameSink,
+) -> Result<()> {
+    // Following sessions start on the picked window (the primary display if none)
+    if let Some(follow) = target_id.as_deref().and_then(Follow::parse) {
+        let current = follow.initial_window()?;
+        start_capture_session(session_id, current.map(|id| format!("window:{}", id)), on_frame)?;
+        start_following(session_id, follow, current);
+        return Ok(());
+    }
+    follow::stop(session_id);
+
+    if session_id == sessions::DEFAULT_SESSION {
+        return start_capture_stream(target_id, on_frame);
+    }
+    backpressure::register(session_id, on_frame.flow());
+    if !macos_xcap::sck_supported() || is_virtual_desktop(target_id.as_deref()) {
+        return start_xcap_session(session_id, target_id, on_frame);
+    }
+
+    log::info!(
+        "[ScreenCapture] Starting capture session {} with target: {:?}",
+        session_id,
+        target_id
+    );
+
+    // Replace any session already running under this id
+    if let Some(previous) = video_sessions().lock().remove(session_id) {
+        previous.stop();
+    }
+
+    let content = SCShareableContent::get()
+        .map_err(|e| Error::Platform(format!("Failed to get shareable content: {:?}", e)))?;
+    let (filter, source_frame) = content_filter(&content, target_id.as_deref())?;
+    let capture_config = *on_frame.config();
+    let (out_width, out_height) =
+        capture_pixel_dimensions(&filter, source_frame.width, source_frame.height, capture_config.max_width());
+    geometry::set_for(session_id, Some(source_geometry(&filter, &source_frame, out_width, out_height)));
+
+    let config = stream_configuration(&capture_config, out_width, out_height, false);
+
+    let is_active = Arc::new(AtomicBool::new(true));
+    let frame_count = Arc::new(AtomicU64::new(0));
+
+    let id_for_delegate = session_id.to_string();
+    let target_for_delegate = target_id.clone();
+    let active_for_delegate = is_active.clone();
+    let delegate = StreamCallbacks::new()
+        .on_stop(move |error| {
+            if let Some(e) = &error {
+                log::error!("[ScreenCapture] Session {} stream stopped with error: {}", id_for_delegate, e);
+                report_stream_lost(&id_for_delegate, target_for_delegate.clone());
+            }
+            active_for_delegate.store(false, Ordering::SeqCst);
+        })
+        .on_error(|error| {
+            log::error!("[ScreenCapture] SCStream error: {}", error);
+        });
+
+    let mut stream = SCStream::new_with_delegate(&filter, &config, delegate);
+
+    let count_for_video = frame_count.clone();
+    let id_for_video = session_id.to_string();
+    stream.add_output_handler(
+        Box::new(move |sample: CMSampleBuffer, of_type: SCStreamOutputType| {
+            if of_type != SCStreamOutputType::Screen {
+                return;
+            }
+            let Some(image_buffer) = sample.image_buffer() else {
+                return;
+            };
+            let Ok(guard) = image_buffer.lock(CVPixelBufferLockFlags::READ_ONLY) else {
+                return;
+            };
+
+            if pause::is_paused() || secure_input::should_skip_frame() {
+                let _ = guard.as_slice().first();
+                return;
+            }
+
+            let Some(quality) = on_frame.admit() else {
+                return;
+            };
+            if let Some(frame_data) = encode_bgra_frame(
+                guard.as_slice(),
+                guard.width() as u32,
+                guard.height() as u32,
+                guard.bytes_per_row(),
+                &count_for_video,
+                &id_for_video,
+                quality,
+                &on_frame,
+            ) {
+                if let Err(e) = on_frame.send(frame_data) {
+                    log::error!("[ScreenCapture] Failed to send session video frame: {:?}", e);
+                }
+            }
+        }),
+        SCStreamOutputType::Screen,
+    );
+
+    if let Err(e) = stream.start_capture() {
+        geometry::set_for(session_id, None);
+        return Err(Error::Platform(format!("Failed to start stream: {}", e)));
+    }
+
+    video_sessions().lock().insert(
+        session_id.to_string(),
+        Arc::new(VideoSession {
+            id: session_id.to_string(),
+            target_id: Mutex::new(target_id),
+            config: capture_config,
+            is_active,
+            frame_count,
+            stream: Mutex::new(Some(SessionStream::ScreenCaptureKit(stream))),
+        }),
+    );
+
+    log::info!("[ScreenCapture] Capture session {} started", session_id);
+    Ok(())
+}
+
+fn is_virtual_desktop(target_id: Option<&str>) -> bool {
+    target_id == Some(targets::VIRTUAL_DESKTOP)
+}
+
+/// Start (or restart) a session on the xcap fallback
+fn start_xcap_session(session_id: &str, target_id: Option<String>, on_frame: FrameSink) -> Result<()> {
+    let target = target_id.as_deref().map(targets::parse_target_id).transpose()?;
+    if let Some(previous) = video_sessions().lock().remove(session_id) {
+        previous.stop();
+    }
+
+    let is_active = Arc::new(AtomicBool::new(true));
+    let frame_count = Arc::new(AtomicU64::new(0));
+    let config = *on_frame.config();
+    let poller = macos_xcap::start(session_id, target, on_frame, is_active.clone(), frame_count.clone())?;
+
+    video_sessions().lock().insert(
+        session_id.to_string(),
+        Arc::new(VideoSession {
+            id: session_id.to_string(),
+            target_id: Mutex::new(target_id),
+            config,
+            is_active,
+            frame_count,
+            stream: Mutex::new(Some(SessionStream::Xcap(poller))),
+        }),
+    );
+
+    log::info!("[ScreenCapture] Capture session {} started (xcap fallback)", session_id);
+    Ok(())
+}
+
+/// Point a running session at another target without restarting it. SCStreams get a new
+/// content filter (and output size) in place; `events::TARGET_SWITCHED` is emitted.
+/// `targets::ACTIVE_WINDOW` and `app:{name}` make the session follow the focused window
+/// or that app's windows from then on.
+pub fn switch_capture_target(session_id: &str, target_id: Option<String>) -> Result<()> {
+    if let Some(follow) = target_id.as_deref().and_then(Follow::parse) {
+        if !list_capture_sessions().iter().any(|s| s.id == session_id) {
+            return Err(Error::NotStarted);
+        }
+        let current = follow.initial_window()?;
+        if let Some(window_id) = current {
+            retarget(session_id, Some(format!("window:{}", window_id)))?;
+        }
+        start_following(session_id, follow, current);
+        return Ok(());
+    }
+    follow::stop(session_id);
+    retarget(session_id, target_id)
+}
+
+/// Start the follow watcher for a running session showing window `current`
+fn start_following(session_id: &str, follow: Follow, current: Option<u32>) {
+    let id = session_id.to_string();
+    let switch_id = id.clone();
+    follow::start(
+        session_id,
+        follow,
+        current,
+        move || list_capture_sessions().iter().any(|s| s.id == id && s.is_active),
+        move |target_id| retarget(&switch_id, Some(target_id)),
+    );
+}
+
+fn retarget(session_id: &str, target_id: Option<String>) -> Result<()> {
+    log::info!("[ScreenCapture] Switching session {} to {:?}", session_id, target_id);
+
+    // Polled sessions (pre-13 systems, the virtual desktop) switch their poller in place
+    let polled = video_sessions()
+        .lock()
+        .get(session_id)
+        .filter(|session| matches!(&*session.stream.lock(), Some(SessionStream::Xcap(_))))
+        .cloned();
+    if !macos_xcap::sck_supported() || polled.is_some() {
+        let target = target_id.as_deref().map(targets::parse_target_id).transpose()?;
+        let session = polled.ok_or(Error::NotStarted)?;
+        match &*session.stream.lock() {
+            Some(SessionStream::Xcap(poller)) => poller.switch(target)?,
+            _ => return Err(Error::NotStarted),
+        }
+        *session.target_id.lock() = target_id.clone();
+        emit_switched(session_id, target_id, session.frame_count.load(Ordering::SeqCst));
+        return Ok(());
+    }
+
+    let content = SCShareableContent::get()
+        .map_err(|e| Error::Platform(format!("Failed to get shareable content: {:?}", e)))?;
+    let (filter, source_frame) = content_filter(&content, target_id.as_deref())?;
+    let update = |stream: &SCStream, config: &CaptureConfig, with_audio: bool| -> Result<FrameGeometry> {
+        let (out_width, out_height) =
+            capture_pixel_dimensions(&filter, source_frame.width, source_frame.height, config.max_width());
+        stream
+            .update_configuration(&stream_configuration(config, out_width, out_height, with_audio))
+            .and_then(|_| stream.update_content_filter(&filter))
+            .map_err(|e| Error::Platform(format!("Failed to switch stream target: {}", e)))?;
+        Ok(source_geometry(&filter, &source_frame, out_width, out_height))
+    };
+
+    if session_id == sessions::DEFAULT_SESSION {
+        let state = get_capture_state();
+        if !state.wants_video.load(Ordering::SeqCst) {
+            return Err(Error::NotStarted);
+        }
+        let new_geometry =
+            update(state.active_stream.lock().as_ref().ok_or(Error::NotStarted)?, &default_config(&state), true)?;
+        *state.selected_target.lock() = target_id.clone();
+        geometry::set_current(Some(new_geometry));
+        emit_switched(session_id, target_id, state.frame_count.load(Ordering::SeqCst));
+    } else {
+        let session = video_sessions().lock().get(session_id).cloned().ok_or(Error::NotStarted)?;
+        let new_geometry = match &*session.stream.lock() {
+            Some(SessionStream::ScreenCaptureKit(stream)) => update(stream, &session.config, false)?,
+            _ => return Err(Error::NotStarted),
+        };
+        *session.target_id.lock() = target_id.clone();
+        geometry::set_for(session_id, Some(new_geometry));
+        emit_switched(session_id, target_id, session.frame_count.load(Ordering::SeqCst));
+    }
+    Ok(())
+}
+
+/// ScreenCaptureKit stops a stream with an error when its window closes or its display
+/// goes away. The stream can't be revived from its delegate, so the session always ends
+/// here whatever `target_lost_policy` says.
+fn report_stream_lost(session_id: &str, target_id: Option<String>) {
+    let target = target_id.as_deref().and_then(|id| targets::parse_target_id(id).ok());
+    lifecycle::notify(lifecycle::lost_kind(target.as_ref()), session_id, target_id, TargetLostPolicy::Stop);
+}
+
+fn emit_switched(session_id: &str, target_id: Option<String>, frame_count: u64) {
+    events::emit(events::TARGET_SWITCHED, events::TargetSwitched {
+        session_id: session_id.to_string(),
+        target_id,
+        frame_count,
+    });
+}
+
+/// Stop one capture session; other sessions keep running
+pub fn stop_capture_session(session_id: &str) -> Result<()> {
+    follow::stop(session_id);
+    backpressure::unregister(session_id);
+    if session_id == sessions::DEFAULT_SESSION {
+        return stop_default_video();
+    }
+    match video_sessions().lock().remove(session_id) {
+        Some(session) => {
+            session.stop();
+            log::info!("[ScreenCapture] Capture session {} stopped", session_id);
+        }
+        None => log::info!("[ScreenCapture] Capture session {} not active", session_id),
+    }
+    Ok(())
+}
+
+/// Stop every capture session (audio is left alone)
+pub fn stop_all_sessions() -> Result<()> {
+    let stopped: Vec<Arc<VideoSession>> = video_sessions().lock().drain().map(|(_, s)| s).collect();
+    for session in &stopped {
+        session.stop();
+    }
+    backpressure::unregister_all();
+    stop_default_video()
+}
+
+/// All running capture sessions
+pub fn list_capture_sessions() -> Vec<SessionInfo> {
+    let state = get_capture_state();
+    let mut list: Vec<SessionInfo> = video_sessions().lock().values().map(|s| s.info()).collect();
+    if state.wants_video.load(Ordering::SeqCst) {
+        list.push(SessionInfo {
+            id: sessions::DEFAULT_SESSION.to_string(),
+            target_id: state.selected_target.lock().clone(),
+            is_active: state.is_active.load(Ordering::SeqCst),
+            frame_count: state.frame_count.load(Ordering::SeqCst),
+            following: follow::is_following(sessions::DEFAULT_SESSION),
+        });
+    }
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
+
 /// Ensure the unified capture is running
 fn ensure_capture_running(
     state: &Arc<UnifiedCaptureState>,
@@ -353,70 +795,16 @@ fn ensure_capture_running(
         content.windows().len()
     );
 
-    let displays = content.displays();
-    let windows = content.windows();
-
-    // Build the content filter and remember the source's size in POINTS (used only as a
-    // fallback if the native pixel size isn't available). The actual buffer is sized in
-    // pixels below — SCStreamConfiguration is pixel-based, and sizing it from points
-    // captures Retina sources at half resolution (the root cause of soft/pixelated frames).
-    let (filter, frame_w_pts, frame_h_pts) = if let Some(id) = &target_id {
-        if let Ok((kind, numeric_id)) = targets::parse_target_id(id) {
-            match kind {
-                TargetKind::Monitor => {
-                    let display = displays
-                        .iter()
-                        .find(|d| d.display_id() == numeric_id)
-                        .ok_or_else(|| Error::Platform(format!("Display {} not found", numeric_id)))?;
-
-                    let frame = display.frame();
-                    log::info!(
-                        "[ScreenCapture] Capturing display: {} ({}x{} pts)",
-                        display.display_id(),
-                        frame.width,
-                        frame.height
-                    );
-
-                    (SCContentFilter::create().with_display(display).build(), frame.width, frame.height)
-                }
-                TargetKind::Window => {
-                    let window = windows
-                        .iter()
-                        .find(|w| w.window_id() == numeric_id)
-                        .ok_or_else(|| Error::Platform(format!("Window {} not found", numeric_id)))?;
-
-                    let frame = window.frame();
-                    log::info!(
-                        "[ScreenCapture] Capturing window: {:?} ({}x{} pts)",
-                        window.title(),
-                        frame.width,
-                        frame.height
-                    );
-
-                    (SCContentFilter::create().with_window(window).build(), frame.width, frame.height)
-                }
-            }
-        } else {
-            return Err(Error::Platform("Invalid target ID format".to_string()));
-        }
-    } else {
-        let display = displays
-            .first()
-            .ok_or_else(|| Error::Platform("No displays found".to_string()))?;
-
-        let frame = display.frame();
-        log::info!(
-            "[ScreenCapture] Capturing primary display: {} ({}x{} pts)",
-            display.display_id(),
-            frame.width,
-            frame.height
-        );
-
-        (SCContentFilter::create().with_display(display).build(), frame.width, frame.height)
-    };
+    let (filter, source_frame) = content_filter(&content, target_id.as_deref())?;
 
     // Size the capture buffer to the source's NATIVE PIXEL resolution (capped at MAX_WIDTH).
-    let (out_width, out_height) = capture_pixel_dimensions(&filter, frame_w_pts, frame_h_pts);
+    let capture_config = default_config(state);
+    let (out_width, out_height) =
+        capture_pixel_dimensions(&filter, source_frame.width, source_frame.height, capture_config.max_width());
+
+    // The source rect is read once here; a captured window that moves afterwards keeps
+    // its start position until capture restarts.
+    geometry::set_current(Some(source_geometry(&filter, &source_frame, out_width, out_height)));
 
     log::info!(
         "[ScreenCapture] Output buffer sized to {}x{} (aspect-matched, no letterbox)",
@@ -425,17 +813,7 @@ fn ensure_capture_running(
     );
 
     // Configure stream for BOTH video and audio
-    let frame_interval = CMTime::new(1, capture_config::target_fps() as i32);
-    let config = SCStreamConfiguration::new()
-        .with_width(out_width)
-        .with_height(out_height)
-        .with_minimum_frame_interval(&frame_interval)
-        .with_pixel_format(PixelFormat::BGRA)
-        .with_shows_cursor(true)
-        .with_captures_audio(true)
-        .with_excludes_current_process_audio(false)
-        .with_sample_rate(AUDIO_SAMPLE_RATE as i32)
-        .with_channel_count(1); // Mono
+    let config = stream_configuration(&capture_config, out_width, out_height, true);
 
     // Create delegate to receive stream lifecycle events and errors
     let state_for_delegate = state.clone();
@@ -443,6 +821,8 @@ fn ensure_capture_running(
         .on_stop(move |error| {
             if let Some(e) = &error {
                 log::error!("[ScreenCapture] SCStream stopped with error: {}", e);
+                let target_id = state_for_delegate.selected_target.lock().clone();
+                report_stream_lost(sessions::DEFAULT_SESSION, target_id);
             } else {
                 log::info!("[ScreenCapture] SCStream stopped normally");
             }
@@ -500,6 +880,13 @@ fn ensure_capture_running(
                 return;
             }
 
+            // Drop frames while something (e.g. screen sharing) holds the pause gate, and
+            // whenever a password field has focus
+            if pause::is_paused() || secure_input::should_skip_frame() {
+                let _ = guard.as_slice().first();
+                return;
+            }
+
             // Get the channel (if available)
             let channel_guard = state_for_video.video_channel.read();
             let channel = match channel_guard.as_ref() {
@@ -518,9 +905,30 @@ fn ensure_capture_running(
             // let the SIMD JPEG encoder read the BGRA bytes directly. Keeping this handler
             // cheap is what stops frames backing up on SCK's dispatch queue (the cause of
             // the growing capture-to-screen latency).
-            if let Some(frame_data) =
-                encode_bgra_frame(data, width as u32, height as u32, bytes_per_row, &state_for_video)
-            {
+            let Some(quality) = channel.admit() else {
+                return;
+            };
+            if let Some(frame_data) = encode_bgra_frame(
+                data,
+                width as u32,
+                height as u32,
+                bytes_per_row,
+                &state_for_video.frame_count,
+                sessions::DEFAULT_SESSION,
+                quality,
+                channel,
+            ) {
+                // Tile and YUV frames were published whole while encoding
+                if frame_data.is_whole() {
+                    frames::publish(|| frames::Frame {
+                        data: frame_data.frame.clone(),
+                        format: frame_data.format,
+                        timestamp: frame_data.timestamp,
+                        width: frame_data.width,
+                        height: frame_data.height,
+                        frame_count: frame_data.frame_count,
+                    });
+                }
                 if let Err(e) = channel.send(frame_data) {
                     log::error!("[ScreenCapture] Failed to send video frame: {:?}", e);
                 }
@@ -694,6 +1102,144 @@ fn maybe_stop_capture(state: &Arc<UnifiedCaptureState>) {
     log::info!("[ScreenCapture] Unified capture stopped");
 }
 
+/// Capture settings of the default session's video channel (the defaults while the
+/// unified stream only carries audio)
+fn default_config(state: &UnifiedCaptureState) -> CaptureConfig {
+    state.video_channel.read().as_ref().map(|sink| *sink.config()).unwrap_or_default()
+}
+
+/// Stream settings for `width`x`height` BGRA frames at `config`'s FPS; the unified
+/// stream also captures audio
+fn stream_configuration(config: &CaptureConfig, width: u32, height: u32, with_audio: bool) -> SCStreamConfiguration {
+    let frame_interval = CMTime::new(1, config.target_fps() as i32);
+    let stream = SCStreamConfiguration::new()
+        .with_width(width)
+        .with_height(height)
+        .with_minimum_frame_interval(&frame_interval)
+        .with_pixel_format(PixelFormat::BGRA)
+        .with_shows_cursor(config.show_cursor());
+    if !with_audio {
+        return stream;
+    }
+    stream
+        .with_captures_audio(true)
+        .with_excludes_current_process_audio(false)
+        .with_sample_rate(AUDIO_SAMPLE_RATE as i32)
+        .with_channel_count(1) // Mono
+}
+
+/// Build the content filter for a capture target (None = primary display) and return the
+/// source's rect in POINTS alongside it.
+fn content_filter(
+    content: &SCShareableContent,
+    target_id: Option<&str>,
+) -> Result<(SCContentFilter, CGRect)> {
+    let displays = content.displays();
+    let windows = content.windows();
+
+    // Build the content filter and remember the source's rect in POINTS (its size is used
+    // only as a fallback if the native pixel size isn't available). The actual buffer is sized in
+    // pixels by the caller — SCStreamConfiguration is pixel-based, and sizing it from points
+    // captures Retina sources at half resolution (the root cause of soft/pixelated frames).
+    let (filter, source_frame) = if let Some(id) = target_id {
+        if let Ok((kind, numeric_id)) = targets::parse_target_id(id) {
+            match kind {
+                TargetKind::Monitor => {
+                    let display = displays
+                        .iter()
+                        .find(|d| d.display_id() == numeric_id)
+                        .ok_or_else(|| Error::Platform(format!("Display {} not found", numeric_id)))?;
+
+                    let frame = display.frame();
+                    log::info!(
+                        "[ScreenCapture] Capturing display: {} ({}x{} pts)",
+                        display.display_id(),
+                        frame.width,
+                        frame.height
+                    );
+
+                    (display_filter(display, &windows), frame)
+                }
+                TargetKind::Window => {
+                    let window = windows
+                        .iter()
+                        .find(|w| w.window_id() == numeric_id)
+                        .ok_or_else(|| Error::Platform(format!("Window {} not found", numeric_id)))?;
+
+                    let frame = window.frame();
+                    log::info!(
+                        "[ScreenCapture] Capturing window: {:?} ({}x{} pts)",
+                        window.title(),
+                        frame.width,
+                        frame.height
+                    );
+
+                    (SCContentFilter::create().with_window(window).build(), frame)
+                }
+                TargetKind::VirtualDesktop => {
+                    return Err(Error::Platform(
+                        "A stream can't switch to the virtual desktop; start a session on it instead".to_string(),
+                    ));
+                }
+            }
+        } else {
+            return Err(Error::Platform("Invalid target ID format".to_string()));
+        }
+    } else {
+        let display = displays
+            .first()
+            .ok_or_else(|| Error::Platform("No displays found".to_string()))?;
+
+        let frame = display.frame();
+        log::info!(
+            "[ScreenCapture] Capturing primary display: {} ({}x{} pts)",
+            display.display_id(),
+            frame.width,
+            frame.height
+        );
+
+        (display_filter(display, &windows), frame)
+    };
+
+    Ok((filter, source_frame))
+}
+
+/// Filter for a whole display, minus the windows in `exclusions` and our own windows
+fn display_filter(display: &SCDisplay, windows: &[SCWindow]) -> SCContentFilter {
+    let excluded_ids = exclusions::window_ids();
+    let excluded: Vec<&SCWindow> = windows
+        .iter()
+        .filter(|w| {
+            excluded_ids.contains(&w.window_id())
+                || w.owning_application()
+                    .is_some_and(|app| exclusions::is_own_window(app.process_id() as u32))
+        })
+        .collect();
+    if !excluded.is_empty() {
+        log::info!("[ScreenCapture] Excluding {} window(s) from display capture", excluded.len());
+    }
+    SCContentFilter::create()
+        .with_display(display)
+        .with_excluding_windows(&excluded)
+        .build()
+}
+
+/// Geometry of a stream built from `filter`, delivering `width`x`height` frames
+fn source_geometry(filter: &SCContentFilter, frame: &CGRect, width: u32, height: u32) -> FrameGeometry {
+    FrameGeometry {
+        screen_x: frame.x,
+        screen_y: frame.y,
+        screen_width: frame.width,
+        screen_height: frame.height,
+        scale_factor: SCShareableContentInfo::for_filter(filter)
+            .map(|info| info.point_pixel_scale() as f64)
+            .unwrap_or(1.0),
+        crop: None,
+        frame_width: width,
+        frame_height: height,
+    }
+}
+
 /// Determine the capture buffer size in PIXELS for a given content filter.
 ///
 /// `SCStreamConfiguration` sizes are in pixels, but `SCDisplay`/`SCWindow` frames are
@@ -704,7 +1250,12 @@ fn maybe_stop_capture(state: &Arc<UnifiedCaptureState>) {
 /// resolution. On older macOS the info isn't available (`for_filter` returns `None` via
 /// the bridge's `@available` guard), so we fall back to the point dimensions — no
 /// regression versus the previous behavior.
-fn capture_pixel_dimensions(filter: &SCContentFilter, frame_w_pts: f64, frame_h_pts: f64) -> (u32, u32) {
+fn capture_pixel_dimensions(
+    filter: &SCContentFilter,
+    frame_w_pts: f64,
+    frame_h_pts: f64,
+    max_width: u32,
+) -> (u32, u32) {
     if let Some(info) = SCShareableContentInfo::for_filter(filter) {
         let (px_w, px_h) = info.pixel_size();
         if px_w > 0 && px_h > 0 {
@@ -714,24 +1265,24 @@ fn capture_pixel_dimensions(filter: &SCContentFilter, frame_w_pts: f64, frame_h_
                 px_h,
                 info.point_pixel_scale()
             );
-            return output_dimensions(px_w as f64, px_h as f64);
+            return output_dimensions(px_w as f64, px_h as f64, max_width);
         }
     }
 
     log::info!("[ScreenCapture] Native pixel size unavailable (macOS <14); using point dimensions");
-    output_dimensions(frame_w_pts, frame_h_pts)
+    output_dimensions(frame_w_pts, frame_h_pts, max_width)
 }
 
 /// Pick an output buffer size that preserves the source's aspect ratio while capping the
 /// width at the configured max width. Matching the source aspect ratio is what keeps
 /// ScreenCaptureKit from padding frames with black bars. Dimensions are rounded to
 /// even numbers to stay friendly to the capture pipeline.
-fn output_dimensions(src_width: f64, src_height: f64) -> (u32, u32) {
+fn output_dimensions(src_width: f64, src_height: f64, max_width: u32) -> (u32, u32) {
     if !(src_width > 0.0) || !(src_height > 0.0) {
         return (1280, 720); // sensible 16:9 fallback if the source size is unknown
     }
 
-    let max_width = capture_config::max_width() as f64;
+    let max_width = f64::from(max_width);
     let (w, h) = if src_width > max_width {
         let scale = max_width / src_width;
         (max_width, src_height * scale)
@@ -748,57 +1299,145 @@ fn output_dimensions(src_width: f64, src_height: f64) -> (u32, u32) {
     (to_even(w), to_even(h))
 }
 
-/// Encode a captured BGRA frame straight to JPEG.
+/// Encode a captured BGRA frame in the configured format.
 ///
 /// ScreenCaptureKit delivers frames already scaled to our configured output size,
-/// so there is no downscale step on macOS. We feed the BGRA bytes (respecting the
-/// buffer's row stride) directly into the SIMD JPEG encoder — no intermediate
+/// so there is no downscale step on macOS. For JPEG we feed the BGRA bytes (respecting
+/// the buffer's row stride) directly into the SIMD JPEG encoder — no intermediate
 /// RGBA/RGB copies — which is what keeps the 10fps capture loop from backing up.
+/// PNG/WebP take one BGRA→RGB pass first. The encode time goes to `sink`'s stats; a
+/// crop on `sink` is applied by reading a window of the buffer; privacy regions, the
+/// overlay and redaction placeholders are drawn on a packed copy.
 fn encode_bgra_frame(
     bgra: &[u8],
     width: u32,
     height: u32,
     bytes_per_row: usize,
-    state: &Arc<UnifiedCaptureState>,
+    frame_count: &AtomicU64,
+    session_id: &str,
+    quality: u8,
+    sink: &FrameSink,
 ) -> Option<FrameData> {
+    let full_width = width;
+    let region = crop_region(session_id, sink, width, height);
+    let origin = region.map(|(x, y, _, _)| (x, y));
+    let (bgra, width, height) = match region {
+        Some((x, y, crop_width, crop_height)) => {
+            let offset = y as usize * bytes_per_row + x as usize * 4;
+            (bgra.get(offset..)?, crop_width, crop_height)
+        }
+        None => (bgra, width, height),
+    };
+    let source = source_metrics(session_id);
     let w = width as usize;
     let h = height as usize;
-    let row_bytes = w * 4;
 
-    if bgra.len() < bytes_per_row * h {
+    // Rows are `bytes_per_row` apart; a cropped window's last row ends before the buffer
+    if bgra.len() < bytes_per_row * h.saturating_sub(1) + w * 4 {
         log::error!("[ScreenCapture] BGRA buffer smaller than expected, skipping frame");
         return None;
     }
 
-    // jpeg-encoder wants tightly packed rows. CVPixelBuffer rows are frequently
-    // padded for alignment, so compact only when there's real padding — unpadded
-    // buffers are encoded in place with zero copies.
-    let packed: std::borrow::Cow<[u8]> = if bytes_per_row == row_bytes {
-        std::borrow::Cow::Borrowed(&bgra[..row_bytes * h])
-    } else {
-        let mut v = Vec::with_capacity(row_bytes * h);
-        for y in 0..h {
-            let start = y * bytes_per_row;
-            v.extend_from_slice(&bgra[start..start + row_bytes]);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    // Privacy regions are in source pixels, shifted by the crop's offset in the buffer
+    let scale = buffer_scale(session_id, full_width).unwrap_or(1.0);
+    let origin = origin.map_or((0.0, 0.0), |(x, y)| (f64::from(x) / scale, f64::from(y) / scale));
+    let target_id = session_target_id(session_id);
+    let redacted = redaction::should_redact(target_id.as_deref());
+    let hidden = if redacted { Vec::new() } else { privacy::for_frame(target_id.as_deref(), origin) };
+    let overlay = overlay::for_session(session_id);
+    // The sample buffer is read-only: redact and draw the overlay on a packed copy
+    let overlaid = (redacted || !hidden.is_empty() || overlay.is_some()).then(|| {
+        let mut packed: Vec<u8> = if redacted {
+            let mut placeholder = vec![0; w * h * 4];
+            redaction::placeholder(&mut placeholder, width, height, w * 4);
+            placeholder
+        } else {
+            (0..h).flat_map(|row| &bgra[row * bytes_per_row..row * bytes_per_row + w * 4]).copied().collect()
+        };
+        privacy::apply(&hidden, &mut packed, width, height, w * 4, scale);
+        if let Some(overlay) = overlay {
+            overlay.draw(&mut packed, width, height, w * 4, timestamp);
         }
-        std::borrow::Cow::Owned(v)
+        packed
+    });
+    let (bgra, bytes_per_row) = match &overlaid {
+        Some(packed) => (packed.as_slice(), w * 4),
+        None => (bgra, bytes_per_row),
     };
 
-    let mut jpeg_bytes = Vec::new();
-    let encoder = Encoder::new(&mut jpeg_bytes, capture_config::jpeg_quality());
-    if let Err(e) = encoder.encode(&packed, width as u16, height as u16, ColorType::Bgra) {
-        log::error!("[ScreenCapture] Failed to encode JPEG: {:?}", e);
-        return None;
+    let config = sink.config();
+    let format = config.encoding();
+    let grayscale = config.grayscale();
+    let change = sink.change(similarity::dhash(bgra, width, height, bytes_per_row, PixelOrder::Bgra));
+    let stats = frame_stats::observe(
+        session_id,
+        config.frame_stats(),
+        bgra,
+        width,
+        height,
+        bytes_per_row,
+        PixelOrder::Bgra,
+        timestamp,
+    );
+    let encode_start = Instant::now();
+    let encode_full = || {
+        if grayscale {
+            let luma = encode::bgra_to_luma(bgra, w, h, bytes_per_row);
+            encode::encode_luma(&luma, width, height, format, quality)
+        } else if format == FrameEncoding::Jpeg {
+            encode_bgra_jpeg(bgra, width, height, bytes_per_row, quality)
+        } else {
+            let rgb = encode::bgra_to_rgb(bgra, w, h, bytes_per_row);
+            encode::encode_rgb(&rgb, width, height, format, quality)
+        }
+    };
+    // Planes come straight from the BGRA buffer, no RGB pass
+    let (encoded, delta, yuv) = if let Some(yuv) = sink.yuv() {
+        let (planes, yuv) = yuv.convert_frame(bgra, width, height, bytes_per_row, PixelOrder::Bgra)?;
+        (planes, None, Some(yuv))
+    } else if let Some(encoder) = sink.delta() {
+        let (bytes, delta) = delta::encode_frame(
+            encoder,
+            bgra,
+            width,
+            height,
+            bytes_per_row,
+            PixelOrder::Bgra,
+            format,
+            grayscale,
+            quality,
+            &encode_full,
+        )?;
+        (bytes, Some(delta), None)
+    } else {
+        (encode_full()?, None, None)
+    };
+    sink.record(Stage::Encode, encode_start.elapsed());
+
+    let current_frame = frame_count.fetch_add(1, Ordering::SeqCst);
+    // The frame tap gets whole frames
+    if (delta.as_ref().is_some_and(|d| !d.key) || yuv.is_some()) && session_id == sessions::DEFAULT_SESSION {
+        frames::publish(|| frames::Frame {
+            data: encode_full().unwrap_or_default(),
+            format,
+            timestamp,
+            width,
+            height,
+            frame_count: current_frame,
+        });
     }
 
-    let current_frame = state.frame_count.fetch_add(1, Ordering::SeqCst);
-
     if current_frame == 0 {
         log::info!(
-            "[ScreenCapture] First video frame processed ({}x{}, {} bytes)",
+            "[ScreenCapture] First video frame processed ({}x{}, {:?}, {} bytes)",
             width,
             height,
-            jpeg_bytes.len()
+            format,
+            encoded.len()
         );
     }
 
@@ -806,16 +1445,89 @@ fn encode_bgra_frame(
         log::info!("[ScreenCapture] Video stream alive: {} frames", current_frame);
     }
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs_f64();
-
     Some(FrameData {
-        frame: jpeg_bytes,
+        frame: encoded,
+        format,
         timestamp,
         width,
         height,
         frame_count: current_frame,
+        source,
+        delta,
+        yuv,
+        change,
+        stats,
     })
 }
+
+/// Region of a `width`x`height` SCK buffer that `sink`'s crop covers. SCK buffers are
+/// the source scaled to the output size, so the crop (in source pixels) is scaled to
+/// match; the session's geometry is updated to describe the cropped frames.
+fn crop_region(session_id: &str, sink: &FrameSink, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let geometry = geometry::for_session(session_id)?;
+    let scale = buffer_scale(session_id, width)?;
+    let region = sink.crop().and_then(|crop| crop.region(width, height, scale));
+    let (frame_width, frame_height) = region.map_or((width, height), |r| (r.2, r.3));
+    let updated = FrameGeometry {
+        crop: region.map(|region| CropRect::from_region(region, scale)),
+        frame_width,
+        frame_height,
+        ..geometry
+    };
+    if updated != geometry {
+        geometry::set_for(session_id, Some(updated));
+    }
+    region
+}
+
+/// Buffer pixels per source pixel for a `width`-wide SCK buffer of `session_id`
+fn buffer_scale(session_id: &str, width: u32) -> Option<f64> {
+    let geometry = geometry::for_session(session_id)?;
+    let source_width = geometry.screen_width * geometry.scale_factor;
+    Some(f64::from(width) / source_width.max(1.0))
+}
+
+/// Target a session captures (None = primary monitor)
+fn session_target_id(session_id: &str) -> Option<String> {
+    if session_id == sessions::DEFAULT_SESSION {
+        return get_capture_state().selected_target.lock().clone();
+    }
+    let session = video_sessions().lock().get(session_id).cloned()?;
+    let target_id = session.target_id.lock().clone();
+    target_id
+}
+
+/// DPI metrics of what a session streams, from its recorded geometry
+fn source_metrics(session_id: &str) -> Option<SourceMetrics> {
+    geometry::for_session(session_id).map(|g| SourceMetrics::from_geometry(&g))
+}
+
+/// JPEG-encode a BGRA buffer without converting it first
+fn encode_bgra_jpeg(bgra: &[u8], width: u32, height: u32, bytes_per_row: usize, quality: u8) -> Option<Vec<u8>> {
+    let w = width as usize;
+    let h = height as usize;
+    let row_bytes = w * 4;
+
+    // jpeg-encoder wants tightly packed rows. CVPixelBuffer rows are frequently
+    // padded for alignment, so compact only when there's real padding — unpadded
+    // buffers are encoded in place with zero copies.
+    let packed: std::borrow::Cow<[u8]> = if bytes_per_row == row_bytes {
+        std::borrow::Cow::Borrowed(&bgra[..row_bytes * h])
+    } else {
+        let mut v = Vec::with_capacity(row_bytes * h);
+        for y in 0..h {
+            let start = y * bytes_per_row;
+            v.extend_from_slice(&bgra[start..start + row_bytes]);
+        }
+        std::borrow::Cow::Owned(v)
+    };
+
+    let mut jpeg_bytes = Vec::new();
+    let encoder = Encoder::new(&mut jpeg_bytes, quality);
+    if let Err(e) = encoder.encode(&packed, width as u16, height as u16, ColorType::Bgra) {
+        log::error!("[ScreenCapture] Failed to encode JPEG: {:?}", e);
+        return None;
+    }
+
+    Some(jpeg_bytes)
+}
@@ -0,0 +1,400 @@
+//! xcap polling fallback for macOS releases older than 13.
+//!
+//! ScreenCaptureKit only gained audio capture in macOS 13, and the unified stream in
+//! `desktop` (macos.rs) depends on it. On older systems video sessions fall back to
+//! polling CGWindowList through xcap, the same way Windows/Linux capture; system audio
+//! is unavailable and window exclusions don't apply. The virtual desktop (see `stitch`)
+//! polls here on every release, since a ScreenCaptureKit stream covers one display.
+
+use crate::activity::Pacer;
+use crate::delta::{self, PixelOrder};
+use crate::desktop::FrameData;
+use crate::encode;
+use crate::error::{Error, Result};
+use crate::frame_stats;
+use crate::frames;
+use crate::geometry::{self, CropRect, FrameGeometry, SourceMetrics};
+use crate::overlay;
+use crate::pause;
+use crate::privacy::{self, PrivacyRegion};
+use crate::redaction;
+use crate::recovery::{self, Backoff, ErrorAction};
+use crate::secure_input;
+use crate::sessions;
+use crate::similarity;
+use crate::stitch;
+use crate::stats::Stage;
+use crate::targets::{self, TargetKind};
+use crate::wire::FrameSink;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tokio::sync::watch;
+use xcap::{Monitor, Window};
+
+/// First macOS release whose ScreenCaptureKit can capture audio
+const SCK_MIN_MAJOR: u32 = 13;
+
+/// Whether this macOS release gets the ScreenCaptureKit backend
+pub fn sck_supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        let version = std::process::Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .ok()
+            .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+        // If the version can't be read, assume a current system
+        let major = version
+            .as_deref()
+            .and_then(|v| v.split('.').next())
+            .and_then(|major| major.parse::<u32>().ok())
+            .unwrap_or(SCK_MIN_MAJOR);
+        let supported = major >= SCK_MIN_MAJOR;
+        if !supported {
+            log::warn!(
+                "[ScreenCapture] macOS {} predates ScreenCaptureKit audio capture, using xcap for video",
+                version.unwrap_or_default()
+            );
+        }
+        supported
+    })
+}
+
+/// A running polling thread
+pub struct Poller {
+    stop_signal: watch::Sender<bool>,
+    /// Target and source to switch to before the next frame
+    next_source: Arc<Mutex<Option<(Option<(TargetKind, u32)>, Source)>>>,
+}
+
+impl Poller {
+    pub fn stop(&self) {
+        let _ = self.stop_signal.send(true);
+    }
+
+    /// Capture another target from the next frame on
+    pub fn switch(&self, target: Option<(TargetKind, u32)>) -> Result<()> {
+        let source = find_source(target.clone())?;
+        *self.next_source.lock() = Some((target, source));
+        Ok(())
+    }
+}
+
+enum Source {
+    Monitor(Monitor),
+    Window(Window),
+    /// Every monitor, stitched into one frame
+    VirtualDesktop(Vec<Monitor>),
+}
+
+fn find_source(target: Option<(TargetKind, u32)>) -> Result<Source> {
+    match target {
+        Some((TargetKind::Window, id)) => Window::all()
+            .map_err(|e| Error::Platform(e.to_string()))?
+            .into_iter()
+            .find(|w| w.id().ok() == Some(id))
+            .map(Source::Window)
+            .ok_or_else(|| Error::Platform(format!("Window {} not found", id))),
+        Some((TargetKind::Monitor, id)) => Monitor::all()
+            .map_err(|e| Error::Platform(e.to_string()))?
+            .into_iter()
+            .find(|m| m.id().ok() == Some(id))
+            .map(Source::Monitor)
+            .ok_or_else(|| Error::Platform(format!("Monitor {} not found", id))),
+        Some((TargetKind::VirtualDesktop, _)) => stitch::monitors().map(Source::VirtualDesktop),
+        None => {
+            let monitors = Monitor::all().map_err(|e| Error::Platform(e.to_string()))?;
+            let primary = monitors.iter().position(|m| m.is_primary().unwrap_or(false)).unwrap_or(0);
+            monitors
+                .into_iter()
+                .nth(primary)
+                .map(Source::Monitor)
+                .ok_or_else(|| Error::Platform("No monitors found".to_string()))
+        }
+    }
+}
+
+/// Start polling `target` for a session. `is_active`/`frame_count` are the session's
+/// shared counters; the thread clears `is_active` when it exits.
+pub fn start(
+    session_id: &str,
+    target: Option<(TargetKind, u32)>,
+    on_frame: FrameSink,
+    is_active: Arc<AtomicBool>,
+    frame_count: Arc<AtomicU64>,
+) -> Result<Poller> {
+    // Resolve up front so a missing target fails the call instead of the thread
+    let source = find_source(target.clone())?;
+    let (stop_signal, stop_rx) = watch::channel(false);
+    let next_source = Arc::new(Mutex::new(None));
+    let session_id = session_id.to_string();
+
+    let thread_next = next_source.clone();
+    std::thread::spawn(move || {
+        log::info!("[ScreenCapture] xcap fallback capturing session {}", session_id);
+        run(&session_id, target, source, &thread_next, stop_rx, on_frame, &frame_count);
+        is_active.store(false, Ordering::SeqCst);
+    });
+
+    Ok(Poller { stop_signal, next_source })
+}
+
+fn run(
+    session_id: &str,
+    mut target: Option<(TargetKind, u32)>,
+    mut source: Source,
+    next_source: &Mutex<Option<(Option<(TargetKind, u32)>, Source)>>,
+    stop_rx: watch::Receiver<bool>,
+    on_frame: FrameSink,
+    frame_count: &AtomicU64,
+) {
+    let config = *on_frame.config();
+    let mut pacer = Pacer::new(&on_frame);
+    let mut backoff = Backoff::default();
+    loop {
+        let frame_start = Instant::now();
+        let target_frame_time = pacer.frame_time(frame_start);
+
+        if *stop_rx.borrow() {
+            break;
+        }
+        if let Some((next_target, next)) = next_source.lock().take() {
+            target = next_target;
+            source = next;
+            backoff.success();
+        }
+
+        if !pause::is_paused() && !secure_input::should_skip_frame() {
+            let capture_start = Instant::now();
+            let xcap_error = |e: xcap::XCapError| Error::Platform(e.to_string());
+            let (captured, x, y, width, height) = match &source {
+                Source::Monitor(m) => {
+                    (m.capture_image().map_err(xcap_error), m.x().ok(), m.y().ok(), m.width().ok(), m.height().ok())
+                }
+                Source::Window(w) => {
+                    (w.capture_image().map_err(xcap_error), w.x().ok(), w.y().ok(), w.width().ok(), w.height().ok())
+                }
+                Source::VirtualDesktop(monitors) => {
+                    let bounds = stitch::bounds(monitors.iter().filter_map(stitch::Rect::of));
+                    (
+                        stitch::capture_at(monitors, config.max_width()),
+                        bounds.map(|b| b.x),
+                        bounds.map(|b| b.y),
+                        bounds.map(|b| b.width),
+                        bounds.map(|b| b.height),
+                    )
+                }
+            };
+            on_frame.record(Stage::Capture, capture_start.elapsed());
+            match captured {
+                Ok(image) => {
+                    backoff.success();
+                    pacer.observe(image.as_raw(), image.width(), image.height(), Instant::now());
+                    let region = on_frame.crop().and_then(|crop| crop.region(image.width(), image.height(), 1.0));
+                    let cropped = region.map(|(x, y, w, h)| image::imageops::crop_imm(&image, x, y, w, h).to_image());
+                    let frame_image = cropped.as_ref().unwrap_or(&image);
+                    let target_id = target.as_ref().map(|(kind, id)| targets::format_target_id(kind, *id));
+                    let redacted = redaction::should_redact(target_id.as_deref()).then(|| {
+                        let mut placeholder = RgbaImage::new(frame_image.width(), frame_image.height());
+                        let (width, height) = placeholder.dimensions();
+                        redaction::placeholder(&mut placeholder, width, height, width as usize * 4);
+                        placeholder
+                    });
+                    let origin = region.map_or((0.0, 0.0), |(x, y, _, _)| (f64::from(x), f64::from(y)));
+                    let hidden = match redacted {
+                        Some(_) => Vec::new(),
+                        None => privacy::for_frame(target_id.as_deref(), origin),
+                    };
+                    let frame_image = redacted.as_ref().unwrap_or(frame_image);
+                    let encoded = on_frame
+                        .admit()
+                        .and_then(|quality| encode_frame(session_id, frame_image, frame_count, quality, &hidden, &on_frame));
+                    if let Some(mut frame_data) = encoded {
+                        let screen_width = f64::from(width.unwrap_or(image.width()).max(1));
+                        let frame_geometry = FrameGeometry {
+                            screen_x: f64::from(x.unwrap_or(0)),
+                            screen_y: f64::from(y.unwrap_or(0)),
+                            screen_width,
+                            screen_height: f64::from(height.unwrap_or(image.height()).max(1)),
+                            scale_factor: f64::from(image.width()) / screen_width,
+                            crop: region.map(|region| CropRect::from_region(region, 1.0)),
+                            frame_width: frame_data.width,
+                            frame_height: frame_data.height,
+                        };
+                        frame_data.source = Some(SourceMetrics::from_geometry(&frame_geometry));
+                        geometry::set_for(session_id, Some(frame_geometry));
+                        // Tile and YUV frames were published whole while encoding
+                        if session_id == sessions::DEFAULT_SESSION && frame_data.is_whole() {
+                            frames::publish(|| frames::Frame {
+                                data: frame_data.frame.clone(),
+                                format: frame_data.format,
+                                timestamp: frame_data.timestamp,
+                                width: frame_data.width,
+                                height: frame_data.height,
+                                frame_count: frame_data.frame_count,
+                            });
+                        }
+                        if on_frame.send(frame_data).is_err() {
+                            // Channel closed
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    // Log the first failure of a run, not one per frame
+                    if backoff.failures() == 0 {
+                        log::error!("[ScreenCapture] xcap capture failed: {:?}", e);
+                    }
+                    let retry_in = backoff.failure();
+                    if backoff.exhausted(config.max_capture_failures()) {
+                        let target_id = target.as_ref().map(|(kind, id)| targets::format_target_id(kind, *id));
+                        match recovery::give_up(session_id, target_id, &e, &backoff, config.error_fallback()) {
+                            ErrorAction::Stopped => break,
+                            ErrorAction::FallbackToPrimary => match find_source(None) {
+                                Ok(primary) => {
+                                    target = None;
+                                    source = primary;
+                                    backoff.success();
+                                    continue;
+                                }
+                                Err(e) => {
+                                    log::error!("[ScreenCapture] Fallback to primary monitor failed: {}", e);
+                                    break;
+                                }
+                            },
+                        }
+                    }
+                    if recovery::wait(retry_in.max(target_frame_time), &stop_rx) {
+                        break;
+                    }
+                    continue;
+                }
+            }
+        }
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < target_frame_time {
+            std::thread::sleep(target_frame_time - elapsed);
+        }
+    }
+
+    geometry::set_for(session_id, None);
+    frame_stats::clear(session_id);
+    log::info!("[ScreenCapture] xcap fallback for session {} stopped", session_id);
+}
+
+/// Downscale to the stream's max width and encode in its format
+fn encode_frame(
+    session_id: &str,
+    image: &RgbaImage,
+    frame_count: &AtomicU64,
+    quality: u8,
+    hidden: &[PrivacyRegion],
+    sink: &FrameSink,
+) -> Option<FrameData> {
+    let resize_start = Instant::now();
+    let config = sink.config();
+    let max_width = config.max_width();
+    let mut resized = None;
+    if image.width() > max_width {
+        let height = (u64::from(image.height()) * u64::from(max_width) / u64::from(image.width())) as u32;
+        resized = Some(image::imageops::resize(image, max_width, height.max(1), FilterType::Triangle));
+    }
+    sink.record(Stage::Resize, resize_start.elapsed());
+
+    if !hidden.is_empty() {
+        // Regions are in source pixels; the image was captured at source resolution
+        let scale = f64::from(resized.as_ref().map_or(image.width(), |r| r.width())) / f64::from(image.width());
+        let target = resized.get_or_insert_with(|| image.clone());
+        let (width, height) = target.dimensions();
+        privacy::apply(hidden, target, width, height, width as usize * 4, scale);
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    if let Some(overlay) = overlay::for_session(session_id) {
+        let target = resized.get_or_insert_with(|| image.clone());
+        let (width, height) = target.dimensions();
+        overlay.draw(target, width, height, width as usize * 4, timestamp);
+    }
+    let image = resized.as_ref().unwrap_or(image);
+
+    let format = config.encoding();
+    let grayscale = config.grayscale();
+    let encode_start = Instant::now();
+    let (width, height) = image.dimensions();
+    let stride = width as usize * 4;
+    let hash = similarity::dhash(image.as_raw(), width, height, stride, PixelOrder::Rgba);
+    let change = sink.change(hash);
+    let stats = frame_stats::observe(
+        session_id,
+        config.frame_stats(),
+        image.as_raw(),
+        width,
+        height,
+        stride,
+        PixelOrder::Rgba,
+        timestamp,
+    );
+    let encode_full = || {
+        if grayscale {
+            encode::encode_luma(&encode::rgba_to_luma(image.as_raw()), width, height, format, quality)
+        } else {
+            let rgb: Vec<u8> = image.as_raw().chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+            encode::encode_rgb(&rgb, width, height, format, quality)
+        }
+    };
+    let (encoded, delta, yuv) = if let Some(yuv) = sink.yuv() {
+        let (planes, yuv) = yuv.convert_frame(image.as_raw(), width, height, stride, PixelOrder::Rgba)?;
+        (planes, None, Some(yuv))
+    } else if let Some(encoder) = sink.delta() {
+        let (bytes, delta) = delta::encode_frame(
+            encoder,
+            image.as_raw(),
+            width,
+            height,
+            stride,
+            PixelOrder::Rgba,
+            format,
+            grayscale,
+            quality,
+            &encode_full,
+        )?;
+        (bytes, Some(delta), None)
+    } else {
+        (encode_full()?, None, None)
+    };
+    sink.record(Stage::Encode, encode_start.elapsed());
+
+    let frame_count = frame_count.fetch_add(1, Ordering::SeqCst);
+    let frame_data = FrameData {
+        frame: encoded,
+        format,
+        timestamp,
+        width,
+        height,
+        frame_count,
+        source: None,
+        delta,
+        yuv,
+        change,
+        stats,
+    };
+    // The frame tap gets whole frames
+    if !frame_data.is_whole() && session_id == sessions::DEFAULT_SESSION {
+        frames::publish(|| frames::Frame {
+            data: encode_full().unwrap_or_default(),
+            format,
+            timestamp,
+            width,
+            height,
+            frame_count,
+        });
+    }
+    Some(frame_data)
+}
@@ -1,6 +1,7 @@
 use serde::{de::DeserializeOwned, Deserialize};
 use tauri::{plugin::{PluginApi, PluginHandle}, AppHandle, Runtime};
 use crate::error::Result;
+use crate::permission::PermissionStatus;
 
 #[cfg(target_os = "ios")]
 tauri::ios_plugin_binding!(init_plugin_screen_capture);
@@ -12,6 +13,12 @@ struct AndroidBoolResponse {
     value: Option<bool>,
 }
 
+/// Permission status from either native plugin (returns {status: "..."})
+#[derive(Deserialize)]
+struct PermissionResponse {
+    status: PermissionStatus,
+}
+
 // Initialize the mobile plugin and return a handle
 pub fn init<R: Runtime, C: DeserializeOwned>(
     _app: &AppHandle<R>,
@@ -67,6 +74,19 @@ impl<R: Runtime> ScreenCapture<R> {
             .map_err(Into::into)
     }
 
+    pub fn check_permission(&self) -> Result<PermissionStatus> {
+        log::info!("[ScreenCapture] Calling native checkPermission");
+        let response: PermissionResponse = self.0.run_mobile_plugin("checkPermission", ())?;
+        Ok(response.status)
+    }
+
+    /// Show the system consent dialog (Android); iOS asks in the broadcast picker instead
+    pub fn request_permission(&self) -> Result<PermissionStatus> {
+        log::info!("[ScreenCapture] Calling native requestPermission");
+        let response: PermissionResponse = self.0.run_mobile_plugin("requestPermission", ())?;
+        Ok(response.status)
+    }
+
     /// Get the App Group container path (iOS only)
     pub fn get_app_group_path(&self) -> Result<String> {
         log::info!("[ScreenCapture] Getting App Group path");
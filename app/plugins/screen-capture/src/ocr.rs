@@ -0,0 +1,401 @@
+//! On-device OCR (Tesseract, behind the `ocr` feature).
+//!
+//! Language packs are plain `<lang>.traineddata` files in a tessdata directory the host
+//! app points us at (see [`set_tessdata_dir`]). Callers pass per-agent language hints;
+//! without hints, a first pass runs with one installed pack per script, the dominant
+//! script of the result is detected, and the frame is re-read with every installed pack
+//! for that script so CJK, Cyrillic, Arabic, ... screens come out as usable text.
+//!
+//! Besides encoded images, text can be read straight off a capture target (a
+//! full-resolution `screenshot`, so small text survives) or the next frame of the
+//! default stream. Either way it goes through the capture guards first, so denied apps
+//! and privacy regions never reach OCR.
+
+use crate::error::{Error, Result};
+use crate::frames;
+use crate::screenshot;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tesseract::Tesseract;
+use unicode_script::{Script, UnicodeScript};
+
+static TESSDATA_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+// Initialising Tesseract loads every requested model from disk, so the last engine is
+// kept around and reused while the language set stays the same.
+static ENGINE: Mutex<Option<(String, Tesseract)>> = Mutex::new(None);
+
+/// Tesseract language packs grouped by the script they read. The first entry of each
+/// group is the one used for the detection pass.
+const SCRIPT_LANGUAGES: &[(&str, &[&str])] = &[
+    ("Latin", &["eng", "deu", "fra", "spa", "ita", "por", "nld", "pol", "tur", "vie"]),
+    ("Han", &["chi_sim", "chi_tra", "jpn"]),
+    ("Japanese", &["jpn"]),
+    ("Hangul", &["kor"]),
+    ("Cyrillic", &["rus", "ukr", "bul", "srp"]),
+    ("Arabic", &["ara", "fas", "urd"]),
+    ("Hebrew", &["heb"]),
+    ("Devanagari", &["hin", "mar", "nep"]),
+    ("Greek", &["ell"]),
+    ("Thai", &["tha"]),
+];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrOptions {
+    /// Tesseract language codes to use (e.g. `["jpn", "eng"]`). Empty = detect.
+    #[serde(default)]
+    pub languages: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrLine {
+    pub text: String,
+    /// Mean word confidence, 0-100
+    pub confidence: f32,
+    pub bbox: BoundingBox,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrResult {
+    pub text: String,
+    pub lines: Vec<OcrLine>,
+    /// Language packs the final pass ran with, `+`-joined
+    pub languages: String,
+    /// Dominant script of the recognised text, if any
+    pub script: Option<String>,
+}
+
+/// Text read from a capture target or stream frame. Bounding boxes are in the pixels
+/// of the image that was read: the target's native resolution, or the frame's size.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameText {
+    /// Target read (None = primary monitor or the stream's frame)
+    pub target_id: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    /// Capture time, seconds since the epoch
+    pub timestamp: f64,
+    /// Stream frame read, for `latest_frame`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_count: Option<u64>,
+    #[serde(flatten)]
+    pub result: OcrResult,
+}
+
+/// Point OCR at the directory holding `*.traineddata` files
+pub fn set_tessdata_dir(dir: PathBuf) {
+    log::info!("[ScreenCapture] OCR tessdata directory: {}", dir.display());
+    *TESSDATA_DIR.write().unwrap() = Some(dir);
+    // Cached engine may have been built against the old directory
+    *ENGINE.lock().unwrap() = None;
+}
+
+pub fn tessdata_dir() -> Option<PathBuf> {
+    TESSDATA_DIR.read().unwrap().clone()
+}
+
+/// Language codes with a `.traineddata` file in the tessdata directory
+pub fn installed_languages() -> Vec<String> {
+    let Some(dir) = tessdata_dir() else {
+        return Vec::new();
+    };
+    let mut languages: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .and_then(|name| name.strip_suffix(".traineddata"))
+                        .filter(|lang| *lang != "osd")
+                        .map(String::from)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    languages.sort();
+    languages
+}
+
+/// Recognise text in an RGBA image
+pub fn recognize(image: &RgbaImage, options: &OcrOptions) -> Result<OcrResult> {
+    let installed = installed_languages();
+    if installed.is_empty() {
+        return Err(Error::Ocr("No OCR language packs installed".to_string()));
+    }
+
+    let requested: Vec<String> = options
+        .languages
+        .iter()
+        .filter(|lang| installed.contains(lang))
+        .cloned()
+        .collect();
+    if !requested.is_empty() {
+        let (text, lines) = run_pass(image, &requested.join("+"))?;
+        let script = dominant_script(&text);
+        return Ok(OcrResult {
+            text,
+            lines,
+            languages: requested.join("+"),
+            script,
+        });
+    }
+
+    // Detection pass: one representative pack per installed script
+    let detection_set = detection_languages(&installed);
+    let detection_langs = detection_set.join("+");
+    let (text, lines) = run_pass(image, &detection_langs)?;
+    let script = dominant_script(&text);
+
+    // Re-read with every installed pack for the detected script, if that's a different set
+    let script_langs = script
+        .as_deref()
+        .map(|s| languages_for_script(s, &installed))
+        .unwrap_or_default();
+    if script_langs.is_empty() || script_langs == detection_set {
+        return Ok(OcrResult {
+            text,
+            lines,
+            languages: detection_langs,
+            script,
+        });
+    }
+
+    let final_langs = script_langs.join("+");
+    let (text, lines) = run_pass(image, &final_langs)?;
+    Ok(OcrResult {
+        text,
+        lines,
+        languages: final_langs,
+        script,
+    })
+}
+
+/// Decode a PNG/JPEG and recognise text in it
+pub fn recognize_encoded(bytes: &[u8], options: &OcrOptions) -> Result<OcrResult> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| Error::Ocr(format!("Failed to decode image: {}", e)))?
+        .to_rgba8();
+    recognize(&image, options)
+}
+
+/// Capture `target_id` (None = primary monitor) at full resolution and read it
+pub fn recognize_target(target_id: Option<&str>, options: &OcrOptions) -> Result<FrameText> {
+    let (target_id, image, timestamp) = screenshot::capture_image(target_id)?;
+    let result = recognize(&image, options)?;
+    Ok(FrameText { target_id, width: image.width(), height: image.height(), timestamp, frame_count: None, result })
+}
+
+/// Read the next frame of the default stream
+pub async fn recognize_latest_frame(options: &OcrOptions) -> Result<FrameText> {
+    let frame = frames::next(frames::NEXT_TIMEOUT)
+        .await
+        .ok_or_else(|| Error::Ocr("No frame from the capture stream (is one running?)".to_string()))?;
+    let options = options.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || recognize_encoded(&frame.data, &options).map(|r| (frame, r)))
+        .await
+        .map_err(|e| Error::Ocr(e.to_string()))?;
+    let (frame, result) = result?;
+    Ok(FrameText {
+        target_id: None,
+        width: frame.width,
+        height: frame.height,
+        timestamp: frame.timestamp,
+        frame_count: Some(frame.frame_count),
+        result,
+    })
+}
+
+fn detection_languages(installed: &[String]) -> Vec<String> {
+    let mut set: Vec<String> = Vec::new();
+    for (_, langs) in SCRIPT_LANGUAGES {
+        if let Some(lang) = langs.iter().find(|l| installed.iter().any(|i| i == *l)) {
+            if !set.iter().any(|s| s == lang) {
+                set.push(lang.to_string());
+            }
+        }
+    }
+    if set.is_empty() {
+        // Only packs we don't know the script of; use them all
+        set = installed.to_vec();
+    }
+    set
+}
+
+fn languages_for_script(script: &str, installed: &[String]) -> Vec<String> {
+    SCRIPT_LANGUAGES
+        .iter()
+        .find(|(name, _)| *name == script)
+        .map(|(_, langs)| {
+            langs
+                .iter()
+                .filter(|l| installed.iter().any(|i| i == *l))
+                .map(|l| l.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Most common script among the letters of `text`. Kana anywhere means Japanese.
+fn dominant_script(text: &str) -> Option<String> {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        let name = match c.script() {
+            Script::Latin => "Latin",
+            Script::Han => "Han",
+            Script::Hiragana | Script::Katakana => "Japanese",
+            Script::Hangul => "Hangul",
+            Script::Cyrillic => "Cyrillic",
+            Script::Arabic => "Arabic",
+            Script::Hebrew => "Hebrew",
+            Script::Devanagari => "Devanagari",
+            Script::Greek => "Greek",
+            Script::Thai => "Thai",
+            _ => continue,
+        };
+        *counts.entry(name).or_default() += 1;
+    }
+    if counts.get("Japanese").copied().unwrap_or(0) > 0 {
+        return Some("Japanese".to_string());
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(script, _)| script.to_string())
+}
+
+/// One Tesseract pass. Returns the full text and its lines with bounding boxes.
+fn run_pass(image: &RgbaImage, languages: &str) -> Result<(String, Vec<OcrLine>)> {
+    let dir = tessdata_dir().ok_or_else(|| Error::Ocr("OCR data directory not set".to_string()))?;
+    let dir = dir.to_string_lossy().to_string();
+
+    let mut engine_slot = ENGINE.lock().unwrap();
+    let engine = match engine_slot.take() {
+        Some((langs, engine)) if langs == languages => engine,
+        _ => Tesseract::new(Some(&dir), Some(languages))
+            .map_err(|e| Error::Ocr(format!("Failed to load '{}': {}", languages, e)))?,
+    };
+
+    let width = image.width() as i32;
+    let height = image.height() as i32;
+    let mut engine = engine
+        .set_frame(image.as_raw(), width, height, 4, width * 4)
+        .map_err(|e| Error::Ocr(e.to_string()))?
+        .recognize()
+        .map_err(|e| Error::Ocr(e.to_string()))?;
+
+    let tsv = engine.get_tsv_text(0).map_err(|e| Error::Ocr(e.to_string()));
+    *engine_slot = Some((languages.to_string(), engine));
+
+    let lines = parse_tsv_lines(&tsv?);
+    let text = lines
+        .iter()
+        .map(|l| l.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok((text, lines))
+}
+
+/// Fold Tesseract's word-level TSV rows into lines
+fn parse_tsv_lines(tsv: &str) -> Vec<OcrLine> {
+    // level page block par line word left top width height conf text
+    let mut lines: Vec<((u32, u32, u32), Vec<(BoundingBox, f32, String)>)> = Vec::new();
+    for row in tsv.lines() {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let text = cols[11].trim();
+        if text.is_empty() {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let key = (num(2), num(3), num(4));
+        let word = (
+            BoundingBox {
+                x: num(6),
+                y: num(7),
+                width: num(8),
+                height: num(9),
+            },
+            cols[10].parse::<f32>().unwrap_or(0.0),
+            text.to_string(),
+        );
+        match lines.last_mut() {
+            Some((last_key, words)) if *last_key == key => words.push(word),
+            _ => lines.push((key, vec![word])),
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|(_, words)| {
+            let x = words.iter().map(|(b, _, _)| b.x).min().unwrap_or(0);
+            let y = words.iter().map(|(b, _, _)| b.y).min().unwrap_or(0);
+            let right = words.iter().map(|(b, _, _)| b.x + b.width).max().unwrap_or(0);
+            let bottom = words.iter().map(|(b, _, _)| b.y + b.height).max().unwrap_or(0);
+            let confidence = words.iter().map(|(_, c, _)| c).sum::<f32>() / words.len() as f32;
+            OcrLine {
+                text: words
+                    .iter()
+                    .map(|(_, _, t)| t.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                confidence,
+                bbox: BoundingBox {
+                    x,
+                    y,
+                    width: right - x,
+                    height: bottom - y,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_dominant_script() {
+        assert_eq!(dominant_script("Привет, мир"), Some("Cyrillic".to_string()));
+        assert_eq!(dominant_script("hello world"), Some("Latin".to_string()));
+        // Kanji mixed with kana is Japanese, not Chinese
+        assert_eq!(dominant_script("日本語のテキスト"), Some("Japanese".to_string()));
+        assert_eq!(dominant_script("12345 !!"), None);
+    }
+
+    #[test]
+    fn groups_tsv_words_into_lines() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   5\t1\t1\t1\t1\t1\t10\t20\t30\t10\t90\tHello\n\
+                   5\t1\t1\t1\t1\t2\t45\t18\t40\t12\t80\tworld\n\
+                   5\t1\t1\t1\t2\t1\t10\t40\t25\t10\t70\tNext";
+        let lines = parse_tsv_lines(tsv);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, "Hello world");
+        assert_eq!(lines[0].bbox.x, 10);
+        assert_eq!(lines[0].bbox.y, 18);
+        assert_eq!(lines[0].bbox.width, 75);
+        assert_eq!(lines[0].bbox.height, 12);
+        assert_eq!(lines[1].text, "Next");
+    }
+}
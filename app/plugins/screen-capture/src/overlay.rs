@@ -0,0 +1,287 @@
+//! Burned-in frame overlay: capture time and a label drawn into a corner of each frame.
+//!
+//! Frames saved by agents then say where and when they came from without relying on
+//! file names. The overlay is drawn after downscaling, right before encoding, so the
+//! text has the same size in every frame of a stream and isn't blurred by the resize.
+//! It's sized to the frame height (one font pixel per 360 rows) on a darkened box.
+//!
+//! `set` configures a default for every session and optional per-session overrides
+//! (e.g. each agent labels its own stream). Times are UTC; the built-in 5x7 font has
+//! digits, letters (drawn in upper case) and common punctuation, other characters show
+//! as `?`. Drawing only writes gray levels, so it works on RGBA and BGRA buffers alike.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// Frame rows per font pixel
+const ROWS_PER_PIXEL: u32 = 360;
+
+/// Corner the overlay is drawn in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    #[default]
+    BottomLeft,
+    BottomRight,
+}
+
+/// What to burn into frames
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameOverlay {
+    /// Draw the capture time (UTC)
+    #[serde(default)]
+    pub timestamp: bool,
+    /// Text drawn before the time, e.g. the agent or target name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub corner: Corner,
+}
+
+impl FrameOverlay {
+    fn is_empty(&self) -> bool {
+        !self.timestamp && !self.label.as_deref().is_some_and(|l| !l.trim().is_empty())
+    }
+
+    /// The line of text for a frame captured at `timestamp` (seconds since the epoch)
+    pub fn text(&self, timestamp: f64) -> String {
+        let label = self.label.as_deref().map(str::trim).filter(|l| !l.is_empty());
+        let time = self.timestamp.then(|| format_utc(timestamp as u64));
+        match (label, time) {
+            (Some(label), Some(time)) => format!("{} | {}", label, time),
+            (Some(label), None) => label.to_string(),
+            (None, Some(time)) => time,
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Draw onto a 4-bytes-per-pixel buffer whose rows are `stride` bytes apart
+    pub fn draw(&self, pixels: &mut [u8], width: u32, height: u32, stride: usize, timestamp: f64) {
+        let text = self.text(timestamp);
+        let scale = (height / ROWS_PER_PIXEL).max(1) as usize;
+        let (width, height) = (width as usize, height as usize);
+        let advance = (GLYPH_WIDTH + 1) * scale;
+        let padding = 2 * scale;
+        let margin = 3 * scale;
+
+        let room = width.saturating_sub(2 * (margin + padding)) / advance;
+        let chars: Vec<char> = text.chars().take(room).collect();
+        if chars.is_empty() {
+            return;
+        }
+        let box_width = chars.len() * advance - scale + 2 * padding;
+        let box_height = GLYPH_HEIGHT * scale + 2 * padding;
+        if box_height + 2 * margin > height {
+            return;
+        }
+        let left = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => margin,
+            Corner::TopRight | Corner::BottomRight => width - margin - box_width,
+        };
+        let top = match self.corner {
+            Corner::TopLeft | Corner::TopRight => margin,
+            Corner::BottomLeft | Corner::BottomRight => height - margin - box_height,
+        };
+
+        let pixel = |pixels: &mut [u8], x: usize, y: usize| -> Option<std::ops::Range<usize>> {
+            let start = y * stride + x * 4;
+            (start + 3 <= pixels.len()).then_some(start..start + 3)
+        };
+        // Darken the box so the text reads on any background
+        for y in top..top + box_height {
+            for x in left..left + box_width {
+                if let Some(range) = pixel(pixels, x, y) {
+                    for channel in &mut pixels[range] {
+                        *channel = (u16::from(*channel) * 2 / 5) as u8;
+                    }
+                }
+            }
+        }
+        for (index, c) in chars.into_iter().enumerate() {
+            let rows = glyph(c);
+            let glyph_left = left + padding + index * advance;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let x = glyph_left + column * scale + dx;
+                            let y = top + padding + row * scale + dy;
+                            if let Some(range) = pixel(pixels, x, y) {
+                                pixels[range].fill(255);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Overlays {
+    default: FrameOverlay,
+    sessions: HashMap<String, FrameOverlay>,
+}
+
+fn overlays() -> &'static Mutex<Overlays> {
+    static OVERLAYS: OnceLock<Mutex<Overlays>> = OnceLock::new();
+    OVERLAYS.get_or_init(|| Mutex::new(Overlays::default()))
+}
+
+/// Set the overlay for `session_id`, or the default for sessions without their own when
+/// None. A None overlay turns the default off, or drops the session's override.
+pub fn set(session_id: Option<&str>, overlay: Option<FrameOverlay>) {
+    log::info!("[ScreenCapture] Frame overlay for {}: {:?}", session_id.unwrap_or("all sessions"), overlay);
+    let mut overlays = overlays().lock();
+    match (session_id, overlay) {
+        (Some(id), Some(overlay)) => {
+            overlays.sessions.insert(id.to_string(), overlay);
+        }
+        (Some(id), None) => {
+            overlays.sessions.remove(id);
+        }
+        (None, overlay) => overlays.default = overlay.unwrap_or_default(),
+    }
+}
+
+/// Overlay to draw on `session_id`'s frames, None if there's nothing to draw
+pub fn for_session(session_id: &str) -> Option<FrameOverlay> {
+    let overlays = overlays().lock();
+    let overlay = overlays.sessions.get(session_id).unwrap_or(&overlays.default);
+    (!overlay.is_empty()).then(|| overlay.clone())
+}
+
+/// `YYYY-MM-DD HH:MM:SS UTC` for seconds since the epoch
+fn format_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rest = secs % 86_400;
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
+
+/// 5x7 bitmap of `c`, one row per byte, leftmost pixel in bit 4
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '|' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '[' => [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E],
+        ']' => [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '@' => [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E],
+        '&' => [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_utc_dates() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_utc(1_791_987_825), "2026-10-14 14:23:45 UTC");
+    }
+
+    #[test]
+    fn text_joins_label_and_time() {
+        let overlay = FrameOverlay { timestamp: true, label: Some(" Zoom agent ".to_string()), ..Default::default() };
+        assert_eq!(overlay.text(0.0), "Zoom agent | 1970-01-01 00:00:00 UTC");
+        assert!(FrameOverlay { label: Some("  ".to_string()), ..Default::default() }.is_empty());
+    }
+
+    #[test]
+    fn draws_inside_the_corner_box() {
+        let (width, height) = (200u32, 100u32);
+        let mut pixels = vec![100u8; (width * height * 4) as usize];
+        let overlay = FrameOverlay { label: Some("1".to_string()), corner: Corner::BottomRight, ..Default::default() };
+        overlay.draw(&mut pixels, width, height, width as usize * 4, 0.0);
+
+        let at = |x: u32, y: u32| pixels[((y * width + x) * 4) as usize];
+        // Untouched outside the box, darkened inside it, white where the glyph is
+        assert_eq!(at(10, 10), 100);
+        assert_eq!(at(width - 4, height - 4), 40);
+        let glyph_top = height - 3 - 11 + 2;
+        let glyph_left = width - 3 - 9 + 2;
+        assert_eq!(at(glyph_left + 2, glyph_top), 255);
+        // Alpha is left alone
+        assert_eq!(pixels[(((glyph_top * width) + glyph_left + 2) * 4 + 3) as usize], 100);
+    }
+}
@@ -0,0 +1,53 @@
+//! Capture pause gate.
+//!
+//! Independent subsystems (screen-share detection, incognito mode, idle detection, ...) can ask capture to hold off
+//! without tearing the stream down. Each holds its own bit; frames are dropped while
+//! any bit is set, and streaming resumes on its own once every reason is released.
+//! Setting a reason also empties the replay buffer, so nothing captured before the pause
+//! can be exported during or after it.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static PAUSE_REASONS: AtomicU32 = AtomicU32::new(0);
+
+/// Why capture is being held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// The user is sharing their screen in a meeting
+    ScreenShare,
+    /// Incognito mode is on in the host app
+    Incognito,
+    /// Nobody has used the keyboard or mouse for a while
+    UserIdle,
+}
+
+impl PauseReason {
+    fn bit(self) -> u32 {
+        match self {
+            PauseReason::ScreenShare => 1 << 0,
+            PauseReason::Incognito => 1 << 1,
+            PauseReason::UserIdle => 1 << 2,
+        }
+    }
+}
+
+/// Set or release one pause reason
+pub fn set(reason: PauseReason, paused: bool) {
+    if paused {
+        PAUSE_REASONS.fetch_or(reason.bit(), Ordering::SeqCst);
+        crate::replay::clear();
+    } else {
+        PAUSE_REASONS.fetch_and(!reason.bit(), Ordering::SeqCst);
+    }
+    log::info!("[ScreenCapture] Pause reason {:?} {}", reason, if paused { "set" } else { "released" });
+}
+
+/// Whether frames should currently be dropped
+pub fn is_paused() -> bool {
+    PAUSE_REASONS.load(Ordering::SeqCst) != 0
+}
+
+/// Whether a specific reason is currently holding capture
+pub fn is_set(reason: PauseReason) -> bool {
+    PAUSE_REASONS.load(Ordering::SeqCst) & reason.bit() != 0
+}
@@ -0,0 +1,130 @@
+//! Screen-recording permission, checked and requested before a stream starts.
+//!
+//! Without the permission capture doesn't fail outright on every platform: macOS hands
+//! back black frames (or only the wallpaper) until Screen Recording is granted in System
+//! Settings. The UI checks the status first and walks the user through the grant.
+//!
+//! - macOS: `CGPreflightScreenCaptureAccess` / `CGRequestScreenCaptureAccess`. The system
+//!   prompt appears only the first time; after that, requesting opens the Screen Recording
+//!   pane of System Settings. macOS doesn't tell a refusal apart from a prompt that was
+//!   never shown, so the status stays `Prompt` until this process has requested once.
+//! - Linux (Wayland): the ScreenCast portal dialog is the grant; requesting opens it the
+//!   way `get_capture_targets` does, and an open portal session counts as granted.
+//! - Windows / X11: no permission needed.
+//! - Android: MediaProjection consent, asked through the system dialog and kept until
+//!   capture stops. iOS: ReplayKit asks in the broadcast picker each time a broadcast
+//!   starts, so the status is always `Prompt` (see `mobile`).
+
+use serde::{Deserialize, Serialize};
+
+/// Whether capture may start without asking the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionStatus {
+    Granted,
+    /// Refused; the user has to change it in the OS settings
+    Denied,
+    /// The OS will ask (on request, or when capture starts)
+    Prompt,
+}
+
+/// Current permission status
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn check() -> PermissionStatus {
+    platform::check()
+}
+
+/// Trigger the OS permission flow; returns the status afterwards
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn request() -> PermissionStatus {
+    let status = platform::request();
+    log::info!("[ScreenCapture] Screen recording permission requested: {:?}", status);
+    status
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::PermissionStatus;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const SETTINGS_URL: &str = "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture";
+
+    /// Set once this process has asked; a refusal is only known after that
+    static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    pub fn check() -> PermissionStatus {
+        if unsafe { CGPreflightScreenCaptureAccess() } {
+            PermissionStatus::Granted
+        } else if REQUESTED.load(Ordering::SeqCst) {
+            PermissionStatus::Denied
+        } else {
+            PermissionStatus::Prompt
+        }
+    }
+
+    pub fn request() -> PermissionStatus {
+        if unsafe { CGPreflightScreenCaptureAccess() } {
+            return PermissionStatus::Granted;
+        }
+        let first = !REQUESTED.swap(true, Ordering::SeqCst);
+        // Shows the system prompt the first time the app ever asks, returns right away
+        if unsafe { CGRequestScreenCaptureAccess() } {
+            return PermissionStatus::Granted;
+        }
+        if !first {
+            // The prompt won't show again; send the user to the setting instead
+            if let Err(e) = std::process::Command::new("open").arg(SETTINGS_URL).status() {
+                log::warn!("[ScreenCapture] Failed to open System Settings: {}", e);
+            }
+        }
+        PermissionStatus::Denied
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::PermissionStatus;
+    use crate::desktop_wayland;
+    use crate::error::Error;
+
+    pub fn check() -> PermissionStatus {
+        if !desktop_wayland::is_wayland() || desktop_wayland::has_session() {
+            PermissionStatus::Granted
+        } else {
+            PermissionStatus::Prompt
+        }
+    }
+
+    pub fn request() -> PermissionStatus {
+        if !desktop_wayland::is_wayland() {
+            return PermissionStatus::Granted;
+        }
+        match desktop_wayland::select_targets() {
+            Ok(_) => PermissionStatus::Granted,
+            Err(Error::PermissionDenied) => PermissionStatus::Denied,
+            Err(e) => {
+                log::warn!("[ScreenCapture] Portal selection failed: {}", e);
+                PermissionStatus::Prompt
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "android", target_os = "ios")))]
+mod platform {
+    use super::PermissionStatus;
+
+    pub fn check() -> PermissionStatus {
+        PermissionStatus::Granted
+    }
+
+    pub fn request() -> PermissionStatus {
+        PermissionStatus::Granted
+    }
+}
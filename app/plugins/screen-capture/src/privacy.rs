@@ -0,0 +1,272 @@
+//! Privacy regions: parts of a target that are blurred or blacked out in every frame.
+//!
+//! Regions are rects in the target's source pixels (the same space as a profile's crop),
+//! stored per target id, e.g. a password manager sidebar on `monitor:1` or an email
+//! preview pane in `window:42`. Regions under `ALL_TARGETS` apply to every target.
+//! Sessions that don't name a target capture the primary monitor and get its regions
+//! too; its id is looked up at most every `PRIMARY_REFRESH`.
+//!
+//! They're applied to the downscaled frame right before encoding, so every consumer of
+//! a stream (channel, frame tap, recordings and replay built from it) gets the redacted
+//! image. "Blur" is a coarse mosaic: blocks are a quarter of the region's shorter side,
+//! too large for text to survive. Target previews and thumbnails get them as well.
+//!
+//! Regions are kept in memory and persisted to `privacy_regions.json` next to the
+//! capture profiles, loaded once at plugin setup.
+
+use crate::error::{Error, Result};
+use crate::geometry::CropRect;
+use crate::targets::{self, TargetKind};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const FILE_NAME: &str = "privacy_regions.json";
+
+/// Key of the regions applied to every target
+pub const ALL_TARGETS: &str = "*";
+
+/// Smallest mosaic block, in frame pixels
+const MIN_BLOCK: u32 = 8;
+
+/// How long the primary monitor's id is reused before it is looked up again
+const PRIMARY_REFRESH: Duration = Duration::from_secs(5);
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+static REGIONS: Mutex<BTreeMap<String, Vec<PrivacyRegion>>> = Mutex::new(BTreeMap::new());
+static PRIMARY: Mutex<Option<(Instant, Option<String>)>> = Mutex::new(None);
+
+/// How a region is hidden
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RedactMode {
+    #[default]
+    Blur,
+    Black,
+}
+
+/// A rect hidden in every frame of a target
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacyRegion {
+    /// In the target's source pixels
+    #[serde(flatten)]
+    pub rect: CropRect,
+    #[serde(default)]
+    pub mode: RedactMode,
+}
+
+/// Load the saved regions from `dir`
+pub fn init(dir: PathBuf) {
+    let path = dir.join(FILE_NAME);
+    match load(&path) {
+        Ok(regions) => *REGIONS.lock() = regions,
+        Err(e) => log::warn!("[ScreenCapture] {}", e),
+    }
+    let _ = PATH.set(path);
+}
+
+fn io_error(e: impl std::fmt::Display) -> Error {
+    Error::Platform(format!("Privacy regions: {}", e))
+}
+
+fn load(path: &Path) -> Result<BTreeMap<String, Vec<PrivacyRegion>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(io_error),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+/// Write through a temp file so a crash mid-write can't truncate the saved regions
+fn store(path: &Path, regions: &BTreeMap<String, Vec<PrivacyRegion>>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_vec_pretty(regions).map_err(io_error)?;
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json).map_err(io_error)?;
+    std::fs::rename(&temp, path).map_err(io_error)
+}
+
+/// Every target's regions
+pub fn list() -> BTreeMap<String, Vec<PrivacyRegion>> {
+    REGIONS.lock().clone()
+}
+
+/// Replace `target_id`'s regions (an empty list clears them) and save
+pub fn set(target_id: &str, regions: Vec<PrivacyRegion>) -> Result<()> {
+    let target_id = target_id.trim();
+    if target_id.is_empty() {
+        return Err(Error::Platform("Privacy regions need a target id".to_string()));
+    }
+    if regions.iter().any(|r| r.rect.width <= 0.0 || r.rect.height <= 0.0) {
+        return Err(Error::Platform("Privacy regions must have a positive size".to_string()));
+    }
+    let path = PATH.get().ok_or_else(|| Error::Platform("Privacy region storage not initialized".to_string()))?;
+
+    let mut all = REGIONS.lock();
+    let mut updated = all.clone();
+    if regions.is_empty() {
+        updated.remove(target_id);
+    } else {
+        updated.insert(target_id.to_string(), regions);
+    }
+    store(path, &updated)?;
+    *all = updated;
+    log::info!(
+        "[ScreenCapture] Privacy regions for {}: {}",
+        target_id,
+        all.get(target_id).map_or(0, Vec::len)
+    );
+    Ok(())
+}
+
+/// Regions to hide in frames of `target_id` (None = primary monitor) whose top-left is
+/// at `origin` in source pixels (a crop's offset), shifted into frame coordinates
+pub fn for_frame(target_id: Option<&str>, origin: (f64, f64)) -> Vec<PrivacyRegion> {
+    if REGIONS.lock().is_empty() {
+        return Vec::new();
+    }
+    let primary = match target_id {
+        Some(_) => None,
+        None => primary_monitor_id(),
+    };
+    let all = REGIONS.lock();
+    let targeted = target_id.or(primary.as_deref()).and_then(|id| all.get(id));
+    all.get(ALL_TARGETS)
+        .into_iter()
+        .chain(targeted)
+        .flatten()
+        .map(|region| PrivacyRegion {
+            rect: CropRect { x: region.rect.x - origin.0, y: region.rect.y - origin.1, ..region.rect },
+            mode: region.mode,
+        })
+        .collect()
+}
+
+/// Target id of the monitor sessions without a target capture
+fn primary_monitor_id() -> Option<String> {
+    let mut cached = PRIMARY.lock();
+    if let Some((_, id)) = cached.as_ref().filter(|(at, _)| at.elapsed() < PRIMARY_REFRESH) {
+        return id.clone();
+    }
+    let id = primary_monitor().map(|id| targets::format_target_id(&TargetKind::Monitor, id));
+    *cached = Some((Instant::now(), id.clone()));
+    id
+}
+
+fn primary_monitor() -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    if crate::desktop_wayland::is_wayland() {
+        return crate::desktop_wayland::primary_node();
+    }
+    let monitors = xcap::Monitor::all().ok()?;
+    let primary = monitors.iter().find(|m| m.is_primary().unwrap_or(false)).or(monitors.first())?;
+    primary.id().ok()
+}
+
+/// Hide `regions` in a 4-bytes-per-pixel buffer (RGBA or BGRA) whose rows are `stride`
+/// bytes apart, with `scale` buffer pixels per source pixel. Alpha is left alone.
+pub fn apply(regions: &[PrivacyRegion], pixels: &mut [u8], width: u32, height: u32, stride: usize, scale: f64) {
+    for region in regions {
+        let Some((x, y, w, h)) = region.rect.region(width, height, scale) else {
+            continue;
+        };
+        let block = match region.mode {
+            RedactMode::Black => w.max(h),
+            RedactMode::Blur => (w.min(h) / 4).max(MIN_BLOCK),
+        };
+        for block_y in (y..y + h).step_by(block as usize) {
+            for block_x in (x..x + w).step_by(block as usize) {
+                let block_w = block.min(x + w - block_x);
+                let block_h = block.min(y + h - block_y);
+                let color = match region.mode {
+                    RedactMode::Black => [0, 0, 0],
+                    RedactMode::Blur => average(pixels, stride, (block_x, block_y, block_w, block_h)),
+                };
+                fill(pixels, stride, (block_x, block_y, block_w, block_h), color);
+            }
+        }
+    }
+}
+
+fn rows(stride: usize, (x, y, w, h): (u32, u32, u32, u32)) -> impl Iterator<Item = std::ops::Range<usize>> {
+    (y..y + h).map(move |row| {
+        let start = row as usize * stride + x as usize * 4;
+        start..start + w as usize * 4
+    })
+}
+
+fn average(pixels: &[u8], stride: usize, rect: (u32, u32, u32, u32)) -> [u8; 3] {
+    let mut sums = [0u64; 3];
+    let mut count = 0u64;
+    for range in rows(stride, rect) {
+        let Some(row) = pixels.get(range) else { continue };
+        for pixel in row.chunks_exact(4) {
+            for (sum, &value) in sums.iter_mut().zip(pixel) {
+                *sum += u64::from(value);
+            }
+            count += 1;
+        }
+    }
+    sums.map(|sum| (sum / count.max(1)) as u8)
+}
+
+fn fill(pixels: &mut [u8], stride: usize, rect: (u32, u32, u32, u32), color: [u8; 3]) {
+    for range in rows(stride, rect) {
+        let Some(row) = pixels.get_mut(range) else { continue };
+        for pixel in row.chunks_exact_mut(4) {
+            pixel[..3].copy_from_slice(&color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: f64, y: f64, width: f64, height: f64, mode: RedactMode) -> PrivacyRegion {
+        PrivacyRegion { rect: CropRect { x, y, width, height }, mode }
+    }
+
+    #[test]
+    fn blacks_out_and_mosaics_scaled_regions() {
+        let (width, height) = (64u32, 32u32);
+        // Horizontal gradient, opaque
+        let mut pixels: Vec<u8> = (0..width * height).flat_map(|i| [(i % width * 4) as u8, 0, 0, 255]).collect();
+        let stride = width as usize * 4;
+        let regions = [
+            region(0.0, 0.0, 16.0, 16.0, RedactMode::Black),
+            region(32.0, 0.0, 32.0, 16.0, RedactMode::Blur),
+        ];
+        // Source is twice the frame size
+        apply(&regions, &mut pixels, width, height, stride, 0.5);
+
+        let at = |x: u32, y: u32| &pixels[(y as usize * stride + x as usize * 4)..][..4];
+        assert_eq!(at(0, 0), [0, 0, 0, 255]);
+        assert_eq!(at(7, 7), [0, 0, 0, 255]);
+        assert_eq!(at(8, 0)[0], 32);
+        // One 8px block covers x 16..24: every pixel gets the block's average
+        assert_eq!(at(16, 0), at(23, 7));
+        assert_eq!(at(16, 0)[0], (64 + 92) / 2);
+        assert_eq!(at(16, 8)[0], 64);
+    }
+
+    #[test]
+    fn frame_regions_include_all_targets_and_shift_by_origin() {
+        let mut all = REGIONS.lock();
+        all.insert(ALL_TARGETS.to_string(), vec![region(10.0, 10.0, 5.0, 5.0, RedactMode::Black)]);
+        all.insert("window:1".to_string(), vec![region(100.0, 50.0, 20.0, 20.0, RedactMode::Blur)]);
+        drop(all);
+
+        let regions = for_frame(Some("window:1"), (100.0, 40.0));
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[1].rect, CropRect { x: 0.0, y: 10.0, width: 20.0, height: 20.0 });
+        assert_eq!(for_frame(None, (0.0, 0.0)).len(), 1);
+        REGIONS.lock().clear();
+    }
+}
@@ -0,0 +1,175 @@
+//! Named capture profiles, persisted to disk.
+//!
+//! A profile bundles a target with the settings to stream it with: capture config (fps,
+//! JPEG quality, encoding, ...) and an optional crop in source pixels. Agents and
+//! shortcuts start a stream by profile name instead of repeating the settings.
+//!
+//! Profiles live in `profiles.json` under the plugin's directory in the app data dir
+//! (`init` records it at plugin setup). The file is read on every call, so edits made
+//! while the app runs are picked up. Starting a profile gives its stream the profile's
+//! config and crop the way the start commands' `config` argument does; other streams
+//! keep their own settings.
+
+use crate::capture_config::CaptureConfig;
+use crate::desktop;
+use crate::error::{Error, Result};
+use crate::geometry::CropRect;
+use crate::wire::FrameSink;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const FILE_NAME: &str = "profiles.json";
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+/// Serializes read-modify-write cycles on the file
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A saved target + capture settings combination
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureProfile {
+    pub name: String,
+    /// Target to capture (None = primary monitor); any id a session accepts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    /// Settings applied when the profile starts; unset fields keep their current value
+    #[serde(default)]
+    pub config: CaptureConfig,
+    /// Part of the source to stream, in source pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<CropRect>,
+}
+
+/// Store profiles under `dir`
+pub fn init(dir: PathBuf) {
+    let _ = PATH.set(dir.join(FILE_NAME));
+}
+
+fn path() -> Result<&'static Path> {
+    PATH.get()
+        .map(PathBuf::as_path)
+        .ok_or_else(|| Error::Platform("Profile storage not initialized".to_string()))
+}
+
+fn io_error(e: impl std::fmt::Display) -> Error {
+    Error::Platform(format!("Capture profiles: {}", e))
+}
+
+fn load(path: &Path) -> Result<Vec<CaptureProfile>> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(io_error),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+/// Write through a temp file so a crash mid-write can't truncate the saved profiles
+fn store(path: &Path, profiles: &[CaptureProfile]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_vec_pretty(profiles).map_err(io_error)?;
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json).map_err(io_error)?;
+    std::fs::rename(&temp, path).map_err(io_error)
+}
+
+/// Insert `profile`, replacing the one with the same name; keeps the list sorted by name
+fn upsert(profiles: &mut Vec<CaptureProfile>, profile: CaptureProfile) {
+    profiles.retain(|p| p.name != profile.name);
+    profiles.push(profile);
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+}
+
+/// Saved profiles, sorted by name
+pub fn list() -> Result<Vec<CaptureProfile>> {
+    load(path()?)
+}
+
+pub fn get(name: &str) -> Result<CaptureProfile> {
+    list()?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| Error::Platform(format!("No capture profile named {}", name)))
+}
+
+/// Save `profile`, replacing any profile with the same name
+pub fn save(mut profile: CaptureProfile) -> Result<CaptureProfile> {
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        return Err(Error::Platform("Capture profiles need a name".to_string()));
+    }
+    if profile.crop.is_some_and(|crop| crop.width <= 0.0 || crop.height <= 0.0) {
+        return Err(Error::Platform("Crop must have a positive size".to_string()));
+    }
+
+    let path = path()?;
+    let _guard = WRITE_LOCK.lock();
+    let mut profiles = load(path)?;
+    upsert(&mut profiles, profile.clone());
+    store(path, &profiles)?;
+    log::info!("[ScreenCapture] Saved capture profile {}", profile.name);
+    Ok(profile)
+}
+
+/// Delete the profile called `name`; false if there was none
+pub fn delete(name: &str) -> Result<bool> {
+    let path = path()?;
+    let _guard = WRITE_LOCK.lock();
+    let mut profiles = load(path)?;
+    let before = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == before {
+        return Ok(false);
+    }
+    store(path, &profiles)?;
+    log::info!("[ScreenCapture] Deleted capture profile {}", name);
+    Ok(true)
+}
+
+/// Start `session_id` on profile `name`'s target with its config, cropped
+pub fn start(name: &str, session_id: &str, sink: FrameSink) -> Result<()> {
+    let profile = get(name)?;
+    log::info!("[ScreenCapture] Starting session {} from profile {}", session_id, name);
+    let sink = sink.with_config(Some(profile.config)).with_crop(profile.crop);
+    desktop::start_capture_session(session_id, profile.target_id, sink)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, fps: u32) -> CaptureProfile {
+        CaptureProfile {
+            name: name.to_string(),
+            target_id: Some("monitor:1".to_string()),
+            config: CaptureConfig { fps: Some(fps), ..CaptureConfig::default() },
+            crop: None,
+        }
+    }
+
+    #[test]
+    fn upsert_replaces_by_name_and_sorts() {
+        let mut profiles = vec![profile("zoom", 5), profile("code", 1)];
+        upsert(&mut profiles, profile("browser", 2));
+        upsert(&mut profiles, profile("zoom", 10));
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["browser", "code", "zoom"]);
+        assert_eq!(profiles[2].config.fps, Some(10));
+    }
+
+    #[test]
+    fn round_trips_through_disk() {
+        let dir = std::env::temp_dir().join(format!("observer-profiles-{}", std::process::id()));
+        let path = dir.join(FILE_NAME);
+        assert_eq!(load(&path).unwrap(), Vec::new());
+
+        let mut cropped = profile("editor", 2);
+        cropped.crop = Some(CropRect { x: 0.0, y: 100.0, width: 800.0, height: 600.0 });
+        store(&path, &[cropped.clone()]).unwrap();
+        assert_eq!(load(&path).unwrap(), vec![cropped]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
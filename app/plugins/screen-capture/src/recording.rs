@@ -0,0 +1,588 @@
+//! MP4 (H.264) recording to disk.
+//!
+//! A recording is an ordinary capture session (`RECORDING_SESSION`) whose frame sink is
+//! a Rust-side channel instead of the webview, so it works on every capture backend and
+//! shows up in `list_capture_sessions`. Frames arrive encoded in the capture config's
+//! format; a worker thread encodes them to H.264 (see `h264`) and muxes the result into
+//! an MP4. JPEG frames carry their compression artifacts into the video, so switch the
+//! encoding to PNG for cleaner recordings.
+//!
+//! The video takes the size of the first frame; later frames of a different size are
+//! scaled to fit. Sample durations follow the capture timestamps, so dropped or paused
+//! frames keep real time. One recording runs at a time.
+//!
+//! An existing file at the path is only replaced with `overwrite`, and nothing is
+//! recorded while incognito mode holds capture.
+
+use crate::desktop::{self, FrameData};
+use crate::error::{Error, Result};
+use crate::h264::{self, H264Encoder, NAL_PPS, NAL_SPS};
+use crate::pause::{self, PauseReason};
+use crate::wire::FrameSink;
+use bytes::Bytes;
+use mp4::{
+    AvcConfig, ChannelConfig, MediaConfig, Mp4Config, Mp4Sample, Mp4Writer, OpusConfig, SampleFreqIndex, TrackConfig,
+    TrackType,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri_plugin_audio_capture::capture::{self as audio_capture, SourceKind};
+use tauri_plugin_audio_capture::pipeline::{AudioEncoding, AudioStreamConfig, Pipeline};
+
+/// Session id recordings capture under
+pub const RECORDING_SESSION: &str = "recording";
+
+/// MP4 timescale: sample times are in milliseconds
+const TIMESCALE: u32 = 1000;
+const VIDEO_TRACK: u32 = 1;
+const DEFAULT_BITRATE_KBPS: u32 = 2500;
+
+/// Audio tracks are 48 kHz Opus in 20 ms packets
+const AUDIO_RATE: u32 = 48000;
+const AUDIO_PACKET_SAMPLES: u64 = 960;
+const AUDIO_PACKET_SECS: f64 = 0.02;
+
+/// How far (in packets) a track may drift from its timestamps before it is realigned
+const MAX_DRIFT_PACKETS: i64 = 3;
+
+/// A 20 ms Opus packet of silence (CELT fullband, mono; decoders upmix it), for gaps
+const OPUS_SILENCE: [u8; 3] = [0xf8, 0xff, 0xfe];
+
+/// Samples of encoder delay at the start of every libopus stream, for the track header
+const OPUS_PRE_SKIP: u16 = 312;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingOptions {
+    /// H.264 target bitrate (default 2500 kbps)
+    pub bitrate_kbps: Option<u32>,
+    /// Record what the system plays as a stereo audio track
+    #[serde(default)]
+    pub system_audio: bool,
+    /// Record a microphone as a mono audio track
+    #[serde(default)]
+    pub microphone: bool,
+    /// Input device for `microphone` (None = default)
+    #[serde(default)]
+    pub microphone_device_id: Option<String>,
+    /// Replace a file already at the path instead of refusing
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// A finished recording
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSummary {
+    pub path: String,
+    pub duration_secs: f64,
+    pub frame_count: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Sources of the audio tracks, in track order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub audio_tracks: Vec<SourceKind>,
+}
+
+enum Message {
+    Frame(FrameData),
+    /// Packets of audio track `track`, the first captured at `start` (seconds)
+    Audio { track: usize, start: f64, packets: Vec<Vec<u8>> },
+    Finish,
+}
+
+struct ActiveRecording {
+    tx: mpsc::Sender<Message>,
+    worker: JoinHandle<Result<RecordingSummary>>,
+    audio: AudioReaders,
+}
+
+/// The threads reading audio tracks
+struct AudioReaders {
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl AudioReaders {
+    fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+static RECORDING: Mutex<Option<ActiveRecording>> = Mutex::new(None);
+
+/// Start recording `target_id` (None = primary monitor) to an MP4 at `path`
+pub fn start(target_id: Option<String>, path: PathBuf, options: RecordingOptions) -> Result<()> {
+    if pause::is_set(PauseReason::Incognito) {
+        return Err(Error::Platform("Recording is disabled while incognito mode is on".to_string()));
+    }
+    let mut recording = RECORDING.lock();
+    if recording.is_some() {
+        return Err(Error::Platform("A recording is already running".to_string()));
+    }
+
+    let mut tracks = Vec::new();
+    if options.system_audio {
+        tracks.push((SourceKind::Loopback, None));
+    }
+    if options.microphone {
+        tracks.push((SourceKind::Microphone, options.microphone_device_id.clone()));
+    }
+    let audio_tracks: Vec<SourceKind> = tracks.iter().map(|(kind, _)| *kind).collect();
+
+    let file = match options.overwrite {
+        true => File::create(&path),
+        false => File::create_new(&path),
+    };
+    let file = file.map_err(|e| Error::Platform(format!("Can't create {}: {}", path.display(), e)))?;
+    let (tx, rx) = mpsc::channel();
+    let bitrate_kbps = options.bitrate_kbps.unwrap_or(DEFAULT_BITRATE_KBPS).clamp(100, 50_000);
+    let worker_path = path.clone();
+    let worker_tracks = audio_tracks.clone();
+    let worker = std::thread::spawn(move || {
+        let result = encode_frames(rx, BufWriter::new(file), worker_path.clone(), bitrate_kbps, worker_tracks);
+        if let Err(e) = &result {
+            log::error!("[ScreenCapture] Recording to {} failed: {}", worker_path.display(), e);
+        }
+        result
+    });
+
+    let audio = match start_audio(tracks, &tx) {
+        Ok(audio) => audio,
+        Err(e) => {
+            let _ = tx.send(Message::Finish);
+            let _ = worker.join();
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+    };
+
+    let frame_tx = tx.clone();
+    let channel = Channel::new(move |body| {
+        if let InvokeResponseBody::Raw(payload) = body {
+            if let Some(frame) = FrameData::from_binary(&payload) {
+                // Fails only once the worker has finished; late frames are dropped
+                let _ = frame_tx.send(Message::Frame(frame));
+            }
+        }
+        Ok(())
+    });
+
+    if let Err(e) = desktop::start_capture_session(RECORDING_SESSION, target_id, FrameSink::new(channel, true)) {
+        audio.stop();
+        let _ = tx.send(Message::Finish);
+        let _ = worker.join();
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+
+    log::info!("[ScreenCapture] Recording to {} at {} kbps, audio {:?}", path.display(), bitrate_kbps, audio_tracks);
+    *recording = Some(ActiveRecording { tx, worker, audio });
+    Ok(())
+}
+
+/// Open each audio source on its own thread and start sending its packets to the worker.
+/// Fails if any source fails to open.
+fn start_audio(tracks: Vec<(SourceKind, Option<String>)>, tx: &mpsc::Sender<Message>) -> Result<AudioReaders> {
+    let mut readers = AudioReaders { stop: Arc::new(AtomicBool::new(false)), threads: Vec::new() };
+    for (track, (kind, device_id)) in tracks.into_iter().enumerate() {
+        let (opened, on_open) = mpsc::sync_channel::<std::result::Result<(), String>>(1);
+        let stop = readers.stop.clone();
+        let tx = tx.clone();
+        readers.threads.push(std::thread::spawn(move || {
+            let config = AudioStreamConfig {
+                encoding: AudioEncoding::Opus,
+                sample_rate: AUDIO_RATE,
+                channels: audio_channels(kind),
+                chunk_ms: 100,
+                bitrate: None,
+            };
+            let opening = audio_capture::open_source(kind, device_id.as_deref())
+                .and_then(|(source, _)| Pipeline::new(config, source.format()).map(|pipeline| (source, pipeline)));
+            let (mut source, mut pipeline) = match opening {
+                Ok(opening) => {
+                    let _ = opened.send(Ok(()));
+                    opening
+                }
+                Err(e) => {
+                    let _ = opened.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            read_audio(track, source.as_mut(), &mut pipeline, &tx, &stop);
+        }));
+        let result = on_open
+            .recv_timeout(audio_capture::OPEN_TIMEOUT)
+            .unwrap_or_else(|_| Err("timed out opening the device".to_string()));
+        if let Err(e) = result {
+            readers.stop();
+            return Err(Error::Platform(format!("Can't record {:?} audio: {}", kind, e)));
+        }
+    }
+    Ok(readers)
+}
+
+fn audio_channels(kind: SourceKind) -> u16 {
+    match kind {
+        SourceKind::Loopback => 2,
+        SourceKind::Microphone => 1,
+    }
+}
+
+/// Reader: encode a source to Opus and send the packets with their capture time
+fn read_audio(
+    track: usize,
+    source: &mut dyn audio_capture::AudioSource,
+    pipeline: &mut Pipeline,
+    tx: &mpsc::Sender<Message>,
+    stop: &AtomicBool,
+) {
+    let mut samples = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        samples.clear();
+        if let Err(e) = source.read(&mut samples) {
+            log::error!("[ScreenCapture] Recording audio track {} stopped: {}", track, e);
+            return;
+        }
+        if samples.is_empty() {
+            continue;
+        }
+        let packets = match pipeline.push_packets(&samples) {
+            Ok(packets) if !packets.is_empty() => packets,
+            Ok(_) => continue,
+            Err(e) => {
+                log::error!("[ScreenCapture] Recording audio track {} stopped: {}", track, e);
+                return;
+            }
+        };
+        // The packets end now; the first one started their total duration ago
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+        let start = now - packets.len() as f64 * AUDIO_PACKET_SECS;
+        if tx.send(Message::Audio { track, start, packets }).is_err() {
+            return;
+        }
+    }
+}
+
+/// Stop the running recording, finish the MP4 and return where it went
+pub fn stop() -> Result<RecordingSummary> {
+    let recording = RECORDING.lock().take().ok_or(Error::NotStarted)?;
+    if let Err(e) = desktop::stop_capture_session(RECORDING_SESSION) {
+        log::warn!("[ScreenCapture] Failed to stop recording session: {}", e);
+    }
+    recording.audio.stop();
+    let _ = recording.tx.send(Message::Finish);
+    recording
+        .worker
+        .join()
+        .map_err(|_| Error::Platform("Recording worker panicked".to_string()))?
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.lock().is_some()
+}
+
+fn mp4_error(e: mp4::Error) -> Error {
+    Error::Platform(format!("MP4: {}", e))
+}
+
+/// Worker: feed frames to an `Mp4Encoder` until `Finish` (or the sender is dropped)
+fn encode_frames(
+    rx: mpsc::Receiver<Message>,
+    out: BufWriter<File>,
+    path: PathBuf,
+    bitrate_kbps: u32,
+    audio_tracks: Vec<SourceKind>,
+) -> Result<RecordingSummary> {
+    let mut encoder = Mp4Encoder::new(out, path, bitrate_kbps)?.with_audio(audio_tracks);
+    loop {
+        match rx.recv() {
+            Ok(Message::Frame(frame)) => encoder.push(&frame.frame, frame.timestamp)?,
+            Ok(Message::Audio { track, start, packets }) => encoder.push_audio(track, start, packets)?,
+            Ok(Message::Finish) | Err(_) => break,
+        }
+    }
+    encoder.finish()
+}
+
+/// An Opus track being written
+struct AudioTrack {
+    source: SourceKind,
+    /// Packets written so far; the next one starts at `written * AUDIO_PACKET_SECS`
+    written: u64,
+}
+
+/// Encoded frames in, H.264 MP4 out. Also used to export the replay buffer.
+pub(crate) struct Mp4Encoder {
+    encoder: H264Encoder,
+    path: PathBuf,
+    out: Option<BufWriter<File>>,
+    writer: Option<Mp4Writer<BufWriter<File>>>,
+    /// Samples are written one behind so each knows its duration
+    pending: Option<(u64, Vec<u8>, bool)>,
+    first_timestamp: Option<f64>,
+    last_time_ms: u64,
+    frame_count: u64,
+    audio: Vec<AudioTrack>,
+}
+
+impl Mp4Encoder {
+    pub(crate) fn new(out: BufWriter<File>, path: PathBuf, bitrate_kbps: u32) -> Result<Self> {
+        Ok(Self {
+            encoder: H264Encoder::new(bitrate_kbps)?,
+            path,
+            out: Some(out),
+            writer: None,
+            pending: None,
+            first_timestamp: None,
+            last_time_ms: 0,
+            frame_count: 0,
+            audio: Vec::new(),
+        })
+    }
+
+    /// Add an Opus track per source after the video track
+    pub(crate) fn with_audio(mut self, sources: Vec<SourceKind>) -> Self {
+        self.audio = sources.into_iter().map(|source| AudioTrack { source, written: 0 }).collect();
+        self
+    }
+
+    /// Create `path` and an encoder writing to it
+    pub(crate) fn create(path: PathBuf, bitrate_kbps: u32) -> Result<Self> {
+        let file = File::create(&path).map_err(|e| Error::Platform(format!("Can't create {}: {}", path.display(), e)))?;
+        Self::new(BufWriter::new(file), path, bitrate_kbps)
+    }
+
+    /// Add an encoded image (any format `image` decodes) captured at `timestamp` (seconds)
+    pub(crate) fn push(&mut self, encoded: &[u8], timestamp: f64) -> Result<()> {
+        let Some(unit) = self.encoder.encode(encoded)? else {
+            return Ok(());
+        };
+        let (width, height, annex_b, is_sync) = (unit.width, unit.height, unit.annex_b, unit.keyframe);
+
+        if self.writer.is_none() {
+            let Some((sps, pps)) = h264::parameter_sets(&annex_b) else {
+                return Ok(());
+            };
+            let out = self.out.take().expect("writer starts once");
+            let sources: Vec<SourceKind> = self.audio.iter().map(|track| track.source).collect();
+            self.writer = Some(start_mp4(out, width, height, sps, pps, &sources)?);
+        }
+
+        let start = *self.first_timestamp.get_or_insert(timestamp);
+        let time_ms = (((timestamp - start) * 1000.0).max(0.0) as u64).max(self.last_time_ms);
+        if let (Some(writer), Some((pending_time, bytes, sync))) = (self.writer.as_mut(), self.pending.take()) {
+            write_sample(writer, pending_time, (time_ms - pending_time).max(1) as u32, bytes, sync)?;
+        }
+        self.pending = Some((time_ms, to_avcc(&annex_b), is_sync));
+        self.last_time_ms = time_ms;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    /// Add Opus packets of audio track `track`, the first captured at `start` (seconds).
+    /// Audio before the first video frame is dropped.
+    pub(crate) fn push_audio(&mut self, track: usize, start: f64, packets: Vec<Vec<u8>>) -> Result<()> {
+        let (Some(writer), Some(origin), Some(audio)) =
+            (self.writer.as_mut(), self.first_timestamp, self.audio.get_mut(track))
+        else {
+            return Ok(());
+        };
+        let track_id = VIDEO_TRACK + 1 + track as u32;
+        let expected = audio.written as f64 * AUDIO_PACKET_SECS;
+        let drift = packet_drift(start - origin, expected);
+        let mut packets = packets.into_iter();
+        if drift > MAX_DRIFT_PACKETS {
+            for _ in 0..drift {
+                write_audio_sample(writer, track_id, audio.written, OPUS_SILENCE.to_vec())?;
+                audio.written += 1;
+            }
+        } else if drift < -MAX_DRIFT_PACKETS {
+            packets.by_ref().take(drift.unsigned_abs() as usize).for_each(drop);
+        }
+        for packet in packets {
+            write_audio_sample(writer, track_id, audio.written, packet)?;
+            audio.written += 1;
+        }
+        Ok(())
+    }
+
+    /// Write the last sample and the MP4 index. With no frames the file is removed.
+    pub(crate) fn finish(mut self) -> Result<RecordingSummary> {
+        let frame_ms = 1000 / u64::from(crate::capture_config::target_fps().max(1));
+        let duration_ms = if let (Some(writer), Some((time, bytes, sync))) = (self.writer.as_mut(), self.pending.take()) {
+            write_sample(writer, time, frame_ms as u32, bytes, sync)?;
+            time + frame_ms
+        } else {
+            0
+        };
+        match self.writer {
+            Some(mut writer) => writer.write_end().map_err(mp4_error)?,
+            None => {
+                drop(self.out);
+                let _ = std::fs::remove_file(&self.path);
+                return Err(Error::NoFrame);
+            }
+        }
+
+        let (width, height) = self.encoder.size().unwrap_or_default();
+        log::info!(
+            "[ScreenCapture] MP4 saved to {} ({} frames, {:.1}s)",
+            self.path.display(),
+            self.frame_count,
+            duration_ms as f64 / 1000.0
+        );
+        Ok(RecordingSummary {
+            path: self.path.to_string_lossy().into_owned(),
+            duration_secs: duration_ms as f64 / 1000.0,
+            frame_count: self.frame_count,
+            width,
+            height,
+            audio_tracks: self.audio.iter().map(|track| track.source).collect(),
+        })
+    }
+}
+
+fn start_mp4(
+    out: BufWriter<File>,
+    width: u32,
+    height: u32,
+    sps: Vec<u8>,
+    pps: Vec<u8>,
+    audio: &[SourceKind],
+) -> Result<Mp4Writer<BufWriter<File>>> {
+    let brand = |b: &str| b.parse().expect("valid four-character brand");
+    let config = Mp4Config {
+        major_brand: brand("isom"),
+        minor_version: 512,
+        compatible_brands: vec![brand("isom"), brand("iso2"), brand("avc1"), brand("mp41")],
+        timescale: TIMESCALE,
+    };
+    let mut writer = Mp4Writer::write_start(out, &config).map_err(mp4_error)?;
+    writer
+        .add_track(&TrackConfig {
+            track_type: TrackType::Video,
+            timescale: TIMESCALE,
+            language: "und".to_string(),
+            media_conf: MediaConfig::AvcConfig(AvcConfig {
+                width: width as u16,
+                height: height as u16,
+                seq_param_set: sps,
+                pic_param_set: pps,
+            }),
+        })
+        .map_err(mp4_error)?;
+    for &source in audio {
+        writer
+            .add_track(&TrackConfig {
+                track_type: TrackType::Audio,
+                timescale: AUDIO_RATE,
+                language: "und".to_string(),
+                media_conf: MediaConfig::OpusConfig(OpusConfig {
+                    bitrate: 0,
+                    freq_index: SampleFreqIndex::Freq48000,
+                    chan_conf: if audio_channels(source) == 2 { ChannelConfig::Stereo } else { ChannelConfig::Mono },
+                    pre_skip: OPUS_PRE_SKIP,
+                }),
+            })
+            .map_err(mp4_error)?;
+    }
+    Ok(writer)
+}
+
+/// Packets between where a track's next packet goes (`expected` seconds) and where its
+/// capture time says it belongs (`actual`); positive when the audio is late
+fn packet_drift(actual: f64, expected: f64) -> i64 {
+    ((actual - expected) / AUDIO_PACKET_SECS).round() as i64
+}
+
+/// Write the `index`th 20 ms packet of an audio track
+fn write_audio_sample(
+    writer: &mut Mp4Writer<BufWriter<File>>,
+    track_id: u32,
+    index: u64,
+    bytes: Vec<u8>,
+) -> Result<()> {
+    writer
+        .write_sample(
+            track_id,
+            &Mp4Sample {
+                start_time: index * AUDIO_PACKET_SAMPLES,
+                duration: AUDIO_PACKET_SAMPLES as u32,
+                rendering_offset: 0,
+                is_sync: true,
+                bytes: Bytes::from(bytes),
+            },
+        )
+        .map_err(mp4_error)
+}
+
+fn write_sample(
+    writer: &mut Mp4Writer<BufWriter<File>>,
+    start_time: u64,
+    duration: u32,
+    bytes: Vec<u8>,
+    is_sync: bool,
+) -> Result<()> {
+    writer
+        .write_sample(
+            VIDEO_TRACK,
+            &Mp4Sample {
+                start_time,
+                duration,
+                rendering_offset: 0,
+                is_sync,
+                bytes: Bytes::from(bytes),
+            },
+        )
+        .map_err(mp4_error)
+}
+
+/// Annex-B to MP4's length-prefixed form. Parameter sets live in the track header.
+fn to_avcc(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for unit in h264::nal_units(data) {
+        if matches!(h264::nal_type(unit), NAL_SPS | NAL_PPS) {
+            continue;
+        }
+        out.extend_from_slice(&(unit.len() as u32).to_be_bytes());
+        out.extend_from_slice(unit);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_prefixes_slices() {
+        // SPS, PPS (4-byte start codes) then an IDR slice (3-byte start code)
+        let stream = [
+            0, 0, 0, 1, 0x67, 1, 2, //
+            0, 0, 0, 1, 0x68, 3, //
+            0, 0, 1, 0x65, 4, 5, 6,
+        ];
+        assert_eq!(to_avcc(&stream), vec![0, 0, 0, 4, 0x65, 4, 5, 6]);
+    }
+
+    #[test]
+    fn measures_audio_drift_in_packets() {
+        assert_eq!(packet_drift(0.0, 0.0), 0);
+        // Audio that started 300 ms into the video needs 15 packets of silence first
+        assert_eq!(packet_drift(0.3, 0.0), 15);
+        // Audio captured before the first frame is ahead of where it belongs
+        assert_eq!(packet_drift(-0.1, 0.0), -5);
+        assert_eq!(packet_drift(10.04, 10.0), 2);
+    }
+}
@@ -0,0 +1,178 @@
+//! Backing off and giving up when a capture source keeps failing.
+//!
+//! The polling loops used to retry a failing grab at the full frame rate forever. Now
+//! each run of consecutive failures waits longer between attempts (`BASE_DELAY`,
+//! doubling up to `MAX_DELAY`), and after `capture_config::max_capture_failures` of them
+//! the session gives up: it emits a `CaptureError` event and either stops or, with
+//! `capture_config::error_fallback`, moves to the primary monitor.
+//!
+//! This covers sources that still exist but can't be read (revoked permission, a
+//! protected or minimized window, a driver hiccup). Targets that disappear are handled
+//! by `lifecycle` first, with its own policy.
+
+use crate::error::Error;
+use crate::events;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Wait after the first failure of a run
+pub const BASE_DELAY: Duration = Duration::from_millis(100);
+/// Longest wait between attempts
+pub const MAX_DELAY: Duration = Duration::from_secs(5);
+/// How often a backoff wait checks for a stop
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// Counts a run of failed grabs and how long to wait before the next one
+#[derive(Debug, Default)]
+pub struct Backoff {
+    failures: u32,
+}
+
+impl Backoff {
+    /// A frame was captured
+    pub fn success(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Consecutive failures so far
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// A grab failed; returns how long to wait before trying again
+    pub fn failure(&mut self) -> Duration {
+        self.failures = self.failures.saturating_add(1);
+        let doublings = (self.failures - 1).min(16);
+        BASE_DELAY.saturating_mul(1 << doublings).min(MAX_DELAY)
+    }
+
+    /// Whether the run reached `limit` failures (0 = no limit)
+    pub fn exhausted(&self, limit: u32) -> bool {
+        limit > 0 && self.failures >= limit
+    }
+}
+
+/// Why capture failed, for the frontend to branch on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorReason {
+    /// Screen-recording permission is missing or was revoked (see `permission`)
+    PermissionDenied,
+    TargetLost,
+    /// The capture API isn't usable on this system
+    NotAvailable,
+    /// Anything else the backend reported
+    CaptureFailed,
+}
+
+impl ErrorReason {
+    pub fn of(error: &Error) -> Self {
+        match error {
+            Error::PermissionDenied => ErrorReason::PermissionDenied,
+            Error::TargetLost => ErrorReason::TargetLost,
+            Error::NotAvailable => ErrorReason::NotAvailable,
+            _ => ErrorReason::CaptureFailed,
+        }
+    }
+}
+
+/// What the session did after giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorAction {
+    /// Continues on the primary monitor
+    FallbackToPrimary,
+    /// Ended
+    Stopped,
+}
+
+/// Payload of `events::CAPTURE_ERROR`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureError {
+    pub session_id: String,
+    /// Target that kept failing (None = primary monitor)
+    pub target_id: Option<String>,
+    pub reason: ErrorReason,
+    /// The last error, for display
+    pub message: String,
+    pub consecutive_failures: u32,
+    pub action: ErrorAction,
+}
+
+/// Report that `session_id` gave up on its target after `backoff.failures()` errors and
+/// decide what happens next. Falling back needs `fallback` (the stream's `error_fallback`)
+/// and a target other than the primary monitor.
+pub fn give_up(
+    session_id: &str,
+    target_id: Option<String>,
+    error: &Error,
+    backoff: &Backoff,
+    fallback: bool,
+) -> ErrorAction {
+    let action = if fallback && target_id.is_some() {
+        ErrorAction::FallbackToPrimary
+    } else {
+        ErrorAction::Stopped
+    };
+    log::error!(
+        "[ScreenCapture] Session {} failed {} times in a row ({}), {:?}",
+        session_id,
+        backoff.failures(),
+        error,
+        action
+    );
+    events::emit(events::CAPTURE_ERROR, CaptureError {
+        session_id: session_id.to_string(),
+        target_id,
+        reason: ErrorReason::of(error),
+        message: error.to_string(),
+        consecutive_failures: backoff.failures(),
+        action,
+    });
+    action
+}
+
+/// Sleep for `duration`, returning early (true) if the session is told to stop
+pub fn wait(duration: Duration, stop_rx: &watch::Receiver<bool>) -> bool {
+    let deadline = Instant::now() + duration;
+    loop {
+        if *stop_rx.borrow() {
+            return true;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        std::thread::sleep(STOP_POLL.min(deadline - now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<Duration> = (0..8).map(|_| backoff.failure()).collect();
+        assert_eq!(delays[0], BASE_DELAY);
+        assert_eq!(delays[1], BASE_DELAY * 2);
+        assert_eq!(delays[3], BASE_DELAY * 8);
+        assert_eq!(delays[7], MAX_DELAY);
+        backoff.success();
+        assert_eq!(backoff.failure(), BASE_DELAY);
+    }
+
+    #[test]
+    fn exhausted_at_the_limit_unless_unlimited() {
+        let mut backoff = Backoff::default();
+        for _ in 0..3 {
+            backoff.failure();
+        }
+        assert!(backoff.exhausted(3));
+        assert!(!backoff.exhausted(4));
+        assert!(!backoff.exhausted(0));
+    }
+}
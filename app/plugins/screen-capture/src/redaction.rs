@@ -0,0 +1,228 @@
+//! App deny-list: frames that would show a denied application are replaced with a
+//! placeholder before encoding.
+//!
+//! A frame is redacted when its session captures a window of a denied app, or when a
+//! denied app has focus (whatever the target: the focused window is usually on screen).
+//! App names are matched against the window owner's name, case-insensitively, the same
+//! way `app:{name}` targets are. Sessions following an app get its windows, so they're
+//! redacted too.
+//!
+//! The window list is re-read at most every `REFRESH_INTERVAL` by whichever capture
+//! thread needs it, so a denied app can show for that long after it takes focus. While
+//! the list can't be read every frame is redacted: the check fails closed.
+//!
+//! Placeholder frames keep the captured size and go through the normal encode path, so
+//! consumers get a gray "REDACTED" frame instead of a gap in the stream; target
+//! thumbnails get it too. Not available on Wayland, where clients can't see other apps'
+//! windows: a list saved in another session redacts every frame there. The list is
+//! persisted to `redaction.json` next to the capture profiles.
+
+use crate::error::{Error, Result};
+use crate::overlay::{Corner, FrameOverlay};
+use crate::targets::{self, TargetKind};
+use parking_lot::{Mutex, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use xcap::Window;
+
+const FILE_NAME: &str = "redaction.json";
+
+/// How long a read of the window list is reused
+pub const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Gray level of placeholder frames
+const PLACEHOLDER_GRAY: u8 = 48;
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+static APPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+static SNAPSHOT: Mutex<Option<Snapshot>> = Mutex::new(None);
+
+/// Which windows were showing denied apps at the last read
+struct Snapshot {
+    taken: Instant,
+    /// The list couldn't be read
+    failed: bool,
+    focused_denied: bool,
+    denied_windows: Vec<u32>,
+}
+
+/// Window facts a snapshot is built from
+struct WindowFacts {
+    id: u32,
+    app_name: String,
+    focused: bool,
+}
+
+/// Load the saved deny-list from `dir`
+pub fn init(dir: PathBuf) {
+    let path = dir.join(FILE_NAME);
+    match load(&path) {
+        Ok(apps) => {
+            if !apps.is_empty() {
+                log::info!("[ScreenCapture] Redacting {} app(s)", apps.len());
+            }
+            *APPS.write() = apps;
+        }
+        Err(e) => log::warn!("[ScreenCapture] {}", e),
+    }
+    let _ = PATH.set(path);
+}
+
+fn io_error(e: impl std::fmt::Display) -> Error {
+    Error::Platform(format!("Redaction list: {}", e))
+}
+
+fn load(path: &Path) -> Result<Vec<String>> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map(normalize).map_err(io_error),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+/// Write through a temp file so a crash mid-write can't truncate the saved list
+fn store(path: &Path, apps: &[String]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_vec_pretty(apps).map_err(io_error)?;
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json).map_err(io_error)?;
+    std::fs::rename(&temp, path).map_err(io_error)
+}
+
+/// Trimmed, sorted, without blanks or case-insensitive duplicates
+fn normalize(apps: Vec<String>) -> Vec<String> {
+    let mut apps: Vec<String> = apps.into_iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect();
+    apps.sort_by_key(|a| a.to_lowercase());
+    apps.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    apps
+}
+
+/// Denied app names
+pub fn list() -> Vec<String> {
+    APPS.read().clone()
+}
+
+/// Replace the deny-list (empty turns redaction off) and save; returns the stored list
+pub fn set(apps: Vec<String>) -> Result<Vec<String>> {
+    let apps = normalize(apps);
+    #[cfg(target_os = "linux")]
+    if !apps.is_empty() && crate::desktop_wayland::is_wayland() {
+        return Err(Error::Platform("App redaction is not available on Wayland".to_string()));
+    }
+    let path = PATH.get().ok_or_else(|| Error::Platform("Redaction storage not initialized".to_string()))?;
+    store(path, &apps)?;
+    log::info!("[ScreenCapture] Redacted apps: {:?}", apps);
+    *APPS.write() = apps;
+    // Re-read windows against the new list on the next frame
+    *SNAPSHOT.lock() = None;
+    Ok(list())
+}
+
+/// Whether a frame of `target_id` (None = primary monitor) must be replaced by a
+/// placeholder. Called by capture threads right before encoding.
+pub(crate) fn should_redact(target_id: Option<&str>) -> bool {
+    if APPS.read().is_empty() {
+        return false;
+    }
+    // Denied windows can't be seen on Wayland, so every frame could be showing one
+    #[cfg(target_os = "linux")]
+    if crate::desktop_wayland::is_wayland() {
+        return true;
+    }
+    let window = match target_id.map(targets::parse_target_id) {
+        Some(Ok((TargetKind::Window, id))) => Some(id),
+        _ => None,
+    };
+
+    let mut snapshot = SNAPSHOT.lock();
+    if !snapshot.as_ref().is_some_and(|s| s.taken.elapsed() < REFRESH_INTERVAL) {
+        let next = take_snapshot();
+        let (was_failed, was_focused) = snapshot.as_ref().map_or((false, false), |s| (s.failed, s.focused_denied));
+        if next.failed && !was_failed {
+            log::warn!("[ScreenCapture] Can't read the window list, redacting all frames");
+        }
+        if next.focused_denied != was_focused {
+            log::info!(
+                "[ScreenCapture] Denied app {} focus, {} frames",
+                if next.focused_denied { "has" } else { "lost" },
+                if next.focused_denied { "redacting" } else { "resuming" }
+            );
+        }
+        *snapshot = Some(next);
+    }
+    snapshot.as_ref().is_some_and(|s| {
+        s.failed || s.focused_denied || window.is_some_and(|id| s.denied_windows.contains(&id))
+    })
+}
+
+fn take_snapshot() -> Snapshot {
+    let windows = match Window::all() {
+        Ok(windows) => windows,
+        Err(e) => {
+            log::debug!("[ScreenCapture] Window list failed: {}", e);
+            return Snapshot { taken: Instant::now(), failed: true, focused_denied: false, denied_windows: Vec::new() };
+        }
+    };
+    let facts: Vec<WindowFacts> = windows
+        .iter()
+        .filter_map(|w| {
+            Some(WindowFacts {
+                id: w.id().ok()?,
+                app_name: w.app_name().unwrap_or_default(),
+                focused: w.is_focused().unwrap_or(false),
+            })
+        })
+        .collect();
+    let (focused_denied, denied_windows) = match_windows(&APPS.read(), &facts);
+    Snapshot { taken: Instant::now(), failed: false, focused_denied, denied_windows }
+}
+
+/// Whether a denied app has focus, and the ids of all denied apps' windows
+fn match_windows(apps: &[String], windows: &[WindowFacts]) -> (bool, Vec<u32>) {
+    let denied: Vec<&WindowFacts> =
+        windows.iter().filter(|w| apps.iter().any(|app| app.eq_ignore_ascii_case(w.app_name.trim()))).collect();
+    (denied.iter().any(|w| w.focused), denied.iter().map(|w| w.id).collect())
+}
+
+/// Overwrite a 4-bytes-per-pixel buffer whose rows are `stride` bytes apart with the
+/// placeholder frame
+pub fn placeholder(pixels: &mut [u8], width: u32, height: u32, stride: usize) {
+    let gray = [PLACEHOLDER_GRAY, PLACEHOLDER_GRAY, PLACEHOLDER_GRAY, 255];
+    for row in 0..height as usize {
+        let start = row * stride;
+        let Some(row) = pixels.get_mut(start..start + width as usize * 4) else { continue };
+        for pixel in row.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&gray);
+        }
+    }
+    let label = FrameOverlay { timestamp: false, label: Some("REDACTED".to_string()), corner: Corner::TopLeft };
+    label.draw(pixels, width, height, stride, 0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, app_name: &str, focused: bool) -> WindowFacts {
+        WindowFacts { id, app_name: app_name.to_string(), focused }
+    }
+
+    #[test]
+    fn normalizes_the_list() {
+        let apps = normalize(vec![" 1Password ".into(), "Mail".into(), "".into(), "mail".into()]);
+        assert_eq!(apps, vec!["1Password".to_string(), "Mail".to_string()]);
+    }
+
+    #[test]
+    fn matches_denied_windows_and_focus() {
+        let apps = vec!["1Password".to_string()];
+        let windows = [window(1, "Safari", true), window(2, "1password", false), window(3, "1Password", false)];
+        assert_eq!(match_windows(&apps, &windows), (false, vec![2, 3]));
+
+        let windows = [window(1, "Safari", false), window(2, "1Password", true)];
+        assert_eq!(match_windows(&apps, &windows), (true, vec![2]));
+    }
+}
@@ -0,0 +1,347 @@
+//! Instant replay: the last few seconds of captured frames, kept in memory.
+//!
+//! A background thread reads the frame tap (`frames`, i.e. the default session) into a
+//! ring buffer trimmed to `capture_config::replay_seconds()` — and to `MAX_BYTES`, so a
+//! high-FPS PNG stream can't eat the machine. `export` writes the newest part of it out
+//! as an MP4 (through the recording encoder) or as numbered image files, which is how an
+//! agent's alert can show what was on screen just before it fired. `export_gif` turns
+//! the same frames into a small looping GIF for attaching to notifications.
+//!
+//! Setting the duration to 0 turns the buffer off; the thread then drops its tap
+//! subscription so frames aren't copied for it. Pausing capture empties the buffer, and
+//! exports are refused while the host's incognito mode holds capture. Exports without a
+//! path go to `replays` in the app data dir.
+
+use crate::capture_config;
+use crate::error::{Error, Result};
+use crate::frames::{self, Frame};
+use crate::pause::{self, PauseReason};
+use crate::recording::{Mp4Encoder, RecordingSummary};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::FilterType;
+use image::{Delay, Frame as GifFrame};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Once, OnceLock};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Upper bound on buffered frame bytes
+const MAX_BYTES: usize = 256 * 1024 * 1024;
+const EXPORT_BITRATE_KBPS: u32 = 2500;
+
+#[derive(Default)]
+struct Buffer {
+    frames: VecDeque<Arc<Frame>>,
+    bytes: usize,
+}
+
+impl Buffer {
+    fn push(&mut self, frame: Arc<Frame>, seconds: f64) {
+        self.bytes += frame.data.len();
+        let newest = frame.timestamp;
+        self.frames.push_back(frame);
+        while let Some(oldest) = self.frames.front() {
+            if newest - oldest.timestamp <= seconds && self.bytes <= MAX_BYTES {
+                break;
+            }
+            self.bytes -= oldest.data.len();
+            self.frames.pop_front();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+    }
+
+    /// Frames from the last `seconds` of the buffer, oldest first
+    fn tail(&self, seconds: f64) -> Vec<Arc<Frame>> {
+        let Some(newest) = self.frames.back().map(|f| f.timestamp) else {
+            return Vec::new();
+        };
+        self.frames
+            .iter()
+            .filter(|f| newest - f.timestamp <= seconds)
+            .cloned()
+            .collect()
+    }
+}
+
+static BUFFER: Mutex<Buffer> = Mutex::new(Buffer {
+    frames: VecDeque::new(),
+    bytes: 0,
+});
+static EXPORT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set where exports without a path go
+pub fn init(dir: PathBuf) {
+    let _ = EXPORT_DIR.set(dir);
+}
+
+/// Drop every buffered frame
+pub fn clear() {
+    BUFFER.lock().clear();
+}
+
+/// Frames of the last `seconds`, unless incognito mode holds capture
+fn frames_for_export(seconds: f64) -> Result<Vec<Arc<Frame>>> {
+    if pause::is_set(PauseReason::Incognito) {
+        return Err(Error::Platform("Replay export is disabled while incognito mode is on".to_string()));
+    }
+    Ok(BUFFER.lock().tail(seconds))
+}
+
+/// Start the buffering thread (idempotent)
+pub fn start() {
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        std::thread::Builder::new()
+            .name("replay-buffer".to_string())
+            .spawn(run)
+            .map_err(|e| log::error!("[ScreenCapture] Failed to start replay buffer: {}", e))
+            .ok();
+    });
+}
+
+fn run() {
+    let mut rx = None;
+    loop {
+        let seconds = capture_config::replay_seconds();
+        if seconds == 0 {
+            if rx.take().is_some() {
+                BUFFER.lock().clear();
+                log::info!("[ScreenCapture] Replay buffer off");
+            }
+            std::thread::sleep(Duration::from_secs(1));
+            continue;
+        }
+
+        match rx.get_or_insert_with(frames::subscribe).blocking_recv() {
+            Ok(frame) => BUFFER.lock().push(frame, f64::from(seconds)),
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// How an exported replay is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplayFormat {
+    /// H.264 MP4 at `path`
+    #[default]
+    Mp4,
+    /// Numbered image files (in the frames' own format) in the directory `path`
+    Frames,
+}
+
+/// Write the last `seconds` (default: the whole buffer) to `path` (default: a file or
+/// directory in the export dir)
+pub fn export(seconds: Option<f64>, path: Option<PathBuf>, format: ReplayFormat) -> Result<RecordingSummary> {
+    let seconds = seconds.unwrap_or(f64::from(capture_config::replay_seconds())).max(0.0);
+    let frames = frames_for_export(seconds)?;
+    let (Some(first), Some(last)) = (frames.first(), frames.last()) else {
+        return Err(Error::NoFrame);
+    };
+    let (width, height) = (last.width, last.height);
+    let duration_secs = last.timestamp - first.timestamp;
+
+    let path = match path {
+        Some(path) => path,
+        None => default_path(match format {
+            ReplayFormat::Mp4 => Some("mp4"),
+            ReplayFormat::Frames => None,
+        })?,
+    };
+    log::info!(
+        "[ScreenCapture] Exporting {} replay frames ({:.1}s) to {}",
+        frames.len(),
+        duration_secs,
+        path.display()
+    );
+
+    match format {
+        ReplayFormat::Mp4 => {
+            let mut encoder = Mp4Encoder::create(path, EXPORT_BITRATE_KBPS)?;
+            for frame in &frames {
+                encoder.push(&frame.data, frame.timestamp)?;
+            }
+            encoder.finish()
+        }
+        ReplayFormat::Frames => {
+            let io_error = |e: std::io::Error| Error::Platform(format!("Replay export failed: {}", e));
+            std::fs::create_dir_all(&path).map_err(io_error)?;
+            for (index, frame) in frames.iter().enumerate() {
+                let extension = frame.format.mime_type().trim_start_matches("image/");
+                let file = path.join(format!("frame_{:05}.{}", index, extension));
+                std::fs::write(file, &frame.data).map_err(io_error)?;
+            }
+            Ok(RecordingSummary {
+                path: path.to_string_lossy().into_owned(),
+                duration_secs,
+                frame_count: frames.len() as u64,
+                width,
+                height,
+            })
+        }
+    }
+}
+
+/// GIF export settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GifOptions {
+    /// Frames per second in the GIF (default 5); frames in between are dropped
+    pub fps: Option<f64>,
+    /// Downscale to this width (default 640)
+    pub max_width: Option<u32>,
+    /// Palette quantization speed, 1 (best colors, slowest) to 30 (fastest); default 10
+    pub quantize_speed: Option<i32>,
+}
+
+/// Default output path in the export dir, which is created if needed
+fn default_path(extension: Option<&str>) -> Result<PathBuf> {
+    let dir = EXPORT_DIR.get().ok_or_else(|| Error::Platform("No replay export dir; pass a path".to_string()))?;
+    std::fs::create_dir_all(dir).map_err(|e| Error::Platform(format!("Replay export failed: {}", e)))?;
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = match extension {
+        Some(extension) => format!("observer-replay-{}.{}", stamp, extension),
+        None => format!("observer-replay-{}", stamp),
+    };
+    Ok(dir.join(name))
+}
+
+/// Which frames (by index) make a `fps` animation, each with its display time in ms
+fn pick_frames(timestamps: &[f64], fps: f64) -> Vec<(usize, u32)> {
+    let interval = 1.0 / fps;
+    let mut picked: Vec<(usize, f64)> = Vec::new();
+    let mut next = f64::NEG_INFINITY;
+    for (index, &timestamp) in timestamps.iter().enumerate() {
+        if timestamp >= next {
+            picked.push((index, timestamp));
+            next = timestamp + interval * 0.95; // tolerate capture jitter
+        }
+    }
+    let last_delay = (interval * 1000.0).round() as u32;
+    (0..picked.len())
+        .map(|i| {
+            let delay = picked
+                .get(i + 1)
+                .map(|next| ((next.1 - picked[i].1) * 1000.0).round() as u32)
+                .unwrap_or(last_delay);
+            (picked[i].0, delay.max(20))
+        })
+        .collect()
+}
+
+/// Write the last `seconds` (default: the whole buffer) as a looping GIF
+pub fn export_gif(seconds: Option<f64>, path: Option<PathBuf>, options: GifOptions) -> Result<RecordingSummary> {
+    let seconds = seconds.unwrap_or(f64::from(capture_config::replay_seconds())).max(0.0);
+    let frames = frames_for_export(seconds)?;
+    if frames.is_empty() {
+        return Err(Error::NoFrame);
+    }
+    let fps = options.fps.unwrap_or(5.0).clamp(0.5, 30.0);
+    let max_width = options.max_width.unwrap_or(640).clamp(64, 1920);
+    let speed = options.quantize_speed.unwrap_or(10).clamp(1, 30);
+    let path = match path {
+        Some(path) => path,
+        None => default_path(Some("gif"))?,
+    };
+
+    let io_error = |e: std::io::Error| Error::Platform(format!("GIF export failed: {}", e));
+    let gif_error = |e: image::ImageError| Error::Platform(format!("GIF export failed: {}", e));
+    let file = std::fs::File::create(&path).map_err(io_error)?;
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), speed);
+    encoder.set_repeat(Repeat::Infinite).map_err(gif_error)?;
+
+    let timestamps: Vec<f64> = frames.iter().map(|f| f.timestamp).collect();
+    let picked = pick_frames(&timestamps, fps);
+    let mut size = (0, 0);
+    let mut duration_ms = 0u64;
+    for &(index, delay_ms) in &picked {
+        let Ok(decoded) = image::load_from_memory(&frames[index].data) else {
+            continue;
+        };
+        let mut rgba = decoded.to_rgba8();
+        if rgba.width() > max_width {
+            let height = (u64::from(rgba.height()) * u64::from(max_width) / u64::from(rgba.width())) as u32;
+            rgba = image::imageops::resize(&rgba, max_width, height.max(1), FilterType::Triangle);
+        }
+        size = rgba.dimensions();
+        encoder
+            .encode_frame(GifFrame::from_parts(rgba, 0, 0, Delay::from_numer_denom_ms(delay_ms, 1)))
+            .map_err(gif_error)?;
+        duration_ms += u64::from(delay_ms);
+    }
+    drop(encoder);
+
+    log::info!(
+        "[ScreenCapture] GIF saved to {} ({} frames at {} fps)",
+        path.display(),
+        picked.len(),
+        fps
+    );
+    Ok(RecordingSummary {
+        path: path.to_string_lossy().into_owned(),
+        duration_secs: duration_ms as f64 / 1000.0,
+        frame_count: picked.len() as u64,
+        width: size.0,
+        height: size.1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_config::FrameEncoding;
+
+    fn frame(timestamp: f64, size: usize) -> Arc<Frame> {
+        Arc::new(Frame {
+            data: vec![0; size],
+            format: FrameEncoding::Jpeg,
+            timestamp,
+            width: 2,
+            height: 2,
+            frame_count: 0,
+        })
+    }
+
+    #[test]
+    fn trims_to_duration_and_exports_tail() {
+        let mut buffer = Buffer::default();
+        for i in 0..50 {
+            buffer.push(frame(f64::from(i) * 0.5, 10), 10.0);
+        }
+        // 24.5s is newest; everything older than 14.5s is gone
+        assert_eq!(buffer.frames.front().unwrap().timestamp, 14.5);
+        assert_eq!(buffer.bytes, buffer.frames.len() * 10);
+        assert_eq!(buffer.tail(2.0).len(), 5);
+        assert!(Buffer::default().tail(5.0).is_empty());
+    }
+
+    #[test]
+    fn picks_frames_at_gif_rate() {
+        // 10 fps capture, 2 fps GIF
+        let timestamps: Vec<f64> = (0..10).map(|i| f64::from(i) * 0.1).collect();
+        assert_eq!(pick_frames(&timestamps, 2.0), vec![(0, 500), (5, 500)]);
+        // Slower capture than requested keeps every frame with its real spacing
+        assert_eq!(pick_frames(&[0.0, 1.0], 5.0), vec![(0, 1000), (1, 200)]);
+    }
+
+    #[test]
+    fn trims_to_byte_cap() {
+        let mut buffer = Buffer::default();
+        buffer.push(frame(0.0, MAX_BYTES / 2), 60.0);
+        buffer.push(frame(1.0, MAX_BYTES / 2), 60.0);
+        buffer.push(frame(2.0, 1), 60.0);
+        assert_eq!(buffer.frames.len(), 2);
+        assert!(buffer.bytes <= MAX_BYTES);
+    }
+}
@@ -0,0 +1,155 @@
+//! One-shot, full-resolution screenshots.
+//!
+//! Streams are downscaled to `capture_config::max_width` and encoded at stream quality,
+//! which leaves small text unreadable. A screenshot is a single capture at the source's
+//! native resolution, encoded lossless (PNG, the default) or at `QUALITY`, for agents
+//! that need to read fine detail once. It's returned base64-encoded or written to a
+//! file.
+//!
+//! Screenshots honor the same guards as streams: they fail while capture is paused or a
+//! password field has focus, denied apps are replaced by the redaction placeholder and
+//! privacy regions are hidden. Captured through xcap, so not available on Wayland.
+
+use crate::capture_config::FrameEncoding;
+use crate::encode;
+use crate::error::{Error, Result};
+use crate::follow::Follow;
+use crate::pause;
+use crate::privacy;
+use crate::redaction;
+use crate::secure_input;
+use crate::stitch;
+use crate::targets::{self, TargetKind};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::RgbaImage;
+use serde::Serialize;
+use std::path::Path;
+use xcap::{Monitor, Window};
+
+/// JPEG / lossy WebP quality of screenshots
+pub const QUALITY: u8 = 95;
+
+/// A captured screenshot
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Screenshot {
+    /// Target captured (None = primary monitor); followed targets resolve to a window
+    pub target_id: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub format: FrameEncoding,
+    /// Capture time, seconds since the epoch
+    pub timestamp: f64,
+    /// Base64 image, unless it was written to `path`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Capture `target_id` (None = primary monitor) once at full resolution. With `path`
+/// the image is written there instead of returned.
+pub fn capture(target_id: Option<&str>, format: FrameEncoding, path: Option<&Path>) -> Result<Screenshot> {
+    let (target_id, image, timestamp) = capture_image(target_id)?;
+    let (width, height) = image.dimensions();
+    let rgb: Vec<u8> = image.as_raw().chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+    let encoded = encode::encode_rgb(&rgb, width, height, format, QUALITY)
+        .ok_or_else(|| Error::Platform(format!("Failed to encode {:?} screenshot", format)))?;
+    log::info!(
+        "[ScreenCapture] Screenshot of {} ({}x{}, {:?}, {} bytes)",
+        target_id.as_deref().unwrap_or("primary monitor"),
+        width,
+        height,
+        format,
+        encoded.len()
+    );
+
+    let mut screenshot = Screenshot { target_id, width, height, format, timestamp, data: None, path: None };
+    match path {
+        Some(path) => {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir).map_err(|e| Error::Platform(e.to_string()))?;
+            }
+            std::fs::write(path, encoded)
+                .map_err(|e| Error::Platform(format!("Failed to write {}: {}", path.display(), e)))?;
+            screenshot.path = Some(path.display().to_string());
+        }
+        None => screenshot.data = Some(STANDARD.encode(encoded)),
+    }
+    Ok(screenshot)
+}
+
+/// The unencoded image behind `capture`, with guards applied: the resolved target id,
+/// RGBA pixels and capture time (seconds since the epoch)
+pub fn capture_image(target_id: Option<&str>) -> Result<(Option<String>, RgbaImage, f64)> {
+    #[cfg(target_os = "linux")]
+    if crate::desktop_wayland::is_wayland() {
+        return Err(Error::Platform("Screenshots are not available on Wayland".to_string()));
+    }
+    check_allowed()?;
+
+    let target_id = match target_id.and_then(Follow::parse) {
+        Some(follow) => follow.initial_window()?.map(|id| targets::format_target_id(&TargetKind::Window, id)),
+        None => target_id.map(str::to_string),
+    };
+    let mut image = grab(target_id.as_deref())?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    protect(target_id.as_deref(), &mut image);
+    Ok((target_id, image, timestamp))
+}
+
+/// Fail while capture is paused or a password field has focus
+pub(crate) fn check_allowed() -> Result<()> {
+    if pause::is_paused() {
+        return Err(Error::Platform("Capture is paused".to_string()));
+    }
+    if secure_input::is_enabled() && secure_input::is_secure_input_active() {
+        return Err(Error::Platform("Secure input is active, screenshot skipped".to_string()));
+    }
+    Ok(())
+}
+
+/// Replace a full-resolution capture of a denied app with the placeholder, or else hide
+/// the privacy regions in it
+pub(crate) fn protect(target_id: Option<&str>, image: &mut RgbaImage) {
+    let (width, height) = image.dimensions();
+    let stride = width as usize * 4;
+    if redaction::should_redact(target_id) {
+        redaction::placeholder(image, width, height, stride);
+    } else {
+        let hidden = privacy::for_frame(target_id, (0.0, 0.0));
+        privacy::apply(&hidden, image, width, height, stride, 1.0);
+    }
+}
+
+/// Native-resolution capture of a target
+fn grab(target_id: Option<&str>) -> Result<RgbaImage> {
+    let captured = match target_id.map(targets::parse_target_id).transpose()? {
+        Some((TargetKind::Monitor, id)) => Monitor::all()
+            .map_err(|e| Error::Platform(format!("Failed to get monitors: {}", e)))?
+            .into_iter()
+            .find(|m| m.id().ok() == Some(id))
+            .ok_or_else(|| Error::Platform(format!("Monitor {} not found", id)))?
+            .capture_image(),
+        Some((TargetKind::Window, id)) => Window::all()
+            .map_err(|e| Error::Platform(format!("Failed to get windows: {}", e)))?
+            .into_iter()
+            .find(|w| w.id().ok() == Some(id))
+            .ok_or_else(|| Error::Platform(format!("Window {} not found", id)))?
+            .capture_image(),
+        Some((TargetKind::VirtualDesktop, _)) => return stitch::capture_at(&stitch::monitors()?, u32::MAX),
+        None => {
+            let monitors = Monitor::all().map_err(|e| Error::Platform(format!("Failed to get monitors: {}", e)))?;
+            let primary = monitors.iter().position(|m| m.is_primary().unwrap_or(false)).unwrap_or(0);
+            monitors
+                .into_iter()
+                .nth(primary)
+                .ok_or_else(|| Error::Platform("No monitors found".to_string()))?
+                .capture_image()
+        }
+    };
+    captured.map_err(|e| Error::Platform(e.to_string()))
+}
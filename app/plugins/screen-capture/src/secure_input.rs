@@ -0,0 +1,154 @@
+//! Sensitive-content detection.
+//!
+//! Before a frame is grabbed, the capture backends ask whether the user is typing into a
+//! password field (or, on macOS, whether any app has enabled secure event input). While
+//! that is the case the frame is skipped, so credentials never reach the encoder.
+//!
+//! - macOS: `IsSecureEventInputEnabled()` plus the focused element's `AXSecureTextField`
+//!   subrole (the latter needs Accessibility permission and is skipped without it).
+//! - Windows: UI Automation `IsPassword` on the focused element.
+//! - Linux: no portable API without an AT-SPI session; detection always reports false.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static HOLDING: AtomicBool = AtomicBool::new(false);
+
+/// Turn detection on or off (on by default)
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    log::info!("[ScreenCapture] Secure input detection {}", if enabled { "enabled" } else { "disabled" });
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Whether the last check found a password field / secure input
+pub fn is_holding() -> bool {
+    HOLDING.load(Ordering::SeqCst)
+}
+
+/// Check right before capturing. Returns true if the frame must be skipped.
+pub(crate) fn should_skip_frame() -> bool {
+    let active = is_enabled() && is_secure_input_active();
+    if HOLDING.swap(active, Ordering::SeqCst) != active {
+        log::info!(
+            "[ScreenCapture] Secure input {}, {} frames",
+            if active { "detected" } else { "ended" },
+            if active { "skipping" } else { "resuming" }
+        );
+    }
+    active
+}
+
+/// Query the OS for secure input / a focused password field
+pub fn is_secure_input_active() -> bool {
+    platform::is_secure_input_active()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use core_foundation::base::{CFRelease, CFTypeRef, TCFType};
+    use core_foundation::string::{CFString, CFStringRef};
+    use std::ffi::c_void;
+
+    type AXUIElementRef = *const c_void;
+    const AX_ERROR_SUCCESS: i32 = 0;
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn IsSecureEventInputEnabled() -> u8;
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+        fn AXUIElementCopyAttributeValue(
+            element: AXUIElementRef,
+            attribute: CFStringRef,
+            value: *mut CFTypeRef,
+        ) -> i32;
+    }
+
+    pub fn is_secure_input_active() -> bool {
+        // Set by password fields, Terminal's "Secure Keyboard Entry", sudo prompts, ...
+        if unsafe { IsSecureEventInputEnabled() } != 0 {
+            return true;
+        }
+        focused_element_is_secure()
+    }
+
+    fn copy_attribute(element: AXUIElementRef, name: &str) -> Option<CFTypeRef> {
+        let attribute = CFString::new(name);
+        let mut value: CFTypeRef = std::ptr::null();
+        let err = unsafe {
+            AXUIElementCopyAttributeValue(element, attribute.as_concrete_TypeRef(), &mut value)
+        };
+        (err == AX_ERROR_SUCCESS && !value.is_null()).then_some(value)
+    }
+
+    fn focused_element_is_secure() -> bool {
+        unsafe {
+            let system = AXUIElementCreateSystemWide();
+            if system.is_null() {
+                return false;
+            }
+            let focused = copy_attribute(system, "AXFocusedUIElement");
+            CFRelease(system as CFTypeRef);
+            let Some(focused) = focused else {
+                return false;
+            };
+
+            let subrole = copy_attribute(focused as AXUIElementRef, "AXSubrole");
+            CFRelease(focused);
+            let Some(subrole) = subrole else {
+                return false;
+            };
+
+            // AXSubrole values are CFStrings; the create rule hands ownership to `subrole`
+            let subrole = CFString::wrap_under_create_rule(subrole as CFStringRef);
+            subrole.to_string() == "AXSecureTextField"
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{CUIAutomation, IUIAutomation};
+
+    thread_local! {
+        // One UI Automation client per capture thread
+        static AUTOMATION: Option<IUIAutomation> = unsafe {
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+                .map_err(|e| log::warn!("[ScreenCapture] UI Automation unavailable: {}", e))
+                .ok()
+        };
+    }
+
+    pub fn is_secure_input_active() -> bool {
+        AUTOMATION.with(|automation| {
+            let Some(automation) = automation else {
+                return false;
+            };
+            unsafe {
+                automation
+                    .GetFocusedElement()
+                    .and_then(|element| element.CurrentIsPassword())
+                    .map(|is_password| is_password.as_bool())
+                    .unwrap_or(false)
+            }
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    pub fn is_secure_input_active() -> bool {
+        false
+    }
+}
@@ -0,0 +1,35 @@
+//! Capture sessions.
+//!
+//! Each video stream runs as a named session with its own target, frame counter and stop
+//! signal, so several agents can watch different windows at once and stop independently.
+//! Callers that don't name a session (the app's main capture) use `DEFAULT_SESSION`;
+//! starting a session under an id that is already running replaces that session.
+//!
+//! Only the default session feeds the in-process frame tap (`frames`), and
+//! `geometry::current()` reports the default session's geometry.
+
+use serde::Serialize;
+
+/// Session used when no id is given
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Id to use for an optional caller-supplied session id
+pub fn resolve(session_id: Option<&str>) -> &str {
+    match session_id {
+        Some(id) if !id.trim().is_empty() => id,
+        _ => DEFAULT_SESSION,
+    }
+}
+
+/// A running capture session as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub id: String,
+    /// None = primary display
+    pub target_id: Option<String>,
+    pub is_active: bool,
+    pub frame_count: u64,
+    /// Whether the session follows the focused window or an app (see `follow`)
+    pub following: bool,
+}
@@ -0,0 +1,197 @@
+//! Frame similarity: a per-frame change score on every stream and a comparison of two
+//! images on demand.
+//!
+//! Every frame carries `change`, how much it differs from the previous frame of its
+//! stream: the Hamming distance between the two frames' 64-bit difference hashes
+//! (dHash), over 64. 0 means the frames look the same at a glance, a few hundredths is
+//! a blinking cursor or clock, and anything past ~0.1 is a visible change. It's computed
+//! from the pixels right before encoding by sampling a 9x8 grid, so it costs next to
+//! nothing and ignores encoder noise. Agents can skip a model call while it stays low.
+//!
+//! `compare` takes two encoded images (PNG or JPEG) and reports the hash distance plus
+//! a structural similarity (SSIM) over grayscale, which is slower but catches small
+//! changes (a new line of text) the hash can miss.
+
+use crate::delta::PixelOrder;
+use crate::error::{Error, Result};
+use image::imageops::FilterType;
+use image::GrayImage;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Hash bits
+const BITS: u32 = 64;
+
+/// Samples per grid cell side when hashing
+const SAMPLES: u32 = 4;
+
+/// Longest side images are reduced to for SSIM
+const SSIM_SIZE: u32 = 256;
+
+/// SSIM window side
+const WINDOW: u32 = 8;
+
+/// How similar two images are
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Similarity {
+    /// Differing dHash bits, 0–64
+    pub hash_distance: u32,
+    /// `hash_distance` over 64: 0 = same, 1 = unrelated
+    pub change: f32,
+    /// Mean structural similarity, 1 = identical
+    pub ssim: f32,
+}
+
+/// 64-bit difference hash of a 4-bytes-per-pixel buffer whose rows are `stride` bytes
+/// apart: each bit says whether a cell of a 9x8 grid is brighter than its right neighbor
+pub fn dhash(pixels: &[u8], width: u32, height: u32, stride: usize, order: PixelOrder) -> u64 {
+    if width == 0 || height == 0 {
+        return 0;
+    }
+    let luma = |x: u32, y: u32| -> u32 {
+        let p = &pixels[y as usize * stride + x as usize * 4..][..4];
+        let (r, g, b) = match order {
+            PixelOrder::Rgba => (p[0], p[1], p[2]),
+            PixelOrder::Bgra => (p[2], p[1], p[0]),
+        };
+        77 * u32::from(r) + 150 * u32::from(g) + 29 * u32::from(b)
+    };
+    // Mean of SAMPLES x SAMPLES points spread over each cell
+    let cell = |cx: u32, cy: u32| -> u32 {
+        let mut sum = 0;
+        for sy in 0..SAMPLES {
+            for sx in 0..SAMPLES {
+                let x = ((cx * SAMPLES + sx) * 2 + 1) * width / (9 * SAMPLES * 2);
+                let y = ((cy * SAMPLES + sy) * 2 + 1) * height / (8 * SAMPLES * 2);
+                sum += luma(x, y);
+            }
+        }
+        sum
+    };
+    let mut hash = 0u64;
+    for cy in 0..8 {
+        let row: Vec<u32> = (0..9).map(|cx| cell(cx, cy)).collect();
+        for cx in 0..8 {
+            hash = (hash << 1) | u64::from(row[cx] > row[cx + 1]);
+        }
+    }
+    hash
+}
+
+/// Change from the hash of the previous frame, kept per stream
+#[derive(Debug, Default)]
+pub struct ChangeTracker {
+    /// Previous hash + 1, 0 before the first frame
+    previous: AtomicU64,
+}
+
+impl ChangeTracker {
+    /// Change score of a frame with `hash` against the previous one; None on the first
+    /// frame
+    pub fn update(&self, hash: u64) -> Option<f32> {
+        let previous = self.previous.swap(hash.wrapping_add(1), Ordering::Relaxed);
+        (previous != 0).then(|| (previous.wrapping_sub(1) ^ hash).count_ones() as f32 / BITS as f32)
+    }
+}
+
+/// Compare two encoded images
+pub fn compare(a: &[u8], b: &[u8]) -> Result<Similarity> {
+    let decode = |bytes: &[u8]| {
+        image::load_from_memory(bytes)
+            .map(|image| image.to_rgba8())
+            .map_err(|e| Error::Platform(format!("Failed to decode image: {}", e)))
+    };
+    let (a, b) = (decode(a)?, decode(b)?);
+    let hash = |image: &image::RgbaImage| {
+        let (width, height) = image.dimensions();
+        dhash(image.as_raw(), width, height, width as usize * 4, PixelOrder::Rgba)
+    };
+    let hash_distance = (hash(&a) ^ hash(&b)).count_ones();
+
+    // SSIM needs matching sizes: reduce both to the first image's aspect
+    let (width, height) = a.dimensions();
+    let scale = SSIM_SIZE as f32 / width.max(height).max(1) as f32;
+    let (w, h) = if scale < 1.0 {
+        (((width as f32 * scale) as u32).max(1), ((height as f32 * scale) as u32).max(1))
+    } else {
+        (width.max(1), height.max(1))
+    };
+    let gray = |image: &image::RgbaImage| {
+        image::imageops::grayscale(&image::imageops::resize(image, w, h, FilterType::Triangle))
+    };
+    Ok(Similarity { hash_distance, change: hash_distance as f32 / BITS as f32, ssim: ssim(&gray(&a), &gray(&b)) })
+}
+
+/// Mean SSIM over `WINDOW`-pixel tiles of two same-size grayscale images
+fn ssim(a: &GrayImage, b: &GrayImage) -> f32 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let (width, height) = a.dimensions();
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y0 in (0..height).step_by(WINDOW as usize) {
+        for x0 in (0..width).step_by(WINDOW as usize) {
+            let pixels: Vec<(f64, f64)> = (y0..(y0 + WINDOW).min(height))
+                .flat_map(|y| (x0..(x0 + WINDOW).min(width)).map(move |x| (x, y)))
+                .map(|(x, y)| (f64::from(a.get_pixel(x, y)[0]), f64::from(b.get_pixel(x, y)[0])))
+                .collect();
+            let n = pixels.len() as f64;
+            let mean_a = pixels.iter().map(|p| p.0).sum::<f64>() / n;
+            let mean_b = pixels.iter().map(|p| p.1).sum::<f64>() / n;
+            let (mut var_a, mut var_b, mut covariance) = (0.0, 0.0, 0.0);
+            for &(pa, pb) in &pixels {
+                var_a += (pa - mean_a) * (pa - mean_a);
+                var_b += (pb - mean_b) * (pb - mean_b);
+                covariance += (pa - mean_a) * (pb - mean_b);
+            }
+            let (var_a, var_b, covariance) = (var_a / n, var_b / n, covariance / n);
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+    if windows == 0 {
+        1.0
+    } else {
+        (total / f64::from(windows)) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32, flip: bool) -> Vec<u8> {
+        (0..height)
+            .flat_map(|_| (0..width).map(move |x| if flip { width - 1 - x } else { x }))
+            .flat_map(|x| {
+                let v = (x * 255 / width) as u8;
+                [v, v, v, 255]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn change_score_follows_hash_distance() {
+        let (width, height) = (90, 40);
+        let left = gradient(width, height, false);
+        let right = gradient(width, height, true);
+        let hash = |pixels: &[u8]| dhash(pixels, width, height, width as usize * 4, PixelOrder::Rgba);
+        assert_eq!(hash(&left), 0);
+        assert_eq!(hash(&right), u64::MAX);
+
+        let tracker = ChangeTracker::default();
+        assert_eq!(tracker.update(hash(&left)), None);
+        assert_eq!(tracker.update(hash(&left)), Some(0.0));
+        assert_eq!(tracker.update(hash(&right)), Some(1.0));
+    }
+
+    #[test]
+    fn ssim_is_one_for_identical_images() {
+        let image = GrayImage::from_fn(32, 24, |x, y| image::Luma([(x * 7 + y * 3) as u8]));
+        assert!((ssim(&image, &image) - 1.0).abs() < 1e-6);
+        let inverted = GrayImage::from_fn(32, 24, |x, y| image::Luma([255 - image.get_pixel(x, y)[0]]));
+        assert!(ssim(&image, &inverted) < 0.0);
+    }
+}
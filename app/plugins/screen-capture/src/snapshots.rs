@@ -0,0 +1,214 @@
+//! Periodic snapshots to disk, for time-lapse logs and review without a live frontend.
+//!
+//! While running, a background thread takes a full-resolution `screenshot` of the
+//! configured target every `interval_secs` and writes it to `directory` as
+//! `snapshot-<unix millis>.<ext>`, so names sort by capture time. After each write the
+//! oldest snapshots are deleted until the directory is within `max_files` and
+//! `max_bytes`; other files there are left alone.
+//!
+//! Snapshots run independently of streams, so they work with or without one. They get
+//! the screenshot guards: nothing is written while capture is paused (incognito, screen
+//! sharing) or secure input is active, and denied apps and privacy regions are hidden.
+//! Only one snapshot writer runs at a time; starting it again replaces the config.
+
+use crate::capture_config::FrameEncoding;
+use crate::error::{Error, Result};
+use crate::screenshot;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+const PREFIX: &str = "snapshot-";
+/// How often a waiting writer checks whether it was stopped
+const STOP_POLL: Duration = Duration::from_millis(250);
+
+fn default_interval() -> u32 {
+    60
+}
+
+/// What to snapshot, how often and where
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotConfig {
+    pub directory: String,
+    /// Seconds between snapshots (at least 1)
+    #[serde(default = "default_interval")]
+    pub interval_secs: u32,
+    /// None = primary monitor
+    #[serde(default)]
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub format: FrameEncoding,
+    /// Snapshots kept; the oldest are deleted past this (None = no limit)
+    #[serde(default)]
+    pub max_files: Option<u32>,
+    /// Total size kept, in bytes (None = no limit)
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+/// The running writer
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotStatus {
+    pub config: SnapshotConfig,
+    /// Snapshots written since it started
+    pub count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_path: Option<String>,
+    /// Why the last snapshot wasn't written, cleared by the next success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Generation of the running writer; a writer thread exits once this moves on
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static STATUS: Mutex<Option<SnapshotStatus>> = Mutex::new(None);
+
+/// Start writing snapshots, replacing a running writer
+pub fn start(mut config: SnapshotConfig) -> Result<()> {
+    config.interval_secs = config.interval_secs.max(1);
+    let directory = PathBuf::from(config.directory.trim());
+    if directory.as_os_str().is_empty() {
+        return Err(Error::Platform("Snapshots need a directory".to_string()));
+    }
+    std::fs::create_dir_all(&directory)
+        .map_err(|e| Error::Platform(format!("Failed to create {}: {}", directory.display(), e)))?;
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    log::info!(
+        "[ScreenCapture] Snapshots of {} every {}s to {}",
+        config.target_id.as_deref().unwrap_or("primary monitor"),
+        config.interval_secs,
+        directory.display()
+    );
+    *STATUS.lock() = Some(SnapshotStatus { config: config.clone(), count: 0, last_path: None, last_error: None });
+    std::thread::spawn(move || run(generation, config, directory));
+    Ok(())
+}
+
+/// Stop the running writer; false if none was running
+pub fn stop() -> bool {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    let stopped = STATUS.lock().take().is_some();
+    if stopped {
+        log::info!("[ScreenCapture] Snapshots stopped");
+    }
+    stopped
+}
+
+/// The running writer, if any
+pub fn status() -> Option<SnapshotStatus> {
+    STATUS.lock().clone()
+}
+
+fn is_current(generation: u64) -> bool {
+    GENERATION.load(Ordering::SeqCst) == generation
+}
+
+fn run(generation: u64, config: SnapshotConfig, directory: PathBuf) {
+    let interval = Duration::from_secs(u64::from(config.interval_secs));
+    let extension = match config.format {
+        FrameEncoding::Jpeg => "jpg",
+        FrameEncoding::Png => "png",
+        FrameEncoding::Webp | FrameEncoding::WebpLossless => "webp",
+    };
+    while is_current(generation) {
+        let started = Instant::now();
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = directory.join(format!("{}{}.{}", PREFIX, millis, extension));
+        let result = screenshot::capture(config.target_id.as_deref(), config.format, Some(&path));
+        if !is_current(generation) {
+            break;
+        }
+        match result {
+            Ok(_) => {
+                if let Err(e) = prune(&directory, config.max_files, config.max_bytes) {
+                    log::warn!("[ScreenCapture] Snapshot rotation failed: {}", e);
+                }
+                if let Some(status) = STATUS.lock().as_mut() {
+                    status.count += 1;
+                    status.last_path = Some(path.display().to_string());
+                    status.last_error = None;
+                }
+            }
+            Err(e) => {
+                log::debug!("[ScreenCapture] Snapshot skipped: {}", e);
+                if let Some(status) = STATUS.lock().as_mut() {
+                    status.last_error = Some(e.to_string());
+                }
+            }
+        }
+        while is_current(generation) && started.elapsed() < interval {
+            std::thread::sleep(STOP_POLL.min(interval.saturating_sub(started.elapsed())));
+        }
+    }
+}
+
+/// Delete the oldest snapshots in `directory` past the limits
+fn prune(directory: &Path, max_files: Option<u32>, max_bytes: Option<u64>) -> std::io::Result<()> {
+    if max_files.is_none() && max_bytes.is_none() {
+        return Ok(());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(PREFIX) && entry.file_type()?.is_file() {
+            files.push((name, entry.metadata()?.len()));
+        }
+    }
+    files.sort();
+    for name in expired(&files, max_files, max_bytes) {
+        std::fs::remove_file(directory.join(name))?;
+    }
+    Ok(())
+}
+
+/// Names to delete, oldest first, from `files` sorted oldest first, so the rest fit
+/// `max_files` and `max_bytes`. The newest snapshot is always kept.
+fn expired(files: &[(String, u64)], max_files: Option<u32>, max_bytes: Option<u64>) -> Vec<&str> {
+    let mut count = files.len();
+    let mut total: u64 = files.iter().map(|(_, size)| size).sum();
+    let mut expired = Vec::new();
+    for (name, size) in &files[..files.len().saturating_sub(1)] {
+        let over_count = max_files.is_some_and(|max| count > max as usize);
+        let over_size = max_bytes.is_some_and(|max| total > max);
+        if !over_count && !over_size {
+            break;
+        }
+        expired.push(name.as_str());
+        count -= 1;
+        total -= size;
+    }
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(sizes: &[u64]) -> Vec<(String, u64)> {
+        sizes.iter().enumerate().map(|(i, &size)| (format!("{}{}.png", PREFIX, 1000 + i), size)).collect()
+    }
+
+    #[test]
+    fn expires_oldest_past_the_limits() {
+        let files = files(&[10, 20, 30, 40]);
+        assert!(expired(&files, None, None).is_empty());
+        assert_eq!(expired(&files, Some(2), None), vec!["snapshot-1000.png", "snapshot-1001.png"]);
+        assert_eq!(expired(&files, None, Some(75)), vec!["snapshot-1000.png", "snapshot-1001.png"]);
+        assert_eq!(expired(&files, Some(3), Some(100)), vec!["snapshot-1000.png"]);
+    }
+
+    #[test]
+    fn keeps_the_newest_snapshot() {
+        let files = files(&[10, 500]);
+        assert_eq!(expired(&files, Some(0), Some(100)), vec!["snapshot-1000.png"]);
+    }
+}
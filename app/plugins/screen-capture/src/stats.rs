@@ -0,0 +1,219 @@
+//! Per-session capture statistics, reported by `get_capture_stats_cmd`.
+//!
+//! Each frame channel (`wire::FrameSink`) keeps a `Recorder` next to its backpressure
+//! state. Capture loops record how long each stage took; stage times are smoothed
+//! averages, rates are measured over the last `WINDOW`. Stages a backend doesn't run
+//! itself are left out: ScreenCaptureKit captures and scales frames in the OS, so its
+//! streams report only encode times. Frame and byte totals and a histogram of encode
+//! times are kept as well, for the API server's `/metrics`.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window fps and throughput are measured over
+pub const WINDOW: Duration = Duration::from_secs(2);
+
+/// A step of the frame pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Grabbing the frame (or reading it back from the GPU / PipeWire buffer)
+    Capture,
+    /// Downscaling to the configured max width
+    Resize,
+    /// Encoding to the configured format
+    Encode,
+}
+
+/// Upper bounds of the default `Histogram` buckets, in seconds
+pub const LATENCY_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// Distribution of durations, with cumulative buckets as Prometheus expects them
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+    /// Upper bounds of the buckets, in seconds
+    pub bounds: &'static [f64],
+    /// Observations at or below each bound
+    pub counts: Vec<u64>,
+    pub count: u64,
+    /// Sum of all observations, in seconds
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: vec![0; bounds.len()], count: 0, sum: 0.0 }
+    }
+
+    pub fn observe(&mut self, took: Duration) {
+        let secs = took.as_secs_f64();
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter_mut()) {
+            if secs <= *bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(LATENCY_BUCKETS)
+    }
+}
+
+/// Smoothed duration
+#[derive(Debug, Default, Clone, Copy)]
+struct Average(Option<Duration>);
+
+impl Average {
+    fn add(&mut self, took: Duration) {
+        self.0 = Some(self.0.map_or(took, |average| (average * 7 + took) / 8));
+    }
+
+    fn ms(self) -> Option<f64> {
+        self.0.map(|average| average.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Stage times and throughput of one frame channel
+#[derive(Debug, Default)]
+pub struct Recorder {
+    capture: Average,
+    resize: Average,
+    encode: Average,
+    send: Average,
+    /// When recent frames were sent and their size
+    sent: VecDeque<(Instant, usize)>,
+    frames_sent: u64,
+    bytes_sent: u64,
+    encode_times: Histogram,
+}
+
+impl Recorder {
+    pub fn record(&mut self, stage: Stage, took: Duration) {
+        match stage {
+            Stage::Capture => self.capture.add(took),
+            Stage::Resize => self.resize.add(took),
+            Stage::Encode => {
+                self.encode.add(took);
+                self.encode_times.observe(took);
+            }
+        }
+    }
+
+    /// A frame of `bytes` went out; the send took `took`
+    pub fn sent(&mut self, bytes: usize, took: Duration, now: Instant) {
+        self.send.add(took);
+        self.frames_sent += 1;
+        self.bytes_sent += bytes as u64;
+        self.sent.push_back((now, bytes));
+        while self.sent.front().is_some_and(|&(at, _)| now.duration_since(at) > WINDOW) {
+            self.sent.pop_front();
+        }
+    }
+
+    /// Smoothed send time
+    pub fn send_time(&self) -> Duration {
+        self.send.0.unwrap_or_default()
+    }
+
+    /// Frames and bytes sent since the channel opened
+    pub fn totals(&self) -> (u64, u64) {
+        (self.frames_sent, self.bytes_sent)
+    }
+
+    pub fn encode_times(&self) -> &Histogram {
+        &self.encode_times
+    }
+
+    fn recent(&self, now: Instant) -> impl Iterator<Item = usize> + '_ {
+        self.sent
+            .iter()
+            .filter(move |&&(at, _)| now.duration_since(at) <= WINDOW)
+            .map(|&(_, bytes)| bytes)
+    }
+
+    pub fn snapshot(&self, now: Instant) -> Rates {
+        let window = WINDOW.as_secs_f64();
+        Rates {
+            fps: self.recent(now).count() as f64 / window,
+            bytes_per_sec: self.recent(now).sum::<usize>() as f64 / window,
+            capture_ms: self.capture.ms(),
+            resize_ms: self.resize.ms(),
+            encode_ms: self.encode.ms(),
+            send_ms: self.send.ms().unwrap_or(0.0),
+        }
+    }
+}
+
+/// What a `Recorder` measured
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rates {
+    /// Frames sent per second
+    pub fps: f64,
+    pub bytes_per_sec: f64,
+    /// Average stage times; absent for stages the backend doesn't run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capture_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resize_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encode_ms: Option<f64>,
+    pub send_ms: f64,
+}
+
+/// Statistics of one capture session's stream
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStats {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub rates: Rates,
+    /// JPEG quality frames are encoded with right now (see `backpressure`)
+    pub quality: u8,
+    /// Frames dropped because the consumer was behind
+    pub dropped_frames: u64,
+    /// Frames sent but not acknowledged (0 for consumers that don't ack)
+    pub in_flight: u64,
+    /// Frames and bytes sent since the session started
+    pub frames_sent: u64,
+    pub bytes_sent: u64,
+    pub encode_times: Histogram,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_cover_the_window_only() {
+        let start = Instant::now();
+        let mut recorder = Recorder::default();
+        recorder.sent(1000, Duration::from_millis(2), start);
+        for i in 1..=4 {
+            recorder.sent(500, Duration::from_millis(2), start + WINDOW + Duration::from_millis(i * 100));
+        }
+        let rates = recorder.snapshot(start + WINDOW + Duration::from_millis(500));
+        assert_eq!(rates.fps, 2.0);
+        assert_eq!(rates.bytes_per_sec, 1000.0);
+        assert_eq!(rates.capture_ms, None);
+    }
+
+    #[test]
+    fn stage_times_are_smoothed() {
+        let mut recorder = Recorder::default();
+        recorder.record(Stage::Encode, Duration::from_millis(8));
+        recorder.record(Stage::Encode, Duration::from_millis(16));
+        assert_eq!(recorder.snapshot(Instant::now()).encode_ms, Some(9.0));
+
+        let histogram = recorder.encode_times();
+        assert_eq!(histogram.count, 2);
+        // 8 ms and 16 ms both fall under 25 ms, neither under 5 ms
+        assert_eq!(histogram.counts[2], 0);
+        assert_eq!(histogram.counts[4], 2);
+    }
+}
@@ -0,0 +1,152 @@
+//! Virtual-desktop capture: every monitor composited into one frame.
+//!
+//! `targets::VIRTUAL_DESKTOP` captures each monitor and draws it onto one canvas at its
+//! position in the desktop layout (the x/y `CaptureTarget` reports for monitors), so an
+//! agent sees a multi-monitor setup as a single image. The canvas keeps the densest
+//! monitor's pixel density but is capped at `capture_config::max_width`, so monitors
+//! are scaled straight into place instead of building a full-resolution canvas first.
+//! Gaps left by monitors of different sizes stay black.
+//!
+//! Capture goes through xcap on every platform: ScreenCaptureKit and WGC streams cover
+//! one display each, and Wayland portals hand out one stream per picked monitor, so the
+//! virtual desktop isn't available there.
+
+use crate::capture_config;
+use crate::error::{Error, Result};
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use xcap::Monitor;
+
+/// A rect in desktop coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn of(monitor: &Monitor) -> Option<Self> {
+        Some(Rect {
+            x: monitor.x().ok()?,
+            y: monitor.y().ok()?,
+            width: monitor.width().ok()?,
+            height: monitor.height().ok()?,
+        })
+    }
+}
+
+/// Smallest rect covering all of `rects`, None if there are none
+pub fn bounds(rects: impl IntoIterator<Item = Rect>) -> Option<Rect> {
+    let mut rects = rects.into_iter();
+    let first = rects.next()?;
+    let (mut left, mut top) = (i64::from(first.x), i64::from(first.y));
+    let (mut right, mut bottom) = (left + i64::from(first.width), top + i64::from(first.height));
+    for rect in rects {
+        left = left.min(i64::from(rect.x));
+        top = top.min(i64::from(rect.y));
+        right = right.max(i64::from(rect.x) + i64::from(rect.width));
+        bottom = bottom.max(i64::from(rect.y) + i64::from(rect.height));
+    }
+    Some(Rect {
+        x: left as i32,
+        y: top as i32,
+        width: (right - left) as u32,
+        height: (bottom - top) as u32,
+    })
+}
+
+/// Draw captured monitors onto one canvas no wider than `max_width`
+pub fn compose(parts: &[(Rect, RgbaImage)], max_width: u32) -> Option<RgbaImage> {
+    let bounds = bounds(parts.iter().map(|(rect, _)| *rect))?;
+    // Frame pixels per desktop unit: the densest monitor's, unless that overflows max_width
+    let density = parts
+        .iter()
+        .map(|(rect, image)| f64::from(image.width()) / f64::from(rect.width.max(1)))
+        .fold(0.0, f64::max);
+    let scale = density.min(f64::from(max_width.max(1)) / f64::from(bounds.width.max(1)));
+    let scaled = |length: f64| (length * scale).round() as i64;
+
+    let mut canvas = RgbaImage::from_pixel(
+        scaled(f64::from(bounds.width)).max(1) as u32,
+        scaled(f64::from(bounds.height)).max(1) as u32,
+        Rgba([0, 0, 0, 255]),
+    );
+    for (rect, image) in parts {
+        let x = scaled(f64::from(rect.x) - f64::from(bounds.x));
+        let y = scaled(f64::from(rect.y) - f64::from(bounds.y));
+        let width = scaled(f64::from(rect.width)).max(1) as u32;
+        let height = scaled(f64::from(rect.height)).max(1) as u32;
+        if image.dimensions() == (width, height) {
+            imageops::replace(&mut canvas, image, x, y);
+        } else {
+            let resized = imageops::resize(image, width, height, FilterType::Triangle);
+            imageops::replace(&mut canvas, &resized, x, y);
+        }
+    }
+    Some(canvas)
+}
+
+/// Capture every monitor and composite them at the configured max width
+pub fn capture(monitors: &[Monitor]) -> Result<RgbaImage> {
+    capture_at(monitors, capture_config::max_width())
+}
+
+/// Capture every monitor and composite them no wider than `max_width` (`u32::MAX` keeps
+/// the densest monitor's full resolution)
+pub fn capture_at(monitors: &[Monitor], max_width: u32) -> Result<RgbaImage> {
+    let mut parts = Vec::with_capacity(monitors.len());
+    for monitor in monitors {
+        let Some(rect) = Rect::of(monitor) else { continue };
+        let image = monitor
+            .capture_image()
+            .map_err(|e| Error::Platform(format!("Failed to capture monitor: {}", e)))?;
+        parts.push((rect, image));
+    }
+    compose(&parts, max_width)
+        .ok_or_else(|| Error::Platform("No monitors found".to_string()))
+}
+
+/// Every monitor, for a virtual-desktop source
+pub fn monitors() -> Result<Vec<Monitor>> {
+    let monitors = Monitor::all().map_err(|e| Error::Platform(format!("Failed to get monitors: {}", e)))?;
+    if monitors.is_empty() {
+        return Err(Error::Platform("No monitors found".to_string()));
+    }
+    Ok(monitors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    #[test]
+    fn bounds_cover_offset_layouts() {
+        let layout = [rect(0, 0, 1920, 1080), rect(-1280, 200, 1280, 1024), rect(1920, -300, 1080, 1920)];
+        assert_eq!(bounds(layout), Some(rect(-1280, -300, 4280, 1920)));
+        assert_eq!(bounds([]), None);
+    }
+
+    #[test]
+    fn composes_at_layout_positions_within_max_width() {
+        let left = (rect(0, 0, 200, 100), RgbaImage::from_pixel(200, 100, Rgba([255, 0, 0, 255])));
+        // HiDPI monitor below-right: twice the pixels per desktop unit
+        let right = (rect(200, 100, 100, 50), RgbaImage::from_pixel(200, 100, Rgba([0, 0, 255, 255])));
+
+        let full = compose(&[left.clone(), right.clone()], 10_000).unwrap();
+        assert_eq!(full.dimensions(), (600, 300));
+        assert_eq!(full.get_pixel(10, 10), &Rgba([255, 0, 0, 255]));
+        assert_eq!(full.get_pixel(500, 250), &Rgba([0, 0, 255, 255]));
+        // Gap under the left monitor
+        assert_eq!(full.get_pixel(10, 250), &Rgba([0, 0, 0, 255]));
+
+        let capped = compose(&[left, right], 150).unwrap();
+        assert_eq!(capped.dimensions(), (150, 75));
+        assert_eq!(capped.get_pixel(120, 60), &Rgba([0, 0, 255, 255]));
+    }
+}
@@ -1,17 +1,26 @@
 //! Target enumeration for screen capture
 //! Provides cross-platform window and monitor discovery using xcap
 
+use crate::capture_config::CaptureBackend;
 use crate::error::{Error, Result};
-use base64::{engine::general_purpose::STANDARD, Engine};
-use image::codecs::jpeg::JpegEncoder;
-use image::imageops::FilterType;
+use crate::exclusions;
+use crate::geometry::SourceMetrics;
+use crate::stitch;
+use crate::thumbnails;
 use serde::{Deserialize, Serialize};
-use std::io::Cursor;
 use xcap::{Monitor, Window};
 
-/// Thumbnail settings
-const THUMBNAIL_MAX_WIDTH: u32 = 320;
-const THUMBNAIL_JPEG_QUALITY: u8 = 60;
+/// Target id for follow-active-window mode: the session captures whichever window has
+/// focus (see `follow`)
+pub const ACTIVE_WINDOW: &str = "active-window";
+
+/// Prefix of app target ids (`app:{name}`): the session captures that app's focused or
+/// largest window and follows it across window recreation (see `follow`)
+pub const APP_PREFIX: &str = "app:";
+
+/// Target id of the virtual desktop: every monitor composited into one frame (see
+/// `stitch`)
+pub const VIRTUAL_DESKTOP: &str = "virtual-desktop";
 
 /// Kind of capture target
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -19,13 +28,16 @@ const THUMBNAIL_JPEG_QUALITY: u8 = 60;
 pub enum TargetKind {
     Monitor,
     Window,
+    #[serde(rename = "virtual-desktop")]
+    VirtualDesktop,
 }
 
 /// A capture target (monitor or window) with metadata and optional thumbnail
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CaptureTarget {
-    /// Unique identifier: "monitor:{id}" or "window:{id}"
+    /// Unique identifier: "monitor:{id}", "window:{id}" or `VIRTUAL_DESKTOP` (sessions
+    /// also accept `ACTIVE_WINDOW` and "app:{name}")
     pub id: String,
     /// Type of target
     pub kind: TargetKind,
@@ -34,7 +46,7 @@ pub struct CaptureTarget {
     /// For windows: the application name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub app_name: Option<String>,
-    /// Base64-encoded JPEG thumbnail
+    /// Base64-encoded JPEG thumbnail, from the cache (see `thumbnails`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail: Option<String>,
     /// Width in pixels
@@ -47,6 +59,57 @@ pub struct CaptureTarget {
     pub x: i32,
     /// Y position (for monitors)
     pub y: i32,
+    /// Backends able to capture this target, where the platform has a choice (Windows)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<CaptureBackend>,
+    /// Source rect with the display's scale factor and physical/logical size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceMetrics>,
+}
+
+/// Lightweight window metadata for presence/focus checks (no capture involved)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+    pub pid: u32,
+    pub is_focused: bool,
+}
+
+/// List every window the platform reports, including small and untitled ones that
+/// `get_all_targets` filters out (share toolbars, indicators, ...).
+pub fn list_windows() -> Result<Vec<WindowInfo>> {
+    let windows = Window::all().map_err(|e| Error::Platform(format!("Failed to enumerate windows: {}", e)))?;
+
+    Ok(windows
+        .into_iter()
+        .map(|window| WindowInfo {
+            id: window.id().unwrap_or(0),
+            title: window.title().unwrap_or_default(),
+            app_name: window.app_name().unwrap_or_default(),
+            pid: window.pid().unwrap_or(0),
+            is_focused: window.is_focused().unwrap_or(false),
+        })
+        .collect())
+}
+
+fn source_metrics(x: i32, y: i32, width: u32, height: u32, scale: f64) -> SourceMetrics {
+    SourceMetrics::new(f64::from(x), f64::from(y), f64::from(width), f64::from(height), scale)
+}
+
+/// Scale of the display holding the center of a window's rect (1.0 if none does)
+fn display_scale(displays: &[(i32, i32, u32, u32, f64)], x: i32, y: i32, width: u32, height: u32) -> f64 {
+    let cx = i64::from(x) + i64::from(width / 2);
+    let cy = i64::from(y) + i64::from(height / 2);
+    displays
+        .iter()
+        .find(|&&(dx, dy, dw, dh, _)| {
+            (i64::from(dx)..i64::from(dx) + i64::from(dw)).contains(&cx)
+                && (i64::from(dy)..i64::from(dy) + i64::from(dh)).contains(&cy)
+        })
+        .map_or(1.0, |display| display.4)
 }
 
 /// Get all available capture targets (monitors and windows)
@@ -55,6 +118,8 @@ pub fn get_all_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget>> {
 
     // Get monitors
     let monitors = Monitor::all().map_err(|e| Error::Platform(format!("Failed to enumerate monitors: {}", e)))?;
+    // Monitor rects and scales, for finding the display a window is on
+    let mut displays = Vec::new();
 
     for monitor in monitors {
         let id = format!("monitor:{}", monitor.id().unwrap_or(0));
@@ -64,27 +129,47 @@ pub fn get_all_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget>> {
         let x = monitor.x().unwrap_or(0);
         let y = monitor.y().unwrap_or(0);
         let is_primary = monitor.is_primary().unwrap_or(false);
-
-        let thumbnail = if include_thumbnails {
-            capture_monitor_thumbnail(&monitor).ok()
-        } else {
-            None
-        };
+        let scale = monitor.scale_factor().map(f64::from).unwrap_or(1.0);
+        displays.push((x, y, width, height, scale));
 
         targets.push(CaptureTarget {
             id,
             kind: TargetKind::Monitor,
             name,
             app_name: None,
-            thumbnail,
+            thumbnail: None,
             width,
             height,
             is_primary,
             x,
             y,
+            backends: Vec::new(),
+            source: Some(source_metrics(x, y, width, height, scale)),
         });
     }
 
+    // With more than one monitor, offer them all as one stitched frame
+    if displays.len() > 1 {
+        let rects = displays.iter().map(|&(x, y, width, height, _)| stitch::Rect { x, y, width, height });
+        if let Some(bounds) = stitch::bounds(rects) {
+            let scale = displays.iter().map(|display| display.4).fold(1.0, f64::max);
+            targets.push(CaptureTarget {
+                id: VIRTUAL_DESKTOP.to_string(),
+                kind: TargetKind::VirtualDesktop,
+                name: format!("All Monitors ({})", displays.len()),
+                app_name: None,
+                thumbnail: None,
+                width: bounds.width,
+                height: bounds.height,
+                is_primary: false,
+                x: bounds.x,
+                y: bounds.y,
+                backends: Vec::new(),
+                source: Some(source_metrics(bounds.x, bounds.y, bounds.width, bounds.height, scale)),
+            });
+        }
+    }
+
     // Get windows
     let windows = Window::all().map_err(|e| Error::Platform(format!("Failed to enumerate windows: {}", e)))?;
 
@@ -108,15 +193,14 @@ pub fn get_all_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget>> {
             continue;
         }
 
+        // Skip Observer's own windows (capturing them feeds the overlay back into frames)
+        if exclusions::is_own_window(window.pid().unwrap_or(0)) {
+            continue;
+        }
+
         let id = format!("window:{}", window.id().unwrap_or(0));
         let app_name = window.app_name().unwrap_or_default();
 
-        let thumbnail = if include_thumbnails {
-            capture_window_thumbnail(&window).ok()
-        } else {
-            None
-        };
-
         let x = window.x().unwrap_or(0);
         let y = window.y().unwrap_or(0);
 
@@ -125,20 +209,26 @@ pub fn get_all_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget>> {
             kind: TargetKind::Window,
             name: title,
             app_name: Some(app_name),
-            thumbnail,
+            thumbnail: None,
             width,
             height,
             is_primary: false,
             x,
             y,
+            backends: Vec::new(),
+            source: Some(source_metrics(x, y, width, height, display_scale(&displays, x, y, width, height))),
         });
     }
 
-    // Sort: monitors first (primary first), then windows sorted by app name
+    // Sort: monitors first (primary first), then the virtual desktop, then windows
+    // sorted by app name
+    let rank = |kind: &TargetKind| match kind {
+        TargetKind::Monitor => 0,
+        TargetKind::VirtualDesktop => 1,
+        TargetKind::Window => 2,
+    };
     targets.sort_by(|a, b| {
         match (&a.kind, &b.kind) {
-            (TargetKind::Monitor, TargetKind::Window) => std::cmp::Ordering::Less,
-            (TargetKind::Window, TargetKind::Monitor) => std::cmp::Ordering::Greater,
             (TargetKind::Monitor, TargetKind::Monitor) => {
                 // Primary monitor first
                 b.is_primary.cmp(&a.is_primary)
@@ -152,14 +242,27 @@ pub fn get_all_targets(include_thumbnails: bool) -> Result<Vec<CaptureTarget>> {
                     _ => a.name.cmp(&b.name)
                 }
             }
+            (a_kind, b_kind) => rank(a_kind).cmp(&rank(b_kind)),
         }
     });
 
+    // Cached thumbnails only; missing ones arrive later as a `thumbnails` event
+    if include_thumbnails {
+        let ids: Vec<String> = targets.iter().map(|t| t.id.clone()).collect();
+        let mut cached = thumbnails::lookup(&ids);
+        for target in &mut targets {
+            target.thumbnail = cached.remove(&target.id);
+        }
+    }
+
     Ok(targets)
 }
 
 /// Parse a target ID into its components
 pub fn parse_target_id(target_id: &str) -> Result<(TargetKind, u32)> {
+    if target_id == VIRTUAL_DESKTOP {
+        return Ok((TargetKind::VirtualDesktop, 0));
+    }
     let parts: Vec<&str> = target_id.splitn(2, ':').collect();
     if parts.len() != 2 {
         return Err(Error::Platform(format!("Invalid target ID format: {}", target_id)));
@@ -177,55 +280,11 @@ pub fn parse_target_id(target_id: &str) -> Result<(TargetKind, u32)> {
     Ok((kind, id))
 }
 
-/// Capture a thumbnail of a monitor
-fn capture_monitor_thumbnail(monitor: &Monitor) -> Result<String> {
-    let image = monitor.capture_image()
-        .map_err(|e| Error::Platform(format!("Failed to capture monitor: {}", e)))?;
-
-    encode_thumbnail(&image)
-}
-
-/// Capture a thumbnail of a window
-fn capture_window_thumbnail(window: &Window) -> Result<String> {
-    let image = window.capture_image()
-        .map_err(|e| Error::Platform(format!("Failed to capture window: {}", e)))?;
-
-    encode_thumbnail(&image)
-}
-
-/// Encode an image as a base64 JPEG thumbnail
-fn encode_thumbnail(image: &image::RgbaImage) -> Result<String> {
-    let width = image.width();
-    let height = image.height();
-
-    // Downscale if needed
-    let resized = if width > THUMBNAIL_MAX_WIDTH {
-        let scale = THUMBNAIL_MAX_WIDTH as f32 / width as f32;
-        let new_height = (height as f32 * scale) as u32;
-        image::imageops::resize(image, THUMBNAIL_MAX_WIDTH, new_height, FilterType::Nearest)
-    } else {
-        image.clone()
-    };
-
-    let final_width = resized.width();
-    let final_height = resized.height();
-
-    // Convert RGBA to RGB directly (avoid DynamicImage overhead)
-    let rgba_bytes = resized.as_raw();
-    let mut rgb_bytes = Vec::with_capacity((final_width * final_height * 3) as usize);
-    for chunk in rgba_bytes.chunks_exact(4) {
-        rgb_bytes.push(chunk[0]); // R
-        rgb_bytes.push(chunk[1]); // G
-        rgb_bytes.push(chunk[2]); // B
+/// Target id for a parsed target; the inverse of `parse_target_id`
+pub fn format_target_id(kind: &TargetKind, id: u32) -> String {
+    match kind {
+        TargetKind::Monitor => format!("monitor:{}", id),
+        TargetKind::Window => format!("window:{}", id),
+        TargetKind::VirtualDesktop => VIRTUAL_DESKTOP.to_string(),
     }
-
-    // Encode as JPEG
-    let mut jpeg_buffer = Cursor::new(Vec::new());
-    let mut encoder = JpegEncoder::new_with_quality(&mut jpeg_buffer, THUMBNAIL_JPEG_QUALITY);
-
-    encoder.encode(&rgb_bytes, final_width, final_height, image::ExtendedColorType::Rgb8)
-        .map_err(|e| Error::Platform(format!("Failed to encode thumbnail: {}", e)))?;
-
-    let jpeg_bytes = jpeg_buffer.into_inner();
-    Ok(STANDARD.encode(&jpeg_bytes))
 }
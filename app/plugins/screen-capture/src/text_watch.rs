@@ -0,0 +1,233 @@
+//! Text watches: OCR a target at a low rate and report the lines that changed.
+//!
+//! Each watch reads its target (or the default stream's next frame) every
+//! `interval_secs` with `ocr`, compares the lines with the previous read and emits a
+//! `screen-capture://text-changed` event listing the lines that appeared and the ones
+//! that went away. Agents can trigger on "a new log line appeared" this way without
+//! sending frames to a model.
+//!
+//! Lines are compared as a multiset, ignoring order and whitespace differences, so
+//! scrolling a log shows up as the new lines added and the old ones removed rather than
+//! every line moving. Lines OCR'd below `min_confidence` are dropped first: flickering
+//! half-read lines would otherwise come and go on every read. The first read only sets
+//! the baseline. Reads that fail (capture paused, no stream frame, ...) are skipped and
+//! don't reset it.
+//!
+//! Every watch runs on its own thread until stopped. Needs the `ocr` feature.
+
+use crate::error::{Error, Result};
+use crate::events;
+use crate::ocr::{self, FrameText, OcrOptions};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How often a waiting watch checks whether it was stopped
+const STOP_POLL: Duration = Duration::from_millis(250);
+
+fn default_interval() -> u32 {
+    5
+}
+
+fn default_min_confidence() -> f32 {
+    60.0
+}
+
+/// What a watch reads and how often
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextWatchConfig {
+    pub id: String,
+    /// None = primary monitor
+    #[serde(default)]
+    pub target_id: Option<String>,
+    /// Read the default stream's frames instead of capturing `target_id`
+    #[serde(default)]
+    pub latest_frame: bool,
+    /// Seconds between reads (at least 1)
+    #[serde(default = "default_interval")]
+    pub interval_secs: u32,
+    /// Tesseract codes; empty detects the script
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Lines read with less confidence (0–100) are ignored
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f32,
+}
+
+/// Payload of `events::TEXT_CHANGED`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextChanged {
+    pub watch_id: String,
+    pub target_id: Option<String>,
+    /// Capture time of the read, seconds since the epoch
+    pub timestamp: f64,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+struct Watch {
+    config: TextWatchConfig,
+    generation: u64,
+}
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+static WATCHES: Mutex<Option<HashMap<String, Watch>>> = Mutex::new(None);
+
+/// Start a watch, replacing one with the same id
+pub fn start(mut config: TextWatchConfig) -> Result<()> {
+    config.id = config.id.trim().to_string();
+    if config.id.is_empty() {
+        return Err(Error::Ocr("Text watches need an id".to_string()));
+    }
+    config.interval_secs = config.interval_secs.max(1);
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::SeqCst);
+    log::info!(
+        "[ScreenCapture] Text watch '{}' on {} every {}s",
+        config.id,
+        if config.latest_frame { "stream frames" } else { config.target_id.as_deref().unwrap_or("primary monitor") },
+        config.interval_secs
+    );
+    WATCHES
+        .lock()
+        .get_or_insert_with(HashMap::new)
+        .insert(config.id.clone(), Watch { config: config.clone(), generation });
+    std::thread::spawn(move || run(generation, config));
+    Ok(())
+}
+
+/// Stop a watch; false if none had that id
+pub fn stop(id: &str) -> bool {
+    let stopped = WATCHES.lock().as_mut().and_then(|watches| watches.remove(id)).is_some();
+    if stopped {
+        log::info!("[ScreenCapture] Text watch '{}' stopped", id);
+    }
+    stopped
+}
+
+/// Stop every watch
+pub fn stop_all() {
+    WATCHES.lock().take();
+}
+
+/// Running watches
+pub fn list() -> Vec<TextWatchConfig> {
+    let watches = WATCHES.lock();
+    let mut list: Vec<TextWatchConfig> =
+        watches.iter().flat_map(|watches| watches.values()).map(|watch| watch.config.clone()).collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
+
+fn is_current(id: &str, generation: u64) -> bool {
+    WATCHES.lock().as_ref().and_then(|watches| watches.get(id)).is_some_and(|watch| watch.generation == generation)
+}
+
+fn run(generation: u64, config: TextWatchConfig) {
+    let interval = Duration::from_secs(u64::from(config.interval_secs));
+    let options = OcrOptions { languages: config.languages.clone() };
+    let mut previous: Option<Vec<String>> = None;
+    while is_current(&config.id, generation) {
+        let started = Instant::now();
+        let read = if config.latest_frame {
+            tauri::async_runtime::block_on(ocr::recognize_latest_frame(&options))
+        } else {
+            ocr::recognize_target(config.target_id.as_deref(), &options)
+        };
+        match read {
+            Ok(text) => {
+                let lines = lines(&text, config.min_confidence);
+                if let Some(previous) = &previous {
+                    let (added, removed) = diff(previous, &lines);
+                    if (!added.is_empty() || !removed.is_empty()) && is_current(&config.id, generation) {
+                        events::emit(events::TEXT_CHANGED, TextChanged {
+                            watch_id: config.id.clone(),
+                            target_id: text.target_id.clone(),
+                            timestamp: text.timestamp,
+                            added,
+                            removed,
+                        });
+                    }
+                }
+                previous = Some(lines);
+            }
+            Err(e) => log::debug!("[ScreenCapture] Text watch '{}' skipped a read: {}", config.id, e),
+        }
+        while is_current(&config.id, generation) && started.elapsed() < interval {
+            std::thread::sleep(STOP_POLL.min(interval.saturating_sub(started.elapsed())));
+        }
+    }
+}
+
+/// Confident lines with whitespace collapsed
+fn lines(text: &FrameText, min_confidence: f32) -> Vec<String> {
+    text.result
+        .lines
+        .iter()
+        .filter(|line| line.confidence >= min_confidence)
+        .map(|line| line.text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Lines of `current` missing from `previous` and lines of `previous` missing from
+/// `current`, counting repeats, each in reading order. Of repeated lines, the last ones
+/// count as added (new log lines come in at the bottom) and the first as removed.
+fn diff(previous: &[String], current: &[String]) -> (Vec<String>, Vec<String>) {
+    // Positive counts were removed, negative ones added
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    for line in previous {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in current {
+        *counts.entry(line).or_default() -= 1;
+    }
+    let mut added: Vec<String> = current
+        .iter()
+        .rev()
+        .filter(|line| {
+            let count = counts.get_mut(line.as_str()).expect("counted above");
+            *count < 0 && {
+                *count += 1;
+                true
+            }
+        })
+        .cloned()
+        .collect();
+    added.reverse();
+    let removed = previous
+        .iter()
+        .filter(|line| {
+            let count = counts.get_mut(line.as_str()).expect("counted above");
+            *count > 0 && {
+                *count -= 1;
+                true
+            }
+        })
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn diffs_lines_as_a_multiset() {
+        let previous = strings(&["[12:00] start", "[12:01] ok", "[12:01] ok"]);
+        let current = strings(&["[12:01] ok", "[12:01] ok", "[12:02] error", "[12:01] ok"]);
+        let (added, removed) = diff(&previous, &current);
+        assert_eq!(added, strings(&["[12:02] error", "[12:01] ok"]));
+        assert_eq!(removed, strings(&["[12:00] start"]));
+
+        assert_eq!(diff(&current, &current), (vec![], vec![]));
+    }
+}
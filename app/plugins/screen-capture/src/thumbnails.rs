@@ -0,0 +1,282 @@
+//! Target-list thumbnails, captured off the calling thread and cached.
+//!
+//! Each thumbnail is a full capture of a monitor or window, so building them inline made
+//! `get_capture_targets_cmd(include_thumbnails)` take seconds with many windows open.
+//! The target list now carries whatever is cached (up to `TTL` old, or older while a
+//! refresh is running) and missing or expired thumbnails are captured by up to
+//! `WORKERS` background threads. Each thread emits its finished batch as
+//! `screen-capture://thumbnails` so the picker can fill them in.
+//!
+//! While the picker is open it can instead `start_preview` a channel that gets every
+//! target's thumbnail re-captured once per `PREVIEW_INTERVAL`, until `stop_preview`.
+//!
+//! Thumbnails are screenshots, so they get the screenshot guards: none are captured while
+//! capture is paused or a password field has focus, denied apps show the redaction
+//! placeholder and privacy regions are hidden. Pausing also drops the cached ones.
+
+use crate::events;
+use crate::pause;
+use crate::screenshot;
+use crate::stitch;
+use crate::targets::{self, TargetKind};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use xcap::{Monitor, Window};
+
+/// How long a thumbnail is served before it is captured again
+pub const TTL: Duration = Duration::from_secs(10);
+/// Threads capturing one refresh
+const WORKERS: usize = 4;
+/// How often a live preview re-captures every target
+pub const PREVIEW_INTERVAL: Duration = Duration::from_secs(1);
+
+const THUMBNAIL_MAX_WIDTH: u32 = 320;
+const THUMBNAIL_JPEG_QUALITY: u8 = 60;
+
+/// A freshly captured thumbnail (`events::THUMBNAILS` payload entries)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnail {
+    pub target_id: String,
+    /// Base64-encoded JPEG
+    pub thumbnail: String,
+}
+
+struct Entry {
+    data: String,
+    captured_at: Instant,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    /// Ids a worker is capturing right now
+    pending: HashSet<String>,
+}
+
+impl Cache {
+    fn get(&self, target_id: &str) -> Option<&str> {
+        self.entries.get(target_id).map(|e| e.data.as_str())
+    }
+
+    /// Ids that are missing or expired and not already being captured; they are marked
+    /// pending until `store` is called for them
+    fn claim_stale(&mut self, target_ids: &[String], now: Instant) -> Vec<String> {
+        let stale: Vec<String> = target_ids
+            .iter()
+            .filter(|id| !self.pending.contains(*id))
+            .filter(|id| {
+                self.entries
+                    .get(*id)
+                    .is_none_or(|e| now.duration_since(e.captured_at) >= TTL)
+            })
+            .cloned()
+            .collect();
+        self.pending.extend(stale.iter().cloned());
+        stale
+    }
+
+    /// Finish a capture; a failed one (`None`) keeps the previous thumbnail
+    fn store(&mut self, target_id: &str, data: Option<String>, now: Instant) {
+        self.pending.remove(target_id);
+        if let Some(data) = data {
+            self.entries.insert(target_id.to_string(), Entry { data, captured_at: now });
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<Cache> {
+    static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(Cache::default()))
+}
+
+/// Cached thumbnails for `target_ids`, scheduling captures for missing or expired ones
+pub fn lookup(target_ids: &[String]) -> HashMap<String, String> {
+    if pause::is_paused() {
+        cache().lock().entries.clear();
+        return HashMap::new();
+    }
+    let (cached, stale) = {
+        let mut cache = cache().lock();
+        let cached = target_ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), cache.get(id)?.to_string())))
+            .collect();
+        (cached, cache.claim_stale(target_ids, Instant::now()))
+    };
+    if !stale.is_empty() {
+        log::debug!("[ScreenCapture] Refreshing {} thumbnail(s)", stale.len());
+        let batch_size = stale.len().div_ceil(WORKERS);
+        for batch in stale.chunks(batch_size) {
+            let batch = batch.to_vec();
+            std::thread::spawn(move || refresh(batch));
+        }
+    }
+    cached
+}
+
+/// Capture and cache one batch, then emit what succeeded
+fn refresh(target_ids: Vec<String>) {
+    let done = capture_batch(target_ids);
+    if !done.is_empty() {
+        events::emit(events::THUMBNAILS, &done);
+    }
+}
+
+/// Capture and cache `target_ids`, returning the thumbnails that succeeded
+fn capture_batch(target_ids: Vec<String>) -> Vec<Thumbnail> {
+    // Enumerate each kind at most once per batch
+    let mut monitors: Option<Vec<Monitor>> = None;
+    let mut windows: Option<Vec<Window>> = None;
+    let mut done = Vec::new();
+
+    for target_id in target_ids {
+        let allowed = screenshot::check_allowed().is_ok();
+        let data = match targets::parse_target_id(&target_id).ok().filter(|_| allowed) {
+            Some((TargetKind::Monitor, id)) => monitors
+                .get_or_insert_with(|| Monitor::all().unwrap_or_default())
+                .iter()
+                .find(|m| m.id().ok() == Some(id))
+                .and_then(|m| m.capture_image().ok()),
+            Some((TargetKind::Window, id)) => windows
+                .get_or_insert_with(|| Window::all().unwrap_or_default())
+                .iter()
+                .find(|w| w.id().ok() == Some(id))
+                .and_then(|w| w.capture_image().ok()),
+            Some((TargetKind::VirtualDesktop, _)) => {
+                stitch::capture(monitors.get_or_insert_with(|| Monitor::all().unwrap_or_default())).ok()
+            }
+            None => None,
+        }
+        .and_then(|mut image| {
+            screenshot::protect(Some(&target_id), &mut image);
+            encode(&image)
+        });
+
+        cache().lock().store(&target_id, data.clone(), Instant::now());
+        if let Some(thumbnail) = data {
+            done.push(Thumbnail { target_id, thumbnail });
+        }
+    }
+    done
+}
+
+/// Generation of the running preview; a preview thread exits once this moves on
+static PREVIEW: AtomicU64 = AtomicU64::new(0);
+
+/// Push fresh thumbnails for every target to `on_update` once per `PREVIEW_INTERVAL`
+/// until `stop_preview` (or the channel closes). Replaces a running preview.
+pub fn start_preview(on_update: Channel<Vec<Thumbnail>>) {
+    let generation = PREVIEW.fetch_add(1, Ordering::SeqCst) + 1;
+    log::info!("[ScreenCapture] Target preview started");
+
+    std::thread::spawn(move || {
+        while PREVIEW.load(Ordering::SeqCst) == generation {
+            let started = Instant::now();
+            let ids: Vec<String> = match targets::get_all_targets(false) {
+                Ok(list) => list.into_iter().map(|t| t.id).collect(),
+                Err(e) => {
+                    log::warn!("[ScreenCapture] Target preview could not list targets: {}", e);
+                    Vec::new()
+                }
+            };
+
+            let batch_size = ids.len().div_ceil(WORKERS).max(1);
+            let updates: Vec<Thumbnail> = std::thread::scope(|scope| {
+                let workers: Vec<_> = ids
+                    .chunks(batch_size)
+                    .map(|batch| scope.spawn(move || capture_batch(batch.to_vec())))
+                    .collect();
+                workers.into_iter().flat_map(|w| w.join().unwrap_or_default()).collect()
+            });
+
+            // Stopped while capturing: don't push a stale batch
+            if PREVIEW.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            if on_update.send(updates).is_err() {
+                break;
+            }
+            if let Some(rest) = PREVIEW_INTERVAL.checked_sub(started.elapsed()) {
+                std::thread::sleep(rest);
+            }
+        }
+        log::info!("[ScreenCapture] Target preview stopped");
+    });
+}
+
+/// Stop the running preview, if any
+pub fn stop_preview() {
+    PREVIEW.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Downscale and encode as a base64 JPEG
+fn encode(image: &image::RgbaImage) -> Option<String> {
+    let width = image.width();
+    let height = image.height();
+
+    // Downscale if needed
+    let resized = if width > THUMBNAIL_MAX_WIDTH {
+        let scale = THUMBNAIL_MAX_WIDTH as f32 / width as f32;
+        let new_height = ((height as f32 * scale) as u32).max(1);
+        image::imageops::resize(image, THUMBNAIL_MAX_WIDTH, new_height, FilterType::Nearest)
+    } else {
+        image.clone()
+    };
+
+    // Convert RGBA to RGB directly (avoid DynamicImage overhead)
+    let rgb_bytes: Vec<u8> = resized.as_raw().chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+
+    let mut jpeg_buffer = Cursor::new(Vec::new());
+    let mut encoder = JpegEncoder::new_with_quality(&mut jpeg_buffer, THUMBNAIL_JPEG_QUALITY);
+    if let Err(e) = encoder.encode(&rgb_bytes, resized.width(), resized.height(), image::ExtendedColorType::Rgb8) {
+        log::warn!("[ScreenCapture] Failed to encode thumbnail: {}", e);
+        return None;
+    }
+    Some(STANDARD.encode(jpeg_buffer.into_inner()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn claims_missing_and_expired_once() {
+        let now = Instant::now();
+        let mut cache = Cache::default();
+        let wanted = ids(&["window:1", "window:2"]);
+
+        assert_eq!(cache.claim_stale(&wanted, now), wanted);
+        // Already being captured
+        assert!(cache.claim_stale(&wanted, now).is_empty());
+
+        cache.store("window:1", Some("a".into()), now);
+        cache.store("window:2", None, now);
+        assert_eq!(cache.get("window:1"), Some("a"));
+        assert_eq!(cache.claim_stale(&wanted, now), ids(&["window:2"]));
+        assert_eq!(cache.claim_stale(&wanted, now + TTL), ids(&["window:1"]));
+    }
+
+    #[test]
+    fn failed_refresh_keeps_old_thumbnail() {
+        let now = Instant::now();
+        let mut cache = Cache::default();
+        cache.store("monitor:1", Some("old".into()), now);
+        cache.claim_stale(&ids(&["monitor:1"]), now + TTL);
+        cache.store("monitor:1", None, now + TTL);
+        assert_eq!(cache.get("monitor:1"), Some("old"));
+    }
+}
@@ -0,0 +1,361 @@
+//! Watch rules: capture sessions that start and stop with matching windows.
+//!
+//! A rule is a regex over window titles and/or app names (e.g. `Zoom Meeting.*`). While
+//! a visible window matches, the rule's session streams it to the rule's channel; when
+//! the window closes and no other one matches, the session stops. If the captured
+//! window stops matching but another does, the session hot-switches to that one.
+//! Every start, switch and stop is emitted as a `screen-capture://watch-rule` event so
+//! agents can trigger on them.
+//!
+//! One watcher thread polls the window list every `POLL_INTERVAL` while any rule is
+//! registered. Observer's own windows never match. Each rule runs under its own session
+//! id (`watch:{rule id}`) and owns it: a session that ends while its window still
+//! matches is started again, so remove the rule to stop capturing. Rules are idle while
+//! the pause gate is held (`pause`), and pick up again after. Not available on
+//! Wayland, where clients can't see other apps' windows.
+
+use crate::desktop;
+use crate::error::{Error, Result};
+use crate::events;
+use crate::pause;
+use crate::wire::FrameSink;
+use parking_lot::Mutex;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use xcap::Window;
+
+/// How often windows are checked against the rules
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Prefix of the session ids rules capture under
+pub const SESSION_PREFIX: &str = "watch:";
+
+/// Which window property a rule's pattern is matched against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchOn {
+    Title,
+    App,
+    /// Title or app name
+    #[default]
+    Any,
+}
+
+/// A window pattern to capture automatically
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchRule {
+    pub id: String,
+    /// Regex, matched anywhere in the property (anchor it with `^…$` for a full match)
+    pub pattern: String,
+    #[serde(default)]
+    pub match_on: MatchOn,
+    /// Match regardless of case (default true)
+    #[serde(default = "default_true")]
+    pub case_insensitive: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A rule as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchRuleStatus {
+    #[serde(flatten)]
+    pub rule: WatchRule,
+    pub session_id: String,
+    /// Window being captured (`window:<id>`), None while nothing matches
+    pub target_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchEventKind {
+    /// A matching window appeared; the session started
+    Matched,
+    /// The captured window went away and another matching one took over
+    Switched,
+    /// No window matches anymore; the session stopped
+    Unmatched,
+}
+
+/// Payload of `events::WATCH_RULE`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchEvent {
+    pub kind: WatchEventKind,
+    pub rule_id: String,
+    pub session_id: String,
+    /// Window now captured (None for `unmatched`)
+    pub target_id: Option<String>,
+    pub title: Option<String>,
+    pub app_name: Option<String>,
+}
+
+/// Window facts rules are matched against
+#[derive(Debug, Clone)]
+struct Candidate {
+    id: u32,
+    pid: u32,
+    title: String,
+    app_name: String,
+    focused: bool,
+    minimized: bool,
+    area: u64,
+}
+
+struct ActiveRule {
+    rule: WatchRule,
+    regex: Regex,
+    sink: FrameSink,
+    /// Window the rule's session shows
+    current: Option<u32>,
+}
+
+impl ActiveRule {
+    fn session_id(&self) -> String {
+        session_id(&self.rule.id)
+    }
+
+    fn matches(&self, window: &Candidate) -> bool {
+        let title = || self.regex.is_match(&window.title);
+        let app = || self.regex.is_match(&window.app_name);
+        match self.rule.match_on {
+            MatchOn::Title => title(),
+            MatchOn::App => app(),
+            MatchOn::Any => title() || app(),
+        }
+    }
+
+    /// Window to capture: the current one while it still matches, else the focused
+    /// match, else the largest
+    fn pick<'a>(&self, windows: &'a [Candidate], own_pid: u32) -> Option<&'a Candidate> {
+        let mut matching = windows
+            .iter()
+            .filter(|w| w.pid != own_pid && !w.minimized && w.area > 0 && self.matches(w));
+        if let Some(current) = matching.clone().find(|w| Some(w.id) == self.current) {
+            return Some(current);
+        }
+        matching.clone().find(|w| w.focused).or_else(|| matching.max_by_key(|w| w.area))
+    }
+}
+
+static RULES: Mutex<Vec<ActiveRule>> = Mutex::new(Vec::new());
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Session id the rule `rule_id` captures under
+pub fn session_id(rule_id: &str) -> String {
+    format!("{}{}", SESSION_PREFIX, rule_id)
+}
+
+fn compile(rule: &WatchRule) -> Result<Regex> {
+    RegexBuilder::new(&rule.pattern)
+        .case_insensitive(rule.case_insensitive)
+        .build()
+        .map_err(|e| Error::Platform(format!("Invalid watch pattern {:?}: {}", rule.pattern, e)))
+}
+
+/// Register `rule`, replacing one with the same id; matching windows stream to `sink`
+pub fn add(mut rule: WatchRule, sink: FrameSink) -> Result<WatchRuleStatus> {
+    #[cfg(target_os = "linux")]
+    if crate::desktop_wayland::is_wayland() {
+        return Err(Error::Platform("Watch rules are not available on Wayland".to_string()));
+    }
+    rule.id = rule.id.trim().to_string();
+    if rule.id.is_empty() {
+        return Err(Error::Platform("Watch rules need an id".to_string()));
+    }
+    let regex = compile(&rule)?;
+    remove(&rule.id);
+
+    let status = WatchRuleStatus { session_id: session_id(&rule.id), target_id: None, rule: rule.clone() };
+    log::info!("[ScreenCapture] Watch rule {} added: {:?}", rule.id, rule.pattern);
+    RULES.lock().push(ActiveRule { rule, regex, sink, current: None });
+    ensure_watcher();
+    Ok(status)
+}
+
+/// Remove rule `id` and stop its session; false if there was none
+pub fn remove(id: &str) -> bool {
+    let removed = {
+        let mut rules = RULES.lock();
+        let index = rules.iter().position(|r| r.rule.id == id);
+        index.map(|index| rules.remove(index))
+    };
+    let Some(removed) = removed else {
+        return false;
+    };
+    if removed.current.is_some() {
+        let _ = desktop::stop_capture_session(&removed.session_id());
+    }
+    log::info!("[ScreenCapture] Watch rule {} removed", id);
+    true
+}
+
+/// Registered rules and what they capture
+pub fn list() -> Vec<WatchRuleStatus> {
+    RULES
+        .lock()
+        .iter()
+        .map(|active| WatchRuleStatus {
+            rule: active.rule.clone(),
+            session_id: active.session_id(),
+            target_id: active.current.map(|id| format!("window:{}", id)),
+        })
+        .collect()
+}
+
+/// Start the watcher thread unless it is running; it exits once no rules are left
+fn ensure_watcher() {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        log::info!("[ScreenCapture] Watch rule watcher started");
+        loop {
+            if RULES.lock().is_empty() {
+                WATCHING.store(false, Ordering::SeqCst);
+                // A rule added between the check and the store would have found the
+                // flag still set; pick it up instead of leaving it unwatched
+                if RULES.lock().is_empty() || WATCHING.swap(true, Ordering::SeqCst) {
+                    break;
+                }
+            }
+            // Nothing starts while capture is held (incognito, screen sharing)
+            if !pause::is_paused() {
+                if let Some(windows) = candidates() {
+                    apply(&windows);
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        log::info!("[ScreenCapture] Watch rule watcher stopped");
+    });
+}
+
+fn candidates() -> Option<Vec<Candidate>> {
+    let windows = Window::all()
+        .map_err(|e| log::warn!("[ScreenCapture] Watch rules could not list windows: {}", e))
+        .ok()?;
+    Some(
+        windows
+            .iter()
+            .filter_map(|w| {
+                Some(Candidate {
+                    id: w.id().ok()?,
+                    pid: w.pid().unwrap_or(0),
+                    title: w.title().unwrap_or_default(),
+                    app_name: w.app_name().unwrap_or_default(),
+                    focused: w.is_focused().unwrap_or(false),
+                    minimized: w.is_minimized().unwrap_or(false),
+                    area: u64::from(w.width().unwrap_or(0)) * u64::from(w.height().unwrap_or(0)),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Start, switch or stop each rule's session for the current windows
+fn apply(windows: &[Candidate]) {
+    let own_pid = std::process::id();
+    let mut rules = RULES.lock();
+    let sessions = desktop::list_capture_sessions();
+    for active in rules.iter_mut() {
+        let session_id = active.session_id();
+        // The session may have ended on its own (target lost, stopped by the user)
+        let running = sessions.iter().any(|s| s.id == session_id && s.is_active);
+        let picked = active.pick(windows, own_pid);
+        if picked.map(|w| w.id) == active.current && (running || picked.is_none()) {
+            continue;
+        }
+        let kind = match (active.current, picked) {
+            (_, None) => WatchEventKind::Unmatched,
+            (Some(_), Some(_)) if running => WatchEventKind::Switched,
+            (_, Some(_)) => WatchEventKind::Matched,
+        };
+
+        let result = match picked {
+            Some(window) if kind == WatchEventKind::Switched => {
+                desktop::switch_capture_target(&session_id, Some(format!("window:{}", window.id)))
+            }
+            Some(window) => desktop::start_capture_session(
+                &session_id,
+                Some(format!("window:{}", window.id)),
+                active.sink.clone(),
+            ),
+            None => desktop::stop_capture_session(&session_id),
+        };
+        if let Err(e) = result {
+            log::warn!("[ScreenCapture] Watch rule {} could not {:?}: {}", active.rule.id, kind, e);
+            continue;
+        }
+
+        active.current = picked.map(|w| w.id);
+        log::info!("[ScreenCapture] Watch rule {} {:?} window {:?}", active.rule.id, kind, active.current);
+        events::emit(events::WATCH_RULE, WatchEvent {
+            kind,
+            rule_id: active.rule.id.clone(),
+            session_id,
+            target_id: active.current.map(|id| format!("window:{}", id)),
+            title: picked.map(|w| w.title.clone()),
+            app_name: picked.map(|w| w.app_name.clone()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, title: &str, app_name: &str, focused: bool, area: u64) -> Candidate {
+        Candidate {
+            id,
+            pid: 7,
+            title: title.to_string(),
+            app_name: app_name.to_string(),
+            focused,
+            minimized: false,
+            area,
+        }
+    }
+
+    fn rule(pattern: &str, match_on: MatchOn) -> WatchRule {
+        WatchRule { id: "zoom".to_string(), pattern: pattern.to_string(), match_on, case_insensitive: true }
+    }
+
+    fn active(rule: WatchRule) -> ActiveRule {
+        let regex = compile(&rule).unwrap();
+        // Only for matching; the sink is never used by `pick`
+        ActiveRule { rule, regex, sink: FrameSink::discard(), current: None }
+    }
+
+    #[test]
+    fn matches_title_app_or_either() {
+        let meeting = window(1, "Zoom Meeting - Standup", "zoom.us", false, 100);
+        assert!(active(rule("zoom meeting.*", MatchOn::Title)).matches(&meeting));
+        assert!(!active(rule("^zoom\\.us$", MatchOn::Title)).matches(&meeting));
+        assert!(active(rule("^zoom\\.us$", MatchOn::App)).matches(&meeting));
+        assert!(active(rule("^zoom\\.us$", MatchOn::Any)).matches(&meeting));
+        assert!(compile(&rule("(", MatchOn::Any)).is_err());
+    }
+
+    #[test]
+    fn keeps_current_window_then_prefers_focused_then_largest() {
+        let windows = [
+            window(1, "Zoom Meeting", "zoom.us", false, 100),
+            window(2, "Zoom Meeting 2", "zoom.us", false, 400),
+            window(3, "Zoom Meeting (Observer)", "zoom.us", true, 900),
+        ];
+        let mut zoom = active(rule("Zoom Meeting", MatchOn::Title));
+        assert!(zoom.pick(&windows, 7).is_none());
+        assert_eq!(zoom.pick(&windows, 0).map(|w| w.id), Some(3));
+        zoom.current = Some(1);
+        assert_eq!(zoom.pick(&windows, 0).map(|w| w.id), Some(1));
+        zoom.current = Some(9);
+        assert_eq!(zoom.pick(&windows[..2], 0).map(|w| w.id), Some(2));
+    }
+}
@@ -0,0 +1,302 @@
+//! Windows.Graphics.Capture backend.
+//!
+//! xcap's GDI/DXGI grabs return black frames for many hardware-accelerated and UWP
+//! windows and copy the whole desktop on the CPU. WGC hands us the compositor's own
+//! surface for a monitor or window as a D3D11 texture instead; we copy it into a
+//! CPU-readable staging texture and read it back as RGBA for the usual
+//! downscale/encode path.
+//!
+//! Frames are pulled from a free-threaded frame pool at the target FPS rather than via
+//! `FrameArrived`, so the capture thread keeps the same shape as the xcap loop. WGC
+//! draws the cursor itself (`IsCursorCaptureEnabled`). It needs Windows 10 1903+;
+//! a stream's `backend` setting can force xcap instead. When the window closes or the
+//! monitor goes away the item raises `Closed`, reported as `Error::TargetLost`.
+//!
+//! Targets on an HDR display are captured as FP16 and tone mapped (see `hdr`); the
+//! display is checked when a pass starts.
+
+use crate::activity::Pacer;
+use crate::capture_config::CaptureBackend;
+use crate::error::{Error, Result};
+use crate::hdr::{self, ToneMapper};
+use crate::pause;
+use crate::secure_input;
+use crate::targets::TargetKind;
+use crate::wire::FrameSink;
+use image::RgbaImage;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use windows::core::Interface;
+use windows::Foundation::TypedEventHandler;
+use windows::Graphics::Capture::{Direct3D11CaptureFramePool, GraphicsCaptureItem, GraphicsCaptureSession};
+use windows::Graphics::DirectX::Direct3D11::IDirect3DDevice;
+use windows::Graphics::DirectX::DirectXPixelFormat;
+use windows::Graphics::SizeInt32;
+use windows::Win32::Foundation::{E_INVALIDARG, HMODULE, HWND};
+use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE;
+use windows::Win32::Graphics::Direct3D11::{
+    D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D, D3D11_CPU_ACCESS_READ,
+    D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ, D3D11_SDK_VERSION,
+    D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING,
+};
+use windows::Win32::Graphics::Dxgi::IDXGIDevice;
+use windows::Win32::Graphics::Gdi::HMONITOR;
+use windows::Win32::System::WinRT::Direct3D11::{CreateDirect3D11DeviceFromDXGIDevice, IDirect3DDxgiInterfaceAccess};
+use windows::Win32::System::WinRT::Graphics::Capture::IGraphicsCaptureItemInterop;
+use windows::Win32::System::WinRT::{RoInitialize, RO_INIT_MULTITHREADED};
+
+const PIXEL_FORMAT: DirectXPixelFormat = DirectXPixelFormat::B8G8R8A8UIntNormalized;
+/// scRGB pool format for HDR displays
+const HDR_PIXEL_FORMAT: DirectXPixelFormat = DirectXPixelFormat::R16G16B16A16Float;
+const POOL_BUFFERS: i32 = 2;
+
+fn wgc_error(e: windows::core::Error) -> Error {
+    Error::Platform(format!("Windows.Graphics.Capture: {}", e))
+}
+
+/// Whether WGC is available on this system
+pub fn is_supported() -> bool {
+    GraphicsCaptureSession::IsSupported().unwrap_or(false)
+}
+
+/// Whether a new capture session should use WGC, per its configured `backend`
+pub fn should_use(backend: CaptureBackend) -> bool {
+    match backend {
+        CaptureBackend::Xcap => false,
+        CaptureBackend::Wgc if !is_supported() => {
+            log::warn!("[ScreenCapture] WGC requested but not supported here, using xcap");
+            false
+        }
+        CaptureBackend::Wgc | CaptureBackend::Auto => is_supported(),
+    }
+}
+
+/// Backends that can capture targets on this system, preferred first
+pub fn available_backends() -> Vec<CaptureBackend> {
+    if is_supported() {
+        vec![CaptureBackend::Wgc, CaptureBackend::Xcap]
+    } else {
+        vec![CaptureBackend::Xcap]
+    }
+}
+
+fn create_item(kind: &TargetKind, handle: u32) -> windows::core::Result<GraphicsCaptureItem> {
+    let interop = windows::core::factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
+    // xcap ids are the HMONITOR / HWND values
+    let raw = handle as usize as *mut std::ffi::c_void;
+    unsafe {
+        match kind {
+            TargetKind::Monitor => interop.CreateForMonitor(HMONITOR(raw)),
+            TargetKind::Window => interop.CreateForWindow(HWND(raw)),
+            // Spans several displays; the capture loop stitches it with xcap instead
+            TargetKind::VirtualDesktop => Err(E_INVALIDARG.into()),
+        }
+    }
+}
+
+fn create_device() -> windows::core::Result<(ID3D11Device, ID3D11DeviceContext, IDirect3DDevice)> {
+    let mut device = None;
+    let mut context = None;
+    unsafe {
+        D3D11CreateDevice(
+            None,
+            D3D_DRIVER_TYPE_HARDWARE,
+            HMODULE::default(),
+            D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+            None,
+            D3D11_SDK_VERSION,
+            Some(&mut device),
+            None,
+            Some(&mut context),
+        )?;
+    }
+    let (Some(device), Some(context)) = (device, context) else {
+        return Err(windows::core::Error::from_hresult(windows::Win32::Foundation::E_FAIL));
+    };
+    let dxgi: IDXGIDevice = device.cast()?;
+    let winrt_device: IDirect3DDevice = unsafe { CreateDirect3D11DeviceFromDXGIDevice(&dxgi)? }.cast()?;
+    Ok((device, context, winrt_device))
+}
+
+/// CPU-readable copy target, recreated when the frame size changes
+struct Staging {
+    texture: ID3D11Texture2D,
+    width: u32,
+    height: u32,
+}
+
+fn read_texture(
+    device: &ID3D11Device,
+    context: &ID3D11DeviceContext,
+    staging: &mut Option<Staging>,
+    texture: &ID3D11Texture2D,
+    width: u32,
+    height: u32,
+    tone_mapper: Option<&ToneMapper>,
+) -> windows::core::Result<Option<RgbaImage>> {
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe { texture.GetDesc(&mut desc) };
+
+    if staging.as_ref().is_none_or(|s| s.width != desc.Width || s.height != desc.Height) {
+        let mut staging_desc = desc;
+        staging_desc.Usage = D3D11_USAGE_STAGING;
+        staging_desc.BindFlags = 0;
+        staging_desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ.0 as u32;
+        staging_desc.MiscFlags = 0;
+        let mut created = None;
+        unsafe { device.CreateTexture2D(&staging_desc, None, Some(&mut created))? };
+        *staging = created.map(|texture| Staging {
+            texture,
+            width: desc.Width,
+            height: desc.Height,
+        });
+    }
+    let Some(staging) = staging.as_ref() else {
+        return Ok(None);
+    };
+
+    // The pool texture can be larger than the content (e.g. a window that shrank)
+    let width = width.min(desc.Width);
+    let height = height.min(desc.Height);
+
+    unsafe { context.CopyResource(&staging.texture, texture) };
+    let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+    unsafe { context.Map(&staging.texture, 0, D3D11_MAP_READ, 0, Some(&mut mapped))? };
+
+    let row_pitch = mapped.RowPitch as usize;
+    let data = unsafe { std::slice::from_raw_parts(mapped.pData as *const u8, row_pitch * desc.Height as usize) };
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height as usize {
+        match tone_mapper {
+            // RGBA, 16-bit float per channel
+            Some(mapper) => {
+                let row = &data[y * row_pitch..y * row_pitch + width as usize * 8];
+                for pixel in row.chunks_exact(8) {
+                    rgba.extend_from_slice(&mapper.map_pixel(pixel));
+                }
+            }
+            None => {
+                let row = &data[y * row_pitch..y * row_pitch + width as usize * 4];
+                for pixel in row.chunks_exact(4) {
+                    rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+                }
+            }
+        }
+    }
+    unsafe { context.Unmap(&staging.texture, 0) };
+
+    Ok(RgbaImage::from_raw(width, height, rgba))
+}
+
+/// Capture `handle` (an xcap monitor/window id) with `sink`'s config and rate until
+/// `stop_rx` fires or `on_image` returns false. `on_image` also gets how long the GPU readback took. Frames are skipped
+/// while capture is paused or a password field has focus. Fails with
+/// `Error::TargetLost` once the target closes.
+pub fn run_capture(
+    kind: TargetKind,
+    handle: u32,
+    sink: &FrameSink,
+    stop_rx: watch::Receiver<bool>,
+    mut on_image: impl FnMut(RgbaImage, Duration) -> bool,
+) -> Result<()> {
+    // WinRT needs the apartment initialized on this thread; "already initialized" is fine
+    let _ = unsafe { RoInitialize(RO_INIT_MULTITHREADED) };
+    let config = sink.config();
+
+    let item = create_item(&kind, handle).map_err(wgc_error)?;
+    let closed = Arc::new(AtomicBool::new(false));
+    let closed_flag = closed.clone();
+    let closed_token = item
+        .Closed(&TypedEventHandler::new(move |_, _| {
+            closed_flag.store(true, Ordering::SeqCst);
+            Ok(())
+        }))
+        .map_err(wgc_error)?;
+    let (device, context, winrt_device) = create_device().map_err(wgc_error)?;
+    let tone_mapper = hdr::tone_mapper_for(&kind, handle);
+    let pixel_format = if tone_mapper.is_some() { HDR_PIXEL_FORMAT } else { PIXEL_FORMAT };
+    let mut pool_size = item.Size().map_err(wgc_error)?;
+    let pool = Direct3D11CaptureFramePool::CreateFreeThreaded(&winrt_device, pixel_format, POOL_BUFFERS, pool_size)
+        .map_err(wgc_error)?;
+    let session = pool.CreateCaptureSession(&item).map_err(wgc_error)?;
+    // Both setters need newer Windows builds; older ones keep their defaults
+    let _ = session.SetIsCursorCaptureEnabled(config.show_cursor());
+    let _ = session.SetIsBorderRequired(false);
+    session.StartCapture().map_err(wgc_error)?;
+    log::info!(
+        "[ScreenCapture] WGC capturing {:?} {} ({}x{})",
+        kind,
+        handle,
+        pool_size.Width,
+        pool_size.Height
+    );
+
+    let mut staging = None;
+    let mut pacer = Pacer::new(sink);
+    let result = loop {
+        let frame_start = Instant::now();
+        let target_frame_time = pacer.frame_time(frame_start);
+
+        if *stop_rx.borrow() {
+            break Ok(());
+        }
+        if closed.load(Ordering::SeqCst) {
+            break Err(Error::TargetLost);
+        }
+
+        // Always drain the pool so it doesn't stall, even when the frame is dropped
+        if let Ok(frame) = pool.TryGetNextFrame() {
+            let content_size = frame.ContentSize().unwrap_or(pool_size);
+            if !pause::is_paused() && !secure_input::should_skip_frame() {
+                let readback_start = Instant::now();
+                let image = frame
+                    .Surface()
+                    .and_then(|surface| surface.cast::<IDirect3DDxgiInterfaceAccess>())
+                    .and_then(|access| unsafe { access.GetInterface::<ID3D11Texture2D>() })
+                    .and_then(|texture| {
+                        read_texture(
+                            &device,
+                            &context,
+                            &mut staging,
+                            &texture,
+                            content_size.Width.max(1) as u32,
+                            content_size.Height.max(1) as u32,
+                            tone_mapper.as_ref(),
+                        )
+                    });
+                match image {
+                    Ok(Some(image)) => {
+                        let readback = readback_start.elapsed();
+                        pacer.observe(image.as_raw(), image.width(), image.height(), Instant::now());
+                        if !on_image(image, readback) {
+                            break Ok(());
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::error!("[ScreenCapture] WGC frame readback failed: {}", e),
+                }
+            }
+            let _ = frame.Close();
+
+            // Windows resize; the pool must follow or frames get cropped/padded
+            if content_size != pool_size && content_size.Width > 0 && content_size.Height > 0 {
+                pool_size = SizeInt32 { Width: content_size.Width, Height: content_size.Height };
+                if let Err(e) = pool.Recreate(&winrt_device, pixel_format, POOL_BUFFERS, pool_size) {
+                    break Err(wgc_error(e));
+                }
+            }
+        }
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < target_frame_time {
+            std::thread::sleep(target_frame_time - elapsed);
+        }
+    };
+
+    let _ = item.RemoveClosed(closed_token);
+    let _ = session.Close();
+    let _ = pool.Close();
+    result
+}
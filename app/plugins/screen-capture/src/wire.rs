@@ -0,0 +1,278 @@
+//! Binary frame transfer.
+//!
+//! By default a stream's channel carries `FrameData` as JSON, which turns every image
+//! into a large number array (or base64 string) that both sides have to serialize.
+//! Streams started with `binary: true` send each frame as a raw IPC payload instead —
+//! the frontend receives an `ArrayBuffer` laid out as:
+//!
+//! ```text
+//! [u32 big-endian header length][JSON header][encoded image bytes]
+//! ```
+//!
+//! The header carries everything in `FrameData` except the image. The JSON form stays
+//! as a fallback for older frontends. On delta streams (see `delta`) the image part of
+//! most frames is a run of tile images; on YUV streams (see `yuv`) it's raw planes, or
+//! nothing when they're in shared memory.
+//!
+//! Every sink carries a `backpressure::Flow`; capture loops call `admit` before encoding
+//! so frames are dropped or cheapened while the consumer is behind.
+
+use crate::backpressure::Flow;
+use crate::capture_config::{CaptureConfig, FrameEncoding};
+use crate::delta::{DeltaConfig, DeltaEncoder, FrameDelta};
+use crate::desktop::FrameData;
+use crate::frame_stats::FrameStats;
+use crate::geometry::{CropRect, SourceMetrics};
+use crate::similarity::ChangeTracker;
+use crate::stats::Stage;
+use crate::yuv::{YuvFrame, YuvOutput, YuvSink};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, InvokeResponseBody, Response};
+
+/// Frame metadata sent ahead of the image bytes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameHeader {
+    /// Absent from peers that only sent JPEG
+    #[serde(default)]
+    pub format: FrameEncoding,
+    pub timestamp: f64,
+    pub width: u32,
+    pub height: u32,
+    pub frame_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceMetrics>,
+    /// Set on every frame of a delta stream (see `delta`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<FrameDelta>,
+    /// Set on every frame of a YUV stream (see `yuv`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yuv: Option<YuvFrame>,
+    /// Change from the previous frame, 0–1 (see `similarity`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<f32>,
+    /// Set with `capture_config::frame_stats` (see `frame_stats`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FrameStats>,
+}
+
+/// Pack a header and image into one binary payload
+pub fn encode(header: &FrameHeader, image: &[u8]) -> Vec<u8> {
+    let header = serde_json::to_vec(header).unwrap_or_default();
+    let mut payload = Vec::with_capacity(4 + header.len() + image.len());
+    payload.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    payload.extend_from_slice(&header);
+    payload.extend_from_slice(image);
+    payload
+}
+
+/// Split a binary payload back into header and image
+pub fn decode(payload: &[u8]) -> Option<(FrameHeader, &[u8])> {
+    let header_len = u32::from_be_bytes(payload.get(..4)?.try_into().ok()?) as usize;
+    let header = serde_json::from_slice(payload.get(4..4 + header_len)?).ok()?;
+    Some((header, &payload[4 + header_len..]))
+}
+
+impl FrameData {
+    pub fn header(&self) -> FrameHeader {
+        FrameHeader {
+            format: self.format,
+            timestamp: self.timestamp,
+            width: self.width,
+            height: self.height,
+            frame_count: self.frame_count,
+            source: self.source,
+            delta: self.delta.clone(),
+            yuv: self.yuv.clone(),
+            change: self.change,
+            stats: self.stats.clone(),
+        }
+    }
+
+    /// The payload is a whole encoded image (not tiles or YUV planes)
+    pub fn is_whole(&self) -> bool {
+        self.delta.as_ref().is_none_or(|d| d.key) && self.yuv.is_none()
+    }
+
+    pub fn from_binary(payload: &[u8]) -> Option<Self> {
+        let (header, image) = decode(payload)?;
+        Some(FrameData {
+            frame: image.to_vec(),
+            format: header.format,
+            timestamp: header.timestamp,
+            width: header.width,
+            height: header.height,
+            frame_count: header.frame_count,
+            source: header.source,
+            delta: header.delta,
+            yuv: header.yuv,
+            change: header.change,
+            stats: header.stats,
+        })
+    }
+}
+
+/// A stream's frame channel plus how frames are delivered on it
+#[derive(Clone)]
+pub struct FrameSink {
+    channel: Channel<Response>,
+    binary: bool,
+    flow: Arc<Flow>,
+    /// Part of the source to stream, in source pixels (from a capture profile)
+    crop: Option<CropRect>,
+    /// Send changed tiles instead of whole frames
+    delta: Option<Arc<Mutex<DeltaEncoder>>>,
+    /// Send raw planes instead of encoded images
+    yuv: Option<Arc<YuvSink>>,
+    /// Hash of the last frame, for change scores
+    change: Arc<ChangeTracker>,
+    /// The stream's own capture settings over the defaults
+    config: CaptureConfig,
+}
+
+impl FrameSink {
+    pub fn new(channel: Channel<Response>, binary: bool) -> Self {
+        Self {
+            channel,
+            binary,
+            flow: Arc::new(Flow::default()),
+            crop: None,
+            delta: None,
+            yuv: None,
+            change: Arc::new(ChangeTracker::default()),
+            config: CaptureConfig::default(),
+        }
+    }
+
+    /// Capture this stream with `config` instead of the defaults (None follows them)
+    pub fn with_config(self, config: Option<CaptureConfig>) -> Self {
+        Self { config: config.unwrap_or_default(), ..self }
+    }
+
+    pub fn config(&self) -> &CaptureConfig {
+        &self.config
+    }
+
+    /// Stream only `crop` of the source
+    pub fn with_crop(self, crop: Option<CropRect>) -> Self {
+        Self { crop, ..self }
+    }
+
+    pub fn crop(&self) -> Option<CropRect> {
+        self.crop
+    }
+
+    /// Delta-encode frames with `config` (None sends whole frames)
+    pub fn with_delta(self, config: Option<DeltaConfig>) -> Self {
+        Self { delta: config.map(|config| Arc::new(Mutex::new(DeltaEncoder::new(config)))), ..self }
+    }
+
+    /// Delta state, for sinks that send changed tiles
+    pub fn delta(&self) -> Option<&Mutex<DeltaEncoder>> {
+        self.delta.as_deref()
+    }
+
+    /// Send YUV planes as `output` says (None sends encoded images)
+    pub fn with_yuv(self, output: Option<YuvOutput>) -> Self {
+        Self { yuv: output.map(|output| Arc::new(YuvSink::new(output))), ..self }
+    }
+
+    /// YUV state, for sinks that send raw planes
+    pub fn yuv(&self) -> Option<&YuvSink> {
+        self.yuv.as_deref()
+    }
+
+    /// Change score of a frame with dHash `hash` against the sink's previous frame
+    pub fn change(&self, hash: u64) -> Option<f32> {
+        self.change.update(hash)
+    }
+
+    /// A sink that drops every frame (for streams read through the frame tap)
+    pub fn discard() -> Self {
+        Self::new(Channel::new(|_| Ok(())), true)
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    pub fn flow(&self) -> Arc<Flow> {
+        self.flow.clone()
+    }
+
+    /// JPEG quality to encode the next frame with, or None to drop it because the
+    /// consumer is behind
+    pub fn admit(&self) -> Option<u8> {
+        self.flow.admit(self.config.jpeg_quality())
+    }
+
+    /// Record how long a pipeline stage took, for `get_capture_stats_cmd`
+    pub fn record(&self, stage: Stage, took: Duration) {
+        self.flow.record(stage, took);
+    }
+
+    pub fn send(&self, frame: FrameData) -> tauri::Result<()> {
+        let started = Instant::now();
+        let frame_count = frame.frame_count;
+        let bytes = frame.frame.len();
+        let body = if self.binary {
+            InvokeResponseBody::Raw(encode(&frame.header(), &frame.frame))
+        } else {
+            InvokeResponseBody::Json(serde_json::to_string(&frame)?)
+        };
+        let result = self.channel.send(Response::new(body));
+        self.flow.sent(frame_count, bytes, started.elapsed());
+        result
+    }
+
+    /// Forward an already-encoded binary payload, converting it for JSON sinks
+    pub fn send_binary(&self, payload: Vec<u8>) -> tauri::Result<()> {
+        if self.binary {
+            return self.channel.send(Response::new(InvokeResponseBody::Raw(payload)));
+        }
+        match FrameData::from_binary(&payload) {
+            Some(frame) => self.send(frame),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_and_image_round_trip() {
+        let header = FrameHeader {
+            format: FrameEncoding::Png,
+            timestamp: 12.5,
+            width: 640,
+            height: 360,
+            frame_count: 3,
+            source: None,
+            delta: None,
+            yuv: None,
+            change: None,
+            stats: None,
+        };
+        let payload = encode(&header, &[1, 2, 3]);
+        let (decoded, image) = decode(&payload).unwrap();
+        assert_eq!(decoded.format, FrameEncoding::Png);
+        assert_eq!(decoded.frame_count, 3);
+        assert_eq!(image, &[1, 2, 3]);
+        assert!(decode(&payload[..6]).is_none());
+    }
+
+    #[test]
+    fn missing_format_defaults_to_jpeg() {
+        let header = br#"{"timestamp":1.0,"width":2,"height":1,"frameCount":7}"#;
+        let mut payload = (header.len() as u32).to_be_bytes().to_vec();
+        payload.extend_from_slice(header);
+        let (decoded, image) = decode(&payload).unwrap();
+        assert_eq!(decoded.format, FrameEncoding::Jpeg);
+        assert!(image.is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Raw YUV output for feeding video encoders.
+//!
+//! A sink started with a `YuvOutput` gets the frame's planes instead of an encoded
+//! image, converted straight from the capture loop's RGBA/BGRA buffer after resizing,
+//! redaction and the overlay. That skips the encode on our side and the decode and
+//! color conversion on the consumer's. Conversion is BT.601, limited range, with 4:2:0
+//! chroma (each chroma sample averages a 2x2 block):
+//!
+//! - `nv12`: the Y plane, then one plane of interleaved U/V pairs
+//! - `i420`: the Y plane, then the U plane, then the V plane
+//!
+//! Chroma planes are `ceil(width / 2)` x `ceil(height / 2)`; most encoders want even
+//! sizes, so set `capture_config::max_width` accordingly.
+//!
+//! Planes are about 1.5 bytes per pixel, far more than a JPEG. With `shared_memory`
+//! they aren't copied through the IPC channel: each frame is written to one of `SLOTS`
+//! slots of a per-sink file in shared memory (`/dev/shm` on Linux, the temp dir
+//! elsewhere) and the payload is empty; `YuvFrame::shm` says where to read it. A slot is
+//! reused `SLOTS` frames later, so read it before then.
+//!
+//! YUV takes precedence over `delta` on the same sink. The frame tap still gets encoded
+//! frames.
+
+use crate::delta::PixelOrder;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Slots in a shared-memory file
+pub const SLOTS: usize = 3;
+
+/// Plane layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum YuvLayout {
+    Nv12,
+    I420,
+}
+
+/// How a sink delivers YUV frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YuvOutput {
+    pub layout: YuvLayout,
+    /// Write planes to a shared-memory file instead of the frame payload
+    #[serde(default)]
+    pub shared_memory: bool,
+}
+
+/// Where a frame's planes were written in shared memory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShmSlot {
+    pub path: String,
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// How to read the frame of a YUV sink
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YuvFrame {
+    pub layout: YuvLayout,
+    /// Set when the planes are in shared memory; the payload is empty then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm: Option<ShmSlot>,
+}
+
+/// Per-sink YUV state
+#[derive(Debug)]
+pub struct YuvSink {
+    output: YuvOutput,
+    shared: Mutex<Option<SharedFile>>,
+}
+
+impl YuvSink {
+    pub fn new(output: YuvOutput) -> Self {
+        Self { output, shared: Mutex::new(None) }
+    }
+
+    /// Convert a `width`x`height` frame whose rows are `stride` bytes apart. Returns the
+    /// payload (empty in shared-memory mode) and how to read it.
+    pub fn convert_frame(
+        &self,
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        stride: usize,
+        order: PixelOrder,
+    ) -> Option<(Vec<u8>, YuvFrame)> {
+        let layout = self.output.layout;
+        let planes = convert(pixels, width, height, stride, order, layout);
+        if !self.output.shared_memory {
+            return Some((planes, YuvFrame { layout, shm: None }));
+        }
+
+        let mut shared = self.shared.lock();
+        if shared.as_ref().is_none_or(|file| file.slot_len != planes.len()) {
+            match SharedFile::create(planes.len()) {
+                Ok(file) => *shared = Some(file),
+                Err(e) => {
+                    log::error!("[ScreenCapture] Failed to create shared frame buffer: {}", e);
+                    return None;
+                }
+            }
+        }
+        let file = shared.as_mut()?;
+        match file.write(&planes) {
+            Ok(slot) => Some((Vec::new(), YuvFrame { layout, shm: Some(slot) })),
+            Err(e) => {
+                log::error!("[ScreenCapture] Failed to write shared frame: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// A file of `SLOTS` frame-sized slots, removed with the sink
+#[derive(Debug)]
+struct SharedFile {
+    file: File,
+    path: PathBuf,
+    slot_len: usize,
+    next: usize,
+}
+
+static FILE_ID: AtomicU64 = AtomicU64::new(0);
+
+impl SharedFile {
+    fn create(slot_len: usize) -> std::io::Result<Self> {
+        let dir = PathBuf::from("/dev/shm");
+        let dir = if cfg!(target_os = "linux") && dir.is_dir() { dir } else { std::env::temp_dir() };
+        let name = format!(
+            "observer-frames-{}-{}.yuv",
+            std::process::id(),
+            FILE_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(name);
+        let file = File::options().read(true).write(true).create(true).truncate(true).open(&path)?;
+        file.set_len((slot_len * SLOTS) as u64)?;
+        log::info!("[ScreenCapture] Shared YUV frames in {}", path.display());
+        Ok(Self { file, path, slot_len, next: 0 })
+    }
+
+    fn write(&mut self, planes: &[u8]) -> std::io::Result<ShmSlot> {
+        let offset = (self.next * self.slot_len) as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(planes)?;
+        self.next = (self.next + 1) % SLOTS;
+        Ok(ShmSlot { path: self.path.display().to_string(), offset, len: planes.len() as u64 })
+    }
+}
+
+impl Drop for SharedFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// BT.601 limited-range Y, U and V of an RGB pixel
+fn yuv(r: i32, g: i32, b: i32) -> (u8, u8, u8) {
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    (y as u8, u.clamp(0, 255) as u8, v.clamp(0, 255) as u8)
+}
+
+/// Planes of a 4-bytes-per-pixel buffer in `layout`
+pub fn convert(pixels: &[u8], width: u32, height: u32, stride: usize, order: PixelOrder, layout: YuvLayout) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    let (chroma_w, chroma_h) = (w.div_ceil(2), h.div_ceil(2));
+    let rgb = |x: usize, y: usize| {
+        let p = &pixels[y * stride + x * 4..][..4];
+        let (r, g, b) = match order {
+            PixelOrder::Rgba => (p[0], p[1], p[2]),
+            PixelOrder::Bgra => (p[2], p[1], p[0]),
+        };
+        (i32::from(r), i32::from(g), i32::from(b))
+    };
+
+    let mut out = Vec::with_capacity(w * h + chroma_w * chroma_h * 2);
+    for y in 0..h {
+        out.extend((0..w).map(|x| {
+            let (r, g, b) = rgb(x, y);
+            yuv(r, g, b).0
+        }));
+    }
+
+    let mut u_plane = Vec::with_capacity(chroma_w * chroma_h);
+    let mut v_plane = Vec::with_capacity(chroma_w * chroma_h);
+    for cy in 0..chroma_h {
+        for cx in 0..chroma_w {
+            let (mut r, mut g, mut b, mut n) = (0, 0, 0, 0);
+            for y in cy * 2..(cy * 2 + 2).min(h) {
+                for x in cx * 2..(cx * 2 + 2).min(w) {
+                    let p = rgb(x, y);
+                    r += p.0;
+                    g += p.1;
+                    b += p.2;
+                    n += 1;
+                }
+            }
+            let (_, u, v) = yuv(r / n, g / n, b / n);
+            u_plane.push(u);
+            v_plane.push(v);
+        }
+    }
+    match layout {
+        YuvLayout::Nv12 => out.extend(u_plane.iter().zip(&v_plane).flat_map(|(&u, &v)| [u, v])),
+        YuvLayout::I420 => {
+            out.extend_from_slice(&u_plane);
+            out.extend_from_slice(&v_plane);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_limited_range_planes() {
+        assert_eq!(yuv(0, 0, 0), (16, 128, 128));
+        assert_eq!(yuv(255, 255, 255), (235, 128, 128));
+
+        // 3x2 with 4 bytes of row padding: white, black, red / white, black, red
+        let row = [255, 255, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255, 9, 9, 9, 9];
+        let pixels = [row, row].concat();
+        let red = yuv(255, 0, 0);
+        let gray = yuv(127, 127, 127);
+
+        let i420 = convert(&pixels, 3, 2, 16, PixelOrder::Rgba, YuvLayout::I420);
+        assert_eq!(&i420[..6], &[235, 16, red.0, 235, 16, red.0]);
+        assert_eq!(&i420[6..], &[gray.1, red.1, gray.2, red.2]);
+
+        let nv12 = convert(&pixels, 3, 2, 16, PixelOrder::Rgba, YuvLayout::Nv12);
+        assert_eq!(&nv12[6..], &[gray.1, gray.2, red.1, red.2]);
+
+        // Same frame in BGRA order
+        let bgra: Vec<u8> = pixels.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], p[3]]).collect();
+        assert_eq!(convert(&bgra, 3, 2, 16, PixelOrder::Bgra, YuvLayout::Nv12), nv12);
+    }
+
+    #[test]
+    fn shared_memory_cycles_through_slots() {
+        let sink = YuvSink::new(YuvOutput { layout: YuvLayout::I420, shared_memory: true });
+        let pixels = vec![0u8; 2 * 2 * 4];
+        let mut offsets = Vec::new();
+        for _ in 0..SLOTS + 1 {
+            let (payload, frame) = sink.convert_frame(&pixels, 2, 2, 8, PixelOrder::Rgba).unwrap();
+            assert!(payload.is_empty());
+            let slot = frame.shm.unwrap();
+            assert_eq!(slot.len, 6);
+            assert_eq!(std::fs::read(&slot.path).unwrap().len(), 6 * SLOTS);
+            offsets.push(slot.offset);
+        }
+        assert_eq!(offsets, vec![0, 6, 12, 0]);
+
+        let path = sink.shared.lock().as_ref().unwrap().path.clone();
+        drop(sink);
+        assert!(!path.exists());
+    }
+}
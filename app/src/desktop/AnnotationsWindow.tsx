@@ -0,0 +1,164 @@
+import { useState, useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+// Shapes arrive already mapped to this window's CSS pixels (see annotations.rs)
+type Shape =
+  | { kind: 'rect'; x: number; y: number; width: number; height: number }
+  | { kind: 'arrow'; fromX: number; fromY: number; toX: number; toY: number }
+  | { kind: 'callout'; x: number; y: number; text: string }
+  | { kind: 'toast'; text: string };
+
+type Annotation = Shape & {
+  id: string;
+  agentId: string | null;
+  label: string | null;
+  color: string | null;
+  z: number;
+  createdAt: number;
+  expiresAt: number | null;
+};
+
+const DEFAULT_COLOR = '#22d3ee';
+
+function useAnnotations() {
+  const [annotations, setAnnotations] = useState<Annotation[]>([]);
+
+  // Transparent background, same as the overlay window
+  useEffect(() => {
+    document.documentElement.style.backgroundColor = 'transparent';
+    document.body.style.backgroundColor = 'transparent';
+    const root = document.getElementById('root');
+    if (root) {
+      root.style.backgroundColor = 'transparent';
+    }
+  }, []);
+
+  useEffect(() => {
+    invoke<Annotation[]>('get_overlay_annotations')
+      .then(setAnnotations)
+      .catch(error => console.error('Failed to fetch overlay annotations:', error));
+
+    let unlisten: (() => void) | null = null;
+    listen<Annotation[]>('overlay-annotations-updated', (event) => {
+      setAnnotations(event.payload);
+    }).then(fn => {
+      unlisten = fn;
+    });
+
+    return () => unlisten?.();
+  }, []);
+
+  return annotations;
+}
+
+// Re-render once a second while any toast is counting down
+function useNow(active: boolean) {
+  const [now, setNow] = useState(Date.now());
+  useEffect(() => {
+    if (!active) return;
+    const timer = setInterval(() => setNow(Date.now()), 1000);
+    return () => clearInterval(timer);
+  }, [active]);
+  return now;
+}
+
+function ArrowShape({ a, color }: { a: Extract<Annotation, { kind: 'arrow' }>; color: string }) {
+  const markerId = `arrowhead-${a.id}`;
+  return (
+    <svg className="absolute inset-0 w-full h-full overflow-visible" style={{ zIndex: a.z }}>
+      <defs>
+        <marker id={markerId} markerWidth="10" markerHeight="8" refX="9" refY="4" orient="auto">
+          <polygon points="0 0, 10 4, 0 8" fill={color} />
+        </marker>
+      </defs>
+      <line
+        x1={a.fromX}
+        y1={a.fromY}
+        x2={a.toX}
+        y2={a.toY}
+        stroke={color}
+        strokeWidth={3}
+        strokeLinecap="round"
+        markerEnd={`url(#${markerId})`}
+        style={{ filter: `drop-shadow(0 0 4px ${color})` }}
+      />
+    </svg>
+  );
+}
+
+export default function AnnotationsWindow() {
+  const annotations = useAnnotations();
+  const toasts = annotations.filter((a): a is Extract<Annotation, { kind: 'toast' }> => a.kind === 'toast');
+  const now = useNow(toasts.length > 0);
+
+  return (
+    <div className="fixed inset-0 pointer-events-none overflow-hidden">
+      {annotations.map((a) => {
+        const color = a.color || DEFAULT_COLOR;
+        switch (a.kind) {
+          case 'rect':
+            return (
+              <div
+                key={a.id}
+                className="absolute rounded-sm animate-in fade-in duration-200"
+                style={{
+                  left: a.x,
+                  top: a.y,
+                  width: a.width,
+                  height: a.height,
+                  zIndex: a.z,
+                  border: `2px solid ${color}`,
+                  boxShadow: `0 0 0 1px rgba(0, 0, 0, 0.4), 0 0 12px ${color}`,
+                }}
+              >
+                {a.label && (
+                  <div
+                    className="absolute left-0 -top-6 whitespace-nowrap rounded px-1.5 py-0.5 text-xs font-medium text-black"
+                    style={{ backgroundColor: color }}
+                  >
+                    {a.label}
+                  </div>
+                )}
+              </div>
+            );
+          case 'arrow':
+            return <ArrowShape key={a.id} a={a} color={color} />;
+          case 'callout':
+            return (
+              <div
+                key={a.id}
+                className="absolute max-w-xs bg-black/80 backdrop-blur-xl rounded-lg px-3 py-2 text-white/90 text-sm shadow-xl animate-in fade-in duration-200"
+                style={{ left: a.x, top: a.y, zIndex: a.z, border: `1px solid ${color}` }}
+              >
+                {a.label && <div className="text-xs font-semibold mb-0.5" style={{ color }}>{a.label}</div>}
+                {a.text}
+              </div>
+            );
+          default:
+            return null;
+        }
+      })}
+
+      {/* Toasts stack at the top center of the monitor */}
+      <div className="absolute top-6 left-1/2 -translate-x-1/2 flex flex-col items-center gap-2">
+        {toasts.map((t) => {
+          const color = t.color || DEFAULT_COLOR;
+          const remaining = t.expiresAt ? Math.max(0, Math.ceil((t.expiresAt - now) / 1000)) : null;
+          return (
+            <div
+              key={t.id}
+              className="bg-black/80 backdrop-blur-xl rounded-md px-4 py-2 border border-white/20 text-white/90 text-sm flex items-center gap-3 shadow-xl animate-in slide-in-from-top-2 duration-300"
+              style={{ zIndex: t.z }}
+            >
+              <span>{t.text}</span>
+              {remaining !== null && (
+                <span className="font-mono text-xs font-bold" style={{ color }}>{remaining}s</span>
+              )}
+            </div>
+          );
+        })}
+      </div>
+    </div>
+  );
+}
@@ -85,8 +85,8 @@ export default function ScreenSelectorWindow() {
     };
   }, [loadTargets]);
 
-  // Group targets by type
-  const monitors = targets.filter(t => t.kind === 'monitor');
+  // Group targets by type (the stitched all-monitors target sits with the screens)
+  const monitors = targets.filter(t => t.kind === 'monitor' || t.kind === 'virtual-desktop');
   const windows = targets.filter(t => t.kind === 'window');
 
   // Group windows by app
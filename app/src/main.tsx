@@ -15,9 +15,12 @@ import App from './web/App'; // Your existing App.tsx, now the "WebApp"
 //import LauncherShell from './desktop/LauncherShell'; // The new "DesktopApp"
 import OverlayWindow from './desktop/OverlayWindow'; // The overlay window
 import ScreenSelectorWindow from './desktop/ScreenSelectorWindow'; // Screen/window selector
+import AnnotationsWindow from './desktop/AnnotationsWindow'; // Boxes drawn over the screen
 
 // Import platform detection utilities
 import { isDesktop, initTauriLogForwarding, initPlatformFetch } from './utils/platform';
+import { initApiToken } from './utils/apiToken';
+import { initAgentEventForwarding } from './utils/tauriEvents';
 
 // Initialize Tauri log forwarding (fire and forget)
 initTauriLogForwarding();
@@ -25,6 +28,12 @@ initTauriLogForwarding();
 // Pre-load Tauri HTTP plugin for desktop localhost requests
 initPlatformFetch();
 
+// Send the API server's token with requests to it
+initApiToken();
+
+// Report agent runs to the API server's event stream
+initAgentEventForwarding();
+
 // Decide which component to render at the root level
 function getRootComponent() {
   // Desktop only: overlay route
@@ -32,6 +41,11 @@ function getRootComponent() {
     return OverlayWindow;
   }
 
+  // Desktop only: on-screen annotations route
+  if (isDesktop() && window.location.pathname === '/annotations') {
+    return AnnotationsWindow;
+  }
+
   // Desktop only: screen selector route
   if (isDesktop() && window.location.pathname === '/screen-selector') {
     return ScreenSelectorWindow;
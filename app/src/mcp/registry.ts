@@ -219,7 +219,7 @@ async function captureScreenTauri(): Promise<ToolResult> {
       captured: true,
       note: 'Native screen capture is live (whole screen) and will be reused by start_agent — no second prompt.',
     },
-    images: [`data:image/jpeg;base64,${raw}`],
+    images: [`data:${tauriStreamCapture.getLatestFrameMimeType()};base64,${raw}`],
   };
 }
 
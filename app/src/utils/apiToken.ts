@@ -0,0 +1,95 @@
+import { invoke } from '@tauri-apps/api/core';
+import { isTauri } from './platform';
+import { Logger } from './logging';
+
+/**
+ * Token for the desktop app's built-in API server.
+ *
+ * The server rejects API requests without its per-install token. The app's own windows get
+ * it with `get_api_token`; a browser tab can be handed it once as `?token=...` in the URL,
+ * after which it is remembered. `initApiToken` makes every fetch to the API server carry
+ * it, and `withApiToken` adds it to URLs for EventSource and WebSocket, which can't send
+ * headers.
+ */
+
+export const API_TOKEN_HEADER = 'x-observer-token';
+const STORAGE_KEY = 'observer_api_token';
+
+let apiToken: string | null = null;
+const apiOrigins = new Set(['http://localhost:3838', 'http://127.0.0.1:3838']);
+
+export interface ApiAuthSettings {
+  required: boolean;  // Default true; off opens the API to anything that can reach it
+}
+
+const urlOf = (input: RequestInfo | URL): string =>
+  typeof input === 'string' ? input : input instanceof URL ? input.href : input.url;
+
+const isApiUrl = (url: string): boolean => {
+  try {
+    return apiOrigins.has(new URL(url, window.location.href).origin);
+  } catch {
+    return false;
+  }
+};
+
+/** `init` with the token header added, if `input` goes to the API server */
+export function withApiHeaders(input: RequestInfo | URL, init?: RequestInit): RequestInit | undefined {
+  if (!apiToken || !isApiUrl(urlOf(input))) return init;
+  const headers = new Headers(init?.headers ?? (input instanceof Request ? input.headers : undefined));
+  if (!headers.has(API_TOKEN_HEADER)) headers.set(API_TOKEN_HEADER, apiToken);
+  return { ...init, headers };
+}
+
+/** `url` with the token as a query parameter, if it goes to the API server */
+export function withApiToken(url: string): string {
+  if (!apiToken || !isApiUrl(url)) return url;
+  const withToken = new URL(url, window.location.href);
+  withToken.searchParams.set('token', apiToken);
+  return withToken.toString();
+}
+
+export async function initApiToken(): Promise<void> {
+  if (isTauri()) {
+    try {
+      apiToken = await invoke<string>('get_api_token');
+      apiOrigins.add(new URL(await invoke<string>('get_server_url')).origin);
+    } catch (err) {
+      Logger.warn('ApiToken', `Could not get the API token: ${err}`);
+    }
+  } else {
+    const params = new URLSearchParams(window.location.search);
+    const fromUrl = params.get('token');
+    if (fromUrl) {
+      localStorage.setItem(STORAGE_KEY, fromUrl);
+      // Keep it out of the address bar and history
+      params.delete('token');
+      const query = params.toString();
+      window.history.replaceState(null, '', window.location.pathname + (query ? `?${query}` : '') + window.location.hash);
+    }
+    apiToken = localStorage.getItem(STORAGE_KEY);
+    // Served by the desktop app itself, e.g. on another device on the LAN
+    if (window.location.port === '3838') apiOrigins.add(window.location.origin);
+  }
+
+  const originalFetch = window.fetch.bind(window);
+  window.fetch = (input: RequestInfo | URL, init?: RequestInit) => originalFetch(input, withApiHeaders(input, init));
+}
+
+export async function getApiToken(): Promise<string> {
+  return invoke<string>('get_api_token');
+}
+
+/** New token; clients using the old one (other than this app) need the new one */
+export async function rotateApiToken(): Promise<string> {
+  apiToken = await invoke<string>('rotate_api_token');
+  return apiToken;
+}
+
+export async function getApiAuthSettings(): Promise<ApiAuthSettings> {
+  return invoke<ApiAuthSettings>('get_api_auth_settings');
+}
+
+export async function setApiAuthSettings(settings: ApiAuthSettings): Promise<void> {
+  return invoke<void>('set_api_auth_settings', { settings });
+}
@@ -3,6 +3,7 @@
 
 import { isAgentLoopRunning, startAgentLoop, stopAgentLoop } from './main_loop';
 import { Logger } from './logging';
+import { withApiToken } from './apiToken';
 
 export type TokenProvider = () => Promise<string | undefined>;
 
@@ -66,7 +67,7 @@ class CommandSSE {
 
   private connectSSE(): void {
     try {
-      this.eventSource = new EventSource(`${this.serverUrl}/commands-stream`);
+      this.eventSource = new EventSource(withApiToken(`${this.serverUrl}/commands-stream`));
 
       this.eventSource.onopen = () => {
         Logger.info('Commands', 'SSE connection established');
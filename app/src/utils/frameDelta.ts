@@ -0,0 +1,52 @@
+import { FrameData, FRAME_MIME_TYPES } from './tauriStreamCapture';
+
+/**
+ * Rebuilds whole frames from a delta stream (a video stream started with `delta`).
+ *
+ * Keyframes carry the whole image. Other frames carry only the tiles that differ from
+ * their keyframe, so a frame is the keyframe with the latest frame's tiles drawn over
+ * it. Tiles are always relative to the keyframe, never to the previous frame, so
+ * dropped frames don't matter; a frame whose keyframe was never seen is skipped.
+ */
+export class FrameReassembler {
+  private keyframe = 0;
+  private key: ImageBitmap | null = null;
+  private canvas: OffscreenCanvas | null = null;
+
+  /** The whole image of `frame`, or null until its keyframe has arrived */
+  async push(frame: FrameData): Promise<OffscreenCanvas | null> {
+    const mimeType = FRAME_MIME_TYPES[frame.format ?? 'jpeg'] ?? 'image/jpeg';
+    const delta = frame.delta;
+
+    if (!delta || delta.key) {
+      const bitmap = await createImageBitmap(new Blob([frame.frame], { type: mimeType }));
+      this.key?.close();
+      this.key = bitmap;
+      this.keyframe = delta?.keyframe ?? 0;
+      this.canvas = new OffscreenCanvas(bitmap.width, bitmap.height);
+      this.canvas.getContext('2d')!.drawImage(bitmap, 0, 0);
+      return this.canvas;
+    }
+
+    if (!this.key || !this.canvas || delta.keyframe !== this.keyframe) return null;
+    const context = this.canvas.getContext('2d')!;
+    context.drawImage(this.key, 0, 0);
+    let offset = 0;
+    for (const tile of delta.tiles ?? []) {
+      const bytes = frame.frame.subarray(offset, offset + tile.len);
+      offset += tile.len;
+      const bitmap = await createImageBitmap(new Blob([bytes], { type: mimeType }));
+      context.drawImage(bitmap, tile.x, tile.y);
+      bitmap.close();
+    }
+    return this.canvas;
+  }
+
+  /** Forget the keyframe, e.g. when the stream restarts */
+  reset(): void {
+    this.key?.close();
+    this.key = null;
+    this.canvas = null;
+    this.keyframe = 0;
+  }
+}
@@ -1,6 +1,7 @@
 // Platform detection utilities for Observer
 import { platform as getPlatform } from '@tauri-apps/plugin-os';
 import { Logger, LogLevel } from './logging';
+import { withApiHeaders } from './apiToken';
 
 /**
  * Check if the app is running in Tauri
@@ -250,7 +251,8 @@ export const platformFetch = async (
 
   if (isDesktop() && isHttp && tauriFetchFn) {
     Logger.debug('platform', `Using Tauri HTTP for: ${url}`);
-    return tauriFetchFn(input as any, init as any);
+    // Native requests skip the window.fetch wrapper that adds the API token
+    return tauriFetchFn(input as any, withApiHeaders(input, init) as any);
   }
 
   // Use regular fetch for HTTPS and non-desktop platforms
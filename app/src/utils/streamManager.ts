@@ -391,6 +391,7 @@ class Manager {
     // This is more reliable than canvas-backed video element (especially on iOS)
     if (!isWeb() && streamType === 'screen') {
       const rawFrame = tauriStreamCapture.getLatestBase64Frame();
+      const mimeType = tauriStreamCapture.getLatestFrameMimeType();
       if (!rawFrame) {
         Logger.warn("StreamManager", "Cannot capture screen: no raw frame available from channel");
         return null;
@@ -406,7 +407,7 @@ class Manager {
       // Apply crop by decoding, cropping, and re-encoding.
       // crop is normalized (0–1); resolve it against the real frame pixels here.
       try {
-        const blob = await fetch(`data:image/jpeg;base64,${rawFrame}`).then(r => r.blob());
+        const blob = await fetch(`data:${mimeType};base64,${rawFrame}`).then(r => r.blob());
         const bitmap = await createImageBitmap(blob);
 
         const px = resolveCrop(crop, bitmap.width, bitmap.height);
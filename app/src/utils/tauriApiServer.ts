@@ -0,0 +1,124 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Built-in API server address (desktop app only).
+ *
+ * The server listens on `host:port` from these settings, unless OBSERVER_API_HOST /
+ * OBSERVER_API_PORT are set. Host 0.0.0.0 makes it reachable from the LAN. If the port
+ * is taken and `fallbackPort` is on, another one is used; API_SERVER_STARTED_EVENT (and
+ * `get_server_url`) tell which. With `tls.enabled` the server speaks HTTPS, using the
+ * given certificate or a generated self-signed one that clients must trust first.
+ * `basePath` (or OBSERVER_BASE_PATH) serves the API under a sub-path as well, for reverse
+ * proxies that keep theirs, and is included in the reported URL. Browsers may call the
+ * API only from the app's own origins plus `cors.allowedOrigins` (or OBSERVER_CORS_ORIGINS;
+ * `*` for any). With `singleInstance`, starting the app while it already runs focuses the
+ * running one instead; every running instance is listed in `<app data>/instances/`.
+ * `localSocket` also serves the API on a Unix socket (`<app data>/api.sock`) or, on
+ * Windows, the named pipe `\\.\pipe\observer-api`, for local tools without a port.
+ * A server open to the LAN is announced over mDNS as MDNS_SERVICE_TYPE (TXT: `tls`,
+ * `path`, `version`) unless `mdns` is off. `webrtc` sets up the WebRTC video stream
+ * (POST /webrtc/offer): STUN/TURN servers for viewers outside the LAN and a bitrate cap.
+ * `grpc` serves the gRPC API (app/desktop/proto/observer/v1/observer.proto) on its own
+ * port, with the same token. Changes apply after a restart, except `limits`: per-client
+ * rate limits (loopback exempt by default) and request body caps, which apply at once.
+ */
+
+export const API_SERVER_STARTED_EVENT = 'api-server-started';
+export const MDNS_SERVICE_TYPE = '_observer._tcp.local.';
+
+export interface TlsSettings {
+  enabled: boolean;
+  certPath?: string | null;  // PEM; leave both paths empty for a self-signed pair
+  keyPath?: string | null;
+}
+
+export interface CorsSettings {
+  allowedOrigins: string[];  // e.g. 'https://dashboard.example.com', or '*'
+}
+
+export interface LocalSocketSettings {
+  enabled: boolean;
+  path?: string | null;  // Socket file or pipe name; empty for the default
+}
+
+export interface WebRtcSettings {
+  iceServers: string[];  // e.g. 'stun:stun.l.google.com:19302'
+  maxBitrateKbps: number;  // Default 4000
+}
+
+export interface GrpcSettings {
+  enabled: boolean;
+  port: number;  // Default 50051
+}
+
+export interface LimitSettings {
+  requestsPerMinute: number;  // Default 300; 0 for no rate limit
+  burst: number;  // Default 60
+  exemptLoopback: boolean;  // Default true
+  trustForwardedFor: boolean;  // Count the client in X-Forwarded-For; only behind a proxy
+  maxBodyBytes: number;  // Default 1 MiB
+  maxUploadBytes: number;  // Model proxy requests, which carry images; default 64 MiB
+}
+
+export interface ApiServerSettings {
+  host: string;  // Default 127.0.0.1
+  port: number;  // Default 3838
+  fallbackPort: boolean;
+  tls: TlsSettings;
+  basePath: string;  // e.g. '/observer-api'; empty for none
+  cors: CorsSettings;
+  singleInstance: boolean;  // Default true
+  localSocket: LocalSocketSettings;
+  mdns: boolean;  // Default true; only servers listening beyond loopback are announced
+  webrtc: WebRtcSettings;
+  grpc: GrpcSettings;
+  limits: LimitSettings;
+}
+
+export interface TlsInfo {
+  enabled: boolean;
+  selfSigned: boolean;
+  certPath: string;
+  fingerprint?: string;  // SHA-256, colon-separated hex
+}
+
+/** A running instance, as registered in the discovery directory */
+export interface InstanceInfo {
+  pid: number;
+  url: string;
+  port: number;
+  version: string;
+  startedAt: number;  // Unix ms
+}
+
+/** Payload of API_SERVER_STARTED_EVENT */
+export interface ServerStarted {
+  url: string;
+  host: string;
+  port: number;
+  requestedPort: number;
+  tls: boolean;
+  basePath: string;
+}
+
+export async function getApiServerSettings(): Promise<ApiServerSettings> {
+  return invoke<ApiServerSettings>('get_api_server_settings');
+}
+
+export async function setApiServerSettings(settings: ApiServerSettings): Promise<void> {
+  return invoke<void>('set_api_server_settings', { settings });
+}
+
+export async function getTlsInfo(): Promise<TlsInfo> {
+  return invoke<TlsInfo>('get_tls_info');
+}
+
+/** New self-signed certificate (e.g. after the LAN address changed); applies after a restart */
+export async function regenerateTlsCertificate(): Promise<TlsInfo> {
+  return invoke<TlsInfo>('regenerate_tls_certificate');
+}
+
+/** Other running instances of the app */
+export async function listInstances(): Promise<InstanceInfo[]> {
+  return invoke<InstanceInfo[]>('list_instances');
+}
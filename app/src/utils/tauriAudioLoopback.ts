@@ -0,0 +1,169 @@
+import { invoke, Channel } from '@tauri-apps/api/core';
+
+/**
+ * System audio loopback through the audio-capture plugin (desktop app only).
+ *
+ * Unlike `startSystemAudioCapture`, nothing is shared through a picker: the app taps
+ * the output mix directly (WASAPI loopback, a Core Audio tap, or the Pulse/PipeWire
+ * monitor) and streams chunks in the format asked for. Microphones stream the same
+ * chunks through the same pipeline, so a consumer can take either source.
+ */
+
+export type AudioEncoding = 'pcm' | 'opus';
+
+export interface AudioStreamConfig {
+  encoding?: AudioEncoding;  // Default pcm
+  sampleRate?: number;  // Default 16000; Opus needs 8000/12000/16000/24000/48000
+  channels?: 1 | 2;  // Default 1
+  chunkMs?: number;  // Default 100; rounded up to 20 ms steps for Opus
+  bitrate?: number;  // Opus bits per second
+}
+
+export interface AudioChunk {
+  encoding: AudioEncoding;
+  data: string;  // Base64: f32 LE interleaved samples (pcm) or Opus packets back to back
+  packets?: number[];  // Byte length of each 20 ms Opus packet in `data`
+  sampleRate: number;
+  channels: number;
+  timestamp: number;
+  chunkCount: number;
+}
+
+export type AudioSourceKind = 'loopback' | 'microphone';
+
+export interface AudioDevice {
+  id: string;  // Pass to startMicrophone
+  name: string;
+  isDefault: boolean;
+  sampleRate: number;  // The device's own format
+  channels: number;
+}
+
+export interface AudioCaptureStatus {
+  kind: AudioSourceKind;
+  active: boolean;
+  device?: string;  // Input device name, for microphone captures
+  config?: Required<Omit<AudioStreamConfig, 'bitrate'>> & { bitrate?: number };
+  source?: { sampleRate: number; channels: number };  // What the device delivers
+  chunks: number;
+}
+
+/** Start the loopback (replacing a running one); resolves once the device is open */
+export async function startAudioLoopback(
+  onChunk: (chunk: AudioChunk) => void,
+  config: AudioStreamConfig = {},
+): Promise<AudioCaptureStatus> {
+  const channel = new Channel<AudioChunk>();
+  channel.onmessage = onChunk;
+  return invoke<AudioCaptureStatus>('audio_start_loopback', { config, onChunk: channel });
+}
+
+export async function stopAudioLoopback(): Promise<boolean> {
+  return invoke<boolean>('audio_stop_loopback');
+}
+
+export async function getAudioLoopbackStatus(): Promise<AudioCaptureStatus> {
+  return invoke<AudioCaptureStatus>('audio_get_loopback_status');
+}
+
+export async function getAudioDevices(): Promise<AudioDevice[]> {
+  return invoke<AudioDevice[]>('audio_get_devices');
+}
+
+/** Record a microphone (the default input unless `deviceId` is given), replacing a running one */
+export async function startMicrophone(
+  onChunk: (chunk: AudioChunk) => void,
+  config: AudioStreamConfig = {},
+  deviceId?: string,
+): Promise<AudioCaptureStatus> {
+  const channel = new Channel<AudioChunk>();
+  channel.onmessage = onChunk;
+  return invoke<AudioCaptureStatus>('audio_start_microphone', { deviceId, config, onChunk: channel });
+}
+
+export async function stopMicrophone(): Promise<boolean> {
+  return invoke<boolean>('audio_stop_microphone');
+}
+
+export async function getMicrophoneStatus(): Promise<AudioCaptureStatus> {
+  return invoke<AudioCaptureStatus>('audio_get_microphone_status');
+}
+
+/** Samples of a PCM chunk, interleaved when stereo */
+export function decodePcmChunk(chunk: AudioChunk): Float32Array {
+  const bytes = Uint8Array.from(atob(chunk.data), c => c.charCodeAt(0));
+  return new Float32Array(bytes.buffer, 0, bytes.byteLength / 4);
+}
+
+// Voice activity detection
+
+export type VadMode = 'quality' | 'lowBitrate' | 'aggressive' | 'veryAggressive';
+
+export interface VadConfig {
+  source?: AudioSourceKind;  // Default microphone
+  deviceId?: string;
+  mode?: VadMode;  // Default aggressive; veryAggressive flags the least as speech
+  startMs?: number;  // Speech before speechStarted; default 210
+  hangoverMs?: number;  // Silence before speechEnded; default 900
+}
+
+/** Payload of the audio-capture://speech event */
+export interface SpeechEvent {
+  kind: 'speechStarted' | 'speechEnded';
+  source: AudioSourceKind;
+  timestamp: number;  // When the speech began or stopped, seconds since the epoch
+  durationSecs?: number;  // On speechEnded
+}
+
+/**
+ * Start the VAD (replacing a running one); listen for audio-capture://speech events.
+ * With `speechFps`, screen capture runs at that rate while someone talks.
+ */
+export async function startVad(config: VadConfig = {}, speechFps?: number): Promise<void> {
+  return invoke<void>('audio_start_vad', { config, speechFps });
+}
+
+export async function stopVad(): Promise<boolean> {
+  return invoke<boolean>('audio_stop_vad');
+}
+
+// Local transcription (builds with the `whisper` feature)
+
+export interface TranscriptionConfig {
+  modelPath: string;  // File name from listWhisperModels, or a full path
+  source?: AudioSourceKind;  // Default microphone
+  deviceId?: string;
+  language?: string;  // e.g. "en"; detected when unset
+  windowSecs?: number;  // Audio per pass, 1-30; default 5
+  phrases?: string[];  // Also emit audio-capture://phrase-detected when heard
+}
+
+export interface TranscriptSegment {
+  text: string;
+  start: number;  // Seconds since the epoch
+  end: number;
+  phrases?: string[];  // Phrases from the config found in text
+}
+
+export interface PhraseDetected {
+  phrase: string;
+  segment: TranscriptSegment;
+}
+
+export async function listWhisperModels(): Promise<string[]> {
+  return invoke<string[]>('transcription_list_models');
+}
+
+/** Start transcribing (replacing a running transcription); resolves once the model is loaded */
+export async function startTranscription(
+  onSegment: (segment: TranscriptSegment) => void,
+  config: TranscriptionConfig,
+): Promise<void> {
+  const channel = new Channel<TranscriptSegment>();
+  channel.onmessage = onSegment;
+  return invoke<void>('transcription_start', { config, onSegment: channel });
+}
+
+export async function stopTranscription(): Promise<boolean> {
+  return invoke<boolean>('transcription_stop');
+}
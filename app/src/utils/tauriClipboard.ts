@@ -0,0 +1,37 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Clipboard monitoring through the clipboard-monitor plugin (desktop app only).
+ *
+ * Opt-in: nothing is read until startClipboardMonitor, and images need `images: true`.
+ * Each copy emits `clipboard-monitor://changed` with a ClipboardChange payload. Copies
+ * that password managers mark as concealed are never reported.
+ */
+
+export const CLIPBOARD_CHANGED_EVENT = 'clipboard-monitor://changed';
+
+export interface ClipboardMonitorConfig {
+  text?: boolean;  // Default true
+  images?: boolean;  // Default false
+  pollMs?: number;  // Default 500
+  maxTextBytes?: number;  // Longer text is cut; default 16 KiB
+  maxImageBytes?: number;  // Bigger images (uncompressed RGBA) come without data; default 64 MiB
+  maxImageWidth?: number;  // Wider images are downscaled; default 1280
+}
+
+export interface ClipboardChange {
+  kind: 'text' | 'image';
+  timestamp: number;  // Seconds since the epoch
+  bytes: number;  // Size of the copy: UTF-8 bytes of text, RGBA bytes of an image
+  text?: string;
+  truncated: boolean;  // text was cut at maxTextBytes
+  image?: { width: number; height: number; data?: string };  // data: base64 PNG
+}
+
+export async function startClipboardMonitor(config: ClipboardMonitorConfig = {}): Promise<void> {
+  return invoke<void>('clipboard_start_monitor', { config });
+}
+
+export async function stopClipboardMonitor(): Promise<boolean> {
+  return invoke<boolean>('clipboard_stop_monitor');
+}
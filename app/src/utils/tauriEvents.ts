@@ -0,0 +1,47 @@
+import { invoke } from '@tauri-apps/api/core';
+import { isTauri } from './platform';
+import { Logger, LogEntry } from './logging';
+
+/**
+ * Event stream of the desktop app's API server (`GET /events`, server-sent events).
+ *
+ * Agent runs happen here in the frontend, so their start and end are reported to the
+ * backend, which publishes them along with capture, inference error and notification
+ * events. Each SSE event is named after its `type`.
+ */
+
+export type ApiEvent =
+  | { type: 'agent-started'; timestamp: number; agentId: string; iterationId?: string | null }
+  | {
+      type: 'agent-finished';
+      timestamp: number;
+      agentId: string;
+      iterationId?: string | null;
+      success: boolean;
+      error?: string | null;
+    }
+  | { type: 'capture-started'; timestamp: number; sessionId: string; targetId?: string | null }
+  | { type: 'capture-stopped'; timestamp: number; sessionId?: string | null }
+  | { type: 'inference-error'; timestamp: number; agentId?: string | null; error: string }
+  | { type: 'notification'; timestamp: number; title: string; body: string };
+
+function publishAgentEvent(entry: LogEntry): void {
+  const logType = entry.details?.logType;
+  const finished = logType === 'iteration-end' || logType === 'iteration-skipped';
+  if (logType !== 'iteration-start' && !finished) return;
+
+  const content = entry.details?.content;
+  invoke('publish_agent_event', {
+    agentId: entry.source,
+    iterationId: entry.details?.iterationId ?? null,
+    finished,
+    success: finished ? content?.success !== false && !content?.error : null,
+    error: content?.error ?? null,
+  }).catch(() => { /* best effort; the app works without the event stream */ });
+}
+
+/** Report agent runs to `/events` (desktop app only) */
+export function initAgentEventForwarding(): void {
+  if (!isTauri()) return;
+  Logger.addListener(publishAgentEvent);
+}
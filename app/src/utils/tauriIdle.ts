@@ -0,0 +1,41 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * System idle detection through the idle plugin (desktop app only).
+ *
+ * The idle time is how long since the last keyboard or mouse input anywhere in the
+ * session, from the platform's own idle clock; no keystrokes are seen. A monitor emits
+ * `idle://user-idle` and `idle://user-active` (IdleEvent payloads) at a threshold.
+ */
+
+export const USER_IDLE_EVENT = 'idle://user-idle';
+export const USER_ACTIVE_EVENT = 'idle://user-active';
+
+export interface IdleConfig {
+  thresholdSecs?: number;  // Seconds without input before userIdle; default 300
+}
+
+export interface IdleEvent {
+  kind: 'userIdle' | 'userActive';
+  timestamp: number;  // The last input before going idle, or the one that ended it; seconds since the epoch
+  idleSecs: number;  // So far on userIdle, in total on userActive
+}
+
+export interface IdleStatus {
+  idleSecs: number;
+  thresholdSecs?: number;  // Set while a monitor runs
+  idle: boolean;  // Whether the monitor has reported the user idle
+}
+
+export async function getIdleTime(): Promise<IdleStatus> {
+  return invoke<IdleStatus>('plugin:idle|get_idle_time_cmd');
+}
+
+/** Start the monitor (replacing a running one); with `pauseCapture`, screen capture holds while idle */
+export async function startIdleMonitor(config: IdleConfig = {}, pauseCapture = false): Promise<void> {
+  return invoke<void>('idle_start_monitor', { config, pauseCapture });
+}
+
+export async function stopIdleMonitor(): Promise<boolean> {
+  return invoke<boolean>('idle_stop_monitor');
+}
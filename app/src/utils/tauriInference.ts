@@ -0,0 +1,248 @@
+import { Channel, invoke } from '@tauri-apps/api/core';
+
+/**
+ * Backend inference providers (desktop app only).
+ *
+ * OpenAI-compatible endpoints (vLLM, LM Studio, llama.cpp server, OpenRouter, ...) and
+ * Anthropic are configured here and called from the Rust backend, so API keys are written
+ * once and never read back: providers only report `hasApiKey`. Agents without a provider
+ * use the default Ollama URL (provider id 'ollama').
+ *
+ * Endpoints behind an authenticating reverse proxy take extra headers and basic auth.
+ * Those stay in the backend too: only header names and the basic-auth user come back.
+ *
+ * When a request's provider fails or takes longer than `fallbackAfterSecs`, the providers
+ * in the fallback chain are tried in order; `fallbackFrom` lists the ones that failed. A
+ * request the provider refused outright (400, 413, 422) isn't passed on.
+ * Each provider's RequestPolicy limits how long a try may take and how often transient
+ * failures are retried. A provider that keeps failing is marked unhealthy and skipped
+ * until its cooldown ends (`provider-unhealthy` / `provider-healthy` events).
+ *
+ * With the reply cache on, a request repeating an earlier one (same provider, model, text
+ * and parameters, and images that look the same) is answered from memory (`cached`).
+ *
+ * With an `outputSchema`, the reply must be JSON matching it: providers are asked for
+ * structured output, and a reply that doesn't match is sent back with what was wrong, up
+ * to `maxReasks` times. The parsed reply comes back as `output`.
+ *
+ * A request naming `tools` (see tauriTools.ts) lets the model call them; the backend runs
+ * the calls and sends the results back until the model answers, listed in `toolRuns`.
+ *
+ * `inferenceChatStream` delivers the reply piece by piece as it is generated. Cancel it
+ * midway with `inference_cancel` and the request's `requestId`.
+ */
+
+export const DEFAULT_PROVIDER_ID = 'ollama';
+export const PROVIDER_UNHEALTHY_EVENT = 'provider-unhealthy';
+export const PROVIDER_HEALTHY_EVENT = 'provider-healthy';
+
+export type ProviderKind = 'ollama' | 'openAi' | 'anthropic';
+
+export interface ProviderInfo {
+  id: string;
+  name: string;
+  kind: ProviderKind;
+  baseUrl: string;  // openAi: including the version (.../v1); anthropic: https://api.anthropic.com
+  model?: string;  // Used when a request doesn't name one
+  hasApiKey: boolean;
+  headerNames: string[];  // Extra headers sent with every request
+  basicAuthUser?: string;
+  agents: string[];  // Agents assigned to this provider
+}
+
+/**
+ * Omit `headers` to keep the saved ones; given, they replace them, and a header with an
+ * empty value keeps its saved value. Omit `basicAuth` to keep it, send an empty username to
+ * clear it, and omit the password to keep the saved one.
+ */
+export interface AuthUpdate {
+  headers?: Record<string, string>;
+  basicAuth?: { username: string; password?: string };
+}
+
+export interface ProviderUpdate extends AuthUpdate {
+  id: string;
+  name: string;
+  kind: ProviderKind;
+  baseUrl: string;
+  apiKey?: string;  // Omit to keep the saved key, '' to clear it
+  model?: string;
+}
+
+export type ContentPart =
+  | { type: 'text'; text: string }
+  | { type: 'image_url'; image_url: { url: string } };
+
+export interface ChatMessage {
+  role: 'system' | 'user' | 'assistant';
+  content: string | ContentPart[];
+}
+
+export interface OutputSchema {
+  name?: string;  // Shown to the model; defaults to 'response'
+  schema: Record<string, unknown>;  // JSON Schema
+  maxReasks?: number;  // Defaults to 2
+}
+
+export interface ChatRequest {
+  agentId?: string;
+  providerId?: string;  // Overrides the agent's provider
+  model?: string;  // Overrides the provider's default model
+  messages: ChatMessage[];
+  params?: Record<string, unknown>;  // Passed through: temperature, max_tokens, ...
+  priority?: 'low' | 'normal' | 'high';
+  requestId?: string;  // For inference_cancel
+  cache?: boolean;  // Use or bypass the reply cache; defaults to its setting
+  outputSchema?: OutputSchema;
+  tools?: string[];  // Tool names the model may call; the agent must be allowed each
+}
+
+export interface ChatResponse {
+  content: string;
+  model: string;
+  providerId: string;
+  requestId: string;
+  finishReason?: string;
+  usage?: { promptTokens: number; completionTokens: number };
+  fallbackFrom: { providerId: string; error: string }[];  // Providers tried first, in order
+  cached: boolean;  // Answered from the reply cache; usage is then omitted
+  output?: unknown;  // The reply parsed, for requests with an outputSchema
+  reasks: number;  // Times the reply was sent back for not matching the schema
+  toolRuns: { name: string; arguments: unknown; ok: boolean; output: string }[];
+}
+
+export type StreamEvent =
+  | { event: 'started'; providerId: string; model: string; requestId: string }  // Again after a fallback or re-ask
+  | { event: 'delta'; content: string }
+  | { event: 'reasoning'; content: string }  // Thinking of reasoning models, not part of the reply
+  | { event: 'toolCall'; name: string };  // With tools the reply then arrives as one delta
+
+export interface FallbackChain {
+  providers: string[];  // Provider ids, tried in order after the request's own provider
+  fallbackAfterSecs: number;  // How long an attempt may take before moving on
+}
+
+export interface RequestPolicy {
+  timeoutSecs: number;  // Per try; for a streamed reply, until its first token
+  maxRetries: number;  // After connection errors, timeouts, 429 and 5xx
+  failureThreshold: number;  // Failed requests in a row before the provider is marked unhealthy
+  cooldownSecs: number;  // How long an unhealthy provider is skipped
+}
+
+/** Payload of PROVIDER_UNHEALTHY_EVENT and PROVIDER_HEALTHY_EVENT */
+export interface ProviderHealthEvent {
+  kind: 'providerUnhealthy' | 'providerHealthy';
+  providerId: string;
+  error?: string;
+  retryAfterSecs?: number;
+}
+
+export interface ProviderHealthStatus {
+  providerId: string;
+  healthy: boolean;
+  consecutiveFailures: number;
+  retryAfterSecs?: number;
+  lastError?: string;
+}
+
+export interface CacheSettings {
+  enabled: boolean;
+  maxEntries: number;
+  ttlSecs: number;
+  maxFrameDistance: number;  // Differing dHash bits (of 64) up to which frames count as the same
+}
+
+export interface CacheStats {
+  hits: number;
+  misses: number;
+  entries: number;
+  evictions: number;
+  tokensSaved: number;  // Prompt + completion tokens of cached replies
+}
+
+export async function getProviders(): Promise<ProviderInfo[]> {
+  return invoke<ProviderInfo[]>('get_providers');
+}
+
+export async function saveProvider(provider: ProviderUpdate): Promise<void> {
+  return invoke<void>('save_provider', { provider });
+}
+
+export async function removeProvider(providerId: string): Promise<void> {
+  return invoke<void>('remove_provider', { providerId });
+}
+
+/** Assign an agent to a provider, or with null back to the default */
+export async function setAgentProvider(agentId: string, providerId: string | null): Promise<void> {
+  return invoke<void>('set_agent_provider', { agentId, providerId });
+}
+
+export async function listProviderModels(providerId: string): Promise<string[]> {
+  return invoke<string[]>('list_provider_models', { providerId });
+}
+
+/** Header names and basic-auth user sent to the default Ollama URL */
+export async function getOllamaAuth(): Promise<{ headerNames: string[]; basicAuthUser?: string }> {
+  return invoke('get_ollama_auth');
+}
+
+export async function setOllamaAuth(auth: AuthUpdate): Promise<void> {
+  return invoke<void>('set_ollama_auth', { auth });
+}
+
+/** Every provider's policy by id, the default Ollama included */
+export async function getProviderPolicies(): Promise<Record<string, RequestPolicy>> {
+  return invoke<Record<string, RequestPolicy>>('get_provider_policies');
+}
+
+/** Set a provider's policy, or with null reset it to the default */
+export async function setProviderPolicy(providerId: string, policy: RequestPolicy | null): Promise<void> {
+  return invoke<void>('set_provider_policy', { providerId, policy });
+}
+
+/** Providers that have failed since their last success */
+export async function getProviderHealth(): Promise<ProviderHealthStatus[]> {
+  return invoke<ProviderHealthStatus[]>('get_provider_health');
+}
+
+export async function resetProviderHealth(providerId: string): Promise<void> {
+  return invoke<void>('reset_provider_health', { providerId });
+}
+
+export async function getFallbackChain(): Promise<FallbackChain> {
+  return invoke<FallbackChain>('get_fallback_chain');
+}
+
+export async function setFallbackChain(chain: FallbackChain): Promise<void> {
+  return invoke<void>('set_fallback_chain', { chain });
+}
+
+export async function inferenceChat(request: ChatRequest): Promise<ChatResponse> {
+  return invoke<ChatResponse>('inference_chat', { request });
+}
+
+/** Like inferenceChat, calling onEvent as the reply streams in; resolves with the full reply */
+export async function inferenceChatStream(
+  request: ChatRequest,
+  onEvent: (event: StreamEvent) => void,
+): Promise<ChatResponse> {
+  const channel = new Channel<StreamEvent>();
+  channel.onmessage = onEvent;
+  return invoke<ChatResponse>('inference_chat_stream', { request, onEvent: channel });
+}
+
+export async function getInferenceCacheStats(): Promise<CacheStats> {
+  return invoke<CacheStats>('get_inference_cache_stats');
+}
+
+export async function clearInferenceCache(): Promise<void> {
+  return invoke<void>('clear_inference_cache');
+}
+
+export async function getInferenceCacheSettings(): Promise<CacheSettings> {
+  return invoke<CacheSettings>('get_inference_cache_settings');
+}
+
+export async function setInferenceCacheSettings(settings: CacheSettings): Promise<void> {
+  return invoke<void>('set_inference_cache_settings', { settings });
+}
@@ -0,0 +1,66 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Input simulation through the input-sim plugin (desktop app built with `input-sim`).
+ *
+ * Every action names the agent performing it and fails unless input is enabled globally
+ * (off at every launch; setInputEnabled(false) is the kill switch) and the user has allowed
+ * that agent. Points are screen coordinates unless `space` says otherwise.
+ */
+
+export type MouseButton = 'left' | 'right' | 'middle';
+export type CoordSpace = 'frame' | 'screen' | 'window';
+
+export interface InputPermissions {
+  enabled: boolean;  // The global switch
+  suspended: boolean;  // Held off while incognito is on
+  agents: string[];  // Agents allowed to send input
+}
+
+/** A point in `space`, as screen coordinates (frame points use the active capture) */
+async function toScreen(x: number, y: number, space: CoordSpace): Promise<{ x: number; y: number }> {
+  if (space === 'screen') return { x, y };
+  return invoke<{ x: number; y: number }>('sc_map_point', { x, y, from: space, to: 'screen' });
+}
+
+/** Type `text` and/or press a combo such as ['cmdorctrl', 'v'] */
+export async function sendKeys(agentId: string, input: { text?: string; keys?: string[] }): Promise<void> {
+  return invoke<void>('plugin:input-sim|send_keys_cmd', { agentId, ...input });
+}
+
+export async function clickAt(
+  agentId: string,
+  x: number,
+  y: number,
+  options: { button?: MouseButton; double?: boolean; space?: CoordSpace } = {},
+): Promise<void> {
+  const point = await toScreen(x, y, options.space ?? 'screen');
+  return invoke<void>('plugin:input-sim|click_at_cmd', {
+    agentId,
+    ...point,
+    button: options.button,
+    double: options.double,
+  });
+}
+
+/** Scroll by wheel notches (positive dy is down), over `at` if given */
+export async function scroll(
+  agentId: string,
+  delta: { dx?: number; dy?: number },
+  at?: { x: number; y: number; space?: CoordSpace },
+): Promise<void> {
+  const point = at ? await toScreen(at.x, at.y, at.space ?? 'screen') : {};
+  return invoke<void>('plugin:input-sim|scroll_cmd', { agentId, ...delta, ...point });
+}
+
+export async function getInputPermissions(): Promise<InputPermissions> {
+  return invoke<InputPermissions>('plugin:input-sim|get_input_permissions_cmd');
+}
+
+export async function setInputEnabled(enabled: boolean): Promise<void> {
+  return invoke<void>('plugin:input-sim|set_input_enabled_cmd', { enabled });
+}
+
+export async function setAgentInputPermission(agentId: string, allowed: boolean): Promise<void> {
+  return invoke<void>('plugin:input-sim|set_agent_input_permission_cmd', { agentId, allowed });
+}
@@ -0,0 +1,31 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Ollama through the app's own server (desktop app only).
+ *
+ * `<server url>/v1/ollama/<path>` is forwarded to `<ollama url>/<path>`, so the frontend
+ * never talks to Ollama directly: no mixed-content blocks or CORS errors, whatever the
+ * Ollama URL. https upstreams with a self-signed certificate need the certificate trusted
+ * here first.
+ */
+
+export const OLLAMA_PROXY_PATH = '/v1/ollama';
+
+export interface OllamaProxySettings {
+  trustedCertPath?: string;  // PEM certificate to trust for an https Ollama
+  acceptInvalidCerts: boolean;  // Skip certificate checks for Ollama entirely
+}
+
+/** The Ollama base URL to use from the frontend, given `get_server_url` */
+export function ollamaProxyUrl(serverUrl: string): string {
+  return `${serverUrl.replace(/\/+$/, '')}${OLLAMA_PROXY_PATH}`;
+}
+
+export async function getOllamaProxySettings(): Promise<OllamaProxySettings> {
+  return invoke<OllamaProxySettings>('get_ollama_proxy_settings');
+}
+
+/** Rejects without saving if the certificate can't be read */
+export async function setOllamaProxySettings(settings: OllamaProxySettings): Promise<void> {
+  return invoke<void>('set_ollama_proxy_settings', { settings });
+}
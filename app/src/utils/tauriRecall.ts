@@ -0,0 +1,44 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Retrieval-augmented context from capture history (desktop app only).
+ *
+ * With `recordHistory` on, OCR text is embedded into the `capture-history` vector
+ * collection as it is read; transcript segments are added with `recordObservation`.
+ * Agents listed in `agents` then get the past observations most similar to their latest
+ * message put in front of each `inference_chat` request. Clear the history with
+ * `deleteVectorCollection(CAPTURE_HISTORY_COLLECTION)`.
+ */
+
+export const CAPTURE_HISTORY_COLLECTION = 'capture-history';
+
+export type ObservationSource = 'ocr' | 'transcript';
+
+export interface AgentRecall {
+  topK: number;  // Default 5
+  minScore: number;  // Cosine similarity, default 0.5
+  sources: ObservationSource[];
+}
+
+export interface RecallSettings {
+  recordHistory: boolean;
+  agents: Record<string, AgentRecall>;
+}
+
+/** Add text (e.g. a transcript segment) to the capture history */
+export async function recordObservation(
+  source: ObservationSource,
+  text: string,
+  agentId?: string,
+  timestampMs?: number,
+): Promise<void> {
+  return invoke<void>('record_observation', { source, text, agentId, timestampMs });
+}
+
+export async function getRecallSettings(): Promise<RecallSettings> {
+  return invoke<RecallSettings>('get_recall_settings');
+}
+
+export async function setRecallSettings(settings: RecallSettings): Promise<void> {
+  return invoke<void>('set_recall_settings', { settings });
+}
@@ -0,0 +1,29 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * The capture as an RTSP camera (desktop app only), for NVRs, VLC or Frigate:
+ * `rtsp://<host>:<port>/<path>`, H.264 over TCP. Streams without a username and password
+ * only answer clients on this machine. Changes apply after restarting the app.
+ */
+
+export interface RtspStream {
+  path: string;
+  username: string;
+  password: string;
+  bitrateKbps: number;  // Default 2000
+}
+
+export interface RtspSettings {
+  enabled: boolean;
+  host: string;  // Default 127.0.0.1; 0.0.0.0 to serve the LAN
+  port: number;  // Default 8554
+  streams: RtspStream[];
+}
+
+export async function getRtspSettings(): Promise<RtspSettings> {
+  return invoke<RtspSettings>('get_rtsp_settings');
+}
+
+export async function setRtspSettings(settings: RtspSettings): Promise<void> {
+  return invoke<void>('set_rtsp_settings', { settings });
+}
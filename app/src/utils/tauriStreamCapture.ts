@@ -18,7 +18,7 @@ export interface BroadcastStatus {
 
 export interface CaptureTarget {
   id: string;
-  kind: 'monitor' | 'window';
+  kind: 'monitor' | 'window' | 'virtual-desktop';
   name: string;
   appName?: string;
   thumbnail?: string;
@@ -27,15 +27,101 @@ export interface CaptureTarget {
   isPrimary: boolean;
   x: number;
   y: number;
+  source?: SourceMetrics;
+}
+
+/** Source rect and DPI facts reported with targets and frames */
+export interface SourceMetrics {
+  x: number;  // Source rect in screen coordinates (points on macOS, pixels elsewhere)
+  y: number;
+  width: number;
+  height: number;
+  scaleFactor: number;  // Physical pixels per logical point
+  physicalWidth: number;
+  physicalHeight: number;
+  logicalWidth: number;
+  logicalHeight: number;
 }
 
 /** Frame data received from Rust via Channel */
+export type FrameEncoding = 'jpeg' | 'png' | 'webp' | 'webpLossless';
+
+export const FRAME_MIME_TYPES: Record<FrameEncoding, string> = {
+  jpeg: 'image/jpeg',
+  png: 'image/png',
+  webp: 'image/webp',
+  webpLossless: 'image/webp',
+};
+
 export interface FrameData {
-  frame: Uint8Array;  // Raw JPEG bytes
+  frame: Uint8Array;  // Encoded image bytes, in `format`
+  format?: FrameEncoding;  // Absent from older backends (JPEG)
   timestamp: number;  // Unix timestamp
   width: number;
   height: number;
   frameCount: number;
+  source?: SourceMetrics;  // Absent from older backends
+  delta?: FrameDelta;  // Only on streams started with `delta` (see frameDelta.ts)
+  yuv?: YuvFrame;  // Only on streams started with `yuv`; `frame` holds planes, not an image
+  change?: number;  // 0 = same as the previous frame, 1 = unrelated; absent on the first frame
+  stats?: FrameStats;  // Only with the `frameStats` capture setting
+}
+
+/** Color statistics of a frame, sampled from its pixels */
+export interface FrameStats {
+  timestamp: number;
+  meanLuminance: number;  // 0 = black, 1 = white
+  darkShare: number;  // Share of dark pixels
+  theme: 'dark' | 'light' | 'mixed';
+  dominantColors: { hex: string; share: number }[];  // Largest first
+}
+
+/** A changed tile of a delta frame; its image is the next `len` bytes of `frame` */
+export interface FrameTile {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  len: number;
+}
+
+/** How to read a frame of a delta stream */
+export interface FrameDelta {
+  key: boolean;  // `frame` is the whole image
+  keyframe: number;  // Keyframe the tiles apply to
+  tiles?: FrameTile[];  // Absent on keyframes and unchanged frames
+}
+
+/** Tile streaming options for `sc_start_video_stream` */
+export interface DeltaConfig {
+  tileSize?: number;  // Default 64
+  keyframeInterval?: number;  // Frames between keyframes, default 100 (0 = only when needed)
+  maxChanged?: number;  // Fraction of changed tiles that forces a keyframe, default 0.5
+}
+
+/** Raw plane output for `sc_start_video_stream`, for external video encoders */
+export interface YuvOutput {
+  layout: 'nv12' | 'i420';
+  sharedMemory?: boolean;  // Write planes to a shared file instead of the payload
+}
+
+/** How to read a frame of a YUV stream */
+export interface YuvFrame {
+  layout: 'nv12' | 'i420';
+  shm?: { path: string; offset: number; len: number };  // Set in shared-memory mode; `frame` is empty
+}
+
+/**
+ * Frames from streams started with `binary: true` arrive as a raw ArrayBuffer:
+ * [u32 big-endian header length][JSON header][image bytes]. JSON FrameData is the
+ * fallback (mobile, older backends).
+ */
+function toFrameData(message: FrameData | ArrayBuffer): FrameData {
+  if (!(message instanceof ArrayBuffer)) return message;
+  const view = new DataView(message);
+  const headerLength = view.getUint32(0, false);
+  const header = JSON.parse(new TextDecoder().decode(new Uint8Array(message, 4, headerLength)));
+  return { ...header, frame: new Uint8Array(message, 4 + headerLength) };
 }
 
 /** Audio data received from Rust via Channel */
@@ -109,6 +195,7 @@ class TauriStreamCapture {
   private capturing = false;
   private latestFrameBytes: Uint8Array | null = null;
   private latestBase64Frame: string | null = null; // Cached base64 conversion
+  private latestFrameMimeType = 'image/jpeg';
   private latestAudioData: AudioData | null = null;
   private pipOverlayDrawer: PipOverlayDrawer | null = null;
 
@@ -426,6 +513,11 @@ class TauriStreamCapture {
     }
   }
 
+  /** MIME type of the frame returned by getLatestBase64Frame() */
+  getLatestFrameMimeType(): string {
+    return this.latestFrameMimeType;
+  }
+
   /**
    * Get the latest base64 frame directly (for pre-processor).
    * Computes base64 lazily from raw bytes when needed.
@@ -513,22 +605,24 @@ class TauriStreamCapture {
     let frameCount = 0;
     let isActive = true;
 
-    const frameChannel = new Channel<FrameData>();
+    const frameChannel = new Channel<FrameData | ArrayBuffer>();
 
     // Coalescing decode pump: we only ever hold the NEWEST undecoded frame and decode
     // one at a time. If frames arrive faster than we can decode+draw, the intermediate
     // ones are dropped instead of queuing — so latency stays at ~one frame no matter how
     // hard Rust pushes. (Decoding every frame is what let a multi-second backlog build.)
     let pendingFrame: Uint8Array | null = null;
+    let pendingFrameCount = 0;
     let draining = false;
 
     const drainFrames = async () => {
       draining = true;
       while (pendingFrame && isActive) {
         const bytes = pendingFrame;
+        const drawnFrameCount = pendingFrameCount;
         pendingFrame = null; // frames arriving during this decode overwrite the slot
         try {
-          const bitmap = await createImageBitmap(new Blob([bytes], { type: 'image/jpeg' }));
+          const bitmap = await createImageBitmap(new Blob([bytes], { type: this.latestFrameMimeType }));
           if (!isActive) { bitmap.close(); break; }
 
           if (!canvasSizeInitialized && bitmap.width > 0) {
@@ -551,6 +645,10 @@ class TauriStreamCapture {
             }
           }
           bitmap.close();
+          // Lets Rust drop frames instead of queueing them while we're behind
+          if (isDesktop()) {
+            invoke('sc_ack_frames', { frameCount: drawnFrameCount }).catch(() => {});
+          }
         } catch (e) {
           Logger.error("TAURI_STREAM", `Image decode error: ${e}`);
         }
@@ -558,7 +656,8 @@ class TauriStreamCapture {
       draining = false;
     };
 
-    frameChannel.onmessage = (frameData: FrameData) => {
+    frameChannel.onmessage = (message: FrameData | ArrayBuffer) => {
+      const frameData = toFrameData(message);
       if (!isActive) return;
 
       frameCount++;
@@ -587,10 +686,12 @@ class TauriStreamCapture {
 
       // Store converted bytes for getLatestFrame() - base64 computed lazily when needed
       this.latestFrameBytes = frameBytes;
+      this.latestFrameMimeType = FRAME_MIME_TYPES[frameData.format ?? 'jpeg'] ?? 'image/jpeg';
       this.latestBase64Frame = null; // Clear cached base64, will be recomputed on demand
 
       // Hand the freshest frame to the decode pump (drops any older undecoded frame).
       pendingFrame = frameBytes;
+      pendingFrameCount = frameData.frameCount;
       if (!draining) void drainFrames();
     };
 
@@ -603,6 +704,7 @@ class TauriStreamCapture {
       // denied for the main window on Linux.
       await invoke('sc_start_video_stream', {
         targetId: selectedTargetId || null,
+        binary: true,
         onFrame: frameChannel,
       });
     } else {
@@ -931,7 +1033,7 @@ class TauriStreamCapture {
     let isActive = true;
 
     // Create channels to receive frames and audio from Rust
-    const frameChannel = new Channel<FrameData>();
+    const frameChannel = new Channel<FrameData | ArrayBuffer>();
     const audioChannel = new Channel<AudioData>();
     let audioChunkCount = 0;
 
@@ -1048,15 +1150,17 @@ class TauriStreamCapture {
     // time, dropping intermediates under load so latency stays at ~one frame. See the
     // matching pump in startVideoStream for the rationale.
     let pendingFrame: Uint8Array | null = null;
+    let pendingFrameCount = 0;
     let draining = false;
 
     const drainFrames = async () => {
       draining = true;
       while (pendingFrame && isActive) {
         const bytes = pendingFrame;
+        const drawnFrameCount = pendingFrameCount;
         pendingFrame = null; // frames arriving during this decode overwrite the slot
         try {
-          const bitmap = await createImageBitmap(new Blob([bytes], { type: 'image/jpeg' }));
+          const bitmap = await createImageBitmap(new Blob([bytes], { type: this.latestFrameMimeType }));
           if (!isActive) { bitmap.close(); break; }
 
           // Adapt canvas size on first frame
@@ -1083,6 +1187,10 @@ class TauriStreamCapture {
             }
           }
           bitmap.close();
+          // Lets Rust drop frames instead of queueing them while we're behind
+          if (isDesktop()) {
+            invoke('sc_ack_frames', { frameCount: drawnFrameCount }).catch(() => {});
+          }
         } catch (e) {
           Logger.error("TAURI_STREAM", `Image decode error: ${e}`);
         }
@@ -1090,7 +1198,8 @@ class TauriStreamCapture {
       draining = false;
     };
 
-    frameChannel.onmessage = (frameData: FrameData) => {
+    frameChannel.onmessage = (message: FrameData | ArrayBuffer) => {
+      const frameData = toFrameData(message);
       if (!isActive) return;
 
       frameCount++;
@@ -1119,10 +1228,12 @@ class TauriStreamCapture {
 
       // Store converted bytes for getLatestFrame() - base64 computed lazily when needed
       this.latestFrameBytes = frameBytes;
+      this.latestFrameMimeType = FRAME_MIME_TYPES[frameData.format ?? 'jpeg'] ?? 'image/jpeg';
       this.latestBase64Frame = null; // Clear cached base64, will be recomputed on demand
 
       // Hand the freshest frame to the decode pump (drops any older undecoded frame).
       pendingFrame = frameBytes;
+      pendingFrameCount = frameData.frameCount;
       if (!draining) void drainFrames();
     };
 
@@ -1172,6 +1283,7 @@ class TauriStreamCapture {
         // independently, mirroring acquireMasterStream). See sc_* in lib.rs.
         await invoke('sc_start_video_stream', {
           targetId: selectedTargetId || null,
+          binary: true,
           onFrame: frameChannel,
         });
         await invoke('sc_start_audio_stream', {
@@ -0,0 +1,58 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Token usage and budgets (desktop app only).
+ *
+ * The backend adds up prompt and completion tokens per day, agent and provider, for
+ * inference through `inference_chat` and through the Ollama proxy. An agent with a daily
+ * budget is stopped when it reaches it (AGENT_BUDGET_EXCEEDED_EVENT), and its model calls
+ * are refused until the next day or until the budget is raised.
+ */
+
+export const AGENT_BUDGET_EXCEEDED_EVENT = 'agent-budget-exceeded';
+
+export interface TokenTotals {
+  promptTokens: number;
+  completionTokens: number;
+  requests: number;  // Calls that reported their token counts
+}
+
+export interface AgentTokens extends TokenTotals {
+  agentId?: string;  // Omitted for calls made without an agent
+  todayTokens: number;
+  dailyBudget?: number;
+  overBudget: boolean;
+}
+
+export interface TokenUsageReport {
+  fromDay: string;  // YYYY-MM-DD, inclusive
+  toDay: string;
+  daily: (TokenTotals & { day: string })[];
+  agents: AgentTokens[];
+  providers: (TokenTotals & { providerId: string })[];
+}
+
+/** Payload of AGENT_BUDGET_EXCEEDED_EVENT */
+export interface BudgetEvent {
+  agentId: string;
+  usedTokens: number;
+  budgetTokens: number;
+}
+
+/** Totals over the last `days` days (default 30) */
+export async function getTokenUsage(days?: number): Promise<TokenUsageReport> {
+  return invoke<TokenUsageReport>('get_usage_cmd', { days });
+}
+
+export async function getTokenBudgets(): Promise<{ dailyBudgets: Record<string, number> }> {
+  return invoke('get_token_budgets');
+}
+
+/** Set an agent's daily token budget, or with null remove it */
+export async function setAgentTokenBudget(agentId: string, dailyTokens: number | null): Promise<void> {
+  return invoke<void>('set_agent_token_budget', { agentId, dailyTokens });
+}
+
+export async function clearTokenUsage(): Promise<void> {
+  return invoke<void>('clear_token_usage');
+}
@@ -0,0 +1,37 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Backend tools models can call during inferenceChat (desktop app only).
+ *
+ * A request lists the tools it offers the model in `tools`. Nothing is available until an
+ * agent is allowed a tool here, and only to requests from the main window; `run_command`
+ * and `http_request` act on the user's machine and network, so grant them with care. They
+ * are refused while incognito mode is on or capture is paused.
+ */
+
+export type ToolName = 'notify' | 'capture_screenshot' | 'run_command' | 'http_request';
+
+export interface ToolInfo {
+  name: ToolName;
+  description: string;
+  parameters: Record<string, unknown>;  // JSON schema of the arguments
+}
+
+export interface ToolSettings {
+  agents: Record<string, ToolName[]>;  // The tools each agent may call
+  maxRounds: number;  // Rounds of tool calls per request before giving up
+  commandTimeoutSecs: number;
+  allowLocalNetwork: boolean;  // Let http_request reach this machine and its local network
+}
+
+export async function listTools(): Promise<ToolInfo[]> {
+  return invoke<ToolInfo[]>('list_tools');
+}
+
+export async function getToolSettings(): Promise<ToolSettings> {
+  return invoke<ToolSettings>('get_tool_settings');
+}
+
+export async function setToolSettings(settings: ToolSettings): Promise<void> {
+  return invoke<void>('set_tool_settings', { settings });
+}
@@ -0,0 +1,65 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Semantic memory (desktop app only).
+ *
+ * Texts are embedded by the configured embedding provider (Ollama's nomic-embed-text by
+ * default) and kept on disk in named collections, so an agent can later look up past
+ * observations by meaning. A collection stays tied to the model that filled it; delete
+ * it before switching embedding models.
+ */
+
+export interface EmbeddingSettings {
+  providerId?: string | null;  // null for the default Ollama
+  model: string;
+}
+
+export interface SearchHit {
+  id: number;
+  text: string;
+  metadata?: unknown;
+  score: number;  // Cosine similarity, 1 for identical meaning
+  createdAt: number;  // ms since epoch
+}
+
+export interface CollectionInfo {
+  name: string;
+  model: string;
+  count: number;
+}
+
+/** Store `text` in `collection`; returns its id */
+export async function embedAndStore(
+  collection: string,
+  text: string,
+  metadata?: unknown,
+  agentId?: string,
+): Promise<number> {
+  return invoke<number>('embed_and_store_cmd', { collection, text, metadata, agentId });
+}
+
+/** The stored texts most similar to `query`, best first (default limit 5) */
+export async function similaritySearch(
+  collection: string,
+  query: string,
+  options: { limit?: number; minScore?: number; agentId?: string } = {},
+): Promise<SearchHit[]> {
+  return invoke<SearchHit[]>('similarity_search_cmd', { collection, query, ...options });
+}
+
+export async function listVectorCollections(): Promise<CollectionInfo[]> {
+  return invoke<CollectionInfo[]>('list_vector_collections');
+}
+
+/** Returns the number of entries removed */
+export async function deleteVectorCollection(collection: string): Promise<number> {
+  return invoke<number>('delete_vector_collection', { collection });
+}
+
+export async function getEmbeddingSettings(): Promise<EmbeddingSettings> {
+  return invoke<EmbeddingSettings>('get_embedding_settings');
+}
+
+export async function setEmbeddingSettings(settings: EmbeddingSettings): Promise<void> {
+  return invoke<void>('set_embedding_settings', { settings });
+}